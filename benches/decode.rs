@@ -0,0 +1,223 @@
+//! Throughput of the indexing pipeline on one large log batch, decoded
+//! inline and by parallel decode workers.
+//!
+//! ```bash
+//! cargo bench --bench decode
+//! ```
+//!
+//! Each run indexes 100k logs (50k Sync and 50k Swap events) fetched in a
+//! single batch, as a backfill shard does, once into a storage that discards
+//! the rows (decoding and pricing only) and once into an in-memory SQLite
+//! database (end to end). The best of `RUNS` runs is reported. Decode workers
+//! only pay off with as many idle cores, so compare on a multi-core machine.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+#![allow(clippy::cast_precision_loss)]
+
+use std::time::{Duration, Instant};
+
+use alloy::primitives::aliases::U112;
+use alloy::primitives::{Address, B256, U256};
+use alloy::rpc::types::Log;
+use alloy::sol_types::SolEvent;
+use async_trait::async_trait;
+use eth_uniswap_alloy::db::create_pool;
+use eth_uniswap_alloy::db::models::{
+    IndexerState, PoolRecord, PricePointRecord, SwapEventRecord, SyncEventRecord,
+};
+use eth_uniswap_alloy::db::repository::Repository;
+use eth_uniswap_alloy::db::storage::Storage;
+use eth_uniswap_alloy::error::TrackerResult;
+use eth_uniswap_alloy::events::{Swap, Sync};
+use eth_uniswap_alloy::pipeline::Pipeline;
+use eth_uniswap_alloy::state::State;
+
+/// Sync/Swap pairs in the batch.
+const EVENTS: u64 = 50_000;
+
+/// Runs per configuration.
+const RUNS: usize = 3;
+
+/// Decode worker counts compared.
+const WORKERS: [usize; 4] = [1, 2, 4, 8];
+
+/// Storage that discards every write.
+struct Discard;
+
+#[async_trait]
+impl Storage for Discard {
+    async fn find_pool(&self, _identifier: &str) -> TrackerResult<Option<PoolRecord>> {
+        Ok(None)
+    }
+
+    async fn insert_sync_events(&self, _events: Vec<SyncEventRecord>) -> TrackerResult<()> {
+        Ok(())
+    }
+
+    async fn insert_price_points(&self, _prices: Vec<PricePointRecord>) -> TrackerResult<()> {
+        Ok(())
+    }
+
+    async fn insert_swap_events(&self, _events: Vec<SwapEventRecord>) -> TrackerResult<()> {
+        Ok(())
+    }
+
+    async fn get_state(&self, _pool_id: i64) -> TrackerResult<Option<IndexerState>> {
+        Ok(None)
+    }
+
+    async fn set_state(&self, _state: &IndexerState) -> TrackerResult<()> {
+        Ok(())
+    }
+
+    async fn invalidate_from_block(&self, _pool_id: i64, _from_block: u64) -> TrackerResult<()> {
+        Ok(())
+    }
+
+    async fn confirm_up_to_block(&self, _pool_id: i64, _up_to_block: u64) -> TrackerResult<()> {
+        Ok(())
+    }
+}
+
+fn log(pool: Address, block: u64, log_index: u64, data: alloy::primitives::LogData) -> Log {
+    Log {
+        inner: alloy::primitives::Log {
+            address: pool,
+            data,
+        },
+        block_hash: Some(B256::from(U256::from(block))),
+        block_number: Some(block),
+        block_timestamp: Some(1_700_000_000 + block * 12),
+        transaction_hash: Some(B256::from(U256::from(block) << 8)),
+        transaction_index: Some(0),
+        log_index: Some(log_index),
+        removed: false,
+    }
+}
+
+/// One Sync and one Swap per block, with a drifting price.
+fn logs(pool: Address) -> Vec<Log> {
+    let weth = U112::from(10u64).pow(U112::from(18u64));
+    let usdt = U112::from(10u64).pow(U112::from(6u64));
+    (1..=EVENTS)
+        .flat_map(|block| {
+            let sync = Sync {
+                reserve0: U112::from(1_000u64) * weth,
+                reserve1: U112::from(2_000_000 + block % 1_000) * usdt,
+            };
+            let swap = Swap {
+                sender: Address::repeat_byte(0x77),
+                amount0In: U256::ZERO,
+                amount1In: U256::from(2_000_000_000u64),
+                amount0Out: U256::from(10u64).pow(U256::from(18u64)),
+                amount1Out: U256::ZERO,
+                to: Address::repeat_byte(0xa1),
+            };
+            [
+                log(pool, block, 0, sync.encode_log_data()),
+                log(pool, block, 1, swap.encode_log_data()),
+            ]
+        })
+        .collect()
+}
+
+async fn index(storage: &dyn Storage, pool: &PoolRecord, logs: &[Log], workers: usize) -> Duration {
+    let pipeline = Pipeline::new(storage, pool, 1)
+        .unwrap()
+        .with_decode_workers(workers)
+        .without_state_updates();
+    let mut batch = Some(logs.to_vec());
+    let started = Instant::now();
+    let events = pipeline
+        .run(
+            [(1, EVENTS)],
+            move |_, _| {
+                let batch = batch.take().unwrap_or_default();
+                async move { Ok(batch) }
+            },
+            &mut State::new(),
+            &mut None,
+            |_| {},
+        )
+        .await
+        .unwrap();
+    let elapsed = started.elapsed();
+    assert_eq!(events as u64, EVENTS);
+    elapsed
+}
+
+/// Where a run writes its rows.
+enum Target {
+    /// [`Discard`], for decoding and pricing only
+    Discard(Box<PoolRecord>),
+    /// A fresh in-memory SQLite database per run
+    Sqlite,
+}
+
+impl Target {
+    async fn run(&self, logs: &[Log], workers: usize) -> Duration {
+        match self {
+            Self::Discard(pool) => index(&Discard, pool, logs, workers).await,
+            Self::Sqlite => {
+                let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+                let pool_id = repo.ensure_default_pool().await.unwrap();
+                let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+                index(&repo, &pool, logs, workers).await
+            }
+        }
+    }
+}
+
+async fn bench(name: &str, logs: &[Log], target: Target) {
+    println!("{name}");
+    let mut baseline = None;
+    for workers in WORKERS {
+        let mut best = Duration::MAX;
+        for _ in 0..RUNS {
+            best = best.min(target.run(logs, workers).await);
+        }
+        let baseline = *baseline.get_or_insert(best);
+        println!(
+            "  {workers} decode worker(s): {:>8.1} ms  {:>10.0} logs/s  {:.2}x",
+            best.as_secs_f64() * 1000.0,
+            logs.len() as f64 / best.as_secs_f64(),
+            baseline.as_secs_f64() / best.as_secs_f64()
+        );
+    }
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let mut pool = PoolRecord::new(
+            Address::repeat_byte(0x0d),
+            Some("WETH/USDT".to_string()),
+            Address::repeat_byte(0xc0),
+            Some("WETH".to_string()),
+            18,
+            Address::repeat_byte(0xda),
+            Some("USDT".to_string()),
+            6,
+        );
+        pool.id = 1;
+        let logs = logs(pool.address.get());
+        let cpus = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+        println!(
+            "{} logs in one batch, best of {RUNS} runs, {cpus} CPU(s)",
+            logs.len()
+        );
+
+        bench(
+            "decode + price (rows discarded)",
+            &logs,
+            Target::Discard(Box::new(pool)),
+        )
+        .await;
+        bench("end to end (in-memory SQLite)", &logs, Target::Sqlite).await;
+    });
+}
@@ -0,0 +1,225 @@
+//! Criterion benchmarks of the indexing hot path, to catch regressions while
+//! the pipeline is refactored for throughput.
+//!
+//! ```bash
+//! cargo bench --features bench --bench hot_path
+//!
+//! # Record a baseline on main, then compare a branch against it
+//! cargo bench --features bench --bench hot_path -- --save-baseline main
+//! cargo bench --features bench --bench hot_path -- --baseline main
+//! ```
+//!
+//! Groups:
+//!
+//! - `decode`: Sync and Swap log decoding
+//! - `price`: exact and `f64` prices from reserves
+//! - `insert`: batch inserts of sync events and price points into a fresh
+//!   in-memory database
+//! - `candles`: recording prices into the 1m/5m candle book and reading it
+//!   back
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+#![allow(clippy::cast_precision_loss)]
+// criterion_group! generates an undocumented public function
+#![allow(missing_docs)]
+
+use std::time::{Duration, Instant};
+
+use alloy::primitives::aliases::U112;
+use alloy::primitives::{Address, FixedBytes, B256, U256};
+use alloy::rpc::types::Log;
+use alloy::sol_types::SolEvent;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use eth_uniswap_alloy::candles::CandleBook;
+use eth_uniswap_alloy::db::create_pool;
+use eth_uniswap_alloy::db::models::{PricePointRecord, SyncEventRecord};
+use eth_uniswap_alloy::db::repository::Repository;
+use eth_uniswap_alloy::events::{decode_swap_event, decode_sync_event, Swap, Sync};
+use eth_uniswap_alloy::pricing::{calculate_price, calculate_price_exact};
+
+/// Rows per insert batch, about one backfill shard of a busy pool.
+const BATCH: u64 = 1_000;
+
+/// Prices recorded into the candle book: a day of blocks.
+const DAY_OF_BLOCKS: i64 = 7_200;
+
+fn log(block: u64, log_index: u64, data: alloy::primitives::LogData) -> Log {
+    Log {
+        inner: alloy::primitives::Log {
+            address: Address::repeat_byte(0x0d),
+            data,
+        },
+        block_hash: Some(B256::from(U256::from(block))),
+        block_number: Some(block),
+        block_timestamp: Some(1_700_000_000 + block * 12),
+        transaction_hash: Some(B256::from(U256::from(block) << 8)),
+        transaction_index: Some(0),
+        log_index: Some(log_index),
+        removed: false,
+    }
+}
+
+fn sync_log() -> Log {
+    let sync = Sync {
+        reserve0: U112::from(1_000u64) * U112::from(10u64).pow(U112::from(18u64)),
+        reserve1: U112::from(2_000_000u64) * U112::from(10u64).pow(U112::from(6u64)),
+    };
+    log(19_000_000, 0, sync.encode_log_data())
+}
+
+fn swap_log() -> Log {
+    let swap = Swap {
+        sender: Address::repeat_byte(0x77),
+        amount0In: U256::ZERO,
+        amount1In: U256::from(2_000_000_000u64),
+        amount0Out: U256::from(10u64).pow(U256::from(18u64)),
+        amount1Out: U256::ZERO,
+        to: Address::repeat_byte(0xa1),
+    };
+    log(19_000_000, 1, swap.encode_log_data())
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let sync = sync_log();
+    let swap = swap_log();
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("sync", |b| {
+        b.iter(|| decode_sync_event(black_box(&sync)).unwrap());
+    });
+    group.bench_function("swap", |b| {
+        b.iter(|| decode_swap_event(black_box(&swap)).unwrap());
+    });
+    group.finish();
+}
+
+fn bench_price(c: &mut Criterion) {
+    let reserve0 = U256::from(15_234u64) * U256::from(10u64).pow(U256::from(18u64));
+    let reserve1 = U256::from(35_070_411u64) * U256::from(10u64).pow(U256::from(6u64));
+    let mut group = c.benchmark_group("price");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("exact", |b| {
+        b.iter(|| calculate_price_exact(black_box(reserve0), black_box(reserve1), 18, 6).unwrap());
+    });
+    group.bench_function("f64", |b| {
+        b.iter(|| calculate_price(black_box(reserve0), black_box(reserve1), 18, 6).unwrap());
+    });
+    group.finish();
+}
+
+fn sync_events(pool_id: i64) -> Vec<SyncEventRecord> {
+    (0..BATCH)
+        .map(|block| {
+            let mut event = SyncEventRecord::new(
+                pool_id,
+                19_000_000 + block,
+                B256::from(U256::from(block)),
+                1_700_000_000 + block * 12,
+                B256::from(U256::from(block) << 8),
+                0,
+                U256::from(1_000u64) << 60,
+                U256::from(2_000_000u64 + block) << 20,
+                true,
+            );
+            event.event_id = Some(format!("sync-{block}"));
+            event
+        })
+        .collect()
+}
+
+fn price_points(pool_id: i64) -> Vec<PricePointRecord> {
+    (0..BATCH)
+        .map(|block| {
+            let mut price = PricePointRecord::new(
+                pool_id,
+                19_000_000 + block,
+                1_700_000_000 + block * 12,
+                FixedBytes::from(U256::from(block) << 8),
+                2_000.0 + (block % 100) as f64,
+                U256::from(1_000u64) << 60,
+                U256::from(2_000_000u64) << 20,
+                1_000.0,
+                2_000_000.0,
+                true,
+            );
+            price.event_id = Some(format!("price-{block}"));
+            price
+        })
+        .collect()
+}
+
+/// Times inserting a batch of `rows` into a fresh database per iteration,
+/// leaving database setup and row construction out of the measurement.
+fn bench_insert<T, R, F, Fut>(c: &mut Criterion, name: &str, rows: R, insert: F)
+where
+    R: Fn(i64) -> T,
+    F: Fn(Repository, T) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("insert");
+    group.throughput(Throughput::Elements(BATCH));
+    group.sample_size(20);
+    group.bench_function(name, |b| {
+        b.iter_custom(|iters| {
+            runtime.block_on(async {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+                    let batch = rows(repo.ensure_default_pool().await.unwrap());
+                    let started = Instant::now();
+                    insert(repo, batch).await;
+                    elapsed += started.elapsed();
+                }
+                elapsed
+            })
+        });
+    });
+    group.finish();
+}
+
+fn bench_inserts(c: &mut Criterion) {
+    bench_insert(c, "sync_events", sync_events, |repo, events| async move {
+        repo.batch_insert_sync_events(events).await.unwrap();
+    });
+    bench_insert(c, "price_points", price_points, |repo, prices| async move {
+        repo.batch_insert_price_points(prices).await.unwrap();
+    });
+}
+
+fn bench_candles(c: &mut Criterion) {
+    let start = 1_700_000_000;
+    let record_day = |book: &CandleBook| {
+        for block in 0..DAY_OF_BLOCKS {
+            book.record(1, block, start + block * 12, 2_000.0 + (block % 50) as f64);
+        }
+    };
+
+    let mut group = c.benchmark_group("candles");
+    group.throughput(Throughput::Elements(DAY_OF_BLOCKS.unsigned_abs()));
+    group.bench_function("record_day", |b| {
+        b.iter_batched(
+            CandleBook::new,
+            |book| record_day(&book),
+            BatchSize::SmallInput,
+        );
+    });
+
+    let book = CandleBook::new();
+    record_day(&book);
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("read_5m", |b| {
+        b.iter(|| book.candles(1, 300, black_box(288)).unwrap());
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_decode,
+    bench_price,
+    bench_inserts,
+    bench_candles
+);
+criterion_main!(benches);
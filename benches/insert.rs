@@ -0,0 +1,141 @@
+//! Throughput of the multi-row batch inserts against one `INSERT` per row.
+//!
+//! ```bash
+//! cargo bench --bench insert
+//! ```
+//!
+//! Each run upserts 100k sync events, as a large backfill does, into a fresh
+//! database: once with one statement per row inside a single transaction
+//! (the original write path) and once with `batch_insert_sync_events`. Both
+//! an in-memory and an on-disk (WAL) database are measured. The best of
+//! `RUNS` runs is reported.
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+#![allow(clippy::cast_precision_loss)]
+
+use std::time::{Duration, Instant};
+
+use alloy::primitives::{FixedBytes, U256};
+use eth_uniswap_alloy::db::create_pool;
+use eth_uniswap_alloy::db::models::SyncEventRecord;
+use eth_uniswap_alloy::db::repository::Repository;
+use sqlx::SqlitePool;
+
+/// Sync events per run.
+const EVENTS: u64 = 100_000;
+
+/// Runs per configuration.
+const RUNS: usize = 3;
+
+fn events(pool_id: i64) -> Vec<SyncEventRecord> {
+    (0..EVENTS)
+        .map(|i| {
+            SyncEventRecord::new(
+                pool_id,
+                19_000_000 + i,
+                FixedBytes::from(U256::from(i).to_be_bytes::<32>()),
+                1_706_745_600 + i * 12,
+                FixedBytes::from(U256::from(i << 8).to_be_bytes::<32>()),
+                0,
+                U256::from(45_000_000_000_000_000_000_u128 + u128::from(i)),
+                U256::from(110_250_000_000_u64 - i),
+                true,
+            )
+            .with_event_id(format!("sync-{i}"))
+        })
+        .collect()
+}
+
+/// One upsert per row, in one transaction.
+async fn insert_per_row(pool: &SqlitePool, events: &[SyncEventRecord]) {
+    let mut tx = pool.begin().await.unwrap();
+    for event in events {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_events (
+                pool_id, block_number, block_hash, block_timestamp, tx_hash,
+                log_index, reserve0, reserve1, is_confirmed, created_at, event_id
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (pool_id, block_number, tx_hash, log_index) DO UPDATE SET
+                event_id = COALESCE(excluded.event_id, sync_events.event_id),
+                block_hash = excluded.block_hash,
+                block_timestamp = excluded.block_timestamp,
+                reserve0 = excluded.reserve0,
+                reserve1 = excluded.reserve1,
+                is_confirmed = excluded.is_confirmed
+            "#,
+        )
+        .bind(event.pool_id)
+        .bind(event.block_number)
+        .bind(&event.block_hash)
+        .bind(event.block_timestamp)
+        .bind(event.tx_hash)
+        .bind(event.log_index)
+        .bind(&event.reserve0)
+        .bind(&event.reserve1)
+        .bind(event.is_confirmed)
+        .bind(event.created_at)
+        .bind(&event.event_id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+}
+
+async fn run(url: &str, batched: bool) -> Duration {
+    let pool = create_pool(url).await.unwrap();
+    let repo = Repository::new(pool.clone());
+    let pool_id = repo.ensure_default_pool().await.unwrap();
+    let events = events(pool_id);
+
+    let started = Instant::now();
+    if batched {
+        repo.batch_insert_sync_events(events).await.unwrap();
+    } else {
+        insert_per_row(&pool, &events).await;
+    }
+    let elapsed = started.elapsed();
+    pool.close().await;
+    elapsed
+}
+
+async fn bench(name: &str, url: impl Fn() -> String) {
+    println!("{name}");
+    let mut baseline = None;
+    for (label, batched) in [("per-row", false), ("multi-row", true)] {
+        let mut best = Duration::MAX;
+        for _ in 0..RUNS {
+            best = best.min(run(&url(), batched).await);
+        }
+        let baseline = *baseline.get_or_insert(best);
+        println!(
+            "  {label:>9}: {:>8.1} ms  {:>10.0} rows/s  {:.2}x",
+            best.as_secs_f64() * 1000.0,
+            EVENTS as f64 / best.as_secs_f64(),
+            baseline.as_secs_f64() / best.as_secs_f64()
+        );
+    }
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        bench("in-memory", || "sqlite::memory:".to_string()).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let runs = std::cell::Cell::new(0);
+        bench("on disk", || {
+            runs.set(runs.get() + 1);
+            let path = dir.path().join(format!("insert-{}.db", runs.get()));
+            format!("sqlite://{}?mode=rwc", path.display())
+        })
+        .await;
+    });
+}
@@ -0,0 +1,21 @@
+//! Compiles `proto/price_tracker.proto` into the gRPC service when the `grpc`
+//! feature is enabled.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    compile_protos()
+}
+
+#[cfg(feature = "grpc")]
+fn compile_protos() -> Result<(), Box<dyn std::error::Error>> {
+    // protox parses the proto in Rust, so building doesn't need `protoc`
+    let descriptors = protox::compile(["price_tracker.proto"], ["proto"])?;
+    tonic_build::configure().compile_fds(descriptors)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "grpc"))]
+#[allow(clippy::unnecessary_wraps)]
+const fn compile_protos() -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
@@ -0,0 +1,15 @@
+-- Deterministic record identifiers
+-- Version: 002
+-- Description: Adds stable event_id columns derived from on-chain coordinates
+
+-- =============================================================================
+-- SYNC EVENTS / PRICE POINTS
+-- =============================================================================
+-- event_id = UUIDv8 formatted keccak256(kind, chain_id, pool, block_hash, tx_hash, log_index)
+-- Computed by the indexer (see src/db/ids.rs), stable across database rebuilds.
+-- Nullable so that rows written before this migration can be backfilled in place.
+ALTER TABLE sync_events ADD COLUMN event_id TEXT;
+ALTER TABLE price_points ADD COLUMN event_id TEXT;
+
+CREATE UNIQUE INDEX idx_sync_events_event_id ON sync_events(event_id);
+CREATE UNIQUE INDEX idx_price_points_event_id ON price_points(event_id);
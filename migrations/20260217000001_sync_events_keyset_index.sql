@@ -0,0 +1,10 @@
+-- Keyset pagination index for sync events
+-- Version: 003
+-- Description: Supports cursor pagination over (block_number, log_index)
+
+-- =============================================================================
+-- SYNC EVENTS
+-- =============================================================================
+-- The events API pages with WHERE (block_number, log_index) > (?, ?), which
+-- needs an index whose column order matches the cursor exactly.
+CREATE INDEX idx_sync_events_pool_block_log ON sync_events(pool_id, block_number, log_index);
@@ -0,0 +1,21 @@
+-- API keys
+-- Version: 004
+-- Description: Hashed API keys for authenticating REST and WebSocket clients
+
+-- =============================================================================
+-- API KEYS TABLE
+-- =============================================================================
+-- Only the keccak256 hash of each key is stored; the plaintext is shown once
+-- when the key is minted. key_prefix identifies a key in listings and logs
+-- without revealing it.
+CREATE TABLE api_keys (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    rate_limit_rpm INTEGER,  -- NULL = use the server's default rate limit
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    revoked_at INTEGER  -- NULL = active
+);
+
+CREATE INDEX idx_api_keys_prefix ON api_keys(key_prefix);
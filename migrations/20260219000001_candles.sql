@@ -0,0 +1,25 @@
+-- Rolling candles
+-- Version: 005
+-- Description: 1m/5m OHLC candles flushed from the API server's in-memory candle book
+
+-- =============================================================================
+-- CANDLES TABLE
+-- =============================================================================
+-- The API server builds candles incrementally as new prices arrive and flushes
+-- changed buckets here periodically. On restart it reloads completed buckets
+-- from this table and rebuilds only the tail from price_points.
+-- price_sum keeps averages exact when candles are merged into longer periods.
+CREATE TABLE candles (
+    pool_id INTEGER NOT NULL,
+    interval_secs INTEGER NOT NULL,
+    bucket_start INTEGER NOT NULL,  -- unix seconds, aligned to interval_secs
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    price_sum REAL NOT NULL,
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    PRIMARY KEY (pool_id, interval_secs, bucket_start),
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+-- Exact prices
+-- Version: 006
+-- Description: Stores each price as an exact decimal string alongside the f64 display value
+
+-- =============================================================================
+-- PRICE POINTS
+-- =============================================================================
+-- price_exact = reserve1 / reserve0 adjusted for token decimals, computed in
+-- 18-decimal fixed point (see pricing::calculate_price_exact) and stored as a
+-- decimal string, e.g. '3333.333333333333333333'. The REAL price column is kept
+-- for display, sorting and aggregates.
+-- Nullable so that rows written before this migration can be backfilled in place.
+ALTER TABLE price_points ADD COLUMN price_exact TEXT;
@@ -0,0 +1,11 @@
+-- Smoothed prices
+-- Version: 007
+-- Description: Stores the indexer's EWMA-smoothed price alongside each spot price
+
+-- =============================================================================
+-- PRICE POINTS
+-- =============================================================================
+-- price_ewma = time-weighted exponential moving average of price as of this
+-- row (see smoothing::PriceEwma), with the half-life set by
+-- PRICE_EWMA_HALF_LIFE_SECS. NULL when smoothing is disabled.
+ALTER TABLE price_points ADD COLUMN price_ewma REAL;
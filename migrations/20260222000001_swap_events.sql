@@ -0,0 +1,34 @@
+-- Swap events
+-- Version: 008
+-- Description: Raw Uniswap V2 Swap events for trader analytics
+
+-- =============================================================================
+-- SWAP_EVENTS TABLE
+-- =============================================================================
+-- One row per Swap log, indexed alongside the Sync events of the same range
+-- and confirmed/invalidated together with them on finality and reorgs.
+-- Amounts are raw token units stored as TEXT for U256 precision.
+CREATE TABLE swap_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pool_id INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    block_timestamp INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index INTEGER NOT NULL,
+    sender TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    amount0_in TEXT NOT NULL,
+    amount1_in TEXT NOT NULL,
+    amount0_out TEXT NOT NULL,
+    amount1_out TEXT NOT NULL,
+    is_confirmed BOOLEAN NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    UNIQUE (pool_id, block_number, tx_hash, log_index),
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
+
+-- Analytics windows filter by time
+CREATE INDEX idx_swap_events_pool_timestamp ON swap_events(pool_id, block_timestamp);
+
+-- Per-trader queries
+CREATE INDEX idx_swap_events_pool_recipient ON swap_events(pool_id, recipient);
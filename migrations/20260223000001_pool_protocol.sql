@@ -0,0 +1,7 @@
+-- Pool protocols
+-- Version: 009
+-- Description: Which Uniswap V2 fork deployed each pool
+
+-- Forks share the pair contract (and so the Sync/Swap ABI) but not the
+-- factory or the swap fee. Existing pools are Uniswap V2.
+ALTER TABLE pools ADD COLUMN protocol TEXT NOT NULL DEFAULT 'uniswap_v2';
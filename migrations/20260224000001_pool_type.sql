@@ -0,0 +1,8 @@
+-- Pool types
+-- Version: 010
+-- Description: Pricing formula of each pool
+
+-- `constant_product` for Uniswap V2 and forks, `stable_swap:<A>:<fee_bps>`
+-- for Curve-style stable pools (see src/adapters.rs). Existing pools are
+-- constant product.
+ALTER TABLE pools ADD COLUMN pool_type TEXT NOT NULL DEFAULT 'constant_product';
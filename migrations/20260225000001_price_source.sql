@@ -0,0 +1,11 @@
+-- Price sources
+-- Version: 011
+-- Description: Records whether a price point came from a Sync event or a getReserves() call
+
+-- =============================================================================
+-- PRICE POINTS
+-- =============================================================================
+-- source = 'event' for prices computed from a Sync event, 'call' for
+-- snapshots watch mode takes with getReserves() when the pool has been quiet
+-- for RESERVE_SNAPSHOT_SECS (tx_hash is then all zeros).
+ALTER TABLE price_points ADD COLUMN source TEXT NOT NULL DEFAULT 'event';
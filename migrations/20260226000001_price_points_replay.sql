@@ -0,0 +1,31 @@
+-- Price replay shadow table
+-- Version: 012
+-- Description: Staging table for prices recomputed from stored Sync events by `replay`
+
+-- =============================================================================
+-- PRICE POINTS REPLAY TABLE
+-- =============================================================================
+-- `replay` recomputes a pool's prices from sync_events into this table, then
+-- compares them with price_points and, unless run with --shadow, swaps them
+-- in within one transaction. Rows are deleted once applied, so the table is
+-- only non-empty for a pool after a --shadow run.
+CREATE TABLE price_points_replay (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pool_id INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    block_timestamp INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    price REAL NOT NULL,
+    reserve0_raw TEXT NOT NULL,
+    reserve1_raw TEXT NOT NULL,
+    reserve0_human REAL NOT NULL,
+    reserve1_human REAL NOT NULL,
+    is_confirmed BOOLEAN NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    event_id TEXT,
+    price_exact TEXT,
+    price_ewma REAL,
+    source TEXT NOT NULL DEFAULT 'event',
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE,
+    UNIQUE(pool_id, block_number, tx_hash)
+);
@@ -0,0 +1,20 @@
+-- Data migration log
+-- Version: 013
+-- Description: Progress of Rust data migrations (see src/db/data_migrations.rs)
+
+-- =============================================================================
+-- MIGRATIONS LOG TABLE
+-- =============================================================================
+-- Schema changes stay in these SQL files. Data changes that need Rust (such as
+-- recomputing derived columns) run as named data migrations in batches, and
+-- record their checkpoint here after every batch so an interrupted run resumes
+-- where it stopped. A row with completed_at set is never run again.
+CREATE TABLE migrations_log (
+    name TEXT PRIMARY KEY,
+    description TEXT NOT NULL,
+    checkpoint TEXT,  -- Migration-defined resume position (NULL = from the start)
+    rows_done INTEGER NOT NULL DEFAULT 0,
+    started_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    completed_at INTEGER  -- NULL = not finished
+);
@@ -0,0 +1,8 @@
+-- Pool quote directions
+-- Version: 014
+-- Description: Direction in which each pool's prices are reported
+
+-- Prices are always stored as token1 per token0; `token0_per_token1` pools
+-- report the reciprocal (see QuoteDirection in src/pricing.rs). Existing
+-- pools keep reporting prices as stored.
+ALTER TABLE pools ADD COLUMN quote_direction TEXT NOT NULL DEFAULT 'token1_per_token0';
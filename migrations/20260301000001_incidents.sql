@@ -0,0 +1,26 @@
+-- Incidents
+-- Version: 015
+-- Description: Periods in which a pool's price misbehaved, such as stablecoin depegs
+
+-- =============================================================================
+-- INCIDENTS TABLE
+-- =============================================================================
+-- One row per incident, opened when the condition has held long enough and
+-- closed when it clears. The peak is the price farthest from normal seen so
+-- far, updated while the incident is ongoing.
+CREATE TABLE incidents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pool_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,  -- 'depeg'
+    band_bps INTEGER NOT NULL,  -- Allowed deviation when the incident opened
+    started_block INTEGER NOT NULL,  -- First block outside the band
+    started_at INTEGER NOT NULL,
+    ended_block INTEGER,  -- First block back inside the band (NULL = ongoing)
+    ended_at INTEGER,
+    peak_price REAL NOT NULL,
+    peak_deviation_bps REAL NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_incidents_pool ON incidents(pool_id, kind, started_block);
@@ -0,0 +1,23 @@
+-- Alert deliveries
+-- Version: 016
+-- Description: Outcome of every alert notification, for delivery status reporting
+
+-- =============================================================================
+-- ALERT DELIVERIES TABLE
+-- =============================================================================
+-- One row per notification, written once its retries are exhausted or it got
+-- through. Destinations are stored redacted, as webhook URLs often embed
+-- secrets.
+CREATE TABLE alert_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source TEXT NOT NULL,  -- Rule ID, 'lag_watchdog' or 'depeg'
+    event TEXT NOT NULL,  -- e.g. 'price_alert', 'lag_exceeded'
+    channel TEXT NOT NULL,  -- 'webhook', 'slack', 'telegram' or 'email'
+    destination TEXT NOT NULL,
+    status TEXT NOT NULL,  -- 'delivered' or 'failed'
+    attempts INTEGER NOT NULL,
+    error TEXT,  -- Last error of a failed delivery
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_alert_deliveries_created ON alert_deliveries(created_at);
@@ -0,0 +1,23 @@
+-- Reorgs
+-- Version: 017
+-- Description: Chain reorganizations handled by the indexer
+
+-- =============================================================================
+-- REORGS TABLE
+-- =============================================================================
+-- One row per reorg handled by `watch`. Blocks after the fork point were
+-- rolled back; `invalidated_events` counts the pool's sync events in them
+-- that were marked unconfirmed.
+CREATE TABLE reorgs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pool_id INTEGER NOT NULL,
+    detected_at INTEGER NOT NULL,
+    fork_point INTEGER NOT NULL,  -- Last block common to both chains
+    depth INTEGER NOT NULL,  -- Indexed blocks rolled back
+    invalidated_events INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_reorgs_pool ON reorgs(pool_id, detected_at);
+CREATE INDEX idx_reorgs_detected ON reorgs(detected_at);
@@ -0,0 +1,26 @@
+-- Event archive
+-- Version: 018
+-- Description: Compressed segments of old sync events
+
+-- =============================================================================
+-- EVENT ARCHIVE TABLE
+-- =============================================================================
+-- Confirmed sync events of a pool, grouped by segments of consecutive blocks
+-- and stored as one compressed blob per segment. Archived rows are deleted
+-- from sync_events; reads of historical reserves, price paths, replays and
+-- event listings decompress the segments they need.
+CREATE TABLE event_archive (
+    pool_id INTEGER NOT NULL,
+    segment_start INTEGER NOT NULL,  -- First block of the segment
+    segment_end INTEGER NOT NULL,  -- Last block of the segment
+    event_count INTEGER NOT NULL,
+    first_block INTEGER NOT NULL,  -- First block with an archived event
+    last_block INTEGER NOT NULL,  -- Last block with an archived event
+    last_timestamp INTEGER NOT NULL,
+    codec TEXT NOT NULL,  -- 'zstd-json'
+    raw_bytes INTEGER NOT NULL,  -- Size before compression
+    data BLOB NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+    PRIMARY KEY (pool_id, segment_start),
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
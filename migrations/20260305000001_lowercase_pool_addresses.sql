@@ -0,0 +1,17 @@
+-- Lowercase pool addresses
+-- Version: 019
+-- Description: Stores every pool address in the canonical lowercase form
+
+-- =============================================================================
+-- POOLS
+-- =============================================================================
+-- The default pool used to be inserted with its checksummed address, so
+-- lookups had to compare lower(address). Pool addresses are now always bound
+-- as lowercase hex (db::types::PoolAddress); rewrite older rows to match,
+-- unless the lowercase form is already taken by another row.
+UPDATE pools
+SET address = lower(address)
+WHERE address != lower(address)
+  AND NOT EXISTS (
+      SELECT 1 FROM pools other WHERE other.address = lower(pools.address)
+  );
@@ -0,0 +1,11 @@
+-- Per-pool indexing settings
+-- Version: 020
+-- Description: Pause indexing of a pool and override the block it starts at
+
+-- Disabled pools are skipped by watch and refused by backfill until they are
+-- enabled again; their indexed data stays queryable.
+ALTER TABLE pools ADD COLUMN enabled INTEGER NOT NULL DEFAULT 1;
+
+-- First block to index for a pool without a checkpoint (e.g. its creation
+-- block). NULL keeps the default of starting near the chain head.
+ALTER TABLE pools ADD COLUMN start_block INTEGER;
@@ -0,0 +1,19 @@
+-- Indexer tasks
+-- Version: 021
+-- Description: Status of the per-pool tasks run by the watch scheduler
+
+-- =============================================================================
+-- INDEXER TASKS TABLE
+-- =============================================================================
+-- One row per pool the watch scheduler has run a task for, updated as the
+-- task starts, finishes passes, fails and restarts. Read by the API to show
+-- each pool's indexing status next to its checkpoint.
+CREATE TABLE indexer_tasks (
+    pool_id INTEGER PRIMARY KEY,
+    status TEXT NOT NULL,  -- starting, running, failing, restarting, disabled, stopped
+    last_block INTEGER,  -- Last block indexed by the task
+    restarts INTEGER NOT NULL DEFAULT 0,  -- Times the task was restarted after failing
+    last_error TEXT,  -- Error of the last failed pass or task, cleared by a successful pass
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
@@ -0,0 +1,20 @@
+-- Composite prices
+-- Version: 022
+-- Description: Liquidity-weighted price index across several pools
+
+-- =============================================================================
+-- COMPOSITE PRICES TABLE
+-- =============================================================================
+-- One row per recorded index value, written by the API server's composite
+-- indexer whenever a constituent pool has a newer confirmed price. Each pool's
+-- price is weighted by its quote-side liquidity.
+CREATE TABLE composite_prices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    block_number INTEGER NOT NULL UNIQUE,  -- Newest block among the constituents
+    block_timestamp INTEGER NOT NULL,  -- Block timestamp of block_number
+    price REAL NOT NULL,  -- Liquidity-weighted average price
+    liquidity REAL NOT NULL,  -- Total quote-side liquidity of the constituents
+    pools INTEGER NOT NULL,  -- Constituents included in the price
+    constituents TEXT NOT NULL,  -- JSON array of {pool_id, block_number, price, liquidity}
+    created_at INTEGER NOT NULL DEFAULT (unixepoch())
+);
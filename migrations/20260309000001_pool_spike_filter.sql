@@ -0,0 +1,8 @@
+-- Pool spike filter
+-- Version: 023
+-- Description: Per-pool threshold for leaving flash-loan spikes out of candles and stats
+
+-- A price point moving at least spike_filter_bps basis points away from the
+-- price before its block, in a block that closes back where it opened, is
+-- left out of candles and stats. NULL keeps every price point.
+ALTER TABLE pools ADD COLUMN spike_filter_bps INTEGER;
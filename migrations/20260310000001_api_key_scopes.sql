@@ -0,0 +1,17 @@
+-- API key scopes
+-- Version: 024
+-- Description: Restrict API keys to a set of pools and a chain
+
+-- A key with pool_scoped = 1 only sees the pools listed for it in
+-- api_key_pools (none once they are all removed); other keys see every
+-- pool. chain_id NULL accepts the key on any chain.
+ALTER TABLE api_keys ADD COLUMN pool_scoped INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE api_keys ADD COLUMN chain_id INTEGER;
+
+CREATE TABLE api_key_pools (
+    api_key_id INTEGER NOT NULL,
+    pool_id INTEGER NOT NULL,
+    PRIMARY KEY (api_key_id, pool_id),
+    FOREIGN KEY (api_key_id) REFERENCES api_keys(id) ON DELETE CASCADE,
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
@@ -0,0 +1,24 @@
+-- Price webhook dead letters
+-- Version: 025
+-- Description: Price webhook deliveries that failed every attempt, kept for replay
+
+-- =============================================================================
+-- PRICE WEBHOOK DEAD LETTERS TABLE
+-- =============================================================================
+-- One row per price that could not be delivered to one of PRICE_WEBHOOK_URLS.
+-- The URL itself is not stored, as webhook URLs often embed secrets: rows
+-- keep its redacted form for display and a hash to replay them to it.
+CREATE TABLE price_webhook_dead_letters (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    destination TEXT NOT NULL,  -- Redacted URL
+    destination_key TEXT NOT NULL,  -- Hash of the full URL
+    pool_id INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    payload TEXT NOT NULL,  -- JSON body that was posted
+    attempts INTEGER NOT NULL,
+    error TEXT NOT NULL,  -- Last error
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_price_webhook_dead_letters_created ON price_webhook_dead_letters(created_at);
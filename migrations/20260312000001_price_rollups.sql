@@ -0,0 +1,143 @@
+-- Hourly price rollups
+-- Version: 026
+-- Description: Per-hour aggregates of confirmed price points, maintained by triggers
+
+-- =============================================================================
+-- PRICE ROLLUPS TABLE
+-- =============================================================================
+-- One row per pool and hour holding the OHLC, count and sum of the hour's
+-- confirmed price points, so period stats and the 24h change read a few
+-- hundred rollups instead of every price point. New confirmed prices are
+-- folded in by a trigger. open_* and close_* hold the (block_number, id) of
+-- the hour's first and last price, so rows folded in out of order (concurrent
+-- backfill workers) still end up with the right open and close.
+CREATE TABLE price_rollups (
+    pool_id INTEGER NOT NULL,
+    hour_start INTEGER NOT NULL,  -- unix seconds, multiple of 3600
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    price_sum REAL NOT NULL,
+    first_timestamp INTEGER NOT NULL,
+    last_timestamp INTEGER NOT NULL,
+    open_block INTEGER NOT NULL,
+    open_id INTEGER NOT NULL,
+    close_block INTEGER NOT NULL,
+    close_id INTEGER NOT NULL,
+    PRIMARY KEY (pool_id, hour_start),
+    FOREIGN KEY (pool_id) REFERENCES pools(id) ON DELETE CASCADE
+);
+
+-- =============================================================================
+-- STALE ROLLUPS
+-- =============================================================================
+-- Minimum and maximum can't be taken back incrementally, so confirmations,
+-- invalidations, rewrites and deletes only record the hours they touch here.
+-- The repository rebuilds them from price_rollups_rebuilt once per statement
+-- batch (reorg, replay, retention) instead of once per row, and reads fall
+-- back to price points for a pool while any of its hours are stale.
+CREATE TABLE price_rollups_stale (
+    pool_id INTEGER NOT NULL,
+    hour_start INTEGER NOT NULL,
+    PRIMARY KEY (pool_id, hour_start)
+) WITHOUT ROWID;
+
+-- The rollups of the stale hours, aggregated from their confirmed price points
+-- (hours left without any are absent)
+CREATE VIEW price_rollups_rebuilt AS
+SELECT pool_id, hour_start, open, MAX(price) AS high, MIN(price) AS low, close,
+       COUNT(*) AS samples, SUM(price) AS price_sum,
+       MIN(block_timestamp) AS first_timestamp, MAX(block_timestamp) AS last_timestamp,
+       open_block, open_id, close_block, close_id
+FROM (
+    SELECT s.pool_id, s.hour_start, p.price, p.block_timestamp,
+           FIRST_VALUE(p.price) OVER hour AS open,
+           FIRST_VALUE(p.block_number) OVER hour AS open_block,
+           FIRST_VALUE(p.id) OVER hour AS open_id,
+           LAST_VALUE(p.price) OVER hour AS close,
+           LAST_VALUE(p.block_number) OVER hour AS close_block,
+           LAST_VALUE(p.id) OVER hour AS close_id
+    FROM price_rollups_stale s
+    JOIN price_points p
+      ON p.pool_id = s.pool_id AND p.is_confirmed = 1
+     AND p.block_timestamp >= s.hour_start AND p.block_timestamp < s.hour_start + 3600
+    WINDOW hour AS (
+        PARTITION BY s.pool_id, s.hour_start ORDER BY p.block_number, p.id
+        ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+    )
+)
+GROUP BY pool_id, hour_start;
+
+-- Existing confirmed prices: every hour starts out stale
+INSERT INTO price_rollups_stale (pool_id, hour_start)
+SELECT DISTINCT pool_id, (block_timestamp / 3600) * 3600
+FROM price_points
+WHERE is_confirmed = 1;
+
+INSERT INTO price_rollups SELECT * FROM price_rollups_rebuilt;
+
+DELETE FROM price_rollups_stale;
+
+-- =============================================================================
+-- TRIGGERS
+-- =============================================================================
+-- A new confirmed price is folded into its hour
+CREATE TRIGGER price_rollups_insert
+AFTER INSERT ON price_points
+WHEN NEW.is_confirmed = 1
+BEGIN
+    INSERT INTO price_rollups (
+        pool_id, hour_start, open, high, low, close, samples, price_sum,
+        first_timestamp, last_timestamp, open_block, open_id, close_block, close_id
+    )
+    VALUES (
+        NEW.pool_id, (NEW.block_timestamp / 3600) * 3600, NEW.price, NEW.price, NEW.price,
+        NEW.price, 1, NEW.price, NEW.block_timestamp, NEW.block_timestamp,
+        NEW.block_number, NEW.id, NEW.block_number, NEW.id
+    )
+    ON CONFLICT (pool_id, hour_start) DO UPDATE SET
+        open = CASE WHEN (excluded.open_block, excluded.open_id) < (open_block, open_id)
+            THEN excluded.open ELSE open END,
+        open_block = MIN(open_block, excluded.open_block),
+        open_id = CASE WHEN (excluded.open_block, excluded.open_id) < (open_block, open_id)
+            THEN excluded.open_id ELSE open_id END,
+        close = CASE WHEN (excluded.close_block, excluded.close_id) > (close_block, close_id)
+            THEN excluded.close ELSE close END,
+        close_block = MAX(close_block, excluded.close_block),
+        close_id = CASE WHEN (excluded.close_block, excluded.close_id) > (close_block, close_id)
+            THEN excluded.close_id ELSE close_id END,
+        high = MAX(high, excluded.high),
+        low = MIN(low, excluded.low),
+        samples = samples + excluded.samples,
+        price_sum = price_sum + excluded.price_sum,
+        first_timestamp = MIN(first_timestamp, excluded.first_timestamp),
+        last_timestamp = MAX(last_timestamp, excluded.last_timestamp);
+END;
+
+-- Confirming, invalidating or rewriting a price marks the hours it was and is in
+CREATE TRIGGER price_rollups_update
+AFTER UPDATE OF is_confirmed, price, block_timestamp, block_number ON price_points
+WHEN (OLD.is_confirmed = 1 OR NEW.is_confirmed = 1) AND NOT (
+    OLD.is_confirmed = NEW.is_confirmed
+    AND OLD.price = NEW.price
+    AND OLD.block_timestamp = NEW.block_timestamp
+    AND OLD.block_number = NEW.block_number
+)
+BEGIN
+    INSERT INTO price_rollups_stale (pool_id, hour_start)
+    VALUES (OLD.pool_id, (OLD.block_timestamp / 3600) * 3600),
+           (NEW.pool_id, (NEW.block_timestamp / 3600) * 3600)
+    ON CONFLICT DO NOTHING;
+END;
+
+-- Deleting a confirmed price (replay, retention) marks its hour
+CREATE TRIGGER price_rollups_delete
+AFTER DELETE ON price_points
+WHEN OLD.is_confirmed = 1
+BEGIN
+    INSERT INTO price_rollups_stale (pool_id, hour_start)
+    VALUES (OLD.pool_id, (OLD.block_timestamp / 3600) * 3600)
+    ON CONFLICT DO NOTHING;
+END;
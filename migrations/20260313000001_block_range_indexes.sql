@@ -0,0 +1,22 @@
+-- Block-range indexes for history queries
+-- Version: 027
+-- Description: Supports from_block/to_block filters alongside timestamp ranges
+
+-- =============================================================================
+-- PRICE POINTS
+-- =============================================================================
+-- History, export and stats filter confirmed prices by block number as well
+-- as by timestamp. Leading with is_confirmed keeps the range scan to the
+-- confirmed rows of the pool instead of filtering unconfirmed ones out of
+-- idx_price_points_pool_block.
+CREATE INDEX idx_price_points_pool_confirmed_block
+    ON price_points(pool_id, is_confirmed, block_number);
+CREATE INDEX idx_price_points_pool_confirmed_timestamp
+    ON price_points(pool_id, is_confirmed, block_timestamp);
+
+-- =============================================================================
+-- SYNC EVENTS
+-- =============================================================================
+-- Block ranges over events are served by idx_sync_events_pool_block_log
+-- (pool_id, block_number, log_index), which also keeps keyset pagination
+-- within the range an index seek.
@@ -0,0 +1,469 @@
+//! Pricing adapters for pool types.
+//!
+//! Every pool is priced from its two reserves, but the formula depends on the
+//! pool's curve. A [`PriceAdapter`] turns reserves into an exact price and
+//! simulates swaps for one [`PoolType`]:
+//!
+//! - [`ConstantProduct`]: Uniswap V2 and its forks (`x * y = k`)
+//! - [`StableSwap`]: Curve-style two-coin stable pools, whose amplified
+//!   invariant keeps the price near 1:1 until the pool is unbalanced
+//!
+//! The pool type is stored in `pools.pool_type` (`constant_product`, or
+//! `stable_swap:<A>[:<fee_bps>]` with the pool's amplification coefficient
+//! and fee), so the repository and the API stay agnostic of it:
+//! [`PoolRecord::price_adapter`](crate::db::models::PoolRecord::price_adapter)
+//! picks the adapter wherever a pool is priced.
+//!
+//! Reserves still come from the indexed events; a stable pool is priced
+//! correctly once its balances are recorded as the pool's reserves.
+
+use alloy::primitives::{U256, U512};
+use std::str::FromStr;
+
+use crate::error::{TrackerError, TrackerResult};
+use crate::pricing::{
+    calculate_price, calculate_price_exact, quote_exact_input_with_fee, SwapQuote, PRICE_DECIMALS,
+};
+
+/// Curve's default swap fee in basis points (0.04%).
+pub const DEFAULT_STABLE_SWAP_FEE_BPS: u32 = 4;
+
+/// Newton iterations before the invariant math gives up (Curve uses 255).
+const MAX_ITERATIONS: usize = 255;
+
+/// Decimals balances are normalized to for the invariant math.
+const NORMALIZED_DECIMALS: u8 = 18;
+
+/// The curve a pool trades on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoolType {
+    /// `x * y = k` (Uniswap V2 and forks)
+    #[default]
+    ConstantProduct,
+    /// Curve `StableSwap` invariant with two coins
+    StableSwap {
+        /// Amplification coefficient `A`
+        amplification: u64,
+        /// Swap fee in basis points
+        fee_bps: u32,
+    },
+}
+
+impl PoolType {
+    /// Returns the adapter pricing pools of this type.
+    ///
+    /// `fee_bps` is the swap fee of constant-product pools, which depends on
+    /// the protocol (see [`DexProtocol::fee_bps`](crate::protocol::DexProtocol::fee_bps));
+    /// stable pools carry their own.
+    #[must_use]
+    pub fn adapter(self, fee_bps: u32) -> Box<dyn PriceAdapter> {
+        match self {
+            Self::ConstantProduct => Box::new(ConstantProduct { fee_bps }),
+            Self::StableSwap {
+                amplification,
+                fee_bps,
+            } => Box::new(StableSwap {
+                amplification,
+                fee_bps,
+            }),
+        }
+    }
+}
+
+impl FromStr for PoolType {
+    type Err = TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            TrackerError::config(
+                format!(
+                    "Pool type must be constant_product or stable_swap:<A>[:<fee_bps>], got: {}",
+                    s.trim()
+                ),
+                None,
+            )
+        };
+
+        let mut parts = s.trim().split(':');
+        match parts.next().map(str::to_ascii_lowercase).as_deref() {
+            Some("" | "constant_product") if parts.next().is_none() => Ok(Self::ConstantProduct),
+            Some("stable_swap") => {
+                let amplification = parts
+                    .next()
+                    .and_then(|a| a.trim().parse::<u64>().ok())
+                    .filter(|a| *a > 0)
+                    .ok_or_else(invalid)?;
+                let fee_bps = match parts.next() {
+                    Some(fee) => fee
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|f| *f < 10_000)
+                        .ok_or_else(invalid)?,
+                    None => DEFAULT_STABLE_SWAP_FEE_BPS,
+                };
+                if parts.next().is_some() {
+                    return Err(invalid());
+                }
+                Ok(Self::StableSwap {
+                    amplification,
+                    fee_bps,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for PoolType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ConstantProduct => f.write_str("constant_product"),
+            Self::StableSwap {
+                amplification,
+                fee_bps,
+            } => write!(f, "stable_swap:{amplification}:{fee_bps}"),
+        }
+    }
+}
+
+/// Prices a pool from its reserves.
+pub trait PriceAdapter: Send + Sync {
+    /// Returns the pool type this adapter prices.
+    fn pool_type(&self) -> PoolType;
+
+    /// Returns the swap fee in basis points.
+    fn fee_bps(&self) -> u32;
+
+    /// Returns the marginal price of token0 in token1, scaled by
+    /// 10^[`PRICE_DECIMALS`] like
+    /// [`calculate_price_exact`](crate::pricing::calculate_price_exact).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reserve is zero or the calculation overflows.
+    fn price_exact(
+        &self,
+        reserve0: U256,
+        reserve1: U256,
+        decimals0: u8,
+        decimals1: u8,
+    ) -> TrackerResult<U256>;
+
+    /// Simulates selling `amount_in` into the pool, fee included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the amount or a reserve is zero, or the
+    /// calculation overflows.
+    fn quote(
+        &self,
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        decimals_in: u8,
+        decimals_out: u8,
+    ) -> TrackerResult<SwapQuote>;
+}
+
+/// Constant-product (`x * y = k`) pricing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantProduct {
+    /// Swap fee in basis points
+    pub fee_bps: u32,
+}
+
+impl PriceAdapter for ConstantProduct {
+    fn pool_type(&self) -> PoolType {
+        PoolType::ConstantProduct
+    }
+
+    fn fee_bps(&self) -> u32 {
+        self.fee_bps
+    }
+
+    fn price_exact(
+        &self,
+        reserve0: U256,
+        reserve1: U256,
+        decimals0: u8,
+        decimals1: u8,
+    ) -> TrackerResult<U256> {
+        calculate_price_exact(reserve0, reserve1, decimals0, decimals1)
+    }
+
+    fn quote(
+        &self,
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        decimals_in: u8,
+        decimals_out: u8,
+    ) -> TrackerResult<SwapQuote> {
+        quote_exact_input_with_fee(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            decimals_in,
+            decimals_out,
+            self.fee_bps,
+        )
+    }
+}
+
+/// Curve `StableSwap` pricing for two-coin pools.
+///
+/// Balances are normalized to 18 decimals and solved against the invariant
+/// `A·n·S + D = A·n·D + D³ / (4·x·y)` (with Curve's `Ann = A·n`), using the
+/// same integer Newton iterations as the pool contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableSwap {
+    /// Amplification coefficient `A`
+    pub amplification: u64,
+    /// Swap fee in basis points
+    pub fee_bps: u32,
+}
+
+impl StableSwap {
+    /// `A·n` for two coins.
+    fn ann(&self) -> U512 {
+        U512::from(self.amplification) * U512::from(2u8)
+    }
+
+    /// Solves the invariant `D` for balances `x` and `y`.
+    fn invariant(&self, x: U512, y: U512) -> TrackerResult<U512> {
+        let sum = x + y;
+        if sum.is_zero() {
+            return Ok(U512::ZERO);
+        }
+        let ann = self.ann();
+        let two = U512::from(2u8);
+        let mut d = sum;
+        for _ in 0..MAX_ITERATIONS {
+            let d_squared = d * d;
+            let d_p = d_squared / (x * two) * d / (y * two);
+            let previous = d;
+            d = (ann * sum + d_p * two) * d / ((ann - U512::from(1u8)) * d + U512::from(3u8) * d_p);
+            if d.abs_diff(previous) <= U512::from(1u8) {
+                return Ok(d);
+            }
+        }
+        Err(TrackerError::math(
+            "StableSwap invariant did not converge",
+            None,
+        ))
+    }
+
+    /// Solves the balance of one coin given the other's balance `x` and `d`.
+    fn balance_for(&self, x: U512, d: U512) -> TrackerResult<U512> {
+        let ann = self.ann();
+        let two = U512::from(2u8);
+        let d_squared = d * d;
+        let constant = d_squared / (x * two) * d / (ann * two);
+        let linear = x + d / ann;
+        let mut y = d;
+        for _ in 0..MAX_ITERATIONS {
+            let previous = y;
+            y = (y * y + constant) / (two * y + linear - d);
+            if y.abs_diff(previous) <= U512::from(1u8) {
+                return Ok(y);
+            }
+        }
+        Err(TrackerError::math(
+            "StableSwap balance did not converge",
+            None,
+        ))
+    }
+}
+
+impl PriceAdapter for StableSwap {
+    fn pool_type(&self) -> PoolType {
+        PoolType::StableSwap {
+            amplification: self.amplification,
+            fee_bps: self.fee_bps,
+        }
+    }
+
+    fn fee_bps(&self) -> u32 {
+        self.fee_bps
+    }
+
+    fn price_exact(
+        &self,
+        reserve0: U256,
+        reserve1: U256,
+        decimals0: u8,
+        decimals1: u8,
+    ) -> TrackerResult<U256> {
+        if reserve0.is_zero() || reserve1.is_zero() {
+            return Err(TrackerError::math(
+                "Pool reserve is zero, cannot calculate price",
+                None,
+            ));
+        }
+        let x = normalize(reserve0, decimals0)?;
+        let y = normalize(reserve1, decimals1)?;
+        let d = self.invariant(x, y)?;
+
+        // -dy/dx of the invariant, with both partial derivatives multiplied
+        // by 4·x²·y² to stay in integers
+        let d_cubed = d * d * d;
+        let base = self.ann() * U512::from(4u8) * x * x * y * y;
+        let numerator = (base + d_cubed * y) * U512::from(10u8).pow(U512::from(PRICE_DECIMALS));
+        let denominator = base + d_cubed * x;
+
+        narrow(numerator / denominator)
+            .ok_or_else(|| TrackerError::math("StableSwap price overflows U256", None))
+    }
+
+    fn quote(
+        &self,
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        decimals_in: u8,
+        decimals_out: u8,
+    ) -> TrackerResult<SwapQuote> {
+        if amount_in.is_zero() {
+            return Err(TrackerError::math("Input amount is zero", None));
+        }
+        let spot_price = crate::pricing::exact_price_to_f64(self.price_exact(
+            reserve_in,
+            reserve_out,
+            decimals_in,
+            decimals_out,
+        )?);
+
+        let x = normalize(reserve_in, decimals_in)?;
+        let y = normalize(reserve_out, decimals_out)?;
+        let d = self.invariant(x, y)?;
+        let new_y = self.balance_for(x + normalize(amount_in, decimals_in)?, d)?;
+
+        // Like the contract: round down by one unit, then take the fee
+        let dy = y.saturating_sub(new_y).saturating_sub(U512::from(1u8));
+        let dy = dy - dy * U512::from(self.fee_bps) / U512::from(10_000u32);
+        let amount_out = narrow(dy / scale(decimals_out)?)
+            .ok_or_else(|| TrackerError::math("Overflow when simulating swap", None))?;
+
+        let execution_price = if amount_out.is_zero() {
+            0.0
+        } else {
+            calculate_price(amount_in, amount_out, decimals_in, decimals_out)?
+        };
+
+        Ok(SwapQuote {
+            amount_out,
+            spot_price,
+            execution_price,
+            price_impact_pct: (1.0 - execution_price / spot_price) * 100.0,
+        })
+    }
+}
+
+/// Factor bringing an amount with `decimals` to 18 decimals.
+fn scale(decimals: u8) -> TrackerResult<U512> {
+    let exponent = NORMALIZED_DECIMALS.checked_sub(decimals).ok_or_else(|| {
+        TrackerError::math(
+            format!("StableSwap pools support at most 18 decimals, got {decimals}"),
+            None,
+        )
+    })?;
+    Ok(U512::from(10u8).pow(U512::from(exponent)))
+}
+
+fn normalize(amount: U256, decimals: u8) -> TrackerResult<U512> {
+    Ok(U512::from(amount) * scale(decimals)?)
+}
+
+const fn narrow(value: U512) -> Option<U256> {
+    U256::checked_from_limbs_slice(value.as_limbs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::format_exact_price;
+
+    fn units(amount: u128, decimals: u8) -> U256 {
+        U256::from(amount) * U256::from(10u8).pow(U256::from(decimals))
+    }
+
+    #[test]
+    fn test_pool_type_round_trip() {
+        assert_eq!(
+            "constant_product".parse::<PoolType>().unwrap(),
+            PoolType::ConstantProduct
+        );
+        let stable = "stable_swap:100".parse::<PoolType>().unwrap();
+        assert_eq!(
+            stable,
+            PoolType::StableSwap {
+                amplification: 100,
+                fee_bps: DEFAULT_STABLE_SWAP_FEE_BPS
+            }
+        );
+        assert_eq!(stable.to_string().parse::<PoolType>().unwrap(), stable);
+        assert!("stable_swap".parse::<PoolType>().is_err());
+        assert!("stable_swap:0".parse::<PoolType>().is_err());
+        assert!("weighted".parse::<PoolType>().is_err());
+    }
+
+    #[test]
+    fn test_stable_swap_prices_balanced_pool_at_par() {
+        let pool = StableSwap {
+            amplification: 100,
+            fee_bps: 4,
+        };
+        // 1M USDC (6 decimals) / 1M DAI (18 decimals)
+        let price = pool
+            .price_exact(units(1_000_000, 6), units(1_000_000, 18), 6, 18)
+            .unwrap();
+        assert_eq!(format_exact_price(price), "1");
+    }
+
+    #[test]
+    fn test_stable_swap_stays_flatter_than_constant_product() {
+        let stable = StableSwap {
+            amplification: 100,
+            fee_bps: 0,
+        };
+        let constant = ConstantProduct { fee_bps: 0 };
+        // Unbalanced 2:1 pool
+        let (r0, r1) = (units(2_000_000, 18), units(1_000_000, 18));
+
+        let stable_price =
+            crate::pricing::exact_price_to_f64(stable.price_exact(r0, r1, 18, 18).unwrap());
+        let constant_price =
+            crate::pricing::exact_price_to_f64(constant.price_exact(r0, r1, 18, 18).unwrap());
+        assert!((constant_price - 0.5).abs() < 1e-12);
+        assert!(stable_price < 1.0 && stable_price > 0.95, "{stable_price}");
+
+        // A 10k swap into a balanced pool barely moves a stable pool
+        let balanced = units(1_000_000, 18);
+        let quote = stable
+            .quote(units(10_000, 18), balanced, balanced, 18, 18)
+            .unwrap();
+        assert!(quote.price_impact_pct < 0.01, "{}", quote.price_impact_pct);
+        let quote = constant
+            .quote(units(10_000, 18), balanced, balanced, 18, 18)
+            .unwrap();
+        assert!(quote.price_impact_pct > 0.9);
+    }
+
+    #[test]
+    fn test_stable_swap_quote_charges_fee() {
+        let pool = StableSwap {
+            amplification: 200,
+            fee_bps: 4,
+        };
+        let balanced = units(1_000_000, 18);
+        let quote = pool
+            .quote(units(100, 6), units(1_000_000, 6), balanced, 6, 18)
+            .unwrap();
+        // ~100 out minus the 0.04% fee
+        let out = crate::pricing::format_token_amount(quote.amount_out, 18)
+            .parse::<f64>()
+            .unwrap();
+        assert!((out - 99.96).abs() < 0.001, "{out}");
+    }
+}
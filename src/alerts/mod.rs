@@ -0,0 +1,239 @@
+//! Price alerts delivered to webhooks, Slack, Telegram, email or Discord.
+//!
+//! The [`AlertEngine`] evaluates [`AlertRule`]s against every new price the API
+//! server observes and sends matching alerts to each rule's destination through
+//! [`Notifiers`], which retries failed deliveries and records each outcome.
+//! Delivery goes through an [`AlertThrottle`] that applies a per-rule de-duplication
+//! window and a per-destination rate limit; everything it suppresses is
+//! counted and reported by `GET /api/v1/alerts`.
+//!
+//! # Modules
+//!
+//! - `notify`: Notification channels, retries and the delivery log
+//! - `rules`: Rule definitions and JSON loading
+//! - `throttle`: De-duplication windows and destination rate limits
+//! - `webhook`: HTTP delivery
+
+pub mod notify;
+pub mod rules;
+pub mod throttle;
+pub mod webhook;
+
+pub use notify::{Channel, Destination, Notification, Notifier, Notifiers};
+pub use rules::{load_rules, AlertCondition, AlertRule};
+pub use throttle::{AlertThrottle, SuppressionCounts, ThrottleDecision};
+pub use webhook::{AlertPayload, WebhookNotifier};
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Snapshot of a rule's state for status reporting.
+#[derive(Debug, Clone)]
+pub struct RuleStatus {
+    /// The rule
+    pub rule: AlertRule,
+    /// Whether the condition held at the last observed price
+    pub active: bool,
+    /// When the rule last fired
+    pub last_fired_at: Option<DateTime<Utc>>,
+    /// Delivery and suppression counters
+    pub counts: SuppressionCounts,
+}
+
+#[derive(Debug, Default)]
+struct EngineState {
+    throttle: AlertThrottle,
+    last_price: HashMap<String, f64>,
+    active: HashMap<String, bool>,
+    last_fired_at: HashMap<String, DateTime<Utc>>,
+}
+
+/// Evaluates alert rules and delivers throttled alerts.
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    notifiers: Notifiers,
+    state: Mutex<EngineState>,
+}
+
+impl AlertEngine {
+    /// Creates an engine for the given rules.
+    #[must_use]
+    pub fn new(rules: Vec<AlertRule>, notifiers: Notifiers) -> Self {
+        Self {
+            rules,
+            notifiers,
+            state: Mutex::new(EngineState::default()),
+        }
+    }
+
+    /// Returns the configured rules.
+    #[must_use]
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Evaluates all rules for a pool against a new price.
+    ///
+    /// Returns the payloads that passed the throttle, so callers can deliver
+    /// them (see [`Self::on_price`]) without holding the engine lock.
+    pub fn evaluate(
+        &self,
+        pool: &str,
+        price: f64,
+        block_number: u64,
+        now: Instant,
+    ) -> Vec<(Destination, AlertPayload)> {
+        let Ok(mut state) = self.state.lock() else {
+            warn!("Alert engine state poisoned, skipping evaluation");
+            return Vec::new();
+        };
+
+        let previous = state.last_price.insert(pool.to_string(), price);
+        let mut due = Vec::new();
+
+        for rule in self.rules.iter().filter(|r| r.pool == pool) {
+            let triggered = rule.condition.is_triggered(price, previous);
+            state.active.insert(rule.id.clone(), triggered);
+            if !triggered {
+                continue;
+            }
+
+            match state.throttle.check(rule, now) {
+                ThrottleDecision::Send => {
+                    let triggered_at = Utc::now();
+                    state.last_fired_at.insert(rule.id.clone(), triggered_at);
+                    due.push((
+                        rule.destination.clone(),
+                        AlertPayload {
+                            rule_id: rule.id.clone(),
+                            pool: pool.to_string(),
+                            condition: rule.condition.to_string(),
+                            price,
+                            block_number,
+                            triggered_at,
+                        },
+                    ));
+                }
+                decision => debug!(rule = %rule.id, ?decision, "Alert suppressed"),
+            }
+        }
+
+        due
+    }
+
+    /// Evaluates rules for a new price and delivers any alerts that fire.
+    ///
+    /// Deliveries run on a background task so a slow destination never stalls
+    /// the caller; failures are retried, logged and recorded, and do not
+    /// affect other rules. Must be
+    /// called from within a Tokio runtime.
+    pub fn on_price(&self, pool: &str, price: f64, block_number: u64) {
+        let due = self.evaluate(pool, price, block_number, Instant::now());
+        if due.is_empty() {
+            return;
+        }
+
+        let notifiers = self.notifiers.clone();
+        tokio::spawn(async move {
+            for (destination, payload) in due {
+                let notification = Notification::new(
+                    "price_alert",
+                    format!(
+                        "{} price {:.4} hit rule {} ({}) at block {}",
+                        payload.pool,
+                        payload.price,
+                        payload.rule_id,
+                        payload.condition,
+                        payload.block_number
+                    ),
+                    &payload,
+                );
+                match notifiers
+                    .deliver(&payload.rule_id, &destination, &notification)
+                    .await
+                {
+                    Ok(()) => info!(rule = %payload.rule_id, price, "Alert delivered"),
+                    Err(e) => warn!(rule = %payload.rule_id, error = %e, "Alert delivery failed"),
+                }
+            }
+        });
+    }
+
+    /// Returns the current status of every rule.
+    #[must_use]
+    pub fn status(&self) -> Vec<RuleStatus> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+
+        self.rules
+            .iter()
+            .map(|rule| RuleStatus {
+                rule: rule.clone(),
+                active: state.active.get(&rule.id).copied().unwrap_or(false),
+                last_fired_at: state.last_fired_at.get(&rule.id).copied(),
+                counts: state.throttle.counts(&rule.id),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn engine() -> AlertEngine {
+        let rules = rules::parse_rules(
+            r#"[{
+                "id": "high",
+                "pool": "WETH/USDT",
+                "condition": { "type": "price_above", "value": 3000.0 },
+                "webhook_url": "https://example.com/hook",
+                "dedup_window_secs": 60
+            }]"#,
+        )
+        .unwrap();
+        AlertEngine::new(rules, Notifiers::new().unwrap())
+    }
+
+    #[test]
+    fn test_threshold_fires_once_per_dedup_window() {
+        let engine = engine();
+        let t0 = Instant::now();
+
+        // Price stays above the threshold for five 12s blocks
+        let fired: usize = (0..5)
+            .map(|i| {
+                engine
+                    .evaluate(
+                        "WETH/USDT",
+                        3100.0,
+                        100 + i,
+                        t0 + Duration::from_secs(12 * i),
+                    )
+                    .len()
+            })
+            .sum();
+        assert_eq!(fired, 1);
+
+        let status = &engine.status()[0];
+        assert!(status.active);
+        assert!(status.last_fired_at.is_some());
+        assert_eq!(status.counts.fired, 1);
+        assert_eq!(status.counts.suppressed_duplicate, 4);
+    }
+
+    #[test]
+    fn test_other_pools_are_ignored() {
+        let engine = engine();
+        assert!(engine
+            .evaluate("WETH/USDC", 5000.0, 1, Instant::now())
+            .is_empty());
+        assert!(!engine.status()[0].active);
+    }
+}
@@ -0,0 +1,191 @@
+//! Alert rule definitions and loading.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::notify::Destination;
+use crate::error::{TrackerError, TrackerResult};
+
+/// Default de-duplication window (5 minutes).
+const fn default_dedup_window_secs() -> u64 {
+    300
+}
+
+/// Default per-destination delivery budget.
+const fn default_max_per_minute() -> u32 {
+    10
+}
+
+/// Condition that triggers an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Price rose above the threshold
+    PriceAbove(f64),
+    /// Price fell below the threshold
+    PriceBelow(f64),
+    /// Price moved by at least this many percent (either direction)
+    /// between two consecutive observations
+    ChangePctAbove(f64),
+}
+
+impl AlertCondition {
+    /// Evaluates the condition for a new price.
+    ///
+    /// `previous` is the last observed price for the same pool, if any.
+    #[must_use]
+    pub fn is_triggered(&self, price: f64, previous: Option<f64>) -> bool {
+        match *self {
+            Self::PriceAbove(threshold) => price > threshold,
+            Self::PriceBelow(threshold) => price < threshold,
+            Self::ChangePctAbove(pct) => previous
+                .filter(|prev| *prev > 0.0)
+                .is_some_and(|prev| ((price - prev) / prev * 100.0).abs() >= pct),
+        }
+    }
+}
+
+impl std::fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PriceAbove(v) => write!(f, "price > {v}"),
+            Self::PriceBelow(v) => write!(f, "price < {v}"),
+            Self::ChangePctAbove(v) => write!(f, "|change| >= {v}%"),
+        }
+    }
+}
+
+/// A single alert rule.
+///
+/// Prices are compared in the pool's quote direction (`QUOTE_DIRECTION`).
+///
+/// Rules are loaded from the JSON file named by `ALERT_RULES_FILE`:
+///
+/// ```json
+/// [
+///   {
+///     "id": "eth-above-4k",
+///     "pool": "WETH/USDT",
+///     "condition": { "type": "price_above", "value": 4000.0 },
+///     "destination": "https://hooks.example.com/alerts",
+///     "dedup_window_secs": 600,
+///     "max_per_minute": 5
+///   }
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Unique rule identifier
+    pub id: String,
+    /// Pool name the rule watches (e.g. "WETH/USDT")
+    pub pool: String,
+    /// Trigger condition
+    pub condition: AlertCondition,
+    /// Where the alert is sent: a webhook URL, `slack:<webhook URL>`,
+    /// `telegram:<chat id>` or `mailto:<address>` (`webhook_url` is accepted
+    /// as an older name)
+    #[serde(alias = "webhook_url")]
+    pub destination: Destination,
+    /// Minimum seconds between two firings of this rule
+    #[serde(default = "default_dedup_window_secs")]
+    pub dedup_window_secs: u64,
+    /// Maximum deliveries per minute to this rule's destination
+    #[serde(default = "default_max_per_minute")]
+    pub max_per_minute: u32,
+}
+
+/// Loads and validates alert rules from a JSON file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, is not valid JSON, contains
+/// duplicate rule IDs, or a rule has an invalid destination or zero rate limit.
+pub fn load_rules(path: &Path) -> TrackerResult<Vec<AlertRule>> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        TrackerError::config(
+            format!("Failed to read alert rules from {}", path.display()),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    parse_rules(&contents)
+}
+
+/// Parses and validates alert rules from a JSON string.
+///
+/// # Errors
+///
+/// See [`load_rules`].
+pub fn parse_rules(json: &str) -> TrackerResult<Vec<AlertRule>> {
+    let rules: Vec<AlertRule> = serde_json::from_str(json)
+        .map_err(|e| TrackerError::config("Invalid alert rules JSON", Some(Box::new(e))))?;
+
+    let mut seen = std::collections::HashSet::new();
+    for rule in &rules {
+        if !seen.insert(rule.id.as_str()) {
+            return Err(TrackerError::config(
+                format!("Duplicate alert rule id: {}", rule.id),
+                None,
+            ));
+        }
+        if rule.max_per_minute == 0 {
+            return Err(TrackerError::config(
+                format!(
+                    "Alert rule {} must allow at least one delivery per minute",
+                    rule.id
+                ),
+                None,
+            ));
+        }
+    }
+
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules_with_defaults() {
+        let rules = parse_rules(
+            r#"[{
+                "id": "high",
+                "pool": "WETH/USDT",
+                "condition": { "type": "price_above", "value": 4000.0 },
+                "webhook_url": "https://example.com/hook"
+            }]"#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].condition, AlertCondition::PriceAbove(4000.0));
+        assert_eq!(rules[0].dedup_window_secs, 300);
+        assert_eq!(rules[0].max_per_minute, 10);
+    }
+
+    #[test]
+    fn test_parse_rules_rejects_duplicates_and_bad_destinations() {
+        let rule = r#"{
+            "id": "dup",
+            "pool": "WETH/USDT",
+            "condition": { "type": "price_below", "value": 1000.0 },
+            "destination": "telegram:-1001234567890"
+        }"#;
+        assert!(parse_rules(&format!("[{rule}]")).is_ok());
+        assert!(parse_rules(&format!("[{rule},{rule}]")).is_err());
+        assert!(parse_rules(&format!("[{}]", rule.replace("telegram:", "fax:"))).is_err());
+    }
+
+    #[test]
+    fn test_condition_evaluation() {
+        assert!(AlertCondition::PriceAbove(100.0).is_triggered(101.0, None));
+        assert!(!AlertCondition::PriceAbove(100.0).is_triggered(100.0, None));
+        assert!(AlertCondition::PriceBelow(100.0).is_triggered(99.0, None));
+
+        let change = AlertCondition::ChangePctAbove(5.0);
+        assert!(!change.is_triggered(110.0, None));
+        assert!(change.is_triggered(94.0, Some(100.0)));
+        assert!(!change.is_triggered(104.0, Some(100.0)));
+    }
+}
@@ -0,0 +1,168 @@
+//! Soft rate limiting and de-duplication for outbound alerts.
+//!
+//! Two independent checks run before every delivery:
+//!
+//! 1. **De-duplication window (per rule)**: once a rule fires, further
+//!    triggers of the same rule are suppressed until `dedup_window_secs` have
+//!    elapsed, so a threshold that stays crossed doesn't page every block.
+//! 2. **Rate limit (per destination)**: deliveries to one webhook URL are
+//!    capped at `max_per_minute` over a sliding 60-second window, shared by
+//!    every rule that targets the same destination.
+//!
+//! Suppressed alerts are dropped, not queued ("soft" limiting), but each one
+//! is counted so operators can see how much noise the limits are absorbing.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::rules::AlertRule;
+
+/// Sliding window used for per-destination rate limits.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Outcome of a throttle check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleDecision {
+    /// Deliver the alert
+    Send,
+    /// The rule already fired within its de-duplication window
+    SuppressedDuplicate,
+    /// The destination has exhausted its delivery budget
+    SuppressedRateLimited,
+}
+
+/// Per-rule delivery and suppression counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SuppressionCounts {
+    /// Alerts delivered (or attempted)
+    pub fired: u64,
+    /// Alerts suppressed by the de-duplication window
+    pub suppressed_duplicate: u64,
+    /// Alerts suppressed by the destination rate limit
+    pub suppressed_rate_limited: u64,
+}
+
+/// Tracks recent deliveries to enforce de-duplication and rate limits.
+#[derive(Debug, Default)]
+pub struct AlertThrottle {
+    /// Delivery timestamps per destination, oldest first
+    deliveries: HashMap<String, VecDeque<Instant>>,
+    /// Last firing per rule ID
+    last_fired: HashMap<String, Instant>,
+    /// Counters per rule ID
+    counts: HashMap<String, SuppressionCounts>,
+}
+
+impl AlertThrottle {
+    /// Creates an empty throttle.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decides whether a triggered rule may be delivered at `now`.
+    ///
+    /// A `Send` decision is recorded immediately, so the caller must deliver
+    /// (or at least attempt) the alert.
+    pub fn check(&mut self, rule: &AlertRule, now: Instant) -> ThrottleDecision {
+        let counts = self.counts.entry(rule.id.clone()).or_default();
+
+        if let Some(last) = self.last_fired.get(&rule.id) {
+            if now.saturating_duration_since(*last) < Duration::from_secs(rule.dedup_window_secs) {
+                counts.suppressed_duplicate += 1;
+                return ThrottleDecision::SuppressedDuplicate;
+            }
+        }
+
+        let window = self
+            .deliveries
+            .entry(rule.destination.to_string())
+            .or_default();
+        while window
+            .front()
+            .is_some_and(|t| now.saturating_duration_since(*t) >= RATE_WINDOW)
+        {
+            window.pop_front();
+        }
+
+        if window.len() >= rule.max_per_minute as usize {
+            counts.suppressed_rate_limited += 1;
+            return ThrottleDecision::SuppressedRateLimited;
+        }
+
+        window.push_back(now);
+        self.last_fired.insert(rule.id.clone(), now);
+        counts.fired += 1;
+        ThrottleDecision::Send
+    }
+
+    /// Returns the counters for a rule.
+    #[must_use]
+    pub fn counts(&self, rule_id: &str) -> SuppressionCounts {
+        self.counts.get(rule_id).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::rules::AlertCondition;
+
+    fn rule(id: &str, url: &str, dedup_secs: u64, max_per_minute: u32) -> AlertRule {
+        AlertRule {
+            id: id.to_string(),
+            pool: "WETH/USDT".to_string(),
+            condition: AlertCondition::PriceAbove(0.0),
+            destination: url.parse().unwrap(),
+            dedup_window_secs: dedup_secs,
+            max_per_minute,
+        }
+    }
+
+    #[test]
+    fn test_dedup_window_suppresses_repeats() {
+        let mut throttle = AlertThrottle::new();
+        let r = rule("a", "https://x", 300, 10);
+        let t0 = Instant::now();
+
+        assert_eq!(throttle.check(&r, t0), ThrottleDecision::Send);
+        assert_eq!(
+            throttle.check(&r, t0 + Duration::from_secs(12)),
+            ThrottleDecision::SuppressedDuplicate
+        );
+        assert_eq!(
+            throttle.check(&r, t0 + Duration::from_secs(300)),
+            ThrottleDecision::Send
+        );
+
+        let counts = throttle.counts("a");
+        assert_eq!(counts.fired, 2);
+        assert_eq!(counts.suppressed_duplicate, 1);
+    }
+
+    #[test]
+    fn test_rate_limit_is_shared_per_destination() {
+        let mut throttle = AlertThrottle::new();
+        let t0 = Instant::now();
+
+        // Two rules, same destination, budget of 2 per minute
+        let a = rule("a", "https://shared", 0, 2);
+        let b = rule("b", "https://shared", 0, 2);
+        let other = rule("c", "https://other", 0, 2);
+
+        assert_eq!(throttle.check(&a, t0), ThrottleDecision::Send);
+        assert_eq!(throttle.check(&b, t0), ThrottleDecision::Send);
+        assert_eq!(
+            throttle.check(&a, t0 + Duration::from_secs(1)),
+            ThrottleDecision::SuppressedRateLimited
+        );
+        assert_eq!(throttle.check(&other, t0), ThrottleDecision::Send);
+
+        // Budget frees up once the window slides past the first deliveries
+        assert_eq!(
+            throttle.check(&a, t0 + Duration::from_secs(60)),
+            ThrottleDecision::Send
+        );
+        assert_eq!(throttle.counts("a").suppressed_rate_limited, 1);
+    }
+}
@@ -0,0 +1,83 @@
+//! Webhook delivery for alerts.
+//!
+//! [`WebhookNotifier`] is the HTTP client behind the webhook, Slack and
+//! Telegram channels (see [`super::notify`]).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::{TrackerError, TrackerResult};
+
+/// Timeout for a single webhook delivery.
+pub(crate) const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON body posted to alert webhooks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertPayload {
+    /// Rule that fired
+    pub rule_id: String,
+    /// Pool name
+    pub pool: String,
+    /// Human-readable condition (e.g. "price > 4000")
+    pub condition: String,
+    /// Price that triggered the alert
+    pub price: f64,
+    /// Block the price was observed at
+    pub block_number: u64,
+    /// When the alert fired
+    pub triggered_at: DateTime<Utc>,
+}
+
+/// Posts alert payloads to webhook URLs.
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    /// Creates a notifier with a bounded request timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be constructed.
+    pub fn new() -> TrackerResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .map_err(|e| {
+                TrackerError::config("Failed to build webhook HTTP client", Some(Box::new(e)))
+            })?;
+
+        Ok(Self { client })
+    }
+
+    /// Delivers any JSON payload to a webhook URL; `what` names it in the
+    /// error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the endpoint responds with a
+    /// non-success status.
+    pub async fn send_json<T: Serialize + Sync>(
+        &self,
+        url: &str,
+        what: &str,
+        payload: &T,
+    ) -> TrackerResult<()> {
+        self.client
+            .post(url)
+            .json(payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| {
+                TrackerError::rpc(
+                    format!("Webhook delivery for {what} failed"),
+                    Some(Box::new(e)),
+                )
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+//! LP fee APR estimates from indexed Swap events.
+//!
+//! Every swap pays the pool's fee (`fee_bps` of the input amount) to
+//! liquidity providers. Over a window, the fees earned are valued in token1
+//! at the latest pool price and compared with the pool's average liquidity:
+//!
+//! ```text
+//! fees = (amount0_in × price + amount1_in) × fee_bps / 10_000
+//! apr  = fees / avg_liquidity × (365 days / window) × 100
+//! ```
+//!
+//! Fees are counted from swap inputs rather than reserve growth between Sync
+//! events: reserves also move with mints, burns and the price itself, and
+//! separating those out would need the LP token supply, which isn't indexed.
+//!
+//! Average liquidity is the mean of `reserve1 + reserve0 × price` over the
+//! window's price points, so busy periods weigh more than quiet ones. A
+//! window reaching back before the first indexed price is annualized over
+//! the indexed part only, so a freshly indexed pool doesn't report a
+//! diluted APR.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::analytics::fees::estimate_fee_apr;
+//! use eth_uniswap_alloy::db::models::FeeWindowRow;
+//!
+//! // A day of 1,000,000 USDT of volume in a 10,000,000 USDT pool at 0.3%
+//! let window = FeeWindowRow {
+//!     swaps: 500,
+//!     amount0_in: 0.0,
+//!     amount1_in: 1_000_000e6,
+//!     avg_liquidity: Some(10_000_000.0),
+//!     first_timestamp: Some(0),
+//! };
+//! let apr = estimate_fee_apr(&window, (18, 6), 30, Some(2_000.0), 0, 86_400);
+//! assert!((apr.apr.unwrap() - 10.95).abs() < 1e-9);
+//! ```
+
+use super::{scale, SECONDS_PER_DAY};
+use crate::db::models::{FeeWindowRow, PoolRecord};
+use crate::db::repository::Repository;
+use crate::error::TrackerResult;
+
+/// Windows reported by default, in days.
+pub const DEFAULT_APR_WINDOWS: [u32; 3] = [1, 7, 30];
+
+/// Length of a year for annualizing fees.
+pub const SECONDS_PER_YEAR: i64 = 365 * SECONDS_PER_DAY;
+
+/// Fee earnings of a pool's liquidity providers over one window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeApr {
+    /// Window length in days
+    pub days: u32,
+    /// Start of the window (unix seconds)
+    pub since: i64,
+    /// Seconds of the window covered by indexed prices
+    pub covered_secs: i64,
+    /// Number of swaps
+    pub swaps: u64,
+    /// Swap inputs valued in token1 (`None` without a price)
+    pub volume: Option<f64>,
+    /// Fees earned, in token1 (`None` without a price)
+    pub fees: Option<f64>,
+    /// Average liquidity in token1 (`None` without price points)
+    pub avg_liquidity: Option<f64>,
+    /// Annualized fees as a percentage of liquidity
+    pub apr: Option<f64>,
+}
+
+/// Estimates the fee APR of one window from its swap inputs and liquidity.
+///
+/// `price` is token0 in token1; `since` and `now` bound the window.
+#[must_use]
+pub fn estimate_fee_apr(
+    window: &FeeWindowRow,
+    decimals: (u8, u8),
+    fee_bps: u32,
+    price: Option<f64>,
+    since: i64,
+    now: i64,
+) -> FeeApr {
+    let start = window
+        .first_timestamp
+        .map_or(since, |first| first.max(since));
+    let covered_secs = (now - start).max(0);
+    let volume = price.map(|price| {
+        scale(window.amount0_in, decimals.0).mul_add(price, scale(window.amount1_in, decimals.1))
+    });
+    let fees = volume.map(|volume| volume * f64::from(fee_bps) / 10_000.0);
+    #[allow(clippy::cast_precision_loss)] // Window lengths are far below 2^52 seconds
+    let apr = match (fees, window.avg_liquidity) {
+        (Some(fees), Some(liquidity)) if liquidity > 0.0 && covered_secs > 0 => {
+            Some(fees / liquidity * (SECONDS_PER_YEAR as f64 / covered_secs as f64) * 100.0)
+        }
+        _ => None,
+    };
+
+    FeeApr {
+        days: u32::try_from((now - since) / SECONDS_PER_DAY).unwrap_or(0),
+        since,
+        covered_secs,
+        swaps: u64::try_from(window.swaps).unwrap_or(0),
+        volume,
+        fees,
+        avg_liquidity: window.avg_liquidity,
+        apr,
+    }
+}
+
+/// Estimates a pool's fee APR over the trailing `windows` (in days) ending
+/// at `now`, valuing fees at the latest confirmed price.
+///
+/// # Errors
+///
+/// Returns an error if a database query fails.
+pub async fn pool_fee_apr(
+    reader: &Repository,
+    pool: &PoolRecord,
+    windows: &[u32],
+    now: i64,
+) -> TrackerResult<Vec<FeeApr>> {
+    let decimals = (
+        u8::try_from(pool.token0_decimals).unwrap_or(18),
+        u8::try_from(pool.token1_decimals).unwrap_or(18),
+    );
+    let fee_bps = pool.dex_protocol().fee_bps();
+    let price = reader.get_latest_price(pool.id).await?.map(|p| p.price);
+
+    let mut estimates = Vec::with_capacity(windows.len());
+    for &days in windows {
+        let since = now - i64::from(days) * SECONDS_PER_DAY;
+        let window = reader.get_fee_window(pool.id, since).await?;
+        estimates.push(estimate_fee_apr(
+            &window, decimals, fee_bps, price, since, now,
+        ));
+    }
+    Ok(estimates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(amount0_in: f64, amount1_in: f64, first_timestamp: Option<i64>) -> FeeWindowRow {
+        FeeWindowRow {
+            swaps: 10,
+            amount0_in,
+            amount1_in,
+            avg_liquidity: Some(4_000_000.0),
+            first_timestamp,
+        }
+    }
+
+    #[test]
+    fn test_fees_value_both_inputs() {
+        // 100 WETH and 200,000 USDT in at 2,000: 400,000 USDT of volume
+        let apr = estimate_fee_apr(
+            &window(100e18, 200_000e6, Some(0)),
+            (18, 6),
+            30,
+            Some(2_000.0),
+            0,
+            7 * SECONDS_PER_DAY,
+        );
+        assert_eq!(apr.days, 7);
+        assert!((apr.volume.unwrap() - 400_000.0).abs() < 1e-6);
+        assert!((apr.fees.unwrap() - 1_200.0).abs() < 1e-6);
+        // 1,200 a week on 4,000,000 is 0.03% a week
+        assert!((apr.apr.unwrap() - 0.03 * 365.0 / 7.0).abs() < 1e-9);
+
+        let unpriced = estimate_fee_apr(&window(100e18, 0.0, Some(0)), (18, 6), 30, None, 0, 1);
+        assert_eq!((unpriced.fees, unpriced.apr), (None, None));
+    }
+
+    #[test]
+    fn test_short_history_is_annualized_over_indexed_part() {
+        // A 30-day window over a pool indexed for one day
+        let now = 30 * SECONDS_PER_DAY;
+        let since = 0;
+        let apr = estimate_fee_apr(
+            &window(0.0, 1_000_000e6, Some(now - SECONDS_PER_DAY)),
+            (18, 6),
+            30,
+            Some(2_000.0),
+            since,
+            now,
+        );
+        assert_eq!(apr.covered_secs, SECONDS_PER_DAY);
+        // 3,000 a day on 4,000,000
+        assert!((apr.apr.unwrap() - 3_000.0 / 4_000_000.0 * 365.0 * 100.0).abs() < 1e-9);
+
+        let empty = FeeWindowRow::default();
+        assert_eq!(
+            estimate_fee_apr(&empty, (18, 6), 30, Some(2_000.0), since, now).apr,
+            None
+        );
+    }
+}
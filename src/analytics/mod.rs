@@ -0,0 +1,130 @@
+//! Trader analytics over indexed Swap events.
+//!
+//! LP fee APR estimates live in [`fees`], sandwich attack detection in
+//! [`sandwich`].
+//!
+//! Swaps are attributed to their recipient (the Swap event's `to`). That is
+//! the trader for direct swaps and for router swaps that send the output to
+//! the caller; swaps routed through another contract (aggregators, multi-hop
+//! paths) are attributed to that contract instead.
+//!
+//! The P&L estimate is mark-to-market: a trader's net token flows over the
+//! window, valued in token1 at the pool's current price:
+//!
+//! ```text
+//! pnl = net_token1 + net_token0 × price
+//! ```
+//!
+//! A positive value means the trader took out more than they put in, at
+//! today's price. Gas, fees paid elsewhere and positions held before the
+//! window are not accounted for.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::analytics::estimate_pnl;
+//! use eth_uniswap_alloy::db::models::TraderTotalsRow;
+//!
+//! // Bought 1 WETH for 2,000 USDT; WETH now trades at 2,100 USDT
+//! let totals = TraderTotalsRow {
+//!     address: "0x01".to_string(),
+//!     swaps: 1,
+//!     amount0_in: 0.0,
+//!     amount1_in: 2_000e6,
+//!     amount0_out: 1e18,
+//!     amount1_out: 0.0,
+//! };
+//! let pnl = estimate_pnl(&totals, (18, 6), Some(2_100.0));
+//! assert!((pnl.pnl.unwrap() - 100.0).abs() < 1e-6);
+//! ```
+
+pub mod fees;
+pub mod sandwich;
+
+use crate::db::models::TraderTotalsRow;
+
+/// Length of a daily analytics bucket.
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A trader's decimal-adjusted flows and P&L estimate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraderPnl {
+    /// Token1 paid in plus token1 received
+    pub volume: f64,
+    /// Token0 received minus token0 paid in
+    pub net_token0: f64,
+    /// Token1 received minus token1 paid in
+    pub net_token1: f64,
+    /// Net flows valued in token1 at `price` (`None` without a price)
+    pub pnl: Option<f64>,
+}
+
+/// Converts a raw token amount to a decimal-adjusted one.
+#[must_use]
+pub fn scale(raw: f64, decimals: u8) -> f64 {
+    raw / 10f64.powi(i32::from(decimals))
+}
+
+/// Estimates a trader's P&L from their swap totals at `price` (token0 in
+/// token1).
+#[must_use]
+pub fn estimate_pnl(totals: &TraderTotalsRow, decimals: (u8, u8), price: Option<f64>) -> TraderPnl {
+    let (decimals0, decimals1) = decimals;
+    let net_token0 = scale(totals.amount0_out - totals.amount0_in, decimals0);
+    let net_token1 = scale(totals.amount1_out - totals.amount1_in, decimals1);
+    TraderPnl {
+        volume: scale(totals.amount1_in + totals.amount1_out, decimals1),
+        net_token0,
+        net_token1,
+        pnl: price.map(|price| net_token0.mul_add(price, net_token1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(
+        amount0_in: f64,
+        amount1_in: f64,
+        amount0_out: f64,
+        amount1_out: f64,
+    ) -> TraderTotalsRow {
+        TraderTotalsRow {
+            address: "0x01".to_string(),
+            swaps: 2,
+            amount0_in,
+            amount1_in,
+            amount0_out,
+            amount1_out,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_pnl_is_realized() {
+        // Bought 1 WETH for 2,000 USDT, sold it for 2,050 USDT
+        let pnl = estimate_pnl(
+            &totals(1e18, 2_000e6, 1e18, 2_050e6),
+            (18, 6),
+            Some(1_000.0),
+        );
+        assert!(pnl.net_token0.abs() < 1e-12);
+        assert!((pnl.net_token1 - 50.0).abs() < 1e-9);
+        assert!((pnl.volume - 4_050.0).abs() < 1e-9);
+        // No open position, so the current price doesn't matter
+        assert!((pnl.pnl.unwrap() - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_open_position_marked_to_price() {
+        // Sold 2 WETH for 4,000 USDT; buying them back now costs 4,400
+        let pnl = estimate_pnl(&totals(2e18, 0.0, 0.0, 4_000e6), (18, 6), Some(2_200.0));
+        assert!((pnl.net_token0 + 2.0).abs() < 1e-12);
+        assert!((pnl.pnl.unwrap() + 400.0).abs() < 1e-9);
+        assert!(
+            estimate_pnl(&totals(2e18, 0.0, 0.0, 4_000e6), (18, 6), None)
+                .pnl
+                .is_none()
+        );
+    }
+}
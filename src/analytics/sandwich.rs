@@ -0,0 +1,326 @@
+//! Sandwich attack detection from the order of swaps within a block.
+//!
+//! A sandwich brackets a victim's swap with two swaps of the attacker's in
+//! the same block: a front-run buying the token the victim is about to buy,
+//! pushing the price up, and a back-run selling it straight back after the
+//! victim has paid the inflated price. Swaps are replayed in log order, so
+//! each block's swaps appear in the order they moved the pool's reserves,
+//! and a swap pair is flagged when:
+//!
+//! - the front-run and back-run have the same recipient (the attacker) and
+//!   are in different transactions
+//! - the back-run sells what the front-run bought, to within
+//!   [`MAX_LEG_MISMATCH`] of the amount
+//! - at least one swap between them, from another transaction and to
+//!   another recipient, buys the same token as the front-run
+//!
+//! The attacker's profit is what the back-run returned of the token the
+//! front-run spent, less what it spent. Swaps are only seen per pool, so
+//! sandwiches spanning several pools, and attackers that route the two legs
+//! to different addresses, go undetected; a trader who just bought and sold
+//! around someone else's swap in one block is flagged.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::analytics::sandwich::{detect_sandwiches, Side};
+//! use eth_uniswap_alloy::db::models::SwapEventRecord;
+//!
+//! let swap = |tx: u8, log_index: i32, to: &str, amounts: [&str; 4]| SwapEventRecord {
+//!     id: 0,
+//!     pool_id: 1,
+//!     block_number: 100,
+//!     block_timestamp: 1_200,
+//!     tx_hash: format!("0x{}", format!("{tx:02x}").repeat(32)).parse().unwrap(),
+//!     log_index,
+//!     sender: to.to_string(),
+//!     recipient: to.to_string(),
+//!     amount0_in: amounts[0].to_string(),
+//!     amount1_in: amounts[1].to_string(),
+//!     amount0_out: amounts[2].to_string(),
+//!     amount1_out: amounts[3].to_string(),
+//!     is_confirmed: true,
+//!     created_at: 0,
+//! };
+//!
+//! let sandwiches = detect_sandwiches(&[
+//!     swap(1, 0, "0xbot", ["0", "1000", "10", "0"]),
+//!     swap(2, 1, "0xvictim", ["0", "500", "4", "0"]),
+//!     swap(3, 2, "0xbot", ["10", "0", "0", "1040"]),
+//! ]);
+//! assert_eq!(sandwiches.len(), 1);
+//! assert_eq!(sandwiches[0].side, Side::BuyToken0);
+//! assert_eq!(sandwiches[0].profit, 40.0);
+//! ```
+
+use crate::db::models::SwapEventRecord;
+use crate::db::types::TxHash;
+
+/// Largest relative difference between what the front-run bought and what
+/// the back-run sold.
+pub const MAX_LEG_MISMATCH: f64 = 0.1;
+
+/// The token a swap buys from the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Pays token1 for token0
+    BuyToken0,
+    /// Pays token0 for token1
+    BuyToken1,
+}
+
+impl Side {
+    /// Returns the side of a swap, from which token it takes out on net.
+    #[must_use]
+    pub fn of(swap: &Swap) -> Self {
+        if swap.amount0_out > swap.amount0_in {
+            Self::BuyToken0
+        } else {
+            Self::BuyToken1
+        }
+    }
+
+    /// Returns the side's name, as reported by the API.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::BuyToken0 => "buy_token0",
+            Self::BuyToken1 => "buy_token1",
+        }
+    }
+}
+
+/// A swap with its amounts as raw token units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Swap {
+    /// Block of the swap
+    pub block_number: i64,
+    /// Block timestamp (unix seconds)
+    pub block_timestamp: i64,
+    /// Transaction of the swap
+    pub tx_hash: TxHash,
+    /// Recipient of the output
+    pub recipient: String,
+    /// Token0 paid in
+    pub amount0_in: f64,
+    /// Token1 paid in
+    pub amount1_in: f64,
+    /// Token0 paid out
+    pub amount0_out: f64,
+    /// Token1 paid out
+    pub amount1_out: f64,
+}
+
+impl From<&SwapEventRecord> for Swap {
+    fn from(record: &SwapEventRecord) -> Self {
+        // Raw amounts are decimal strings; f64 keeps ample precision for
+        // comparing legs
+        let amount = |raw: &str| raw.parse::<f64>().unwrap_or(0.0);
+        Self {
+            block_number: record.block_number,
+            block_timestamp: record.block_timestamp,
+            tx_hash: record.tx_hash,
+            recipient: record.recipient.clone(),
+            amount0_in: amount(&record.amount0_in),
+            amount1_in: amount(&record.amount1_in),
+            amount0_out: amount(&record.amount0_out),
+            amount1_out: amount(&record.amount1_out),
+        }
+    }
+}
+
+impl Swap {
+    /// Amount of the bought token received.
+    fn bought(&self, side: Side) -> f64 {
+        match side {
+            Side::BuyToken0 => self.amount0_out - self.amount0_in,
+            Side::BuyToken1 => self.amount1_out - self.amount1_in,
+        }
+    }
+
+    /// Amount of the paid token spent; negative if it was received.
+    fn spent(&self, side: Side) -> f64 {
+        match side {
+            Side::BuyToken0 => self.amount1_in - self.amount1_out,
+            Side::BuyToken1 => self.amount0_in - self.amount0_out,
+        }
+    }
+}
+
+/// A detected sandwich.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sandwich {
+    /// Block of the three legs
+    pub block_number: i64,
+    /// Block timestamp (unix seconds)
+    pub block_timestamp: i64,
+    /// Recipient of the front-run and back-run
+    pub attacker: String,
+    /// Token the front-run and victims bought
+    pub side: Side,
+    /// Front-run transaction
+    pub front_run: TxHash,
+    /// Victim transactions, in block order
+    pub victims: Vec<TxHash>,
+    /// Back-run transaction
+    pub back_run: TxHash,
+    /// Attacker's gain in the token the front-run paid (token1 for
+    /// [`Side::BuyToken0`], token0 otherwise), raw units
+    pub profit: f64,
+}
+
+/// Finds sandwiches in a pool's swaps.
+///
+/// `swaps` must be sorted by block and log index. Each swap belongs to at
+/// most one sandwich; a front-run is paired with the first matching
+/// back-run after it.
+#[must_use]
+pub fn detect_sandwiches(swaps: &[SwapEventRecord]) -> Vec<Sandwich> {
+    let swaps: Vec<Swap> = swaps.iter().map(Swap::from).collect();
+    let mut sandwiches = Vec::new();
+    let mut start = 0;
+    while start < swaps.len() {
+        let block = swaps[start].block_number;
+        let end = swaps[start..]
+            .iter()
+            .position(|swap| swap.block_number != block)
+            .map_or(swaps.len(), |len| start + len);
+        detect_in_block(&swaps[start..end], &mut sandwiches);
+        start = end;
+    }
+    sandwiches
+}
+
+fn detect_in_block(swaps: &[Swap], sandwiches: &mut Vec<Sandwich>) {
+    let mut used = vec![false; swaps.len()];
+    for front in 0..swaps.len() {
+        if used[front] {
+            continue;
+        }
+        let attacker = &swaps[front];
+        let side = Side::of(attacker);
+        let bought = attacker.bought(side);
+        if bought <= 0.0 {
+            continue;
+        }
+
+        let back = (front + 1..swaps.len()).find(|&i| {
+            let swap = &swaps[i];
+            !used[i]
+                && swap.recipient == attacker.recipient
+                && swap.tx_hash != attacker.tx_hash
+                && Side::of(swap) != side
+                && ((-swap.bought(side)) - bought).abs() <= bought * MAX_LEG_MISMATCH
+        });
+        let Some(back) = back else {
+            continue;
+        };
+
+        let victims: Vec<usize> = (front + 1..back)
+            .filter(|&i| {
+                let swap = &swaps[i];
+                !used[i]
+                    && Side::of(swap) == side
+                    && swap.recipient != attacker.recipient
+                    && swap.tx_hash != attacker.tx_hash
+                    && swap.tx_hash != swaps[back].tx_hash
+            })
+            .collect();
+        if victims.is_empty() {
+            continue;
+        }
+
+        for &i in victims.iter().chain([&front, &back]) {
+            used[i] = true;
+        }
+        sandwiches.push(Sandwich {
+            block_number: attacker.block_number,
+            block_timestamp: attacker.block_timestamp,
+            attacker: attacker.recipient.clone(),
+            side,
+            front_run: attacker.tx_hash,
+            victims: victims.iter().map(|&i| swaps[i].tx_hash).collect(),
+            back_run: swaps[back].tx_hash,
+            profit: -swaps[back].spent(side) - attacker.spent(side),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap(block: i64, tx: u8, to: &str, amounts: [u64; 4]) -> SwapEventRecord {
+        SwapEventRecord {
+            id: 0,
+            pool_id: 1,
+            block_number: block,
+            block_timestamp: block * 12,
+            tx_hash: format!("0x{}", format!("{tx:02x}").repeat(32))
+                .parse()
+                .unwrap(),
+            log_index: i32::from(tx),
+            sender: to.to_string(),
+            recipient: to.to_string(),
+            amount0_in: amounts[0].to_string(),
+            amount1_in: amounts[1].to_string(),
+            amount0_out: amounts[2].to_string(),
+            amount1_out: amounts[3].to_string(),
+            is_confirmed: true,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_detects_sell_side_sandwich_with_two_victims() {
+        let sandwiches = detect_sandwiches(&[
+            // Unrelated swap before the attack
+            swap(100, 1, "0xa", [0, 100, 1, 0]),
+            swap(100, 2, "0xbot", [50, 0, 0, 5_000]),
+            swap(100, 3, "0xv1", [10, 0, 0, 950]),
+            swap(100, 4, "0xv2", [20, 0, 0, 1_850]),
+            swap(100, 5, "0xbot", [0, 5_000, 52, 0]),
+        ]);
+
+        assert_eq!(sandwiches.len(), 1);
+        let sandwich = &sandwiches[0];
+        assert_eq!(sandwich.side, Side::BuyToken1);
+        assert_eq!(sandwich.attacker, "0xbot");
+        assert_eq!(sandwich.victims.len(), 2);
+        assert_eq!(sandwich.profit, 2.0);
+    }
+
+    #[test]
+    fn test_ignores_non_sandwich_patterns() {
+        // No victim between the legs
+        assert!(detect_sandwiches(&[
+            swap(100, 1, "0xbot", [0, 1_000, 10, 0]),
+            swap(100, 2, "0xbot", [10, 0, 0, 1_010]),
+        ])
+        .is_empty());
+
+        // Legs in different blocks
+        assert!(detect_sandwiches(&[
+            swap(100, 1, "0xbot", [0, 1_000, 10, 0]),
+            swap(100, 2, "0xv", [0, 500, 4, 0]),
+            swap(101, 3, "0xbot", [10, 0, 0, 1_040]),
+        ])
+        .is_empty());
+
+        // The swap in between sells, so it wasn't harmed
+        assert!(detect_sandwiches(&[
+            swap(100, 1, "0xbot", [0, 1_000, 10, 0]),
+            swap(100, 2, "0xv", [4, 0, 0, 420]),
+            swap(100, 3, "0xbot", [10, 0, 0, 1_040]),
+        ])
+        .is_empty());
+
+        // The second leg only sells a fraction of what was bought
+        assert!(detect_sandwiches(&[
+            swap(100, 1, "0xbot", [0, 1_000, 10, 0]),
+            swap(100, 2, "0xv", [0, 500, 4, 0]),
+            swap(100, 3, "0xbot", [5, 0, 0, 520]),
+        ])
+        .is_empty());
+    }
+}
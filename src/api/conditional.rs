@@ -0,0 +1,173 @@
+//! Conditional GET for polled endpoints.
+//!
+//! Dashboards poll price history and candles far more often than new prices
+//! arrive. Handlers describe the state a response is built from with a
+//! [`Validators`] (a weak `ETag` hashed from the request parameters and the
+//! latest data, and optionally a `Last-Modified` time). When the client's
+//! `If-None-Match` or, without one, `If-Modified-Since` shows it already has
+//! that state, the handler answers `304 Not Modified` without a body, and
+//! without running the queries behind it where it can.
+
+use alloy::primitives::keccak256;
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+
+/// Format of HTTP dates (IMF-fixdate).
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Cache validators of a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+    etag: String,
+    last_modified: Option<i64>,
+}
+
+impl Validators {
+    /// Builds a weak `ETag` from everything the response depends on, in
+    /// order.
+    #[must_use]
+    pub fn new(parts: &[&dyn std::fmt::Display]) -> Self {
+        let key = parts
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\u{1f}");
+        let hash = keccak256(key.as_bytes());
+        Self {
+            etag: format!("W/\"{}\"", alloy::hex::encode(&hash[..8])),
+            last_modified: None,
+        }
+    }
+
+    /// Sets `Last-Modified` to a unix timestamp.
+    #[must_use]
+    pub const fn with_last_modified(mut self, secs: Option<i64>) -> Self {
+        self.last_modified = secs;
+        self
+    }
+
+    /// Returns the `ETag` header value.
+    #[must_use]
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Returns true if the request's preconditions show the client already
+    /// has this state.
+    ///
+    /// `If-None-Match` takes precedence; `If-Modified-Since` is only checked
+    /// without it.
+    #[must_use]
+    pub fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        if let Some(value) = headers.get(header::IF_NONE_MATCH) {
+            let Ok(value) = value.to_str() else {
+                return false;
+            };
+            // Weak comparison: W/"x" matches "x"
+            let ours = self.etag.trim_start_matches("W/");
+            return value
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == ours);
+        }
+
+        match (self.last_modified, headers.get(header::IF_MODIFIED_SINCE)) {
+            (Some(modified), Some(since)) => since
+                .to_str()
+                .ok()
+                .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+                .is_some_and(|since| modified <= since.timestamp()),
+            _ => false,
+        }
+    }
+
+    /// Returns `304 Not Modified` with the validators.
+    #[must_use]
+    pub fn not_modified(&self) -> Response {
+        self.attach(StatusCode::NOT_MODIFIED.into_response())
+    }
+
+    /// Returns `body` with the validators.
+    pub fn with_body(&self, body: impl IntoResponse) -> Response {
+        self.attach(body.into_response())
+    }
+
+    fn attach(&self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(date) = self
+            .last_modified
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+        {
+            if let Ok(value) = HeaderValue::from_str(&date.format(HTTP_DATE).to_string()) {
+                headers.insert(header::LAST_MODIFIED, value);
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_etag_depends_on_every_part() {
+        let a = Validators::new(&[&1, &"2024", &19_000_000]);
+        assert_eq!(a, Validators::new(&[&1, &"2024", &19_000_000]));
+        assert_ne!(a, Validators::new(&[&1, &"2024", &19_000_001]));
+        assert_ne!(
+            Validators::new(&[&"1", &"2"]),
+            Validators::new(&[&"12", &""])
+        );
+        assert!(a.etag().starts_with("W/\""));
+    }
+
+    #[test]
+    fn test_preconditions() {
+        let validators = Validators::new(&[&1]).with_last_modified(Some(1_706_745_600));
+        let etag = validators.etag().to_string();
+
+        assert!(!validators.is_fresh(&HeaderMap::new()));
+        assert!(validators.is_fresh(&request(header::IF_NONE_MATCH, &etag)));
+        assert!(validators.is_fresh(&request(
+            header::IF_NONE_MATCH,
+            &format!("\"other\", {}", etag.trim_start_matches("W/"))
+        )));
+        assert!(!validators.is_fresh(&request(header::IF_NONE_MATCH, "\"other\"")));
+
+        // 2024-02-01 00:00:00 UTC is the last modification
+        let since = |date| request(header::IF_MODIFIED_SINCE, date);
+        assert!(validators.is_fresh(&since("Thu, 01 Feb 2024 00:00:00 GMT")));
+        assert!(!validators.is_fresh(&since("Wed, 31 Jan 2024 23:59:59 GMT")));
+        assert!(!validators.is_fresh(&since("yesterday")));
+    }
+
+    #[test]
+    fn test_responses_carry_validators() {
+        let validators = Validators::new(&[&1]).with_last_modified(Some(1_706_745_600));
+
+        let response = validators.not_modified();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], validators.etag());
+        assert_eq!(
+            response.headers()[header::LAST_MODIFIED],
+            "Thu, 01 Feb 2024 00:00:00 GMT"
+        );
+
+        let response = validators.with_body("body");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], validators.etag());
+    }
+}
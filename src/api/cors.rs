@@ -0,0 +1,196 @@
+//! Cross-origin access to the API.
+//!
+//! Browsers only let a dashboard on another origin read API responses when
+//! the server allows that origin. [`CorsPolicy`] is built from
+//! `API_CORS_ORIGINS` and friends and turned into the server's CORS layer:
+//!
+//! - `*` allows any origin; otherwise only the listed origins get CORS headers
+//! - with credentials allowed, browsers may send cookies and `Authorization`
+//!   headers, which the CORS spec forbids together with `*`
+//! - preflight responses are cached by browsers for `max_age_secs`
+//!
+//! In strict mode (`API_CORS_STRICT`, on by default in the `prod` profile) a
+//! wildcard is rejected when the config is loaded, and with no origins set
+//! no cross-origin requests are allowed.
+
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::error::{TrackerError, TrackerResult};
+
+/// Default time browsers may cache a preflight response, in seconds.
+pub const DEFAULT_CORS_MAX_AGE_SECS: u64 = 600;
+
+/// Origin value that allows any origin.
+const ANY_ORIGIN: &str = "*";
+
+/// Which origins may call the API, and how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    /// Allowed origins such as `https://app.example.com`, or `*`
+    pub origins: Vec<String>,
+    /// Whether browsers may send credentials
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight response, in seconds
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        Self {
+            origins: vec![ANY_ORIGIN.to_string()],
+            allow_credentials: false,
+            max_age_secs: DEFAULT_CORS_MAX_AGE_SECS,
+        }
+    }
+}
+
+impl CorsPolicy {
+    /// Returns true if any origin is allowed.
+    #[must_use]
+    pub fn allows_any_origin(&self) -> bool {
+        self.origins.iter().any(|origin| origin == ANY_ORIGIN)
+    }
+
+    /// Checks the policy, rejecting a wildcard origin in `strict` mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an origin is not a `scheme://host[:port]` origin,
+    /// credentials are allowed for any origin, or `strict` is set and any
+    /// origin is allowed.
+    pub fn validate(&self, strict: bool) -> TrackerResult<()> {
+        if self.allows_any_origin() {
+            if strict {
+                return Err(TrackerError::config(
+                    "API_CORS_ORIGINS must list explicit origins when API_CORS_STRICT is enabled, got: *",
+                    None,
+                ));
+            }
+            if self.allow_credentials {
+                return Err(TrackerError::config(
+                    "API_CORS_ALLOW_CREDENTIALS requires explicit API_CORS_ORIGINS, not *",
+                    None,
+                ));
+            }
+        }
+
+        for origin in self.origins.iter().filter(|o| *o != ANY_ORIGIN) {
+            let valid = origin.split_once("://").is_some_and(|(scheme, host)| {
+                !scheme.is_empty() && !host.is_empty() && !host.contains('/')
+            }) && HeaderValue::from_str(origin).is_ok();
+            if !valid {
+                return Err(TrackerError::config(
+                    format!(
+                        "API_CORS_ORIGINS entry '{origin}' must be an origin like https://app.example.com"
+                    ),
+                    None,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the CORS layer of the API server.
+    ///
+    /// Call [`validate`](Self::validate) first; origins that are not valid
+    /// header values are skipped.
+    pub fn layer(&self) -> CorsLayer {
+        let origin = if self.allows_any_origin() {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(
+                self.origins
+                    .iter()
+                    .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+            )
+        };
+
+        CorsLayer::new()
+            .allow_origin(origin)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::OPTIONS])
+            .allow_headers([
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                header::IF_NONE_MATCH,
+                header::IF_MODIFIED_SINCE,
+                HeaderName::from_static("x-api-key"),
+            ])
+            .expose_headers([header::ETAG, header::LAST_MODIFIED, header::RETRY_AFTER])
+            .allow_credentials(self.allow_credentials)
+            .max_age(Duration::from_secs(self.max_age_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn policy(origins: &[&str], allow_credentials: bool) -> CorsPolicy {
+        CorsPolicy {
+            origins: origins.iter().map(ToString::to_string).collect(),
+            allow_credentials,
+            max_age_secs: 300,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(CorsPolicy::default().validate(false).is_ok());
+        assert!(CorsPolicy::default().validate(true).is_err());
+        assert!(policy(&["*"], true).validate(false).is_err());
+        assert!(policy(&["https://app.example"], true)
+            .validate(true)
+            .is_ok());
+        assert!(policy(&[], false).validate(true).is_ok());
+        assert!(policy(&["app.example"], false).validate(false).is_err());
+        assert!(policy(&["https://app.example/path"], false)
+            .validate(false)
+            .is_err());
+    }
+
+    async fn preflight(policy: &CorsPolicy, origin: &str, method: &str) -> axum::http::HeaderMap {
+        let app = Router::new()
+            .route("/price", get(|| async { "ok" }))
+            .layer(policy.layer());
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/price")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, method)
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap().headers().clone()
+    }
+
+    #[tokio::test]
+    async fn test_layer_allows_only_listed_origins() {
+        let policy = policy(&["https://a.example", "https://b.example"], true);
+
+        for origin in ["https://a.example", "https://b.example"] {
+            let headers = preflight(&policy, origin, "GET").await;
+            assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], origin);
+            assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+            assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "300");
+        }
+
+        let headers = preflight(&policy, "https://evil.example", "GET").await;
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let headers = preflight(&CorsPolicy::default(), "https://evil.example", "GET").await;
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
+
+    #[tokio::test]
+    async fn test_layer_allows_admin_put() {
+        let policy = policy(&["https://a.example"], true);
+        let headers = preflight(&policy, "https://a.example", "PUT").await;
+        let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(methods.split(',').any(|m| m.trim() == "PUT"));
+    }
+}
@@ -1,6 +1,14 @@
 //! OpenAPI documentation for the REST API.
+//!
+//! The spec is generated from the handlers' `#[utoipa::path]`
+//! attributes and the models' `ToSchema` derives, served at
+//! `/api-docs/openapi.json` and browsable with Swagger UI at `/docs`. Nested
+//! schemas must be listed in `components` explicitly; the tests fail if a
+//! referenced schema is missing.
 
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::openapi::{ArrayBuilder, ObjectBuilder, Ref, RefOr, Schema};
+use utoipa::{Modify, OpenApi, ToSchema};
 
 use crate::api::handlers;
 
@@ -9,31 +17,130 @@ use crate::api::handlers;
 #[openapi(
     paths(
         handlers::health::health_check,
+        handlers::health::liveness,
+        handlers::status::get_status,
         handlers::pools::list_pools,
+        handlers::pools::get_quote,
+        handlers::pools::get_reserves_at,
+        handlers::pools::get_impermanent_loss,
+        handlers::pools::get_price_path,
+        handlers::pools::get_timeseries,
+        handlers::incidents::list_incidents,
         handlers::price::get_current_price,
+        handlers::price::get_latest_price,
         handlers::price::get_price_history,
+        handlers::price::export_price_history,
+        handlers::price::get_prices_at_blocks,
+        handlers::price::get_price_at,
+        handlers::composite::get_composite_price,
+        handlers::composite::get_composite_history,
+        handlers::route::get_route,
+        handlers::signing::get_public_key,
         handlers::stats::get_stats,
+        handlers::reorgs::list_reorgs,
+        handlers::reorgs::get_reorg_stats,
+        handlers::analytics::get_analytics,
+        handlers::analytics::get_fee_apr,
+        handlers::analytics::get_sandwiches,
+        handlers::candles::get_candles,
         handlers::events::get_recent_events,
+        handlers::events::list_pool_events,
         handlers::stream::websocket_handler,
+        handlers::alerts::get_alert_status,
+        handlers::admin::get_standby_status,
+        handlers::admin::promote,
+        handlers::admin::enable_pool,
+        handlers::admin::disable_pool,
+        handlers::admin::set_pool_start_block,
+        handlers::admin::set_pool_spike_filter,
     ),
     components(schemas(
         crate::api::models::HealthResponse,
+        crate::api::models::LivenessResponse,
+        crate::api::models::StatusResponse,
+        crate::api::models::StatusFormat,
+        crate::api::models::RpcStatusInfo,
+        crate::api::models::PoolStatusInfo,
+        crate::api::models::StatusIncidentInfo,
+        crate::api::models::PriceCacheInfo,
+        crate::api::models::LagWatchdogInfo,
+        crate::api::models::RpcCapabilitiesInfo,
         crate::api::models::PoolInfo,
+        crate::api::models::PoolTaskInfo,
+        crate::api::models::PoolSettingsResponse,
+        crate::api::models::PoolStartBlockRequest,
+        crate::api::models::PoolSpikeFilterRequest,
+        crate::api::models::QuoteResponse,
+        crate::api::models::RouteResponse,
+        crate::api::models::RouteHop,
+        crate::api::models::ReservesAtResponse,
+        crate::api::models::ReserveAmount,
+        crate::api::models::ReserveSource,
+        crate::api::models::ImpermanentLossResponse,
+        crate::api::models::PositionAmounts,
+        crate::api::models::PricePathResponse,
+        crate::api::models::BlockPricePath,
+        crate::api::models::PricePathStep,
+        crate::api::models::TimeseriesResponse,
         crate::api::models::CurrentPriceResponse,
+        crate::api::models::ConversionStep,
         crate::api::models::PricePoint,
-        crate::api::models::PaginatedResponse<crate::api::models::PricePoint>,
+        crate::api::models::PricesAtBlocksRequest,
+        crate::api::models::PricesAtBlocksResponse,
+        crate::api::models::PriceAtBlock,
+        crate::api::models::PriceAtResponse,
+        crate::api::models::PublicKeyResponse,
+        crate::api::models::CompositePriceInfo,
+        crate::api::models::CompositeConstituentInfo,
+        PaginatedCompositePrices,
+        PaginatedPricePoints,
+        PaginatedSyncEvents,
+        PaginatedPools,
+        PaginatedIncidents,
+        crate::api::models::IncidentInfo,
         crate::api::models::StatsResponse,
+        PaginatedReorgs,
+        crate::api::models::ReorgInfo,
+        crate::api::models::ReorgStatsResponse,
+        crate::api::models::AnalyticsResponse,
+        crate::api::models::TraderStats,
+        crate::api::models::DailyTraders,
+        crate::api::models::FeeAprResponse,
+        crate::api::models::FeeAprWindow,
+        crate::api::models::SandwichResponse,
+        crate::api::models::SandwichInfo,
+        crate::api::models::CandlesResponse,
+        crate::api::models::CandleInfo,
         crate::api::models::ErrorResponse,
         crate::api::models::RecentEventResponse,
+        crate::api::models::SortOrder,
+        crate::api::models::ExportFormat,
+        crate::api::models::AlertStatusResponse,
+        crate::api::models::AlertRuleStatus,
+        crate::api::models::AlertDeliveryInfo,
+        crate::api::models::StandbyStatusResponse,
+        crate::api::models::InstanceRole,
+        crate::api::models::ReservesInfo,
+        crate::api::models::PaginationInfo,
+        crate::api::models::TokenInfo,
+        crate::api::models::HealthStatus,
+        crate::api::models::StatsPeriod,
+        crate::api::models::SyncEventInfo,
+        crate::api::models::PriceStreamMessage,
+        crate::api::models::StreamChannel,
     )),
     tags(
         (name = "Health", description = "Health check endpoints"),
         (name = "Pools", description = "Pool management"),
         (name = "Price", description = "Price data endpoints"),
         (name = "Statistics", description = "Statistical data"),
+        (name = "Analytics", description = "Trader analytics, fee APR and sandwich detection from Swap events"),
         (name = "Events", description = "Event listing"),
         (name = "Streaming", description = "WebSocket streaming"),
+        (name = "Alerts", description = "Price alert status"),
+        (name = "Admin", description = "Standby and promotion"),
     ),
+    modifiers(&ApiKeySecurity),
     info(
         title = "ETH Price Tracker API",
         version = "1.0.0",
@@ -41,3 +148,142 @@ use crate::api::handlers;
     )
 )]
 pub struct ApiDoc;
+
+/// Registers the `api_key` security scheme used by protected routes.
+struct ApiKeySecurity;
+
+impl Modify for ApiKeySecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-API-Key",
+                "API key minted with `eth-uniswap-alloy keys create`",
+            ))),
+        );
+    }
+}
+
+/// Declares the schema of a concrete `Paginated<T>`.
+///
+/// utoipa cannot substitute generic parameters without generating an
+/// undocumented type alias, so each concrete shape is spelled out here.
+macro_rules! paginated_schema {
+    ($name:ident, $item:literal) => {
+        #[doc = concat!("Schema for `Paginated<", $item, ">`.")]
+        struct $name;
+
+        impl<'s> ToSchema<'s> for $name {
+            fn schema() -> (&'s str, RefOr<Schema>) {
+                (
+                    stringify!($name),
+                    ObjectBuilder::new()
+                        .description(Some(
+                            "Response envelope shared by paginated list endpoints.",
+                        ))
+                        .property(
+                            "data",
+                            ArrayBuilder::new().items(Ref::from_schema_name($item)),
+                        )
+                        .required("data")
+                        .property("pagination", Ref::from_schema_name("PaginationInfo"))
+                        .required("pagination")
+                        .into(),
+                )
+            }
+        }
+    };
+}
+
+paginated_schema!(PaginatedPricePoints, "PricePoint");
+paginated_schema!(PaginatedSyncEvents, "SyncEventInfo");
+paginated_schema!(PaginatedPools, "PoolInfo");
+paginated_schema!(PaginatedIncidents, "IncidentInfo");
+paginated_schema!(PaginatedReorgs, "ReorgInfo");
+paginated_schema!(PaginatedCompositePrices, "CompositePriceInfo");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collects every `$ref` target in a JSON document.
+    fn collect_refs(value: &serde_json::Value, refs: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, v) in map {
+                    match (key.as_str(), v) {
+                        ("$ref", serde_json::Value::String(r)) => refs.push(r.clone()),
+                        _ => collect_refs(v, refs),
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for v in items {
+                    collect_refs(v, refs);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_all_schema_refs_resolve() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+
+        let mut refs = Vec::new();
+        collect_refs(&spec, &mut refs);
+        assert!(!refs.is_empty());
+
+        for r in refs {
+            let name = r.strip_prefix("#/components/schemas/").unwrap();
+            assert!(
+                schemas.get(name).is_some(),
+                "{r} is referenced but not registered in ApiDoc components"
+            );
+        }
+    }
+
+    #[test]
+    fn test_spec_covers_rest_endpoints() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+
+        for path in [
+            "/api/v1/health",
+            "/api/v1/health/live",
+            "/api/v1/pools",
+            "/api/v1/pools/{id}/events",
+            "/api/v1/pools/{id}/quote",
+            "/api/v1/pools/{id}/reserves/at",
+            "/api/v1/pools/{id}/price/at",
+            "/api/v1/pools/{id}/impermanent-loss",
+            "/api/v1/pools/{id}/sandwiches",
+            "/api/v1/price/current/{pool}",
+            "/api/v1/price/latest/{pool}",
+            "/api/v1/price/history/{pool}",
+            "/api/v1/prices/at-blocks",
+            "/api/v1/composite",
+            "/api/v1/composite/history",
+            "/api/v1/route",
+            "/.well-known/pubkey",
+            "/status",
+            "/api/v1/stats/{pool}",
+            "/api/v1/reorgs",
+            "/api/v1/pools/{id}/reorgs/stats",
+            "/api/v1/candles/{pool}",
+            "/api/v1/events/{pool}",
+            "/api/v1/stream/{pool}",
+            "/api/v1/alerts",
+            "/api/v1/admin/standby",
+            "/api/v1/admin/promote",
+            "/api/v1/admin/pools/{id}/enable",
+            "/api/v1/admin/pools/{id}/disable",
+            "/api/v1/admin/pools/{id}/start-block",
+            "/api/v1/admin/pools/{id}/spike-filter",
+        ] {
+            assert!(paths.contains_key(path), "{path} missing from OpenAPI spec");
+        }
+    }
+}
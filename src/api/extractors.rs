@@ -1,9 +1,14 @@
 //! Custom extractors for API parameters.
 
+use std::convert::Infallible;
+
 use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
 use axum::http::StatusCode;
 
+use crate::api::middleware::auth::AuthenticatedKey;
+use crate::db::models::PoolScope;
+
 /// Extracts and normalizes a pool name from path parameters.
 ///
 /// Accepts "WETH-USDT" and normalizes to "WETH/USDT".
@@ -23,3 +28,22 @@ where
         Ok(Self(normalized))
     }
 }
+
+/// Extracts the pools visible to the request's API key, as set by
+/// [`authenticate`](crate::api::middleware::auth::authenticate); requests
+/// without a key see every pool.
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for PoolScope
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<AuthenticatedKey>()
+            .map(|key| key.scope.clone())
+            .unwrap_or_default())
+    }
+}
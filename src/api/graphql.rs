@@ -0,0 +1,529 @@
+//! Optional GraphQL API (enabled with the `graphql` cargo feature).
+//!
+//! Exposes the same data as the REST endpoints — pools, prices, candles and
+//! raw sync events — as a single graph, so dashboards can fetch exactly the
+//! fields they need in one round trip:
+//!
+//! ```graphql
+//! {
+//!   pool(id: "WETH-USDT") {
+//!     name
+//!     latestPrice { price blockNumber }
+//!     candles(interval: H1, limit: 24) { bucketStart open high low close }
+//!     events(first: 10) { nodes { blockNumber logIndex } nextCursor }
+//!   }
+//! }
+//! ```
+//!
+//! Mounted at `/api/v1/graphql` (POST for queries, GET for the `GraphiQL` IDE)
+//! behind the same middleware stack as the REST routes; API keys scoped to
+//! some pools only see those.
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, Object, Result, Schema, SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::response::{Html, IntoResponse};
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+
+use crate::app_state::AppState;
+use crate::db::models::{
+    BlockRange, CandleRow, EventCursor, PoolRecord, PoolRow, PoolScope, PricePointRow, SyncEventRow,
+};
+
+/// Maximum page size accepted by list fields.
+const MAX_PAGE_SIZE: i32 = 1000;
+
+/// Maximum query depth, to bound the cost of nested selections.
+const MAX_DEPTH: usize = 8;
+
+/// Maximum query complexity (roughly, number of resolved fields).
+const MAX_COMPLEXITY: usize = 1000;
+
+/// The GraphQL schema type served by the API.
+pub type TrackerSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Builds the GraphQL schema with the shared application state attached.
+#[must_use]
+pub fn build_schema(state: AppState) -> TrackerSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Routes serving the GraphQL endpoint and the `GraphiQL` IDE.
+pub fn routes(state: AppState) -> Router<AppState> {
+    let schema = build_schema(state);
+    let execute = move |scope: PoolScope, request: GraphQLRequest| async move {
+        GraphQLResponse::from(schema.execute(request.into_inner().data(scope)).await)
+    };
+    Router::new().route("/graphql", get(graphiql).post(execute))
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/api/v1/graphql").finish())
+}
+
+fn app_state<'a>(ctx: &Context<'a>) -> Result<&'a AppState> {
+    ctx.data::<AppState>()
+}
+
+/// Pools visible to the request's API key (every pool without one).
+fn pool_scope<'a>(ctx: &Context<'a>) -> &'a PoolScope {
+    static ALL: PoolScope = PoolScope::All;
+    ctx.data_opt::<PoolScope>().unwrap_or(&ALL)
+}
+
+fn page_size(first: i32) -> Result<i64> {
+    if !(1..=MAX_PAGE_SIZE).contains(&first) {
+        return Err(format!("page size must be between 1 and {MAX_PAGE_SIZE}").into());
+    }
+    Ok(i64::from(first))
+}
+
+fn to_datetime(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap_or_default()
+}
+
+/// Root query type.
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// All tracked pools.
+    async fn pools(&self, ctx: &Context<'_>) -> Result<Vec<Pool>> {
+        let pools = app_state(ctx)?.reader.get_all_pools().await?;
+        let scope = pool_scope(ctx);
+        Ok(pools
+            .into_iter()
+            .filter(|pool| scope.allows(pool.id))
+            .map(Pool::from)
+            .collect())
+    }
+
+    /// A single pool by database ID, contract address, or name (e.g. `WETH-USDT`).
+    async fn pool(&self, ctx: &Context<'_>, id: String) -> Result<Option<Pool>> {
+        let pool = app_state(ctx)?.reader.find_pool(&id).await?;
+        let scope = pool_scope(ctx);
+        Ok(pool.filter(|pool| scope.allows(pool.id)).map(Pool::from))
+    }
+}
+
+/// A tracked Uniswap V2 pool.
+pub struct Pool {
+    id: i64,
+    name: String,
+    address: String,
+    token0: Token,
+    token1: Token,
+    spike_filter_bps: Option<u32>,
+}
+
+impl From<PoolRecord> for Pool {
+    fn from(p: PoolRecord) -> Self {
+        let spike_filter_bps = p.spike_filter();
+        Self {
+            id: p.id,
+            name: p.name.unwrap_or_else(|| p.address.to_string()),
+            address: p.address.to_string(),
+            token0: Token {
+                symbol: p.token0_symbol.unwrap_or_default(),
+                address: p.token0_address,
+                decimals: p.token0_decimals,
+            },
+            token1: Token {
+                symbol: p.token1_symbol.unwrap_or_default(),
+                address: p.token1_address,
+                decimals: p.token1_decimals,
+            },
+            spike_filter_bps,
+        }
+    }
+}
+
+impl From<PoolRow> for Pool {
+    fn from(p: PoolRow) -> Self {
+        let spike_filter_bps = p.spike_filter();
+        Self {
+            id: p.id,
+            name: p.name.unwrap_or_else(|| p.address.to_string()),
+            address: p.address.to_string(),
+            token0: Token {
+                symbol: p.token0_symbol.unwrap_or_default(),
+                address: p.token0_address,
+                decimals: i32::try_from(p.token0_decimals).unwrap_or_default(),
+            },
+            token1: Token {
+                symbol: p.token1_symbol.unwrap_or_default(),
+                address: p.token1_address,
+                decimals: i32::try_from(p.token1_decimals).unwrap_or_default(),
+            },
+            spike_filter_bps,
+        }
+    }
+}
+
+#[Object]
+impl Pool {
+    /// Database ID.
+    async fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Pool name (e.g. `WETH/USDT`).
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Pool contract address.
+    async fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Token0 metadata.
+    async fn token0(&self) -> &Token {
+        &self.token0
+    }
+
+    /// Token1 metadata.
+    async fn token1(&self) -> &Token {
+        &self.token1
+    }
+
+    /// Latest confirmed price.
+    async fn latest_price(&self, ctx: &Context<'_>) -> Result<Option<Price>> {
+        let price = app_state(ctx)?.reader.get_latest_price(self.id).await?;
+        Ok(price.map(Price::from))
+    }
+
+    /// Confirmed prices, newest first, optionally bounded by unix timestamps.
+    async fn prices(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<i64>,
+        to: Option<i64>,
+        #[graphql(default = 100)] first: i32,
+        #[graphql(default = 0)] offset: i32,
+    ) -> Result<PriceConnection> {
+        let limit = page_size(first)?;
+        let offset = i64::from(offset.max(0));
+        let page = app_state(ctx)?
+            .reader
+            .get_price_history_paginated(self.id, from, to, BlockRange::ALL, limit, offset)
+            .await?;
+
+        let total = i64::try_from(page.total).unwrap_or(i64::MAX);
+        Ok(PriceConnection {
+            has_next_page: offset + limit < total,
+            total_count: total,
+            nodes: page.items.into_iter().map(Price::from).collect(),
+        })
+    }
+
+    /// The most recent OHLC candles, oldest first, optionally bounded by unix
+    /// timestamps.
+    async fn candles(
+        &self,
+        ctx: &Context<'_>,
+        interval: CandleInterval,
+        from: Option<i64>,
+        to: Option<i64>,
+        #[graphql(default = 500)] limit: i32,
+    ) -> Result<Vec<Candle>> {
+        let limit = page_size(limit)?;
+        let rows = app_state(ctx)?
+            .reader
+            .get_candles(
+                self.id,
+                interval.seconds(),
+                from,
+                to,
+                BlockRange::ALL,
+                limit,
+                self.spike_filter_bps,
+            )
+            .await?;
+        Ok(rows.into_iter().map(Candle::from).collect())
+    }
+
+    /// Raw sync events with cursor pagination.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 100)] first: i32,
+        after: Option<String>,
+        #[graphql(default)] order: Order,
+    ) -> Result<EventConnection> {
+        let limit = page_size(first)?;
+        let cursor = after
+            .as_deref()
+            .map(str::parse::<EventCursor>)
+            .transpose()?;
+
+        let mut rows = app_state(ctx)?
+            .reader
+            .get_events_page(
+                self.id,
+                BlockRange::ALL,
+                cursor,
+                limit + 1,
+                order == Order::Desc,
+            )
+            .await?
+            .items;
+
+        let limit = usize::try_from(limit).unwrap_or_default();
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|e| {
+                EventCursor::new(
+                    u64::try_from(e.block_number).unwrap_or_default(),
+                    u32::try_from(e.log_index).unwrap_or_default(),
+                )
+                .to_string()
+            })
+        } else {
+            None
+        };
+
+        Ok(EventConnection {
+            nodes: rows.into_iter().map(Event::from).collect(),
+            next_cursor,
+        })
+    }
+}
+
+/// Token metadata.
+#[derive(SimpleObject)]
+pub struct Token {
+    /// Token symbol
+    symbol: String,
+    /// Token contract address
+    address: String,
+    /// Token decimals
+    decimals: i32,
+}
+
+/// A price observation.
+#[derive(SimpleObject)]
+#[allow(clippy::struct_field_names)]
+pub struct Price {
+    /// Deterministic price point ID
+    id: Option<String>,
+    /// Block number
+    block_number: i64,
+    /// Block timestamp
+    timestamp: DateTime<Utc>,
+    /// Transaction hash
+    tx_hash: String,
+    /// Price (token1 per token0)
+    price: f64,
+    /// Exact price as a decimal string
+    price_exact: Option<String>,
+    /// EWMA-smoothed price
+    price_ewma: Option<f64>,
+    /// Human-readable reserve0
+    reserve0: f64,
+    /// Human-readable reserve1
+    reserve1: f64,
+}
+
+impl From<PricePointRow> for Price {
+    fn from(p: PricePointRow) -> Self {
+        Self {
+            id: p.event_id,
+            block_number: p.block_number,
+            timestamp: to_datetime(p.block_timestamp),
+            tx_hash: p.tx_hash.to_string(),
+            price: p.price,
+            price_exact: p.price_exact,
+            price_ewma: p.price_ewma,
+            reserve0: p.reserve0_human,
+            reserve1: p.reserve1_human,
+        }
+    }
+}
+
+/// A page of prices.
+#[derive(SimpleObject)]
+pub struct PriceConnection {
+    /// Prices on this page
+    nodes: Vec<Price>,
+    /// Total matching prices
+    total_count: i64,
+    /// Whether another page exists
+    has_next_page: bool,
+}
+
+/// An OHLC candle.
+#[derive(SimpleObject)]
+pub struct Candle {
+    /// Bucket start
+    bucket_start: DateTime<Utc>,
+    /// Opening price
+    open: f64,
+    /// Highest price
+    high: f64,
+    /// Lowest price
+    low: f64,
+    /// Closing price
+    close: f64,
+    /// Number of price points in the bucket
+    samples: i64,
+}
+
+impl From<CandleRow> for Candle {
+    fn from(c: CandleRow) -> Self {
+        Self {
+            bucket_start: to_datetime(c.bucket_start),
+            open: c.open,
+            high: c.high,
+            low: c.low,
+            close: c.close,
+            samples: c.samples,
+        }
+    }
+}
+
+/// Candle bucket width.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum CandleInterval {
+    /// 1 minute
+    M1,
+    /// 5 minutes
+    M5,
+    /// 15 minutes
+    M15,
+    /// 1 hour
+    H1,
+    /// 4 hours
+    H4,
+    /// 1 day
+    D1,
+}
+
+impl CandleInterval {
+    const fn seconds(self) -> i64 {
+        match self {
+            Self::M1 => 60,
+            Self::M5 => 300,
+            Self::M15 => 900,
+            Self::H1 => 3_600,
+            Self::H4 => 14_400,
+            Self::D1 => 86_400,
+        }
+    }
+}
+
+/// A raw sync event.
+#[derive(SimpleObject)]
+pub struct Event {
+    /// Deterministic event ID
+    id: Option<String>,
+    /// Block number
+    block_number: i64,
+    /// Log index within the block
+    log_index: i64,
+    /// Block timestamp
+    timestamp: DateTime<Utc>,
+    /// Transaction hash
+    tx_hash: String,
+    /// Raw reserve0
+    reserve0: String,
+    /// Raw reserve1
+    reserve1: String,
+}
+
+impl From<SyncEventRow> for Event {
+    fn from(e: SyncEventRow) -> Self {
+        Self {
+            id: e.event_id,
+            block_number: e.block_number,
+            log_index: e.log_index,
+            timestamp: to_datetime(e.block_timestamp),
+            tx_hash: e.tx_hash.to_string(),
+            reserve0: e.reserve0,
+            reserve1: e.reserve1,
+        }
+    }
+}
+
+/// A page of events.
+#[derive(SimpleObject)]
+pub struct EventConnection {
+    /// Events on this page
+    nodes: Vec<Event>,
+    /// Cursor for the next page, null on the last page
+    next_cursor: Option<String>,
+}
+
+/// Sort order for event pages.
+#[derive(Enum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    /// Oldest first
+    Asc,
+    /// Newest first
+    #[default]
+    Desc,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, repository::Repository};
+
+    async fn setup_schema() -> TrackerSchema {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let repository = Repository::new(pool);
+        repository.ensure_default_pool().await.unwrap();
+        build_schema(AppState::new(repository))
+    }
+
+    #[tokio::test]
+    async fn test_query_pools() {
+        let schema = setup_schema().await;
+        let response = schema
+            .execute("{ pools { name token0 { symbol decimals } } }")
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["pools"][0]["name"], "WETH/USDT");
+        assert_eq!(data["pools"][0]["token0"]["decimals"], 18);
+    }
+
+    #[tokio::test]
+    async fn test_query_pool_by_name_with_nested_fields() {
+        let schema = setup_schema().await;
+        let response = schema
+            .execute(
+                r#"{ pool(id: "WETH-USDT") {
+                    latestPrice { price }
+                    candles(interval: H1) { open }
+                    events(first: 5) { nodes { blockNumber } nextCursor }
+                } }"#,
+            )
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert!(data["pool"]["latestPrice"].is_null());
+        assert_eq!(data["pool"]["candles"], serde_json::json!([]));
+        assert!(data["pool"]["events"]["nextCursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_page() {
+        let schema = setup_schema().await;
+        let response = schema
+            .execute(r#"{ pool(id: "1") { events(first: 5000) { nextCursor } } }"#)
+            .await;
+
+        assert_eq!(response.errors.len(), 1);
+    }
+}
@@ -0,0 +1,342 @@
+//! Optional gRPC API (enabled with the `grpc` cargo feature).
+//!
+//! Serves the `PriceTracker` service of `proto/price_tracker.proto` on its own
+//! port (`api --grpc-port`), for backends that prefer typed clients and
+//! server streaming over REST and `WebSocket` streams:
+//!
+//! - `GetLatestPrice` and `GetHistory` answer like
+//!   `/api/v1/price/latest/{pool}` and `/api/v1/price/history/{pool}`, from
+//!   the same price cache and read repository
+//! - `SubscribePrices` streams the messages broadcast to the
+//!   `/api/v1/stream/{pool}` WebSocket, optionally for every pool at once
+//!
+//! The service shares the REST server's [`AppState`]. API keys, their pool
+//! scopes and rate limits are not enforced on the gRPC port, so expose it
+//! only to trusted networks.
+
+use futures_util::Stream;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Request, Response, Status};
+use tracing::{error, info, warn};
+
+use crate::api::handlers::price::{current_price, price_point};
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{CurrentPriceQuery, PriceStreamMessage, ReservesInfo, StreamChannel};
+use crate::app_state::AppState;
+use crate::db::models::{BlockRange, PoolScope};
+use crate::error::{TrackerError, TrackerResult};
+
+/// Types and service generated from `proto/price_tracker.proto`.
+#[allow(clippy::all, clippy::pedantic, clippy::nursery, missing_docs)]
+pub mod proto {
+    tonic::include_proto!("eth_price_tracker.v1");
+}
+
+use proto::price_tracker_server::{PriceTracker, PriceTrackerServer};
+use proto::{
+    Channel, GetHistoryRequest, GetHistoryResponse, GetLatestPriceRequest, LatestPrice, PricePoint,
+    PriceUpdate, Reserves, SubscribePricesRequest,
+};
+
+/// Page size of `GetHistory` when the request doesn't set one.
+const DEFAULT_HISTORY_LIMIT: u32 = 100;
+
+/// Largest page `GetHistory` returns.
+const MAX_HISTORY_LIMIT: u32 = 1000;
+
+/// Stream of `SubscribePrices` updates.
+type PriceUpdateStream = Pin<Box<dyn Stream<Item = Result<PriceUpdate, Status>> + Send>>;
+
+/// The `PriceTracker` gRPC service.
+#[derive(Clone)]
+pub struct PriceTrackerService {
+    state: AppState,
+}
+
+impl PriceTrackerService {
+    /// Creates the service over the API server's state.
+    #[must_use]
+    pub const fn new(state: AppState) -> Self {
+        Self { state }
+    }
+}
+
+#[tonic::async_trait]
+impl PriceTracker for PriceTrackerService {
+    async fn get_latest_price(
+        &self,
+        request: Request<GetLatestPriceRequest>,
+    ) -> Result<Response<LatestPrice>, Status> {
+        let request = request.into_inner();
+        let query = CurrentPriceQuery {
+            strict: false,
+            invert: request.invert,
+            denominate: None,
+        };
+        let price = current_price(&self.state, &PoolScope::All, &request.pool, &query)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(LatestPrice {
+            pool: price.pool,
+            price: price.price,
+            price_exact: price.price_exact,
+            price_ewma: price.price_ewma,
+            block_number: price.block_number,
+            timestamp: price.timestamp.timestamp(),
+            tx_hash: price.tx_hash,
+            reserves: Some(reserves(&price.reserves)),
+            change_24h: price.change_24h,
+            stale: price.stale,
+            age_seconds: price.age_seconds,
+            quote_direction: price.quote_direction,
+        }))
+    }
+
+    async fn get_history(
+        &self,
+        request: Request<GetHistoryRequest>,
+    ) -> Result<Response<GetHistoryResponse>, Status> {
+        let request = request.into_inner();
+        let limit = match request.limit {
+            0 => DEFAULT_HISTORY_LIMIT,
+            limit if limit > MAX_HISTORY_LIMIT => {
+                return Err(Status::invalid_argument(format!(
+                    "limit must be <= {MAX_HISTORY_LIMIT}"
+                )))
+            }
+            limit => limit,
+        };
+
+        let name = request.pool.replace('-', "/");
+        let pool = self
+            .state
+            .reader
+            .get_pool_by_name(&name)
+            .await
+            .map_err(|e| to_status(e.into()))?
+            .ok_or_else(|| Status::not_found(format!("Pool {name} not found")))?;
+        let direction = pool.quote_direction().inverted(request.invert);
+        let bound = |ts: i64| (ts != 0).then_some(ts);
+
+        let page = self
+            .state
+            .reader
+            .get_price_history_paginated(
+                pool.id,
+                bound(request.from),
+                bound(request.to),
+                BlockRange::ALL,
+                i64::from(limit),
+                i64::try_from(request.offset).unwrap_or(i64::MAX),
+            )
+            .await
+            .map_err(|e| to_status(e.into()))?;
+
+        let prices = page
+            .items
+            .into_iter()
+            .map(|row| {
+                let point = price_point(row, direction);
+                PricePoint {
+                    block_number: point.block_number,
+                    timestamp: point.timestamp.timestamp(),
+                    price: point.price,
+                    price_exact: point.price_exact,
+                    tx_hash: point.tx_hash,
+                    source: point.source,
+                    reserves: Some(reserves(&point.reserves)),
+                }
+            })
+            .collect();
+
+        Ok(Response::new(GetHistoryResponse {
+            prices,
+            total: page.total,
+            quote_direction: direction.to_string(),
+        }))
+    }
+
+    type SubscribePricesStream = PriceUpdateStream;
+
+    async fn subscribe_prices(
+        &self,
+        request: Request<SubscribePricesRequest>,
+    ) -> Result<Response<Self::SubscribePricesStream>, Status> {
+        let request = request.into_inner();
+        let pool = request.pool.replace('-', "/");
+        let channel = match request.channel() {
+            Channel::Confirmed => StreamChannel::Confirmed,
+            Channel::Preview => StreamChannel::Preview,
+            Channel::All => StreamChannel::All,
+        };
+        info!(pool = %pool, ?channel, "gRPC price subscription started");
+
+        let rx = self.state.price_broadcast.subscribe();
+        let stream = futures_util::stream::unfold(rx, move |mut rx| {
+            let pool = pool.clone();
+            async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(msg)
+                            if (pool.is_empty() || msg.pool == pool)
+                                && channel.carries(msg.is_confirmed) =>
+                        {
+                            return Some((Ok(price_update(msg)), rx));
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!(skipped, "gRPC subscriber fell behind, prices skipped");
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+const fn reserves(reserves: &ReservesInfo) -> Reserves {
+    Reserves {
+        reserve0: reserves.weth,
+        reserve1: reserves.usdt,
+    }
+}
+
+fn price_update(msg: PriceStreamMessage) -> PriceUpdate {
+    PriceUpdate {
+        event_type: msg.event_type,
+        pool: msg.pool,
+        price: msg.price,
+        price_ewma: msg.price_ewma,
+        block_number: msg.block_number,
+        timestamp: msg.timestamp.timestamp(),
+        reserves: Some(reserves(&msg.reserves)),
+        is_confirmed: msg.is_confirmed,
+    }
+}
+
+/// Maps an API error to a gRPC status, keeping internal details in the log
+/// as the REST API does.
+fn to_status(e: ApiError) -> Status {
+    match e {
+        ApiError::NotFound(msg) => Status::not_found(msg),
+        ApiError::BadRequest(msg) => Status::invalid_argument(msg),
+        ApiError::Unauthorized(msg) => Status::unauthenticated(msg),
+        ApiError::Forbidden(msg) => Status::permission_denied(msg),
+        ApiError::ServiceUnavailable(msg) => Status::unavailable(msg),
+        ApiError::RateLimitExceeded { retry_after_secs } => Status::resource_exhausted(format!(
+            "Rate limit exceeded. Retry in {retry_after_secs}s."
+        )),
+        ApiError::DatabaseError(msg) => {
+            error!(error = %msg, "Database error in gRPC handler");
+            Status::internal("Database operation failed")
+        }
+        ApiError::InternalError(msg) => {
+            error!(error = %msg, "Internal error in gRPC handler");
+            Status::internal("Internal server error")
+        }
+        ApiError::Tracker(err) => {
+            error!(error = %err, code = err.code(), "Indexer error in gRPC handler");
+            Status::internal("Internal server error")
+        }
+    }
+}
+
+/// Serves the gRPC API on `port` until the server fails.
+///
+/// # Errors
+///
+/// Returns an error if the port can't be bound or the server fails.
+pub async fn serve(state: AppState, port: u16) -> TrackerResult<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    info!(addr = %addr, "Starting gRPC server");
+
+    tonic::transport::Server::builder()
+        .add_service(PriceTrackerServer::new(PriceTrackerService::new(state)))
+        .serve(addr)
+        .await
+        .map_err(|e| TrackerError::state("gRPC server failed", Some(Box::new(e))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, repository::Repository};
+    use futures_util::StreamExt;
+
+    fn message(pool: &str, is_confirmed: bool) -> PriceStreamMessage {
+        PriceStreamMessage {
+            event_type: if is_confirmed {
+                "price_update"
+            } else {
+                "price_preview"
+            }
+            .to_string(),
+            pool: pool.to_string(),
+            price: 2_000.0,
+            price_ewma: None,
+            block_number: 100,
+            timestamp: chrono::Utc::now(),
+            reserves: ReservesInfo {
+                weth: 1.0,
+                usdt: 2_000.0,
+            },
+            is_confirmed,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscription_filters_pool_and_channel() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let state = AppState::new(repository);
+        let service = PriceTrackerService::new(state.clone());
+
+        let mut stream = service
+            .subscribe_prices(Request::new(SubscribePricesRequest {
+                pool: "WETH-USDT".to_string(),
+                channel: Channel::Confirmed.into(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        state.broadcast_price_update(message("WETH/USDC", true));
+        state.broadcast_price_update(message("WETH/USDT", false));
+        state.broadcast_price_update(message("WETH/USDT", true));
+
+        let update = stream.next().await.unwrap().unwrap();
+        assert_eq!(update.pool, "WETH/USDT");
+        assert!(update.is_confirmed);
+        assert_eq!(update.reserves.unwrap().reserve1, 2_000.0);
+    }
+
+    #[tokio::test]
+    async fn test_history_rejects_unknown_pool_and_large_page() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let service = PriceTrackerService::new(AppState::new(repository));
+
+        let status = service
+            .get_history(Request::new(GetHistoryRequest {
+                pool: "NOPE-NOPE".to_string(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let status = service
+            .get_history(Request::new(GetHistoryRequest {
+                pool: "WETH-USDT".to_string(),
+                limit: MAX_HISTORY_LIMIT + 1,
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
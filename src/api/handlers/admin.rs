@@ -0,0 +1,237 @@
+//! Administrative endpoints.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use tracing::{info, instrument};
+
+use crate::api::handlers::pools::resolve_pool;
+use crate::api::middleware::auth::require_unscoped;
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{
+    InstanceRole, PoolSettingsResponse, PoolSpikeFilterRequest, PoolStartBlockRequest,
+    StandbyStatusResponse,
+};
+use crate::app_state::AppState;
+use crate::db::models::{PoolRecord, PoolScope};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/standby",
+    responses(
+        (status = 200, description = "Replication role and follow progress", body = StandbyStatusResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key is limited to some pools", body = ErrorResponse)
+    ),
+    security(("api_key" = [])),
+    tag = "Admin"
+)]
+/// Returns whether this instance is a primary or a standby.
+#[instrument(skip(state, scope))]
+pub async fn get_standby_status(
+    State(state): State<AppState>,
+    scope: PoolScope,
+) -> Result<Json<StandbyStatusResponse>, ApiError> {
+    require_unscoped(&scope, "Standby control")?;
+    Ok(Json(standby_status(&state)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/promote",
+    responses(
+        (status = 200, description = "Standby promoted to primary", body = StandbyStatusResponse),
+        (status = 400, description = "Instance is not a standby or was already promoted", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 403, description = "API key is limited to some pools", body = ErrorResponse)
+    ),
+    security(("api_key" = [])),
+    tag = "Admin"
+)]
+/// Promotes a warm standby to primary.
+///
+/// The standby stops following, applies a final follow pass and resumes
+/// indexing from the primary's last recorded block.
+#[instrument(skip(state, scope))]
+pub async fn promote(
+    State(state): State<AppState>,
+    scope: PoolScope,
+) -> Result<Json<StandbyStatusResponse>, ApiError> {
+    require_unscoped(&scope, "Standby control")?;
+    let control = state.standby.as_ref().ok_or_else(|| {
+        ApiError::BadRequest("Instance is not running in standby mode".to_string())
+    })?;
+
+    if !control.promote() {
+        return Err(ApiError::BadRequest(
+            "Instance has already been promoted".to_string(),
+        ));
+    }
+
+    info!("Standby promotion requested");
+    Ok(Json(standby_status(&state)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/pools/{id}/enable",
+    params(("id" = String, Path, description = "Pool ID, address or name")),
+    responses(
+        (status = 200, description = "Pool indexing resumed", body = PoolSettingsResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    security(("api_key" = [])),
+    tag = "Admin"
+)]
+/// Resumes indexing of a pool.
+#[instrument(skip(state, scope))]
+pub async fn enable_pool(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+) -> Result<Json<PoolSettingsResponse>, ApiError> {
+    set_pool_enabled(&state, &scope, &id, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/pools/{id}/disable",
+    params(("id" = String, Path, description = "Pool ID, address or name")),
+    responses(
+        (status = 200, description = "Pool indexing paused", body = PoolSettingsResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    security(("api_key" = [])),
+    tag = "Admin"
+)]
+/// Pauses indexing of a pool.
+///
+/// `watch` skips the pool from its next pass and `backfill` refuses it; its
+/// indexed data stays queryable.
+#[instrument(skip(state, scope))]
+pub async fn disable_pool(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+) -> Result<Json<PoolSettingsResponse>, ApiError> {
+    set_pool_enabled(&state, &scope, &id, false).await
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/pools/{id}/start-block",
+    params(("id" = String, Path, description = "Pool ID, address or name")),
+    request_body = PoolStartBlockRequest,
+    responses(
+        (status = 200, description = "Start block updated", body = PoolSettingsResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    security(("api_key" = [])),
+    tag = "Admin"
+)]
+/// Sets the block a pool without a checkpoint starts indexing at.
+#[instrument(skip(state, scope))]
+pub async fn set_pool_start_block(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Json(request): Json<PoolStartBlockRequest>,
+) -> Result<Json<PoolSettingsResponse>, ApiError> {
+    let mut pool = resolve_pool(&state, &scope, &id).await?;
+    state
+        .repository
+        .set_pool_start_block(pool.id, request.start_block)
+        .await?;
+    pool.start_block = request
+        .start_block
+        .map(|block| i64::try_from(block).unwrap_or(i64::MAX));
+    info!(pool_id = pool.id, start_block = ?request.start_block, "Pool start block set");
+    Ok(Json(pool_settings(pool)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/pools/{id}/spike-filter",
+    params(("id" = String, Path, description = "Pool ID, address or name")),
+    request_body = PoolSpikeFilterRequest,
+    responses(
+        (status = 200, description = "Spike filter updated", body = PoolSettingsResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    security(("api_key" = [])),
+    tag = "Admin"
+)]
+/// Sets the threshold from which reverted single-block spikes are left out
+/// of a pool's candles and stats.
+///
+/// Applies to prices recorded from now on and to stats queries; candles
+/// already in memory or flushed keep their spikes.
+#[instrument(skip(state, scope))]
+pub async fn set_pool_spike_filter(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Json(request): Json<PoolSpikeFilterRequest>,
+) -> Result<Json<PoolSettingsResponse>, ApiError> {
+    let mut pool = resolve_pool(&state, &scope, &id).await?;
+    state
+        .repository
+        .set_pool_spike_filter(pool.id, request.spike_filter_bps)
+        .await?;
+    pool.spike_filter_bps = request.spike_filter_bps.map(i64::from);
+    info!(pool_id = pool.id, spike_filter_bps = ?request.spike_filter_bps, "Pool spike filter set");
+    Ok(Json(pool_settings(pool)))
+}
+
+async fn set_pool_enabled(
+    state: &AppState,
+    scope: &PoolScope,
+    id: &str,
+    enabled: bool,
+) -> Result<Json<PoolSettingsResponse>, ApiError> {
+    let mut pool = resolve_pool(state, scope, id).await?;
+    state.repository.set_pool_enabled(pool.id, enabled).await?;
+    pool.enabled = enabled;
+    info!(pool_id = pool.id, enabled, "Pool indexing toggled");
+    Ok(Json(pool_settings(pool)))
+}
+
+fn pool_settings(pool: PoolRecord) -> PoolSettingsResponse {
+    PoolSettingsResponse {
+        pool_id: pool.id,
+        start_block: pool.start_block(),
+        spike_filter_bps: pool.spike_filter(),
+        enabled: pool.enabled,
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+    }
+}
+
+fn standby_status(state: &AppState) -> StandbyStatusResponse {
+    let Some(control) = &state.standby else {
+        return StandbyStatusResponse {
+            role: InstanceRole::Primary,
+            promoted: false,
+            followed_block: None,
+            last_follow_at: None,
+        };
+    };
+
+    let promoted = control.is_promoted();
+    let last_follow = control.last_follow();
+
+    StandbyStatusResponse {
+        role: if promoted {
+            InstanceRole::Primary
+        } else {
+            InstanceRole::Standby
+        },
+        promoted,
+        followed_block: last_follow.and_then(|f| u64::try_from(f.report.last_indexed_block).ok()),
+        last_follow_at: last_follow.map(|f| f.synced_at),
+    }
+}
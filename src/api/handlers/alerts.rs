@@ -0,0 +1,77 @@
+//! Alert status endpoints.
+
+use axum::{extract::State, Json};
+use chrono::DateTime;
+use tracing::instrument;
+
+use crate::alerts::RuleStatus;
+use crate::api::middleware::auth::require_unscoped;
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{AlertDeliveryInfo, AlertRuleStatus, AlertStatusResponse};
+use crate::app_state::AppState;
+use crate::db::models::PoolScope;
+
+/// Deliveries reported by the status endpoint.
+const RECENT_DELIVERIES: i64 = 20;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/alerts",
+    responses(
+        (status = 200, description = "Alert rule status and recent deliveries", body = AlertStatusResponse),
+        (status = 403, description = "API key is limited to some pools", body = ErrorResponse)
+    ),
+    tag = "Alerts"
+)]
+/// Returns every alert rule with its fired and suppressed counts, and the
+/// latest alert deliveries.
+#[instrument(skip(state, scope))]
+pub async fn get_alert_status(
+    State(state): State<AppState>,
+    scope: PoolScope,
+) -> Result<Json<AlertStatusResponse>, ApiError> {
+    require_unscoped(&scope, "Alert status")?;
+    let rules = state
+        .alerts
+        .as_ref()
+        .map(|engine| engine.status().into_iter().map(rule_status).collect())
+        .unwrap_or_default();
+    let recent_deliveries = state
+        .reader
+        .get_recent_alert_deliveries(RECENT_DELIVERIES)
+        .await?
+        .into_iter()
+        .map(|row| AlertDeliveryInfo {
+            source: row.source,
+            event: row.event,
+            channel: row.channel,
+            destination: row.destination,
+            status: row.status,
+            attempts: u32::try_from(row.attempts).unwrap_or(0),
+            error: row.error,
+            delivered_at: DateTime::from_timestamp(row.created_at, 0).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(Json(AlertStatusResponse {
+        enabled: state.alerts.is_some(),
+        rules,
+        recent_deliveries,
+    }))
+}
+
+fn rule_status(status: RuleStatus) -> AlertRuleStatus {
+    AlertRuleStatus {
+        destination: status.rule.destination.redacted(),
+        id: status.rule.id,
+        pool: status.rule.pool,
+        condition: status.rule.condition.to_string(),
+        active: status.active,
+        dedup_window_secs: status.rule.dedup_window_secs,
+        max_per_minute: status.rule.max_per_minute,
+        fired: status.counts.fired,
+        suppressed_duplicate: status.counts.suppressed_duplicate,
+        suppressed_rate_limited: status.counts.suppressed_rate_limited,
+        last_fired_at: status.last_fired_at,
+    }
+}
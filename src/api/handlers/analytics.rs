@@ -0,0 +1,304 @@
+//! Trader analytics endpoint.
+
+use alloy::primitives::Address;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::DateTime;
+use std::collections::HashSet;
+use tracing::instrument;
+
+use super::pools::resolve_pool;
+use super::price::block_range;
+use crate::analytics::fees::{pool_fee_apr, DEFAULT_APR_WINDOWS};
+use crate::analytics::sandwich::{detect_sandwiches, Side};
+use crate::analytics::{estimate_pnl, scale, SECONDS_PER_DAY};
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{
+    AnalyticsQuery, AnalyticsResponse, DailyTraders, FeeAprQuery, FeeAprResponse, FeeAprWindow,
+    SandwichInfo, SandwichQuery, SandwichResponse, TraderStats,
+};
+use crate::app_state::AppState;
+use crate::db::models::PoolScope;
+
+/// Most days of history per request.
+const MAX_DAYS: u32 = 90;
+
+/// Most traders per request.
+const MAX_TRADERS: u32 = 100;
+
+/// Longest fee APR window.
+const MAX_APR_DAYS: u32 = 365;
+
+/// Most sandwiches listed per request.
+const MAX_SANDWICHES: u32 = 100;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/analytics",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        AnalyticsQuery
+    ),
+    responses(
+        (status = 200, description = "Top traders, P&L estimates and daily unique traders", body = AnalyticsResponse),
+        (status = 400, description = "Invalid window, limit, address or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Analytics"
+)]
+/// Returns trader analytics for a pool over the last `days` days.
+///
+/// `from_block`/`to_block` further limit the swaps counted to those blocks.
+/// Traders are swap recipients, ranked by token1 volume. Each trader's P&L
+/// is estimated by valuing their net token flows at the latest pool price
+/// (see [`crate::analytics`]).
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_analytics(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AnalyticsResponse>, ApiError> {
+    let days = query.days.unwrap_or(7);
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(ApiError::BadRequest(format!(
+            "days must be between 1 and {MAX_DAYS}"
+        )));
+    }
+    let limit = query.limit.unwrap_or(10);
+    if !(1..=MAX_TRADERS).contains(&limit) {
+        return Err(ApiError::BadRequest(format!(
+            "limit must be between 1 and {MAX_TRADERS}"
+        )));
+    }
+    // Stored addresses are lowercase hex
+    let address = query
+        .address
+        .as_deref()
+        .map(|a| {
+            a.parse::<Address>()
+                .map(|a| format!("{a:?}"))
+                .map_err(|_| ApiError::BadRequest(format!("Invalid address: {a}")))
+        })
+        .transpose()?;
+    let blocks = block_range(query.from_block, query.to_block)?;
+
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let decimals = (
+        u8::try_from(pool.token0_decimals).unwrap_or(18),
+        u8::try_from(pool.token1_decimals).unwrap_or(18),
+    );
+    let since = chrono::Utc::now().timestamp() - i64::from(days) * SECONDS_PER_DAY;
+    let mark_price = state
+        .reader
+        .get_latest_price(pool.id)
+        .await?
+        .map(|p| p.price);
+
+    let top_traders = state
+        .reader
+        .get_trader_totals(pool.id, since, blocks, address.as_deref(), i64::from(limit))
+        .await?
+        .into_iter()
+        .map(|totals| {
+            let pnl = estimate_pnl(&totals, decimals, mark_price);
+            TraderStats {
+                address: totals.address,
+                swaps: u64::try_from(totals.swaps).unwrap_or(0),
+                volume: pnl.volume,
+                net_token0: pnl.net_token0,
+                net_token1: pnl.net_token1,
+                pnl_estimate: pnl.pnl,
+            }
+        })
+        .collect();
+
+    let daily = state
+        .reader
+        .get_daily_trader_counts(pool.id, since, blocks)
+        .await?
+        .into_iter()
+        .map(|day| DailyTraders {
+            date: DateTime::from_timestamp(day.day_start, 0)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            unique_traders: u64::try_from(day.unique_traders).unwrap_or(0),
+            swaps: u64::try_from(day.swaps).unwrap_or(0),
+            volume: scale(day.volume1, decimals.1),
+        })
+        .collect();
+
+    Ok(Json(AnalyticsResponse {
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+        days,
+        since,
+        mark_price,
+        top_traders,
+        daily,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/apr",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        FeeAprQuery
+    ),
+    responses(
+        (status = 200, description = "Estimated LP fee APR per trailing window", body = FeeAprResponse),
+        (status = 400, description = "Invalid window", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Analytics"
+)]
+/// Returns a pool's estimated LP fee APR over trailing windows.
+///
+/// Fees are the pool's swap fee on every confirmed swap's inputs, valued at
+/// the latest price, and annualized against the average liquidity (see
+/// [`crate::analytics::fees`]).
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_fee_apr(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<FeeAprQuery>,
+) -> Result<Json<FeeAprResponse>, ApiError> {
+    let windows = match query.days {
+        Some(days) if (1..=MAX_APR_DAYS).contains(&days) => vec![days],
+        Some(_) => {
+            return Err(ApiError::BadRequest(format!(
+                "days must be between 1 and {MAX_APR_DAYS}"
+            )))
+        }
+        None => DEFAULT_APR_WINDOWS.to_vec(),
+    };
+
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let now = chrono::Utc::now().timestamp();
+    let estimates = pool_fee_apr(&state.reader, &pool, &windows, now).await?;
+    let price = state
+        .reader
+        .get_latest_price(pool.id)
+        .await?
+        .map(|p| p.price);
+
+    Ok(Json(FeeAprResponse {
+        fee_bps: pool.dex_protocol().fee_bps(),
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+        price,
+        windows: estimates
+            .into_iter()
+            .map(|e| FeeAprWindow {
+                days: e.days,
+                since: e.since,
+                covered_secs: e.covered_secs,
+                swaps: e.swaps,
+                volume: e.volume,
+                fees: e.fees,
+                avg_liquidity: e.avg_liquidity,
+                apr: e.apr,
+            })
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/sandwiches",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        SandwichQuery
+    ),
+    responses(
+        (status = 200, description = "Sandwich counts, attacker profit and the most recent sandwiches", body = SandwichResponse),
+        (status = 400, description = "Invalid window, limit or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Analytics"
+)]
+/// Returns likely sandwich attacks on a pool over the last `days` days,
+/// optionally only within `from_block`/`to_block`.
+///
+/// Each block's confirmed swaps are replayed in log order and a buy-victim-
+/// sell pattern by one recipient is flagged (see
+/// [`crate::analytics::sandwich`]). Profits in token0 are valued at the
+/// latest pool price.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_sandwiches(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<SandwichQuery>,
+) -> Result<Json<SandwichResponse>, ApiError> {
+    let days = query.days.unwrap_or(7);
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(ApiError::BadRequest(format!(
+            "days must be between 1 and {MAX_DAYS}"
+        )));
+    }
+    let limit = query.limit.unwrap_or(20);
+    if !(1..=MAX_SANDWICHES).contains(&limit) {
+        return Err(ApiError::BadRequest(format!(
+            "limit must be between 1 and {MAX_SANDWICHES}"
+        )));
+    }
+    let blocks = block_range(query.from_block, query.to_block)?;
+
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let decimals = (
+        u8::try_from(pool.token0_decimals).unwrap_or(18),
+        u8::try_from(pool.token1_decimals).unwrap_or(18),
+    );
+    let since = chrono::Utc::now().timestamp() - i64::from(days) * SECONDS_PER_DAY;
+    let mark_price = state
+        .reader
+        .get_latest_price(pool.id)
+        .await?
+        .map(|p| p.price);
+
+    let swaps = state
+        .reader
+        .get_sandwich_candidates(pool.id, since, blocks)
+        .await?;
+    let sandwiches = detect_sandwiches(&swaps);
+
+    // Profit is in the token the front-run paid
+    let profit = |side: Side, raw: f64| match side {
+        Side::BuyToken0 => Some(scale(raw, decimals.1)),
+        Side::BuyToken1 => mark_price.map(|price| scale(raw, decimals.0) * price),
+    };
+    let total_profit = sandwiches
+        .iter()
+        .map(|s| profit(s.side, s.profit))
+        .sum::<Option<f64>>();
+    let attackers: HashSet<&str> = sandwiches.iter().map(|s| s.attacker.as_str()).collect();
+
+    Ok(Json(SandwichResponse {
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+        days,
+        since,
+        mark_price,
+        sandwiches: sandwiches.len() as u64,
+        victims: sandwiches.iter().map(|s| s.victims.len() as u64).sum(),
+        attackers: attackers.len() as u64,
+        profit: total_profit,
+        recent: sandwiches
+            .iter()
+            .rev()
+            .take(limit as usize)
+            .map(|s| SandwichInfo {
+                block_number: s.block_number,
+                timestamp: s.block_timestamp,
+                attacker: s.attacker.clone(),
+                side: s.side.as_str().to_string(),
+                front_run_tx: s.front_run.to_string(),
+                victim_txs: s.victims.iter().map(ToString::to_string).collect(),
+                back_run_tx: s.back_run.to_string(),
+                profit: profit(s.side, s.profit),
+            })
+            .collect(),
+    }))
+}
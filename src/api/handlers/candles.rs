@@ -0,0 +1,264 @@
+//! Candle endpoints.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Response,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tracing::instrument;
+use utoipa::IntoParams;
+
+use super::price::block_range;
+use crate::api::conditional::Validators;
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{CandleInfo, CandlesResponse};
+use crate::app_state::AppState;
+use crate::candles::{CandleFill, FilledCandle};
+use crate::db::models::{BlockRange, PoolRecord, PoolScope};
+use crate::pricing::QuoteDirection;
+
+/// Maximum candles per request (24 hours of 1-minute candles).
+const MAX_CANDLES: u32 = 1440;
+
+/// Query parameters for recent candles.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CandlesQuery {
+    /// Candle width: 1m (default) or 5m
+    #[serde(default = "default_interval")]
+    #[param(default = "1m")]
+    interval: String,
+    /// Number of most recent candles (max 1440)
+    #[serde(default = "default_limit")]
+    #[param(default = 60)]
+    limit: u32,
+    /// Report buckets without prices as flat candles at the previous close
+    /// (`previous`) or with null prices (`null`); omitted by default
+    #[serde(default)]
+    fill: Option<String>,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    to_block: Option<u64>,
+    /// Quote the prices in the direction opposite to the pool's default
+    #[serde(default)]
+    invert: bool,
+}
+
+fn default_interval() -> String {
+    "1m".to_string()
+}
+
+const fn default_limit() -> u32 {
+    60
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/candles/{pool}",
+    params(
+        ("pool" = String, Path, description = "Pool name"),
+        CandlesQuery
+    ),
+    responses(
+        (status = 200, description = "Recent candles", body = CandlesResponse),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag or `If-Modified-Since` date"),
+        (status = 400, description = "Invalid interval, limit, fill or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Statistics"
+)]
+/// Returns the most recent 1m or 5m candles from the in-memory candle book.
+///
+/// With `from_block`/`to_block`, the candles are instead aggregated from the
+/// stored prices within those blocks: the most recent `limit` of the range,
+/// oldest first. With `fill`, which a block range doesn't accept,
+/// every bucket up to the current one is returned, so a quiet pool still gets
+/// a continuous series. With `invert`, prices are quoted in
+/// the direction opposite to the pool's default, high and low swapping places. Responses carry an `ETag` and `Last-Modified`; polling clients that send
+/// them back get `304 Not Modified` until a new price arrives.
+#[instrument(skip(state, headers, scope), fields(pool = %pool_name))]
+pub async fn get_candles(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(pool_name): Path<String>,
+    Query(query): Query<CandlesQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let pool_name_normalized = pool_name.replace('-', "/");
+
+    let interval_secs: i64 = match query.interval.as_str() {
+        "1m" => 60,
+        "5m" => 300,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Invalid interval. Use: 1m or 5m".to_string(),
+            ))
+        }
+    };
+    if query.limit == 0 || query.limit > MAX_CANDLES {
+        return Err(ApiError::BadRequest(format!(
+            "limit must be between 1 and {MAX_CANDLES}"
+        )));
+    }
+    let fill = query
+        .fill
+        .as_deref()
+        .map(str::parse::<CandleFill>)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        .unwrap_or_default();
+    let blocks = block_range(query.from_block, query.to_block)?;
+    if !blocks.is_all() && fill != CandleFill::Omit {
+        return Err(ApiError::BadRequest(
+            "fill is not supported with from_block/to_block".to_string(),
+        ));
+    }
+
+    let pool = state
+        .reader
+        .get_pool_by_name(&pool_name_normalized)
+        .await?
+        .filter(|pool| scope.allows(pool.id))
+        .ok_or_else(|| ApiError::NotFound("Pool not found".to_string()))?;
+
+    if !blocks.is_all() {
+        return stored_candles(
+            &state,
+            &pool,
+            pool_name_normalized,
+            interval_secs,
+            &query,
+            blocks,
+            &headers,
+        )
+        .await;
+    }
+    let direction = pool.quote_direction().inverted(query.invert);
+
+    // Candles only change when a price is recorded or old ones are trimmed,
+    // which moves the first bucket, and filled ones also when a new bucket
+    // starts. The block is read first so a price recorded in between can
+    // only make the tag older than the candles.
+    let last_block = state.candles.last_block(pool.id).unwrap_or(0);
+    let last_timestamp = state.candles.last_timestamp(pool.id);
+    let now = Utc::now().timestamp();
+    let current_bucket = match fill {
+        CandleFill::Omit => None,
+        CandleFill::Previous | CandleFill::Null => {
+            Some(now.div_euclid(interval_secs) * interval_secs)
+        }
+    };
+    // Empty until the poll loop has warmed up this pool
+    let rows = state
+        .candles
+        .filled_candles(pool.id, interval_secs, query.limit as usize, fill, now)
+        .unwrap_or_default();
+    let first_bucket = rows.first().map_or(0, |c| c.bucket_start);
+
+    let validators = Validators::new(&[
+        &pool.id,
+        &interval_secs,
+        &query.limit,
+        &fill,
+        &last_block,
+        &first_bucket,
+        &current_bucket.unwrap_or(0),
+        &direction,
+    ])
+    .with_last_modified(last_timestamp.max(current_bucket));
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let candles = rows
+        .into_iter()
+        .map(|c| candle_info(c, direction))
+        .collect();
+
+    Ok(validators.with_body(Json(CandlesResponse {
+        pool: pool_name_normalized,
+        interval_secs: interval_secs.unsigned_abs(),
+        quote_direction: direction.to_string(),
+        candles,
+    })))
+}
+
+/// Serves candles within a block range, aggregated from the stored prices.
+async fn stored_candles(
+    state: &AppState,
+    pool: &PoolRecord,
+    pool_name: String,
+    interval_secs: i64,
+    query: &CandlesQuery,
+    blocks: BlockRange,
+    headers: &HeaderMap,
+) -> Result<Response, ApiError> {
+    let direction = pool.quote_direction().inverted(query.invert);
+    let version = state
+        .reader
+        .get_price_history_version(pool.id, None, None, blocks)
+        .await?;
+    let (from_block, to_block) = blocks.bounds();
+    let validators = Validators::new(&[
+        &pool.id,
+        &interval_secs,
+        &query.limit,
+        &from_block,
+        &to_block,
+        &version.count,
+        &version.max_block.unwrap_or(0),
+        &version.max_id.unwrap_or(0),
+        &direction,
+    ])
+    .with_last_modified(version.last_timestamp);
+    if validators.is_fresh(headers) {
+        return Ok(validators.not_modified());
+    }
+
+    let candles = state
+        .reader
+        .get_candles(
+            pool.id,
+            interval_secs,
+            None,
+            None,
+            blocks,
+            i64::from(query.limit),
+            pool.spike_filter(),
+        )
+        .await?
+        .into_iter()
+        .map(|c| candle_info(FilledCandle::from(&c), direction))
+        .collect();
+
+    Ok(validators.with_body(Json(CandlesResponse {
+        pool: pool_name,
+        interval_secs: interval_secs.unsigned_abs(),
+        quote_direction: direction.to_string(),
+        candles,
+    })))
+}
+
+/// Converts a candle to its response form, quoted in `direction`.
+fn candle_info(c: FilledCandle, direction: QuoteDirection) -> CandleInfo {
+    // The inverse of the lowest price is the highest inverted price
+    let (high, low) = if direction.is_inverse() {
+        (c.low, c.high)
+    } else {
+        (c.high, c.low)
+    };
+    CandleInfo {
+        bucket_start: DateTime::from_timestamp(c.bucket_start, 0).unwrap_or_else(Utc::now),
+        open: c.open.map(|p| direction.apply(p)),
+        high: high.map(|p| direction.apply(p)),
+        low: low.map(|p| direction.apply(p)),
+        close: c.close.map(|p| direction.apply(p)),
+        samples: u64::try_from(c.samples).unwrap_or(0),
+    }
+}
@@ -0,0 +1,118 @@
+//! Composite price index endpoints.
+
+use axum::extract::{OriginalUri, Query, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+
+use crate::api::middleware::auth::require_unscoped;
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{CompositeConstituentInfo, CompositePriceInfo, PageQuery, Paginated};
+use crate::app_state::AppState;
+use crate::composite::Constituent;
+use crate::db::models::{CompositePriceRow, PoolScope};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/composite",
+    responses(
+        (status = 200, description = "Latest composite price", body = CompositePriceInfo),
+        (status = 403, description = "API key is limited to some pools", body = ErrorResponse),
+        (status = 404, description = "No composite price recorded", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Returns the latest value of the composite price index.
+///
+/// The index is recorded while the API server runs with
+/// `COMPOSITE_INDEX_POOLS` set: the liquidity-weighted average of the listed
+/// pools' latest prices.
+#[instrument(skip(state, scope))]
+pub async fn get_composite_price(
+    State(state): State<AppState>,
+    scope: PoolScope,
+) -> Result<Json<CompositePriceInfo>, ApiError> {
+    require_unscoped(&scope, "The composite index")?;
+    let row = state
+        .reader
+        .get_latest_composite_price()
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No composite price recorded".to_string()))?;
+
+    Ok(Json(composite_info(row)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/composite/history",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Page of composite prices, newest first", body = PaginatedCompositePrices),
+        (status = 400, description = "Invalid limit", body = ErrorResponse),
+        (status = 403, description = "API key is limited to some pools", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Returns a page of recorded composite price index values, newest first.
+#[instrument(skip(state, scope))]
+pub async fn get_composite_history(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Query(query): Query<PageQuery>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Paginated<CompositePriceInfo>, ApiError> {
+    require_unscoped(&scope, "The composite index")?;
+    if query.limit == 0 || query.limit > 1000 {
+        return Err(ApiError::BadRequest(
+            "limit must be between 1 and 1000".to_string(),
+        ));
+    }
+
+    let page = state
+        .reader
+        .get_composite_prices_page(
+            i64::from(query.limit),
+            i64::try_from(query.offset).unwrap_or(i64::MAX),
+        )
+        .await?;
+
+    let data = page
+        .items
+        .into_iter()
+        .map(composite_info)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Paginated::from_offset(
+        data,
+        page.total,
+        query.limit,
+        query.offset,
+        &uri,
+    ))
+}
+
+fn composite_info(row: CompositePriceRow) -> Result<CompositePriceInfo, ApiError> {
+    let constituents: Vec<Constituent> = serde_json::from_str(&row.constituents)
+        .map_err(|e| ApiError::InternalError(format!("Invalid composite constituents: {e}")))?;
+
+    Ok(CompositePriceInfo {
+        price: row.price,
+        liquidity: row.liquidity,
+        block_number: u64::try_from(row.block_number).unwrap_or_default(),
+        timestamp: DateTime::from_timestamp(row.block_timestamp, 0).unwrap_or_else(Utc::now),
+        constituents: constituents
+            .into_iter()
+            .map(|c| CompositeConstituentInfo {
+                pool_id: c.pool_id,
+                block_number: c.block_number,
+                price: c.price,
+                liquidity: c.liquidity,
+                weight: if row.liquidity > 0.0 {
+                    c.liquidity / row.liquidity
+                } else {
+                    0.0
+                },
+            })
+            .collect(),
+    })
+}
@@ -1,22 +1,37 @@
 //! Event listing endpoints.
 
 use axum::{
-    extract::{Path, Query, State},
+    extract::{OriginalUri, Path, Query, State},
     Json,
 };
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use tracing::instrument;
+use utoipa::IntoParams;
 
+use crate::api::handlers::pools::resolve_pool;
+use crate::api::handlers::price::block_range;
 use crate::api::middleware::error::ApiError;
-use crate::api::models::{RecentEventResponse, SyncEventInfo};
+use crate::api::models::{
+    EventPageQuery, Paginated, RecentEventResponse, SortOrder, SyncEventInfo,
+};
 use crate::app_state::AppState;
+use crate::db::models::{EventCursor, PoolScope, SyncEventRow};
 
 /// Query parameters for recent events.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct EventsQuery {
+    /// Number of events to return (1-1000)
     #[serde(default = "default_limit")]
+    #[param(default = 50, minimum = 1, maximum = 1000)]
     limit: u32,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    to_block: Option<u64>,
 }
 
 fn default_limit() -> u32 {
@@ -27,17 +42,21 @@ fn default_limit() -> u32 {
     get,
     path = "/api/v1/events/{pool}",
     params(
-        ("pool" = String, Path, description = "Pool name")
+        ("pool" = String, Path, description = "Pool name"),
+        EventsQuery
     ),
     responses(
-        (status = 200, description = "Recent events", body = RecentEventResponse)
+        (status = 200, description = "Recent events", body = RecentEventResponse),
+        (status = 400, description = "Invalid limit or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
     ),
     tag = "Events"
 )]
-/// Returns recent sync events for a pool.
-#[instrument(skip(state), fields(pool = %pool_name))]
+/// Returns recent sync events for a pool, optionally within a block range.
+#[instrument(skip(state, scope), fields(pool = %pool_name))]
 pub async fn get_recent_events(
     State(state): State<AppState>,
+    scope: PoolScope,
     Path(pool_name): Path<String>,
     Query(query): Query<EventsQuery>,
 ) -> Result<Json<RecentEventResponse>, ApiError> {
@@ -48,31 +67,119 @@ pub async fn get_recent_events(
             "limit must be between 1 and 1000".to_string(),
         ));
     }
+    let blocks = block_range(query.from_block, query.to_block)?;
 
     let pool = state
-        .repository
+        .reader
         .get_pool_by_name(&pool_name_normalized)
         .await?
+        .filter(|pool| scope.allows(pool.id))
         .ok_or_else(|| ApiError::NotFound("Pool not found".to_string()))?;
 
     let events = state
-        .repository
-        .get_recent_events(pool.id, query.limit as i64)
+        .reader
+        .get_recent_events(pool.id, blocks, query.limit as i64)
         .await?;
 
-    let items = events
-        .into_iter()
-        .map(|e| SyncEventInfo {
-            block_number: e.block_number as u64,
-            timestamp: DateTime::from_timestamp(e.block_timestamp, 0).unwrap_or_else(Utc::now),
-            tx_hash: e.tx_hash,
-            reserve0: e.reserve0,
-            reserve1: e.reserve1,
-        })
-        .collect();
+    let items = events.into_iter().map(event_info).collect();
 
     Ok(Json(RecentEventResponse {
         pool: pool_name_normalized,
         events: items,
     }))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/events",
+    params(
+        ("id" = String, Path, description = "Pool ID, address, or name (e.g., WETH-USDT)"),
+        EventPageQuery
+    ),
+    responses(
+        (status = 200, description = "Page of sync events", body = PaginatedSyncEvents),
+        (status = 400, description = "Invalid cursor, limit or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Events"
+)]
+/// Returns raw sync events for a pool using keyset (cursor) pagination.
+///
+/// Pass `pagination.next_cursor` from one response as `cursor`, or follow
+/// `pagination.next`, to fetch the next page. Unlike offset pagination, the cost of a page does not grow with
+/// its depth, so clients can walk the full event history. `from_block` and
+/// `to_block` bound the walk; `total` then counts the events within them.
+#[instrument(skip(state, scope), fields(pool = %pool_id))]
+pub async fn list_pool_events(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(pool_id): Path<String>,
+    Query(query): Query<EventPageQuery>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Paginated<SyncEventInfo>, ApiError> {
+    if query.limit == 0 || query.limit > 1000 {
+        return Err(ApiError::BadRequest(
+            "limit must be between 1 and 1000".to_string(),
+        ));
+    }
+
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(str::parse::<EventCursor>)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let blocks = block_range(query.from_block, query.to_block)?;
+
+    let pool = resolve_pool(&state, &scope, &pool_id).await?;
+
+    // Fetch one extra row to learn whether another page exists
+    let limit = usize::try_from(query.limit).unwrap_or(1000);
+    let mut page = state
+        .reader
+        .get_events_page(
+            pool.id,
+            blocks,
+            cursor,
+            i64::from(query.limit) + 1,
+            query.order == SortOrder::Desc,
+        )
+        .await?;
+
+    let next_cursor = if page.items.len() > limit {
+        page.items.truncate(limit);
+        page.items.last().map(|e| row_cursor(e).to_string())
+    } else {
+        None
+    };
+
+    let data = page.items.into_iter().map(event_info).collect();
+
+    Ok(Paginated::from_cursor(
+        data,
+        page.total,
+        query.limit,
+        cursor.map(|c| c.to_string()),
+        next_cursor,
+        &uri,
+    ))
+}
+
+fn row_cursor(row: &SyncEventRow) -> EventCursor {
+    EventCursor::new(
+        u64::try_from(row.block_number).unwrap_or_default(),
+        u32::try_from(row.log_index).unwrap_or_default(),
+    )
+}
+
+fn event_info(e: SyncEventRow) -> SyncEventInfo {
+    SyncEventInfo {
+        id: e.event_id,
+        block_number: u64::try_from(e.block_number).unwrap_or_default(),
+        timestamp: DateTime::from_timestamp(e.block_timestamp, 0).unwrap_or_else(Utc::now),
+        tx_hash: e.tx_hash.to_string(),
+        log_index: u32::try_from(e.log_index).unwrap_or_default(),
+        reserve0: e.reserve0,
+        reserve1: e.reserve1,
+    }
+}
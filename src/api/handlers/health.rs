@@ -1,41 +1,58 @@
-//! Health check endpoint.
+//! Health check endpoints.
+//!
+//! `/health` is a readiness check: besides database and WebSocket status it
+//! compares the last indexed block with the chain head and answers 503 when
+//! the indexer has fallen more than `HEALTH_MAX_LAG_BLOCKS` behind, or the
+//! database is unreachable, so load balancers take the node out of rotation.
+//! It also reports what the RPC provider supports, as probed at startup.
+//! `/health/live` only reports that the process is up.
 
-use axum::{extract::State, Json};
-use std::time::SystemTime;
-use tracing::instrument;
+use alloy::providers::Provider as _;
+use alloy::rpc::types::{BlockNumberOrTag, BlockTransactionsKind};
+use axum::{extract::State, http::StatusCode, Json};
+use std::time::{Duration, SystemTime};
+use tracing::{instrument, warn};
 
 use crate::api::middleware::error::ApiError;
-use crate::api::models::{HealthResponse, HealthStatus};
+use crate::api::models::{HealthResponse, HealthStatus, LivenessResponse, RpcCapabilitiesInfo};
 use crate::app_state::AppState;
+use crate::rpc::Provider;
+
+/// How long the chain head lookup may take before the RPC counts as down.
+pub(crate) const CHAIN_HEAD_TIMEOUT: Duration = Duration::from_secs(3);
 
 #[utoipa::path(
     get,
     path = "/api/v1/health",
     responses(
-        (status = 200, description = "Service is healthy", body = HealthResponse)
+        (status = 200, description = "Service is ready", body = HealthResponse),
+        (status = 503, description = "Database unreachable or indexer lagging behind the chain head", body = HealthResponse)
     ),
     tag = "Health"
 )]
-/// Returns service health information.
+/// Returns service health and sync-lag information.
 #[instrument(skip(state))]
-pub async fn health_check(State(state): State<AppState>) -> Result<Json<HealthResponse>, ApiError> {
-    let uptime = SystemTime::now()
-        .duration_since(state.start_time)
-        .unwrap_or_default()
-        .as_secs();
+pub async fn health_check(
+    State(state): State<AppState>,
+) -> Result<(StatusCode, Json<HealthResponse>), ApiError> {
+    let uptime = uptime_secs(&state);
 
-    let indexed_block = state
-        .repository
-        .get_state(1)
-        .await?
-        .map(|s| s.last_indexed_block as u64)
-        .unwrap_or(0);
-
-    let db_status = match state.repository.health_check().await {
+    let db_status = match state.reader.health_check().await {
         Ok(()) => HealthStatus::Healthy,
         Err(_) => HealthStatus::Unhealthy,
     };
 
+    let indexer_state = match db_status {
+        HealthStatus::Healthy => state.reader.get_state(1).await?,
+        _ => None,
+    };
+    let indexed_block = indexer_state
+        .as_ref()
+        .map_or(0, |s| s.last_indexed_block as u64);
+    let reorg_count = indexer_state
+        .as_ref()
+        .map_or(0, |s| u64::try_from(s.reorg_count).unwrap_or(0));
+
     let ws_status = if state
         .ws_connected
         .load(std::sync::atomic::Ordering::Relaxed)
@@ -45,19 +62,166 @@ pub async fn health_check(State(state): State<AppState>) -> Result<Json<HealthRe
         HealthStatus::Degraded
     };
 
-    let status = match (&db_status, &ws_status) {
-        (HealthStatus::Healthy, HealthStatus::Healthy) => HealthStatus::Healthy,
-        (HealthStatus::Healthy, _) => HealthStatus::Degraded,
-        (_, HealthStatus::Healthy) => HealthStatus::Degraded,
-        _ => HealthStatus::Unhealthy,
+    let (rpc_status, lag) = match &state.rpc {
+        Some(provider) => match chain_lag(provider, indexed_block).await {
+            Ok(lag) => ("healthy", Some(lag)),
+            Err(e) => {
+                warn!(error = %e, "Chain head lookup failed");
+                ("unhealthy", None)
+            }
+        },
+        None => ("disabled", None),
     };
+    let lag_blocks = lag.map(|l| l.blocks);
+
+    let (status, code) = assess(
+        &db_status,
+        &ws_status,
+        rpc_status != "unhealthy",
+        lag_blocks,
+        state.health_max_lag_blocks,
+    );
 
-    Ok(Json(HealthResponse {
-        status,
+    Ok((
+        code,
+        Json(HealthResponse {
+            status,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_seconds: uptime,
+            indexed_block,
+            chain_head: lag.map(|l| l.head),
+            lag_blocks,
+            lag_seconds: lag.and_then(|l| l.seconds),
+            max_lag_blocks: state.health_max_lag_blocks,
+            reorg_count,
+            database_status: format!("{:?}", db_status).to_lowercase(),
+            websocket_status: format!("{:?}", ws_status).to_lowercase(),
+            rpc_status: rpc_status.to_string(),
+            price_cache: state.prices.stats().into(),
+            lag_watchdog: state.lag_watchdog.as_ref().map(|w| w.stats().into()),
+            rpc_capabilities: state
+                .provider_capabilities
+                .as_deref()
+                .map(RpcCapabilitiesInfo::from),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/health/live",
+    responses(
+        (status = 200, description = "Process is up", body = LivenessResponse)
+    ),
+    tag = "Health"
+)]
+/// Returns 200 as long as the server is running, regardless of sync state.
+#[instrument(skip(state))]
+pub async fn liveness(State(state): State<AppState>) -> Json<LivenessResponse> {
+    Json(LivenessResponse {
+        status: HealthStatus::Healthy,
         version: env!("CARGO_PKG_VERSION").to_string(),
-        uptime_seconds: uptime,
-        indexed_block,
-        database_status: format!("{:?}", db_status).to_lowercase(),
-        websocket_status: format!("{:?}", ws_status).to_lowercase(),
-    }))
+        uptime_seconds: uptime_secs(&state),
+    })
+}
+
+pub(crate) fn uptime_secs(state: &AppState) -> u64 {
+    SystemTime::now()
+        .duration_since(state.start_time)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Distance between the last indexed block and the chain head.
+#[derive(Debug, Clone, Copy)]
+struct ChainLag {
+    head: u64,
+    blocks: u64,
+    /// Seconds between the two blocks' timestamps; unknown before the
+    /// first block is indexed.
+    seconds: Option<u64>,
+}
+
+async fn chain_lag(provider: &Provider, indexed_block: u64) -> Result<ChainLag, String> {
+    let lookup = async {
+        let head = provider
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("latest block not found")?;
+        let head_number = head.header.number;
+
+        let seconds = if indexed_block == 0 {
+            None
+        } else {
+            provider
+                .get_block_by_number(indexed_block.into(), BlockTransactionsKind::Hashes)
+                .await
+                .map_err(|e| e.to_string())?
+                .map(|b| head.header.timestamp.saturating_sub(b.header.timestamp))
+        };
+
+        Ok(ChainLag {
+            head: head_number,
+            blocks: head_number.saturating_sub(indexed_block),
+            seconds,
+        })
+    };
+
+    tokio::time::timeout(CHAIN_HEAD_TIMEOUT, lookup)
+        .await
+        .map_err(|_| format!("timed out after {}s", CHAIN_HEAD_TIMEOUT.as_secs()))?
+}
+
+/// Combines component statuses into the overall status and HTTP code.
+///
+/// Only an unreachable database or a lag above `max_lag_blocks` makes the
+/// node unready; a failed chain head lookup or missing WebSocket degrades it.
+fn assess(
+    db_status: &HealthStatus,
+    ws_status: &HealthStatus,
+    rpc_ok: bool,
+    lag_blocks: Option<u64>,
+    max_lag_blocks: u64,
+) -> (HealthStatus, StatusCode) {
+    let lagging = lag_blocks.is_some_and(|lag| lag > max_lag_blocks);
+    if !matches!(db_status, HealthStatus::Healthy) || lagging {
+        return (HealthStatus::Unhealthy, StatusCode::SERVICE_UNAVAILABLE);
+    }
+    if matches!(ws_status, HealthStatus::Healthy) && rpc_ok {
+        (HealthStatus::Healthy, StatusCode::OK)
+    } else {
+        (HealthStatus::Degraded, StatusCode::OK)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_unready_on_lag_or_database() {
+        let ok = HealthStatus::Healthy;
+
+        let (status, code) = assess(&ok, &ok, true, Some(10), 50);
+        assert!(matches!(status, HealthStatus::Healthy));
+        assert_eq!(code, StatusCode::OK);
+
+        // At the threshold is still fine, one past it is not
+        assert_eq!(assess(&ok, &ok, true, Some(50), 50).1, StatusCode::OK);
+        let (status, code) = assess(&ok, &ok, true, Some(51), 50);
+        assert!(matches!(status, HealthStatus::Unhealthy));
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+
+        let (_, code) = assess(&HealthStatus::Unhealthy, &ok, true, Some(0), 50);
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+
+        // Unknown lag (RPC down) or no WebSocket only degrades
+        let (status, code) = assess(&ok, &ok, false, None, 50);
+        assert!(matches!(status, HealthStatus::Degraded));
+        assert_eq!(code, StatusCode::OK);
+        let (status, code) = assess(&ok, &HealthStatus::Degraded, true, Some(0), 50);
+        assert!(matches!(status, HealthStatus::Degraded));
+        assert_eq!(code, StatusCode::OK);
+    }
 }
@@ -0,0 +1,82 @@
+//! Pool incident endpoints.
+
+use axum::extract::{OriginalUri, Path, Query, State};
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+
+use crate::api::handlers::pools::resolve_pool;
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{IncidentInfo, IncidentQuery, Paginated};
+use crate::app_state::AppState;
+use crate::db::models::{IncidentRow, PoolScope};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/incidents",
+    params(
+        ("id" = String, Path, description = "Pool ID, address, or name (e.g., USDC-USDT)"),
+        IncidentQuery
+    ),
+    responses(
+        (status = 200, description = "Page of incidents, newest first", body = PaginatedIncidents),
+        (status = 400, description = "Invalid limit", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Pools"
+)]
+/// Returns a page of a pool's recorded incidents, newest first.
+///
+/// Depeg incidents are recorded while the server runs with `DEPEG_BAND_BPS`
+/// set; an ongoing incident has no `ended_block`.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn list_incidents(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<IncidentQuery>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Paginated<IncidentInfo>, ApiError> {
+    if query.limit == 0 || query.limit > 1000 {
+        return Err(ApiError::BadRequest(
+            "limit must be between 1 and 1000".to_string(),
+        ));
+    }
+
+    let pool = resolve_pool(&state, &scope, &id).await?;
+
+    let page = state
+        .reader
+        .get_incidents_page(
+            pool.id,
+            query.kind.as_deref(),
+            i64::from(query.limit),
+            i64::try_from(query.offset).unwrap_or(i64::MAX),
+        )
+        .await?;
+
+    let data = page.items.into_iter().map(incident_info).collect();
+
+    Ok(Paginated::from_offset(
+        data,
+        page.total,
+        query.limit,
+        query.offset,
+        &uri,
+    ))
+}
+
+fn incident_info(i: IncidentRow) -> IncidentInfo {
+    let timestamp = |secs| DateTime::from_timestamp(secs, 0).unwrap_or_else(Utc::now);
+    IncidentInfo {
+        id: i.id,
+        kind: i.kind,
+        band_bps: u32::try_from(i.band_bps).unwrap_or_default(),
+        started_block: u64::try_from(i.started_block).unwrap_or_default(),
+        started_at: timestamp(i.started_at),
+        ended_block: i.ended_block.and_then(|b| u64::try_from(b).ok()),
+        ended_at: i.ended_at.map(timestamp),
+        peak_price: i.peak_price,
+        peak_deviation_bps: i.peak_deviation_bps,
+        ongoing: i.ended_block.is_none(),
+    }
+}
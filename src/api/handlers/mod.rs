@@ -1,8 +1,18 @@
 //! HTTP handlers for API endpoints.
 
+pub mod admin;
+pub mod alerts;
+pub mod analytics;
+pub mod candles;
+pub mod composite;
 pub mod events;
 pub mod health;
+pub mod incidents;
 pub mod pools;
 pub mod price;
+pub mod reorgs;
+pub mod route;
+pub mod signing;
 pub mod stats;
+pub mod status;
 pub mod stream;
@@ -1,32 +1,85 @@
-//! Pool listing endpoints.
+//! Pool listing, swap quote, historical reserve, impermanent loss, price path
+//! and time series endpoints.
 
-use axum::{extract::State, Json};
+use axum::{
+    extract::{OriginalUri, Path, Query, State},
+    Json,
+};
+use chrono::Utc;
 use tracing::instrument;
 
+use crate::adapters::{PoolType, PriceAdapter};
+use crate::api::handlers::price::block_range;
 use crate::api::middleware::error::ApiError;
-use crate::api::models::{PoolInfo, TokenInfo};
+use crate::api::models::{
+    BlockPricePath, ImpermanentLossQuery, ImpermanentLossResponse, PageQuery, Paginated, PoolInfo,
+    PoolTaskInfo, PositionAmounts, PricePathQuery, PricePathResponse, PricePathStep, QuoteQuery,
+    QuoteResponse, ReserveAmount, ReserveSource, ReservesAtQuery, ReservesAtResponse,
+    TimeseriesQuery, TimeseriesResponse, TokenInfo,
+};
 use crate::app_state::AppState;
+use crate::db::models::{PoolRecord, PoolScope, SyncEventRow, TimeseriesAgg};
+use crate::error::TrackerError;
+use crate::events::fetch_reserves_at;
+use crate::pricing;
+use crate::pricing::impermanent_loss::LpPosition;
+use crate::protocol::DexProtocol;
+use alloy::primitives::U256;
+
+/// Most blocks covered by one `/pools/{id}/price-path` request.
+pub const MAX_PRICE_PATH_BLOCKS: u64 = 100;
+
+/// Most buckets covered by one `/pools/{id}/timeseries` request.
+pub const MAX_TIMESERIES_POINTS: i64 = 10_000;
 
 #[utoipa::path(
     get,
     path = "/api/v1/pools",
+    params(PageQuery),
     responses(
-        (status = 200, description = "List of tracked pools", body = Vec<PoolInfo>)
+        (status = 200, description = "Page of tracked pools", body = PaginatedPools),
+        (status = 400, description = "Invalid limit", body = ErrorResponse)
     ),
     tag = "Pools"
 )]
-/// Returns the list of tracked pools.
-#[instrument(skip(state))]
-pub async fn list_pools(State(state): State<AppState>) -> Result<Json<Vec<PoolInfo>>, ApiError> {
-    let pools = state.repository.get_all_pools().await?;
+/// Returns a page of tracked pools in the order they were added; API keys
+/// scoped to some pools only see those.
+#[instrument(skip(state, scope))]
+pub async fn list_pools(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Query(query): Query<PageQuery>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Paginated<PoolInfo>, ApiError> {
+    if query.limit == 0 || query.limit > 1000 {
+        return Err(ApiError::BadRequest(
+            "limit must be between 1 and 1000".to_string(),
+        ));
+    }
+
+    let page = state
+        .reader
+        .get_pools_page(
+            &scope,
+            i64::from(query.limit),
+            i64::try_from(query.offset).unwrap_or(i64::MAX),
+        )
+        .await?;
 
-    let pool_infos = pools
+    let pool_infos = page
+        .items
         .into_iter()
         .map(|p| {
-            let name = p.name.unwrap_or_else(|| p.address.clone());
+            let spike_filter_bps = p.spike_filter();
+            let name = p.name.unwrap_or_else(|| p.address.to_string());
+            let protocol = p.protocol.parse::<DexProtocol>().unwrap_or_default();
+            let fee_bps = p.pool_type.parse::<PoolType>().map_or_else(
+                |_| protocol.fee_bps(),
+                |t| t.adapter(protocol.fee_bps()).fee_bps(),
+            );
             PoolInfo {
                 name,
-                address: p.address,
+                address: p.address.to_string(),
                 token0: TokenInfo {
                     symbol: p.token0_symbol.unwrap_or_else(|| "TOKEN0".to_string()),
                     address: p.token0_address,
@@ -37,11 +90,604 @@ pub async fn list_pools(State(state): State<AppState>) -> Result<Json<Vec<PoolIn
                     address: p.token1_address,
                     decimals: p.token1_decimals as u8,
                 },
+                protocol: protocol.to_string(),
+                fee_bps,
+                pool_type: p.pool_type,
+                quote_direction: p.quote_direction,
+                enabled: p.enabled,
+                start_block: p.start_block.and_then(|block| u64::try_from(block).ok()),
+                spike_filter_bps,
                 last_indexed_block: p.last_indexed_block as u64,
                 total_events: p.total_events as u64,
+                indexer: p.task_status.map(|status| PoolTaskInfo {
+                    status,
+                    restarts: p
+                        .task_restarts
+                        .and_then(|restarts| u64::try_from(restarts).ok())
+                        .unwrap_or_default(),
+                    last_error: p.task_error,
+                    updated_at: p.task_updated_at.unwrap_or_default(),
+                }),
             }
         })
         .collect();
 
-    Ok(Json(pool_infos))
+    Ok(Paginated::from_offset(
+        pool_infos,
+        page.total,
+        query.limit,
+        query.offset,
+        &uri,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/quote",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        QuoteQuery
+    ),
+    responses(
+        (status = 200, description = "Simulated swap", body = QuoteResponse),
+        (status = 400, description = "Invalid amount or token", body = ErrorResponse),
+        (status = 404, description = "Pool or price not found", body = ErrorResponse)
+    ),
+    tag = "Pools"
+)]
+/// Simulates selling `amount_in` of `token` into the pool at its latest reserves.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_quote(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<QuoteQuery>,
+) -> Result<Json<QuoteResponse>, ApiError> {
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let pool_name = pool
+        .name
+        .clone()
+        .unwrap_or_else(|| pool.address.to_string());
+    let symbol0 = pool.token0_symbol.as_deref().unwrap_or("TOKEN0");
+    let symbol1 = pool.token1_symbol.as_deref().unwrap_or("TOKEN1");
+
+    let matches = |symbol: &str, address: &str| {
+        query.token.eq_ignore_ascii_case(symbol) || query.token.eq_ignore_ascii_case(address)
+    };
+    let zero_for_one = if matches(symbol0, &pool.token0_address) {
+        true
+    } else if matches(symbol1, &pool.token1_address) {
+        false
+    } else {
+        return Err(ApiError::BadRequest(format!(
+            "token must be {symbol0} or {symbol1}"
+        )));
+    };
+
+    let price = state
+        .reader
+        .get_latest_price_record(pool.id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No price data for pool {pool_name}")))?;
+
+    let decimals0 = u8::try_from(pool.token0_decimals).unwrap_or(18);
+    let decimals1 = u8::try_from(pool.token1_decimals).unwrap_or(18);
+    let reserve0 = price.reserve0_raw.parse().map_err(|_| {
+        ApiError::InternalError(format!("Corrupt reserve0: {}", price.reserve0_raw))
+    })?;
+    let reserve1 = price.reserve1_raw.parse().map_err(|_| {
+        ApiError::InternalError(format!("Corrupt reserve1: {}", price.reserve1_raw))
+    })?;
+
+    let (reserve_in, reserve_out, decimals_in, decimals_out, token_in, token_out) = if zero_for_one
+    {
+        (reserve0, reserve1, decimals0, decimals1, symbol0, symbol1)
+    } else {
+        (reserve1, reserve0, decimals1, decimals0, symbol1, symbol0)
+    };
+
+    let amount_in = pricing::parse_token_amount(&query.amount_in, decimals_in)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let adapter = pool.price_adapter()?;
+    let fee_bps = adapter.fee_bps();
+    let quote = adapter
+        .quote(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            decimals_in,
+            decimals_out,
+        )
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    Ok(Json(QuoteResponse {
+        pool: pool_name,
+        block_number: u64::try_from(price.block_number).unwrap_or(0),
+        token_in: token_in.to_string(),
+        token_out: token_out.to_string(),
+        amount_in: pricing::format_token_amount(amount_in, decimals_in),
+        amount_out: pricing::format_token_amount(quote.amount_out, decimals_out),
+        spot_price: quote.spot_price,
+        execution_price: quote.execution_price,
+        price_impact_pct: quote.price_impact_pct,
+        fee_bps,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/reserves/at",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        ReservesAtQuery
+    ),
+    responses(
+        (status = 200, description = "Reserves as of the block", body = ReservesAtResponse),
+        (status = 404, description = "Pool not found, or no data for the block", body = ErrorResponse),
+        (status = 502, description = "Archive node rejected the call", body = ErrorResponse),
+        (status = 503, description = "Archive node call failed, retry later", body = ErrorResponse)
+    ),
+    tag = "Pools"
+)]
+/// Returns a pool's reserves as of a block.
+///
+/// Served from the last indexed Sync event at or before the block. Blocks
+/// past the last indexed block, or before the first indexed event, fall back
+/// to a `getReserves()` call at that block when an RPC provider is configured,
+/// sent to `RPC_ARCHIVE_URL` for blocks whose state `RPC_URL` has pruned.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_reserves_at(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<ReservesAtQuery>,
+) -> Result<Json<ReservesAtResponse>, ApiError> {
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let pool_name = pool
+        .name
+        .clone()
+        .unwrap_or_else(|| pool.address.to_string());
+
+    let last_indexed = state
+        .reader
+        .get_state(pool.id)
+        .await?
+        .map_or(0, |s| u64::try_from(s.last_indexed_block).unwrap_or(0));
+    let indexed = if query.block <= last_indexed {
+        state.reader.get_sync_event_at(pool.id, query.block).await?
+    } else {
+        None
+    };
+
+    let (source, event_block, tx_hash, reserve0, reserve1) = if let Some(event) = indexed {
+        let parse = |raw: &str| {
+            raw.parse::<U256>()
+                .map_err(|_| ApiError::InternalError(format!("Corrupt reserve: {raw}")))
+        };
+        (
+            ReserveSource::Indexed,
+            u64::try_from(event.block_number).ok(),
+            Some(event.tx_hash.to_string()),
+            parse(&event.reserve0)?,
+            parse(&event.reserve1)?,
+        )
+    } else {
+        let not_found = || {
+            ApiError::NotFound(format!(
+                "No indexed reserves for pool {pool_name} at block {}",
+                query.block
+            ))
+        };
+        let router = state.state_router().ok_or_else(not_found)?;
+        let provider = router.provider_at(query.block).await.map_err(|e| match e {
+            TrackerError::ConfigError { .. } => ApiError::NotFound(format!(
+                "No indexed reserves for pool {pool_name} at block {}, and the RPC node \
+                     keeps no state that old",
+                query.block
+            )),
+            e => e.into(),
+        })?;
+        let (reserve0, reserve1) =
+            fetch_reserves_at(provider, pool.address.get(), query.block).await?;
+        (ReserveSource::Archive, None, None, reserve0, reserve1)
+    };
+
+    let decimals0 = u8::try_from(pool.token0_decimals).unwrap_or(18);
+    let decimals1 = u8::try_from(pool.token1_decimals).unwrap_or(18);
+    let amount = |symbol: Option<&str>, raw: U256, decimals: u8| ReserveAmount {
+        symbol: symbol.unwrap_or_default().to_string(),
+        raw: raw.to_string(),
+        amount: pricing::format_token_amount(raw, decimals),
+    };
+
+    Ok(Json(ReservesAtResponse {
+        pool: pool_name,
+        block: query.block,
+        event_block,
+        tx_hash,
+        source,
+        reserve0: amount(pool.token0_symbol.as_deref(), reserve0, decimals0),
+        reserve1: amount(pool.token1_symbol.as_deref(), reserve1, decimals1),
+        price_exact: pool
+            .price_adapter()?
+            .price_exact(reserve0, reserve1, decimals0, decimals1)
+            .ok()
+            .map(pricing::format_exact_price),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/impermanent-loss",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        ImpermanentLossQuery
+    ),
+    responses(
+        (status = 200, description = "Position valued at the latest price", body = ImpermanentLossResponse),
+        (status = 400, description = "Invalid position, or not a constant-product pool", body = ErrorResponse),
+        (status = 404, description = "Pool not found, or no price for the entry block", body = ErrorResponse)
+    ),
+    tag = "Pools"
+)]
+/// Values an LP position at the pool's latest price and reports its
+/// impermanent loss against holding the deposited tokens.
+///
+/// The entry price comes from the indexed price history at `entry_block`, or
+/// from the supplied entry reserves. Fees earned by the position are not
+/// included.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_impermanent_loss(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<ImpermanentLossQuery>,
+) -> Result<Json<ImpermanentLossResponse>, ApiError> {
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let pool_name = pool
+        .name
+        .clone()
+        .unwrap_or_else(|| pool.address.to_string());
+    if !matches!(
+        pool.pool_type.parse::<PoolType>()?,
+        PoolType::ConstantProduct
+    ) {
+        return Err(ApiError::BadRequest(format!(
+            "Impermanent loss is only computed for constant-product pools, {pool_name} is {}",
+            pool.pool_type
+        )));
+    }
+
+    let entry_price = match (
+        query.entry_block,
+        query.entry_reserve0,
+        query.entry_reserve1,
+    ) {
+        (Some(block), None, None) => {
+            state
+                .reader
+                .get_prices_at_blocks(pool.id, &[block])
+                .await?
+                .into_iter()
+                .find_map(|(_, price)| price)
+                .ok_or_else(|| {
+                    ApiError::NotFound(format!("No price for pool {pool_name} at block {block}"))
+                })?
+                .price
+        }
+        (None, Some(reserve0), Some(reserve1)) if reserve0 > 0.0 => reserve1 / reserve0,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Give entry_block, or positive entry_reserve0 and entry_reserve1".to_string(),
+            ))
+        }
+    };
+    let position = LpPosition::deposit(entry_price, query.amount0, query.amount1)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let latest = state
+        .reader
+        .get_latest_price(pool.id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No price data for pool {pool_name}")))?;
+    let now = position
+        .value_at(latest.price)
+        .map_err(|e| ApiError::InternalError(e.to_string()))?;
+
+    Ok(Json(ImpermanentLossResponse {
+        pool: pool_name,
+        entry_block: query.entry_block,
+        entry_price,
+        block_number: u64::try_from(latest.block_number).unwrap_or(0),
+        price: latest.price,
+        price_change_pct: (now.price_ratio - 1.0) * 100.0,
+        deposited: PositionAmounts {
+            amount0: position.amount0,
+            amount1: position.amount1,
+        },
+        current: PositionAmounts {
+            amount0: now.amount0,
+            amount1: now.amount1,
+        },
+        value: now.value,
+        hold_value: now.hold_value,
+        impermanent_loss_pct: now.impermanent_loss * 100.0,
+        pool_share: if latest.reserve0_human > 0.0 {
+            now.amount0 / latest.reserve0_human
+        } else {
+            0.0
+        },
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/price-path",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        PricePathQuery
+    ),
+    responses(
+        (status = 200, description = "Price after every Sync event in the range", body = PricePathResponse),
+        (status = 400, description = "Invalid block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Pools"
+)]
+/// Returns every price a pool passed through within up to 100 blocks.
+///
+/// Each confirmed Sync event is one step, in log order, so trades that moved
+/// the price and moved it back within a block (sandwiches, arbitrage) stay
+/// visible. The last step of each block is flagged as its final price.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_price_path(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<PricePathQuery>,
+) -> Result<Json<PricePathResponse>, ApiError> {
+    let to_block = query.to_block.unwrap_or(query.from_block);
+    if to_block < query.from_block {
+        return Err(ApiError::BadRequest(
+            "to_block must not be before from_block".to_string(),
+        ));
+    }
+    if to_block - query.from_block >= MAX_PRICE_PATH_BLOCKS {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_PRICE_PATH_BLOCKS} blocks per request"
+        )));
+    }
+
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let events = state
+        .reader
+        .get_sync_events_in_blocks(pool.id, query.from_block, to_block)
+        .await?;
+    let decimals = (
+        u8::try_from(pool.token0_decimals).unwrap_or(18),
+        u8::try_from(pool.token1_decimals).unwrap_or(18),
+    );
+    let adapter = pool.price_adapter()?;
+
+    Ok(Json(PricePathResponse {
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+        from_block: query.from_block,
+        to_block,
+        blocks: price_path(events, decimals, adapter.as_ref())?,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/timeseries",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        TimeseriesQuery
+    ),
+    responses(
+        (status = 200, description = "Bucketed prices", body = TimeseriesResponse),
+        (status = 400, description = "Invalid bucket, aggregation, range or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Pools"
+)]
+/// Returns a pool's confirmed prices aggregated into fixed-width time buckets.
+///
+/// Meant as a Grafana data source: `from` and `to` take the dashboard's
+/// `$__from` and `$__to`, `bucket` its `$__interval`, and `points` is a list
+/// of `[timestamp, value]` pairs in milliseconds. Buckets are aligned to
+/// multiples of their width since the unix epoch and computed in SQL, so any
+/// width works, at most 10,000 buckets per request. `from_block`/`to_block`
+/// further limit the prices to those blocks; the time range still applies.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_timeseries(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<TimeseriesQuery>,
+) -> Result<Json<TimeseriesResponse>, ApiError> {
+    let bucket = query.bucket.as_deref().unwrap_or("5m");
+    let bucket_secs = parse_bucket(bucket).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "Invalid bucket '{bucket}'. Use a number with an s, m, h or d suffix, e.g. 5m"
+        ))
+    })?;
+    let agg = query
+        .agg
+        .as_deref()
+        .map(str::parse::<TimeseriesAgg>)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        .unwrap_or_default();
+
+    let to_ms = query.to.unwrap_or_else(|| Utc::now().timestamp_millis());
+    let from_ms = query.from.unwrap_or(to_ms - 86_400_000);
+    if from_ms > to_ms {
+        return Err(ApiError::BadRequest(
+            "from must not be after to".to_string(),
+        ));
+    }
+    let blocks = block_range(query.from_block, query.to_block)?;
+    let (from_ts, to_ts) = (from_ms.div_euclid(1_000), to_ms.div_euclid(1_000));
+    if (to_ts - from_ts) / bucket_secs >= MAX_TIMESERIES_POINTS {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_TIMESERIES_POINTS} buckets per request; use a wider bucket"
+        )));
+    }
+
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let direction = pool.quote_direction().inverted(query.invert);
+    let points = state
+        .reader
+        .get_price_timeseries(pool.id, bucket_secs, agg, direction, from_ts, to_ts, blocks)
+        .await?
+        .into_iter()
+        .map(|(bucket_start, value)| (bucket_start * 1_000, value))
+        .collect();
+
+    Ok(Json(TimeseriesResponse {
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+        bucket_secs: bucket_secs.unsigned_abs(),
+        agg: agg.to_string(),
+        quote_direction: direction.to_string(),
+        points,
+    }))
+}
+
+/// Parses a bucket width such as `30s`, `5m`, `1h` or `1d` into seconds.
+fn parse_bucket(bucket: &str) -> Option<i64> {
+    let unit = match bucket.chars().last()? {
+        's' => 1,
+        'm' => 60,
+        'h' => 3_600,
+        'd' => 86_400,
+        _ => return None,
+    };
+    let count: i64 = bucket[..bucket.len() - 1].parse().ok()?;
+    count.checked_mul(unit).filter(|secs| *secs > 0)
+}
+
+/// Groups sync events (in chain order) into per-block price paths.
+fn price_path(
+    events: Vec<SyncEventRow>,
+    (decimals0, decimals1): (u8, u8),
+    adapter: &dyn PriceAdapter,
+) -> Result<Vec<BlockPricePath>, ApiError> {
+    let mut blocks: Vec<BlockPricePath> = Vec::new();
+
+    for event in events {
+        let parse = |raw: &str| {
+            raw.parse::<U256>()
+                .map_err(|_| ApiError::InternalError(format!("Corrupt reserve: {raw}")))
+        };
+        let reserve0 = parse(&event.reserve0)?;
+        let reserve1 = parse(&event.reserve1)?;
+        let exact = adapter
+            .price_exact(reserve0, reserve1, decimals0, decimals1)
+            .ok();
+        let step = PricePathStep {
+            tx_hash: event.tx_hash.to_string(),
+            log_index: u64::try_from(event.log_index).unwrap_or(0),
+            price: exact.map_or(0.0, pricing::exact_price_to_f64),
+            price_exact: exact.map(pricing::format_exact_price),
+            reserve0: event.reserve0,
+            reserve1: event.reserve1,
+            is_final: false,
+        };
+
+        let block_number = u64::try_from(event.block_number).unwrap_or(0);
+        match blocks.last_mut() {
+            Some(block) if block.block_number == block_number => block.steps.push(step),
+            _ => blocks.push(BlockPricePath {
+                block_number,
+                block_timestamp: u64::try_from(event.block_timestamp).unwrap_or(0),
+                steps: vec![step],
+                final_price: 0.0,
+                final_price_exact: None,
+            }),
+        }
+    }
+
+    for block in &mut blocks {
+        if let Some(last) = block.steps.last_mut() {
+            last.is_final = true;
+            block.final_price = last.price;
+            block.final_price_exact.clone_from(&last.price_exact);
+        }
+    }
+
+    Ok(blocks)
+}
+
+/// Resolves a pool path parameter to its database record.
+///
+/// See [`crate::db::repository::Repository::find_pool`] for accepted forms.
+/// Pools outside `scope` are not found.
+pub(crate) async fn resolve_pool(
+    state: &AppState,
+    scope: &PoolScope,
+    id: &str,
+) -> Result<PoolRecord, ApiError> {
+    state
+        .reader
+        .find_pool(id)
+        .await?
+        .filter(|pool| scope.allows(pool.id))
+        .ok_or_else(|| ApiError::NotFound(format!("Pool {id} not found")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::ConstantProduct;
+    use alloy::primitives::B256;
+
+    fn event(block_number: i64, log_index: i64, usdt: u64) -> SyncEventRow {
+        SyncEventRow {
+            event_id: None,
+            block_number,
+            block_timestamp: 1_700_000_000 + block_number,
+            tx_hash: B256::with_last_byte(u8::try_from(log_index).unwrap()).into(),
+            log_index,
+            reserve0: (U256::from(1_000u64) * U256::from(10u64).pow(U256::from(18u64))).to_string(),
+            reserve1: (U256::from(usdt) * U256::from(10u64).pow(U256::from(6u64))).to_string(),
+        }
+    }
+
+    #[test]
+    fn test_price_path_flags_final_price_per_block() {
+        // A sandwich in block 100: up, victim, back down
+        let events = vec![
+            event(100, 3, 2_000_000),
+            event(100, 7, 2_050_000),
+            event(100, 9, 2_001_000),
+            event(102, 0, 2_010_000),
+        ];
+
+        let blocks = price_path(events, (18, 6), &ConstantProduct { fee_bps: 30 }).unwrap();
+        assert_eq!(blocks.len(), 2);
+
+        let block = &blocks[0];
+        assert_eq!(block.block_number, 100);
+        let prices: Vec<f64> = block.steps.iter().map(|s| s.price).collect();
+        assert_eq!(prices, vec![2_000.0, 2_050.0, 2_001.0]);
+        let finals: Vec<bool> = block.steps.iter().map(|s| s.is_final).collect();
+        assert_eq!(finals, vec![false, false, true]);
+        assert!((block.final_price - 2_001.0).abs() < f64::EPSILON);
+        assert_eq!(block.final_price_exact.as_deref(), Some("2001"));
+
+        assert_eq!(blocks[1].steps.len(), 1);
+        assert!(blocks[1].steps[0].is_final);
+    }
+
+    #[test]
+    fn test_parse_bucket() {
+        assert_eq!(parse_bucket("30s"), Some(30));
+        assert_eq!(parse_bucket("5m"), Some(300));
+        assert_eq!(parse_bucket("1h"), Some(3_600));
+        assert_eq!(parse_bucket("1d"), Some(86_400));
+        for invalid in ["", "m", "0m", "-5m", "5", "5w", "500ms"] {
+            assert_eq!(parse_bucket(invalid), None, "{invalid}");
+        }
+    }
 }
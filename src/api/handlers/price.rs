@@ -1,77 +1,252 @@
 //! Price endpoints.
 
 use axum::{
-    extract::{Path, Query, State},
+    body::Body,
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
     Json,
 };
-use chrono::{DateTime, Utc};
-use tracing::{info, instrument};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use std::collections::HashMap;
+use tracing::{debug, info, instrument, warn};
 
+use super::pools::resolve_pool;
+use crate::api::conditional::Validators;
 use crate::api::middleware::error::ApiError;
 use crate::api::models::{
-    CurrentPriceResponse, HistoryQuery, PaginatedResponse, PaginationInfo, PricePoint, ReservesInfo,
+    ConversionStep, CurrentPriceQuery, CurrentPriceResponse, ExportFormat, HistoryExportQuery,
+    HistoryQuery, Paginated, PriceAtBlock, PriceAtQuery, PriceAtResponse, PricePoint,
+    PricesAtBlocksRequest, PricesAtBlocksResponse, ReserveSource, ReservesInfo,
 };
 use crate::app_state::AppState;
+use crate::db::models::{BlockRange, PoolRecord, PoolScope, PricePointRow};
+use crate::downsample::Lttb;
+use crate::error::TrackerError;
+use crate::events::fetch_reserves_at;
+use crate::price_cache::CachedPrice;
+use crate::pricing::{self, QuoteDirection};
+use crate::routing::{Denomination, Token, TokenGraph};
+use crate::rpc::{find_block_at_timestamp, get_block_timestamp};
+
+/// Most blocks accepted by one `/prices/at-blocks` request.
+pub const MAX_PRICE_BLOCKS: usize = 1000;
+
+/// Most points a downsampled history request may ask for.
+pub const MAX_DOWNSAMPLE_POINTS: u32 = 10_000;
+
+/// Most rows encoded into one chunk of a history export.
+const EXPORT_CHUNK_ROWS: usize = 256;
+
+/// Header row of CSV history exports.
+const CSV_HEADER: &str =
+    "id,block_number,timestamp,price,price_exact,tx_hash,source,reserve_weth,reserve_usdt\n";
 
 #[utoipa::path(
     get,
     path = "/api/v1/price/current/{pool}",
     params(
-        ("pool" = String, Path, description = "Pool name (e.g., WETH-USDT)")
+        ("pool" = String, Path, description = "Pool name (e.g., WETH-USDT)"),
+        CurrentPriceQuery
     ),
     responses(
         (status = 200, description = "Current price", body = CurrentPriceResponse),
-        (status = 404, description = "Pool not found")
+        (status = 404, description = "Pool not found", body = ErrorResponse),
+        (status = 503, description = "Price is stale and `strict=true`", body = ErrorResponse)
     ),
     tag = "Price"
 )]
 /// Returns the latest confirmed price for a pool.
-#[instrument(skip(state), fields(pool = %pool_name))]
+#[instrument(skip(state, scope), fields(pool = %pool_name))]
 pub async fn get_current_price(
     State(state): State<AppState>,
+    scope: PoolScope,
     Path(pool_name): Path<String>,
+    Query(query): Query<CurrentPriceQuery>,
 ) -> Result<Json<CurrentPriceResponse>, ApiError> {
+    current_price(&state, &scope, &pool_name, &query)
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/price/latest/{pool}",
+    params(
+        ("pool" = String, Path, description = "Pool name (e.g., WETH-USDT)"),
+        CurrentPriceQuery
+    ),
+    responses(
+        (status = 200, description = "Latest price", body = CurrentPriceResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse),
+        (status = 503, description = "Price is stale and `strict=true`", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Alias of `/price/current/{pool}`.
+#[instrument(skip(state, scope), fields(pool = %pool_name))]
+pub async fn get_latest_price(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(pool_name): Path<String>,
+    Query(query): Query<CurrentPriceQuery>,
+) -> Result<Json<CurrentPriceResponse>, ApiError> {
+    current_price(&state, &scope, &pool_name, &query)
+        .await
+        .map(Json)
+}
+
+/// Builds the current price response, flagging (or rejecting) stale prices.
+///
+/// Pools outside `scope` are not found, and conversions only route over
+/// pools in it.
+pub(crate) async fn current_price(
+    state: &AppState,
+    scope: &PoolScope,
+    pool_name: &str,
+    query: &CurrentPriceQuery,
+) -> Result<CurrentPriceResponse, ApiError> {
     info!("Fetching current price");
 
     let pool_name_normalized = pool_name.replace('-', "/");
 
     let pool = state
-        .repository
+        .reader
         .get_pool_by_name(&pool_name_normalized)
         .await?
+        .filter(|pool| scope.allows(pool.id))
         .ok_or_else(|| ApiError::NotFound(format!("Pool {} not found", pool_name_normalized)))?;
 
-    let price_point = state
-        .repository
-        .get_latest_price(pool.id)
+    let CachedPrice {
+        latest: price_point,
+        change_24h,
+    } = state
+        .latest_price(pool.id)
         .await?
         .ok_or_else(|| ApiError::NotFound("No price data available".to_string()))?;
 
-    let change_24h = state.repository.get_24h_price_change(pool.id).await.ok();
+    let age_seconds = price_age_secs(price_point.block_timestamp, Utc::now().timestamp());
+    let is_stale = age_seconds > state.price_stale_after_secs;
+
+    if is_stale && query.strict {
+        warn!(
+            age_seconds,
+            threshold = state.price_stale_after_secs,
+            "Rejecting stale price in strict mode"
+        );
+        return Err(ApiError::ServiceUnavailable(format!(
+            "Latest price is {age_seconds}s old (threshold {}s)",
+            state.price_stale_after_secs
+        )));
+    }
 
     let timestamp =
         DateTime::from_timestamp(price_point.block_timestamp, 0).unwrap_or_else(Utc::now);
+    let direction = pool.quote_direction().inverted(query.invert);
 
-    let response = CurrentPriceResponse {
+    let mut response = CurrentPriceResponse {
+        id: price_point.event_id,
         pool: pool_name_normalized,
-        price: price_point.price,
+        price: direction.apply(price_point.price),
+        price_exact: price_point
+            .price_exact
+            .and_then(|exact| direction.apply_exact(&exact)),
+        price_ewma: price_point.price_ewma.map(|ewma| direction.apply(ewma)),
         block_number: price_point.block_number as u64,
         timestamp,
-        tx_hash: price_point.tx_hash,
+        tx_hash: price_point.tx_hash.to_string(),
+        source: price_point.source,
         reserves: ReservesInfo {
             weth: price_point.reserve0_human,
             usdt: price_point.reserve1_human,
         },
-        change_24h,
+        change_24h: change_24h.map(|pct| direction.apply_change_pct(pct)),
+        stale: is_stale,
+        age_seconds,
+        quote_direction: direction.to_string(),
+        denomination: None,
+        conversion: None,
     };
 
+    if let Some(denominate) = &query.denominate {
+        let denomination = denominate.parse::<Denomination>().map_err(|_| {
+            ApiError::BadRequest(format!("denominate must be usd, got: {denominate}"))
+        })?;
+        let (factor, steps) = conversion(state, scope, &pool, direction, denomination).await?;
+        // Exact prices and changes are in the pool's quote token
+        response.price *= factor;
+        response.price_exact = None;
+        response.price_ewma = response.price_ewma.map(|ewma| ewma * factor);
+        response.change_24h = None;
+        response.denomination = Some(denomination.to_string());
+        response.conversion = Some(steps);
+    }
+
     info!(
         price = response.price,
         block = response.block_number,
+        stale = is_stale,
         "Current price fetched"
     );
 
-    Ok(Json(response))
+    Ok(response)
+}
+
+/// Returns the factor converting the pool's prices, quoted in `direction`,
+/// into `denomination`, and the pools it was derived from.
+///
+/// The quote token is routed to the denomination over the enabled pools in
+/// `scope` (see [`TokenGraph`]) and each pool's latest price supplies one
+/// rate.
+async fn conversion(
+    state: &AppState,
+    scope: &PoolScope,
+    pool: &PoolRecord,
+    direction: QuoteDirection,
+    denomination: Denomination,
+) -> Result<(f64, Vec<ConversionStep>), ApiError> {
+    let quote = Token::quote_of(pool, direction);
+    let mut pools = state.reader.get_enabled_pools().await?;
+    pools.retain(|pool| scope.allows(pool.id));
+    let route = TokenGraph::new(&pools)
+        .route_to(&quote, denomination)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "No route from {} to {denomination} over the indexed pools",
+                quote.symbol
+            ))
+        })?;
+
+    let mut factor = 1.0;
+    let mut steps = Vec::with_capacity(route.len());
+    for hop in route {
+        let rate_price = state
+            .latest_price(hop.pool_id)
+            .await?
+            .map(|cached| cached.latest)
+            .and_then(|latest| Some((hop.rate(latest.price)?, latest.block_number)));
+        let Some((rate, block_number)) = rate_price else {
+            return Err(ApiError::NotFound(format!(
+                "No price data for conversion pool {}",
+                hop.pool
+            )));
+        };
+        factor *= rate;
+        steps.push(ConversionStep {
+            pool: hop.pool,
+            from: hop.from.symbol,
+            to: hop.to.symbol,
+            rate,
+            block_number: u64::try_from(block_number).unwrap_or_default(),
+        });
+    }
+    Ok((factor, steps))
+}
+
+/// Seconds elapsed between a block timestamp and `now` (zero if in the future).
+fn price_age_secs(block_timestamp: i64, now: i64) -> u64 {
+    u64::try_from(now.saturating_sub(block_timestamp)).unwrap_or(0)
 }
 
 #[utoipa::path(
@@ -82,86 +257,436 @@ pub async fn get_current_price(
         HistoryQuery
     ),
     responses(
-        (status = 200, description = "Historical prices", body = PaginatedResponse<PricePoint>)
+        (status = 200, description = "Historical prices", body = PaginatedPricePoints),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag or `If-Modified-Since` date"),
+        (status = 400, description = "Invalid pagination, timestamp or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
     ),
     tag = "Price"
 )]
-/// Returns paginated historical prices for a pool.
-#[instrument(skip(state), fields(pool = %pool_name))]
+/// Returns paginated historical prices for a pool, newest first.
+///
+/// The range can be bounded by timestamp (`from`/`to`), by block number
+/// (`from_block`/`to_block`), or both. Pages are selected with `limit` and `offset`; `page` (1-indexed) is still
+/// accepted in place of `offset`. With `points`, the whole range is instead
+/// downsampled with LTTB (see [`crate::downsample`]) into a single page of
+/// at most that many points. Responses carry an `ETag` and `Last-Modified`; polling clients that send
+/// them back get `304 Not Modified` until a price in the range changes.
+#[instrument(skip(state, headers, scope), fields(pool = %pool_name))]
 pub async fn get_price_history(
     State(state): State<AppState>,
+    scope: PoolScope,
     Path(pool_name): Path<String>,
     Query(query): Query<HistoryQuery>,
-) -> Result<Json<PaginatedResponse<PricePoint>>, ApiError> {
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     let pool_name_normalized = pool_name.replace('-', "/");
 
-    if query.page < 1 {
+    if query.page == Some(0) {
         return Err(ApiError::BadRequest("page must be >= 1".to_string()));
     }
-    if query.page_size > 1000 {
-        return Err(ApiError::BadRequest(
-            "page_size must be <= 1000".to_string(),
-        ));
+    if query.limit > 1000 {
+        return Err(ApiError::BadRequest("limit must be <= 1000".to_string()));
+    }
+    if let Some(points) = query.points {
+        if !(3..=MAX_DOWNSAMPLE_POINTS).contains(&points) {
+            return Err(ApiError::BadRequest(format!(
+                "points must be between 3 and {MAX_DOWNSAMPLE_POINTS}"
+            )));
+        }
     }
+    let offset = query
+        .offset
+        .unwrap_or_else(|| u64::from(query.page.unwrap_or(1) - 1) * u64::from(query.limit));
 
     let pool = state
-        .repository
+        .reader
         .get_pool_by_name(&pool_name_normalized)
         .await?
+        .filter(|pool| scope.allows(pool.id))
         .ok_or_else(|| ApiError::NotFound(format!("Pool {} not found", pool_name_normalized)))?;
 
     let from_ts = parse_timestamp(&query.from)?;
     let to_ts = parse_timestamp(&query.to)?;
+    let blocks = block_range(query.from_block, query.to_block)?;
+    let direction = pool.quote_direction().inverted(query.invert);
 
-    let offset = (query.page - 1) * query.page_size;
+    let version = state
+        .reader
+        .get_price_history_version(pool.id, from_ts, to_ts, blocks)
+        .await?;
+    let (from_block, to_block) = blocks.bounds();
+    let validators = Validators::new(&[
+        &pool.id,
+        &from_ts.unwrap_or(0),
+        &to_ts.unwrap_or(i64::MAX),
+        &from_block,
+        &to_block,
+        &offset,
+        &query.limit,
+        &query.points.unwrap_or(0),
+        &version.count,
+        &version.max_block.unwrap_or(0),
+        &version.max_id.unwrap_or(0),
+        &direction,
+    ])
+    .with_last_modified(version.last_timestamp);
+    if validators.is_fresh(&headers) {
+        debug!("Price history not modified");
+        return Ok(validators.not_modified());
+    }
+
+    if let Some(points) = query.points {
+        let data = downsampled_history(
+            &state,
+            pool.id,
+            (from_ts, to_ts),
+            blocks,
+            version.count,
+            points,
+            direction,
+        )
+        .await?;
+        info!(
+            count = data.len(),
+            total = version.count,
+            "Downsampled historical prices"
+        );
+        let total = data.len() as u64;
+        let response = Paginated::from_offset(data, total, points, 0, &uri);
+        return Ok(validators.with_body(response));
+    }
 
-    let (prices, total_count) = state
-        .repository
+    let page = state
+        .reader
         .get_price_history_paginated(
             pool.id,
             from_ts,
             to_ts,
-            query.page_size as i64,
-            offset as i64,
+            blocks,
+            i64::from(query.limit),
+            i64::try_from(offset).unwrap_or(i64::MAX),
         )
         .await?;
 
-    let data = prices
+    let data = page
+        .items
         .into_iter()
-        .map(|p| PricePoint {
-            block_number: p.block_number as u64,
-            timestamp: DateTime::from_timestamp(p.block_timestamp, 0).unwrap_or_else(Utc::now),
-            price: p.price,
-            tx_hash: p.tx_hash,
-            reserves: ReservesInfo {
-                weth: p.reserve0_human,
-                usdt: p.reserve1_human,
-            },
-        })
+        .map(|p| price_point(p, direction))
         .collect::<Vec<_>>();
-
-    let has_next_page = (offset + query.page_size) < total_count as u32;
-
-    let response = PaginatedResponse {
-        data,
-        pagination: PaginationInfo {
-            page: query.page,
-            page_size: query.page_size,
-            total_count,
-            has_next_page,
-        },
-    };
+    let response = Paginated::from_offset(data, page.total, query.limit, offset, &uri);
 
     info!(
         count = response.data.len(),
-        total = total_count,
-        page = query.page,
+        total = page.total,
+        offset,
         "Historical prices fetched"
     );
 
+    Ok(validators.with_body(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/price/history/{pool}/export",
+    params(
+        ("pool" = String, Path, description = "Pool name"),
+        HistoryExportQuery
+    ),
+    responses(
+        (status = 200, description = "Confirmed prices in the range, oldest first, one per line",
+            content(("application/x-ndjson" = PricePoint), ("text/csv" = String))),
+        (status = 400, description = "Invalid timestamp or block range", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Streams a pool's confirmed price history, oldest first, as NDJSON or CSV.
+///
+/// The range isn't paginated: rows are read from the database in chunks as
+/// the client consumes the response, so exporting a year of per-event history
+/// never holds it in memory. A database error mid-stream aborts the response.
+#[instrument(skip(state, scope), fields(pool = %pool_name))]
+pub async fn export_price_history(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(pool_name): Path<String>,
+    Query(query): Query<HistoryExportQuery>,
+) -> Result<Response, ApiError> {
+    let pool_name_normalized = pool_name.replace('-', "/");
+    let pool = state
+        .reader
+        .get_pool_by_name(&pool_name_normalized)
+        .await?
+        .filter(|pool| scope.allows(pool.id))
+        .ok_or_else(|| ApiError::NotFound(format!("Pool {} not found", pool_name_normalized)))?;
+
+    let from_ts = parse_timestamp(&query.from)?;
+    let to_ts = parse_timestamp(&query.to)?;
+    let blocks = block_range(query.from_block, query.to_block)?;
+    let direction = pool.quote_direction().inverted(query.invert);
+    let format = query.format;
+
+    let header_row = (format == ExportFormat::Csv).then(|| Ok(CSV_HEADER.to_string()));
+    let rows = state
+        .reader
+        .stream_price_history(pool.id, from_ts, to_ts, blocks)
+        .ready_chunks(EXPORT_CHUNK_ROWS)
+        .map(move |rows| -> Result<String, TrackerError> {
+            let mut chunk = String::new();
+            for row in rows {
+                let point = price_point(row?, direction);
+                match format {
+                    ExportFormat::Ndjson => {
+                        let line = serde_json::to_string(&point).map_err(|e| {
+                            TrackerError::decoding(
+                                "Failed to encode price point",
+                                Some(Box::new(e)),
+                            )
+                        })?;
+                        chunk.push_str(&line);
+                        chunk.push('\n');
+                    }
+                    ExportFormat::Csv => chunk.push_str(&csv_row(&point)),
+                }
+            }
+            Ok(chunk)
+        })
+        .inspect_err(|e| warn!(error = %e, "Price history export aborted"));
+
+    let content_type = match format {
+        ExportFormat::Ndjson => "application/x-ndjson",
+        ExportFormat::Csv => "text/csv; charset=utf-8",
+    };
+    info!(?format, from = ?from_ts, to = ?to_ts, ?blocks, "Streaming price history");
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type)],
+        Body::from_stream(stream::iter(header_row).chain(rows)),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/price/at",
+    params(
+        ("id" = String, Path, description = "Pool ID, address or name (e.g. WETH-USDT)"),
+        PriceAtQuery
+    ),
+    responses(
+        (status = 200, description = "Last known price at the block or timestamp", body = PriceAtResponse),
+        (status = 400, description = "Neither or both of block and timestamp, or an invalid timestamp", body = ErrorResponse),
+        (status = 404, description = "Pool not found, or no price at the block or timestamp", body = ErrorResponse),
+        (status = 502, description = "Archive node rejected the call", body = ErrorResponse),
+        (status = 503, description = "Archive node call failed, retry later", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Returns the last known price of a pool at a block or timestamp.
+///
+/// Served from the last confirmed price at or before the point, found with a
+/// single index seek. Points outside the indexed history (past the last
+/// indexed block or its timestamp, or before the first indexed price) fall
+/// back to a `getReserves()` call when an RPC provider
+/// is configured, like `/pools/{id}/reserves/at`; a timestamp is first
+/// resolved to the last block mined at or before it.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_price_at(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+    Query(query): Query<PriceAtQuery>,
+) -> Result<Json<PriceAtResponse>, ApiError> {
+    let timestamp = parse_timestamp(&query.timestamp)?;
+    if query.block.is_some() == timestamp.is_some() {
+        return Err(ApiError::BadRequest(
+            "Pass exactly one of block or timestamp".to_string(),
+        ));
+    }
+
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let pool_name = pool
+        .name
+        .clone()
+        .unwrap_or_else(|| pool.address.to_string());
+    let direction = pool.quote_direction().inverted(query.invert);
+    let point = query.block.map_or_else(
+        || format!("timestamp {}", timestamp.unwrap_or_default()),
+        |block| format!("block {block}"),
+    );
+
+    let indexed = match query.block {
+        Some(block) => {
+            let last_indexed = state
+                .reader
+                .get_state(pool.id)
+                .await?
+                .map_or(0, |s| u64::try_from(s.last_indexed_block).unwrap_or(0));
+            if block <= last_indexed {
+                state.reader.get_price_at_block(pool.id, block).await?
+            } else {
+                None
+            }
+        }
+        None => {
+            let timestamp = timestamp.unwrap_or_default();
+            if indexed_through(&state, pool.id, timestamp).await? {
+                state
+                    .reader
+                    .get_price_at_timestamp(pool.id, timestamp)
+                    .await?
+            } else {
+                None
+            }
+        }
+    };
+
+    let mut response = PriceAtResponse {
+        pool: pool_name,
+        block: query.block,
+        timestamp: timestamp.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+        source: ReserveSource::Indexed,
+        price: 0.0,
+        price_exact: None,
+        price_block: 0,
+        price_timestamp: None,
+        tx_hash: None,
+        quote_direction: direction.to_string(),
+    };
+
+    if let Some(row) = indexed {
+        let price = price_point(row, direction);
+        response.price = price.price;
+        response.price_exact = price.price_exact;
+        response.price_block = price.block_number;
+        response.price_timestamp = Some(price.timestamp);
+        response.tx_hash = Some(price.tx_hash);
+        return Ok(Json(response));
+    }
+
+    let not_found = || {
+        ApiError::NotFound(format!(
+            "No indexed price for pool {} at {point}",
+            response.pool
+        ))
+    };
+    let router = state.state_router().ok_or_else(not_found)?;
+    let block = match query.block {
+        Some(block) => block,
+        None => {
+            let primary = state.rpc.as_deref().ok_or_else(not_found)?;
+            let timestamp = u64::try_from(timestamp.unwrap_or_default()).unwrap_or(0);
+            find_block_at_timestamp(primary, timestamp)
+                .await?
+                .ok_or_else(not_found)?
+        }
+    };
+    let provider = router.provider_at(block).await.map_err(|e| match e {
+        TrackerError::ConfigError { .. } => ApiError::NotFound(format!(
+            "No indexed price for pool {} at {point}, and the RPC node keeps no state \
+                 that old",
+            response.pool
+        )),
+        e => e.into(),
+    })?;
+    let (reserve0, reserve1) = fetch_reserves_at(provider, pool.address.get(), block).await?;
+    let exact = pool
+        .price_adapter()?
+        .price_exact(
+            reserve0,
+            reserve1,
+            u8::try_from(pool.token0_decimals).unwrap_or(18),
+            u8::try_from(pool.token1_decimals).unwrap_or(18),
+        )
+        .map_err(|_| {
+            ApiError::NotFound(format!(
+                "Pool {} had no liquidity at block {block}",
+                response.pool
+            ))
+        })?;
+
+    response.source = ReserveSource::Archive;
+    response.price = direction.apply(pricing::exact_price_to_f64(exact));
+    response.price_exact = direction.apply_exact(&pricing::format_exact_price(exact));
+    response.price_block = block;
+    info!(block, "Price read from the node");
     Ok(Json(response))
 }
 
+/// Whether `timestamp` lies at or before the pool's last indexed block.
+///
+/// The block's timestamp is bounded by the last price indexed up to it; only
+/// a timestamp past that needs the block's header from the node.
+async fn indexed_through(state: &AppState, pool_id: i64, timestamp: i64) -> Result<bool, ApiError> {
+    let Some(last_indexed) = state.reader.get_state(pool_id).await? else {
+        return Ok(false);
+    };
+    let last_indexed = u64::try_from(last_indexed.last_indexed_block).unwrap_or(0);
+    let last_price = state
+        .reader
+        .get_price_at_block(pool_id, last_indexed)
+        .await?;
+    if last_price.is_some_and(|price| timestamp <= price.block_timestamp) {
+        return Ok(true);
+    }
+
+    let Some(provider) = state.rpc.as_deref() else {
+        return Ok(false);
+    };
+    let block_timestamp = get_block_timestamp(provider, last_indexed).await?;
+    Ok(i64::try_from(block_timestamp).is_ok_and(|block_timestamp| timestamp <= block_timestamp))
+}
+
+/// Formats a price point as a CSV row matching [`CSV_HEADER`].
+fn csv_row(point: &PricePoint) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{},{}\n",
+        point.id.as_deref().unwrap_or_default(),
+        point.block_number,
+        point.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+        point.price,
+        point.price_exact.as_deref().unwrap_or_default(),
+        point.tx_hash,
+        point.source,
+        point.reserves.weth,
+        point.reserves.usdt
+    )
+}
+
+/// Streams the range's `total` prices through LTTB, keeping at most
+/// `points`, newest first like the paginated history.
+async fn downsampled_history(
+    state: &AppState,
+    pool_id: i64,
+    (from_ts, to_ts): (Option<i64>, Option<i64>),
+    blocks: BlockRange,
+    total: i64,
+    points: u32,
+    direction: QuoteDirection,
+) -> Result<Vec<PricePoint>, ApiError> {
+    let total = usize::try_from(total).unwrap_or(0);
+    let mut lttb = Lttb::new(total, usize::try_from(points).unwrap_or(usize::MAX));
+    let mut rows = std::pin::pin!(state
+        .reader
+        .stream_price_history(pool_id, from_ts, to_ts, blocks)
+        .take(total));
+    while let Some(row) = rows.try_next().await? {
+        #[allow(clippy::cast_precision_loss)]
+        let x = row.block_timestamp as f64;
+        lttb.push(x, direction.apply(row.price), row);
+    }
+
+    let mut data: Vec<PricePoint> = lttb
+        .finish()
+        .into_iter()
+        .map(|row| price_point(row, direction))
+        .collect();
+    data.reverse();
+    Ok(data)
+}
+
 fn parse_timestamp(ts: &Option<String>) -> Result<Option<i64>, ApiError> {
     match ts {
         None => Ok(None),
@@ -181,3 +706,178 @@ fn parse_timestamp(ts: &Option<String>) -> Result<Option<i64>, ApiError> {
         }
     }
 }
+
+/// Validates the `from_block`/`to_block` query parameters.
+pub(crate) fn block_range(
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+) -> Result<BlockRange, ApiError> {
+    BlockRange::new(from_block, to_block)
+        .ok_or_else(|| ApiError::BadRequest("from_block must not be after to_block".to_string()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/prices/at-blocks",
+    request_body = PricesAtBlocksRequest,
+    responses(
+        (status = 200, description = "Nearest prior price for each block", body = PricesAtBlocksResponse),
+        (status = 400, description = "Empty or oversized block list", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Returns the last confirmed price at or before each of up to 1000 blocks.
+#[instrument(skip(state, request, scope), fields(pool = %request.pool, blocks = request.blocks.len()))]
+pub async fn get_prices_at_blocks(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Json(request): Json<PricesAtBlocksRequest>,
+) -> Result<Json<PricesAtBlocksResponse>, ApiError> {
+    if request.blocks.is_empty() {
+        return Err(ApiError::BadRequest("blocks must not be empty".to_string()));
+    }
+    if request.blocks.len() > MAX_PRICE_BLOCKS {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_PRICE_BLOCKS} blocks per request"
+        )));
+    }
+
+    let pool = resolve_pool(&state, &scope, &request.pool).await?;
+    let direction = pool.quote_direction().inverted(request.invert);
+    let found: HashMap<u64, Option<PricePointRow>> = state
+        .reader
+        .get_prices_at_blocks(pool.id, &request.blocks)
+        .await?
+        .into_iter()
+        .collect();
+
+    let prices = request
+        .blocks
+        .iter()
+        .map(|&block| PriceAtBlock {
+            block,
+            price: found
+                .get(&block)
+                .cloned()
+                .flatten()
+                .map(|p| price_point(p, direction)),
+        })
+        .collect::<Vec<_>>();
+
+    info!(
+        requested = prices.len(),
+        priced = prices.iter().filter(|p| p.price.is_some()).count(),
+        "Prices at blocks fetched"
+    );
+
+    Ok(Json(PricesAtBlocksResponse {
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+        prices,
+        quote_direction: direction.to_string(),
+    }))
+}
+
+/// Converts a stored price point to its API form, quoted in `direction`.
+pub(crate) fn price_point(p: PricePointRow, direction: QuoteDirection) -> PricePoint {
+    PricePoint {
+        id: p.event_id,
+        block_number: p.block_number as u64,
+        timestamp: DateTime::from_timestamp(p.block_timestamp, 0).unwrap_or_else(Utc::now),
+        price: direction.apply(p.price),
+        price_exact: p
+            .price_exact
+            .and_then(|exact| direction.apply_exact(&exact)),
+        tx_hash: p.tx_hash.to_string(),
+        source: p.source,
+        reserves: ReservesInfo {
+            weth: p.reserve0_human,
+            usdt: p.reserve1_human,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_age_secs() {
+        assert_eq!(price_age_secs(1_000, 1_300), 300);
+        assert_eq!(price_age_secs(1_000, 1_000), 0);
+        // Clock skew: block timestamp slightly ahead of the server clock
+        assert_eq!(price_age_secs(1_010, 1_000), 0);
+    }
+
+    #[test]
+    fn test_csv_row_matches_header() {
+        let point = PricePoint {
+            id: None,
+            block_number: 19_000_000,
+            timestamp: DateTime::from_timestamp(1_706_745_600, 0).unwrap(),
+            price: 2_250.5,
+            price_exact: Some("2250.5".to_string()),
+            tx_hash: format!("0x{}", "ab".repeat(32)),
+            source: "event".to_string(),
+            reserves: ReservesInfo {
+                weth: 10.0,
+                usdt: 22_505.0,
+            },
+        };
+
+        let row = csv_row(&point);
+        assert_eq!(
+            row,
+            format!(
+                ",19000000,2024-02-01T00:00:00Z,2250.5,2250.5,0x{},event,10,22505\n",
+                "ab".repeat(32)
+            )
+        );
+        assert_eq!(row.split(',').count(), CSV_HEADER.split(',').count());
+    }
+
+    #[tokio::test]
+    async fn test_timestamps_up_to_the_last_indexed_block_are_indexed() {
+        use crate::db::{create_pool, repository::Repository};
+        use alloy::primitives::{FixedBytes, U256};
+
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repository.ensure_default_pool().await.unwrap();
+        // Prices in blocks 1-3, 12 seconds apart; the indexer is at block 2
+        for block in 1..=3_u64 {
+            repository
+                .insert_price_point(
+                    pool_id,
+                    block,
+                    1_706_745_600 + block * 12,
+                    FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                    2_000.0,
+                    U256::from(1_u64),
+                    U256::from(1_u64),
+                    1.0,
+                    1.0,
+                    true,
+                    &format!("price-{block}"),
+                )
+                .await
+                .unwrap();
+        }
+        repository
+            .update_state(pool_id, 2, FixedBytes::ZERO, 0, 3)
+            .await
+            .unwrap();
+        let state = AppState::new(repository);
+
+        // Block 2's price is the last indexed one, with no later price needed
+        assert!(indexed_through(&state, pool_id, 1_706_745_600 + 24)
+            .await
+            .unwrap());
+        assert!(indexed_through(&state, pool_id, 1_706_745_600)
+            .await
+            .unwrap());
+        // Past block 2, only the node could tell
+        assert!(!indexed_through(&state, pool_id, 1_706_745_600 + 36)
+            .await
+            .unwrap());
+    }
+}
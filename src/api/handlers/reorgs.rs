@@ -0,0 +1,114 @@
+//! Reorg history endpoints.
+
+use axum::extract::{OriginalUri, Path, Query, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+
+use crate::api::handlers::pools::resolve_pool;
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{Paginated, ReorgInfo, ReorgQuery, ReorgStatsResponse};
+use crate::app_state::AppState;
+use crate::db::models::{PoolScope, ReorgRow};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/reorgs",
+    params(ReorgQuery),
+    responses(
+        (status = 200, description = "Page of reorgs, newest first", body = PaginatedReorgs),
+        (status = 400, description = "Invalid limit", body = ErrorResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Statistics"
+)]
+/// Returns a page of the chain reorganizations `watch` handled, newest first.
+///
+/// Each reorg rolled the pool back to `fork_point`; `invalidated_events`
+/// counts the sync events after it that were marked unconfirmed.
+#[instrument(skip(state, scope))]
+pub async fn list_reorgs(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Query(query): Query<ReorgQuery>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Paginated<ReorgInfo>, ApiError> {
+    if query.limit == 0 || query.limit > 1000 {
+        return Err(ApiError::BadRequest(
+            "limit must be between 1 and 1000".to_string(),
+        ));
+    }
+
+    let pool_id = match query.pool.as_deref() {
+        Some(pool) => Some(resolve_pool(&state, &scope, pool).await?.id),
+        None => None,
+    };
+
+    let page = state
+        .reader
+        .get_reorgs_page(
+            &scope,
+            pool_id,
+            i64::from(query.limit),
+            i64::try_from(query.offset).unwrap_or(i64::MAX),
+        )
+        .await?;
+
+    let data = page.items.iter().map(reorg_info).collect();
+
+    Ok(Paginated::from_offset(
+        data,
+        page.total,
+        query.limit,
+        query.offset,
+        &uri,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{id}/reorgs/stats",
+    params(
+        ("id" = String, Path, description = "Pool ID, address, or name (e.g., WETH-USDT)")
+    ),
+    responses(
+        (status = 200, description = "Reorg totals of the pool", body = ReorgStatsResponse),
+        (status = 404, description = "Pool not found", body = ErrorResponse)
+    ),
+    tag = "Statistics"
+)]
+/// Returns how many reorgs hit a pool, how deep they went and how many
+/// events they invalidated.
+#[instrument(skip(state, scope), fields(pool = %id))]
+pub async fn get_reorg_stats(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Path(id): Path<String>,
+) -> Result<Json<ReorgStatsResponse>, ApiError> {
+    let pool = resolve_pool(&state, &scope, &id).await?;
+    let totals = state.reader.get_reorg_stats(pool.id).await?;
+
+    Ok(Json(ReorgStatsResponse {
+        pool_id: pool.id,
+        reorgs: u64::try_from(totals.reorgs).unwrap_or_default(),
+        max_depth: totals.max_depth.and_then(|d| u64::try_from(d).ok()),
+        avg_depth: totals.avg_depth,
+        invalidated_events: u64::try_from(totals.invalidated_events).unwrap_or_default(),
+        last_detected_at: totals.last_detected_at.map(timestamp),
+    }))
+}
+
+fn timestamp(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).unwrap_or_else(Utc::now)
+}
+
+fn reorg_info(r: &ReorgRow) -> ReorgInfo {
+    ReorgInfo {
+        id: r.id,
+        pool_id: r.pool_id,
+        detected_at: timestamp(r.detected_at),
+        fork_point: u64::try_from(r.fork_point).unwrap_or_default(),
+        depth: u64::try_from(r.depth).unwrap_or_default(),
+        invalidated_events: u64::try_from(r.invalidated_events).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,123 @@
+//! Best-route endpoint.
+
+use alloy::primitives::U256;
+use axum::extract::{Query, State};
+use axum::Json;
+use std::collections::{HashMap, HashSet};
+use tracing::{info, instrument};
+
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{RouteHop, RouteQuery, RouteResponse};
+use crate::app_state::AppState;
+use crate::db::models::PoolScope;
+use crate::pricing;
+use crate::routing::{best_route, PoolLiquidity, TokenGraph};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/route",
+    params(RouteQuery),
+    responses(
+        (status = 200, description = "Route with the largest output", body = RouteResponse),
+        (status = 400, description = "Invalid amount, or the same token twice", body = ErrorResponse),
+        (status = 404, description = "Unknown token, or no route with reserves", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Returns the route selling `amount` of `from` for the most `to`.
+///
+/// Every route of up to three enabled pools (of those visible to the API
+/// key) is simulated at the pools'
+/// latest reserves, fees included, and the one with the largest output wins.
+#[instrument(skip(state, scope), fields(from = %query.from, to = %query.to))]
+pub async fn get_route(
+    State(state): State<AppState>,
+    scope: PoolScope,
+    Query(query): Query<RouteQuery>,
+) -> Result<Json<RouteResponse>, ApiError> {
+    let mut pools = state.reader.get_enabled_pools().await?;
+    pools.retain(|pool| scope.allows(pool.id));
+    let graph = TokenGraph::new(&pools);
+    let token = |name: &str| {
+        graph
+            .token(name)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("Token {name} not in any indexed pool")))
+    };
+    let from = token(&query.from)?;
+    let to = token(&query.to)?;
+    if from == to {
+        return Err(ApiError::BadRequest(
+            "from and to must be different tokens".to_string(),
+        ));
+    }
+    let amount_in = pricing::parse_token_amount(&query.amount, from.decimals)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let routes = graph.routes(&from, &to);
+    let pool_ids: HashSet<i64> = routes.iter().flatten().map(|hop| hop.pool_id).collect();
+    let mut liquidity = HashMap::with_capacity(pool_ids.len());
+    for pool in pools.iter().filter(|pool| pool_ids.contains(&pool.id)) {
+        // Pools without a price yet are left out of the routes
+        let Some(price) = state.reader.get_latest_price_record(pool.id).await? else {
+            continue;
+        };
+        let (Ok(reserve0), Ok(reserve1)) = (
+            price.reserve0_raw.parse::<U256>(),
+            price.reserve1_raw.parse::<U256>(),
+        ) else {
+            return Err(ApiError::InternalError(format!(
+                "Corrupt reserves for pool {}",
+                pool.id
+            )));
+        };
+        liquidity.insert(
+            pool.id,
+            PoolLiquidity {
+                reserve0,
+                reserve1,
+                block_number: u64::try_from(price.block_number).unwrap_or(0),
+                adapter: pool.price_adapter()?,
+            },
+        );
+    }
+
+    let best = best_route(&routes, amount_in, &liquidity).ok_or_else(|| {
+        ApiError::NotFound(format!(
+            "No route from {} to {} over pools with reserves",
+            from.symbol, to.symbol
+        ))
+    })?;
+
+    info!(
+        routes = routes.len(),
+        hops = best.hops.len(),
+        "Best route found"
+    );
+
+    Ok(Json(RouteResponse {
+        from: from.symbol,
+        to: to.symbol,
+        amount_in: pricing::format_token_amount(amount_in, from.decimals),
+        amount_out: pricing::format_token_amount(best.amount_out, to.decimals),
+        spot_price: best.spot_price(),
+        execution_price: best.execution_price(),
+        price_impact_pct: best.price_impact_pct(),
+        hops: best
+            .hops
+            .iter()
+            .map(|h| RouteHop {
+                pool: h.hop.pool.clone(),
+                block_number: h.block_number,
+                token_in: h.hop.from.symbol.clone(),
+                token_out: h.hop.to.symbol.clone(),
+                amount_in: pricing::format_token_amount(h.amount_in, h.hop.from.decimals),
+                amount_out: pricing::format_token_amount(h.quote.amount_out, h.hop.to.decimals),
+                execution_price: h.quote.execution_price,
+                price_impact_pct: h.quote.price_impact_pct,
+                fee_bps: h.fee_bps,
+            })
+            .collect(),
+        routes_considered: routes.len(),
+    }))
+}
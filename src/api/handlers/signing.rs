@@ -0,0 +1,38 @@
+//! Response signing key endpoint.
+
+use axum::{extract::State, Json};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use tracing::instrument;
+
+use crate::api::middleware::error::ApiError;
+use crate::api::middleware::signing::{SIGNATURE_HEADER, SIGNED_PATHS};
+use crate::api::models::PublicKeyResponse;
+use crate::app_state::AppState;
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/pubkey",
+    responses(
+        (status = 200, description = "Public key that signs price responses", body = PublicKeyResponse),
+        (status = 404, description = "Response signing is disabled", body = ErrorResponse)
+    ),
+    tag = "Price"
+)]
+/// Returns the Ed25519 public key that signs price responses.
+#[instrument(skip(state))]
+pub async fn get_public_key(
+    State(state): State<AppState>,
+) -> Result<Json<PublicKeyResponse>, ApiError> {
+    let signer = state
+        .response_signer
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Response signing is not enabled".to_string()))?;
+
+    Ok(Json(PublicKeyResponse {
+        algorithm: "ed25519".to_string(),
+        key_id: signer.key_id().to_string(),
+        public_key: STANDARD.encode(signer.public_key()),
+        signature_header: SIGNATURE_HEADER.to_string(),
+        signed_paths: SIGNED_PATHS.iter().map(ToString::to_string).collect(),
+    }))
+}
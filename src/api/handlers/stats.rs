@@ -7,49 +7,77 @@ use axum::{
 use chrono::{DateTime, Duration, Utc};
 use serde::Deserialize;
 use tracing::instrument;
+use utoipa::IntoParams;
 
+use super::price::block_range;
 use crate::api::middleware::error::ApiError;
 use crate::api::models::{StatsPeriod, StatsResponse};
 use crate::app_state::AppState;
+use crate::db::models::PoolScope;
 
 /// Query parameters for statistics.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct StatsQuery {
-    #[serde(default = "default_period")]
-    period: String,
-}
-
-fn default_period() -> String {
-    "24h".to_string()
+    /// Period to aggregate over: 1h, 24h, 7d, 30d or all; defaults to 24h,
+    /// or to all when a block range is given
+    #[serde(default)]
+    period: Option<String>,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    to_block: Option<u64>,
+    /// Quote the prices in the direction opposite to the pool's default
+    #[serde(default)]
+    invert: bool,
 }
 
 #[utoipa::path(
     get,
     path = "/api/v1/stats/{pool}",
     params(
-        ("pool" = String, Path, description = "Pool name")
+        ("pool" = String, Path, description = "Pool name"),
+        StatsQuery
     ),
     responses(
-        (status = 200, description = "Statistics", body = StatsResponse)
+        (status = 200, description = "Statistics", body = StatsResponse),
+        (status = 400, description = "Invalid period or block range", body = ErrorResponse),
+        (status = 404, description = "Pool or price data not found", body = ErrorResponse)
     ),
     tag = "Statistics"
 )]
 /// Returns statistics for a pool over a time period.
-#[instrument(skip(state), fields(pool = %pool_name))]
+///
+/// With `from_block`/`to_block`, only prices within those blocks (and the
+/// period, which then defaults to `all`) are aggregated.
+///
+/// With `invert`, prices are quoted in the direction opposite to the pool's
+/// default; the average is then the reciprocal of the average stored price.
+#[instrument(skip(state, scope), fields(pool = %pool_name))]
 pub async fn get_stats(
     State(state): State<AppState>,
+    scope: PoolScope,
     Path(pool_name): Path<String>,
     Query(query): Query<StatsQuery>,
 ) -> Result<Json<StatsResponse>, ApiError> {
     let pool_name_normalized = pool_name.replace('-', "/");
 
     let pool = state
-        .repository
+        .reader
         .get_pool_by_name(&pool_name_normalized)
         .await?
+        .filter(|pool| scope.allows(pool.id))
         .ok_or_else(|| ApiError::NotFound("Pool not found".to_string()))?;
+    let direction = pool.quote_direction().inverted(query.invert);
+    let blocks = block_range(query.from_block, query.to_block)?;
+    let period = query
+        .period
+        .as_deref()
+        .unwrap_or(if blocks.is_all() { "24h" } else { "all" });
 
-    let (period_enum, from_timestamp) = match query.period.as_str() {
+    let (period_enum, from_timestamp) = match period {
         "1h" => (StatsPeriod::Hour1, Utc::now() - Duration::hours(1)),
         "24h" => (StatsPeriod::Hour24, Utc::now() - Duration::hours(24)),
         "7d" => (StatsPeriod::Day7, Utc::now() - Duration::days(7)),
@@ -62,32 +90,72 @@ pub async fn get_stats(
         }
     };
 
+    // Short periods are served from the in-memory candle book when warm
+    if blocks.is_all() && matches!(period_enum, StatsPeriod::Hour1 | StatsPeriod::Hour24) {
+        if let Some(live) = state
+            .candles
+            .window_stats(pool.id, from_timestamp.timestamp())
+        {
+            let (open, close) = (direction.apply(live.open), direction.apply(live.close));
+            let (low, high) = direction.apply_range(live.low, live.high);
+            let change_percent = if open > 0.0 {
+                ((close - open) / open) * 100.0
+            } else {
+                0.0
+            };
+
+            return Ok(Json(StatsResponse {
+                pool: pool_name_normalized,
+                period: period_enum,
+                current_price: close,
+                high,
+                low,
+                average: direction.apply(live.average),
+                change_percent,
+                quote_direction: direction.to_string(),
+                volume_events: u64::try_from(live.samples).unwrap_or(0),
+                first_timestamp: DateTime::from_timestamp(live.first_timestamp, 0)
+                    .unwrap_or_else(Utc::now),
+                last_timestamp: DateTime::from_timestamp(live.last_timestamp, 0)
+                    .unwrap_or_else(Utc::now),
+            }));
+        }
+    }
+
     let stats_data = state
-        .repository
-        .get_stats_for_period(pool.id, from_timestamp.timestamp())
+        .reader
+        .get_stats_for_period(
+            pool.id,
+            from_timestamp.timestamp(),
+            pool.spike_filter(),
+            blocks,
+        )
         .await?;
 
     let current = state
-        .repository
-        .get_latest_price(pool.id)
+        .latest_price(pool.id)
         .await?
-        .ok_or_else(|| ApiError::NotFound("No price data".to_string()))?;
+        .ok_or_else(|| ApiError::NotFound("No price data".to_string()))?
+        .latest;
 
-    let change_percent = if stats_data.first_price.unwrap_or(0.0) > 0.0 {
-        let first = stats_data.first_price.unwrap_or(0.0);
-        ((current.price - first) / first) * 100.0
+    let current_price = direction.apply(current.price);
+    let first = direction.apply(stats_data.first_price.unwrap_or(0.0));
+    let change_percent = if first > 0.0 {
+        ((current_price - first) / first) * 100.0
     } else {
         0.0
     };
+    let (low, high) = direction.apply_range(stats_data.min_price, stats_data.max_price);
 
     let response = StatsResponse {
         pool: pool_name_normalized,
         period: period_enum,
-        current_price: current.price,
-        high: stats_data.max_price,
-        low: stats_data.min_price,
-        average: stats_data.avg_price,
+        current_price,
+        high,
+        low,
+        average: direction.apply(stats_data.avg_price),
         change_percent,
+        quote_direction: direction.to_string(),
         volume_events: stats_data.total_events as u64,
         first_timestamp: DateTime::from_timestamp(stats_data.first_timestamp, 0)
             .unwrap_or_else(Utc::now),
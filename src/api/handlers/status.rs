@@ -0,0 +1,375 @@
+//! Public status page.
+//!
+//! `/status` summarizes the service for consumers of a hosted API: sync lag
+//! and reorg count per enabled pool, RPC provider health, uptime and the
+//! latest incidents. It is served outside `/api/v1`, so it never needs an
+//! API key. Browsers get a small HTML page, everything else JSON; `?format=`
+//! picks one explicitly.
+//!
+//! Unlike `/health`, the page always answers 200: a lagging pool or an
+//! unreachable RPC shows as `degraded` instead of taking the node out of
+//! rotation.
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use tracing::{instrument, warn};
+
+use super::health::{uptime_secs, CHAIN_HEAD_TIMEOUT};
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{
+    HealthStatus, PoolStatusInfo, RpcStatusInfo, StatusFormat, StatusIncidentInfo, StatusQuery,
+    StatusResponse,
+};
+use crate::app_state::AppState;
+use crate::rpc::get_latest_block;
+
+/// Incidents listed on the page.
+const STATUS_INCIDENTS: i64 = 10;
+
+/// Seconds between reloads of the HTML page.
+const HTML_REFRESH_SECS: u32 = 30;
+
+#[utoipa::path(
+    get,
+    path = "/status",
+    params(StatusQuery),
+    responses(
+        (status = 200, description = "Service status",
+            content(("application/json" = StatusResponse), ("text/html" = String)))
+    ),
+    tag = "Health"
+)]
+/// Returns the public status page, as HTML for browsers and JSON otherwise.
+#[instrument(skip(state, headers))]
+pub async fn get_status(
+    State(state): State<AppState>,
+    Query(query): Query<StatusQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let status = collect_status(&state).await?;
+    let format = query.format.unwrap_or_else(|| negotiate(&headers));
+    Ok(match format {
+        StatusFormat::Json => Json(status).into_response(),
+        StatusFormat::Html => Html(render_html(&status)).into_response(),
+    })
+}
+
+/// Picks HTML when the client accepts it, as browsers do.
+fn negotiate(headers: &HeaderMap) -> StatusFormat {
+    let accepts_html = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+    if accepts_html {
+        StatusFormat::Html
+    } else {
+        StatusFormat::Json
+    }
+}
+
+async fn collect_status(state: &AppState) -> Result<StatusResponse, ApiError> {
+    let (rpc_status, chain_head) = match &state.rpc {
+        Some(provider) => {
+            match tokio::time::timeout(CHAIN_HEAD_TIMEOUT, get_latest_block(provider)).await {
+                Ok(Ok(head)) => ("healthy", Some(head)),
+                Ok(Err(e)) => {
+                    warn!(error = %e, "Chain head lookup failed");
+                    ("unhealthy", None)
+                }
+                Err(_) => {
+                    warn!("Chain head lookup timed out");
+                    ("unhealthy", None)
+                }
+            }
+        }
+        None => ("disabled", None),
+    };
+
+    let all_pools = state.reader.get_all_pools().await?;
+    let names: HashMap<i64, String> = all_pools
+        .iter()
+        .map(|pool| {
+            let name = pool
+                .name
+                .clone()
+                .unwrap_or_else(|| pool.address.to_string());
+            (pool.id, name)
+        })
+        .collect();
+
+    let mut pools = Vec::new();
+    for pool in all_pools.iter().filter(|pool| pool.enabled) {
+        let indexer_state = state.reader.get_state(pool.id).await?;
+        let indexed_block = indexer_state
+            .as_ref()
+            .and_then(|s| u64::try_from(s.last_indexed_block).ok());
+        let lag_blocks = chain_head
+            .zip(indexed_block)
+            .map(|(head, indexed)| head.saturating_sub(indexed));
+        pools.push(PoolStatusInfo {
+            id: pool.id,
+            name: pool
+                .name
+                .clone()
+                .unwrap_or_else(|| pool.address.to_string()),
+            indexed_block,
+            lag_blocks,
+            lagging: lag_blocks.is_some_and(|lag| lag > state.health_max_lag_blocks),
+            reorg_count: indexer_state
+                .as_ref()
+                .map_or(0, |s| u64::try_from(s.reorg_count).unwrap_or(0)),
+            last_synced_at: indexer_state
+                .as_ref()
+                .and_then(|s| DateTime::from_timestamp(s.last_updated_at, 0)),
+        });
+    }
+
+    let incidents = state
+        .reader
+        .get_recent_incidents(STATUS_INCIDENTS)
+        .await?
+        .into_iter()
+        .map(|i| StatusIncidentInfo {
+            pool: names
+                .get(&i.pool_id)
+                .cloned()
+                .unwrap_or_else(|| i.pool_id.to_string()),
+            kind: i.kind,
+            started_at: DateTime::from_timestamp(i.started_at, 0).unwrap_or_else(Utc::now),
+            ended_at: i
+                .ended_at
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            peak_deviation_bps: i.peak_deviation_bps,
+            ongoing: i.ended_block.is_none(),
+        })
+        .collect();
+
+    let degraded = rpc_status == "unhealthy" || pools.iter().any(|pool| pool.lagging);
+    Ok(StatusResponse {
+        status: if degraded {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        },
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_seconds: uptime_secs(state),
+        chain_head,
+        max_lag_blocks: state.health_max_lag_blocks,
+        rpc: RpcStatusInfo {
+            status: rpc_status.to_string(),
+            provider: state
+                .provider_capabilities
+                .as_deref()
+                .and_then(|c| c.provider.clone()),
+            websocket_connected: state.ws_connected.load(Ordering::Relaxed),
+        },
+        pools,
+        incidents,
+        generated_at: Utc::now(),
+    })
+}
+
+/// Renders the status as a self-contained HTML page that reloads itself.
+fn render_html(status: &StatusResponse) -> String {
+    let overall = match status.status {
+        HealthStatus::Healthy => "All systems operational",
+        HealthStatus::Degraded => "Degraded performance",
+        HealthStatus::Unhealthy => "Outage",
+    };
+    let or_dash = |value: Option<u64>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+    let time = |at: DateTime<Utc>| at.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+         <meta http-equiv=\"refresh\" content=\"{HTML_REFRESH_SECS}\">\
+         <title>Status</title><style>\
+         body{{font-family:sans-serif;max-width:56em;margin:2em auto;padding:0 1em}}\
+         table{{border-collapse:collapse;width:100%;margin-bottom:2em}}\
+         th,td{{text-align:left;padding:.3em .6em;border-bottom:1px solid #ddd}}\
+         .healthy{{color:#1a7f37}}.degraded,.lagging{{color:#bf8700}}.unhealthy{{color:#cf222e}}\
+         </style></head><body>\n<h1 class=\"{class}\">{overall}</h1>\n\
+         <p>Version {version}, up {uptime}. Chain head {head}. RPC {rpc}{provider}, \
+         WebSocket {ws}.</p>\n",
+        class = status_class(&status.status),
+        version = escape(&status.version),
+        uptime = format_uptime(status.uptime_seconds),
+        head = or_dash(status.chain_head),
+        rpc = escape(&status.rpc.status),
+        provider = status
+            .rpc
+            .provider
+            .as_deref()
+            .map_or_else(String::new, |p| format!(" ({})", escape(p))),
+        ws = if status.rpc.websocket_connected {
+            "connected"
+        } else {
+            "disconnected"
+        },
+    );
+
+    html.push_str(
+        "<h2>Pools</h2>\n<table><tr><th>Pool</th><th>Indexed block</th><th>Lag (blocks)</th>\
+         <th>Reorgs</th><th>Last sync</th></tr>\n",
+    );
+    for pool in &status.pools {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape(&pool.name),
+            or_dash(pool.indexed_block),
+            if pool.lagging { "lagging" } else { "" },
+            or_dash(pool.lag_blocks),
+            pool.reorg_count,
+            pool.last_synced_at.map_or_else(|| "-".to_string(), time),
+        ));
+    }
+    html.push_str("</table>\n<h2>Recent incidents</h2>\n");
+
+    if status.incidents.is_empty() {
+        html.push_str("<p>No incidents recorded.</p>\n");
+    } else {
+        html.push_str(
+            "<table><tr><th>Pool</th><th>Kind</th><th>Started</th><th>Ended</th>\
+             <th>Peak deviation (bps)</th></tr>\n",
+        );
+        for incident in &status.incidents {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.0}</td></tr>\n",
+                escape(&incident.pool),
+                escape(&incident.kind),
+                time(incident.started_at),
+                incident
+                    .ended_at
+                    .map_or_else(|| "ongoing".to_string(), time),
+                incident.peak_deviation_bps,
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str(&format!(
+        "<p><small>Updated {}. JSON at <a href=\"?format=json\">?format=json</a>.</small></p>\n\
+         </body></html>\n",
+        time(status.generated_at)
+    ));
+    html
+}
+
+const fn status_class(status: &HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Healthy => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Unhealthy => "unhealthy",
+    }
+}
+
+/// Formats seconds as e.g. `3d 4h 5m`.
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3_600, secs % 3_600 / 60);
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// Escapes text for HTML element content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn status() -> StatusResponse {
+        StatusResponse {
+            status: HealthStatus::Degraded,
+            version: "1.0.0".to_string(),
+            uptime_seconds: 90_061,
+            chain_head: Some(1_000),
+            max_lag_blocks: 50,
+            rpc: RpcStatusInfo {
+                status: "healthy".to_string(),
+                provider: Some("Alchemy".to_string()),
+                websocket_connected: true,
+            },
+            pools: vec![PoolStatusInfo {
+                id: 1,
+                name: "<b>WETH/USDT</b>".to_string(),
+                indexed_block: Some(900),
+                lag_blocks: Some(100),
+                lagging: true,
+                reorg_count: 2,
+                last_synced_at: None,
+            }],
+            incidents: vec![StatusIncidentInfo {
+                pool: "USDC/USDT".to_string(),
+                kind: "depeg".to_string(),
+                started_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+                ended_at: None,
+                peak_deviation_bps: 120.4,
+                ongoing: true,
+            }],
+            generated_at: DateTime::from_timestamp(1_700_000_600, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_negotiate_prefers_html_for_browsers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(negotiate(&headers), StatusFormat::Json);
+
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/html,application/xhtml+xml,*/*;q=0.8"),
+        );
+        assert_eq!(negotiate(&headers), StatusFormat::Html);
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+        assert_eq!(negotiate(&headers), StatusFormat::Json);
+    }
+
+    #[test]
+    fn test_render_html_escapes_and_marks_lag() {
+        let html = render_html(&status());
+
+        assert!(html.contains("Degraded performance"));
+        assert!(html.contains("up 1d 1h 1m"));
+        assert!(html.contains("RPC healthy (Alchemy)"));
+        assert!(html.contains("&lt;b&gt;WETH/USDT&lt;/b&gt;"));
+        assert!(!html.contains("<b>WETH"));
+        assert!(html.contains("<td class=\"lagging\">100</td>"));
+        assert!(html.contains("<td>2023-11-14T22:13:20Z</td><td>ongoing</td><td>120</td>"));
+
+        let mut quiet = status();
+        quiet.incidents.clear();
+        assert!(render_html(&quiet).contains("No incidents recorded."));
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(59), "0m");
+        assert_eq!(format_uptime(3_660), "1h 1m");
+        assert_eq!(format_uptime(90_061), "1d 1h 1m");
+    }
+}
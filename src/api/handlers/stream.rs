@@ -3,40 +3,69 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Path, State,
+        Path, Query, State,
     },
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use tracing::{info, instrument, warn};
 
-use crate::api::models::{PriceStreamMessage, ReservesInfo};
+use crate::api::middleware::error::ApiError;
+use crate::api::models::{PriceStreamMessage, ReservesInfo, StreamChannel, StreamQuery};
 use crate::app_state::AppState;
+use crate::db::models::PoolScope;
 use std::sync::atomic::Ordering;
 
 #[utoipa::path(
     get,
     path = "/api/v1/stream/{pool}",
     params(
-        ("pool" = String, Path, description = "Pool name")
+        ("pool" = String, Path, description = "Pool name"),
+        StreamQuery
     ),
     responses(
-        (status = 101, description = "WebSocket upgrade")
+        (status = 101, description = "WebSocket upgrade; the socket then carries JSON `PriceStreamMessage` frames"),
+        (status = 404, description = "Pool outside the API key's scope", body = ErrorResponse)
     ),
     tag = "Streaming"
 )]
 /// WebSocket endpoint for price updates.
-#[instrument(skip(state, ws), fields(pool = %pool_name))]
+///
+/// `channel=preview` (or `all`) also delivers `price_preview` messages with
+/// `is_confirmed: false`, sent while the server runs with `PRICE_PREVIEW` set.
+/// API keys scoped to some pools can only stream those.
+#[instrument(skip(state, ws, scope), fields(pool = %pool_name))]
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     Path(pool_name): Path<String>,
+    Query(query): Query<StreamQuery>,
     State(state): State<AppState>,
+    scope: PoolScope,
 ) -> Response {
-    info!(pool = %pool_name, "WebSocket connection requested");
+    info!(pool = %pool_name, channel = ?query.channel, "WebSocket connection requested");
 
-    ws.on_upgrade(move |socket| handle_socket(socket, pool_name, state))
+    if !scope.is_all() {
+        let visible = match state
+            .reader
+            .get_pool_by_name(&pool_name.replace('-', "/"))
+            .await
+        {
+            Ok(pool) => pool.is_some_and(|pool| scope.allows(pool.id)),
+            Err(e) => return ApiError::from(e).into_response(),
+        };
+        if !visible {
+            return ApiError::NotFound(format!("Pool {pool_name} not found")).into_response();
+        }
+    }
+
+    ws.on_upgrade(move |socket| handle_socket(socket, pool_name, query.channel, state))
 }
 
-async fn handle_socket(mut socket: WebSocket, pool_name: String, state: AppState) {
+async fn handle_socket(
+    mut socket: WebSocket,
+    pool_name: String,
+    channel: StreamChannel,
+    state: AppState,
+) {
     let pool_name_normalized = pool_name.replace('-', "/");
 
     state.ws_connected.store(true, Ordering::Relaxed);
@@ -46,12 +75,14 @@ async fn handle_socket(mut socket: WebSocket, pool_name: String, state: AppState
         event_type: "connected".to_string(),
         pool: pool_name_normalized.clone(),
         price: 0.0,
+        price_ewma: None,
         block_number: 0,
         timestamp: chrono::Utc::now(),
         reserves: ReservesInfo {
             weth: 0.0,
             usdt: 0.0,
         },
+        is_confirmed: true,
     };
 
     if let Ok(json) = serde_json::to_string(&connect_msg) {
@@ -63,7 +94,9 @@ async fn handle_socket(mut socket: WebSocket, pool_name: String, state: AppState
     loop {
         tokio::select! {
             Ok(price_update) = rx.recv() => {
-                if price_update.pool != pool_name_normalized {
+                if price_update.pool != pool_name_normalized
+                    || !channel.carries(price_update.is_confirmed)
+                {
                     continue;
                 }
 
@@ -0,0 +1,319 @@
+//! API key authentication middleware.
+//!
+//! Keys are minted with `eth-uniswap-alloy keys create` and stored as
+//! keccak256 hashes in the `api_keys` table; the plaintext is shown once.
+//! Clients send them as `Authorization: Bearer <key>` or `X-API-Key: <key>`.
+//!
+//! Whether a route needs a key is decided by path prefix under `/api/v1`
+//! (`API_AUTH_REQUIRED_PATHS`, default `/admin`), so the price stream and
+//! read endpoints can stay public while admin writes are protected. A key
+//! presented on a public route is still validated, and authenticated requests
+//! are rate limited per key instead of per IP (see [`super::rate_limit`]).
+//! Invalid keys are counted against the client IP's bucket, and a client IP
+//! whose bucket is empty gets `429` before its key is looked up.
+//!
+//! Keys minted with `keys create --pools` only see those pools: other pools
+//! are left out of listings and answer `404` as if they didn't exist, and
+//! instance-wide endpoints (alerts, the composite index, standby control)
+//! answer `403`. Keys minted with `--chain-id` are rejected with `403` by a
+//! server indexing another chain. Anonymous requests see every pool, so set
+//! `API_AUTH_REQUIRED_PATHS=/` for scopes to separate tenants.
+
+use alloy::primitives::keccak256;
+use axum::{
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::debug;
+
+use super::error::ApiError;
+use super::path_has_prefix;
+use super::rate_limit::{client_ip, rate_limited, RateLimitKey};
+use crate::app_state::AppState;
+use crate::db::models::PoolScope;
+
+/// Prefix of every minted key, so leaked keys are easy to grep for.
+pub const API_KEY_PREFIX: &str = "ethpt_";
+
+/// Number of key characters (after [`API_KEY_PREFIX`]) kept for identification.
+const KEY_ID_CHARS: usize = 8;
+
+/// Authenticated key, inserted into request extensions by [`authenticate`].
+#[derive(Debug, Clone)]
+pub struct AuthenticatedKey {
+    /// Key ID
+    pub id: i64,
+    /// Key name
+    pub name: String,
+    /// Key-specific rate limit, overriding the route group's limit
+    pub rate_limit_rpm: Option<u32>,
+    /// Pools the key may see
+    pub scope: PoolScope,
+}
+
+/// Generates a new random API key.
+#[must_use]
+pub fn generate_api_key() -> String {
+    let bytes: [u8; 24] = rand::random();
+    format!("{API_KEY_PREFIX}{}", alloy::hex::encode(bytes))
+}
+
+/// Hashes an API key for storage and lookup.
+///
+/// Keys carry 192 bits of entropy, so a fast hash is sufficient.
+#[must_use]
+pub fn hash_api_key(key: &str) -> String {
+    alloy::hex::encode(keccak256(key.as_bytes()))
+}
+
+/// Returns the non-secret identifying prefix of a key.
+#[must_use]
+pub fn key_prefix(key: &str) -> String {
+    key.strip_prefix(API_KEY_PREFIX)
+        .unwrap_or(key)
+        .chars()
+        .take(KEY_ID_CHARS)
+        .collect()
+}
+
+/// Per-route key requirements.
+#[derive(Debug)]
+pub struct ApiKeyAuth {
+    required_paths: Vec<String>,
+}
+
+impl ApiKeyAuth {
+    /// Creates an auth policy requiring a key on the given path prefixes
+    /// (relative to `/api/v1`, e.g. `/admin`).
+    #[must_use]
+    pub const fn new(required_paths: Vec<String>) -> Self {
+        Self { required_paths }
+    }
+
+    /// Whether a path (relative to `/api/v1`) requires a key.
+    #[must_use]
+    pub fn requires_key(&self, path: &str) -> bool {
+        self.required_paths
+            .iter()
+            .any(|prefix| path_has_prefix(path, prefix))
+    }
+}
+
+impl Default for ApiKeyAuth {
+    fn default() -> Self {
+        Self::new(vec!["/admin".to_string()])
+    }
+}
+
+/// Rejects keys limited to some pools from an instance-wide endpoint.
+///
+/// # Errors
+///
+/// Returns `403` unless `scope` covers every pool.
+pub fn require_unscoped(scope: &PoolScope, endpoint: &str) -> Result<(), ApiError> {
+    if scope.is_all() {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(format!(
+            "{endpoint} covers every pool; this API key is limited to some pools"
+        )))
+    }
+}
+
+/// Extracts a presented key from `Authorization: Bearer` or `X-API-Key`.
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+}
+
+/// API key authentication middleware.
+///
+/// # Errors
+///
+/// Returns `401` if a required key is missing or a presented key is invalid
+/// or revoked, and `403` if the key is scoped to another chain.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(api_path) = request.uri().path().strip_prefix("/api/v1") else {
+        return Ok(next.run(request).await);
+    };
+    let required = state.api_auth.requires_key(api_path);
+
+    let Some(key) = presented_key(request.headers()) else {
+        if required {
+            return Err(ApiError::Unauthorized("API key required".to_string()));
+        }
+        return Ok(next.run(request).await);
+    };
+
+    // Invalid keys count against the client IP, so a flood of them is turned
+    // away before it reaches the database
+    let ip = RateLimitKey::Ip(client_ip(&request));
+    if let Some(wait) = state
+        .rate_limiter
+        .wait_time(Some(api_path), ip, Instant::now())
+    {
+        return Err(rate_limited(wait));
+    }
+    let Some(row) = state.reader.find_active_api_key(&hash_api_key(key)).await? else {
+        // Only the token matters here; an empty bucket shows on the next try
+        let _ = state
+            .rate_limiter
+            .check(Some(api_path), ip, None, Instant::now());
+        return Err(ApiError::Unauthorized(
+            "Invalid or revoked API key".to_string(),
+        ));
+    };
+
+    if let (Some(key_chain), Some(chain)) = (row.chain_id, state.chain_id) {
+        if u64::try_from(key_chain).ok() != Some(chain) {
+            return Err(ApiError::Forbidden(format!(
+                "API key is scoped to chain {key_chain}; this server indexes chain {chain}"
+            )));
+        }
+    }
+    let scope = state.reader.get_api_key_scope(&row).await?;
+
+    debug!(key = %row.key_prefix, scoped = !scope.is_all(), "Authenticated API request");
+    request.extensions_mut().insert(AuthenticatedKey {
+        id: row.id,
+        name: row.name,
+        rate_limit_rpm: row.rate_limit_rpm.and_then(|rpm| u32::try_from(rpm).ok()),
+        scope,
+    });
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, repository::Repository};
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_generated_keys_are_unique_and_prefixed() {
+        let a = generate_api_key();
+        let b = generate_api_key();
+        assert_ne!(a, b);
+        assert!(a.starts_with(API_KEY_PREFIX));
+        assert_eq!(key_prefix(&a).len(), KEY_ID_CHARS);
+        assert_ne!(hash_api_key(&a), hash_api_key(&b));
+    }
+
+    #[test]
+    fn test_requires_key_matches_path_segments() {
+        let auth = ApiKeyAuth::default();
+        assert!(auth.requires_key("/admin"));
+        assert!(auth.requires_key("/admin/promote"));
+        assert!(!auth.requires_key("/administrator"));
+        assert!(!auth.requires_key("/stream/WETH-USDT"));
+    }
+
+    async fn app() -> (Router, String) {
+        app_with_key_chain(None).await
+    }
+
+    /// Router on chain 1 with one key, restricted to `key_chain` if given.
+    async fn app_with_key_chain(key_chain: Option<u64>) -> (Router, String) {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let key = generate_api_key();
+        let id = repository
+            .insert_api_key("test", &key_prefix(&key), &hash_api_key(&key), Some(1))
+            .await
+            .unwrap();
+        if key_chain.is_some() {
+            repository
+                .set_api_key_scope(id, None, key_chain)
+                .await
+                .unwrap();
+        }
+
+        let state = AppState::new(repository).with_chain_id(1);
+        let router = Router::new()
+            .route("/api/v1/admin/promote", get(|| async { "promoted" }))
+            .route("/api/v1/pools", get(|| async { "pools" }))
+            .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+            .with_state(state);
+        (router, key)
+    }
+
+    async fn status(router: &Router, path: &str, key: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().uri(path);
+        if let Some(key) = key {
+            request = request.header("x-api-key", key);
+        }
+        router
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_key_required_only_on_protected_routes() {
+        let (router, key) = app().await;
+
+        assert_eq!(status(&router, "/api/v1/pools", None).await, StatusCode::OK);
+        assert_eq!(
+            status(&router, "/api/v1/admin/promote", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&router, "/api/v1/pools", Some("ethpt_bogus")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&router, "/api/v1/admin/promote", Some(&key)).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_keys_are_limited_per_ip() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let state = AppState::new(repository).with_rate_limits(2, Vec::new());
+        let router = Router::new()
+            .route("/api/v1/pools", get(|| async { "pools" }))
+            .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+            .with_state(state);
+
+        for _ in 0..2 {
+            assert_eq!(
+                status(&router, "/api/v1/pools", Some("ethpt_bogus")).await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+        assert_eq!(
+            status(&router, "/api/v1/pools", Some("ethpt_bogus")).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_for_other_chain_is_forbidden() {
+        let (router, key) = app_with_key_chain(Some(5)).await;
+        assert_eq!(
+            status(&router, "/api/v1/pools", Some(&key)).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let (router, key) = app_with_key_chain(Some(1)).await;
+        assert_eq!(
+            status(&router, "/api/v1/pools", Some(&key)).await,
+            StatusCode::OK
+        );
+    }
+}
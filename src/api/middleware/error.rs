@@ -1,7 +1,14 @@
 //! Unified API error handling.
+//!
+//! Every error is returned as an RFC 7807 problem document
+//! (`application/problem+json`). Besides the standard `type`, `title`,
+//! `status` and `detail` members it carries the stable `error` code, the
+//! `message` (same as `detail`) and whether the request may be `retryable`.
+//! Errors from the indexer keep their [`TrackerError::code`], so clients can
+//! branch on the same codes as scripts reading the CLI's JSON output.
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -10,6 +17,12 @@ use tracing::error;
 use crate::api::models::ErrorResponse;
 use crate::error::TrackerError;
 
+/// Media type of error responses.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Prefix of the `type` URI of error responses; the error code follows.
+pub const PROBLEM_TYPE_PREFIX: &str = "urn:eth-price-tracker:error:";
+
 /// API-specific error type.
 #[derive(Debug)]
 pub enum ApiError {
@@ -17,63 +30,210 @@ pub enum ApiError {
     NotFound(String),
     /// Invalid request parameters.
     BadRequest(String),
+    /// Missing, invalid or revoked API key.
+    Unauthorized(String),
+    /// API key lacks access to the resource.
+    Forbidden(String),
     /// Internal server error.
     InternalError(String),
     /// Rate limit exceeded.
-    RateLimitExceeded,
+    RateLimitExceeded {
+        /// Seconds until the client may retry.
+        retry_after_secs: u64,
+    },
+    /// Data is temporarily unfit to serve (e.g. a stale price in strict mode).
+    ServiceUnavailable(String),
     /// Database operation failed.
     DatabaseError(String),
+    /// Error from the indexer, classified by its code.
+    Tracker(TrackerError),
+}
+
+impl ApiError {
+    /// Returns the stable code sent as the `error` member.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::BadRequest(_) => "bad_request",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::InternalError(_) => "internal_error",
+            Self::RateLimitExceeded { .. } => "rate_limit_exceeded",
+            Self::ServiceUnavailable(_) => "service_unavailable",
+            Self::DatabaseError(_) => "database_error",
+            Self::Tracker(err) => err.code(),
+        }
+    }
+
+    /// Returns the HTTP status of the response.
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            // Upstream RPC failures: try again later, or the node refused
+            Self::Tracker(
+                err @ (TrackerError::RpcError { .. }
+                | TrackerError::WebSocketConnectionError { .. }
+                | TrackerError::WebSocketSubscriptionError { .. }
+                | TrackerError::WebSocketDisconnected { .. }
+                | TrackerError::MaxReconnectAttemptsExceeded { .. }),
+            ) => {
+                if err.is_retryable() {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::BAD_GATEWAY
+                }
+            }
+            Self::InternalError(_) | Self::DatabaseError(_) | Self::Tracker(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Returns true if the same request may succeed later.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimitExceeded { .. } | Self::ServiceUnavailable(_) => true,
+            Self::Tracker(err) => err.is_retryable(),
+            _ => false,
+        }
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, error_type, message) = match self {
-            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg),
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg),
-            ApiError::RateLimitExceeded => (
-                StatusCode::TOO_MANY_REQUESTS,
-                "rate_limit_exceeded",
-                "Rate limit exceeded. Please try again later.".to_string(),
-            ),
+        let status = self.status();
+        let code = self.code();
+        let retryable = self.is_retryable();
+        let retry_after = match &self {
+            ApiError::RateLimitExceeded { retry_after_secs } => Some(*retry_after_secs),
+            _ => None,
+        };
+
+        let message = match self {
+            ApiError::NotFound(msg)
+            | ApiError::BadRequest(msg)
+            | ApiError::Unauthorized(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::ServiceUnavailable(msg) => msg,
+            ApiError::RateLimitExceeded { retry_after_secs } => {
+                format!("Rate limit exceeded. Retry in {retry_after_secs}s.")
+            }
             ApiError::DatabaseError(msg) => {
                 error!(error = %msg, "Database error in API handler");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "database_error",
-                    "Database operation failed".to_string(),
-                )
+                "Database operation failed".to_string()
             }
             ApiError::InternalError(msg) => {
                 error!(error = %msg, "Internal error in API handler");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "internal_error",
-                    "Internal server error".to_string(),
-                )
+                "Internal server error".to_string()
+            }
+            // Indexer messages may contain RPC URLs with API keys, so only
+            // the log gets them
+            ApiError::Tracker(err) => {
+                error!(error = %err, code, "Indexer error in API handler");
+                match status {
+                    StatusCode::SERVICE_UNAVAILABLE | StatusCode::BAD_GATEWAY => {
+                        "Upstream RPC request failed".to_string()
+                    }
+                    _ if matches!(err, TrackerError::DatabaseError { .. }) => {
+                        "Database operation failed".to_string()
+                    }
+                    _ => "Internal server error".to_string(),
+                }
             }
         };
 
         let body = Json(ErrorResponse {
-            error: error_type.to_string(),
+            problem_type: format!("{PROBLEM_TYPE_PREFIX}{code}"),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: message.clone(),
+            error: code.to_string(),
             message,
+            retryable,
             details: None,
         });
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
 impl From<TrackerError> for ApiError {
     fn from(err: TrackerError) -> Self {
-        match err {
-            TrackerError::DatabaseError { message, .. } => ApiError::DatabaseError(message),
-            _ => ApiError::InternalError(err.to_string()),
-        }
+        ApiError::Tracker(err)
     }
 }
 
 impl From<sqlx::Error> for ApiError {
     fn from(err: sqlx::Error) -> Self {
-        ApiError::DatabaseError(err.to_string())
+        ApiError::Tracker(TrackerError::database("Query failed", Some(Box::new(err))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn problem(err: ApiError) -> (StatusCode, String, serde_json::Value) {
+        let response = err.into_response();
+        let status = response.status();
+        let content_type = response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, content_type, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_problem_json() {
+        let (status, content_type, body) =
+            problem(ApiError::NotFound("Pool not found".to_string())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(content_type, PROBLEM_JSON);
+        assert_eq!(body["type"], "urn:eth-price-tracker:error:not_found");
+        assert_eq!(body["title"], "Not Found");
+        assert_eq!(body["status"], 404);
+        assert_eq!(body["detail"], "Pool not found");
+        assert_eq!(body["error"], "not_found");
+        assert_eq!(body["retryable"], false);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_errors_keep_their_code() {
+        let rpc = TrackerError::rpc("https://rpc.example/v2/secret timed out", None);
+        let (status, _, body) = problem(rpc.into()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "rpc_error");
+        assert_eq!(body["retryable"], true);
+        assert!(!body["detail"].as_str().unwrap().contains("secret"));
+
+        let (status, _, body) = problem(sqlx::Error::PoolTimedOut.into()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "database_error");
+        assert_eq!(body["retryable"], true);
+
+        let (status, _, body) = problem(TrackerError::math("overflow", None).into()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "math_error");
+        assert_eq!(body["retryable"], false);
     }
 }
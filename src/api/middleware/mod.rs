@@ -1,5 +1,13 @@
 //! API middleware components.
 
+pub mod auth;
 pub mod error;
 pub mod logging;
 pub mod rate_limit;
+pub mod signing;
+
+/// Whether `path` equals `prefix` or continues it with a `/` segment.
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
@@ -1,34 +1,317 @@
-//! Rate limiting middleware.
+//! Token-bucket rate limiting middleware.
+//!
+//! Every client gets one bucket per route group: anonymous clients are keyed
+//! by IP address, requests authenticated with an API key by the key. A bucket
+//! holds up to one minute's worth of tokens and refills continuously, so
+//! clients may burst up to their limit and then proceed at the steady rate.
+//!
+//! Route groups are path prefixes under `/api/v1` with their own limit
+//! (`API_RATE_LIMIT_ROUTES`, e.g. `/price=600,/admin=30`); the longest
+//! matching prefix wins and everything else uses the default limit. A key
+//! with its own `rate_limit_rpm` uses that limit in every group. Rejected
+//! requests get `429` with a `Retry-After` header.
 
-use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
-use governor::{
-    clock::DefaultClock,
-    state::{InMemoryState, NotKeyed},
-    Quota, RateLimiter,
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
 };
-use std::num::NonZeroU32;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-/// Shared rate limiter type.
-pub type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>;
+use super::auth::AuthenticatedKey;
+use super::error::ApiError;
+use super::path_has_prefix;
+use crate::app_state::AppState;
 
-/// Create a rate limiter with the specified RPM quota.
-pub fn create_rate_limiter(requests_per_minute: u32) -> SharedRateLimiter {
-    let quota = Quota::per_minute(NonZeroU32::new(requests_per_minute).unwrap_or_else(|| {
-        // Fallback to 60 RPM if invalid
-        NonZeroU32::new(60).unwrap()
-    }));
-    Arc::new(RateLimiter::direct(quota))
+/// Bucket count above which idle (full) buckets are dropped.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// Identity a bucket is keyed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    /// Anonymous client, by IP address
+    Ip(IpAddr),
+    /// Authenticated client, by API key ID
+    ApiKey(i64),
+}
+
+/// A token bucket refilled at `capacity` tokens per minute.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    rpm: u32,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rpm: u32, now: Instant) -> Self {
+        Self {
+            rpm,
+            capacity: f64::from(rpm),
+            tokens: f64::from(rpm),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = elapsed
+            .mul_add(self.capacity / 60.0, self.tokens)
+            .min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Takes one token, or returns how long until one is available.
+    fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - self.tokens;
+            Err(Duration::from_secs_f64(missing * 60.0 / self.capacity))
+        }
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity
+    }
+}
+
+/// Per-route-group token buckets keyed by IP or API key.
+#[derive(Debug)]
+pub struct RateLimiter {
+    default_rpm: u32,
+    routes: Vec<(String, u32)>,
+    buckets: Mutex<HashMap<(usize, RateLimitKey), TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter with a default limit and per-route-group overrides
+    /// (`(prefix, rpm)` pairs, prefixes relative to `/api/v1`).
+    ///
+    /// Zero limits are raised to one request per minute.
+    #[must_use]
+    pub fn new(default_rpm: u32, routes: Vec<(String, u32)>) -> Self {
+        Self {
+            default_rpm: default_rpm.max(1),
+            routes: routes
+                .into_iter()
+                .map(|(prefix, rpm)| (prefix, rpm.max(1)))
+                .collect(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the route group index (0 = default) and its limit for a path
+    /// relative to `/api/v1` (`None` for paths outside the API).
+    fn group(&self, api_path: Option<&str>) -> (usize, u32) {
+        api_path
+            .and_then(|path| {
+                self.routes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (prefix, _))| path_has_prefix(path, prefix))
+                    .max_by_key(|(_, (prefix, _))| prefix.len())
+                    .map(|(i, (_, rpm))| (i + 1, *rpm))
+            })
+            .unwrap_or((0, self.default_rpm))
+    }
+
+    /// Takes a token for `key` on `api_path`.
+    ///
+    /// `key_rpm` overrides the route group's limit (per-key limits).
+    ///
+    /// # Errors
+    ///
+    /// Returns the time until the next token if the bucket is empty.
+    pub fn check(
+        &self,
+        api_path: Option<&str>,
+        key: RateLimitKey,
+        key_rpm: Option<u32>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let (group, group_rpm) = self.group(api_path);
+        let rpm = key_rpm.map_or(group_rpm, |rpm| rpm.max(1));
+
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            buckets.retain(|_, bucket| !bucket.is_full(now));
+        }
+
+        let bucket = buckets
+            .entry((group, key))
+            .or_insert_with(|| TokenBucket::new(rpm, now));
+        // The key's limit was changed since the bucket was created
+        if bucket.rpm != rpm {
+            *bucket = TokenBucket::new(rpm, now);
+        }
+        bucket.try_acquire(now)
+    }
+
+    /// Returns how long until `key` has a token on `api_path` again, without
+    /// taking one (`None` if a request would pass now).
+    pub fn wait_time(
+        &self,
+        api_path: Option<&str>,
+        key: RateLimitKey,
+        now: Instant,
+    ) -> Option<Duration> {
+        let (group, _) = self.group(api_path);
+        let mut buckets = self.buckets.lock().ok()?;
+        let bucket = buckets.get_mut(&(group, key))?;
+        bucket.refill(now);
+        (bucket.tokens < 1.0)
+            .then(|| Duration::from_secs_f64((1.0 - bucket.tokens) * 60.0 / bucket.capacity))
+    }
+}
+
+/// Client IP of a request (unspecified without connection info, e.g. in tests).
+pub(super) fn client_ip(request: &Request) -> IpAddr {
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |info| info.0.ip())
+}
+
+/// `429` asking the client to retry after `wait`, rounded up to whole seconds.
+pub(super) fn rate_limited(wait: Duration) -> ApiError {
+    ApiError::RateLimitExceeded {
+        retry_after_secs: wait.as_secs() + u64::from(wait.subsec_nanos() > 0),
+    }
 }
 
 /// Rate limiting middleware.
+///
+/// Runs after [`super::auth::authenticate`], so authenticated requests are
+/// limited per key and anonymous ones per client IP. Requests presenting an
+/// invalid key are counted against their IP by `authenticate` itself, which
+/// turns them away before the key lookup once the IP's bucket is empty.
+///
+/// # Errors
+///
+/// Returns `429` with `Retry-After` if the client's bucket is empty.
 pub async fn rate_limit(
-    limiter: SharedRateLimiter,
+    State(state): State<AppState>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    match limiter.check() {
-        Ok(_) => Ok(next.run(request).await),
-        Err(_) => Err(StatusCode::TOO_MANY_REQUESTS),
+) -> Result<Response, ApiError> {
+    let (key, key_rpm) = request.extensions().get::<AuthenticatedKey>().map_or_else(
+        || (RateLimitKey::Ip(client_ip(&request)), None),
+        |auth| (RateLimitKey::ApiKey(auth.id), auth.rate_limit_rpm),
+    );
+
+    let api_path = request.uri().path().strip_prefix("/api/v1");
+    if let Err(wait) = state
+        .rate_limiter
+        .check(api_path, key, key_rpm, Instant::now())
+    {
+        return Err(rate_limited(wait));
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, repository::Repository};
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn ip(last: u8) -> RateLimitKey {
+        RateLimitKey::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)))
+    }
+
+    #[test]
+    fn test_bucket_bursts_then_refills() {
+        let limiter = RateLimiter::new(2, Vec::new());
+        let t0 = Instant::now();
+
+        assert!(limiter.check(Some("/pools"), ip(1), None, t0).is_ok());
+        assert!(limiter.check(Some("/pools"), ip(1), None, t0).is_ok());
+        let wait = limiter.check(Some("/pools"), ip(1), None, t0).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(30));
+
+        // Two per minute refills one token every 30 seconds
+        let t1 = t0 + Duration::from_secs(30);
+        assert!(limiter.check(Some("/pools"), ip(1), None, t1).is_ok());
+        assert!(limiter.check(Some("/pools"), ip(1), None, t1).is_err());
+    }
+
+    #[test]
+    fn test_buckets_are_per_client() {
+        let limiter = RateLimiter::new(1, Vec::new());
+        let t0 = Instant::now();
+
+        assert!(limiter.check(None, ip(1), None, t0).is_ok());
+        assert!(limiter.check(None, ip(1), None, t0).is_err());
+        assert!(limiter.check(None, ip(2), None, t0).is_ok());
+        assert!(limiter
+            .check(None, RateLimitKey::ApiKey(7), None, t0)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_route_groups_have_own_limits() {
+        let limiter = RateLimiter::new(
+            1,
+            vec![("/price".to_string(), 3), ("/price/history".to_string(), 2)],
+        );
+        let t0 = Instant::now();
+
+        assert_eq!(limiter.group(Some("/pools")), (0, 1));
+        assert_eq!(limiter.group(Some("/price/current/WETH-USDT")), (1, 3));
+        assert_eq!(limiter.group(Some("/price/history/WETH-USDT")), (2, 2));
+        assert_eq!(limiter.group(Some("/prices")), (0, 1));
+        assert_eq!(limiter.group(None), (0, 1));
+
+        // Exhausting the default group leaves the price group untouched
+        assert!(limiter.check(Some("/pools"), ip(1), None, t0).is_ok());
+        assert!(limiter.check(Some("/pools"), ip(1), None, t0).is_err());
+        assert!(limiter
+            .check(Some("/price/current/WETH-USDT"), ip(1), None, t0)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_key_limit_overrides_group_limit() {
+        let limiter = RateLimiter::new(100, Vec::new());
+        let t0 = Instant::now();
+        let key = RateLimitKey::ApiKey(1);
+
+        assert!(limiter.check(None, key, Some(1), t0).is_ok());
+        assert!(limiter.check(None, key, Some(1), t0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejection_sets_retry_after() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let state = AppState::new(repository).with_rate_limits(1, Vec::new());
+        let router = Router::new()
+            .route("/api/v1/pools", get(|| async { "pools" }))
+            .layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+            .with_state(state);
+
+        let send = || {
+            router
+                .clone()
+                .oneshot(Request::get("/api/v1/pools").body(Body::empty()).unwrap())
+        };
+
+        assert_eq!(send().await.unwrap().status(), StatusCode::OK);
+
+        let response = send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "60");
     }
 }
@@ -0,0 +1,290 @@
+//! Ed25519 signatures on price responses.
+//!
+//! With `RESPONSE_SIGNING_KEY` set, successful JSON responses of the price
+//! endpoints ([`SIGNED_PATHS`]) are re-serialized as canonical JSON (object
+//! keys sorted, no insignificant whitespace) and signed. The signature covers
+//! the exact body bytes and is sent base64-encoded in [`SIGNATURE_HEADER`],
+//! with [`KEY_ID_HEADER`] naming the key. The public key is served at
+//! `/.well-known/pubkey`, so consumers that receive a response second-hand
+//! (from a cache, a queue or another service) can check it with [`verify`].
+//!
+//! Because the body is already canonical, verifiers don't need to
+//! re-serialize it; but a consumer that parsed the body can rebuild the same
+//! bytes with [`canonical_json`].
+//!
+//! The key is the base64 of a 32-byte Ed25519 seed or of a PKCS#8 document,
+//! e.g. `openssl genpkey -algorithm ed25519 -outform DER | base64 -w0`.
+
+use alloy::primitives::keccak256;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde_json::Value;
+use std::fmt;
+
+use super::error::ApiError;
+use super::path_has_prefix;
+use crate::app_state::AppState;
+use crate::error::{TrackerError, TrackerResult};
+
+/// Response header carrying the base64 signature of the body.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Response header carrying the ID of the signing key.
+pub const KEY_ID_HEADER: &str = "x-signature-key-id";
+
+/// Path prefixes whose responses are signed.
+pub const SIGNED_PATHS: [&str; 2] = ["/api/v1/price", "/api/v1/prices"];
+
+/// Signs response bodies with an Ed25519 key.
+pub struct ResponseSigner {
+    key_pair: Ed25519KeyPair,
+    key_id: String,
+}
+
+impl ResponseSigner {
+    /// Loads a key from the base64 of a 32-byte seed or a PKCS#8 document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key isn't valid base64 or a valid Ed25519 key.
+    pub fn from_base64(key: &str) -> TrackerResult<Self> {
+        let invalid =
+            |what: &str| TrackerError::config(format!("RESPONSE_SIGNING_KEY is not {what}"), None);
+        let bytes = STANDARD
+            .decode(key.trim())
+            .map_err(|_| invalid("valid base64"))?;
+        let key_pair = if bytes.len() == 32 {
+            Ed25519KeyPair::from_seed_unchecked(&bytes)
+        } else {
+            Ed25519KeyPair::from_pkcs8_maybe_unchecked(&bytes)
+        }
+        .map_err(|_| invalid("a 32-byte Ed25519 seed or PKCS#8 Ed25519 key"))?;
+
+        let key_id = alloy::hex::encode(&keccak256(key_pair.public_key().as_ref())[..8]);
+        Ok(Self { key_pair, key_id })
+    }
+
+    /// Returns the raw 32-byte public key.
+    #[must_use]
+    pub fn public_key(&self) -> &[u8] {
+        self.key_pair.public_key().as_ref()
+    }
+
+    /// Returns the key ID: the first 8 bytes of the public key's keccak256,
+    /// in hex.
+    #[must_use]
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Signs `message`, returning the base64 signature.
+    #[must_use]
+    pub fn sign(&self, message: &[u8]) -> String {
+        STANDARD.encode(self.key_pair.sign(message))
+    }
+}
+
+impl fmt::Debug for ResponseSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseSigner")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Checks a base64 `signature` of `message` against a raw Ed25519 public key.
+#[must_use]
+pub fn verify(public_key: &[u8], message: &[u8], signature: &str) -> bool {
+    STANDARD.decode(signature.trim()).is_ok_and(|signature| {
+        UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(message, &signature)
+            .is_ok()
+    })
+}
+
+/// Serializes `value` with object keys sorted and no whitespace.
+#[must_use]
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Middleware that canonicalizes and signs successful JSON responses under
+/// [`SIGNED_PATHS`], when a signing key is configured.
+///
+/// # Errors
+///
+/// Returns an error if the response body can't be read or isn't valid JSON.
+pub async fn sign_responses(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let signer = state.response_signer.clone().filter(|_| {
+        SIGNED_PATHS
+            .iter()
+            .any(|prefix| path_has_prefix(request.uri().path(), prefix))
+    });
+    let response = next.run(request).await;
+    let Some(signer) = signer else {
+        return Ok(response);
+    };
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if response.status() != StatusCode::OK || !is_json {
+        return Ok(response);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to read response body: {e}")))?;
+    let value: Value = serde_json::from_slice(&bytes)
+        .map_err(|e| ApiError::InternalError(format!("Response body is not JSON: {e}")))?;
+    let canonical = canonical_json(&value);
+
+    let signature = signer.sign(canonical.as_bytes());
+    for (name, value) in [
+        (SIGNATURE_HEADER, signature.as_str()),
+        (KEY_ID_HEADER, signer.key_id()),
+    ] {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            parts.headers.insert(name, value);
+        }
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, Body::from(canonical)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, repository::Repository};
+    use axum::{middleware, routing::get, Json, Router};
+    use tower::ServiceExt;
+
+    /// RFC 8032 test vector 1.
+    const SEED: &str = "nWGxne/9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A=";
+    const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    #[test]
+    fn test_signer_loads_seed_and_signs_verifiably() {
+        let signer = ResponseSigner::from_base64(SEED).unwrap();
+        assert_eq!(alloy::hex::encode(signer.public_key()), PUBLIC_KEY);
+        assert_eq!(signer.key_id().len(), 16);
+
+        let signature = signer.sign(b"{\"price\":2000.5}");
+        assert!(verify(
+            signer.public_key(),
+            b"{\"price\":2000.5}",
+            &signature
+        ));
+        assert!(!verify(
+            signer.public_key(),
+            b"{\"price\":2000.6}",
+            &signature
+        ));
+        assert!(!verify(
+            signer.public_key(),
+            b"{\"price\":2000.5}",
+            "not base64"
+        ));
+
+        assert!(ResponseSigner::from_base64("AAAA").is_err());
+        assert!(ResponseSigner::from_base64("!").is_err());
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys_recursively() {
+        let value: Value =
+            serde_json::from_str(r#"{ "b": [ { "z": 1, "a": "x\"y" } ], "a": null }"#).unwrap();
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":null,"b":[{"a":"x\"y","z":1}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_price_responses_are_signed() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let signer = ResponseSigner::from_base64(SEED).unwrap();
+        let public_key = signer.public_key().to_vec();
+        let state = AppState::new(repository).with_response_signer(signer);
+        let body = || async { Json(serde_json::json!({ "pool": "WETH/USDT", "price": 2000.5 })) };
+        let router = Router::new()
+            .route("/api/v1/price/latest/:pool", get(body))
+            .route("/api/v1/pools", get(body))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                sign_responses,
+            ))
+            .with_state(state);
+
+        let get = |path: &'static str| {
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(Request::builder().uri(path).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let signature = response
+                    .headers()
+                    .get(SIGNATURE_HEADER)
+                    .map(|v| v.to_str().unwrap().to_string());
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (signature, body)
+            }
+        };
+
+        let (signature, body) = get("/api/v1/price/latest/WETH-USDT").await;
+        assert_eq!(&body[..], br#"{"pool":"WETH/USDT","price":2000.5}"#);
+        assert!(verify(&public_key, &body, &signature.unwrap()));
+
+        let (signature, _) = get("/api/v1/pools").await;
+        assert_eq!(signature, None);
+    }
+}
@@ -1,8 +1,15 @@
 //! HTTP API module for exposing indexed data via REST and WebSocket.
 
+pub mod conditional;
+pub mod cors;
 pub mod docs;
 pub mod extractors;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod handlers;
 pub mod middleware;
 pub mod models;
+pub mod pagination;
 pub mod server;
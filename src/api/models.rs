@@ -4,24 +4,85 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 
+use crate::db::models::PricePointRow;
+use crate::price_cache::PriceCacheStats;
+use crate::pricing::QuoteDirection;
+use crate::rpc::ProviderCapabilities;
+use crate::watchdog::LagWatchdogStats;
+
 /// API response for current price.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CurrentPriceResponse {
+    /// Deterministic ID, stable across re-indexes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Pool identifier (e.g., "WETH/USDT")
     pub pool: String,
-    /// Current ETH/USDT price
+    /// Current price, in `quote_direction`
     pub price: f64,
+    /// Exact price as a decimal string (18 decimal places)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_exact: Option<String>,
+    /// EWMA-smoothed price (absent when smoothing is disabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_ewma: Option<f64>,
     /// Block number where this price was recorded
     pub block_number: u64,
     /// Block timestamp (ISO 8601)
     pub timestamp: DateTime<Utc>,
-    /// Transaction hash
+    /// Transaction hash (all zeros for `call` prices)
     pub tx_hash: String,
+    /// `event` for a price from a Sync event, `call` for a `getReserves()`
+    /// snapshot taken while the pool had no events
+    pub source: String,
     /// Reserve amounts
     pub reserves: ReservesInfo,
     /// 24-hour price change percentage
     #[serde(skip_serializing_if = "Option::is_none")]
     pub change_24h: Option<f64>,
+    /// True when the price is older than the staleness threshold
+    pub stale: bool,
+    /// Seconds since the block that produced this price
+    pub age_seconds: u64,
+    /// Direction the prices are quoted in: `token1_per_token0` (as stored)
+    /// or `token0_per_token1`
+    pub quote_direction: String,
+    /// Unit `price` was converted to (absent unless `denominate` was given)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denomination: Option<String>,
+    /// Pools the price was converted through, in order (empty when the pool
+    /// is quoted in the requested unit already)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversion: Option<Vec<ConversionStep>>,
+}
+
+/// One pool a denominated price was converted through.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConversionStep {
+    /// Pool name
+    pub pool: String,
+    /// Token converted from
+    pub from: String,
+    /// Token converted to
+    pub to: String,
+    /// Units of `to` per unit of `from`, from the pool's latest price
+    pub rate: f64,
+    /// Block of the pool price `rate` comes from
+    pub block_number: u64,
+}
+
+/// Query parameters for the current price.
+#[derive(Debug, Default, Deserialize, ToSchema, IntoParams)]
+pub struct CurrentPriceQuery {
+    /// Return 503 instead of a stale price
+    #[serde(default)]
+    pub strict: bool,
+    /// Quote the price in the direction opposite to the pool's default
+    #[serde(default)]
+    pub invert: bool,
+    /// Convert the price to this unit (`usd`) through other indexed pools
+    #[serde(default)]
+    pub denominate: Option<String>,
 }
 
 /// Reserve amounts for a pool.
@@ -37,39 +98,103 @@ pub struct ReservesInfo {
 /// Historical price point.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PricePoint {
+    /// Deterministic ID, stable across re-indexes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Block number where this price was recorded
     pub block_number: u64,
     /// Block timestamp (ISO 8601)
     pub timestamp: DateTime<Utc>,
-    /// ETH/USDT price
+    /// Price, in the requested quote direction
     pub price: f64,
-    /// Transaction hash
+    /// Exact price as a decimal string (18 decimal places)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_exact: Option<String>,
+    /// Transaction hash (all zeros for `call` prices)
     pub tx_hash: String,
+    /// `event` (Sync event) or `call` (`getReserves()` snapshot)
+    pub source: String,
     /// Reserve amounts
     pub reserves: ReservesInfo,
 }
 
-/// Paginated response wrapper.
-/// Paginated response wrapper.
+/// Request body for prices at a list of blocks.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
-pub struct PaginatedResponse<T> {
-    /// Response data
+pub struct PricesAtBlocksRequest {
+    /// Pool ID, address or name (e.g. "WETH-USDT")
+    pub pool: String,
+    /// Block numbers to price (at most 1000)
+    pub blocks: Vec<u64>,
+    /// Quote the prices in the direction opposite to the pool's default
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// Prices at a list of blocks.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PricesAtBlocksResponse {
+    /// Pool name
+    pub pool: String,
+    /// One entry per requested block, in request order
+    pub prices: Vec<PriceAtBlock>,
+    /// Direction the prices are quoted in
+    pub quote_direction: String,
+}
+
+/// Nearest prior price for one requested block.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PriceAtBlock {
+    /// Requested block number
+    pub block: u64,
+    /// Last confirmed price at or before the block (absent if none)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<PricePoint>,
+}
+
+/// Response envelope shared by paginated list endpoints.
+///
+/// `next` and `prev` are also sent in a `Link` header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    /// Items on this page
     pub data: Vec<T>,
     /// Pagination metadata
     pub pagination: PaginationInfo,
 }
 
 /// Pagination metadata.
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct PaginationInfo {
-    /// Current page number
-    pub page: u32,
-    /// Items per page
-    pub page_size: u32,
-    /// Total number of items
-    pub total_count: u64,
-    /// Whether there is another page
-    pub has_next_page: bool,
+    /// Total number of items across all pages
+    pub total: u64,
+    /// Maximum number of items per page
+    pub limit: u32,
+    /// Items skipped before this page (offset-paginated endpoints)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Cursor this page starts after (cursor-paginated endpoints)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// URL of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    /// URL of the previous page, absent on the first page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+}
+
+/// Query parameters for offset-paginated listings.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct PageQuery {
+    /// Items per page (max 1000)
+    #[serde(default = "default_page_size")]
+    pub limit: u32,
+    /// Items to skip
+    #[serde(default)]
+    pub offset: u64,
 }
 
 /// Query parameters for historical prices.
@@ -81,22 +206,590 @@ pub struct HistoryQuery {
     /// End timestamp (ISO 8601) or UNIX timestamp
     #[serde(default)]
     pub to: Option<String>,
-    /// Page number (1-indexed)
-    #[serde(default = "default_page")]
-    pub page: u32,
-    /// Items per page (max 1000)
-    #[serde(default = "default_page_size")]
-    pub page_size: u32,
-}
-
-fn default_page() -> u32 {
-    1
+    /// First block to include (inclusive)
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    pub to_block: Option<u64>,
+    /// Items per page (max 1000); `page_size` is accepted as an alias
+    #[serde(default = "default_page_size", alias = "page_size")]
+    pub limit: u32,
+    /// Items to skip
+    #[serde(default)]
+    pub offset: Option<u64>,
+    /// Page number (1-indexed), used when `offset` is not set
+    #[serde(default)]
+    pub page: Option<u32>,
+    /// Downsample the whole range to at most this many points (3-10000) for
+    /// charting, instead of returning a page
+    #[serde(default)]
+    pub points: Option<u32>,
+    /// Quote the prices in the direction opposite to the pool's default
+    #[serde(default)]
+    pub invert: bool,
 }
 
 fn default_page_size() -> u32 {
     100
 }
 
+/// Query parameters for a price history export.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct HistoryExportQuery {
+    /// Start timestamp (ISO 8601) or UNIX timestamp
+    #[serde(default)]
+    pub from: Option<String>,
+    /// End timestamp (ISO 8601) or UNIX timestamp
+    #[serde(default)]
+    pub to: Option<String>,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    pub to_block: Option<u64>,
+    /// Output format: "ndjson" (default) or "csv"
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Quote the prices in the direction opposite to the pool's default
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// Streamed export formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON price point per line (`application/x-ndjson`)
+    #[default]
+    Ndjson,
+    /// A header row, then one price point per row (`text/csv`)
+    Csv,
+}
+
+/// Query parameters for a swap quote.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct QuoteQuery {
+    /// Input amount in whole tokens (e.g. "1.5")
+    pub amount_in: String,
+    /// Input token, by symbol or address
+    pub token: String,
+}
+
+/// Simulated swap against the pool's latest reserves.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuoteResponse {
+    /// Pool name
+    pub pool: String,
+    /// Block whose reserves the quote is based on
+    pub block_number: u64,
+    /// Input token symbol
+    pub token_in: String,
+    /// Output token symbol
+    pub token_out: String,
+    /// Input amount in whole tokens
+    pub amount_in: String,
+    /// Output amount in whole tokens
+    pub amount_out: String,
+    /// Price before the trade (output tokens per input token)
+    pub spot_price: f64,
+    /// Effective price of the trade (output tokens per input token)
+    pub execution_price: f64,
+    /// Execution price shortfall relative to the spot price, in percent
+    /// (includes the swap fee)
+    pub price_impact_pct: f64,
+    /// Swap fee in basis points
+    pub fee_bps: u32,
+}
+
+/// Query parameters for the best route between two tokens.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct RouteQuery {
+    /// Token to sell, by symbol or address
+    pub from: String,
+    /// Token to buy, by symbol or address
+    pub to: String,
+    /// Amount of `from` to sell, in whole tokens (e.g. "1.5")
+    pub amount: String,
+}
+
+/// Best route between two tokens over the indexed pools.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RouteResponse {
+    /// Token sold
+    pub from: String,
+    /// Token bought
+    pub to: String,
+    /// Amount sold in whole tokens
+    pub amount_in: String,
+    /// Amount received in whole tokens
+    pub amount_out: String,
+    /// Price before the swaps (`to` per `from`)
+    pub spot_price: f64,
+    /// Effective price of the swaps (`to` per `from`)
+    pub execution_price: f64,
+    /// Execution price shortfall relative to the spot price, in percent
+    /// (includes every pool's fee)
+    pub price_impact_pct: f64,
+    /// Pools swapped through, in order
+    pub hops: Vec<RouteHop>,
+    /// Routes simulated to find this one
+    pub routes_considered: usize,
+}
+
+/// One swap of a route.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RouteHop {
+    /// Pool name
+    pub pool: String,
+    /// Block whose reserves the swap is simulated at
+    pub block_number: u64,
+    /// Token sold into the pool
+    pub token_in: String,
+    /// Token received from the pool
+    pub token_out: String,
+    /// Amount sold in whole tokens
+    pub amount_in: String,
+    /// Amount received in whole tokens
+    pub amount_out: String,
+    /// Effective price of the swap (output tokens per input token)
+    pub execution_price: f64,
+    /// Execution price shortfall relative to the pool's spot price, in percent
+    pub price_impact_pct: f64,
+    /// Swap fee in basis points
+    pub fee_bps: u32,
+}
+
+/// An LP position to value, opened at `entry_block` or at the given entry
+/// reserves.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ImpermanentLossQuery {
+    /// Block the position was opened at; its entry price is the last indexed
+    /// price at or before it
+    pub entry_block: Option<u64>,
+    /// Token0 reserve when the position was opened, in whole tokens (with
+    /// `entry_reserve1`, instead of `entry_block`)
+    pub entry_reserve0: Option<f64>,
+    /// Token1 reserve when the position was opened, in whole tokens
+    pub entry_reserve1: Option<f64>,
+    /// Token0 deposited (default: 1); token1 is matched at the entry price
+    pub amount0: Option<f64>,
+    /// Token1 deposited, instead of `amount0`
+    pub amount1: Option<f64>,
+}
+
+/// Token amounts held by an LP position, in whole tokens.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PositionAmounts {
+    /// Token0
+    pub amount0: f64,
+    /// Token1
+    pub amount1: f64,
+}
+
+/// An LP position valued at the pool's latest price.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImpermanentLossResponse {
+    /// Pool name
+    pub pool: String,
+    /// Block of the entry price (absent for entry reserves)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_block: Option<u64>,
+    /// Price when the position was opened (token0 in token1)
+    pub entry_price: f64,
+    /// Block of the latest confirmed price
+    pub block_number: u64,
+    /// Latest confirmed price (token0 in token1)
+    pub price: f64,
+    /// Price change since entry, in percent
+    pub price_change_pct: f64,
+    /// Tokens deposited
+    pub deposited: PositionAmounts,
+    /// Tokens the position holds now, fees aside
+    pub current: PositionAmounts,
+    /// Position value in token1
+    pub value: f64,
+    /// Value in token1 of the deposited tokens, had they been held
+    pub hold_value: f64,
+    /// Impermanent loss in percent (zero or negative)
+    pub impermanent_loss_pct: f64,
+    /// Share of the pool's current reserves the position holds
+    pub pool_share: f64,
+}
+
+/// Query parameters for historical reserves.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ReservesAtQuery {
+    /// Block number to reconstruct reserves at
+    pub block: u64,
+}
+
+/// Where historical reserves were read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReserveSource {
+    /// The last indexed Sync event at or before the block
+    Indexed,
+    /// A `getReserves()` call against the node at the block
+    Archive,
+}
+
+/// A pool's reserves as of a block.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReservesAtResponse {
+    /// Pool name
+    pub pool: String,
+    /// Requested block
+    pub block: u64,
+    /// Block of the Sync event that set these reserves (indexed source only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_block: Option<u64>,
+    /// Transaction of the Sync event that set these reserves (indexed source only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    /// Where the reserves were read from
+    pub source: ReserveSource,
+    /// Token0 reserve
+    pub reserve0: ReserveAmount,
+    /// Token1 reserve
+    pub reserve1: ReserveAmount,
+    /// Exact price of token0 in token1 at these reserves (absent if a
+    /// reserve is zero)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_exact: Option<String>,
+}
+
+/// Query parameters for a point-in-time price.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct PriceAtQuery {
+    /// Block number to price at
+    #[serde(default)]
+    pub block: Option<u64>,
+    /// Timestamp (ISO 8601) or UNIX timestamp to price at, instead of `block`
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    /// Quote the price in the direction opposite to the pool's default
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// The last known price of a pool at a block or timestamp.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PriceAtResponse {
+    /// Pool name
+    pub pool: String,
+    /// Requested block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<u64>,
+    /// Requested timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Where the price was read from
+    pub source: ReserveSource,
+    /// Price, in the requested quote direction
+    pub price: f64,
+    /// Exact price as a decimal string (18 decimal places)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_exact: Option<String>,
+    /// Block the price was set in (indexed source), or read at (archive source)
+    pub price_block: u64,
+    /// Timestamp of `price_block`, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_timestamp: Option<DateTime<Utc>>,
+    /// Transaction that set the price (indexed source only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    /// Direction the price is quoted in
+    pub quote_direction: String,
+}
+
+/// Query parameters for the intra-block price path.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct PricePathQuery {
+    /// First block of the range
+    pub from_block: u64,
+    /// Last block of the range (default: `from_block`; at most 100 blocks)
+    pub to_block: Option<u64>,
+}
+
+/// Every price a pool passed through within a range of blocks.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PricePathResponse {
+    /// Pool name
+    pub pool: String,
+    /// First block of the range
+    pub from_block: u64,
+    /// Last block of the range
+    pub to_block: u64,
+    /// Blocks with at least one Sync event, in order
+    pub blocks: Vec<BlockPricePath>,
+}
+
+/// The prices set by each Sync event of one block.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BlockPricePath {
+    /// Block number
+    pub block_number: u64,
+    /// Block timestamp (unix seconds)
+    pub block_timestamp: u64,
+    /// Sync events in log order
+    pub steps: Vec<PricePathStep>,
+    /// Price at the end of the block (the last step's price)
+    pub final_price: f64,
+    /// Exact price at the end of the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_price_exact: Option<String>,
+}
+
+/// The price set by one Sync event.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PricePathStep {
+    /// Transaction that emitted the event
+    pub tx_hash: String,
+    /// Log index within the block
+    pub log_index: u64,
+    /// Price of token0 in token1 after the event
+    pub price: f64,
+    /// Exact price after the event (absent if a reserve is zero)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_exact: Option<String>,
+    /// Raw token0 reserve after the event
+    pub reserve0: String,
+    /// Raw token1 reserve after the event
+    pub reserve1: String,
+    /// True for the block's last event, whose price is the block's price
+    pub is_final: bool,
+}
+
+/// Query parameters for a bucketed price time series.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct TimeseriesQuery {
+    /// Bucket width: a number with an `s`, `m`, `h` or `d` suffix (default `5m`)
+    pub bucket: Option<String>,
+    /// Reduction of each bucket's prices: `avg` (default), `last` or `max`
+    pub agg: Option<String>,
+    /// Start of the range (unix milliseconds, default 24 hours before `to`)
+    pub from: Option<i64>,
+    /// End of the range (unix milliseconds, default now)
+    pub to: Option<i64>,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    pub to_block: Option<u64>,
+    /// Quote the prices in the direction opposite to the pool's default
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// Bucketed prices of a pool, in the shape Grafana's JSON data sources read.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TimeseriesResponse {
+    /// Pool name
+    pub pool: String,
+    /// Bucket width in seconds
+    pub bucket_secs: u64,
+    /// Aggregation applied to each bucket
+    pub agg: String,
+    /// Direction the prices are quoted in
+    pub quote_direction: String,
+    /// `[timestamp, value]` pairs, oldest first: bucket start in unix
+    /// milliseconds and the aggregated price. Empty buckets are omitted.
+    #[schema(value_type = Vec<Vec<f64>>)]
+    pub points: Vec<(i64, f64)>,
+}
+
+/// Query parameters for trader analytics.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct AnalyticsQuery {
+    /// Days of history to cover (default 7, at most 90)
+    pub days: Option<u32>,
+    /// Number of top traders to return (default 10, at most 100)
+    pub limit: Option<u32>,
+    /// Only report this trader address
+    pub address: Option<String>,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    pub to_block: Option<u64>,
+}
+
+/// Trader analytics for a pool, from confirmed Swap events.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnalyticsResponse {
+    /// Pool name
+    pub pool: String,
+    /// Days of history covered
+    pub days: u32,
+    /// Start of the window (unix seconds)
+    pub since: i64,
+    /// Latest pool price used to mark open positions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark_price: Option<f64>,
+    /// Traders by token1 volume, largest first
+    pub top_traders: Vec<TraderStats>,
+    /// Activity per UTC day, oldest first
+    pub daily: Vec<DailyTraders>,
+}
+
+/// Swap activity and P&L estimate for one trader (swap recipient).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TraderStats {
+    /// Trader address
+    pub address: String,
+    /// Number of swaps
+    pub swaps: u64,
+    /// Token1 volume (paid in plus received)
+    pub volume: f64,
+    /// Token0 received minus token0 paid in
+    pub net_token0: f64,
+    /// Token1 received minus token1 paid in
+    pub net_token1: f64,
+    /// Net flows valued in token1 at the mark price
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pnl_estimate: Option<f64>,
+}
+
+/// Swap activity for one UTC day.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DailyTraders {
+    /// Day (YYYY-MM-DD, UTC)
+    pub date: String,
+    /// Distinct traders
+    pub unique_traders: u64,
+    /// Number of swaps
+    pub swaps: u64,
+    /// Token1 volume
+    pub volume: f64,
+}
+
+/// Query parameters for sandwich detection.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct SandwichQuery {
+    /// Days of history to scan (default 7, at most 90)
+    pub days: Option<u32>,
+    /// Number of most recent sandwiches to list (default 20, at most 100)
+    pub limit: Option<u32>,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    pub to_block: Option<u64>,
+}
+
+/// Likely sandwich attacks on a pool, from the order of confirmed swaps
+/// within each block.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandwichResponse {
+    /// Pool name
+    pub pool: String,
+    /// Days of history scanned
+    pub days: u32,
+    /// Start of the window (unix seconds)
+    pub since: i64,
+    /// Latest pool price used to value token0 profits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark_price: Option<f64>,
+    /// Sandwiches detected
+    pub sandwiches: u64,
+    /// Victim swaps across all sandwiches
+    pub victims: u64,
+    /// Distinct attacker addresses
+    pub attackers: u64,
+    /// Total attacker profit, in token1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profit: Option<f64>,
+    /// Most recent sandwiches, newest first
+    pub recent: Vec<SandwichInfo>,
+}
+
+/// One detected sandwich.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandwichInfo {
+    /// Block of the three legs
+    pub block_number: i64,
+    /// Block timestamp (unix seconds)
+    pub timestamp: i64,
+    /// Recipient of the front-run and back-run
+    pub attacker: String,
+    /// Token bought by the front-run and the victims
+    /// (`buy_token0` or `buy_token1`)
+    pub side: String,
+    /// Front-run transaction
+    pub front_run_tx: String,
+    /// Victim transactions, in block order
+    pub victim_txs: Vec<String>,
+    /// Back-run transaction
+    pub back_run_tx: String,
+    /// Attacker profit, in token1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profit: Option<f64>,
+}
+
+/// Query parameters for fee APR estimates.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct FeeAprQuery {
+    /// Report a single window of this many days (at most 365) instead of
+    /// the default 1, 7 and 30-day windows
+    pub days: Option<u32>,
+}
+
+/// Estimated LP fee APR of a pool, from confirmed Swap events.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeAprResponse {
+    /// Pool name
+    pub pool: String,
+    /// Swap fee paid to liquidity providers, in basis points
+    pub fee_bps: u32,
+    /// Latest pool price (token0 in token1) fees are valued at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    /// Estimates per trailing window, shortest first
+    pub windows: Vec<FeeAprWindow>,
+}
+
+/// Fee earnings over one trailing window.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeAprWindow {
+    /// Window length in days
+    pub days: u32,
+    /// Start of the window (unix seconds)
+    pub since: i64,
+    /// Seconds of the window covered by indexed prices; APR is annualized
+    /// over these
+    pub covered_secs: i64,
+    /// Number of swaps
+    pub swaps: u64,
+    /// Swap inputs valued in token1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+    /// Fees earned by liquidity providers, in token1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees: Option<f64>,
+    /// Average pool liquidity, in token1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_liquidity: Option<f64>,
+    /// Annualized fees as a percentage of liquidity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apr: Option<f64>,
+}
+
+/// A reserve balance in raw and decimal-adjusted form.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReserveAmount {
+    /// Token symbol
+    pub symbol: String,
+    /// Amount in the token's smallest units
+    pub raw: String,
+    /// Amount in whole tokens
+    pub amount: String,
+}
+
 /// Pool information.
 /// Pool metadata for API responses.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -109,10 +802,42 @@ pub struct PoolInfo {
     pub token0: TokenInfo,
     /// Token1 metadata
     pub token1: TokenInfo,
+    /// Protocol that deployed the pool (e.g. `uniswap_v2`, `sushiswap`)
+    pub protocol: String,
+    /// Swap fee in basis points
+    pub fee_bps: u32,
+    /// Pricing formula (`constant_product` or `stable_swap:<A>:<fee_bps>`)
+    pub pool_type: String,
+    /// Default direction prices are quoted in (`token1_per_token0` or
+    /// `token0_per_token1`)
+    pub quote_direction: String,
+    /// Whether the pool is indexed (disabled pools keep their data)
+    pub enabled: bool,
+    /// First block indexed when the pool has no checkpoint
+    pub start_block: Option<u64>,
+    /// Deviation in basis points from which reverted single-block spikes
+    /// are left out of candles and stats (absent if spikes are kept)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spike_filter_bps: Option<u32>,
     /// Last indexed block number
     pub last_indexed_block: u64,
     /// Total events processed
     pub total_events: u64,
+    /// Status of the pool's `watch` task (absent if it never ran)
+    pub indexer: Option<PoolTaskInfo>,
+}
+
+/// Status of a pool's `watch` task.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolTaskInfo {
+    /// `starting`, `running`, `failing`, `restarting`, `disabled` or `stopped`
+    pub status: String,
+    /// Times the task was restarted after failing
+    pub restarts: u64,
+    /// Error of the last failed pass, if it hasn't succeeded since
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last status update
+    pub updated_at: i64,
 }
 
 /// Token metadata.
@@ -144,6 +869,8 @@ pub struct StatsResponse {
     pub average: f64,
     /// Percentage change from first to last
     pub change_percent: f64,
+    /// Direction the prices are quoted in
+    pub quote_direction: String,
     /// Number of events in period
     pub volume_events: u64,
     /// Timestamp of first event in period
@@ -152,6 +879,38 @@ pub struct StatsResponse {
     pub last_timestamp: DateTime<Utc>,
 }
 
+/// OHLC candle built in memory from confirmed prices.
+///
+/// Prices are null for an empty bucket with `fill=null`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CandleInfo {
+    /// Bucket start (aligned to the interval)
+    pub bucket_start: DateTime<Utc>,
+    /// First price in the bucket
+    pub open: Option<f64>,
+    /// Highest price in the bucket
+    pub high: Option<f64>,
+    /// Lowest price in the bucket
+    pub low: Option<f64>,
+    /// Last price in the bucket
+    pub close: Option<f64>,
+    /// Number of prices in the bucket; 0 for an empty bucket
+    pub samples: u64,
+}
+
+/// Recent candles for a pool.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CandlesResponse {
+    /// Pool name
+    pub pool: String,
+    /// Candle width in seconds
+    pub interval_secs: u64,
+    /// Direction the prices are quoted in
+    pub quote_direction: String,
+    /// Candles, oldest first
+    pub candles: Vec<CandleInfo>,
+}
+
 /// Supported statistics periods.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
@@ -180,10 +939,221 @@ pub struct HealthResponse {
     pub uptime_seconds: u64,
     /// Last indexed block number
     pub indexed_block: u64,
+    /// Latest block on chain (absent when the RPC is disabled or unreachable)
+    pub chain_head: Option<u64>,
+    /// Blocks between the last indexed block and the chain head
+    pub lag_blocks: Option<u64>,
+    /// Seconds between the last indexed block and the chain head
+    pub lag_seconds: Option<u64>,
+    /// Lag in blocks above which the service reports 503
+    pub max_lag_blocks: u64,
+    /// Chain reorganizations detected by the indexer
+    pub reorg_count: u64,
     /// Database status
     pub database_status: String,
     /// WebSocket status
     pub websocket_status: String,
+    /// RPC status (`healthy`, `unhealthy` or `disabled`)
+    pub rpc_status: String,
+    /// Latest-price cache counters
+    pub price_cache: PriceCacheInfo,
+    /// Lag watchdog counters (absent when `LAG_ALERT_BLOCKS` is unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lag_watchdog: Option<LagWatchdogInfo>,
+    /// RPC provider capabilities probed at startup (absent when the RPC is
+    /// disabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_capabilities: Option<RpcCapabilitiesInfo>,
+}
+
+/// What the RPC provider supports; `null` where the probe failed.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RpcCapabilitiesInfo {
+    /// Hosted provider matched by host name
+    pub provider: Option<String>,
+    /// Node client version
+    pub client_version: Option<String>,
+    /// Largest `eth_getLogs` block span accepted
+    pub max_log_range: Option<u64>,
+    /// Most logs one `eth_getLogs` response may hold (published limit)
+    pub max_logs_per_response: Option<usize>,
+    /// Whether JSON-RPC batch requests are answered
+    pub batch_requests: Option<bool>,
+    /// Whether historical state is served (archive node)
+    pub archive: Option<bool>,
+    /// Whether the WebSocket endpoint accepted a connection (`null` without
+    /// `RPC_WS_URL`)
+    pub websocket: Option<bool>,
+    /// When the provider was probed
+    pub probed_at: DateTime<Utc>,
+}
+
+impl From<&ProviderCapabilities> for RpcCapabilitiesInfo {
+    fn from(capabilities: &ProviderCapabilities) -> Self {
+        Self {
+            provider: capabilities.provider.clone(),
+            client_version: capabilities.client_version.clone(),
+            max_log_range: capabilities.max_log_range,
+            max_logs_per_response: capabilities.max_logs_per_response,
+            batch_requests: capabilities.batch_requests,
+            archive: capabilities.archive,
+            websocket: capabilities.websocket,
+            probed_at: capabilities.probed_at,
+        }
+    }
+}
+
+/// Lag watchdog counters since startup.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LagWatchdogInfo {
+    /// Completed lag checks
+    pub checks: u64,
+    /// Times the lag crossed the alert threshold
+    pub alerts: u64,
+    /// Lag at the last check
+    pub lag_blocks: Option<u64>,
+    /// Whether the lag is above the alert threshold
+    pub lagging: bool,
+    /// When the last check completed
+    pub last_check_at: Option<DateTime<Utc>>,
+}
+
+impl From<LagWatchdogStats> for LagWatchdogInfo {
+    fn from(stats: LagWatchdogStats) -> Self {
+        Self {
+            checks: stats.checks,
+            alerts: stats.alerts,
+            lag_blocks: stats.lag_blocks,
+            lagging: stats.lagging,
+            last_check_at: stats.last_check_at,
+        }
+    }
+}
+
+/// Latest-price cache counters since startup.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PriceCacheInfo {
+    /// Lookups answered from memory
+    pub hits: u64,
+    /// Lookups that went to the database
+    pub misses: u64,
+    /// Share of lookups answered from memory (absent before the first lookup)
+    pub hit_ratio: Option<f64>,
+    /// Pools currently cached
+    pub entries: usize,
+}
+
+impl From<PriceCacheStats> for PriceCacheInfo {
+    fn from(stats: PriceCacheStats) -> Self {
+        let lookups = stats.hits + stats.misses;
+        Self {
+            hits: stats.hits,
+            misses: stats.misses,
+            hit_ratio: (lookups > 0).then(|| stats.hits as f64 / lookups as f64),
+            entries: stats.entries,
+        }
+    }
+}
+
+/// Liveness response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LivenessResponse {
+    /// Always `healthy` while the process serves requests
+    pub status: HealthStatus,
+    /// Application version
+    pub version: String,
+    /// Uptime in seconds
+    pub uptime_seconds: u64,
+}
+
+/// Query parameters for the status page.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct StatusQuery {
+    /// Response format; without it, browsers (`Accept: text/html`) get HTML
+    #[serde(default)]
+    pub format: Option<StatusFormat>,
+}
+
+/// Status page formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusFormat {
+    /// [`StatusResponse`] as JSON
+    Json,
+    /// A self-contained HTML page
+    Html,
+}
+
+/// Public status page: sync lag per pool, reorgs, RPC health and recent
+/// incidents.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatusResponse {
+    /// Overall status: `degraded` while the RPC is down or a pool lags more
+    /// than `max_lag_blocks`
+    pub status: HealthStatus,
+    /// Application version
+    pub version: String,
+    /// Uptime in seconds
+    pub uptime_seconds: u64,
+    /// Latest block on chain (absent when the RPC is disabled or unreachable)
+    pub chain_head: Option<u64>,
+    /// Lag in blocks above which a pool counts as lagging
+    pub max_lag_blocks: u64,
+    /// RPC provider health
+    pub rpc: RpcStatusInfo,
+    /// Enabled pools
+    pub pools: Vec<PoolStatusInfo>,
+    /// Latest incidents of all pools, newest first
+    pub incidents: Vec<StatusIncidentInfo>,
+    /// When the status was taken
+    pub generated_at: DateTime<Utc>,
+}
+
+/// RPC provider health on the status page.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RpcStatusInfo {
+    /// `healthy`, `unhealthy` or `disabled`
+    pub status: String,
+    /// Hosted provider matched by host name
+    pub provider: Option<String>,
+    /// Whether the WebSocket subscription is connected
+    pub websocket_connected: bool,
+}
+
+/// Sync state of one pool on the status page.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolStatusInfo {
+    /// Pool ID
+    pub id: i64,
+    /// Pool name, or address when unnamed
+    pub name: String,
+    /// Last indexed block (absent before the first sync)
+    pub indexed_block: Option<u64>,
+    /// Blocks between the last indexed block and the chain head
+    pub lag_blocks: Option<u64>,
+    /// Whether the lag is above `max_lag_blocks`
+    pub lagging: bool,
+    /// Chain reorganizations detected on the pool
+    pub reorg_count: u64,
+    /// When the pool was last synced
+    pub last_synced_at: Option<DateTime<Utc>>,
+}
+
+/// A recent incident on the status page.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatusIncidentInfo {
+    /// Pool name, or address when unnamed
+    pub pool: String,
+    /// Incident kind (e.g. "depeg")
+    pub kind: String,
+    /// Block timestamp of the incident's first block
+    pub started_at: DateTime<Utc>,
+    /// Block timestamp of the first block after it, if it has ended
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Largest deviation during the incident, in basis points
+    pub peak_deviation_bps: f64,
+    /// Whether the incident is ongoing
+    pub ongoing: bool,
 }
 
 /// Health status states.
@@ -198,14 +1168,24 @@ pub enum HealthStatus {
     Unhealthy,
 }
 
-/// Error response.
-/// Error response wrapper.
+/// Error response (RFC 7807 problem document, `application/problem+json`).
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
-    /// Error type
+    /// Problem type URI, ending in the error code
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary of the HTTP status
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Human-readable explanation
+    pub detail: String,
+    /// Stable error code (e.g. `not_found`, `rpc_error`)
     pub error: String,
-    /// Human-readable message
+    /// Human-readable message (same as `detail`)
     pub message: String,
+    /// Whether the same request may succeed later
+    pub retryable: bool,
     /// Optional details
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<serde_json::Value>,
@@ -224,32 +1204,380 @@ pub struct RecentEventResponse {
 /// Sync event data.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SyncEventInfo {
+    /// Deterministic ID, stable across re-indexes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Block number where event occurred
     pub block_number: u64,
     /// Block timestamp
     pub timestamp: DateTime<Utc>,
     /// Transaction hash
     pub tx_hash: String,
+    /// Log index within the block
+    pub log_index: u32,
     /// Reserve0 raw value
     pub reserve0: String,
     /// Reserve1 raw value
     pub reserve1: String,
 }
 
+/// Query parameters for pool incidents.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct IncidentQuery {
+    /// Only incidents of this kind (e.g. "depeg")
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Items per page (max 1000)
+    #[serde(default = "default_page_size")]
+    pub limit: u32,
+    /// Items to skip
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// A recorded pool incident, such as a stablecoin depeg.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IncidentInfo {
+    /// Incident ID
+    pub id: i64,
+    /// Incident kind (e.g. "depeg")
+    pub kind: String,
+    /// Band around 1.0 the price left, in basis points
+    pub band_bps: u32,
+    /// First block outside the band
+    pub started_block: u64,
+    /// Block timestamp of `started_block`
+    pub started_at: DateTime<Utc>,
+    /// First block back inside the band, if the incident has ended
+    pub ended_block: Option<u64>,
+    /// Block timestamp of `ended_block`
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Price farthest from 1.0 during the incident
+    pub peak_price: f64,
+    /// Deviation of `peak_price` from 1.0, in basis points
+    pub peak_deviation_bps: f64,
+    /// Whether the price is still outside the band
+    pub ongoing: bool,
+}
+
+/// A value of the composite price index.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CompositePriceInfo {
+    /// Liquidity-weighted average price of the constituents
+    pub price: f64,
+    /// Total quote-side liquidity of the constituents
+    pub liquidity: f64,
+    /// Newest block among the constituents
+    pub block_number: u64,
+    /// Block timestamp of `block_number`
+    pub timestamp: DateTime<Utc>,
+    /// Pools included in the price
+    pub constituents: Vec<CompositeConstituentInfo>,
+}
+
+/// One pool's contribution to a composite price.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CompositeConstituentInfo {
+    /// Pool ID
+    pub pool_id: i64,
+    /// Block of the pool's price
+    pub block_number: u64,
+    /// Pool price in its quote direction
+    pub price: f64,
+    /// Quote-side liquidity of the pool
+    pub liquidity: f64,
+    /// Share of the total liquidity, between 0 and 1
+    pub weight: f64,
+}
+
+/// Query parameters for reorg history.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct ReorgQuery {
+    /// Only reorgs of this pool (ID, address, or name)
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// Items per page (max 1000)
+    #[serde(default = "default_page_size")]
+    pub limit: u32,
+    /// Items to skip
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// A chain reorganization handled by the indexer.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReorgInfo {
+    /// Reorg ID
+    pub id: i64,
+    /// Pool database ID
+    pub pool_id: i64,
+    /// When the reorg was detected
+    pub detected_at: DateTime<Utc>,
+    /// Last block common to the old and new chain
+    pub fork_point: u64,
+    /// Indexed blocks rolled back
+    pub depth: u64,
+    /// Sync events after the fork point marked unconfirmed
+    pub invalidated_events: u64,
+}
+
+/// Reorg totals of a pool.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReorgStatsResponse {
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Reorgs handled
+    pub reorgs: u64,
+    /// Deepest reorg, in blocks
+    pub max_depth: Option<u64>,
+    /// Average depth, in blocks
+    pub avg_depth: Option<f64>,
+    /// Sync events invalidated across all reorgs
+    pub invalidated_events: u64,
+    /// When the latest reorg was detected
+    pub last_detected_at: Option<DateTime<Utc>>,
+}
+
+/// Query parameters for cursor-paginated events.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct EventPageQuery {
+    /// Opaque cursor from a previous page's `next_cursor`
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Items per page (max 1000)
+    #[serde(default = "default_page_size")]
+    pub limit: u32,
+    /// Sort order: "asc" (oldest first) or "desc" (newest first)
+    #[serde(default)]
+    pub order: SortOrder,
+    /// First block to include (inclusive)
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    /// Last block to include (inclusive)
+    #[serde(default)]
+    pub to_block: Option<u64>,
+}
+
+/// Sort order for paginated listings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Oldest first
+    Asc,
+    /// Newest first
+    #[default]
+    Desc,
+}
+
+/// Status of a single alert rule.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlertRuleStatus {
+    /// Rule identifier
+    pub id: String,
+    /// Pool the rule watches
+    pub pool: String,
+    /// Trigger condition (e.g. "price > 4000")
+    pub condition: String,
+    /// Destination with secrets redacted (webhook URLs are reduced to their
+    /// host)
+    pub destination: String,
+    /// Whether the condition held at the last observed price
+    pub active: bool,
+    /// De-duplication window in seconds
+    pub dedup_window_secs: u64,
+    /// Delivery budget per minute for the destination
+    pub max_per_minute: u32,
+    /// Alerts delivered
+    pub fired: u64,
+    /// Alerts suppressed by the de-duplication window
+    pub suppressed_duplicate: u64,
+    /// Alerts suppressed by the destination rate limit
+    pub suppressed_rate_limited: u64,
+    /// When the rule last fired
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_fired_at: Option<DateTime<Utc>>,
+}
+
+/// Alert engine status.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlertStatusResponse {
+    /// Whether alert rules are configured
+    pub enabled: bool,
+    /// Per-rule status
+    pub rules: Vec<AlertRuleStatus>,
+    /// Latest deliveries of all alert sources, newest first
+    pub recent_deliveries: Vec<AlertDeliveryInfo>,
+}
+
+/// Outcome of one alert delivery.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlertDeliveryInfo {
+    /// Rule ID, `lag_watchdog` or `depeg`
+    pub source: String,
+    /// Event (e.g. `price_alert`, `lag_exceeded`)
+    pub event: String,
+    /// Channel: `webhook`, `slack`, `telegram` or `email`
+    pub channel: String,
+    /// Destination with secrets redacted
+    pub destination: String,
+    /// `delivered` or `failed`
+    pub status: String,
+    /// Attempts made
+    pub attempts: u32,
+    /// Last error of a failed delivery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the delivery succeeded or gave up
+    pub delivered_at: DateTime<Utc>,
+}
+
+/// Replication role of an instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceRole {
+    /// Indexing from the chain
+    Primary,
+    /// Following a primary's database
+    Standby,
+}
+
+/// Standby status response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StandbyStatusResponse {
+    /// Current role
+    pub role: InstanceRole,
+    /// Whether this instance started as a standby and has been promoted
+    pub promoted: bool,
+    /// Last block recorded by the primary, as of the last follow pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub followed_block: Option<u64>,
+    /// When the last follow pass completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_follow_at: Option<DateTime<Utc>>,
+}
+
+/// Request body setting a pool's start block.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolStartBlockRequest {
+    /// First block to index when the pool has no checkpoint (`null` clears it)
+    #[serde(default)]
+    pub start_block: Option<u64>,
+}
+
+/// Request body setting a pool's flash spike filter.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolSpikeFilterRequest {
+    /// Basis points a price must move away from the price before its block
+    /// for a reverted spike to be left out of candles and stats (`null`
+    /// turns the filter off). Stats of a filtered pool are aggregated from
+    /// its price points instead of the hourly rollups.
+    #[serde(default)]
+    pub spike_filter_bps: Option<u32>,
+}
+
+/// Indexing settings of a pool.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolSettingsResponse {
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Pool name
+    pub pool: String,
+    /// Whether the pool is indexed
+    pub enabled: bool,
+    /// First block to index when the pool has no checkpoint
+    pub start_block: Option<u64>,
+    /// Flash spike threshold in basis points (`None` if spikes are kept)
+    pub spike_filter_bps: Option<u32>,
+}
+
 /// WebSocket message for price stream.
 /// WebSocket price update message.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PriceStreamMessage {
-    /// Event type (e.g., "price_update", "connected")
+    /// Event type (e.g., "price_update", "price_preview", "connected")
     pub event_type: String,
     /// Pool name
     pub pool: String,
-    /// Price value
+    /// Price, in the pool's quote direction
     pub price: f64,
+    /// EWMA-smoothed price (absent when smoothing is disabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_ewma: Option<f64>,
     /// Block number
     pub block_number: u64,
     /// Timestamp
     pub timestamp: DateTime<Utc>,
     /// Reserve amounts
     pub reserves: ReservesInfo,
+    /// False for `price_preview` messages, read from an unconfirmed block
+    pub is_confirmed: bool,
+}
+
+impl PriceStreamMessage {
+    /// Creates the `price_update` message for a pool's new confirmed price,
+    /// quoted in `direction`.
+    #[must_use]
+    pub fn confirmed(pool: String, direction: QuoteDirection, latest: &PricePointRow) -> Self {
+        Self {
+            event_type: "price_update".to_string(),
+            pool,
+            price: direction.apply(latest.price),
+            price_ewma: latest.price_ewma.map(|ewma| direction.apply(ewma)),
+            block_number: u64::try_from(latest.block_number).unwrap_or(0),
+            timestamp: DateTime::from_timestamp(latest.block_timestamp, 0).unwrap_or_else(Utc::now),
+            reserves: ReservesInfo {
+                weth: latest.reserve0_human,
+                usdt: latest.reserve1_human,
+            },
+            is_confirmed: true,
+        }
+    }
+}
+
+/// Prices a `/stream/{pool}` subscriber receives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamChannel {
+    /// Indexed prices only (`price_update`)
+    #[default]
+    Confirmed,
+    /// Provisional prices from unconfirmed blocks only (`price_preview`)
+    Preview,
+    /// Both
+    All,
+}
+
+impl StreamChannel {
+    /// Returns true if a message with `is_confirmed` belongs on this channel.
+    #[must_use]
+    pub const fn carries(self, is_confirmed: bool) -> bool {
+        match self {
+            Self::Confirmed => is_confirmed,
+            Self::Preview => !is_confirmed,
+            Self::All => true,
+        }
+    }
+}
+
+/// Query parameters for the price stream.
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct StreamQuery {
+    /// Prices to receive: "confirmed" (default), "preview" or "all"
+    #[serde(default)]
+    pub channel: StreamChannel,
+}
+
+/// Public key that signs price responses.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PublicKeyResponse {
+    /// Signature algorithm ("ed25519")
+    pub algorithm: String,
+    /// Key ID sent in the `X-Signature-Key-Id` header
+    pub key_id: String,
+    /// Raw 32-byte public key, base64
+    pub public_key: String,
+    /// Response header carrying the signature
+    pub signature_header: String,
+    /// Path prefixes whose responses are signed
+    pub signed_paths: Vec<String>,
 }
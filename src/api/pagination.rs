@@ -0,0 +1,212 @@
+//! Pagination of list endpoints.
+//!
+//! Price history, pool events and the pool list all answer with a
+//! [`Paginated`] envelope: the page's items plus the total, the limit, where
+//! the page starts (an `offset` or a `cursor`) and the URLs of the next and
+//! previous pages. The same URLs are sent in a `Link` header (RFC 8288), so
+//! clients can walk pages without building query strings themselves.
+//!
+//! Page URLs keep the request's path and other query parameters and only
+//! replace the paging ones. Cursor pages walk forward only, so they have no
+//! `prev` link.
+
+use axum::{
+    http::{header, HeaderValue, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::api::models::{Paginated, PaginationInfo};
+
+/// Query parameters replaced in page URLs. `page` and `page_size` are the
+/// legacy history parameters, superseded by `offset` and `limit`.
+const PAGING_PARAMS: [&str; 5] = ["limit", "offset", "cursor", "page", "page_size"];
+
+impl<T> Paginated<T> {
+    /// Builds an offset-paginated page of `total` items starting at `offset`.
+    pub fn from_offset(data: Vec<T>, total: u64, limit: u32, offset: u64, uri: &Uri) -> Self {
+        let next_offset = offset + u64::from(limit);
+        let next = (next_offset < total).then(|| {
+            page_url(
+                uri,
+                &[
+                    ("limit", limit.to_string()),
+                    ("offset", next_offset.to_string()),
+                ],
+            )
+        });
+        let prev = (offset > 0).then(|| {
+            let prev_offset = offset.saturating_sub(u64::from(limit));
+            page_url(
+                uri,
+                &[
+                    ("limit", limit.to_string()),
+                    ("offset", prev_offset.to_string()),
+                ],
+            )
+        });
+
+        Self {
+            data,
+            pagination: PaginationInfo {
+                total,
+                limit,
+                offset: Some(offset),
+                next,
+                prev,
+                ..PaginationInfo::default()
+            },
+        }
+    }
+
+    /// Builds a cursor-paginated page of `total` items that starts after
+    /// `cursor` and continues after `next_cursor`.
+    pub fn from_cursor(
+        data: Vec<T>,
+        total: u64,
+        limit: u32,
+        cursor: Option<String>,
+        next_cursor: Option<String>,
+        uri: &Uri,
+    ) -> Self {
+        let next = next_cursor.as_ref().map(|next_cursor| {
+            page_url(
+                uri,
+                &[
+                    ("limit", limit.to_string()),
+                    ("cursor", next_cursor.clone()),
+                ],
+            )
+        });
+
+        Self {
+            data,
+            pagination: PaginationInfo {
+                total,
+                limit,
+                cursor,
+                next_cursor,
+                next,
+                ..PaginationInfo::default()
+            },
+        }
+    }
+
+    /// Returns the `Link` header value for the page, if it has neighbours.
+    #[must_use]
+    pub fn link_header(&self) -> Option<String> {
+        let links = [
+            (self.pagination.next.as_deref(), "next"),
+            (self.pagination.prev.as_deref(), "prev"),
+        ]
+        .into_iter()
+        .filter_map(|(url, rel)| url.map(|url| format!("<{url}>; rel=\"{rel}\"")))
+        .collect::<Vec<_>>();
+
+        (!links.is_empty()).then(|| links.join(", "))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Paginated<T> {
+    fn into_response(self) -> Response {
+        let link = self.link_header();
+        let mut response = Json(self).into_response();
+        if let Some(value) = link.and_then(|link| HeaderValue::from_str(&link).ok()) {
+            response.headers_mut().insert(header::LINK, value);
+        }
+        response
+    }
+}
+
+/// Returns the request's path and query with the paging parameters replaced
+/// by `params`.
+///
+/// Values are query-safe (numbers and event cursors), so they are not
+/// percent-encoded.
+fn page_url(uri: &Uri, params: &[(&str, String)]) -> String {
+    let kept = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+            !pair.is_empty() && !PAGING_PARAMS.contains(&key)
+        })
+        .map(ToString::to_string);
+    let query = kept
+        .chain(params.iter().map(|(key, value)| format!("{key}={value}")))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{}?{query}", uri.path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(s: &str) -> Uri {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_offset_links() {
+        let uri = uri("/api/v1/price/history/WETH-USDT?from=1700000000&page=2&page_size=10");
+
+        let page = Paginated::from_offset(vec![1, 2], 25, 10, 10, &uri);
+        assert_eq!(page.pagination.offset, Some(10));
+        assert_eq!(
+            page.pagination.next.as_deref(),
+            Some("/api/v1/price/history/WETH-USDT?from=1700000000&limit=10&offset=20")
+        );
+        assert_eq!(
+            page.pagination.prev.as_deref(),
+            Some("/api/v1/price/history/WETH-USDT?from=1700000000&limit=10&offset=0")
+        );
+        assert_eq!(
+            page.link_header().unwrap(),
+            "</api/v1/price/history/WETH-USDT?from=1700000000&limit=10&offset=20>; rel=\"next\", \
+             </api/v1/price/history/WETH-USDT?from=1700000000&limit=10&offset=0>; rel=\"prev\""
+        );
+
+        let last = Paginated::from_offset(vec![1], 25, 10, 20, &uri);
+        assert!(last.pagination.next.is_none());
+        let only = Paginated::<u8>::from_offset(vec![], 0, 10, 0, &uri);
+        assert!(only.link_header().is_none());
+    }
+
+    #[test]
+    fn test_cursor_links() {
+        let uri = uri("/api/v1/pools/WETH-USDT/events?order=asc&cursor=5:1");
+
+        let page = Paginated::from_cursor(
+            vec![1],
+            3,
+            1,
+            Some("5:1".to_string()),
+            Some("6:0".to_string()),
+            &uri,
+        );
+        assert_eq!(
+            page.pagination.next.as_deref(),
+            Some("/api/v1/pools/WETH-USDT/events?order=asc&limit=1&cursor=6:0")
+        );
+        assert!(page.pagination.prev.is_none());
+        assert!(page.pagination.offset.is_none());
+
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["pagination"]["next_cursor"], "6:0");
+        assert!(json["pagination"].get("offset").is_none());
+    }
+
+    #[test]
+    fn test_response_carries_link_header() {
+        let uri = uri("/api/v1/pools");
+        let response = Paginated::from_offset(vec![1], 2, 1, 0, &uri).into_response();
+        assert_eq!(
+            response.headers()[header::LINK],
+            "</api/v1/pools?limit=1&offset=1>; rel=\"next\""
+        );
+    }
+}
@@ -1,62 +1,157 @@
 //! Axum server setup and routing.
 
-use axum::http::HeaderValue;
-use axum::{middleware, routing::get, Router};
+use axum::{
+    middleware,
+    response::Redirect,
+    routing::{get, post, put},
+    Router,
+};
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tower::ServiceBuilder;
 use tower_http::{
     compression::CompressionLayer,
-    cors::{Any, CorsLayer},
     services::{ServeDir, ServeFile},
     trace::TraceLayer,
 };
-use tracing::info;
+use tracing::{info, warn};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::api::models::{PriceStreamMessage, ReservesInfo};
+use crate::api::cors::CorsPolicy;
+use crate::api::models::PriceStreamMessage;
 use crate::api::{docs::ApiDoc, handlers, middleware as api_middleware};
 use crate::app_state::AppState;
+use crate::db::models::PoolRow;
+use crate::error::TrackerError;
+use crate::spikes::remove_flash_spikes;
+
+/// How often changed candles are written to the database.
+const CANDLE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Run the Axum API server.
 pub async fn run_server(
     state: AppState,
     port: u16,
-    rate_limit_rpm: u32,
-    cors_origins: Vec<String>,
+    cors: CorsPolicy,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("Ensuring default pool exists in database");
     state.repository.ensure_default_pool().await?;
 
-    let limiter = api_middleware::rate_limit::create_rate_limiter(rate_limit_rpm);
-
     let api_routes = Router::new()
         .route("/health", get(handlers::health::health_check))
+        .route("/health/live", get(handlers::health::liveness))
         .route("/pools", get(handlers::pools::list_pools))
+        .route("/pools/:id/events", get(handlers::events::list_pool_events))
+        .route("/pools/:id/quote", get(handlers::pools::get_quote))
+        .route(
+            "/pools/:id/incidents",
+            get(handlers::incidents::list_incidents),
+        )
+        .route(
+            "/pools/:id/reorgs/stats",
+            get(handlers::reorgs::get_reorg_stats),
+        )
+        .route(
+            "/pools/:id/reserves/at",
+            get(handlers::pools::get_reserves_at),
+        )
+        .route("/pools/:id/price/at", get(handlers::price::get_price_at))
+        .route(
+            "/pools/:id/impermanent-loss",
+            get(handlers::pools::get_impermanent_loss),
+        )
+        .route(
+            "/pools/:id/price-path",
+            get(handlers::pools::get_price_path),
+        )
+        .route(
+            "/pools/:id/timeseries",
+            get(handlers::pools::get_timeseries),
+        )
+        .route(
+            "/pools/:id/analytics",
+            get(handlers::analytics::get_analytics),
+        )
+        .route("/pools/:id/apr", get(handlers::analytics::get_fee_apr))
+        .route(
+            "/pools/:id/sandwiches",
+            get(handlers::analytics::get_sandwiches),
+        )
         .route(
             "/price/current/:pool",
             get(handlers::price::get_current_price),
         )
+        .route(
+            "/price/latest/:pool",
+            get(handlers::price::get_latest_price),
+        )
         .route(
             "/price/history/:pool",
             get(handlers::price::get_price_history),
         )
+        .route(
+            "/price/history/:pool/export",
+            get(handlers::price::export_price_history),
+        )
+        .route(
+            "/prices/at-blocks",
+            post(handlers::price::get_prices_at_blocks),
+        )
+        .route("/composite", get(handlers::composite::get_composite_price))
+        .route(
+            "/composite/history",
+            get(handlers::composite::get_composite_history),
+        )
+        .route("/route", get(handlers::route::get_route))
         .route("/stats/:pool", get(handlers::stats::get_stats))
+        .route("/reorgs", get(handlers::reorgs::list_reorgs))
+        .route("/candles/:pool", get(handlers::candles::get_candles))
         .route("/events/:pool", get(handlers::events::get_recent_events))
-        .route("/stream/:pool", get(handlers::stream::websocket_handler));
+        .route("/stream/:pool", get(handlers::stream::websocket_handler))
+        .route("/alerts", get(handlers::alerts::get_alert_status))
+        .route("/admin/standby", get(handlers::admin::get_standby_status))
+        .route("/admin/promote", post(handlers::admin::promote))
+        .route(
+            "/admin/pools/:id/enable",
+            post(handlers::admin::enable_pool),
+        )
+        .route(
+            "/admin/pools/:id/disable",
+            post(handlers::admin::disable_pool),
+        )
+        .route(
+            "/admin/pools/:id/start-block",
+            put(handlers::admin::set_pool_start_block),
+        )
+        .route(
+            "/admin/pools/:id/spike-filter",
+            put(handlers::admin::set_pool_spike_filter),
+        );
 
-    let cors = build_cors_layer(cors_origins);
+    #[cfg(feature = "graphql")]
+    let api_routes = api_routes.merge(crate::api::graphql::routes(state.clone()));
+
+    let cors = cors.layer();
 
     let middleware_stack = ServiceBuilder::new()
         .layer(TraceLayer::new_for_http())
         .layer(CompressionLayer::new())
         .layer(cors)
         .layer(middleware::from_fn(api_middleware::logging::log_requests))
-        .layer(middleware::from_fn(move |req, next| {
-            api_middleware::rate_limit::rate_limit(limiter.clone(), req, next)
-        }));
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            api_middleware::auth::authenticate,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            api_middleware::rate_limit::rate_limit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            api_middleware::signing::sign_responses,
+        ));
 
     let static_files = ServeDir::new("public")
         .append_index_html_on_directories(true)
@@ -64,7 +159,21 @@ pub async fn run_server(
 
     let app = Router::new()
         .nest_service("/", static_files)
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        // Old location of the Swagger UI
+        .route(
+            "/swagger-ui",
+            get(|| async { Redirect::permanent("/docs/") }),
+        )
+        .route(
+            "/swagger-ui/",
+            get(|| async { Redirect::permanent("/docs/") }),
+        )
+        .route("/status", get(handlers::status::get_status))
+        .route(
+            "/.well-known/pubkey",
+            get(handlers::signing::get_public_key),
+        )
         .nest("/api/v1", api_routes)
         .layer(middleware_stack)
         .with_state(state.clone());
@@ -73,46 +182,69 @@ pub async fn run_server(
 
     info!(addr = %addr, "Starting API server");
 
+    // Behind a shared indexer, new prices arrive over Redis instead of from
+    // this server's database poll
+    #[cfg(feature = "redis")]
+    let relayed = state.redis.is_some();
+    #[cfg(feature = "redis")]
+    if let Some(bus) = state.redis.clone() {
+        tokio::spawn(crate::redis_bus::relay_prices(state.clone(), bus));
+    }
+    #[cfg(not(feature = "redis"))]
+    let relayed = false;
+
     tokio::spawn(async move {
-        poll_and_broadcast_prices(state).await;
+        poll_and_broadcast_prices(state, relayed).await;
     });
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
-fn build_cors_layer(origins: Vec<String>) -> CorsLayer {
-    if origins.is_empty() || origins.iter().any(|o| o == "*") {
-        CorsLayer::new().allow_origin(Any)
-    } else {
-        let mut layer = CorsLayer::new();
-        for origin in origins {
-            if let Ok(header) = origin.parse::<HeaderValue>() {
-                layer = layer.clone().allow_origin(header);
-            }
-        }
-        layer
-    }
-}
-
-async fn poll_and_broadcast_prices(state: AppState) {
+/// Keeps candles current and, unless `relayed` (prices arrive over Redis),
+/// refreshes the price cache and broadcasts new prices.
+async fn poll_and_broadcast_prices(state: AppState, relayed: bool) {
     let mut interval = tokio::time::interval(Duration::from_secs(5));
     let mut last_seen: HashMap<i64, i64> = HashMap::new();
+    let mut last_flush = Instant::now();
 
     loop {
         interval.tick().await;
 
-        let pools = match state.repository.get_all_pools().await {
+        let pools = match state.reader.get_all_pools().await {
             Ok(pools) => pools,
             Err(_) => continue,
         };
 
+        for pool in &pools {
+            if let Err(e) = update_candles(&state, pool).await {
+                warn!(pool_id = pool.id, error = %e, "Failed to update candles");
+            }
+        }
+        state.candles.trim(chrono::Utc::now().timestamp());
+
+        if last_flush.elapsed() >= CANDLE_FLUSH_INTERVAL {
+            flush_candles(&state).await;
+            last_flush = Instant::now();
+        }
+
+        if relayed {
+            continue;
+        }
+
         for pool in pools {
-            let name = pool.name.unwrap_or_else(|| pool.address.clone());
-            let latest = match state.repository.get_latest_price(pool.id).await {
-                Ok(Some(price)) => price,
+            // Alerts and the stream see prices in the pool's quote direction
+            let direction = pool.quote_direction();
+            let name = pool.name.unwrap_or_else(|| pool.address.to_string());
+            // Refreshing every tick keeps the cached price current
+            let latest = match state.prices.refresh(&state.reader, pool.id).await {
+                Ok(Some(cached)) => cached.latest,
                 Ok(None) => continue,
                 Err(_) => continue,
             };
@@ -124,20 +256,74 @@ async fn poll_and_broadcast_prices(state: AppState) {
 
             last_seen.insert(pool.id, latest.block_number);
 
-            let msg = PriceStreamMessage {
-                event_type: "price_update".to_string(),
-                pool: name,
-                price: latest.price,
-                block_number: latest.block_number as u64,
-                timestamp: chrono::DateTime::from_timestamp(latest.block_timestamp, 0)
-                    .unwrap_or_else(chrono::Utc::now),
-                reserves: ReservesInfo {
-                    weth: latest.reserve0_human,
-                    usdt: latest.reserve1_human,
-                },
-            };
+            if let Some(alerts) = &state.alerts {
+                alerts.on_price(
+                    &name,
+                    direction.apply(latest.price),
+                    latest.block_number as u64,
+                );
+            }
+
+            state.broadcast_price_update(PriceStreamMessage::confirmed(name, direction, &latest));
+        }
+    }
+}
+
+/// Feeds a pool's new confirmed prices into the candle book, warming it up
+/// from the database on first sight and again after a reorg rolled back
+/// prices it holds. Flash spikes are left out if the pool filters them.
+async fn update_candles(state: &AppState, pool: &PoolRow) -> Result<(), TrackerError> {
+    let now = chrono::Utc::now().timestamp();
+    let Some(last_block) = state.candles.last_block(pool.id) else {
+        return state
+            .candles
+            .warm_up(&state.reader, pool.id, pool.spike_filter(), now)
+            .await;
+    };
+    if state
+        .candles
+        .rebuild_after_reorg(
+            &state.reader,
+            &state.repository,
+            pool.id,
+            pool.spike_filter(),
+            now,
+        )
+        .await?
+    {
+        return Ok(());
+    }
+
+    // Confirmation is per block, so every batch holds whole blocks
+    let prices = remove_flash_spikes(
+        state
+            .reader
+            .get_confirmed_prices_after(pool.id, last_block, 0)
+            .await?,
+        state.candles.last_price(pool.id),
+        pool.spike_filter(),
+    );
+    for price in prices {
+        state.candles.record(
+            pool.id,
+            price.block_number,
+            price.block_timestamp,
+            price.price,
+        );
+    }
+    Ok(())
+}
 
-            state.broadcast_price_update(msg);
+/// Writes changed candles to the database, keeping them dirty on failure.
+async fn flush_candles(state: &AppState) {
+    for (pool_id, interval_secs, candles) in state.candles.take_dirty() {
+        if let Err(e) = state
+            .repository
+            .upsert_candles(pool_id, interval_secs, &candles)
+            .await
+        {
+            warn!(pool_id, interval_secs, error = %e, "Failed to flush candles");
+            state.candles.mark_dirty(pool_id, interval_secs, &candles);
         }
     }
 }
@@ -4,35 +4,251 @@ use std::sync::{atomic::AtomicBool, Arc};
 use std::time::SystemTime;
 use tokio::sync::broadcast;
 
+use crate::alerts::AlertEngine;
+use crate::api::middleware::auth::ApiKeyAuth;
+use crate::api::middleware::rate_limit::RateLimiter;
+use crate::api::middleware::signing::ResponseSigner;
 use crate::api::models::PriceStreamMessage;
+use crate::candles::CandleBook;
 use crate::db::repository::Repository;
+use crate::error::TrackerResult;
+use crate::price_cache::{CachedPrice, PriceCache};
+#[cfg(feature = "redis")]
+use crate::redis_bus::RedisBus;
+use crate::rpc::{Provider, ProviderCapabilities, StateRouter};
+use crate::standby::StandbyControl;
+use crate::watchdog::LagWatchdog;
+
+/// Default rate limit per client (requests per minute).
+pub const DEFAULT_RATE_LIMIT_RPM: u32 = 100;
+
+/// Default staleness threshold for the latest price (25 blocks).
+pub const DEFAULT_PRICE_STALE_AFTER_SECS: u64 = 300;
+
+/// Default sync lag above which `/health` reports 503 (about 10 minutes).
+pub const DEFAULT_HEALTH_MAX_LAG_BLOCKS: u64 = 50;
 
 /// Shared application state for API handlers.
 #[derive(Clone)]
 pub struct AppState {
-    /// Repository for database access.
+    /// Read-write repository, for the few writes the server makes (default
+    /// pool, candles, pruning).
     pub repository: Arc<Repository>,
+    /// Read-only repository that serves API queries.
+    pub reader: Repository,
     /// WebSocket connection status flag.
     pub ws_connected: Arc<AtomicBool>,
     /// Application start time for uptime tracking.
     pub start_time: SystemTime,
     /// Broadcast channel for price updates.
     pub price_broadcast: broadcast::Sender<PriceStreamMessage>,
+    /// Rolling 1m/5m candles, updated by the price poll loop.
+    pub candles: Arc<CandleBook>,
+    /// Latest price and 24h change per pool, updated by the price poll loop.
+    pub prices: Arc<PriceCache>,
+    /// Alert engine, if alert rules are configured.
+    pub alerts: Option<Arc<AlertEngine>>,
+    /// Standby control, if this instance follows a primary.
+    pub standby: Option<Arc<StandbyControl>>,
+    /// API key requirements.
+    pub api_auth: Arc<ApiKeyAuth>,
+    /// Chain the indexed pools are on, checked against chain-scoped API keys.
+    pub chain_id: Option<u64>,
+    /// Per-IP and per-key token buckets.
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Age in seconds after which the latest price is reported as stale.
+    pub price_stale_after_secs: u64,
+    /// Blocks the indexer may trail the chain head before `/health` fails.
+    pub health_max_lag_blocks: u64,
+    /// RPC provider for on-chain fallbacks, if configured.
+    pub rpc: Option<Arc<Provider>>,
+    /// What the RPC provider supports, probed at startup.
+    pub provider_capabilities: Option<Arc<ProviderCapabilities>>,
+    /// Archive node for state `rpc` has pruned, if `RPC_ARCHIVE_URL` is set.
+    pub archive_rpc: Option<Arc<Provider>>,
+    /// Block lag watchdog, if `LAG_ALERT_BLOCKS` is set.
+    pub lag_watchdog: Option<Arc<LagWatchdog>>,
+    /// Signer of price responses, if `RESPONSE_SIGNING_KEY` is set.
+    pub response_signer: Option<Arc<ResponseSigner>>,
+    /// Shared prices over Redis, if `REDIS_URL` is set.
+    #[cfg(feature = "redis")]
+    pub redis: Option<RedisBus>,
 }
 
 impl AppState {
     /// Create a new AppState instance.
+    ///
+    /// Queries share `repository`'s connections until
+    /// [`with_reader`](Self::with_reader) gives them their own.
     pub fn new(repository: Repository) -> Self {
         let (tx, _) = broadcast::channel(100);
 
         Self {
+            reader: repository.clone(),
             repository: Arc::new(repository),
             ws_connected: Arc::new(AtomicBool::new(false)),
             start_time: SystemTime::now(),
             price_broadcast: tx,
+            candles: Arc::new(CandleBook::new()),
+            prices: Arc::new(PriceCache::new()),
+            alerts: None,
+            standby: None,
+            api_auth: Arc::new(ApiKeyAuth::default()),
+            chain_id: None,
+            rate_limiter: Arc::new(RateLimiter::new(DEFAULT_RATE_LIMIT_RPM, Vec::new())),
+            price_stale_after_secs: DEFAULT_PRICE_STALE_AFTER_SECS,
+            health_max_lag_blocks: DEFAULT_HEALTH_MAX_LAG_BLOCKS,
+            rpc: None,
+            provider_capabilities: None,
+            archive_rpc: None,
+            lag_watchdog: None,
+            response_signer: None,
+            #[cfg(feature = "redis")]
+            redis: None,
         }
     }
 
+    /// Serve API queries from `reader`, typically
+    /// [`Repository::reader`].
+    #[must_use]
+    pub fn with_reader(mut self, reader: Repository) -> Self {
+        self.reader = reader;
+        self
+    }
+
+    /// Attach an alert engine evaluated on every new price.
+    #[must_use]
+    pub fn with_alerts(mut self, engine: AlertEngine) -> Self {
+        self.alerts = Some(Arc::new(engine));
+        self
+    }
+
+    /// Require API keys on the given path prefixes (relative to `/api/v1`).
+    #[must_use]
+    pub fn with_auth_required_paths(mut self, paths: Vec<String>) -> Self {
+        self.api_auth = Arc::new(ApiKeyAuth::new(paths));
+        self
+    }
+
+    /// Reject API keys scoped to a chain other than `chain_id`.
+    #[must_use]
+    pub const fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Rate limit clients to `default_rpm`, with per-route-group overrides
+    /// (`(prefix, rpm)` pairs relative to `/api/v1`).
+    #[must_use]
+    pub fn with_rate_limits(mut self, default_rpm: u32, routes: Vec<(String, u32)>) -> Self {
+        self.rate_limiter = Arc::new(RateLimiter::new(default_rpm, routes));
+        self
+    }
+
+    /// Report prices older than `secs` as stale.
+    #[must_use]
+    pub const fn with_price_stale_after_secs(mut self, secs: u64) -> Self {
+        self.price_stale_after_secs = secs;
+        self
+    }
+
+    /// Report the node unready once it trails the chain head by more than
+    /// `blocks`.
+    #[must_use]
+    pub const fn with_health_max_lag_blocks(mut self, blocks: u64) -> Self {
+        self.health_max_lag_blocks = blocks;
+        self
+    }
+
+    /// Use `provider` to read on-chain data the database doesn't have.
+    #[must_use]
+    pub fn with_rpc(mut self, provider: Provider) -> Self {
+        self.rpc = Some(Arc::new(provider));
+        self
+    }
+
+    /// Report the RPC provider's capabilities in `/health`.
+    #[must_use]
+    pub fn with_provider_capabilities(mut self, capabilities: ProviderCapabilities) -> Self {
+        self.provider_capabilities = Some(Arc::new(capabilities));
+        self
+    }
+
+    /// Read state older than `rpc` keeps from `provider`.
+    #[must_use]
+    pub fn with_archive_rpc(mut self, provider: Provider) -> Self {
+        self.archive_rpc = Some(Arc::new(provider));
+        self
+    }
+
+    /// Returns the router for on-chain state reads, if an RPC provider is
+    /// configured.
+    #[must_use]
+    pub fn state_router(&self) -> Option<StateRouter> {
+        let archive = self.provider_capabilities.as_ref().and_then(|c| c.archive);
+        let router = StateRouter::new(Arc::clone(self.rpc.as_ref()?), archive);
+        Some(match &self.archive_rpc {
+            Some(provider) => router.with_archive(Arc::clone(provider)),
+            None => router,
+        })
+    }
+
+    /// Report the lag watchdog's counters in `/health`.
+    #[must_use]
+    pub fn with_lag_watchdog(mut self, watchdog: Arc<LagWatchdog>) -> Self {
+        self.lag_watchdog = Some(watchdog);
+        self
+    }
+
+    /// Sign price responses with `signer`.
+    #[must_use]
+    pub fn with_response_signer(mut self, signer: ResponseSigner) -> Self {
+        self.response_signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Take new prices from the indexer over Redis, and read latest prices
+    /// missing from the local cache from its shared cache.
+    #[cfg(feature = "redis")]
+    #[must_use]
+    pub fn with_redis(mut self, bus: RedisBus) -> Self {
+        self.redis = Some(bus);
+        self
+    }
+
+    /// Returns a pool's latest price and 24h change: from memory, else from
+    /// the shared Redis cache, else from the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a miss cannot be loaded from the database.
+    pub async fn latest_price(&self, pool_id: i64) -> TrackerResult<Option<CachedPrice>> {
+        #[cfg(feature = "redis")]
+        if let Some(bus) = &self.redis {
+            if let Some(price) = self.prices.get(pool_id) {
+                return Ok(Some(price));
+            }
+            match bus.latest(pool_id).await {
+                Ok(Some(price)) => {
+                    self.prices.insert(pool_id, price.clone());
+                    return Ok(Some(price));
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(pool_id, error = %e, "Shared price cache unavailable"),
+            }
+            return self.prices.refresh(&self.reader, pool_id).await;
+        }
+
+        self.prices.get_or_load(&self.reader, pool_id).await
+    }
+
+    /// Run as a warm standby controlled by `control`.
+    #[must_use]
+    pub fn with_standby(mut self, control: Arc<StandbyControl>) -> Self {
+        self.standby = Some(control);
+        self
+    }
+
     /// Broadcast a price update to all subscribers.
     pub fn broadcast_price_update(&self, update: PriceStreamMessage) {
         let _ = self.price_broadcast.send(update);
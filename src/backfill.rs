@@ -0,0 +1,758 @@
+//! Concurrent backfill of historical block ranges.
+//!
+//! Indexing months of history one batch at a time is bound by RPC latency,
+//! so backfill splits the range into shards of consecutive blocks and
+//! indexes up to `workers` shards at once:
+//!
+//! ```text
+//! [shard 0][shard 1][shard 2][shard 3][shard 4] ...
+//!  worker A worker B worker C worker A worker C     (whoever is free next)
+//! ```
+//!
+//! Shards are handed out in block order as permits of a semaphore free up,
+//! so a worker that finishes a quiet shard takes the next unclaimed one
+//! instead of waiting for a slow neighbour. Each shard runs through its own
+//! [`Pipeline`] and writes with batch inserts; large log batches are decoded
+//! by `decode_workers` blocking tasks in parallel.
+//!
+//! Shards finish out of order, but the stored indexer state only advances
+//! past a shard once every shard before it is written. The resume point is
+//! therefore never ahead of persisted data: after a crash or a failed shard,
+//! rerunning backfill (or `watch`) from the stored state redoes at most the
+//! shards that were in flight, and their rows are rewritten idempotently.
+//!
+//! A log request that fails with a retryable error (see
+//! [`TrackerError::is_retryable`]), such as a provider rate limit, is retried
+//! with exponential backoff before the shard gives up.
+//!
+//! Backfilled price points have no smoothed price; the smoothing average is
+//! sequential and only maintained by `watch`.
+//!
+//! Before a long backfill, [`Backfill::estimate`] fetches a few batches spread
+//! over the range and extrapolates their log density and latency to the
+//! whole range, warning about batches likely to exceed provider limits.
+
+use alloy::primitives::B256;
+use alloy::rpc::types::Log;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::{debug, info, warn};
+
+use crate::db::checkpoint::CheckpointStore;
+use crate::db::models::PoolRecord;
+use crate::db::storage::Storage;
+use crate::error::{TrackerError, TrackerResult};
+use crate::pipeline::{Pipeline, PriceMode};
+use crate::state::State;
+
+/// Default number of shards indexed concurrently.
+pub const DEFAULT_BACKFILL_WORKERS: usize = 4;
+
+/// Default number of blocks per shard.
+pub const DEFAULT_SHARD_BLOCKS: u64 = 10_000;
+
+/// Default number of blocking tasks decoding one large log batch.
+pub const DEFAULT_DECODE_WORKERS: usize = 4;
+
+/// Default retries of a log request that failed with a retryable error.
+pub const DEFAULT_FETCH_RETRIES: u32 = 3;
+
+/// Delay before the first retry; doubled for each further one.
+const FETCH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Default number of batches an estimate samples.
+pub const DEFAULT_ESTIMATE_SAMPLES: usize = 5;
+
+/// Logs per `eth_getLogs` response above which many providers (Infura,
+/// Alchemy and most public endpoints) reject the request.
+pub const COMMON_MAX_LOGS_PER_REQUEST: usize = 10_000;
+
+/// How many times the average density the densest sample may reach before
+/// the estimate is flagged as uncertain.
+const UNEVEN_DENSITY_FACTOR: f64 = 4.0;
+
+/// Outcome of a backfill run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackfillReport {
+    /// Shards indexed
+    pub shards: usize,
+    /// Sync events indexed
+    pub events: usize,
+    /// Block the stored indexer state was advanced to, if it moved
+    pub committed_block: Option<u64>,
+}
+
+/// One batch fetched by an estimate.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EstimateSample {
+    /// First block of the batch
+    pub from_block: u64,
+    /// Last block of the batch
+    pub to_block: u64,
+    /// Logs returned, `None` if the request failed
+    pub logs: Option<usize>,
+    /// Time the request took, in milliseconds
+    pub elapsed_ms: u64,
+}
+
+/// Projected cost of a backfill, extrapolated from sampled batches.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackfillEstimate {
+    /// First block of the range
+    pub from_block: u64,
+    /// Last block of the range
+    pub to_block: u64,
+    /// The sampled batches, in block order
+    pub samples: Vec<EstimateSample>,
+    /// Average logs per block over the successful samples
+    pub logs_per_block: f64,
+    /// Projected logs (Sync and Swap events) in the range
+    pub events: u64,
+    /// `eth_getLogs` requests the backfill makes, without retries
+    pub rpc_calls: u64,
+    /// Projected wall time, from the samples' average latency and the
+    /// number of workers
+    pub duration_secs: u64,
+    /// Problems the backfill is likely to run into
+    pub warnings: Vec<String>,
+}
+
+/// Result of one shard, held until every earlier shard is done.
+#[derive(Debug, Clone, Copy)]
+struct ShardResult {
+    events: usize,
+    /// Last event's block and block hash
+    last_event: Option<(u64, B256)>,
+}
+
+/// Indexes a historical block range of one pool with concurrent workers.
+pub struct Backfill<'a> {
+    storage: &'a dyn Storage,
+    pool: &'a PoolRecord,
+    chain_id: u64,
+    workers: usize,
+    shard_blocks: u64,
+    batch_blocks: u64,
+    decode_workers: usize,
+    fetch_retries: u32,
+    price_mode: PriceMode,
+}
+
+impl<'a> Backfill<'a> {
+    /// Creates a backfill writing `pool`'s events to `storage`.
+    ///
+    /// `batch_blocks` is the block span of each `eth_getLogs` request.
+    #[must_use]
+    pub const fn new(
+        storage: &'a dyn Storage,
+        pool: &'a PoolRecord,
+        chain_id: u64,
+        batch_blocks: u64,
+    ) -> Self {
+        Self {
+            storage,
+            pool,
+            chain_id,
+            workers: DEFAULT_BACKFILL_WORKERS,
+            shard_blocks: DEFAULT_SHARD_BLOCKS,
+            batch_blocks,
+            decode_workers: DEFAULT_DECODE_WORKERS,
+            fetch_retries: DEFAULT_FETCH_RETRIES,
+            price_mode: PriceMode::Event,
+        }
+    }
+
+    /// Sets how many shards are indexed at once (at least 1).
+    #[must_use]
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Sets the number of blocks per shard (at least 1).
+    #[must_use]
+    pub fn with_shard_blocks(mut self, shard_blocks: u64) -> Self {
+        self.shard_blocks = shard_blocks.max(1);
+        self
+    }
+
+    /// Sets how many blocking tasks decode each large log batch (at least
+    /// 1; see [`Pipeline::with_decode_workers`]).
+    #[must_use]
+    pub fn with_decode_workers(mut self, workers: usize) -> Self {
+        self.decode_workers = workers.max(1);
+        self
+    }
+
+    /// Sets how often a log request failing with a retryable error is
+    /// retried (0 disables retries).
+    #[must_use]
+    pub const fn with_fetch_retries(mut self, retries: u32) -> Self {
+        self.fetch_retries = retries;
+        self
+    }
+
+    /// Sets which Sync events become price points.
+    #[must_use]
+    pub const fn with_price_mode(mut self, price_mode: PriceMode) -> Self {
+        self.price_mode = price_mode;
+        self
+    }
+
+    /// Indexes `[from_block, to_block]`, fetching Sync logs with `fetch`.
+    ///
+    /// The stored indexer state is advanced in shard order, and only if the
+    /// range continues from it (or nothing is indexed yet); backfilling
+    /// older history never moves the resume point.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from any shard (after retries), or from
+    /// reading or writing the indexer state. Shards committed before the
+    /// failure stay committed.
+    pub async fn run<F, Fut>(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        fetch: F,
+    ) -> TrackerResult<BackfillReport>
+    where
+        F: Fn(u64, u64) -> Fut + Sync,
+        Fut: Future<Output = TrackerResult<Vec<Log>>> + Send,
+    {
+        let shards = split(from_block, to_block, self.shard_blocks);
+        let pipeline = Pipeline::new(self.storage, self.pool, self.chain_id)?
+            .with_decode_workers(self.decode_workers)
+            .with_price_mode(self.price_mode)
+            .without_state_updates();
+        let semaphore = Semaphore::new(self.workers);
+        let fetch = |from: u64, to: u64| self.fetch_with_retries(&fetch, from, to);
+
+        // Only a range that continues the indexed one may move the resume point
+        let mut checkpoint = self.storage.load(self.pool.id).await?.unwrap_or_default();
+        let resume_block = checkpoint.block;
+        let advance_state = resume_block == 0 || from_block <= resume_block.saturating_add(1);
+
+        info!(
+            from_block,
+            to_block,
+            shards = shards.len(),
+            workers = self.workers,
+            "Starting backfill"
+        );
+
+        let mut running: FuturesUnordered<_> = shards
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| {
+                let (pipeline, semaphore, fetch) = (&pipeline, &semaphore, &fetch);
+                async move {
+                    let _permit = semaphore.acquire().await.map_err(|e| {
+                        TrackerError::state("Backfill semaphore closed", Some(Box::new(e)))
+                    })?;
+                    debug!(start, end, "Indexing shard");
+
+                    let mut last_event = None;
+                    let events = pipeline
+                        .run(
+                            split(start, end, self.batch_blocks),
+                            fetch,
+                            &mut State::new(),
+                            &mut None,
+                            |update| last_event = Some((update.block_number, update.block_hash)),
+                        )
+                        .await?;
+                    Ok::<_, TrackerError>((index, ShardResult { events, last_event }))
+                }
+            })
+            .collect();
+
+        let mut report = BackfillReport::default();
+        let mut finished = BTreeMap::new();
+        let mut last_event = None;
+
+        while let Some(result) = running.next().await {
+            let (index, shard) = result?;
+            finished.insert(index, shard);
+
+            // Commit the longest run of finished shards in block order
+            let mut advanced = false;
+            while let Some(shard) = finished.remove(&report.shards) {
+                report.shards += 1;
+                report.events += shard.events;
+                checkpoint.events_processed += u64::try_from(shard.events).unwrap_or(0);
+                last_event = shard.last_event.or(last_event);
+                advanced = true;
+            }
+
+            let Some((block_number, block_hash)) = last_event else {
+                continue;
+            };
+            if advanced && advance_state && block_number > resume_block {
+                checkpoint = checkpoint.at(block_number, Some(block_hash));
+                self.storage.save(self.pool.id, &checkpoint).await?;
+                report.committed_block = Some(block_number);
+                debug!(block_number, shards = report.shards, "Committed backfill");
+            }
+        }
+
+        info!(
+            shards = report.shards,
+            events = report.events,
+            committed_block = ?report.committed_block,
+            "Backfill complete"
+        );
+        Ok(report)
+    }
+
+    /// Estimates the cost of backfilling `[from_block, to_block]` by fetching
+    /// `samples` batches spread evenly over it with `fetch`.
+    ///
+    /// Nothing is written. A failed sample is reported as a warning, since
+    /// the provider refusing a batch (too many results, too wide a range) is
+    /// what the estimate is meant to catch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is empty or every sample failed.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub async fn estimate<F, Fut>(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        samples: usize,
+        fetch: F,
+    ) -> TrackerResult<BackfillEstimate>
+    where
+        F: Fn(u64, u64) -> Fut,
+        Fut: Future<Output = TrackerResult<Vec<Log>>>,
+    {
+        if from_block > to_block {
+            return Err(TrackerError::state(
+                format!("Block {from_block} is after block {to_block}"),
+                None,
+            ));
+        }
+        let blocks = to_block - from_block + 1;
+        let batch_blocks = self.batch_blocks.max(1);
+        let mut warnings = Vec::new();
+
+        let mut sampled = Vec::new();
+        for (start, end) in sample_ranges(from_block, to_block, batch_blocks, samples.max(1)) {
+            let started = Instant::now();
+            let result = fetch(start, end).await;
+            let elapsed_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            let logs = match result {
+                Ok(logs) => Some(logs.len()),
+                Err(e) => {
+                    warnings.push(format!(
+                        "Fetching blocks {start}-{end} failed ({e}); the provider may limit \
+                         the block range or result size of eth_getLogs, try a lower BATCH_SIZE"
+                    ));
+                    None
+                }
+            };
+            debug!(start, end, ?logs, elapsed_ms, "Sampled backfill batch");
+            sampled.push(EstimateSample {
+                from_block: start,
+                to_block: end,
+                logs,
+                elapsed_ms,
+            });
+        }
+
+        let succeeded: Vec<&EstimateSample> = sampled.iter().filter(|s| s.logs.is_some()).collect();
+        if succeeded.is_empty() {
+            return Err(TrackerError::rpc(
+                format!("Every sampled batch of {batch_blocks} blocks failed"),
+                None,
+            ));
+        }
+        let sampled_blocks: u64 = succeeded
+            .iter()
+            .map(|s| s.to_block - s.from_block + 1)
+            .sum();
+        let sampled_logs: usize = succeeded.iter().filter_map(|s| s.logs).sum();
+        let logs_per_block = sampled_logs as f64 / sampled_blocks as f64;
+        let latency_ms =
+            succeeded.iter().map(|s| s.elapsed_ms).sum::<u64>() / succeeded.len() as u64;
+
+        let rpc_calls = blocks.div_ceil(batch_blocks);
+        let workers = u64::try_from(self.workers).unwrap_or(1).max(1);
+        let duration_secs = rpc_calls.div_ceil(workers).saturating_mul(latency_ms) / 1_000;
+
+        let densest = succeeded.iter().filter_map(|s| s.logs).max().unwrap_or(0);
+        if densest > COMMON_MAX_LOGS_PER_REQUEST {
+            warnings.push(format!(
+                "A sampled batch returned {densest} logs; many providers reject eth_getLogs \
+                 responses over {COMMON_MAX_LOGS_PER_REQUEST} logs, try a lower BATCH_SIZE"
+            ));
+        } else if logs_per_block * batch_blocks as f64 > COMMON_MAX_LOGS_PER_REQUEST as f64 {
+            warnings.push(format!(
+                "Batches of {batch_blocks} blocks average {:.0} logs; many providers reject \
+                 eth_getLogs responses over {COMMON_MAX_LOGS_PER_REQUEST} logs",
+                logs_per_block * batch_blocks as f64
+            ));
+        }
+        let densest_per_block = succeeded
+            .iter()
+            .filter_map(|s| Some(s.logs? as f64 / (s.to_block - s.from_block + 1) as f64))
+            .fold(0.0, f64::max);
+        if logs_per_block > 0.0 && densest_per_block > logs_per_block * UNEVEN_DENSITY_FACTOR {
+            warnings.push(
+                "Event density varies widely between samples; the estimate is rough, \
+                 sample more batches with --samples"
+                    .to_string(),
+            );
+        }
+
+        Ok(BackfillEstimate {
+            from_block,
+            to_block,
+            samples: sampled,
+            logs_per_block,
+            events: (logs_per_block * blocks as f64).round() as u64,
+            rpc_calls,
+            duration_secs,
+            warnings,
+        })
+    }
+
+    /// Calls `fetch`, retrying retryable errors with exponential backoff.
+    async fn fetch_with_retries<F, Fut>(
+        &self,
+        fetch: &F,
+        from_block: u64,
+        to_block: u64,
+    ) -> TrackerResult<Vec<Log>>
+    where
+        F: Fn(u64, u64) -> Fut + Sync,
+        Fut: Future<Output = TrackerResult<Vec<Log>>> + Send,
+    {
+        let mut attempt = 0;
+        loop {
+            match fetch(from_block, to_block).await {
+                Err(e) if e.is_retryable() && attempt < self.fetch_retries => {
+                    let delay = FETCH_RETRY_DELAY * 2u32.saturating_pow(attempt);
+                    attempt += 1;
+                    warn!(
+                        from_block,
+                        to_block,
+                        attempt,
+                        code = e.code(),
+                        error = %e,
+                        "Log request failed, retrying in {:?}",
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Splits `[from_block, to_block]` into consecutive ranges of `size` blocks.
+fn split(from_block: u64, to_block: u64, size: u64) -> Vec<(u64, u64)> {
+    std::iter::successors(Some(from_block), |start| {
+        start
+            .checked_add(size.max(1))
+            .filter(|next| *next <= to_block)
+    })
+    .take_while(|start| *start <= to_block)
+    .map(|start| (start, start.saturating_add(size.max(1) - 1).min(to_block)))
+    .collect()
+}
+
+/// Picks `samples` batches of `size` blocks centred on evenly spaced points
+/// of `[from_block, to_block]`, without overlaps.
+fn sample_ranges(from_block: u64, to_block: u64, size: u64, samples: usize) -> Vec<(u64, u64)> {
+    let blocks = to_block - from_block + 1;
+    if blocks <= size.saturating_mul(samples as u64) {
+        // The samples would cover the range: fetch all of it
+        return split(from_block, to_block, size);
+    }
+    let samples = samples as u64;
+    (0..samples)
+        .map(|i| {
+            let centre = from_block + blocks * (2 * i + 1) / (2 * samples);
+            let start = centre.saturating_sub(size / 2).max(from_block);
+            let end = start.saturating_add(size - 1).min(to_block);
+            (start, end)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::db::repository::Repository;
+    use crate::events::Sync;
+    use alloy::primitives::{aliases::U112, Address, U256};
+    use alloy::sol_types::SolEvent;
+    use std::time::Duration;
+
+    fn sync_log(pool_address: Address, block: u64) -> Log {
+        let sync = Sync {
+            reserve0: U112::from(1_000u64) * U112::from(10u64).pow(U112::from(18u64)),
+            reserve1: U112::from(2_000_000 + block) * U112::from(10u64).pow(U112::from(6u64)),
+        };
+        Log {
+            inner: alloy::primitives::Log {
+                address: pool_address,
+                data: sync.encode_log_data(),
+            },
+            block_hash: Some(B256::from(U256::from(block))),
+            block_number: Some(block),
+            block_timestamp: Some(1_700_000_000 + block),
+            transaction_hash: Some(B256::from(U256::from(block))),
+            transaction_index: Some(0),
+            log_index: Some(0),
+            removed: false,
+        }
+    }
+
+    /// Concurrent shards need a file database: in-memory databases share one
+    /// cache with table-level locks, and concurrent writers deadlock on it.
+    async fn file_repository(dir: &tempfile::TempDir) -> Repository {
+        let url = format!("sqlite:{}", dir.path().join("indexer.db").display());
+        Repository::new(create_pool(&url).await.unwrap())
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split(1, 25, 10), vec![(1, 10), (11, 20), (21, 25)]);
+        assert_eq!(split(5, 5, 10), vec![(5, 5)]);
+        assert!(split(6, 5, 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_commits_shards_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = file_repository(&dir).await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        // Events every 7 blocks; the first shard is the slowest to fetch, so
+        // later shards finish first
+        let fetch = |from: u64, to: u64| {
+            let logs: Vec<Log> = (from..=to)
+                .filter(|b| b % 7 == 0)
+                .map(|b| sync_log(pool_address, b))
+                .collect();
+            async move {
+                if from <= 1_050 {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                Ok(logs)
+            }
+        };
+        let backfill = Backfill::new(&repo, &pool, 1, 10)
+            .with_workers(3)
+            .with_shard_blocks(50);
+        let report = backfill.run(1_001, 1_200, fetch).await.unwrap();
+
+        assert_eq!(report.shards, 4);
+        assert_eq!(report.events, 29);
+        assert_eq!(report.committed_block, Some(1_197));
+
+        let state = repo.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(state.last_indexed_block, 1_197);
+        assert_eq!(state.total_events_processed, 29);
+
+        // Older history is indexed without moving the resume point
+        let report = backfill.run(1, 1_000, fetch).await.unwrap();
+        assert_eq!(report.events, 142);
+        assert_eq!(report.committed_block, None);
+        let state = repo.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(state.last_indexed_block, 1_197);
+        assert_eq!(
+            repo.get_indexed_blocks(pool_id, 1, 1_200)
+                .await
+                .unwrap()
+                .len(),
+            171
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_never_commits_past_a_failed_shard() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = file_repository(&dir).await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        // The second shard fails once the first is committed and the later
+        // ones are written
+        let written = || async {
+            repo.get_state(pool_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .last_indexed_block
+                > 0
+                && !repo
+                    .get_indexed_blocks(pool_id, 200, 200)
+                    .await
+                    .unwrap()
+                    .is_empty()
+        };
+        let result = Backfill::new(&repo, &pool, 1, 10)
+            .with_workers(4)
+            .with_shard_blocks(50)
+            .with_fetch_retries(0)
+            .run(1, 200, |from, to| {
+                let logs = vec![sync_log(pool_address, from), sync_log(pool_address, to)];
+                async move {
+                    if (51..=100).contains(&from) {
+                        for _ in 0..1_000 {
+                            if written().await {
+                                break;
+                            }
+                            tokio::time::sleep(Duration::from_millis(5)).await;
+                        }
+                        return Err(TrackerError::rpc("rate limited", None));
+                    }
+                    Ok(logs)
+                }
+            })
+            .await;
+        assert!(result.is_err());
+
+        // Only the first shard is committed, though later rows exist
+        let state = repo.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(state.last_indexed_block, 50);
+        assert!(!repo
+            .get_indexed_blocks(pool_id, 101, 200)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_retries_only_retryable_errors() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = file_repository(&dir).await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        // A rate limit on the first request is retried
+        let calls = AtomicUsize::new(0);
+        let report = Backfill::new(&repo, &pool, 1, 100)
+            .run(1, 10, |from, to| {
+                let first = calls.fetch_add(1, Ordering::SeqCst) == 0;
+                let logs = vec![sync_log(pool_address, to)];
+                async move {
+                    if first {
+                        return Err(TrackerError::rpc(format!("rate limited {from}"), None));
+                    }
+                    Ok(logs)
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(report.committed_block, Some(10));
+
+        // A log that fails to decode would fail the same way again
+        let calls = AtomicUsize::new(0);
+        let result = Backfill::new(&repo, &pool, 1, 100)
+            .run(11, 20, |_, _| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err(TrackerError::decoding("bad log", None)) }
+            })
+            .await;
+        assert!(result.is_err_and(|e| e.code() == "decoding_error"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_sample_ranges() {
+        assert_eq!(
+            sample_ranges(1_000, 100_999, 1_000, 4),
+            vec![
+                (13_000, 13_999),
+                (38_000, 38_999),
+                (63_000, 63_999),
+                (88_000, 88_999)
+            ]
+        );
+        // A range the samples would cover is fetched whole
+        assert_eq!(
+            sample_ranges(1, 25, 10, 3),
+            vec![(1, 10), (11, 20), (21, 25)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimate_extrapolates_samples_and_warns() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        // Four logs per block; the provider refuses the batch around block 3,750
+        let estimate = Backfill::new(&repo, &pool, 1, 100)
+            .with_workers(2)
+            .estimate(1, 10_000, 4, |from, to| {
+                let logs: Vec<Log> = (from..=to)
+                    .flat_map(|b| std::iter::repeat(sync_log(pool_address, b)).take(4))
+                    .collect();
+                async move {
+                    if (from..=to).contains(&3_750) {
+                        return Err(TrackerError::rpc(
+                            "query returned more than 10000 results",
+                            None,
+                        ));
+                    }
+                    Ok(logs)
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(estimate.samples.len(), 4);
+        assert_eq!(
+            estimate.samples.iter().filter(|s| s.logs.is_none()).count(),
+            1
+        );
+        assert!((estimate.logs_per_block - 4.0).abs() < f64::EPSILON);
+        assert_eq!(estimate.events, 40_000);
+        assert_eq!(estimate.rpc_calls, 100);
+        assert_eq!(estimate.warnings.len(), 1);
+        assert!(estimate.warnings[0].contains("BATCH_SIZE"));
+
+        // Batches over the common result limit are flagged
+        let estimate = Backfill::new(&repo, &pool, 1, 5_000)
+            .estimate(1, 100_000, 2, |from, to| {
+                let logs: Vec<Log> = (from..=to)
+                    .flat_map(|b| std::iter::repeat(sync_log(pool_address, b)).take(3))
+                    .collect();
+                async move { Ok(logs) }
+            })
+            .await
+            .unwrap();
+        assert!(estimate.warnings[0].contains("15000 logs"));
+
+        let failed = Backfill::new(&repo, &pool, 1, 100)
+            .estimate(1, 1_000, 2, |_, _| async {
+                Err(TrackerError::rpc("block range too wide", None))
+            })
+            .await;
+        assert!(failed.is_err());
+    }
+}
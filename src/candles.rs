@@ -0,0 +1,887 @@
+//! Rolling in-memory candles and short-period statistics.
+//!
+//! The API server's price poll loop feeds every new confirmed price into a
+//! [`CandleBook`], which keeps the last 24 hours of 1-minute and 5-minute
+//! OHLC candles per pool. The 1h and 24h stats and `/api/v1/candles/{pool}`
+//! are served from memory instead of aggregating `price_points` on every
+//! request.
+//!
+//! Buckets without prices are left out unless a [`CandleFill`] asks for them:
+//! forward-filled from the previous close, or reported empty.
+//!
+//! Pools with a `spike_filter_bps` threshold leave reverted single-block
+//! spikes out of their candles (see [`crate::spikes`]).
+//!
+//! Changed buckets are flushed to the `candles` table periodically. On
+//! startup, [`CandleBook::warm_up`] reloads flushed buckets and rebuilds only
+//! the tail, from the last flushed bucket onwards, from `price_points`. A
+//! reorg that rolls back prices the book already holds makes
+//! [`CandleBook::rebuild_after_reorg`] delete the flushed buckets from the
+//! fork point on and warm the pool up again.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::RwLock;
+use tracing::debug;
+
+use crate::db::models::CandleRow;
+use crate::db::repository::Repository;
+use crate::error::{TrackerError, TrackerResult};
+use crate::spikes::remove_flash_spikes;
+
+/// Candle widths kept in memory, in seconds.
+pub const CANDLE_INTERVALS: [i64; 2] = [60, 300];
+
+/// How far back candles are kept, in seconds.
+pub const CANDLE_WINDOW_SECS: i64 = 86_400;
+
+/// Widest interval; warm-up replays from a bucket boundary of this width so
+/// no partially rebuilt bucket is mixed with a stored one.
+const WIDEST_INTERVAL: i64 = CANDLE_INTERVALS[CANDLE_INTERVALS.len() - 1];
+
+/// Statistics over a window of 1-minute candles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStats {
+    /// First price in the window
+    pub open: f64,
+    /// Highest price in the window
+    pub high: f64,
+    /// Lowest price in the window
+    pub low: f64,
+    /// Latest price
+    pub close: f64,
+    /// Mean of all prices in the window
+    pub average: f64,
+    /// Number of prices in the window
+    pub samples: i64,
+    /// Start of the first bucket (unix seconds)
+    pub first_timestamp: i64,
+    /// Block timestamp of the latest price (unix seconds)
+    pub last_timestamp: i64,
+}
+
+/// How buckets without any price are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CandleFill {
+    /// Leave them out
+    #[default]
+    Omit,
+    /// Flat candle at the previous bucket's close
+    Previous,
+    /// Candle without prices
+    Null,
+}
+
+impl std::str::FromStr for CandleFill {
+    type Err = TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "omit" => Ok(Self::Omit),
+            "previous" => Ok(Self::Previous),
+            "null" => Ok(Self::Null),
+            _ => Err(TrackerError::config(
+                format!("Candle fill must be previous or null, got: {s}"),
+                None,
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for CandleFill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Omit => "omit",
+            Self::Previous => "previous",
+            Self::Null => "null",
+        })
+    }
+}
+
+/// A candle that may stand for a bucket without prices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilledCandle {
+    /// Bucket start (unix seconds, aligned to the interval)
+    pub bucket_start: i64,
+    /// First price in the bucket
+    pub open: Option<f64>,
+    /// Highest price in the bucket
+    pub high: Option<f64>,
+    /// Lowest price in the bucket
+    pub low: Option<f64>,
+    /// Last price in the bucket
+    pub close: Option<f64>,
+    /// Number of prices in the bucket; 0 for a filled gap
+    pub samples: i64,
+}
+
+impl From<&CandleRow> for FilledCandle {
+    fn from(c: &CandleRow) -> Self {
+        Self {
+            bucket_start: c.bucket_start,
+            open: Some(c.open),
+            high: Some(c.high),
+            low: Some(c.low),
+            close: Some(c.close),
+            samples: c.samples,
+        }
+    }
+}
+
+/// Returns `candles` (oldest first) with a candle for every bucket from the
+/// first candle up to the bucket containing `until`, gaps filled per `fill`.
+///
+/// With [`CandleFill::Omit`] only the existing candles are returned.
+#[must_use]
+pub fn fill_gaps(
+    candles: &[CandleRow],
+    interval_secs: i64,
+    until: i64,
+    fill: CandleFill,
+) -> Vec<FilledCandle> {
+    let Some(first) = candles.first() else {
+        return Vec::new();
+    };
+    if fill == CandleFill::Omit {
+        return candles.iter().map(FilledCandle::from).collect();
+    }
+
+    let last_bucket = until.div_euclid(interval_secs) * interval_secs;
+    let mut filled = Vec::with_capacity(candles.len());
+    let mut next = candles.iter().peekable();
+    let mut previous_close = None;
+    let mut bucket_start = first.bucket_start;
+
+    while bucket_start <= last_bucket || next.peek().is_some() {
+        if let Some(candle) = next.next_if(|c| c.bucket_start <= bucket_start) {
+            previous_close = Some(candle.close);
+            filled.push(FilledCandle::from(candle));
+            bucket_start = candle.bucket_start;
+        } else {
+            let price = if fill == CandleFill::Previous {
+                previous_close
+            } else {
+                None
+            };
+            filled.push(FilledCandle {
+                bucket_start,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                samples: 0,
+            });
+        }
+        bucket_start += interval_secs;
+    }
+    filled
+}
+
+/// Candles of one width for one pool, oldest first.
+#[derive(Debug)]
+struct Series {
+    interval_secs: i64,
+    candles: VecDeque<CandleRow>,
+    /// Buckets changed since the last flush
+    dirty: BTreeSet<i64>,
+}
+
+impl Series {
+    const fn new(interval_secs: i64) -> Self {
+        Self {
+            interval_secs,
+            candles: VecDeque::new(),
+            dirty: BTreeSet::new(),
+        }
+    }
+
+    fn record(&mut self, timestamp: i64, price: f64) {
+        let bucket_start = timestamp.div_euclid(self.interval_secs) * self.interval_secs;
+
+        match self.candles.back_mut() {
+            Some(last) if last.bucket_start == bucket_start => {
+                last.high = last.high.max(price);
+                last.low = last.low.min(price);
+                last.close = price;
+                last.samples += 1;
+                last.price_sum += price;
+            }
+            Some(last) if last.bucket_start > bucket_start => {
+                // Prices arrive in block order, so this only happens if a
+                // block timestamp went backwards; keep the bucket intact
+                debug!(timestamp, "Ignoring out-of-order price for candles");
+                return;
+            }
+            _ => self.candles.push_back(CandleRow {
+                bucket_start,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                samples: 1,
+                price_sum: price,
+            }),
+        }
+
+        self.dirty.insert(bucket_start);
+    }
+
+    fn trim(&mut self, now: i64) {
+        let cutoff = now - CANDLE_WINDOW_SECS;
+        while self
+            .candles
+            .front()
+            .is_some_and(|c| c.bucket_start + self.interval_secs <= cutoff)
+        {
+            self.candles.pop_front();
+        }
+    }
+
+    fn take_dirty(&mut self) -> Vec<CandleRow> {
+        let dirty = std::mem::take(&mut self.dirty);
+        self.candles
+            .iter()
+            .filter(|c| dirty.contains(&c.bucket_start))
+            .cloned()
+            .collect()
+    }
+}
+
+/// All series for one pool.
+#[derive(Debug)]
+struct PoolCandles {
+    series: Vec<Series>,
+    last_block: i64,
+    last_timestamp: i64,
+    /// Last price recorded, the reference for spotting a flash spike in the
+    /// next block
+    last_price: Option<f64>,
+    /// Latest reorg (ID in the `reorgs` table) the candles account for
+    reorg_id: i64,
+}
+
+impl PoolCandles {
+    fn new() -> Self {
+        Self {
+            series: CANDLE_INTERVALS.iter().map(|&i| Series::new(i)).collect(),
+            last_block: 0,
+            last_timestamp: 0,
+            last_price: None,
+            reorg_id: 0,
+        }
+    }
+
+    fn series(&self, interval_secs: i64) -> Option<&Series> {
+        self.series
+            .iter()
+            .find(|s| s.interval_secs == interval_secs)
+    }
+}
+
+/// Rolling 1m/5m candles for every pool the poll loop has seen.
+#[derive(Debug, Default)]
+pub struct CandleBook {
+    pools: RwLock<HashMap<i64, PoolCandles>>,
+}
+
+impl CandleBook {
+    /// Creates an empty book.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last block whose price was recorded for a pool.
+    #[must_use]
+    pub fn last_block(&self, pool_id: i64) -> Option<i64> {
+        self.pools
+            .read()
+            .ok()
+            .and_then(|pools| pools.get(&pool_id).map(|p| p.last_block))
+    }
+
+    /// Block timestamp of the last price recorded for a pool.
+    #[must_use]
+    pub fn last_timestamp(&self, pool_id: i64) -> Option<i64> {
+        self.pools
+            .read()
+            .ok()
+            .and_then(|pools| pools.get(&pool_id).map(|p| p.last_timestamp))
+    }
+
+    /// Last price recorded for a pool.
+    #[must_use]
+    pub fn last_price(&self, pool_id: i64) -> Option<f64> {
+        self.pools
+            .read()
+            .ok()
+            .and_then(|pools| pools.get(&pool_id).and_then(|p| p.last_price))
+    }
+
+    /// Records a confirmed price in every series of a pool.
+    pub fn record(&self, pool_id: i64, block_number: i64, timestamp: i64, price: f64) {
+        let Ok(mut pools) = self.pools.write() else {
+            return;
+        };
+        let pool = pools.entry(pool_id).or_insert_with(PoolCandles::new);
+        for series in &mut pool.series {
+            series.record(timestamp, price);
+        }
+        pool.last_block = pool.last_block.max(block_number);
+        pool.last_timestamp = pool.last_timestamp.max(timestamp);
+        pool.last_price = Some(price);
+    }
+
+    /// Drops candles that fell out of the 24h window.
+    pub fn trim(&self, now: i64) {
+        if let Ok(mut pools) = self.pools.write() {
+            for pool in pools.values_mut() {
+                for series in &mut pool.series {
+                    series.trim(now);
+                }
+            }
+        }
+    }
+
+    /// Returns up to `limit` of the most recent candles, oldest first, or
+    /// `None` if the pool or interval is not tracked.
+    #[must_use]
+    pub fn candles(
+        &self,
+        pool_id: i64,
+        interval_secs: i64,
+        limit: usize,
+    ) -> Option<Vec<CandleRow>> {
+        let pools = self.pools.read().ok()?;
+        let series = pools.get(&pool_id)?.series(interval_secs)?;
+        let skip = series.candles.len().saturating_sub(limit);
+        let candles = series.candles.iter().skip(skip).cloned().collect();
+        drop(pools);
+        Some(candles)
+    }
+
+    /// Returns up to `limit` of the most recent buckets, oldest first, up to
+    /// the one containing `now`, with empty buckets filled per `fill`.
+    /// Returns `None` if the pool or interval is not tracked.
+    #[must_use]
+    pub fn filled_candles(
+        &self,
+        pool_id: i64,
+        interval_secs: i64,
+        limit: usize,
+        fill: CandleFill,
+        now: i64,
+    ) -> Option<Vec<FilledCandle>> {
+        let pools = self.pools.read().ok()?;
+        let series = pools.get(&pool_id)?.series(interval_secs)?;
+        let candles = series.candles.iter().cloned().collect::<Vec<_>>();
+        drop(pools);
+
+        let mut filled = fill_gaps(&candles, interval_secs, now, fill);
+        let skip = filled.len().saturating_sub(limit);
+        filled.drain(..skip);
+        Some(filled)
+    }
+
+    /// Aggregates the 1-minute candles covering `from_ts` onwards.
+    ///
+    /// The window starts at the bucket containing `from_ts`, so it may
+    /// include up to a minute of earlier prices. Returns `None` if the pool
+    /// has no prices in the window.
+    #[must_use]
+    pub fn window_stats(&self, pool_id: i64, from_ts: i64) -> Option<WindowStats> {
+        let pools = self.pools.read().ok()?;
+        let pool = pools.get(&pool_id)?;
+        let series = pool.series(CANDLE_INTERVALS[0])?;
+
+        let mut window = series
+            .candles
+            .iter()
+            .filter(|c| c.bucket_start + series.interval_secs > from_ts);
+        let first = window.next()?;
+
+        let mut stats = WindowStats {
+            open: first.open,
+            high: first.high,
+            low: first.low,
+            close: first.close,
+            average: 0.0,
+            samples: first.samples,
+            first_timestamp: first.bucket_start,
+            last_timestamp: pool.last_timestamp,
+        };
+        let mut sum = first.price_sum;
+        for candle in window {
+            stats.high = stats.high.max(candle.high);
+            stats.low = stats.low.min(candle.low);
+            stats.close = candle.close;
+            stats.samples += candle.samples;
+            sum += candle.price_sum;
+        }
+        drop(pools);
+
+        #[allow(clippy::cast_precision_loss)]
+        let average = sum / stats.samples as f64;
+        stats.average = average;
+        Some(stats)
+    }
+
+    /// Takes the candles changed since the last call, as
+    /// `(pool_id, interval_secs, candles)`.
+    #[must_use]
+    pub fn take_dirty(&self) -> Vec<(i64, i64, Vec<CandleRow>)> {
+        let Ok(mut pools) = self.pools.write() else {
+            return Vec::new();
+        };
+        pools
+            .iter_mut()
+            .flat_map(|(&pool_id, pool)| {
+                pool.series.iter_mut().filter_map(move |series| {
+                    let candles = series.take_dirty();
+                    (!candles.is_empty()).then_some((pool_id, series.interval_secs, candles))
+                })
+            })
+            .collect()
+    }
+
+    /// Marks candles as changed again, e.g. after a failed flush.
+    pub fn mark_dirty(&self, pool_id: i64, interval_secs: i64, candles: &[CandleRow]) {
+        if let Ok(mut pools) = self.pools.write() {
+            if let Some(series) = pools.get_mut(&pool_id).and_then(|p| {
+                p.series
+                    .iter_mut()
+                    .find(|s| s.interval_secs == interval_secs)
+            }) {
+                series.dirty.extend(candles.iter().map(|c| c.bucket_start));
+            }
+        }
+    }
+
+    /// Loads a pool's last 24 hours: flushed buckets from the `candles`
+    /// table, then the remaining prices from `price_points`, leaving out
+    /// flash spikes of at least `spike_filter_bps` (see [`crate::spikes`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database query fails.
+    pub async fn warm_up(
+        &self,
+        repository: &Repository,
+        pool_id: i64,
+        spike_filter_bps: Option<u32>,
+        now: i64,
+    ) -> TrackerResult<()> {
+        let window_start = now - CANDLE_WINDOW_SECS;
+        // Read first, so a reorg recorded during the load is handled again
+        let reorg_id = repository
+            .get_reorgs_after(pool_id, 0)
+            .await?
+            .map_or(0, |(id, _)| id);
+
+        let mut stored = Vec::with_capacity(CANDLE_INTERVALS.len());
+        for interval in CANDLE_INTERVALS {
+            stored.push(
+                repository
+                    .get_stored_candles(pool_id, interval, window_start)
+                    .await?,
+            );
+        }
+
+        // Replay from the oldest "last flushed bucket", which may be incomplete
+        let replay_from = stored
+            .iter()
+            .map(|candles| candles.last().map_or(window_start, |c| c.bucket_start))
+            .min()
+            .unwrap_or(window_start)
+            .div_euclid(WIDEST_INTERVAL)
+            * WIDEST_INTERVAL;
+
+        let mut pool = PoolCandles::new();
+        pool.reorg_id = reorg_id;
+        for (series, candles) in pool.series.iter_mut().zip(stored) {
+            series
+                .candles
+                .extend(candles.into_iter().filter(|c| c.bucket_start < replay_from));
+        }
+
+        // The price before the replayed range isn't loaded, so its first
+        // block is kept as is
+        let prices = remove_flash_spikes(
+            repository
+                .get_confirmed_prices_after(pool_id, -1, replay_from)
+                .await?,
+            None,
+            spike_filter_bps,
+        );
+        for price in &prices {
+            for series in &mut pool.series {
+                series.record(price.block_timestamp, price.price);
+            }
+        }
+
+        let latest = match prices.last() {
+            Some(price) => Some((price.block_number, price.block_timestamp, price.price)),
+            None => repository
+                .get_latest_price(pool_id)
+                .await?
+                .map(|p| (p.block_number, p.block_timestamp, p.price)),
+        };
+        if let Some((block_number, timestamp, price)) = latest {
+            (pool.last_block, pool.last_timestamp) = (block_number, timestamp);
+            pool.last_price = Some(price);
+        }
+
+        debug!(
+            pool_id,
+            replayed = prices.len(),
+            last_block = pool.last_block,
+            "Warmed up candles"
+        );
+
+        if let Ok(mut pools) = self.pools.write() {
+            pools.insert(pool_id, pool);
+        }
+        Ok(())
+    }
+
+    /// Rebuilds a pool's candles if a reorg recorded since they were loaded
+    /// rolled back blocks whose prices they hold.
+    ///
+    /// Flushed buckets from the one holding the fork point's price onwards
+    /// are deleted through `writer`, then the pool is warmed up again from
+    /// `repository`. Reorgs within unconfirmed blocks, which the book never
+    /// saw, are only noted. Returns whether the candles were rebuilt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database query fails.
+    pub async fn rebuild_after_reorg(
+        &self,
+        repository: &Repository,
+        writer: &Repository,
+        pool_id: i64,
+        spike_filter_bps: Option<u32>,
+        now: i64,
+    ) -> TrackerResult<bool> {
+        let Some((last_block, reorg_id)) = self
+            .pools
+            .read()
+            .ok()
+            .and_then(|pools| pools.get(&pool_id).map(|p| (p.last_block, p.reorg_id)))
+        else {
+            return Ok(false);
+        };
+        let Some((latest, fork_point)) = repository.get_reorgs_after(pool_id, reorg_id).await?
+        else {
+            return Ok(false);
+        };
+
+        if fork_point >= last_block {
+            if let Ok(mut pools) = self.pools.write() {
+                if let Some(pool) = pools.get_mut(&pool_id) {
+                    pool.reorg_id = pool.reorg_id.max(latest);
+                }
+            }
+            return Ok(false);
+        }
+
+        // Prices after the fork point are in its block's bucket or later ones
+        let fork_timestamp = repository
+            .get_price_at_block(pool_id, u64::try_from(fork_point).unwrap_or(0))
+            .await?
+            .map_or(0, |p| p.block_timestamp);
+        for interval in CANDLE_INTERVALS {
+            writer
+                .delete_stored_candles_from(
+                    pool_id,
+                    interval,
+                    fork_timestamp.div_euclid(interval) * interval,
+                )
+                .await?;
+        }
+        debug!(pool_id, fork_point, "Rebuilding candles after a reorg");
+        self.warm_up(repository, pool_id, spike_filter_bps, now)
+            .await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::db::models::ReorgRecord;
+    use alloy::primitives::{FixedBytes, U256};
+
+    #[test]
+    fn test_record_builds_candles_per_interval() {
+        let book = CandleBook::new();
+        // [100, 120, 90] in 00:01, [110] in 00:02, all within the first 5m
+        for (block, ts, price) in [
+            (1, 60, 100.0),
+            (2, 75, 120.0),
+            (3, 119, 90.0),
+            (4, 130, 110.0),
+        ] {
+            book.record(1, block, ts, price);
+        }
+
+        let minute = book.candles(1, 60, 10).unwrap();
+        assert_eq!(minute.len(), 2);
+        assert_eq!(
+            (
+                minute[0].open,
+                minute[0].high,
+                minute[0].low,
+                minute[0].close
+            ),
+            (100.0, 120.0, 90.0, 90.0)
+        );
+        assert_eq!(minute[1].bucket_start, 120);
+
+        let five = book.candles(1, 300, 10).unwrap();
+        assert_eq!(five.len(), 1);
+        assert_eq!(
+            (five[0].open, five[0].close, five[0].samples),
+            (100.0, 110.0, 4)
+        );
+
+        assert_eq!(book.last_block(1), Some(4));
+        assert!(book.candles(1, 3600, 10).is_none());
+        assert!(book.candles(2, 60, 10).is_none());
+    }
+
+    #[test]
+    fn test_fill_gaps() {
+        let candle = |bucket_start, close| CandleRow {
+            bucket_start,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            samples: 1,
+            price_sum: close,
+        };
+        // Prices at 00:01 and 00:04, asked for up to 00:05:30
+        let candles = [candle(60, 100.0), candle(240, 110.0)];
+
+        let omitted = fill_gaps(&candles, 60, 330, CandleFill::Omit);
+        assert_eq!(omitted.len(), 2);
+
+        let previous = fill_gaps(&candles, 60, 330, CandleFill::Previous);
+        let starts: Vec<i64> = previous.iter().map(|c| c.bucket_start).collect();
+        assert_eq!(starts, vec![60, 120, 180, 240, 300]);
+        let closes: Vec<Option<f64>> = previous.iter().map(|c| c.close).collect();
+        assert_eq!(
+            closes,
+            vec![
+                Some(100.0),
+                Some(100.0),
+                Some(100.0),
+                Some(110.0),
+                Some(110.0)
+            ]
+        );
+        assert_eq!(previous[1].open, Some(100.0));
+        assert_eq!(previous[1].samples, 0);
+
+        let null = fill_gaps(&candles, 60, 330, CandleFill::Null);
+        let closes: Vec<Option<f64>> = null.iter().map(|c| c.close).collect();
+        assert_eq!(closes, vec![Some(100.0), None, None, Some(110.0), None]);
+
+        // A clock behind the last price never drops candles
+        assert_eq!(fill_gaps(&candles, 60, 0, CandleFill::Null).len(), 4);
+        assert!(fill_gaps(&[], 60, 330, CandleFill::Previous).is_empty());
+        assert!("zero".parse::<CandleFill>().is_err());
+    }
+
+    #[test]
+    fn test_filled_candles_keep_most_recent() {
+        let book = CandleBook::new();
+        book.record(1, 1, 60, 100.0);
+
+        let filled = book
+            .filled_candles(1, 60, 3, CandleFill::Previous, 600)
+            .unwrap();
+        let starts: Vec<i64> = filled.iter().map(|c| c.bucket_start).collect();
+        assert_eq!(starts, vec![480, 540, 600]);
+        assert!(filled.iter().all(|c| c.close == Some(100.0)));
+        assert!(book
+            .filled_candles(2, 60, 3, CandleFill::Null, 600)
+            .is_none());
+    }
+
+    #[test]
+    fn test_window_stats_and_trim() {
+        let book = CandleBook::new();
+        book.record(1, 1, 0, 100.0);
+        book.record(1, 2, 3_600, 200.0);
+        book.record(1, 3, 7_200, 300.0);
+
+        let all = book.window_stats(1, 0).unwrap();
+        assert_eq!(
+            (all.open, all.close, all.high, all.low),
+            (100.0, 300.0, 300.0, 100.0)
+        );
+        assert_eq!(all.samples, 3);
+        assert!((all.average - 200.0).abs() < 1e-9);
+        assert_eq!(all.last_timestamp, 7_200);
+
+        let recent = book.window_stats(1, 3_601).unwrap();
+        assert_eq!((recent.open, recent.samples), (200.0, 2));
+
+        // Past the 24h window only the last two buckets remain
+        book.trim(3_600 + CANDLE_WINDOW_SECS);
+        assert_eq!(book.candles(1, 60, 10).unwrap().len(), 2);
+        assert!(book.window_stats(2, 0).is_none());
+    }
+
+    #[test]
+    fn test_take_dirty_returns_changed_buckets_once() {
+        let book = CandleBook::new();
+        book.record(1, 1, 60, 100.0);
+
+        let dirty = book.take_dirty();
+        assert_eq!(dirty.len(), CANDLE_INTERVALS.len());
+        assert!(book.take_dirty().is_empty());
+
+        book.mark_dirty(1, 60, &dirty[0].2);
+        book.record(1, 2, 61, 101.0);
+        let mut intervals: Vec<i64> = book.take_dirty().iter().map(|d| d.1).collect();
+        intervals.sort_unstable();
+        assert_eq!(intervals, CANDLE_INTERVALS.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_restores_flushed_and_unflushed_prices() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let now = 10_000;
+
+        let points = [(1, 9_000, 100.0), (2, 9_030, 120.0), (3, 9_700, 90.0)];
+        for (block, ts, price) in points {
+            repo.insert_price_point(
+                pool_id,
+                block,
+                ts,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                true,
+                &format!("price-{block}"),
+            )
+            .await
+            .unwrap();
+        }
+
+        // Only the first two prices were flushed before the restart
+        let live = CandleBook::new();
+        live.record(pool_id, 1, 9_000, 100.0);
+        live.record(pool_id, 2, 9_030, 120.0);
+        for (pool, interval, candles) in live.take_dirty() {
+            repo.upsert_candles(pool, interval, &candles).await.unwrap();
+        }
+
+        let book = CandleBook::new();
+        book.warm_up(&repo, pool_id, None, now).await.unwrap();
+
+        let minute = book.candles(pool_id, 60, 10).unwrap();
+        let starts: Vec<i64> = minute.iter().map(|c| c.bucket_start).collect();
+        assert_eq!(starts, vec![9_000, 9_660]);
+        assert_eq!(minute[0].samples, 2);
+        assert_eq!(book.last_block(pool_id), Some(3));
+
+        let hour = book.window_stats(pool_id, now - 3_600).unwrap();
+        assert_eq!((hour.open, hour.close, hour.samples), (100.0, 90.0, 3));
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_after_reorg_drops_rolled_back_prices() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let now = 10_000;
+        let insert = |block: u64, ts: u64, price: f64, event_id: &'static str| {
+            let repo = repo.clone();
+            async move {
+                repo.insert_price_point(
+                    pool_id,
+                    block,
+                    ts,
+                    FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                    price,
+                    U256::from(1_u64),
+                    U256::from(1_u64),
+                    1.0,
+                    1.0,
+                    true,
+                    event_id,
+                )
+                .await
+                .unwrap();
+            }
+        };
+        insert(1, 9_000, 100.0, "price-1").await;
+        insert(2, 9_030, 120.0, "price-2").await;
+
+        let book = CandleBook::new();
+        book.warm_up(&repo, pool_id, None, now).await.unwrap();
+        for (pool, interval, candles) in book.take_dirty() {
+            repo.upsert_candles(pool, interval, &candles).await.unwrap();
+        }
+
+        // A reorg within blocks the book never held changes nothing
+        repo.insert_reorg(&ReorgRecord {
+            pool_id,
+            detected_at: now,
+            fork_point: 2,
+            depth: 1,
+        })
+        .await
+        .unwrap();
+        assert!(!book
+            .rebuild_after_reorg(&repo, &repo, pool_id, None, now)
+            .await
+            .unwrap());
+
+        // Block 2 is replaced by a block with another price
+        repo.invalidate_from_block(pool_id, 2).await.unwrap();
+        repo.insert_reorg(&ReorgRecord {
+            pool_id,
+            detected_at: now,
+            fork_point: 1,
+            depth: 1,
+        })
+        .await
+        .unwrap();
+        insert(2, 9_040, 80.0, "price-2b").await;
+
+        assert!(book
+            .rebuild_after_reorg(&repo, &repo, pool_id, None, now)
+            .await
+            .unwrap());
+        let minute = book.candles(pool_id, 60, 10).unwrap();
+        assert_eq!(minute.len(), 1);
+        assert_eq!(
+            (minute[0].high, minute[0].low, minute[0].samples),
+            (100.0, 80.0, 2)
+        );
+        assert!(!book
+            .rebuild_after_reorg(&repo, &repo, pool_id, None, now)
+            .await
+            .unwrap());
+
+        // The stale flushed bucket was deleted and is flushed again rebuilt
+        assert!(repo
+            .get_stored_candles(pool_id, 60, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        for (pool, interval, candles) in book.take_dirty() {
+            repo.upsert_candles(pool, interval, &candles).await.unwrap();
+        }
+        let stored = repo.get_stored_candles(pool_id, 60, 0).await.unwrap();
+        assert_eq!((stored[0].low, stored[0].samples), (80.0, 2));
+    }
+}
@@ -0,0 +1,392 @@
+//! Fault injection for resilience tests.
+//!
+//! Only compiled with the `chaos` feature; never enable it in production
+//! builds. [`Chaos`] decides, from a seeded RNG, whether an operation is
+//! delayed or fails. Two wrappers apply it at the indexer's I/O boundaries:
+//!
+//! - [`ChaosStorage`] wraps a [`Storage`]. Writes may be delayed, fail before
+//!   reaching the database, or fail *after* it (the write is committed but the
+//!   caller sees an error, like a lost acknowledgement).
+//! - [`ChaosLogs`] wraps the function that fetches pair logs for a block
+//!   range. Fetches may be delayed, fail with an RPC error, fail with a
+//!   WebSocket disconnect, or return a reorged response: the same events with
+//!   a different block hash and reserves, plus a phantom event that the
+//!   canonical chain doesn't have. Each reorged response records its fork
+//!   point, which a test drains with [`ChaosLogs::take_reorgs`] to play the
+//!   part of the reorg detector.
+//!
+//! Seeds make failures reproducible: a failing run can be replayed exactly.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::chaos::{Chaos, ChaosConfig};
+//! use std::sync::Arc;
+//!
+//! let chaos = Arc::new(Chaos::new(ChaosConfig::turbulent(), 42));
+//! assert_eq!(chaos.stats().errors, 0);
+//! ```
+
+use alloy::primitives::{aliases::U112, B256};
+use alloy::rpc::types::Log;
+use alloy::sol_types::SolEvent;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::db::models::{
+    IndexerState, PoolRecord, PricePointRecord, ReorgRecord, SwapEventRecord, SyncEventRecord,
+};
+use crate::db::storage::Storage;
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::{decode_sync_event, is_swap_log, Sync};
+
+/// Probabilities (0.0-1.0) of each fault per operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChaosConfig {
+    /// Chance an operation is delayed by up to `max_delay`
+    pub delay_probability: f64,
+    /// Longest injected delay
+    pub max_delay: Duration,
+    /// Chance an operation fails (RPC or database error)
+    pub error_probability: f64,
+    /// Chance a fetch fails with a WebSocket disconnect
+    pub disconnect_probability: f64,
+    /// Chance a fetch returns a reorged response
+    pub reorg_probability: f64,
+}
+
+impl ChaosConfig {
+    /// No faults.
+    #[must_use]
+    pub const fn calm() -> Self {
+        Self {
+            delay_probability: 0.0,
+            max_delay: Duration::ZERO,
+            error_probability: 0.0,
+            disconnect_probability: 0.0,
+            reorg_probability: 0.0,
+        }
+    }
+
+    /// Frequent faults of every kind with short delays, for tests.
+    #[must_use]
+    pub const fn turbulent() -> Self {
+        Self {
+            delay_probability: 0.3,
+            max_delay: Duration::from_millis(5),
+            error_probability: 0.05,
+            disconnect_probability: 0.02,
+            reorg_probability: 0.05,
+        }
+    }
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self::calm()
+    }
+}
+
+/// Faults injected so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    /// Operations delayed
+    pub delays: u64,
+    /// Operations failed with an RPC or database error
+    pub errors: u64,
+    /// Fetches failed with a WebSocket disconnect
+    pub disconnects: u64,
+    /// Reorged fetch responses
+    pub reorgs: u64,
+}
+
+/// Seeded source of faults shared by the chaos wrappers.
+#[derive(Debug)]
+pub struct Chaos {
+    config: ChaosConfig,
+    rng: Mutex<StdRng>,
+    stats: Mutex<ChaosStats>,
+}
+
+impl Chaos {
+    /// Creates a fault source; the same seed injects the same faults.
+    #[must_use]
+    pub fn new(config: ChaosConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            stats: Mutex::new(ChaosStats::default()),
+        }
+    }
+
+    /// Faults injected so far.
+    #[must_use]
+    pub fn stats(&self) -> ChaosStats {
+        *lock(&self.stats)
+    }
+
+    fn roll(&self, probability: f64) -> bool {
+        probability > 0.0 && lock(&self.rng).gen_bool(probability.min(1.0))
+    }
+
+    fn count(&self, update: impl FnOnce(&mut ChaosStats)) {
+        update(&mut lock(&self.stats));
+    }
+
+    /// Sleeps for a random delay, if one is rolled.
+    async fn maybe_delay(&self) {
+        if !self.roll(self.config.delay_probability) {
+            return;
+        }
+        let max_ms = u64::try_from(self.config.max_delay.as_millis()).unwrap_or(u64::MAX);
+        let delay = Duration::from_millis(lock(&self.rng).gen_range(0..=max_ms));
+        self.count(|s| s.delays += 1);
+        tokio::time::sleep(delay).await;
+    }
+
+    /// Returns true if an error is rolled.
+    fn fail(&self) -> bool {
+        let fail = self.roll(self.config.error_probability);
+        if fail {
+            self.count(|s| s.errors += 1);
+        }
+        fail
+    }
+}
+
+/// Locks a mutex, recovering the data if a panicking test poisoned it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// [`Storage`] wrapper that injects delays and write failures.
+#[derive(Debug)]
+pub struct ChaosStorage<S> {
+    inner: S,
+    chaos: Arc<Chaos>,
+}
+
+impl<S: Storage> ChaosStorage<S> {
+    /// Wraps `inner`.
+    pub const fn new(inner: S, chaos: Arc<Chaos>) -> Self {
+        Self { inner, chaos }
+    }
+
+    /// The wrapped storage, for checking what was actually written.
+    pub const fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Runs a write, failing before or after it when an error is rolled.
+    async fn write<T>(
+        &self,
+        operation: &str,
+        write: impl Future<Output = TrackerResult<T>>,
+    ) -> TrackerResult<T> {
+        self.chaos.maybe_delay().await;
+        if self.chaos.fail() {
+            return Err(injected(operation, "before write"));
+        }
+        let result = write.await?;
+        if self.chaos.fail() {
+            return Err(injected(operation, "after write"));
+        }
+        Ok(result)
+    }
+}
+
+fn injected(operation: &str, when: &str) -> TrackerError {
+    TrackerError::database(format!("chaos: {operation} failed {when}"), None)
+}
+
+#[async_trait]
+impl<S: Storage> Storage for ChaosStorage<S> {
+    async fn find_pool(&self, identifier: &str) -> TrackerResult<Option<PoolRecord>> {
+        self.chaos.maybe_delay().await;
+        self.inner.find_pool(identifier).await
+    }
+
+    async fn insert_sync_events(&self, events: Vec<SyncEventRecord>) -> TrackerResult<()> {
+        self.write("insert_sync_events", self.inner.insert_sync_events(events))
+            .await
+    }
+
+    async fn insert_price_points(&self, prices: Vec<PricePointRecord>) -> TrackerResult<()> {
+        self.write(
+            "insert_price_points",
+            self.inner.insert_price_points(prices),
+        )
+        .await
+    }
+
+    async fn insert_swap_events(&self, events: Vec<SwapEventRecord>) -> TrackerResult<()> {
+        self.write("insert_swap_events", self.inner.insert_swap_events(events))
+            .await
+    }
+
+    async fn write_batch(
+        &self,
+        events: Vec<SyncEventRecord>,
+        prices: Vec<PricePointRecord>,
+        swaps: Vec<SwapEventRecord>,
+        state: Option<&IndexerState>,
+    ) -> TrackerResult<()> {
+        self.write(
+            "write_batch",
+            self.inner.write_batch(events, prices, swaps, state),
+        )
+        .await
+    }
+
+    async fn get_state(&self, pool_id: i64) -> TrackerResult<Option<IndexerState>> {
+        self.chaos.maybe_delay().await;
+        self.inner.get_state(pool_id).await
+    }
+
+    async fn set_state(&self, state: &IndexerState) -> TrackerResult<()> {
+        self.write("set_state", self.inner.set_state(state)).await
+    }
+
+    async fn invalidate_from_block(&self, pool_id: i64, from_block: u64) -> TrackerResult<()> {
+        self.write(
+            "invalidate_from_block",
+            self.inner.invalidate_from_block(pool_id, from_block),
+        )
+        .await
+    }
+
+    async fn confirm_up_to_block(&self, pool_id: i64, up_to_block: u64) -> TrackerResult<()> {
+        self.write(
+            "confirm_up_to_block",
+            self.inner.confirm_up_to_block(pool_id, up_to_block),
+        )
+        .await
+    }
+
+    async fn record_reorg(&self, reorg: &ReorgRecord) -> TrackerResult<()> {
+        self.write("record_reorg", self.inner.record_reorg(reorg))
+            .await
+    }
+}
+
+/// Log fetcher wrapper that injects delays, failures and reorged responses.
+#[derive(Debug)]
+pub struct ChaosLogs {
+    chaos: Arc<Chaos>,
+    /// Fork points of reorged responses not yet taken
+    reorgs: Mutex<Vec<u64>>,
+}
+
+impl ChaosLogs {
+    /// Creates a wrapper drawing faults from `chaos`.
+    #[must_use]
+    pub const fn new(chaos: Arc<Chaos>) -> Self {
+        Self {
+            chaos,
+            reorgs: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Fetches `[from_block, to_block]` through `fetch`, injecting faults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an injected RPC error or WebSocket disconnect, or the error
+    /// from `fetch`.
+    pub async fn fetch<F, Fut>(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        fetch: F,
+    ) -> TrackerResult<Vec<Log>>
+    where
+        F: FnOnce(u64, u64) -> Fut,
+        Fut: Future<Output = TrackerResult<Vec<Log>>>,
+    {
+        self.chaos.maybe_delay().await;
+        if self.chaos.fail() {
+            return Err(TrackerError::rpc(
+                format!("chaos: eth_getLogs {from_block}-{to_block} failed"),
+                None,
+            ));
+        }
+        if self.chaos.roll(self.chaos.config.disconnect_probability) {
+            self.chaos.count(|s| s.disconnects += 1);
+            return Err(TrackerError::websocket_disconnected(
+                "chaos: connection dropped",
+            ));
+        }
+
+        let logs = fetch(from_block, to_block).await?;
+        if logs.is_empty() || !self.chaos.roll(self.chaos.config.reorg_probability) {
+            return Ok(logs);
+        }
+
+        self.chaos.count(|s| s.reorgs += 1);
+        lock(&self.reorgs).push(from_block.saturating_sub(1));
+        fork(&logs)
+    }
+
+    /// Drains the fork points of reorged responses served so far.
+    #[must_use]
+    pub fn take_reorgs(&self) -> Vec<u64> {
+        std::mem::take(&mut *lock(&self.reorgs))
+    }
+}
+
+/// Rewrites `logs` as seen on a competing fork: other block hashes, other
+/// reserves, and one extra event the canonical chain doesn't have.
+fn fork(logs: &[Log]) -> TrackerResult<Vec<Log>> {
+    let mut forked = Vec::with_capacity(logs.len() + 1);
+    for log in logs {
+        let mut log = log.clone();
+        if !is_swap_log(&log) {
+            let (sync, _) = decode_sync_event(&log)?;
+            log.inner.data = Sync {
+                reserve0: sync.reserve0,
+                reserve1: sync.reserve1 + U112::from(1u64),
+            }
+            .encode_log_data();
+        }
+        log.block_hash = log.block_hash.map(|hash| !hash);
+        forked.push(log);
+    }
+
+    if let Some(last) = forked.last() {
+        let mut phantom = last.clone();
+        phantom.transaction_hash = Some(B256::repeat_byte(0xc4));
+        phantom.log_index = phantom.log_index.map(|index| index + 1);
+        forked.push(phantom);
+    }
+    Ok(forked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calm_chaos_injects_nothing() {
+        let chaos = Chaos::new(ChaosConfig::calm(), 1);
+        for _ in 0..100 {
+            assert!(!chaos.fail());
+        }
+        assert_eq!(chaos.stats(), ChaosStats::default());
+    }
+
+    #[test]
+    fn test_same_seed_same_faults() {
+        let rolls = |seed| {
+            let chaos = Chaos::new(ChaosConfig::turbulent(), seed);
+            (0..64).map(|_| chaos.fail()).collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(7), rolls(7));
+        assert!(rolls(7).contains(&true));
+    }
+}
@@ -7,6 +7,10 @@
 //!
 //! - `price`: Fetch current ETH price (one-time)
 //! - `watch`: Monitor price updates in real-time
+//! - `stats`: Print price statistics for a window
+//!
+//! `--output json` makes `price`, `watch` and `stats` print one JSON object
+//! per line instead of colored text; `--output plain` drops the colors.
 //!
 //! # Example
 //!
@@ -18,32 +22,99 @@
 //! eth-uniswap-alloy watch
 //! ```
 
+use crate::adapters::PoolType;
+use crate::alerts::{load_rules, AlertEngine, Notifiers};
+use crate::api::middleware::auth::{generate_api_key, hash_api_key, key_prefix};
+use crate::api::middleware::signing::ResponseSigner;
 use crate::api::server;
 use crate::app_state::AppState;
+use crate::backfill::{
+    Backfill, BackfillEstimate, DEFAULT_BACKFILL_WORKERS, DEFAULT_DECODE_WORKERS,
+    DEFAULT_ESTIMATE_SAMPLES, DEFAULT_SHARD_BLOCKS,
+};
+use crate::composite::{self, CompositeIndexer};
 use crate::config::Config;
-use crate::db::create_pool;
+use crate::consistency;
+use crate::daemon::{self, shutdown_signal, Daemon};
+use crate::db::archive::DEFAULT_ARCHIVE_KEEP_BLOCKS;
+use crate::db::checkpoint::{import_legacy_state, Checkpoint, CheckpointStore};
+use crate::db::data_migrations::{self, DataMigrator, MigrationProgress};
+use crate::db::models::{
+    BlockRange, PoolRecord, PoolScope, PriceWebhookDeadLetterRow, ReorgRecord, StorageStats,
+};
 use crate::db::repository::Repository;
+use crate::db::storage::Storage;
+use crate::db::types::PoolAddress;
+use crate::db::{connect, connect_existing, create_pool_with_backup, pending_migrations, snapshot};
+use crate::dedup::LogDeduplicator;
+use crate::depeg::DepegMonitor;
 use crate::error::{TrackerError, TrackerResult};
-use crate::events::{create_sync_filter_for_pair, Sync, UNISWAP_V2_WETH_USDT_PAIR};
+use crate::events::{
+    create_pair_events_filter, create_sync_filter_for_pair, decode_sync_event, fetch_reserves_at,
+    get_logs_split, UNISWAP_V2_WETH_USDT_PAIR,
+};
+use crate::integrity;
+use crate::journal::EventJournal;
+use crate::log_cache::{self, CacheSummary, LogCache};
+use crate::pipeline::Pipeline;
+use crate::pool_registry::{self, Registration};
+use crate::preview;
+use crate::price_webhooks::{PriceWebhooks, ReplayReport, DEFAULT_PRICE_WEBHOOK_RETRY_DELAY};
 use crate::pricing::calculate_price;
-use crate::reorg::{BlockRecord, ReorgDetector};
-use crate::rpc::{create_provider, get_latest_block};
+use crate::protocol::{self, DexProtocol};
+use crate::reorg::{history_limit, BlockRecord, ReorgDetector};
+use crate::replay;
+use crate::retention::{self, RetentionPolicy};
+use crate::rpc::{
+    capabilities, create_provider, get_latest_block, HybridProviderManager, ProviderMode,
+    StateRouter,
+};
+use crate::scheduler::{Scheduler, TaskContext};
+use crate::smoothing::PriceEwma;
+use crate::standby::StandbyControl;
 use crate::state::State;
-use alloy::primitives::{Log as PrimitiveLog, U256};
+use crate::watchdog::LagWatchdog;
+use alloy::primitives::{Address, U256};
 use alloy::providers::Provider;
 use alloy::rpc::types::Log;
-use alloy::sol_types::SolEvent;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
+/// Output format chosen with `--output`, set once at startup.
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Prints a line of human-readable output; silent with `--output json`.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if output_format() != OutputFormat::Json {
+            println!($($arg)*);
+        }
+    };
+}
+
 /// Uniswap V2 ETH/USDT Price Tracker
 #[derive(Parser, Debug)]
 #[command(name = "eth-uniswap-alloy")]
 #[command(about = "Production-grade Ethereum event indexer for Uniswap V2", long_about = None)]
 #[command(version)]
 struct Cli {
+    /// Print pending database migrations and exit without applying them
+    #[arg(long, global = true)]
+    migrate_dry_run: bool,
+
+    /// Print the effective configuration with secrets redacted and exit
+    #[arg(long, global = true)]
+    print_config: bool,
+
+    /// Output format for `price`, `watch` and `stats`
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
     /// Subcommand to execute
     #[command(subcommand)]
     command: Commands,
@@ -68,6 +139,14 @@ enum Commands {
         /// Starting block number (default: latest - 100)
         #[arg(short, long)]
         start_block: Option<u64>,
+
+        /// How new blocks are detected (ws and hybrid need `RPC_WS_URL`)
+        #[arg(long, value_enum, default_value_t = WatchMode::Http)]
+        mode: WatchMode,
+
+        /// Write a PID file and serve a health socket in `RUN_DIR`
+        #[arg(long)]
+        daemon: bool,
     },
 
     /// Start the REST API server
@@ -76,9 +155,576 @@ enum Commands {
         #[arg(long, default_value = "3000")]
         port: u16,
 
-        /// Rate limit (requests per minute, default: 100)
-        #[arg(long, default_value = "100")]
-        rate_limit: u32,
+        /// Rate limit (requests per minute, default: `API_RATE_LIMIT_RPM`)
+        #[arg(long)]
+        rate_limit: Option<u32>,
+
+        /// Also serve the gRPC API on this port (requires the `grpc` feature)
+        #[arg(long)]
+        grpc_port: Option<u16>,
+
+        /// Write a PID file and serve a health socket in `RUN_DIR`
+        #[arg(long)]
+        daemon: bool,
+    },
+
+    /// Query the health socket of a `--daemon` process (exits non-zero unless healthy)
+    Health {
+        /// Command whose daemon to query
+        #[arg(value_enum, default_value_t = DaemonCommand::Watch)]
+        command: DaemonCommand,
+    },
+
+    /// Find the pair of two tokens on each supported V2 fork
+    FindPair {
+        /// First token address
+        token_a: Address,
+
+        /// Second token address
+        token_b: Address,
+
+        /// Only look in this protocol's factory (default: all)
+        #[arg(long)]
+        protocol: Option<DexProtocol>,
+    },
+
+    /// Check indexed data for missing blocks and optionally backfill them
+    Verify {
+        /// First block to check (default: `--to-block` minus 1000)
+        #[arg(long)]
+        from_block: Option<u64>,
+
+        /// Last block to check (default: last indexed block)
+        #[arg(long)]
+        to_block: Option<u64>,
+
+        /// Backfill any gaps found
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Index a historical block range with concurrent workers
+    Backfill {
+        /// First block to index (default: block after the last indexed one)
+        #[arg(long)]
+        from_block: Option<u64>,
+
+        /// Last block to index (default: chain head minus `CONFIRMATION_DEPTH`)
+        #[arg(long)]
+        to_block: Option<u64>,
+
+        /// Shards indexed concurrently (default: 4)
+        #[arg(long, default_value_t = DEFAULT_BACKFILL_WORKERS)]
+        workers: usize,
+
+        /// Blocks per shard (default: 10000)
+        #[arg(long, default_value_t = DEFAULT_SHARD_BLOCKS)]
+        shard_blocks: u64,
+
+        /// Threads decoding each large log batch (default: 4)
+        #[arg(long, default_value_t = DEFAULT_DECODE_WORKERS)]
+        decode_workers: usize,
+
+        /// Only estimate events, RPC calls, duration and database growth
+        /// from a few sampled batches; nothing is written
+        #[arg(long)]
+        estimate: bool,
+
+        /// Batches sampled by `--estimate` (default: 5)
+        #[arg(long, default_value_t = DEFAULT_ESTIMATE_SAMPLES, requires = "estimate")]
+        samples: usize,
+    },
+
+    /// Print price statistics for a pool
+    Stats {
+        /// Pool ID, address or name (default: WETH/USDT)
+        #[arg(long, default_value = "WETH/USDT")]
+        pool: String,
+
+        /// Window to aggregate over (default: 24h)
+        #[arg(long, value_enum, default_value_t = StatsWindow::Day)]
+        period: StatsWindow,
+    },
+
+    /// Manage API keys
+    Keys {
+        /// Key operation
+        #[command(subcommand)]
+        action: KeyAction,
+    },
+
+    /// Register, list or remove pools
+    Pools {
+        /// Pool operation
+        #[command(subcommand)]
+        action: PoolAction,
+    },
+
+    /// Snapshot or restore the database
+    Db {
+        /// Database operation
+        #[command(subcommand)]
+        action: DbAction,
+    },
+
+    /// Inspect or clear the on-disk log cache (`LOG_CACHE_DIR`)
+    Cache {
+        /// Cache operation
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Inspect or replay failed price webhook deliveries (`PRICE_WEBHOOK_URLS`)
+    Webhooks {
+        /// Price webhook operation
+        #[command(subcommand)]
+        action: WebhookAction,
+    },
+
+    /// Check a TOML config file
+    Config {
+        /// Config file operation
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Record RPC responses to a fixture, or serve one (requires the `testing` feature)
+    Fixture {
+        /// Fixture operation
+        #[command(subcommand)]
+        action: FixtureAction,
+    },
+
+    /// Seed the database from a published snapshot, then start watching
+    Bootstrap {
+        /// URL of a snapshot written by `db snapshot`
+        #[arg(long)]
+        snapshot_url: String,
+
+        /// Expected snapshot checksum (Keccak-256 hex, as published)
+        #[arg(long)]
+        checksum: Option<String>,
+
+        /// Replace an existing database
+        #[arg(long)]
+        force: bool,
+
+        /// Exit after importing instead of starting live indexing
+        #[arg(long)]
+        no_watch: bool,
+
+        /// Polling interval in seconds once indexing starts (default: 12)
+        #[arg(short, long, default_value = "12")]
+        interval: u64,
+    },
+
+    /// Delete rows older than the retention policy and vacuum
+    Prune {
+        /// Days of sync events to keep (default: `RETENTION_SYNC_EVENTS_DAYS`)
+        #[arg(long)]
+        sync_events_days: Option<u32>,
+
+        /// Days of price points to keep (default: `RETENTION_PRICE_POINTS_DAYS`)
+        #[arg(long)]
+        price_points_days: Option<u32>,
+
+        /// Days of 1m/5m candles to keep (default: `RETENTION_CANDLES_DAYS`)
+        #[arg(long)]
+        candles_days: Option<u32>,
+
+        /// Skip the VACUUM after deleting
+        #[arg(long)]
+        no_vacuum: bool,
+    },
+
+    /// Compress a pool's old Sync events into the event archive
+    Archive {
+        /// Pool ID, address or name (default: WETH/USDT)
+        #[arg(long, default_value = "WETH/USDT")]
+        pool: String,
+
+        /// Archive whole segments below this block (default: the last
+        /// indexed block minus --keep-blocks)
+        #[arg(long)]
+        before_block: Option<u64>,
+
+        /// Recent blocks to keep as raw rows
+        #[arg(long, default_value_t = DEFAULT_ARCHIVE_KEEP_BLOCKS)]
+        keep_blocks: u64,
+
+        /// Skip the VACUUM after archiving
+        #[arg(long)]
+        no_vacuum: bool,
+    },
+
+    /// Recompute a pool's prices from its stored Sync events
+    Replay {
+        /// Pool ID, address or name (default: WETH/USDT)
+        #[arg(long, default_value = "WETH/USDT")]
+        pool: String,
+
+        /// Replay events from this block on (default: all stored events)
+        #[arg(long)]
+        from_block: Option<u64>,
+
+        /// Write the prices to the shadow table only and report the differences
+        #[arg(long)]
+        shadow: bool,
+    },
+
+    /// Follow a primary's database and serve the API until promoted
+    Standby {
+        /// Path to the primary's database file
+        #[arg(long)]
+        primary_db: PathBuf,
+
+        /// Seconds between follow passes (default: 2)
+        #[arg(long, default_value = "2")]
+        follow_interval: u64,
+
+        /// Polling interval in seconds once promoted (default: 12)
+        #[arg(short, long, default_value = "12")]
+        interval: u64,
+
+        /// Port to listen on (default: 3000)
+        #[arg(long, default_value = "3000")]
+        port: u16,
+
+        /// Rate limit (requests per minute, default: `API_RATE_LIMIT_RPM`)
+        #[arg(long)]
+        rate_limit: Option<u32>,
+    },
+}
+
+/// Commands that can run with `--daemon`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DaemonCommand {
+    /// The `watch` indexer
+    Watch,
+    /// The `api` server
+    Api,
+}
+
+impl DaemonCommand {
+    /// Name of the PID file and socket in `RUN_DIR`.
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Watch => "watch",
+            Self::Api => "api",
+        }
+    }
+}
+
+/// Output format for command results
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum OutputFormat {
+    /// Colored text for terminals
+    #[default]
+    Table,
+    /// The same text without colors
+    Plain,
+    /// One JSON object per line (logs go to stderr)
+    Json,
+}
+
+fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// A result printed as one JSON line with `--output json`.
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OutputRecord<'a> {
+    /// A price computed from a Sync event
+    Price {
+        block_number: u64,
+        price: f64,
+        reserve0: String,
+        reserve1: String,
+        change_percent: Option<f64>,
+        timestamp: i64,
+    },
+    /// A chain reorganization handled by `watch`
+    Reorg { fork_point: u64, depth: u64 },
+    /// A failed `watch` pass (retried on the next one)
+    Error {
+        message: String,
+        code: &'static str,
+        retryable: bool,
+    },
+    /// No Sync events in the scanned range
+    NoEvents { from_block: u64, to_block: u64 },
+    /// The `stats` report
+    Stats(&'a PoolStatsReport),
+    /// A daemon's health report
+    Health(&'a daemon::HealthReport),
+    /// The `db stats` report
+    DbStats(&'a DbStatsReport),
+    /// A `backfill --estimate` report
+    BackfillEstimate {
+        estimate: &'a BackfillEstimate,
+        db_bytes: Option<u64>,
+    },
+    /// The `cache stats` report
+    CacheStats(&'a CacheSummary),
+    /// A dead-lettered price webhook delivery
+    DeadLetter(&'a PriceWebhookDeadLetterRow),
+    /// The `webhooks replay` outcome
+    WebhookReplay(&'a ReplayReport),
+}
+
+/// Prints `record` as one JSON line if `--output json` is set.
+fn emit(record: &OutputRecord<'_>) {
+    if output_format() != OutputFormat::Json {
+        return;
+    }
+    match serde_json::to_string(record) {
+        Ok(line) => println!("{line}"),
+        Err(e) => error!("Failed to encode output: {}", e),
+    }
+}
+
+/// How watch mode learns about new blocks
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum WatchMode {
+    /// Poll over HTTP every interval
+    Http,
+    /// Index on every new block header from a WebSocket subscription
+    Ws,
+    /// Use the WebSocket subscription, polling over HTTP while it is down
+    Hybrid,
+}
+
+impl From<WatchMode> for ProviderMode {
+    fn from(mode: WatchMode) -> Self {
+        match mode {
+            WatchMode::Http => Self::Http,
+            WatchMode::Ws => Self::WebSocket,
+            WatchMode::Hybrid => Self::Hybrid,
+        }
+    }
+}
+
+/// Window for the `stats` command
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum StatsWindow {
+    /// Last hour
+    #[value(name = "1h")]
+    Hour,
+    /// Last 24 hours
+    #[value(name = "24h")]
+    Day,
+    /// Last 7 days
+    #[value(name = "7d")]
+    Week,
+    /// Last 30 days
+    #[value(name = "30d")]
+    Month,
+    /// Everything indexed
+    All,
+}
+
+impl StatsWindow {
+    /// Window length in seconds (`None` = unbounded).
+    const fn seconds(self) -> Option<i64> {
+        match self {
+            Self::Hour => Some(3_600),
+            Self::Day => Some(86_400),
+            Self::Week => Some(7 * 86_400),
+            Self::Month => Some(30 * 86_400),
+            Self::All => None,
+        }
+    }
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Hour => "1h",
+            Self::Day => "24h",
+            Self::Week => "7d",
+            Self::Month => "30d",
+            Self::All => "all",
+        }
+    }
+}
+
+/// API key operations
+#[derive(Subcommand, Debug)]
+enum KeyAction {
+    /// Mint a new key and print it (shown only once)
+    Create {
+        /// Label for the key (e.g. the client it is issued to)
+        name: String,
+
+        /// Per-key rate limit in requests per minute (default: server limit)
+        #[arg(long)]
+        rate_limit: Option<u32>,
+
+        /// Restrict the key to these pools (IDs, addresses or names)
+        #[arg(long, value_delimiter = ',')]
+        pools: Vec<String>,
+
+        /// Restrict the key to servers tracking this chain
+        #[arg(long)]
+        chain_id: Option<u64>,
+    },
+
+    /// List keys
+    List,
+
+    /// Revoke a key by ID or prefix
+    Revoke {
+        /// Key ID or prefix as shown by `keys list`
+        key: String,
+    },
+}
+
+/// Pool registry operations
+#[derive(Subcommand, Debug)]
+enum PoolAction {
+    /// Register the pools listed in a CSV file, reading their tokens from the chain
+    Add {
+        /// CSV file with `address,name,protocol,pool_type,quote_direction,start_block` lines
+        #[arg(long)]
+        file: PathBuf,
+    },
+
+    /// List registered pools
+    List,
+
+    /// Resume indexing a pool
+    Enable {
+        /// Pool ID, address or name
+        pool: String,
+    },
+
+    /// Pause indexing a pool, keeping its data
+    Disable {
+        /// Pool ID, address or name
+        pool: String,
+    },
+
+    /// Set the block a pool without a checkpoint starts indexing at
+    StartBlock {
+        /// Pool ID, address or name
+        pool: String,
+
+        /// First block to index, e.g. the pool's creation block (omit to clear)
+        block: Option<u64>,
+    },
+
+    /// Leave reverted single-block price spikes out of a pool's candles and stats
+    SpikeFilter {
+        /// Pool ID, address or name
+        pool: String,
+
+        /// Smallest move away from the price before the block, in basis points (omit to turn off)
+        bps: Option<u32>,
+    },
+
+    /// Remove a pool with all of its indexed data
+    Remove {
+        /// Pool ID, address or name
+        pool: String,
+
+        /// Confirm deleting the pool's events, prices and checkpoint
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+/// Database operations
+#[derive(Subcommand, Debug)]
+enum DbAction {
+    /// Write a compressed, checksummed snapshot of the database
+    Snapshot {
+        /// Snapshot file (default: `<database>-<UTC timestamp>.db.gz`)
+        output: Option<PathBuf>,
+    },
+
+    /// Verify a snapshot and restore it as the database
+    Restore {
+        /// Snapshot file written by `db snapshot`
+        snapshot: PathBuf,
+
+        /// Replace an existing database
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Run pending data migrations, resuming interrupted ones
+    Migrate {
+        /// Only list each data migration's progress
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Report table and index sizes, indexed block ranges and growth
+    Stats {
+        /// Days of writes to estimate the growth rate from
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+    },
+}
+
+/// Log cache operations
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Report cached ranges, their size and the hit ratio of past runs
+    Stats,
+
+    /// Delete every cached range and the saved hit counts
+    Clear,
+}
+
+/// Price webhook operations
+#[derive(Subcommand, Debug)]
+enum WebhookAction {
+    /// List deliveries that failed every attempt
+    DeadLetters {
+        /// Most dead letters to list, oldest first
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+
+    /// Redeliver dead letters to the configured URLs, deleting those that get through
+    Replay,
+}
+
+/// Config file operations
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Load a config file with the current environment and report problems
+    Validate {
+        /// Config file (default: `CONFIG_FILE`, or indexer.toml)
+        file: Option<PathBuf>,
+    },
+}
+
+/// RPC fixture operations
+#[derive(Subcommand, Debug)]
+enum FixtureAction {
+    /// Proxy `RPC_URL` on a local port, recording responses until Ctrl+C
+    Record {
+        /// Fixture file to write
+        fixture: PathBuf,
+
+        /// Answer as if this block were the latest (default: the node's head)
+        #[arg(long)]
+        head: Option<u64>,
+
+        /// Local port to proxy on (default: 8546)
+        #[arg(long, default_value = "8546")]
+        port: u16,
+    },
+
+    /// Serve a recorded fixture on a local port, for use as `RPC_URL`
+    Serve {
+        /// Fixture file written by `fixture record`
+        fixture: PathBuf,
+
+        /// Local port to serve on (default: 8546)
+        #[arg(long, default_value = "8546")]
+        port: u16,
     },
 }
 
@@ -92,15 +738,165 @@ enum Commands {
 /// - Command execution fails
 pub async fn run() -> TrackerResult<()> {
     let cli = Cli::parse();
+    if cli.output != OutputFormat::Table {
+        colored::control::set_override(false);
+    }
+    let _ = OUTPUT_FORMAT.set(cli.output);
+
+    if cli.print_config {
+        return print_config();
+    }
+    if cli.migrate_dry_run {
+        return run_migrate_dry_run().await;
+    }
 
     match cli.command {
         Commands::Price { blocks } => run_price_command(blocks).await,
         Commands::Watch {
             interval,
             start_block,
-        } => run_watch_command(interval, start_block).await,
-        Commands::Api { port, rate_limit } => run_api_command(port, rate_limit).await,
+            mode,
+            daemon,
+        } => run_watch_command(interval, start_block, mode, daemon).await,
+        Commands::Api {
+            port,
+            rate_limit,
+            grpc_port,
+            daemon,
+        } => run_api_command(port, rate_limit, grpc_port, daemon).await,
+        Commands::Health { command } => run_health_command(command).await,
+        Commands::FindPair {
+            token_a,
+            token_b,
+            protocol,
+        } => run_find_pair_command(token_a, token_b, protocol).await,
+        Commands::Verify {
+            from_block,
+            to_block,
+            fix,
+        } => run_verify_command(from_block, to_block, fix).await,
+        Commands::Backfill {
+            from_block,
+            to_block,
+            workers,
+            shard_blocks,
+            decode_workers,
+            estimate,
+            samples,
+        } => {
+            let sharding = (workers, shard_blocks, decode_workers);
+            let estimate = estimate.then_some(samples);
+            run_backfill_command(from_block, to_block, sharding, estimate).await
+        }
+        Commands::Stats { pool, period } => run_stats_command(&pool, period).await,
+        Commands::Keys { action } => run_keys_command(action).await,
+        Commands::Pools { action } => run_pools_command(action).await,
+        Commands::Db { action } => run_db_command(action).await,
+        Commands::Cache { action } => run_cache_command(action).await,
+        Commands::Webhooks { action } => run_webhooks_command(action).await,
+        Commands::Config { action } => run_config_command(action),
+        Commands::Fixture { action } => run_fixture_command(action).await,
+        Commands::Bootstrap {
+            snapshot_url,
+            checksum,
+            force,
+            no_watch,
+            interval,
+        } => {
+            run_bootstrap_command(
+                &snapshot_url,
+                checksum.as_deref(),
+                force,
+                !no_watch,
+                interval,
+            )
+            .await
+        }
+        Commands::Prune {
+            sync_events_days,
+            price_points_days,
+            candles_days,
+            no_vacuum,
+        } => {
+            let overrides = RetentionPolicy {
+                sync_events_days,
+                price_points_days,
+                candles_days,
+            };
+            run_prune_command(overrides, !no_vacuum).await
+        }
+        Commands::Archive {
+            pool,
+            before_block,
+            keep_blocks,
+            no_vacuum,
+        } => run_archive_command(&pool, before_block, keep_blocks, !no_vacuum).await,
+        Commands::Replay {
+            pool,
+            from_block,
+            shadow,
+        } => run_replay_command(&pool, from_block, !shadow).await,
+        Commands::Standby {
+            primary_db,
+            follow_interval,
+            interval,
+            port,
+            rate_limit,
+        } => run_standby_command(primary_db, follow_interval, interval, port, rate_limit).await,
+    }
+}
+
+/// Print the effective configuration, one `NAME=value` line per setting.
+fn print_config() -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    for (name, value) in config.redacted_values() {
+        println!("{name}={value}");
+    }
+    Ok(())
+}
+
+/// Print pending migrations without applying them.
+///
+/// The database is opened read-only and must already exist.
+async fn run_migrate_dry_run() -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let pool = connect_existing(config.database_url()).await?;
+    let pending = pending_migrations(&pool).await?;
+
+    if pending.is_empty() {
+        println!("{} Database schema is up to date", "✅".green());
+        return Ok(());
+    }
+
+    println!(
+        "{} {} pending migration(s) for {} (not applied):",
+        "📋".cyan(),
+        pending.len(),
+        config.database_url()
+    );
+    for migration in &pending {
+        println!();
+        println!(
+            "{}",
+            format!("-- {} {}", migration.version, migration.description).bold()
+        );
+        println!("{}", migration.sql.trim_end());
     }
+
+    println!();
+    match config.migration_backup_dir() {
+        Some(dir) => println!(
+            "{} A backup will be written to {} before migrating",
+            "💾".cyan(),
+            dir.display()
+        ),
+        None => println!(
+            "{} MIGRATION_BACKUP_DIR is not set; no backup will be taken",
+            "⚠️".yellow()
+        ),
+    }
+
+    Ok(())
 }
 
 /// Execute the price command (one-time fetch).
@@ -126,7 +922,11 @@ async fn run_price_command(blocks: u64) -> TrackerResult<()> {
 
     if logs.is_empty() {
         warn!("No Sync events found in the last {} blocks", blocks);
-        println!(
+        emit(&OutputRecord::NoEvents {
+            from_block,
+            to_block: latest_block,
+        });
+        say!(
             "{}",
             "No recent price updates found. Try increasing --blocks."
                 .yellow()
@@ -145,7 +945,8 @@ async fn run_price_command(blocks: u64) -> TrackerResult<()> {
     let (sync_event, block_number) = decode_sync_event(latest_log)?;
 
     // Create database connection to fetch pool details
-    let pool_conn = create_pool(config.database_url()).await?;
+    let pool_conn =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
     let repository = Repository::new(pool_conn);
 
     // Fetch pool details for decimals
@@ -171,30 +972,100 @@ async fn run_price_command(blocks: u64) -> TrackerResult<()> {
 }
 
 /// Execute the watch command (continuous monitoring).
-async fn run_watch_command(interval: u64, start_block: Option<u64>) -> TrackerResult<()> {
+async fn run_watch_command(
+    interval: u64,
+    start_block: Option<u64>,
+    mode: WatchMode,
+    daemon: bool,
+) -> TrackerResult<()> {
     info!("Starting price watch mode");
-    println!(
+    say!(
         "{}",
         "🔍 Watching for ETH/USDT price updates...".cyan().bold()
     );
-    println!();
+    say!();
 
     // Load configuration
     let config = Config::from_env()?;
+    check_redis_support(&config)?;
 
-    // Create provider
-    let provider = create_provider(config.rpc_url()).await?;
+    // Under a service manager: PID file and a health socket that reports
+    // stale once passes stop succeeding
+    let daemon = if daemon {
+        let stale_after = Duration::from_secs((interval * DAEMON_STALE_PASSES).max(60));
+        Some(
+            Daemon::start(
+                config.run_dir(),
+                DaemonCommand::Watch.name(),
+                Some(stale_after),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+    let liveness = daemon.as_ref().map(Daemon::liveness);
+
+    // Size getLogs calls to the provider, and poll over HTTP in hybrid mode
+    // when its WebSocket endpoint can't be reached
+    let capabilities = capabilities::probe(
+        &create_provider(config.rpc_url()).await?,
+        config.rpc_url(),
+        config.rpc_ws_url(),
+    )
+    .await;
+    let ws_unreachable = mode == WatchMode::Hybrid && capabilities.websocket == Some(false);
+    let ws_url = config.rpc_ws_url().filter(|_| !ws_unreachable);
+
+    // Create providers: HTTP for all queries, WebSocket (if any) only to
+    // learn about new blocks
+    let manager = HybridProviderManager::new(
+        config.rpc_url().to_string(),
+        ws_url.map(str::to_string),
+        mode.into(),
+    )
+    .await
+    .map_err(|e| TrackerError::rpc(format!("Failed to initialize providers: {e}"), None))?
+    .with_ws_stale_after(Duration::from_secs(config.ws_stale_after_secs()));
+    let provider = manager.http().clone();
+    let mut new_blocks = manager.into_block_notifier();
+
+    // Reserve snapshots read state at the indexed block, which a full node
+    // prunes after 128 blocks; refuse a deep start rather than fail later
+    let mut state_reads = StateRouter::new(Arc::new(provider.clone()), capabilities.archive);
+    if let Some(url) = config.rpc_archive_url() {
+        state_reads = state_reads.with_archive(Arc::new(create_provider(url).await?));
+    }
+    if let (Some(_), Some(block)) = (config.reserve_snapshot_secs(), start_block) {
+        state_reads.route(block, get_latest_block(&provider).await?)?;
+    }
+    if ws_unreachable {
+        warn!("WebSocket endpoint unreachable, falling back to HTTP polling");
+    } else if mode != WatchMode::Http && new_blocks.is_none() {
+        warn!("RPC_WS_URL not set, falling back to HTTP polling");
+    }
 
     // Create database connection for persistence
-    let pool = create_pool(config.database_url()).await?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
     let repository = Repository::new(pool);
 
-    // Ensure the pool exists in database and fetch its details
-    let _pool_id = repository.ensure_default_pool().await?;
+    // Ensure the default pool exists in database and fetch its details
+    let pool_id = repository.ensure_default_pool().await?;
+    repository
+        .set_pool_protocol(pool_id, config.pool_protocol())
+        .await?;
+    repository
+        .set_pool_type(pool_id, config.pool_type())
+        .await?;
+    repository
+        .set_pool_quote_direction(pool_id, config.quote_direction())
+        .await?;
     let pool = repository
         .get_pool_by_name("WETH/USDT")
         .await?
         .ok_or_else(|| TrackerError::state("Pool not found after initialization", None))?;
+    warn_on_protocol_mismatch(&provider, &config, pool.address).await;
     info!(
         "Using pool: {} (token0: {} decimals={}, token1: {} decimals={})",
         pool.name.as_deref().unwrap_or("unknown"),
@@ -204,138 +1075,2202 @@ async fn run_watch_command(interval: u64, start_block: Option<u64>) -> TrackerRe
         pool.token1_decimals
     );
 
-    // Initialize state tracker - load from file if exists
-    let mut state = State::load(config.state_file()).unwrap_or_else(|e| {
-        warn!("Failed to load state: {}, starting fresh", e);
-        State::new()
+    // Rewrite rows stored by older versions (stable IDs, exact prices, ...)
+    DataMigrator::new(&repository)
+        .with_migrations(data_migrations::builtin(config.chain_id()))
+        .run()
+        .await?;
+
+    // The default pool resumes from a legacy state file, imported once
+    import_legacy_state(config.state_file(), pool.id, &repository).await?;
+
+    let shared = Arc::new(WatchShared {
+        provider,
+        repository: repository.clone(),
+        start_block,
+        liveness,
+        // Logs fetched twice (hybrid passes, retried ranges) are only priced
+        // once; log keys are unique across pools, so one deduplicator serves all
+        dedup: LogDeduplicator::default(),
+        // Fetched logs are journaled before they are committed
+        journal: config
+            .event_journal_dir()
+            .map(|dir| EventJournal::new(dir, config.event_journal_segment_blocks())),
+        // Publish new prices to API servers behind Redis
+        #[cfg(feature = "redis")]
+        redis: connect_redis(&config).await?,
+        config: config.clone(),
+        state_reads,
+        batch_blocks: capabilities
+            .max_log_range
+            .map_or(WATCH_BATCH_BLOCKS, |max| max.min(WATCH_MAX_BATCH_BLOCKS)),
     });
-    let mut last_price: Option<f64> = None;
 
-    // Initialize reorg detector
-    let mut reorg_detector = ReorgDetector::new();
+    // One supervised task per enabled pool, each resuming from its own
+    // checkpoint; see `scheduler`
+    let mut scheduler = Scheduler::new(
+        repository,
+        Duration::from_secs(interval),
+        move |pool, context| run_pool_task(Arc::clone(&shared), pool, context),
+    )
+    .with_max_concurrent(config.max_concurrent_pools());
 
-    // If we have a previous block hash, initialize the detector with it
-    if let Some(hash) = state.last_block_hash() {
-        let record = BlockRecord::new(
-            state.get_last_block(),
-            hash,
-            alloy::primitives::B256::ZERO, // We don't have parent hash, but it won't be used for initial check
-            0,                             // Timestamp not needed for initial state
-        );
-        reorg_detector.add_block(record);
-        info!(
-            "Initialized reorg detector with block {} (hash: {})",
-            state.get_last_block(),
-            hash
-        );
-    }
+    // Setup graceful shutdown handler
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
 
-    // Determine starting block (use saved state if available)
-    let latest_block = get_latest_block(&provider).await?;
-    let mut last_processed_block = if state.get_last_block() > 0 {
-        info!(
-            "Resuming from saved state at block: {}",
-            state.get_last_block()
-        );
-        state.get_last_block()
-    } else {
-        start_block.unwrap_or_else(|| latest_block.saturating_sub(100))
-    };
-    info!("Starting from block: {}", last_processed_block);
+    // Main watch loop
+    loop {
+        tokio::select! {
+            // Handle shutdown signal
+            () = &mut shutdown => {
+                info!("Shutdown signal received, cleaning up...");
+                say!();
+                say!("{}", "🛑 Shutting down gracefully...".yellow().bold());
 
-    // Display reorg statistics if any
-    if state.reorg_count() > 0 {
-        info!("Total reorgs detected: {}", state.reorg_count());
-        println!(
-            "{} Total reorgs handled: {}",
-            "📊".cyan(),
-            state.reorg_count()
-        );
+                // Stop waking the pool tasks and let passes in progress write
+                // their batches and commit their checkpoints; a second signal
+                // stops at once
+                let timeout = config.shutdown_drain_timeout();
+                let drained = tokio::select! {
+                    drained = scheduler.drain(timeout) => drained,
+                    () = shutdown_signal() => false,
+                };
+                if !drained {
+                    warn!(
+                        timeout_secs = timeout.as_secs(),
+                        "Aborting passes still in progress; they resume from their checkpoints"
+                    );
+                }
+                scheduler.shutdown().await;
+
+                say!("{}", "👋 Shutdown complete".green().bold());
+                info!("Shutdown complete");
+                break;
+            }
+
+            // Wake the pool tasks, then wait for the next block (or polling
+            // interval)
+            result = async {
+                scheduler.tick().await;
+                wait_for_next_pass(&mut new_blocks, mode, interval).await
+            } => {
+                if let Err(e) = result {
+                    error!("{}", e);
+                    scheduler.shutdown().await;
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Shared by the pool tasks of `watch`.
+struct WatchShared {
+    provider: crate::rpc::Provider,
+    repository: Repository,
+    config: Config,
+    /// `--start-block`, for pools without a checkpoint
+    start_block: Option<u64>,
+    liveness: Option<Arc<daemon::Liveness>>,
+    dedup: LogDeduplicator,
+    journal: Option<EventJournal>,
+    #[cfg(feature = "redis")]
+    redis: Option<crate::redis_bus::RedisBus>,
+    /// Picks the provider for reserve snapshots
+    state_reads: StateRouter,
+    /// Blocks per `eth_getLogs` call
+    batch_blocks: u64,
+}
+
+/// Blocks per `eth_getLogs` call in `watch` when the provider's range is
+/// unknown (Alchemy free tier limit).
+const WATCH_BATCH_BLOCKS: u64 = 10;
+
+/// Most blocks per `eth_getLogs` call in `watch`, however large a range the
+/// provider accepts, so catching up doesn't request more logs than one
+/// response may hold.
+const WATCH_MAX_BATCH_BLOCKS: u64 = 1_000;
+
+/// Indexes one pool, one pass per scheduler wake-up, until the pool is
+/// disabled or `watch` stops.
+///
+/// # Errors
+///
+/// Returns an error if the pool's resume point can't be checked; the
+/// scheduler restarts the task later. Failed passes are reported and retried
+/// on the next wake-up.
+async fn run_pool_task(
+    shared: Arc<WatchShared>,
+    pool: PoolRecord,
+    mut context: TaskContext,
+) -> TrackerResult<()> {
+    let WatchShared {
+        provider,
+        repository,
+        config,
+        ..
+    } = &*shared;
+
+    // Initialize reorg detector, tracking more blocks on chains with deep reorgs
+    let mut reorg_detector =
+        ReorgDetector::new().with_history_limit(history_limit(config.confirmations()));
+
+    // Repair disagreements between the checkpoint, the stored rows and the
+    // chain before resuming, seeding the detector with the stored hashes
+    let check =
+        consistency::verify_resume_point(provider, repository, pool.id, &mut reorg_detector)
+            .await?;
+    let checkpoint = check.checkpoint.unwrap_or_default();
+    let mut state = State::new();
+    let mut last_price: Option<f64> = None;
+
+    // Quiet periods are measured from the last stored price's block
+    let mut last_price_time = repository
+        .get_latest_price(pool.id)
+        .await?
+        .and_then(|p| u64::try_from(p.block_timestamp).ok());
+
+    // Resume the smoothed price from the last stored value
+    let mut price_ewma = match config.price_ewma_half_life_secs() {
+        Some(half_life) => Some(resume_price_ewma(repository, pool.id, half_life).await?),
+        None => None,
+    };
+
+    #[cfg(feature = "redis")]
+    let mut last_published = None;
+
+    // Determine starting block (use the checkpoint if available, then
+    // --start-block, then the pool's start block)
+    let mut last_processed_block = if checkpoint.block > 0 {
+        info!(
+            pool_id = pool.id,
+            "Resuming from checkpoint at block: {}", checkpoint.block
+        );
+        checkpoint.block
+    } else if let Some(block) = shared.start_block {
+        block
+    } else if let Some(block) = pool.start_block() {
+        // The pool's start block itself is indexed
+        info!(
+            pool_id = pool.id,
+            "Starting at the pool's start block: {}", block
+        );
+        block.saturating_sub(1)
+    } else {
+        get_latest_block(provider).await?.saturating_sub(100)
+    };
+    info!(
+        pool_id = pool.id,
+        "Starting from block: {}", last_processed_block
+    );
+
+    // Commit logs journaled before a crash before fetching anything new
+    if let Some(journal) = &shared.journal {
+        last_processed_block = replay_journal(
+            journal,
+            repository,
+            config,
+            &pool,
+            last_processed_block,
+            &mut state,
+            &mut price_ewma,
+            &mut last_price,
+        )
+        .await?;
+    }
+
+    // Display reorg statistics if any
+    if checkpoint.reorg_count > 0 {
+        info!(
+            pool_id = pool.id,
+            "Total reorgs detected: {}", checkpoint.reorg_count
+        );
+        say!(
+            "{} {}: total reorgs handled: {}",
+            "📊".cyan(),
+            pool.name.as_deref().unwrap_or("unknown"),
+            checkpoint.reorg_count
+        );
+    }
+
+    while let Some(_permit) = context.next_pass().await {
+        let result = process_new_blocks(
+            provider,
+            repository,
+            config,
+            pool.id,
+            &mut state,
+            &mut reorg_detector,
+            &mut last_processed_block,
+            &mut last_price,
+            &mut last_price_time,
+            &mut price_ewma,
+            &shared.dedup,
+            shared.journal.as_ref(),
+            &shared.state_reads,
+            shared.batch_blocks,
+        )
+        .await;
+        context.report_pass(&result, last_processed_block).await;
+
+        match result {
+            Ok(()) => {
+                if let Some(liveness) = &shared.liveness {
+                    liveness.record_progress(last_processed_block);
+                    liveness.record_duplicates_dropped(shared.dedup.stats().duplicates_dropped);
+                }
+                #[cfg(feature = "redis")]
+                if let Some(redis) = &shared.redis {
+                    if let Err(e) = redis
+                        .share_latest(repository, &pool, &mut last_published)
+                        .await
+                    {
+                        warn!("Failed to share the latest price over Redis: {}", e);
+                    }
+                }
+                debug!(pool_id = pool.id, "Pass complete, waiting for the next one");
+            }
+            Err(e) => {
+                error!(pool_id = pool.id, "Error processing blocks: {}", e);
+                emit(&OutputRecord::Error {
+                    message: e.to_string(),
+                    code: e.code(),
+                    retryable: e.is_retryable(),
+                });
+                say!("{} {}", "⚠️  Error:".red().bold(), e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Waits until the next watch pass is due.
+///
+/// Without a block subscription this is the polling interval. With one, each
+/// new block (plus any queued behind it) triggers a pass. In hybrid mode a
+/// pass also runs if no block arrives within the interval, so indexing keeps
+/// going over HTTP while the WebSocket reconnects.
+///
+/// # Errors
+///
+/// Returns an error in ws mode once the subscription is lost for good.
+async fn wait_for_next_pass(
+    new_blocks: &mut Option<mpsc::Receiver<u64>>,
+    mode: WatchMode,
+    interval: u64,
+) -> TrackerResult<()> {
+    let poll = Duration::from_secs(interval);
+    let Some(blocks) = new_blocks else {
+        tokio::time::sleep(poll).await;
+        return Ok(());
+    };
+
+    let next = if mode == WatchMode::Ws {
+        blocks.recv().await
+    } else {
+        let Ok(next) = tokio::time::timeout(poll, blocks.recv()).await else {
+            debug!("No new block within {} seconds, polling", interval);
+            return Ok(());
+        };
+        next
+    };
+
+    match next {
+        Some(block) => {
+            // One pass covers every block that arrived meanwhile
+            let mut latest = block;
+            while let Ok(block) = blocks.try_recv() {
+                latest = latest.max(block);
+            }
+            debug!(block = latest, "New block received over WebSocket");
+            Ok(())
+        }
+        None if mode == WatchMode::Ws => Err(TrackerError::websocket_disconnected(
+            "block subscription lost and reconnecting failed",
+        )),
+        None => {
+            warn!("WebSocket block subscription closed, falling back to HTTP polling");
+            *new_blocks = None;
+            Ok(())
+        }
+    }
+}
+
+/// Execute the API server command.
+async fn run_api_command(
+    port: u16,
+    rate_limit: Option<u32>,
+    grpc_port: Option<u16>,
+    daemon: bool,
+) -> TrackerResult<()> {
+    info!("Starting API server");
+
+    if grpc_port.is_some() && !cfg!(feature = "grpc") {
+        return Err(TrackerError::config(
+            "--grpc-port needs a binary built with the `grpc` feature (cargo build --features grpc)",
+            None,
+        ));
+    }
+
+    let config = Config::from_env()?;
+    check_redis_support(&config)?;
+    check_discord_support(&config)?;
+    let _daemon = if daemon {
+        Some(Daemon::start(config.run_dir(), DaemonCommand::Api.name(), None).await?)
+    } else {
+        None
+    };
+
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+
+    let repository = Repository::new(pool);
+    let pool_id = repository.ensure_default_pool().await?;
+    repository
+        .set_pool_protocol(pool_id, config.pool_protocol())
+        .await?;
+    repository
+        .set_pool_type(pool_id, config.pool_type())
+        .await?;
+    repository
+        .set_pool_quote_direction(pool_id, config.quote_direction())
+        .await?;
+    let reader = repository.reader().await?;
+    let provider = create_provider(config.rpc_url()).await?;
+    let mut state = AppState::new(repository)
+        .with_reader(reader)
+        .with_auth_required_paths(config.api_auth_required_paths().to_vec())
+        .with_chain_id(config.chain_id())
+        .with_rate_limits(
+            rate_limit.unwrap_or_else(|| config.api_rate_limit_rpm()),
+            config.api_rate_limit_routes().to_vec(),
+        )
+        .with_price_stale_after_secs(config.price_stale_after_secs())
+        .with_health_max_lag_blocks(config.health_max_lag_blocks())
+        .with_provider_capabilities(
+            capabilities::probe(&provider, config.rpc_url(), config.rpc_ws_url()).await,
+        )
+        .with_rpc(provider);
+    if let Some(url) = config.rpc_archive_url() {
+        state = state.with_archive_rpc(create_provider(url).await?);
+    }
+
+    if let Some(key) = config.response_signing_key() {
+        let signer = ResponseSigner::from_base64(key)?;
+        info!(key_id = signer.key_id(), "Signing price responses");
+        state = state.with_response_signer(signer);
+    }
+
+    #[cfg(feature = "redis")]
+    if let Some(bus) = connect_redis(&config).await? {
+        state = state.with_redis(bus);
+    }
+
+    let notifiers =
+        Notifiers::from_config(&config)?.with_delivery_log(state.repository.as_ref().clone());
+    for destination in [config.lag_alert_webhook_url(), config.depeg_webhook_url()]
+        .into_iter()
+        .flatten()
+    {
+        notifiers.check(destination)?;
+    }
+
+    if let Some(path) = config.alert_rules_file() {
+        let rules = load_rules(path)?;
+        for rule in &rules {
+            notifiers.check(&rule.destination)?;
+        }
+        info!(rules = rules.len(), path = %path.display(), "Loaded alert rules");
+        state = state.with_alerts(AlertEngine::new(rules, notifiers.clone()));
+    }
+
+    if let (Some(max_lag_blocks), Some(provider)) = (config.lag_alert_blocks(), state.rpc.clone()) {
+        info!(max_lag_blocks, "Lag watchdog enabled");
+        let watchdog = Arc::new(LagWatchdog::new(
+            max_lag_blocks,
+            config.lag_alert_webhook_url().cloned(),
+            notifiers.clone(),
+        ));
+        let _watchdog = Arc::clone(&watchdog).spawn(state.reader.clone(), provider, pool_id);
+        state = state.with_lag_watchdog(watchdog);
+    }
+
+    if let Some(band_bps) = config.depeg_band_bps() {
+        if !matches!(config.pool_type(), PoolType::StableSwap { .. }) {
+            warn!("DEPEG_BAND_BPS is set but POOL_TYPE is not stable_swap");
+        }
+        let pool_name = state
+            .reader
+            .get_pool_by_id(pool_id)
+            .await?
+            .and_then(|p| p.name)
+            .unwrap_or_else(|| pool_id.to_string());
+        info!(
+            band_bps,
+            min_blocks = config.depeg_min_blocks(),
+            "Depeg detection enabled"
+        );
+        let _depeg = DepegMonitor::new(
+            pool_id,
+            pool_name,
+            band_bps,
+            config.depeg_min_blocks(),
+            config.depeg_webhook_url().cloned(),
+            notifiers,
+        )
+        .spawn(state.repository.as_ref().clone());
+    }
+
+    if !config.composite_index_pools().is_empty() {
+        let pools = composite::resolve_pools(&state.reader, config.composite_index_pools()).await?;
+        info!(pools = pools.len(), "Composite price index enabled");
+        let _composite = CompositeIndexer::new(pools).spawn(state.repository.as_ref().clone());
+    }
+
+    #[cfg(feature = "discord")]
+    if let Some(token) = config.discord_bot_token() {
+        info!("Discord bot enabled");
+        let _discord = crate::discord::spawn(state.clone(), token.to_string());
+    }
+
+    if let Some(secret) = config.price_webhook_secret() {
+        if !config.price_webhook_urls().is_empty() {
+            let webhooks = PriceWebhooks::new(config.price_webhook_urls().to_vec(), secret)?
+                .with_retry(
+                    config.price_webhook_max_attempts(),
+                    DEFAULT_PRICE_WEBHOOK_RETRY_DELAY,
+                );
+            info!(urls = webhooks.urls().len(), "Price webhooks enabled");
+            let _webhooks = webhooks.spawn(state.repository.as_ref().clone());
+        }
+    }
+
+    if let (Some(block), Some(provider)) = (config.price_preview(), state.rpc.clone()) {
+        info!(%block, "Price preview enabled");
+        let _preview = preview::spawn(state.clone(), provider, block);
+    }
+
+    let retention = config.retention();
+    if retention.is_enabled() {
+        info!(?retention, "Background pruning enabled");
+        let _pruner = retention::spawn_pruner(
+            state.repository.clone(),
+            retention,
+            Duration::from_secs(config.retention_interval_secs()),
+        );
+    }
+
+    let cors = config.api_cors().clone();
+
+    // Return on shutdown so the daemon's PID file and socket are removed
+    tokio::select! {
+        result = server::run_server(state.clone(), port, cors) => {
+            result.map_err(|e| TrackerError::state(format!("API server failed: {e}"), None))?;
+        }
+        result = serve_grpc(state, grpc_port) => result?,
+        () = shutdown_signal() => info!("Shutdown signal received"),
+    }
+
+    Ok(())
+}
+
+/// Rejects `REDIS_URL` in a binary built without the `redis` feature, rather
+/// than silently running without shared prices.
+fn check_redis_support(config: &Config) -> TrackerResult<()> {
+    if config.redis_url().is_some() && !cfg!(feature = "redis") {
+        return Err(TrackerError::config(
+            "REDIS_URL needs a binary built with the `redis` feature (cargo build --features redis)",
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects `DISCORD_BOT_TOKEN` in a binary built without the `discord`
+/// feature, rather than silently running without the bot.
+fn check_discord_support(config: &Config) -> TrackerResult<()> {
+    if config.discord_bot_token().is_some() && !cfg!(feature = "discord") {
+        return Err(TrackerError::config(
+            "DISCORD_BOT_TOKEN needs a binary built with the `discord` feature (cargo build --features discord)",
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Connects to the Redis server for shared prices, if `REDIS_URL` is set.
+#[cfg(feature = "redis")]
+async fn connect_redis(config: &Config) -> TrackerResult<Option<crate::redis_bus::RedisBus>> {
+    let Some(url) = config.redis_url() else {
+        return Ok(None);
+    };
+    let bus = crate::redis_bus::RedisBus::connect(url, config.redis_key_prefix()).await?;
+    info!(channel = %bus.channel(), "Sharing prices over Redis");
+    Ok(Some(bus))
+}
+
+/// Serves the gRPC API next to the REST API, or never returns without a port.
+#[cfg(feature = "grpc")]
+async fn serve_grpc(state: AppState, port: Option<u16>) -> TrackerResult<()> {
+    match port {
+        Some(port) => crate::api::grpc::serve(state, port).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Without the `grpc` feature there's nothing to serve (the port is rejected
+/// at startup).
+#[cfg(not(feature = "grpc"))]
+async fn serve_grpc(_state: AppState, _port: Option<u16>) -> TrackerResult<()> {
+    std::future::pending().await
+}
+
+/// Handle config subcommands.
+fn run_config_command(action: ConfigAction) -> TrackerResult<()> {
+    let ConfigAction::Validate { file } = action;
+    let file = file
+        .or_else(|| std::env::var_os("CONFIG_FILE").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("indexer.toml"));
+    let config = Config::from_file(&file)?;
+
+    println!("{} {} is valid", "✅".green(), file.display());
+    println!("    {:<12}{}", "Profile", config.profile());
+    match config.chain() {
+        Some(chain) => println!(
+            "    {:<12}{} (chain ID {})",
+            "Chain",
+            chain,
+            config.chain_id()
+        ),
+        None => println!("    {:<12}{}", "Chain ID", config.chain_id()),
+    }
+    // RPC URLs usually embed an API key, so only the host is shown
+    let host = reqwest::Url::parse(config.rpc_url())
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    println!("    {:<12}{}", "RPC", host);
+    println!("    {:<12}{}", "Database", config.database_url());
+    println!("    {:<12}{}", "Indexed", config.pool_address());
+    for pool in config.pools() {
+        println!("    {:<12}{} {}", "Pool", pool.name, pool.address);
+    }
+    Ok(())
+}
+
+/// Warn if the pool's factory belongs to another protocol than `POOL_PROTOCOL`.
+///
+/// Forks share the pair ABI, so indexing works either way; only swap quotes
+/// would use the wrong fee.
+async fn warn_on_protocol_mismatch(
+    provider: &crate::rpc::Provider,
+    config: &Config,
+    pool_address: PoolAddress,
+) {
+    match protocol::detect(provider, config.chain_id(), pool_address.get()).await {
+        Ok(Some(detected)) if detected != config.pool_protocol() => warn!(
+            "Pool {} was deployed by the {} factory but POOL_PROTOCOL is {}",
+            pool_address,
+            detected,
+            config.pool_protocol()
+        ),
+        Ok(_) => {}
+        Err(e) => debug!("Could not detect the pool's protocol: {}", e),
+    }
+}
+
+/// Look up the pair of two tokens in each protocol's factory.
+async fn run_find_pair_command(
+    token_a: Address,
+    token_b: Address,
+    protocol: Option<DexProtocol>,
+) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let provider = create_provider(config.rpc_url()).await?;
+
+    let protocols = protocol.map_or_else(|| DexProtocol::ALL.to_vec(), |p| vec![p]);
+    println!(
+        "{} Pairs of {} and {} on chain {}",
+        "🔎".cyan(),
+        token_a,
+        token_b,
+        config.chain_id()
+    );
+    for protocol in protocols {
+        let pair = protocol
+            .find_pair(&provider, config.chain_id(), token_a, token_b)
+            .await?;
+        let found = pair.map_or_else(|| "none".dimmed().to_string(), |a| a.to_string());
+        println!(
+            "    {:<14}{}  (fee {} bps)",
+            protocol.as_str(),
+            found,
+            protocol.fee_bps()
+        );
+    }
+    Ok(())
+}
+
+/// Print the health report of a `--daemon` process.
+///
+/// # Errors
+///
+/// Returns an error if no daemon answers or it reports `stale`, so service
+/// managers can use the exit code as a liveness check.
+async fn run_health_command(command: DaemonCommand) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let report = daemon::probe(config.run_dir(), command.name()).await?;
+
+    if output_format() == OutputFormat::Json {
+        emit(&OutputRecord::Health(&report));
+    } else {
+        let status = if report.is_healthy() {
+            "ok".green().bold()
+        } else {
+            "stale".red().bold()
+        };
+        say!(
+            "{} {} (pid {}): {}",
+            "🩺".cyan(),
+            report.name.bold(),
+            report.pid,
+            status
+        );
+        say!("    {:<16}{}s", "Uptime", report.uptime_secs);
+        if let (Some(block), Some(age)) = (report.last_block, report.last_progress_secs) {
+            say!("    {:<16}{} ({}s ago)", "Last block", block, age);
+        }
+        if report.duplicates_dropped > 0 {
+            say!("    {:<16}{}", "Duplicates", report.duplicates_dropped);
+        }
+    }
+
+    if report.is_healthy() {
+        Ok(())
+    } else {
+        Err(TrackerError::state(
+            format!("{} has made no progress recently", report.name),
+            None,
+        ))
+    }
+}
+
+/// Watch passes (polling intervals) without progress before a daemon's
+/// health socket reports `stale`.
+const DAEMON_STALE_PASSES: u64 = 10;
+
+/// Blocks checked by `verify` when `--from-block` is not given.
+const DEFAULT_VERIFY_BLOCKS: u64 = 1000;
+
+/// Execute the verify command.
+///
+/// Compares the blocks in which the pair emitted Sync events with the blocks
+/// in the database and reports gaps. With `fix`, backfills them and checks
+/// again.
+///
+/// # Errors
+///
+/// Returns an error if gaps remain after the run, so scripts can alert on it.
+async fn run_verify_command(
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+    fix: bool,
+) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let provider = create_provider(config.rpc_url()).await?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+
+    let pool = repository
+        .get_pool_by_name("WETH/USDT")
+        .await?
+        .ok_or_else(|| TrackerError::state("WETH/USDT pool not found in database", None))?;
+
+    let to_block = match to_block {
+        Some(block) => block,
+        None => repository
+            .get_state(pool.id)
+            .await?
+            .and_then(|s| u64::try_from(s.last_indexed_block).ok())
+            .filter(|block| *block > 0)
+            .ok_or_else(|| TrackerError::state("Nothing indexed yet; pass --to-block", None))?,
+    };
+    let from_block = from_block.unwrap_or_else(|| to_block.saturating_sub(DEFAULT_VERIFY_BLOCKS));
+    if from_block > to_block {
+        return Err(TrackerError::state(
+            format!("--from-block {from_block} is after --to-block {to_block}"),
+            None,
+        ));
+    }
+
+    println!(
+        "{} Checking blocks {} to {}...",
+        "🔎".cyan(),
+        from_block,
+        to_block
+    );
+    let logs =
+        integrity::scan_chain(&provider, UNISWAP_V2_WETH_USDT_PAIR, from_block, to_block).await?;
+    let mut report = integrity::check(&repository, pool.id, from_block, to_block, &logs).await?;
+    print_integrity_report(&report);
+
+    if fix && !report.is_clean() {
+        let healed =
+            integrity::heal(&repository, &pool, config.chain_id(), &logs, &report.gaps).await?;
+        println!("{} Backfilled {} event(s)", "🩹".cyan(), healed);
+
+        report = integrity::check(&repository, pool.id, from_block, to_block, &logs).await?;
+        print_integrity_report(&report);
+    }
+
+    if report.is_clean() {
+        Ok(())
+    } else {
+        Err(TrackerError::state(
+            format!(
+                "{} gap(s) covering {} block(s) with events",
+                report.gaps.len(),
+                report.missing_blocks()
+            ),
+            None,
+        ))
+    }
+}
+
+/// Execute the backfill command.
+///
+/// Indexes `[from_block, to_block]` in shards of `shard_blocks` blocks, up to
+/// `workers` at a time; see [`crate::backfill`].
+async fn run_backfill_command(
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+    (workers, shard_blocks, decode_workers): (usize, u64, usize),
+    estimate_samples: Option<usize>,
+) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let provider = create_provider(config.rpc_url()).await?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+
+    let pool = repository
+        .get_pool_by_name("WETH/USDT")
+        .await?
+        .ok_or_else(|| TrackerError::state("WETH/USDT pool not found in database", None))?;
+    if !pool.enabled {
+        return Err(TrackerError::state(
+            "WETH/USDT is disabled; run `pools enable WETH/USDT` first",
+            None,
+        ));
+    }
+
+    let from_block = match from_block {
+        Some(block) => block,
+        None => repository
+            .get_state(pool.id)
+            .await?
+            .and_then(|s| u64::try_from(s.last_indexed_block).ok())
+            .filter(|block| *block > 0)
+            .map(|block| block + 1)
+            .or_else(|| pool.start_block())
+            .ok_or_else(|| {
+                TrackerError::state(
+                    "Nothing indexed yet; pass --from-block or set the pool's start block",
+                    None,
+                )
+            })?,
+    };
+    // Nothing is indexed before the pool's start block
+    let from_block = from_block.max(pool.start_block().unwrap_or_default());
+    let to_block = match to_block {
+        Some(block) => block,
+        None => get_latest_block(&provider)
+            .await?
+            .saturating_sub(config.confirmations()),
+    };
+    if from_block > to_block {
+        return Err(TrackerError::state(
+            format!("--from-block {from_block} is after --to-block {to_block}"),
+            None,
+        ));
+    }
+
+    // BATCH_SIZE, capped at the provider's getLogs range
+    let capabilities = capabilities::probe(&provider, config.rpc_url(), None).await;
+    let batch_size = capabilities.log_range(config.batch_size());
+    if batch_size < config.batch_size() {
+        info!(
+            batch_size,
+            configured = config.batch_size(),
+            "Provider limits getLogs ranges, reducing BATCH_SIZE"
+        );
+    }
+    let backfill = Backfill::new(&repository, &pool, config.chain_id(), batch_size)
+        .with_workers(workers)
+        .with_shard_blocks(shard_blocks)
+        .with_decode_workers(decode_workers)
+        .with_price_mode(config.price_mode());
+    let cache = match config.log_cache_dir() {
+        Some(dir) => Some(LogCache::open(dir, &provider).await),
+        None => None,
+    };
+
+    if let Some(samples) = estimate_samples {
+        let estimate = backfill
+            .estimate(from_block, to_block, samples, |from, to| {
+                fetch_pair_events(&provider, pool.address.get(), from, to, cache.as_ref())
+            })
+            .await;
+        if let Some(cache) = &cache {
+            save_cache_counters(cache).await;
+        }
+        let estimate = estimate?;
+        // Size the new rows like the ones already stored
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss
+        )]
+        let db_bytes = bytes_per_log(&repository.get_storage_stats(i64::MAX).await?)
+            .map(|bytes| (bytes * estimate.events as f64) as u64);
+
+        if output_format() == OutputFormat::Json {
+            emit(&OutputRecord::BackfillEstimate {
+                estimate: &estimate,
+                db_bytes,
+            });
+        } else {
+            print_backfill_estimate(&estimate, db_bytes, workers);
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{} Backfilling blocks {} to {} ({} workers)...",
+        "⏪".cyan(),
+        from_block,
+        to_block,
+        workers
+    );
+    let report = backfill
+        .run(from_block, to_block, |from, to| {
+            fetch_pair_events(&provider, pool.address.get(), from, to, cache.as_ref())
+        })
+        .await;
+    if let Some(cache) = &cache {
+        save_cache_counters(cache).await;
+    }
+    let report = report?;
+
+    println!(
+        "{} Indexed {} event(s) in {} shard(s)",
+        "✅".green(),
+        report.events,
+        report.shards
+    );
+    match report.committed_block {
+        Some(block) => println!("    resume point advanced to block {block}"),
+        None => println!("    resume point unchanged"),
+    }
+    if let Some(cache) = &cache {
+        let counters = cache.counters();
+        println!(
+            "    log cache: {} hit(s), {} miss(es)",
+            counters.hits, counters.misses
+        );
+    }
+
+    Ok(())
+}
+
+/// Adds a run's log cache hits and misses to the saved counts.
+async fn save_cache_counters(cache: &LogCache) {
+    if let Err(e) = cache.save_counters().await {
+        warn!("Failed to save log cache stats: {}", e);
+    }
+}
+
+/// Average bytes stored per fetched log, indexes included: Sync events with
+/// their price points, and Swap events. `None` before anything is stored or
+/// without `dbstat` sizes.
+#[allow(clippy::cast_precision_loss)]
+fn bytes_per_log(storage: &StorageStats) -> Option<f64> {
+    let mut bytes = 0;
+    let mut logs = 0;
+    for table in &storage.tables {
+        let is_log = match table.name.as_str() {
+            "sync_events" | "swap_events" => true,
+            "price_points" => false,
+            _ => continue,
+        };
+        bytes += table.bytes? + table.indexes.iter().filter_map(|i| i.bytes).sum::<i64>();
+        if is_log {
+            logs += table.rows;
+        }
+    }
+    (logs > 0).then(|| bytes as f64 / logs as f64)
+}
+
+/// Print a backfill estimate.
+fn print_backfill_estimate(estimate: &BackfillEstimate, db_bytes: Option<u64>, workers: usize) {
+    println!(
+        "{} Estimate for blocks {} to {}",
+        "🔎".cyan(),
+        estimate.from_block,
+        estimate.to_block
+    );
+    for sample in &estimate.samples {
+        let logs = sample
+            .logs
+            .map_or_else(|| "failed".red().to_string(), |logs| format!("{logs} logs"));
+        println!(
+            "    sampled {}-{}: {} in {} ms",
+            sample.from_block, sample.to_block, logs, sample.elapsed_ms
+        );
+    }
+    println!();
+    println!(
+        "    {:<16}~{} ({:.2} per block)",
+        "Events", estimate.events, estimate.logs_per_block
+    );
+    println!("    {:<16}{}", "RPC calls", estimate.rpc_calls);
+    println!(
+        "    {:<16}~{} with {} workers",
+        "Duration",
+        humanize_secs(estimate.duration_secs),
+        workers
+    );
+    #[allow(clippy::cast_precision_loss)]
+    let db_size = db_bytes.map_or_else(
+        || "- (nothing stored to measure yet)".dimmed().to_string(),
+        |bytes| format!("~{}", format_bytes(bytes as f64)),
+    );
+    println!("    {:<16}{}", "Database", db_size);
+
+    for warning in &estimate.warnings {
+        println!("{} {}", "⚠️".yellow(), warning);
+    }
+}
+
+/// Formats seconds as hours and minutes, e.g. `26h 40m`.
+fn humanize_secs(secs: u64) -> String {
+    match (secs / 3_600, secs % 3_600 / 60) {
+        (0, 0) => format!("{secs}s"),
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
+/// Print the result of an integrity check.
+fn print_integrity_report(report: &integrity::IntegrityReport) {
+    if report.is_clean() {
+        println!(
+            "{} All {} block(s) with Sync events are indexed",
+            "✅".green(),
+            report.chain_blocks
+        );
+        return;
+    }
+
+    println!(
+        "{} {} of {} block(s) with Sync events missing:",
+        "⚠️".yellow(),
+        report.missing_blocks(),
+        report.chain_blocks
+    );
+    for gap in &report.gaps {
+        println!(
+            "    blocks {}..={} ({} missing)",
+            gap.from_block, gap.to_block, gap.missing_blocks
+        );
+    }
+}
+
+/// Execute an API key command.
+/// Price statistics printed by the `stats` command.
+#[derive(Debug, serde::Serialize)]
+struct PoolStatsReport {
+    pool: String,
+    period: &'static str,
+    /// Start of the window (unix seconds)
+    from_timestamp: i64,
+    current_price: Option<f64>,
+    min_price: Option<f64>,
+    max_price: Option<f64>,
+    avg_price: Option<f64>,
+    /// Change from the window's first price to the current one, in percent
+    change_percent: Option<f64>,
+    change_24h_percent: f64,
+    /// Standard deviation of event-to-event returns, in percent
+    volatility_percent: Option<f64>,
+    events_in_window: u64,
+    total_events: u64,
+    last_indexed_block: u64,
+}
+
+/// Print price statistics for a pool over a window.
+async fn run_stats_command(pool: &str, window: StatsWindow) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let db = create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(db);
+
+    let now = chrono::Utc::now().timestamp();
+    let report = collect_pool_stats(&repository, pool, window, now).await?;
+
+    if output_format() == OutputFormat::Json {
+        emit(&OutputRecord::Stats(&report));
+    } else {
+        print_pool_stats(&report);
+    }
+    Ok(())
+}
+
+/// Gathers the `stats` report from the repository's stats queries.
+async fn collect_pool_stats(
+    repository: &Repository,
+    identifier: &str,
+    window: StatsWindow,
+    now: i64,
+) -> TrackerResult<PoolStatsReport> {
+    let pool = repository
+        .find_pool(identifier)
+        .await?
+        .ok_or_else(|| TrackerError::state(format!("Pool {identifier} not found"), None))?;
+    let from_timestamp = window.seconds().map_or(0, |secs| now - secs);
+
+    let period = repository
+        .get_stats_for_period(
+            pool.id,
+            from_timestamp,
+            pool.spike_filter(),
+            BlockRange::ALL,
+        )
+        .await?;
+    let current_price = repository.get_latest_price(pool.id).await?.map(|p| p.price);
+    let history = repository
+        .get_price_history(pool.id, from_timestamp, now)
+        .await?;
+    let prices: Vec<f64> = history
+        .iter()
+        .filter(|p| p.is_confirmed)
+        .map(|p| p.price)
+        .collect();
+    let state = repository.get_state(pool.id).await?;
+
+    let has_prices = period.total_events > 0;
+    let change_percent = match (period.first_price, current_price) {
+        (Some(first), Some(current)) if has_prices && first > 0.0 => {
+            Some((current - first) / first * 100.0)
+        }
+        _ => None,
+    };
+
+    Ok(PoolStatsReport {
+        pool: pool.name.unwrap_or_else(|| pool.address.to_string()),
+        period: window.label(),
+        from_timestamp,
+        current_price,
+        min_price: has_prices.then_some(period.min_price),
+        max_price: has_prices.then_some(period.max_price),
+        avg_price: has_prices.then_some(period.avg_price),
+        change_percent,
+        change_24h_percent: repository.get_24h_price_change(pool.id).await?,
+        volatility_percent: return_volatility(&prices),
+        events_in_window: u64::try_from(period.total_events).unwrap_or(0),
+        total_events: state
+            .as_ref()
+            .map_or(0, |s| u64::try_from(s.total_events_processed).unwrap_or(0)),
+        last_indexed_block: state.map_or(0, |s| u64::try_from(s.last_indexed_block).unwrap_or(0)),
+    })
+}
+
+/// Standard deviation of the returns between consecutive prices, in
+/// percent. `None` with fewer than two returns.
+fn return_volatility(prices: &[f64]) -> Option<f64> {
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .filter(|pair| pair[0] > 0.0)
+        .map(|pair| (pair[1] - pair[0]) / pair[0])
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = f64::from(u32::try_from(returns.len()).unwrap_or(u32::MAX));
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some(variance.sqrt() * 100.0)
+}
+
+/// Display the `stats` report as a table.
+fn print_pool_stats(report: &PoolStatsReport) {
+    let price = |p: Option<f64>| p.map_or_else(|| "-".dimmed().to_string(), |p| format!("${p:.2}"));
+    let change = |c: Option<f64>| match c {
+        Some(c) if c > 0.0 => format!("+{c:.2}%").green().to_string(),
+        Some(c) if c < 0.0 => format!("{c:.2}%").red().to_string(),
+        Some(c) => format!("{c:.2}%"),
+        None => "-".dimmed().to_string(),
+    };
+
+    println!(
+        "{} {} {}",
+        "📊".cyan(),
+        report.pool.bold(),
+        format!("(last {})", report.period).dimmed()
+    );
+    println!(
+        "    {:<16}{}",
+        "Current price",
+        price(report.current_price).bold()
+    );
+    println!("    {:<16}{}", "Change", change(report.change_percent));
+    println!(
+        "    {:<16}{}",
+        "24h change",
+        change(Some(report.change_24h_percent))
+    );
+    println!("    {:<16}{}", "Low", price(report.min_price));
+    println!("    {:<16}{}", "High", price(report.max_price));
+    println!("    {:<16}{}", "Average", price(report.avg_price));
+    println!(
+        "    {:<16}{}",
+        "Volatility",
+        report.volatility_percent.map_or_else(
+            || "-".dimmed().to_string(),
+            |v| format!("{v:.4}% per event")
+        )
+    );
+    println!(
+        "    {:<16}{} in window, {} total",
+        "Events", report.events_in_window, report.total_events
+    );
+    println!("    {:<16}{}", "Last block", report.last_indexed_block);
+}
+
+async fn run_keys_command(action: KeyAction) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+
+    match action {
+        KeyAction::Create {
+            name,
+            rate_limit,
+            pools,
+            chain_id,
+        } => {
+            let mut pool_ids = Vec::with_capacity(pools.len());
+            for pool in &pools {
+                let row = repository
+                    .find_pool(pool)
+                    .await?
+                    .ok_or_else(|| TrackerError::state(format!("No pool matches {pool}"), None))?;
+                pool_ids.push(row.id);
+            }
+
+            let key = generate_api_key();
+            let id = repository
+                .insert_api_key(&name, &key_prefix(&key), &hash_api_key(&key), rate_limit)
+                .await?;
+            if !pool_ids.is_empty() || chain_id.is_some() {
+                let scoped = (!pool_ids.is_empty()).then_some(pool_ids.as_slice());
+                repository.set_api_key_scope(id, scoped, chain_id).await?;
+            }
+            info!(id, name = %name, pools = pool_ids.len(), ?chain_id, "API key created");
+
+            println!("{} Created API key {} ({})", "🔑".cyan(), id, name);
+            println!();
+            println!("    {}", key.bold());
+            println!();
+            println!("{} Store it now; it cannot be shown again.", "⚠️".yellow());
+        }
+        KeyAction::List => {
+            let keys = repository.list_api_keys().await?;
+            if keys.is_empty() {
+                println!("No API keys");
+            }
+            for key in keys {
+                let limit = key
+                    .rate_limit_rpm
+                    .map_or_else(|| "default".to_string(), |rpm| format!("{rpm}/min"));
+                let status = if key.revoked_at.is_some() {
+                    "revoked".red()
+                } else {
+                    "active".green()
+                };
+                let scope = match repository.get_api_key_scope(&key).await? {
+                    PoolScope::All => "all pools".to_string(),
+                    PoolScope::Pools(ids) => format!("{} pool(s)", ids.len()),
+                };
+                let chain = key
+                    .chain_id
+                    .map_or_else(String::new, |chain_id| format!(" on chain {chain_id}"));
+                println!(
+                    "{:>4}  {}  {:<20} {:<10} {:<8} {}{}",
+                    key.id, key.key_prefix, key.name, limit, status, scope, chain
+                );
+            }
+        }
+        KeyAction::Revoke { key } => {
+            let revoked = repository.revoke_api_key(&key).await?;
+            if revoked == 0 {
+                return Err(TrackerError::state(
+                    format!("No active API key matches {key}"),
+                    None,
+                ));
+            }
+            info!(key = %key, revoked, "API key revoked");
+            println!(
+                "{} Revoked {} key(s) matching {}",
+                "✅".green(),
+                revoked,
+                key
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute a pool registry subcommand.
+async fn run_pools_command(action: PoolAction) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+
+    match action {
+        PoolAction::Add { file } => {
+            let text = std::fs::read_to_string(&file).map_err(|e| {
+                TrackerError::config(
+                    format!("Failed to read pools file {}", file.display()),
+                    Some(Box::new(e)),
+                )
+            })?;
+            let specs = pool_registry::parse_pools_csv(&text)?;
+            let provider = create_provider(config.rpc_url()).await?;
+
+            let (mut added, mut existing) = (0, 0);
+            for spec in &specs {
+                match pool_registry::register_pool(&repository, &provider, spec).await? {
+                    Registration::Added(id) => {
+                        added += 1;
+                        println!("{} Added pool {} ({})", "✅".green(), id, spec.address);
+                    }
+                    Registration::Existing(id) => {
+                        existing += 1;
+                        println!(
+                            "{} Pool {} ({}) already registered",
+                            "•".dimmed(),
+                            id,
+                            spec.address
+                        );
+                    }
+                }
+            }
+            println!(
+                "{} {} added, {} already registered",
+                "📊".cyan(),
+                added,
+                existing
+            );
+        }
+        PoolAction::List => {
+            let pools = repository.get_all_pools().await?;
+            if pools.is_empty() {
+                println!("No pools");
+            }
+            for pool in pools {
+                let status = if pool.enabled {
+                    "enabled".green()
+                } else {
+                    "disabled".red()
+                };
+                let start = pool
+                    .start_block
+                    .map_or_else(String::new, |block| format!(" (starts at {block})"));
+                println!(
+                    "{:>4}  {}  {:<16} {}/{} {:<12} {:<8} block {}{}",
+                    pool.id,
+                    pool.address,
+                    pool.name.as_deref().unwrap_or("-"),
+                    pool.token0_symbol.as_deref().unwrap_or("??"),
+                    pool.token1_symbol.as_deref().unwrap_or("??"),
+                    pool.protocol,
+                    status,
+                    pool.last_indexed_block,
+                    start
+                );
+            }
+        }
+        PoolAction::Enable { pool } => set_pool_enabled(&repository, &pool, true).await?,
+        PoolAction::Disable { pool } => set_pool_enabled(&repository, &pool, false).await?,
+        PoolAction::StartBlock { pool, block } => {
+            let record = find_pool_or_err(&repository, &pool).await?;
+            repository.set_pool_start_block(record.id, block).await?;
+            info!(pool_id = record.id, start_block = ?block, "Pool start block set");
+            match block {
+                Some(block) => println!(
+                    "{} Pool {} starts indexing at block {} when it has no checkpoint",
+                    "✅".green(),
+                    record.id,
+                    block
+                ),
+                None => println!(
+                    "{} Cleared the start block of pool {}",
+                    "✅".green(),
+                    record.id
+                ),
+            }
+        }
+        PoolAction::SpikeFilter { pool, bps } => {
+            let record = find_pool_or_err(&repository, &pool).await?;
+            repository.set_pool_spike_filter(record.id, bps).await?;
+            info!(pool_id = record.id, spike_filter_bps = ?bps, "Pool spike filter set");
+            match bps {
+                Some(bps) => println!(
+                    "{} Pool {} leaves out reverted single-block spikes of {} bps or more",
+                    "✅".green(),
+                    record.id,
+                    bps
+                ),
+                None => println!(
+                    "{} Pool {} keeps every price in candles and stats",
+                    "✅".green(),
+                    record.id
+                ),
+            }
+        }
+        PoolAction::Remove { pool, yes } => {
+            let record = find_pool_or_err(&repository, &pool).await?;
+            if !yes {
+                return Err(TrackerError::state(
+                    format!(
+                        "Removing pool {} ({}) deletes all of its indexed data; re-run with --yes",
+                        record.id, record.address
+                    ),
+                    None,
+                ));
+            }
+            repository.delete_pool(record.id).await?;
+            info!(pool_id = record.id, address = %record.address, "Pool removed");
+            println!(
+                "{} Removed pool {} ({})",
+                "✅".green(),
+                record.id,
+                record.address
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Enable or disable indexing of a pool.
+async fn set_pool_enabled(
+    repository: &Repository,
+    identifier: &str,
+    enabled: bool,
+) -> TrackerResult<()> {
+    let record = find_pool_or_err(repository, identifier).await?;
+    repository.set_pool_enabled(record.id, enabled).await?;
+    info!(pool_id = record.id, enabled, "Pool indexing toggled");
+    println!(
+        "{} Pool {} ({}) {}",
+        "✅".green(),
+        record.id,
+        record.address,
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+/// Look up a pool by ID, address or name.
+async fn find_pool_or_err(repository: &Repository, identifier: &str) -> TrackerResult<PoolRecord> {
+    repository
+        .find_pool(identifier)
+        .await?
+        .ok_or_else(|| TrackerError::state(format!("Pool {identifier} not found"), None))
+}
+
+/// Execute the bootstrap command.
+///
+/// Downloads and restores a published snapshot, runs any pending migrations
+/// on it, and continues indexing from the snapshot's last indexed block so
+/// history doesn't have to be backfilled from RPC.
+async fn run_bootstrap_command(
+    snapshot_url: &str,
+    checksum: Option<&str>,
+    force: bool,
+    watch: bool,
+    interval: u64,
+) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let target = snapshot::database_path(config.database_url())?;
+
+    println!("{} Downloading {}", "⬇️".cyan(), snapshot_url);
+    let manifest = snapshot::bootstrap_from_url(snapshot_url, &target, force, checksum).await?;
+    println!(
+        "{} Imported snapshot taken {} (schema version {}, checksum verified)",
+        "✅".green(),
+        manifest.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        manifest.schema_version
+    );
+
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+    let pool_id = repository.ensure_default_pool().await?;
+    let resume_from = repository
+        .get_state(pool_id)
+        .await?
+        .map_or(0, |s| u64::try_from(s.last_indexed_block).unwrap_or(0));
+    println!("    last indexed block: {resume_from}");
+
+    if !watch {
+        return Ok(());
+    }
+
+    run_watch_command(
+        interval,
+        (resume_from > 0).then_some(resume_from),
+        WatchMode::Http,
+        false,
+    )
+    .await
+}
+
+/// Execute the prune command.
+///
+/// Each retention period given on the command line overrides the configured
+/// one; kinds without a period are left alone.
+async fn run_prune_command(overrides: RetentionPolicy, vacuum: bool) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let configured = config.retention();
+    let policy = RetentionPolicy {
+        sync_events_days: overrides.sync_events_days.or(configured.sync_events_days),
+        price_points_days: overrides.price_points_days.or(configured.price_points_days),
+        candles_days: overrides.candles_days.or(configured.candles_days),
+    };
+
+    if !policy.is_enabled() {
+        println!(
+            "{} No retention periods configured; nothing to prune",
+            "ℹ️".cyan()
+        );
+        return Ok(());
+    }
+
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+    let report =
+        retention::prune(&repository, &policy, chrono::Utc::now().timestamp(), vacuum).await?;
+
+    println!("{} Pruned {} rows", "🧹".green(), report.rows_deleted());
+    println!("    sync events:   {}", report.sync_events_deleted);
+    println!("    price points:  {}", report.price_points_deleted);
+    println!(
+        "    candles:       {} (rolled up into {} daily candles)",
+        report.candles_deleted, report.daily_candles_written
+    );
+    if report.vacuumed {
+        println!("    database vacuumed");
+    }
+
+    Ok(())
+}
+
+/// Execute the archive command.
+///
+/// Moves the pool's confirmed Sync events in whole segments below the cutoff
+/// into `event_archive` (see [`crate::db::archive`]).
+async fn run_archive_command(
+    identifier: &str,
+    before_block: Option<u64>,
+    keep_blocks: u64,
+    vacuum: bool,
+) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+    let pool = repository
+        .find_pool(identifier)
+        .await?
+        .ok_or_else(|| TrackerError::state(format!("Pool {identifier} not found"), None))?;
+    let name = pool.name.clone().unwrap_or_else(|| identifier.to_string());
+
+    let before_block = if let Some(block) = before_block {
+        block
+    } else {
+        let last_indexed = repository
+            .get_state(pool.id)
+            .await?
+            .map_or(0, |s| u64::try_from(s.last_indexed_block).unwrap_or(0));
+        last_indexed.saturating_sub(keep_blocks)
+    };
+
+    let report = repository
+        .archive_sync_events(pool.id, before_block)
+        .await?;
+    if report.events == 0 {
+        println!(
+            "{} No complete segments of {} below block {} to archive",
+            "ℹ️".cyan(),
+            name,
+            before_block
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} Archived {} sync event(s) of {} in {} segment(s) below block {}",
+        "🗜️".green(),
+        report.events,
+        name,
+        report.segments,
+        before_block
+    );
+    println!(
+        "    {} bytes -> {} bytes ({:.1}x)",
+        report.raw_bytes,
+        report.compressed_bytes,
+        report.compression_ratio()
+    );
+    if vacuum {
+        repository.vacuum().await?;
+        println!("    database vacuumed");
+    }
+
+    Ok(())
+}
+
+/// Execute the replay command.
+///
+/// With `apply` unset the stored prices are left alone, and the recomputed
+/// ones stay in the shadow table for inspection.
+async fn run_replay_command(
+    identifier: &str,
+    from_block: Option<u64>,
+    apply: bool,
+) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+    let pool = repository
+        .find_pool(identifier)
+        .await?
+        .ok_or_else(|| TrackerError::state(format!("Pool {identifier} not found"), None))?;
+    let name = pool.name.clone().unwrap_or_else(|| identifier.to_string());
+
+    let report = replay::replay(
+        &repository,
+        &pool,
+        config.chain_id(),
+        config.price_ewma_half_life_secs(),
+        config.price_mode(),
+        from_block,
+        apply,
+    )
+    .await?;
+
+    let Some((from, to)) = report.blocks else {
+        println!(
+            "{} No stored Sync events for {}; nothing to replay",
+            "ℹ️".cyan(),
+            name
+        );
+        return Ok(());
+    };
+
+    let verb = if report.applied {
+        "Replayed"
+    } else {
+        "Shadow-replayed"
+    };
+    println!(
+        "{} {verb} {} blocks {from}-{to}: {} events, {} snapshots",
+        "🔁".green(),
+        name,
+        report.events,
+        report.snapshots
+    );
+    println!("    changed prices: {}", report.diff.changed);
+    println!("    added:          {}", report.diff.added);
+    println!("    removed:        {}", report.diff.removed);
+    println!("    max change:     {:.6}", report.diff.max_price_change);
+    if !report.applied {
+        println!("    stored prices unchanged; see the price_points_replay table");
+    }
+
+    Ok(())
+}
+
+/// Execute a database snapshot or restore command.
+async fn run_db_command(action: DbAction) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+
+    match action {
+        DbAction::Snapshot { output } => {
+            let output = output.unwrap_or_else(|| {
+                let stem = snapshot::database_path(config.database_url())
+                    .ok()
+                    .and_then(|path| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                    .unwrap_or_else(|| "database".to_string());
+                PathBuf::from(format!(
+                    "{stem}-{}.db.gz",
+                    chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+                ))
+            });
+
+            // Snapshot the schema as it is, without applying migrations
+            let repository = Repository::new(connect(config.database_url()).await?);
+            let manifest = repository.create_snapshot(&output).await?;
+
+            println!(
+                "{} Wrote snapshot {} ({} bytes uncompressed)",
+                "💾".cyan(),
+                output.display(),
+                manifest.database_bytes
+            );
+            println!("    schema version: {}", manifest.schema_version);
+            println!("    checksum:       {}", manifest.checksum);
+        }
+        DbAction::Restore {
+            snapshot: file,
+            force,
+        } => {
+            let target = snapshot::database_path(config.database_url())?;
+            let manifest = snapshot::restore_snapshot(&file, &target, force).await?;
+
+            println!(
+                "{} Restored {} to {}",
+                "✅".green(),
+                file.display(),
+                target.display()
+            );
+            println!(
+                "    taken {} at schema version {}, checksum verified",
+                manifest.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                manifest.schema_version
+            );
+        }
+        DbAction::Migrate { status } => run_data_migrations(&config, status).await?,
+        DbAction::Stats { days } => {
+            let repository = Repository::new(connect(config.database_url()).await?);
+            let report = collect_db_stats(&repository, config.database_url(), days).await?;
+
+            if output_format() == OutputFormat::Json {
+                emit(&OutputRecord::DbStats(&report));
+            } else {
+                print_db_stats(&report);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute a log cache command.
+async fn run_cache_command(action: CacheAction) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let dir = config
+        .log_cache_dir()
+        .ok_or_else(|| TrackerError::config("LOG_CACHE_DIR is not set", None))?;
+
+    match action {
+        CacheAction::Stats => {
+            let summary = log_cache::summarize(dir).await?;
+
+            if output_format() == OutputFormat::Json {
+                emit(&OutputRecord::CacheStats(&summary));
+            } else {
+                print_cache_stats(&summary);
+            }
+        }
+        CacheAction::Clear => {
+            let summary = log_cache::clear(dir).await?;
+
+            #[allow(clippy::cast_precision_loss)]
+            let bytes = format_bytes(summary.bytes as f64);
+            println!(
+                "{} Deleted {} cached range(s) ({}) from {}",
+                "🧹".green(),
+                summary.entries,
+                bytes,
+                dir.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute a price webhook subcommand.
+async fn run_webhooks_command(action: WebhookAction) -> TrackerResult<()> {
+    let config = Config::from_env()?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+
+    match action {
+        WebhookAction::DeadLetters { limit } => {
+            let letters = repository.get_price_webhook_dead_letters(0, limit).await?;
+            if output_format() == OutputFormat::Json {
+                for letter in &letters {
+                    emit(&OutputRecord::DeadLetter(letter));
+                }
+                return Ok(());
+            }
+            if letters.is_empty() {
+                println!("No dead letters");
+            }
+            for letter in letters {
+                let at = chrono::DateTime::from_timestamp(letter.created_at, 0)
+                    .map_or_else(String::new, |at| at.format("%Y-%m-%d %H:%M:%S").to_string());
+                println!(
+                    "{:>6}  {}  pool {} block {}  {} attempt(s) to {}",
+                    letter.id,
+                    at,
+                    letter.pool_id,
+                    letter.block_number,
+                    letter.attempts,
+                    letter.destination
+                );
+                println!("        {}", letter.error.dimmed());
+            }
+        }
+        WebhookAction::Replay => {
+            let secret = config
+                .price_webhook_secret()
+                .ok_or_else(|| TrackerError::config("PRICE_WEBHOOK_URLS is not set", None))?;
+            let webhooks = PriceWebhooks::new(config.price_webhook_urls().to_vec(), secret)?
+                .with_retry(
+                    config.price_webhook_max_attempts(),
+                    DEFAULT_PRICE_WEBHOOK_RETRY_DELAY,
+                );
+            let report = webhooks.replay(&repository).await?;
+            info!(?report, "Dead letters replayed");
+
+            if output_format() == OutputFormat::Json {
+                emit(&OutputRecord::WebhookReplay(&report));
+            } else {
+                println!(
+                    "{} Delivered {}, failed again {}, skipped {} (URL no longer configured)",
+                    "📮".green(),
+                    report.delivered,
+                    report.failed,
+                    report.skipped
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the `cache stats` report.
+fn print_cache_stats(summary: &CacheSummary) {
+    println!("{} Log cache {}", "📦".cyan(), summary.dir.display());
+    #[allow(clippy::cast_precision_loss)]
+    let bytes = format_bytes(summary.bytes as f64);
+    println!("    {:<16}{} ({})", "Ranges", summary.entries, bytes);
+    println!("    {:<16}{}", "Hits", summary.counters.hits);
+    println!("    {:<16}{}", "Misses", summary.counters.misses);
+    let ratio = summary.hit_ratio.map_or_else(
+        || "- (no lookups yet)".dimmed().to_string(),
+        |ratio| format!("{:.1}%", ratio * 100.0),
+    );
+    println!("    {:<16}{}", "Hit ratio", ratio);
+}
+
+/// `db stats` report: the repository's storage stats plus file sizes.
+#[derive(Debug, serde::Serialize)]
+struct DbStatsReport {
+    /// Database file (`None` for in-memory databases)
+    path: Option<PathBuf>,
+    /// Size of the database file
+    file_bytes: Option<u64>,
+    /// Size of the write-ahead log, if there is one
+    wal_bytes: Option<u64>,
+    /// Days the growth rate is estimated from
+    growth_days: u32,
+    /// Estimated bytes added per day, from the rows written in the last
+    /// `growth_days` days at each table's average row size
+    growth_bytes_per_day: Option<f64>,
+    #[serde(flatten)]
+    storage: StorageStats,
+}
+
+/// Gathers the `db stats` report.
+async fn collect_db_stats(
+    repository: &Repository,
+    database_url: &str,
+    days: u32,
+) -> TrackerResult<DbStatsReport> {
+    let days = days.max(1);
+    let since = chrono::Utc::now().timestamp() - i64::from(days) * 86_400;
+    let storage = repository.get_storage_stats(since).await?;
+
+    let path = snapshot::database_path(database_url).ok();
+    let file_size = |path: &Path| std::fs::metadata(path).ok().map(|m| m.len());
+    let file_bytes = path.as_deref().and_then(file_size);
+    let wal_bytes = path.as_ref().and_then(|path| {
+        let mut wal = path.clone().into_os_string();
+        wal.push("-wal");
+        file_size(Path::new(&wal))
+    });
+
+    Ok(DbStatsReport {
+        path,
+        file_bytes,
+        wal_bytes,
+        growth_days: days,
+        growth_bytes_per_day: estimate_growth(&storage, days),
+        storage,
+    })
+}
+
+/// Estimates bytes added per day from each table's rows written since the
+/// window start and its average row size, indexes included.
+///
+/// Returns `None` without `dbstat` sizes.
+#[allow(clippy::cast_precision_loss)]
+fn estimate_growth(storage: &StorageStats, days: u32) -> Option<f64> {
+    let mut total = 0.0;
+    for table in &storage.tables {
+        let bytes = table.bytes? + table.indexes.iter().filter_map(|i| i.bytes).sum::<i64>();
+        if let Some(recent) = table.recent_rows.filter(|_| table.rows > 0) {
+            total += recent as f64 * bytes as f64 / table.rows as f64;
+        }
+    }
+    Some(total / f64::from(days))
+}
+
+/// Formats a byte count with a binary unit.
+#[allow(clippy::cast_precision_loss)]
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value:.0} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn print_db_stats(report: &DbStatsReport) {
+    let bytes =
+        |b: Option<i64>| b.map_or_else(|| "-".dimmed().to_string(), |b| format_bytes(b as f64));
+    let file =
+        |b: Option<u64>| b.map_or_else(|| "-".dimmed().to_string(), |b| format_bytes(b as f64));
+    let block = |b: Option<i64>| b.map_or_else(|| "-".dimmed().to_string(), |b| b.to_string());
+    let storage = &report.storage;
+
+    println!(
+        "{} {}",
+        "🗄️".cyan(),
+        report
+            .path
+            .as_ref()
+            .map_or_else(
+                || "in-memory database".to_string(),
+                |p| p.display().to_string()
+            )
+            .bold()
+    );
+    println!("    {:<16}{}", "File", file(report.file_bytes));
+    println!("    {:<16}{}", "WAL", file(report.wal_bytes));
+    println!(
+        "    {:<16}{} of {}",
+        "Free pages",
+        format_bytes((storage.freelist_count * storage.page_size) as f64),
+        format_bytes((storage.page_count * storage.page_size) as f64)
+    );
+    println!(
+        "    {:<16}{}",
+        "Growth",
+        report.growth_bytes_per_day.map_or_else(
+            || "-".dimmed().to_string(),
+            |g| format!(
+                "~{}/day (last {} days)",
+                format_bytes(g),
+                report.growth_days
+            )
+        )
+    );
+
+    println!();
+    println!(
+        "    {:<28}{:>12}{:>12}{:>12}{:>12}",
+        "Table".bold(),
+        "Rows".bold(),
+        "Data".bold(),
+        "Indexes".bold(),
+        "Recent".bold()
+    );
+    for table in &storage.tables {
+        let indexes = table.indexes.iter().map(|i| i.bytes).sum::<Option<i64>>();
+        println!(
+            "    {:<28}{:>12}{:>12}{:>12}{:>12}",
+            table.name,
+            table.rows,
+            bytes(table.bytes),
+            bytes(indexes),
+            table
+                .recent_rows
+                .map_or_else(|| "-".to_string(), |r| r.to_string())
+        );
+    }
+
+    if storage.pools.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "    {:<20}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "Pool".bold(),
+        "Oldest".bold(),
+        "Newest".bold(),
+        "Checkpoint".bold(),
+        "Events".bold(),
+        "Archived".bold()
+    );
+    for pool in &storage.pools {
+        println!(
+            "    {:<20}{:>12}{:>12}{:>12}{:>12}{:>12}",
+            pool.name
+                .clone()
+                .unwrap_or_else(|| pool.pool_id.to_string()),
+            block(pool.oldest_block),
+            block(pool.newest_block),
+            block(pool.last_indexed_block),
+            pool.events,
+            pool.archived_events
+        );
+    }
+}
+
+/// Execute an RPC fixture record or serve command.
+#[cfg(feature = "testing")]
+async fn run_fixture_command(action: FixtureAction) -> TrackerResult<()> {
+    use crate::testing::{Recording, RecordingProxy, ReplayProvider};
+
+    match action {
+        FixtureAction::Record {
+            fixture,
+            head,
+            port,
+        } => {
+            let config = Config::from_env()?;
+            let proxy = RecordingProxy::start(config.rpc_url(), head, port).await?;
+            println!(
+                "{} Recording RPC responses at {} (Ctrl+C to save)",
+                "⏺".red(),
+                proxy.http_url().bold()
+            );
+
+            shutdown_signal().await;
+            let recording = proxy.recording();
+            recording.save(&fixture)?;
+            println!(
+                "{} Wrote {} recorded calls to {}",
+                "💾".cyan(),
+                recording.calls.len(),
+                fixture.display()
+            );
+        }
+        FixtureAction::Serve { fixture, port } => {
+            let recording = Recording::load(&fixture)?;
+            let calls = recording.calls.len();
+            let replay = ReplayProvider::start(recording, port).await?;
+            println!(
+                "{} Serving {} recorded calls from {} at {} (Ctrl+C to stop)",
+                "▶".green(),
+                calls,
+                fixture.display(),
+                replay.http_url().bold()
+            );
+            shutdown_signal().await;
+        }
     }
 
-    // Setup graceful shutdown handler
-    let shutdown = tokio::signal::ctrl_c();
-    tokio::pin!(shutdown);
-
-    // Main watch loop
-    loop {
-        tokio::select! {
-            // Handle shutdown signal
-            _ = &mut shutdown => {
-                info!("Shutdown signal received, cleaning up...");
-                println!();
-                println!("{}", "🛑 Shutting down gracefully...".yellow().bold());
+    Ok(())
+}
 
-                // Save final state
-                if let Err(e) = state.save(config.state_file()) {
-                    error!("Failed to save state on shutdown: {}", e);
-                    println!("{} Failed to save state: {}", "⚠️".red(), e);
-                } else {
-                    println!("{} State saved to {}", "✅".green(), config.state_file().display());
-                    println!("{} Last processed block: {}", "📍".cyan(), last_processed_block);
-                }
+/// Without the `testing` feature there's no recorder to run.
+#[cfg(not(feature = "testing"))]
+#[allow(clippy::unused_async)] // Same signature as with the feature
+async fn run_fixture_command(_action: FixtureAction) -> TrackerResult<()> {
+    Err(TrackerError::config(
+        "fixture commands need a binary built with the `testing` feature (cargo build --features testing)",
+        None,
+    ))
+}
 
-                println!("{}", "👋 Shutdown complete".green().bold());
-                info!("Shutdown complete");
-                break;
-            }
+/// Run pending data migrations, or with `status` list their progress.
+async fn run_data_migrations(config: &Config, status: bool) -> TrackerResult<()> {
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
+    let migrator =
+        DataMigrator::new(&repository).with_migrations(data_migrations::builtin(config.chain_id()));
 
-            // Process blocks
-            _ = tokio::time::sleep(Duration::from_secs(0)) => {
-                match process_new_blocks(
-                    &provider,
-                    &repository,
-                    &mut state,
-                    &mut reorg_detector,
-                    &mut last_processed_block,
-                    &mut last_price,
-                )
-                .await
-                {
-                    Ok(()) => {
-                        // Successfully processed, wait for next interval
-                        debug!("Waiting {} seconds for next check", interval);
-                    }
-                    Err(e) => {
-                        error!("Error processing blocks: {}", e);
-                        println!("{} {}", "⚠️  Error:".red().bold(), e);
-                    }
+    if status {
+        for migration in migrator.status().await? {
+            let progress = match migration.progress {
+                MigrationProgress::Pending => "pending".to_string(),
+                MigrationProgress::InProgress {
+                    checkpoint,
+                    rows_done,
+                } => format!(
+                    "interrupted at {} ({rows_done} rows)",
+                    checkpoint.as_deref().unwrap_or("start")
+                ),
+                MigrationProgress::Completed { rows_done, .. } => {
+                    format!("done ({rows_done} rows)")
                 }
-
-                // Wait before next check
-                tokio::time::sleep(Duration::from_secs(interval)).await;
-            }
+            };
+            println!(
+                "{:<22}{:<32}{}",
+                migration.name, progress, migration.description
+            );
         }
+        return Ok(());
+    }
+
+    let outcomes = migrator.run().await?;
+    if outcomes.is_empty() {
+        println!("{} Data migrations are up to date", "✅".green());
+    }
+    for outcome in outcomes {
+        println!(
+            "{} {}{}: {} rows in {} batches",
+            "✅".green(),
+            outcome.name,
+            if outcome.resumed { " (resumed)" } else { "" },
+            outcome.rows,
+            outcome.batches
+        );
     }
 
     Ok(())
 }
 
-/// Execute the API server command.
-async fn run_api_command(port: u16, rate_limit: u32) -> TrackerResult<()> {
-    info!("Starting API server");
+/// Execute the standby command.
+///
+/// Follows the primary's database every `follow_interval` seconds while
+/// serving the API. After `POST /api/v1/admin/promote`, runs a final follow
+/// pass and continues as a primary in watch mode from the last block the
+/// primary recorded.
+async fn run_standby_command(
+    primary_db: PathBuf,
+    follow_interval: u64,
+    interval: u64,
+    port: u16,
+    rate_limit: Option<u32>,
+) -> TrackerResult<()> {
+    info!(primary = %primary_db.display(), "Starting warm standby");
 
     let config = Config::from_env()?;
+    let pool =
+        create_pool_with_backup(config.database_url(), config.migration_backup_dir()).await?;
+    let repository = Repository::new(pool);
 
-    let pool = create_pool(config.database_url()).await?;
+    // Follow once before serving so the API never exposes an empty database
+    let control = Arc::new(StandbyControl::new());
+    let report = repository.follow_primary(&primary_db).await?;
+    control.record_follow(report);
+    info!(
+        block = report.last_indexed_block,
+        "Initial follow complete, serving API"
+    );
 
-    let repository = Repository::new(pool);
-    let state = AppState::new(repository);
+    let state = AppState::new(repository.clone())
+        .with_reader(repository.reader().await?)
+        .with_auth_required_paths(config.api_auth_required_paths().to_vec())
+        .with_chain_id(config.chain_id())
+        .with_rate_limits(
+            rate_limit.unwrap_or_else(|| config.api_rate_limit_rpm()),
+            config.api_rate_limit_routes().to_vec(),
+        )
+        .with_price_stale_after_secs(config.price_stale_after_secs())
+        .with_health_max_lag_blocks(config.health_max_lag_blocks())
+        .with_standby(control.clone());
+    let cors = config.api_cors().clone();
+    let server = tokio::spawn(async move {
+        if let Err(e) = server::run_server(state, port, cors).await {
+            error!("API server failed: {e}");
+        }
+    });
 
-    let cors_origins = config.api_cors_origins().to_vec();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
 
-    server::run_server(state, port, rate_limit, cors_origins)
-        .await
-        .map_err(|e| TrackerError::state(format!("API server failed: {e}"), None))?;
+    loop {
+        tokio::select! {
+            () = &mut shutdown => {
+                info!("Shutdown signal received, stopping standby");
+                server.abort();
+                return Ok(());
+            }
+            () = control.promoted() => break,
+            () = tokio::time::sleep(Duration::from_secs(follow_interval)) => {
+                match repository.follow_primary(&primary_db).await {
+                    Ok(report) => control.record_follow(report),
+                    Err(e) => warn!("Follow pass failed: {}", e),
+                }
+            }
+        }
+    }
 
-    Ok(())
+    println!("{}", "⬆️  Promoted to primary".green().bold());
+
+    // Pick up anything the primary wrote since the last pass; it may already
+    // be gone, in which case the last pass is the best we have
+    match repository.follow_primary(&primary_db).await {
+        Ok(report) => control.record_follow(report),
+        Err(e) => warn!("Final follow pass failed: {}", e),
+    }
+
+    let pool_id = repository.ensure_default_pool().await?;
+    let resume_from = repository
+        .get_state(pool_id)
+        .await?
+        .map_or(0, |s| u64::try_from(s.last_indexed_block).unwrap_or(0));
+    info!(resume_from, "Resuming indexing from primary's last block");
+
+    let result = run_watch_command(
+        interval,
+        (resume_from > 0).then_some(resume_from),
+        WatchMode::Http,
+        false,
+    )
+    .await;
+    server.abort();
+    result
 }
 
 /// Process new blocks since last check (incremental).
 ///
 /// This function only fetches events from blocks that haven't been processed yet,
 /// implementing efficient incremental indexing rather than naive polling.
-/// Batches queries into 10-block chunks for Alchemy free tier compatibility.
+/// Batches queries into chunks of `batch_blocks`, sized to the provider's
+/// `eth_getLogs` range (see [`capabilities`]).
+/// Blocks newer than the configured confirmation depth are left for a later pass.
 ///
 /// ## Reorg Detection
 ///
@@ -344,16 +3279,47 @@ async fn run_api_command(port: u16, rate_limit: u32) -> TrackerResult<()> {
 /// 1. Finds the fork point using binary search
 /// 2. Invalidates state from the fork point forward
 /// 3. Re-indexes blocks from fork point to current
+///
+/// Events, prices and indexer state are written through [`Storage`], so any
+/// backend implementing it can receive them.
+///
+/// ## Reserve Snapshots
+///
+/// With `RESERVE_SNAPSHOT_SECS` set, a pass without events whose last block is
+/// that many seconds past `last_price_time` records the reserves from
+/// `getReserves()` at that block (see [`Pipeline::record_snapshot`]), read
+/// from `RPC_ARCHIVE_URL` when the block's state is too old for `RPC_URL`
+/// (see [`StateRouter`]).
+#[allow(clippy::too_many_arguments)]
 async fn process_new_blocks(
     provider: &crate::rpc::Provider,
-    repository: &Repository,
+    storage: &dyn Storage,
+    config: &Config,
+    pool_id: i64,
     state: &mut State,
     reorg_detector: &mut ReorgDetector,
     last_processed_block: &mut u64,
     last_price: &mut Option<f64>,
+    last_price_time: &mut Option<u64>,
+    price_ewma: &mut Option<PriceEwma>,
+    dedup: &LogDeduplicator,
+    journal: Option<&EventJournal>,
+    state_reads: &StateRouter,
+    batch_blocks: u64,
 ) -> TrackerResult<()> {
-    // Get current latest block
-    let current_latest = get_latest_block(provider).await?;
+    // Get current latest block, staying `confirmations` blocks behind the head
+    let chain_head = get_latest_block(provider).await?;
+    let current_latest = chain_head.saturating_sub(config.confirmations());
+    let chain_id = config.chain_id();
+
+    // Read the pool every pass, so changed settings apply to the next one
+    let pool = storage
+        .find_pool(&pool_id.to_string())
+        .await?
+        .ok_or_else(|| {
+            TrackerError::state(format!("Pool {pool_id} not found in database"), None)
+        })?;
+    let pair = pool.address.get();
 
     // STEP 1: Check for reorgs before processing new blocks
     if *last_processed_block > 0 && reorg_detector.last_block().is_some() {
@@ -364,27 +3330,66 @@ async fn process_new_blocks(
             .await?
         {
             warn!("⚠️  CHAIN REORGANIZATION DETECTED!");
-            println!();
-            println!("{}", "⚠️  CHAIN REORGANIZATION DETECTED!".red().bold());
-            println!("{} Fork point: block {}", "🔀".yellow(), fork_point);
-            println!(
+            emit(&OutputRecord::Reorg {
+                fork_point,
+                depth: *last_processed_block - fork_point,
+            });
+            say!();
+            say!("{}", "⚠️  CHAIN REORGANIZATION DETECTED!".red().bold());
+            say!("{} Fork point: block {}", "🔀".yellow(), fork_point);
+            say!(
                 "{} Reorg depth: {} blocks",
                 "📏".yellow(),
                 *last_processed_block - fork_point
             );
+            // Indexed blocks are at least the confirmation depth old, so
+            // this reorg reached past it
+            warn!(
+                "Reorg from block {} reached past CONFIRMATION_DEPTH ({}); consider raising it",
+                fork_point + 1,
+                config.confirmations()
+            );
 
-            // Increment reorg counter in state
+            // Unconfirm stored rows past the fork first, so a failure here
+            // leaves the reorg to be detected again on the next pass
+            storage
+                .invalidate_from_block(pool.id, fork_point + 1)
+                .await?;
+            storage
+                .record_reorg(&ReorgRecord {
+                    pool_id: pool.id,
+                    detected_at: chrono::Utc::now().timestamp(),
+                    fork_point,
+                    depth: *last_processed_block - fork_point,
+                })
+                .await?;
+
+            // Count the reorg and rewind the checkpoint to the fork point
+            let checkpoint = storage.load(pool.id).await?.unwrap_or_default();
+            storage
+                .save(
+                    pool.id,
+                    &Checkpoint {
+                        reorg_count: checkpoint.reorg_count + 1,
+                        ..checkpoint.at(fork_point, None)
+                    },
+                )
+                .await?;
             state.increment_reorg_count();
 
             // Invalidate state from fork point
             state.invalidate_from(fork_point);
             *last_processed_block = fork_point;
+            if let Some(ewma) = price_ewma.as_mut() {
+                ewma.rewind(fork_point);
+            }
 
-            // Clear block hash from detector (will be repopulated during re-index)
-            *reorg_detector = ReorgDetector::new();
+            // Forget the reorged blocks; the ones before the fork still
+            // guard against a deeper reorg while re-indexing
+            reorg_detector.rewind(fork_point);
 
-            println!("{} Re-indexing from block {}...", "🔄".cyan(), fork_point);
-            println!();
+            say!("{} Re-indexing from block {}...", "🔄".cyan(), fork_point);
+            say!();
 
             info!("Reorg handled: rolling back to block {}", fork_point);
         }
@@ -404,123 +3409,41 @@ async fn process_new_blocks(
 
     debug!("Processing new blocks: {} to {}", from_block, to_block);
 
-    // Batch size: 10 blocks (Alchemy free tier limit)
-    const BATCH_SIZE: u64 = 10;
-    let mut current_block = from_block;
-    let mut total_events = 0;
-
-    // Process blocks in batches
-    while current_block <= to_block {
-        let batch_end = std::cmp::min(current_block + BATCH_SIZE - 1, to_block);
-        debug!("Fetching batch: blocks {} to {}", current_block, batch_end);
-
-        // Fetch events from this batch
-        let logs = fetch_sync_events(provider, current_block, batch_end).await?;
-
-        if !logs.is_empty() {
-            total_events += logs.len();
-            debug!("Found {} events in batch", logs.len());
-
-            // Get the pool_id from database (we know it's the default WETH/USDT pool)
-            let pool = repository
-                .get_pool_by_name("WETH/USDT")
-                .await?
-                .ok_or_else(|| {
-                    TrackerError::state("WETH/USDT pool not found in database".to_string(), None)
-                })?;
-
-            // Process each event
-            for log in logs {
-                let (sync_event, block_number) = decode_sync_event(&log)?;
-
-                // Get block timestamp
-                let block_timestamp = log.block_timestamp.unwrap_or(0);
-
-                // Get transaction hash
-                let tx_hash = log.transaction_hash.unwrap_or_default();
-
-                // Extract log index
-                let log_index = log.log_index.unwrap_or(0) as u32;
-
-                // Get block hash
-                let block_hash = log.block_hash.unwrap_or_default();
-
-                // Update state
-                state.update_from_sync_event(&sync_event, block_number)?;
-
-                // Calculate price with dynamic decimals
-                let (weth_reserve, usdt_reserve) = state.get_reserves();
-                let price = calculate_price(
-                    weth_reserve,
-                    usdt_reserve,
-                    pool.token0_decimals as u8,
-                    pool.token1_decimals as u8,
-                )?;
-
-                // Convert reserves to human-readable format
-                let weth_human = weth_reserve.to::<u128>() as f64 / 1e18;
-                let usdt_human = usdt_reserve.to::<u128>() as f64 / 1e6;
-
-                // Save sync event to database
-                repository
-                    .insert_sync_event(
-                        pool.id,
-                        block_number,
-                        block_hash,
-                        block_timestamp,
-                        tx_hash,
-                        log_index,
-                        alloy::primitives::U256::from(sync_event.reserve0),
-                        alloy::primitives::U256::from(sync_event.reserve1),
-                        true, // Mark as confirmed since we're past confirmation depth
-                    )
-                    .await?;
-
-                // Save price point to database
-                repository
-                    .insert_price_point(
-                        pool.id,
-                        block_number,
-                        block_timestamp,
-                        tx_hash,
-                        price,
-                        alloy::primitives::U256::from(sync_event.reserve0),
-                        alloy::primitives::U256::from(sync_event.reserve1),
-                        weth_human,
-                        usdt_human,
-                        true, // Mark as confirmed
-                    )
-                    .await?;
-
-                // Update indexer state
-                let current_total = repository
-                    .get_state(pool.id)
-                    .await?
-                    .map(|s| s.total_events_processed)
-                    .unwrap_or(0) as u64;
-                repository
-                    .update_state(pool.id, block_number, block_hash, 0, current_total + 1)
-                    .await?;
-
-                // Calculate price change
-                let price_change = last_price.map(|last| ((price - last) / last) * 100.0);
+    // Batches of `batch_blocks`, as many as the provider accepts
+    let batches = std::iter::successors(Some(from_block), |start| {
+        start
+            .checked_add(batch_blocks)
+            .filter(|next| *next <= to_block)
+    })
+    .map(|start| (start, std::cmp::min(start + batch_blocks - 1, to_block)));
 
-                // Display update
+    // Fetch, price and write concurrently; see `pipeline`
+    let mut pipeline = Pipeline::new(storage, &pool, chain_id)?
+        .with_dedup(dedup)
+        .with_price_mode(config.price_mode());
+    if let Some(journal) = journal {
+        pipeline = pipeline.with_journal(journal);
+    }
+    let total_events = pipeline
+        .run(
+            batches,
+            // Blocks near the head aren't finalized, so never cached
+            |from, to| fetch_pair_events(provider, pair, from, to, None),
+            state,
+            price_ewma,
+            |update| {
+                let price_change = last_price.map(|last| ((update.price - last) / last) * 100.0);
                 print_price_update(
-                    block_number,
-                    price,
-                    weth_reserve,
-                    usdt_reserve,
+                    update.block_number,
+                    update.price,
+                    update.reserve0,
+                    update.reserve1,
                     price_change,
                 );
-
-                // Update last price
-                *last_price = Some(price);
-            }
-        }
-
-        current_block = batch_end + 1;
-    }
+                *last_price = Some(update.price);
+            },
+        )
+        .await?;
 
     if total_events > 0 {
         info!(
@@ -531,9 +3454,7 @@ async fn process_new_blocks(
         debug!("No Sync events in blocks {} to {}", from_block, to_block);
     }
 
-    // STEP 3: Update last processed block and store block hash for reorg detection
-    *last_processed_block = to_block;
-
+    // STEP 3: Store the last block's hash for reorg detection, then move past it
     // Fetch the block to get its hash (for reorg detection on next iteration)
     let block = provider
         .get_block_by_number(
@@ -557,19 +3478,159 @@ async fn process_new_blocks(
             )
         })?;
 
-    // Store block hash in state and reorg detector
+    // Store block hash in the checkpoint, state and reorg detector
+    let checkpoint = storage.load(pool.id).await?.unwrap_or_default();
+    storage
+        .save(pool.id, &checkpoint.at(to_block, Some(block.header.hash)))
+        .await?;
+    // Only advance in memory once the checkpoint is stored, so a failed pass
+    // retries the same range
+    *last_processed_block = to_block;
     state.set_block_hash(block.header.hash);
     let block_record = BlockRecord::from_block(&block);
     reorg_detector.add_block(block_record);
+    reorg_detector
+        .prune_finalized(provider, config.finalized_margin_blocks())
+        .await;
+
+    // Committed segments are no longer needed; a leftover one is only replayed
+    if let Some(journal) = journal {
+        if let Err(e) = journal.truncate(pool.id, to_block).await {
+            warn!(error = %e, "Failed to truncate the event journal");
+        }
+    }
 
     debug!(
         "Stored block {} hash {} for reorg detection",
         to_block, block.header.hash
     );
 
+    // STEP 4: Read the reserves directly if the pool has been quiet too long
+    let block_time = block.header.timestamp;
+    if total_events > 0 {
+        *last_price_time = Some(block_time);
+    } else if let Some(window) = config.reserve_snapshot_secs() {
+        let quiet = last_price_time.map_or(true, |t| block_time.saturating_sub(t) >= window);
+        if quiet {
+            let snapshot = async {
+                let provider = state_reads.route(to_block, chain_head)?;
+                let reserves =
+                    fetch_reserves_at(provider, pipeline.pool_address(), to_block).await?;
+                pipeline
+                    .record_snapshot(
+                        to_block,
+                        block.header.hash,
+                        block_time,
+                        reserves,
+                        price_ewma,
+                    )
+                    .await
+            };
+            match snapshot.await {
+                Ok(update) => {
+                    info!(
+                        block = to_block,
+                        price = update.price,
+                        "Recorded reserve snapshot"
+                    );
+                    let price_change =
+                        last_price.map(|last| ((update.price - last) / last) * 100.0);
+                    print_price_update(
+                        update.block_number,
+                        update.price,
+                        update.reserve0,
+                        update.reserve1,
+                        price_change,
+                    );
+                    *last_price = Some(update.price);
+                    *last_price_time = Some(block_time);
+                }
+                // Indexing went fine; try again on the next pass
+                Err(e) => warn!(block = to_block, error = %e, "Reserve snapshot failed"),
+            }
+        }
+    }
+
     Ok(())
 }
 
+/// Commits the logs journaled for `pool` past `after_block`, returning the
+/// last block they cover.
+///
+/// The journaled ranges were fetched by an earlier run that may have stopped
+/// before writing them; rows keep their deterministic IDs, so ranges that were
+/// in fact written are rewritten unchanged. The checkpoint is then moved to
+/// the end of the replayed ranges.
+#[allow(clippy::too_many_arguments)]
+async fn replay_journal(
+    journal: &EventJournal,
+    storage: &dyn Storage,
+    config: &Config,
+    pool: &PoolRecord,
+    after_block: u64,
+    state: &mut State,
+    price_ewma: &mut Option<PriceEwma>,
+    last_price: &mut Option<f64>,
+) -> TrackerResult<u64> {
+    let entries = journal.replay(pool.id, after_block).await?;
+    let Some(last_block) = entries.last().map(|entry| entry.to_block) else {
+        return Ok(after_block);
+    };
+
+    let batches: Vec<(u64, u64)> = entries
+        .iter()
+        .map(|entry| (entry.from_block, entry.to_block))
+        .collect();
+    // The pipeline asks for the batches in order, once each
+    let mut logs = entries.into_iter().map(|entry| entry.logs);
+    let replayed = Pipeline::new(storage, pool, config.chain_id())?
+        .with_price_mode(config.price_mode())
+        .run(
+            batches,
+            |_, _| std::future::ready(Ok(logs.next().unwrap_or_default())),
+            state,
+            price_ewma,
+            |update| *last_price = Some(update.price),
+        )
+        .await?;
+
+    let checkpoint = storage.load(pool.id).await?.unwrap_or_default();
+    if checkpoint.block < last_block {
+        storage
+            .save(pool.id, &checkpoint.at(last_block, None))
+            .await?;
+    }
+    journal.truncate(pool.id, last_block).await?;
+
+    info!(
+        pool_id = pool.id,
+        events = replayed,
+        "Replayed event journal through block {}",
+        last_block
+    );
+    Ok(last_block)
+}
+
+/// Creates the EWMA for a pool, continuing from its last stored smoothed
+/// price if there is one.
+async fn resume_price_ewma(
+    repository: &Repository,
+    pool_id: i64,
+    half_life_secs: u64,
+) -> TrackerResult<PriceEwma> {
+    let latest = repository.get_latest_price(pool_id).await?;
+    Ok(latest
+        .and_then(|p| {
+            Some(PriceEwma::resume(
+                half_life_secs,
+                u64::try_from(p.block_number).ok()?,
+                p.block_timestamp,
+                p.price_ewma?,
+            ))
+        })
+        .unwrap_or_else(|| PriceEwma::new(half_life_secs)))
+}
+
 /// Fetch Sync events from the Uniswap V2 WETH/USDT pair.
 async fn fetch_sync_events(
     provider: &crate::rpc::Provider,
@@ -578,8 +3639,7 @@ async fn fetch_sync_events(
 ) -> TrackerResult<Vec<Log>> {
     let filter = create_sync_filter_for_pair(UNISWAP_V2_WETH_USDT_PAIR, from_block, to_block);
 
-    let logs = provider
-        .get_logs(&filter)
+    let logs = get_logs_split(provider, &filter, from_block, to_block)
         .await
         .map_err(|e| TrackerError::rpc(format!("Failed to fetch Sync events: {e}"), None))?;
 
@@ -588,22 +3648,31 @@ async fn fetch_sync_events(
     Ok(logs)
 }
 
-/// Decode a log into a Sync event.
-fn decode_sync_event(log: &Log) -> TrackerResult<(Sync, u64)> {
-    let block_number = log
-        .block_number
-        .ok_or_else(|| TrackerError::decoding("Log missing block number", None))?;
+/// Fetch Sync and Swap events from a V2 pair.
+///
+/// With a `cache`, finalized ranges are read from and stored in it.
+async fn fetch_pair_events(
+    provider: &crate::rpc::Provider,
+    pair: Address,
+    from_block: u64,
+    to_block: u64,
+    cache: Option<&LogCache>,
+) -> TrackerResult<Vec<Log>> {
+    let filter = create_pair_events_filter(pair, from_block, to_block);
 
-    // Convert RPC Log to Primitive Log for decoding
-    let primitive_log = PrimitiveLog {
-        address: log.address(),
-        data: log.data().clone(),
-    };
+    let logs = match cache {
+        Some(cache) => {
+            cache
+                .get_logs(provider, &filter, from_block, to_block)
+                .await
+        }
+        None => get_logs_split(provider, &filter, from_block, to_block).await,
+    }
+    .map_err(|e| TrackerError::rpc(format!("Failed to fetch pair events: {e}"), None))?;
 
-    let sync_event = Sync::decode_log(&primitive_log, true)
-        .map_err(|e| TrackerError::decoding(format!("Failed to decode Sync event: {e}"), None))?;
+    debug!("Fetched {} logs from blockchain", logs.len());
 
-    Ok((sync_event.data, block_number))
+    Ok(logs)
 }
 
 /// Display a price update with colored formatting.
@@ -614,6 +3683,18 @@ fn print_price_update(
     usdt_reserve: U256,
     price_change: Option<f64>,
 ) {
+    if output_format() == OutputFormat::Json {
+        emit(&OutputRecord::Price {
+            block_number,
+            price,
+            reserve0: weth_reserve.to_string(),
+            reserve1: usdt_reserve.to_string(),
+            change_percent: price_change,
+            timestamp: chrono::Utc::now().timestamp(),
+        });
+        return;
+    }
+
     // Timestamp
     let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
 
@@ -670,6 +3751,7 @@ fn format_reserve(reserve: U256, decimals: u32) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::models::{IndexStats, TableStats};
 
     #[test]
     fn test_format_reserve() {
@@ -703,6 +3785,7 @@ mod tests {
 
         if let Ok(Cli {
             command: Commands::Price { blocks },
+            ..
         }) = cli
         {
             assert_eq!(blocks, 200);
@@ -717,9 +3800,577 @@ mod tests {
 
         if let Ok(Cli {
             command: Commands::Watch { interval, .. },
+            ..
         }) = cli
         {
             assert_eq!(interval, 30);
         }
     }
+
+    #[test]
+    fn test_migrate_dry_run_is_global() {
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "api", "--migrate-dry-run"]).unwrap();
+        assert!(cli.migrate_dry_run);
+
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "api"]).unwrap();
+        assert!(!cli.migrate_dry_run);
+    }
+
+    #[test]
+    fn test_print_config_is_global() {
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "api", "--print-config"]).unwrap();
+        assert!(cli.print_config);
+    }
+
+    #[test]
+    fn test_db_restore_command() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "db",
+            "restore",
+            "indexer.db.gz",
+            "--force",
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                action: DbAction::Restore { force: true, .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_db_migrate_command() {
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "db", "migrate", "--status"]).unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                action: DbAction::Migrate { status: true }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_db_stats_command() {
+        let cli =
+            Cli::try_parse_from(["eth-uniswap-alloy", "db", "stats", "--days", "30"]).unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                action: DbAction::Stats { days: 30 }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cache_commands() {
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "cache", "stats"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Cache {
+                action: CacheAction::Stats
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "cache", "clear"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Cache {
+                action: CacheAction::Clear
+            }
+        ));
+    }
+
+    #[test]
+    fn test_webhooks_commands() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "webhooks",
+            "dead-letters",
+            "--limit",
+            "5",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Webhooks {
+                action: WebhookAction::DeadLetters { limit: 5 }
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "webhooks", "replay"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Webhooks {
+                action: WebhookAction::Replay
+            }
+        ));
+    }
+
+    #[test]
+    fn test_keys_create_scope() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "keys",
+            "create",
+            "tenant-a",
+            "--pools",
+            "WETH-USDT,7",
+            "--chain-id",
+            "1",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Keys {
+                action:
+                    KeyAction::Create {
+                        pools, chain_id, ..
+                    },
+            } => {
+                assert_eq!(pools, ["WETH-USDT", "7"]);
+                assert_eq!(chain_id, Some(1));
+            }
+            _ => panic!("expected keys create"),
+        }
+
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "keys", "create", "ops"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Keys {
+                action: KeyAction::Create { pools, chain_id: None, .. }
+            } if pools.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_estimate_growth() {
+        let table = |name: &str, rows, bytes, recent_rows| TableStats {
+            name: name.to_string(),
+            rows,
+            bytes,
+            recent_rows,
+            indexes: vec![IndexStats {
+                name: format!("idx_{name}"),
+                bytes: bytes.map(|b| b / 2),
+            }],
+        };
+        let mut storage = StorageStats {
+            tables: vec![
+                // 150 bytes per row, 70 rows in 7 days
+                table("sync_events", 100, Some(10_000), Some(70)),
+                table("pools", 2, Some(4_096), None),
+            ],
+            ..StorageStats::default()
+        };
+        assert_eq!(estimate_growth(&storage, 7), Some(1_500.0));
+
+        storage.tables[0].bytes = None;
+        assert_eq!(estimate_growth(&storage, 7), None);
+        assert_eq!(format_bytes(1_536.0), "1.5 KiB");
+        assert_eq!(format_bytes(512.0), "512 B");
+    }
+
+    #[test]
+    fn test_fixture_record_command() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "fixture",
+            "record",
+            "price.json",
+            "--head",
+            "19000000",
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Fixture {
+                action: FixtureAction::Record {
+                    head: Some(19_000_000),
+                    port: 8546,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_bootstrap_command() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "bootstrap",
+            "--snapshot-url",
+            "https://example.com/indexer.db.gz",
+            "--no-watch",
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Bootstrap {
+                checksum: None,
+                force: false,
+                no_watch: true,
+                interval: 12,
+                ..
+            }
+        ));
+
+        // The URL is required
+        assert!(Cli::try_parse_from(["eth-uniswap-alloy", "bootstrap"]).is_err());
+    }
+
+    #[test]
+    fn test_watch_mode() {
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "watch"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Watch {
+                mode: WatchMode::Http,
+                ..
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "watch", "--mode", "hybrid"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Watch {
+                mode: WatchMode::Hybrid,
+                ..
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["eth-uniswap-alloy", "watch", "--mode", "grpc"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_next_pass_falls_back_to_polling() {
+        let (tx, rx) = mpsc::channel(4);
+        let mut new_blocks = Some(rx);
+
+        tx.send(100).await.unwrap();
+        tx.send(101).await.unwrap();
+        wait_for_next_pass(&mut new_blocks, WatchMode::Hybrid, 60)
+            .await
+            .unwrap();
+        assert!(new_blocks.as_mut().unwrap().try_recv().is_err());
+
+        // A closed subscription switches hybrid mode to polling ...
+        drop(tx);
+        wait_for_next_pass(&mut new_blocks, WatchMode::Hybrid, 60)
+            .await
+            .unwrap();
+        assert!(new_blocks.is_none());
+
+        // ... and is fatal in ws mode
+        let (tx, rx) = mpsc::channel(1);
+        drop(tx);
+        assert!(wait_for_next_pass(&mut Some(rx), WatchMode::Ws, 60)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_backfill_command() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "backfill",
+            "--from-block",
+            "10000000",
+            "--workers",
+            "8",
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Backfill {
+                from_block: Some(10_000_000),
+                to_block: None,
+                workers: 8,
+                shard_blocks: DEFAULT_SHARD_BLOCKS,
+                decode_workers: DEFAULT_DECODE_WORKERS,
+                estimate: false,
+                samples: DEFAULT_ESTIMATE_SAMPLES,
+            }
+        ));
+
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "backfill",
+            "--from-block",
+            "10000000",
+            "--estimate",
+            "--samples",
+            "10",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Backfill {
+                estimate: true,
+                samples: 10,
+                ..
+            }
+        ));
+        // --samples only applies to estimates
+        assert!(Cli::try_parse_from(["eth-uniswap-alloy", "backfill", "--samples", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_bytes_per_log() {
+        let table = |name: &str, rows, bytes| TableStats {
+            name: name.to_string(),
+            rows,
+            bytes: Some(bytes),
+            recent_rows: None,
+            indexes: vec![],
+        };
+        let mut storage = StorageStats {
+            tables: vec![
+                table("sync_events", 100, 20_000),
+                table("swap_events", 100, 30_000),
+                table("price_points", 100, 10_000),
+                table("pools", 1, 4_096),
+            ],
+            ..StorageStats::default()
+        };
+        assert_eq!(bytes_per_log(&storage), Some(300.0));
+        assert_eq!(humanize_secs(96_000), "26h 40m");
+        assert_eq!(humanize_secs(45), "45s");
+
+        storage.tables[0].rows = 0;
+        storage.tables[1].rows = 0;
+        assert_eq!(bytes_per_log(&storage), None);
+    }
+
+    #[test]
+    fn test_stats_command() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "stats",
+            "--period",
+            "7d",
+            "--output",
+            "json",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert!(matches!(
+            cli.command,
+            Commands::Stats {
+                period: StatsWindow::Week,
+                ..
+            }
+        ));
+        assert!(Cli::try_parse_from(["eth-uniswap-alloy", "stats", "--period", "2d"]).is_err());
+    }
+
+    #[test]
+    fn test_output_records_are_tagged_json() {
+        let line = serde_json::to_string(&OutputRecord::Reorg {
+            fork_point: 100,
+            depth: 2,
+        })
+        .unwrap();
+        assert_eq!(line, r#"{"type":"reorg","fork_point":100,"depth":2}"#);
+
+        let price = serde_json::to_value(OutputRecord::Price {
+            block_number: 1,
+            price: 2_000.5,
+            reserve0: "10".to_string(),
+            reserve1: "20".to_string(),
+            change_percent: None,
+            timestamp: 0,
+        })
+        .unwrap();
+        assert_eq!(price["type"], "price");
+        assert_eq!(price["reserve1"], "20");
+        assert!(price["change_percent"].is_null());
+    }
+
+    #[test]
+    fn test_return_volatility() {
+        assert_eq!(return_volatility(&[100.0, 101.0]), None);
+        // Constant returns have no spread
+        let steady = return_volatility(&[100.0, 110.0, 121.0]).unwrap();
+        assert!(steady.abs() < 1e-9);
+        // +10% then -10%: returns 0.1 and -0.1, sample std dev ~14.14%
+        let choppy = return_volatility(&[100.0, 110.0, 99.0]).unwrap();
+        assert!((choppy - 14.142_135_6).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_collect_pool_stats() {
+        use crate::db::{create_pool, models::PricePointRecord};
+        use alloy::primitives::B256;
+
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repository.ensure_default_pool().await.unwrap();
+        let now = 1_700_100_000;
+        let prices = [
+            (1, now - 7_200, 1_900.0),
+            (2, now - 1_800, 2_000.0),
+            (3, now - 60, 2_100.0),
+        ];
+        let records = prices
+            .iter()
+            .map(|&(block, timestamp, price)| {
+                PricePointRecord::new(
+                    pool_id,
+                    block,
+                    u64::try_from(timestamp).unwrap(),
+                    B256::from(U256::from(block)),
+                    price,
+                    U256::from(1u64),
+                    U256::from(1u64),
+                    1.0,
+                    price,
+                    true,
+                )
+            })
+            .collect();
+        repository.batch_insert_price_points(records).await.unwrap();
+
+        let report = collect_pool_stats(&repository, "WETH/USDT", StatsWindow::Hour, now)
+            .await
+            .unwrap();
+        assert_eq!(report.events_in_window, 2);
+        assert_eq!(report.min_price, Some(2_000.0));
+        assert_eq!(report.max_price, Some(2_100.0));
+        assert_eq!(report.current_price, Some(2_100.0));
+        assert!((report.change_percent.unwrap() - 5.0).abs() < 1e-9);
+        assert_eq!(report.volatility_percent, None);
+
+        let all = collect_pool_stats(&repository, "WETH/USDT", StatsWindow::All, now)
+            .await
+            .unwrap();
+        assert_eq!(all.events_in_window, 3);
+        assert!(all.volatility_percent.is_some());
+        assert!(
+            collect_pool_stats(&repository, "nope", StatsWindow::All, now)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_daemon_flags() {
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "watch", "--daemon"]).unwrap();
+        assert!(matches!(cli.command, Commands::Watch { daemon: true, .. }));
+
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "health", "api"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Health {
+                command: DaemonCommand::Api
+            }
+        ));
+    }
+
+    #[test]
+    fn test_config_validate_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("indexer.toml");
+        std::fs::write(
+            &path,
+            r#"
+chain = "mainnet"
+
+[chains.mainnet]
+chain_id = 1
+rpc_url = "https://mainnet.example/v2/secret"
+
+[[pools]]
+name = "WETH/USDT"
+address = "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"
+"#,
+        )
+        .unwrap();
+
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "config",
+            "validate",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                action: ConfigAction::Validate { file: Some(_) }
+            }
+        ));
+        let result = run_config_command(ConfigAction::Validate {
+            file: Some(path.clone()),
+        });
+        assert!(result.is_ok());
+
+        std::fs::write(&path, "batch_size = -1\n").unwrap();
+        let result = run_config_command(ConfigAction::Validate { file: Some(path) });
+        assert!(result.is_err_and(|e| e.to_string().contains("batch_size must be")));
+    }
+
+    #[test]
+    fn test_prune_command() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "prune",
+            "--candles-days",
+            "365",
+            "--no-vacuum",
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Prune {
+                sync_events_days: None,
+                candles_days: Some(365),
+                no_vacuum: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_archive_command() {
+        let cli = Cli::try_parse_from(["eth-uniswap-alloy", "archive", "--keep-blocks", "50000"])
+            .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Archive {
+                ref pool,
+                before_block: None,
+                keep_blocks: 50_000,
+                no_vacuum: false,
+            } if pool == "WETH/USDT"
+        ));
+    }
+
+    #[test]
+    fn test_replay_command() {
+        let cli = Cli::try_parse_from([
+            "eth-uniswap-alloy",
+            "replay",
+            "--from-block",
+            "19000000",
+            "--shadow",
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Replay {
+                ref pool,
+                from_block: Some(19_000_000),
+                shadow: true,
+            } if pool == "WETH/USDT"
+        ));
+    }
 }
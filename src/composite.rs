@@ -0,0 +1,332 @@
+//! Composite price index across pools.
+//!
+//! A single pool's price can be pushed around by whoever trades against it.
+//! When `COMPOSITE_INDEX_POOLS` lists several pools quoting the same pair
+//! (e.g. `WETH/USDT,WETH/USDC,WETH/DAI`), the API server runs a
+//! [`CompositeIndexer`] that checks their latest confirmed prices every
+//! [`COMPOSITE_CHECK_INTERVAL`] and, once any of them has a newer one, records
+//! a liquidity-weighted average in the `composite_prices` table:
+//!
+//! - each price is taken in its pool's quote direction, so every listed pool
+//!   must quote the same pair
+//! - a pool's weight is its quote-side liquidity, twice its reserve of the
+//!   quote token, so a thin pool moves the index only by its small share
+//! - a price more than [`MAX_CONSTITUENT_AGE_SECS`] older than the newest
+//!   one is left out, so a pool that stopped trading doesn't hold the index
+//!   at its last price
+//!
+//! The index is served by `/api/v1/composite` and
+//! `/api/v1/composite/history`.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use crate::db::models::{PoolRecord, PricePointRow};
+use crate::db::repository::Repository;
+use crate::error::{TrackerError, TrackerResult};
+use crate::pricing::QuoteDirection;
+
+/// Interval between checks for new constituent prices.
+pub const COMPOSITE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Age, relative to the newest constituent price, beyond which a price is
+/// left out of the index.
+pub const MAX_CONSTITUENT_AGE_SECS: i64 = 3_600;
+
+/// One pool's contribution to the index.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Constituent {
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Block of the pool's price
+    pub block_number: u64,
+    /// Block timestamp of the pool's price
+    #[serde(skip)]
+    pub block_timestamp: i64,
+    /// Price in the pool's quote direction
+    pub price: f64,
+    /// Quote-side liquidity, the price's weight
+    pub liquidity: f64,
+}
+
+impl Constituent {
+    /// Builds a constituent from a pool's confirmed price, oriented by the
+    /// pool's quote direction.
+    #[must_use]
+    pub fn from_price(pool_id: i64, direction: QuoteDirection, price: &PricePointRow) -> Self {
+        // The quote token is token1 unless prices are reported inverted
+        let quote_reserve = if direction.is_inverse() {
+            price.reserve0_human
+        } else {
+            price.reserve1_human
+        };
+        Self {
+            pool_id,
+            block_number: u64::try_from(price.block_number).unwrap_or(0),
+            block_timestamp: price.block_timestamp,
+            price: direction.apply(price.price),
+            liquidity: 2.0 * quote_reserve,
+        }
+    }
+}
+
+/// A value of the index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompositePrice {
+    /// Newest block among the constituents
+    pub block_number: u64,
+    /// Block timestamp of `block_number`
+    pub block_timestamp: i64,
+    /// Liquidity-weighted average price
+    pub price: f64,
+    /// Total quote-side liquidity of the constituents
+    pub liquidity: f64,
+    /// Constituents included in the price
+    pub constituents: Vec<Constituent>,
+}
+
+/// Computes the liquidity-weighted price of the given constituents.
+///
+/// Constituents without a positive price and liquidity, and those more than
+/// [`MAX_CONSTITUENT_AGE_SECS`] older than the newest, are left out. Returns
+/// `None` if none remain.
+#[must_use]
+pub fn aggregate(constituents: &[Constituent]) -> Option<CompositePrice> {
+    let usable: Vec<Constituent> = constituents
+        .iter()
+        .filter(|c| c.price > 0.0 && c.liquidity > 0.0)
+        .copied()
+        .collect();
+    let newest = usable.iter().max_by_key(|c| c.block_number)?;
+    let (block_number, block_timestamp) = (newest.block_number, newest.block_timestamp);
+
+    let included: Vec<Constituent> = usable
+        .into_iter()
+        .filter(|c| block_timestamp - c.block_timestamp <= MAX_CONSTITUENT_AGE_SECS)
+        .collect();
+    let liquidity: f64 = included.iter().map(|c| c.liquidity).sum();
+    let weighted: f64 = included.iter().map(|c| c.price * c.liquidity).sum();
+
+    Some(CompositePrice {
+        block_number,
+        block_timestamp,
+        price: weighted / liquidity,
+        liquidity,
+        constituents: included,
+    })
+}
+
+/// Records the composite index of a set of pools.
+#[derive(Debug)]
+pub struct CompositeIndexer {
+    pools: Vec<PoolRecord>,
+}
+
+impl CompositeIndexer {
+    /// Creates an indexer over the given pools.
+    #[must_use]
+    pub const fn new(pools: Vec<PoolRecord>) -> Self {
+        Self { pools }
+    }
+
+    /// Spawns a task that records a new index value whenever a constituent
+    /// has a newer price, checking every [`COMPOSITE_CHECK_INTERVAL`].
+    #[must_use]
+    pub fn spawn(self, repository: Repository) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut last_block = match repository.get_latest_composite_price().await {
+                Ok(latest) => latest.map_or(0, |row| row.block_number),
+                Err(e) => {
+                    error!(error = %e, "Composite indexer failed to start");
+                    return;
+                }
+            };
+
+            let mut ticker = tokio::time::interval(COMPOSITE_CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                match self.update(&repository, last_block).await {
+                    Ok(Some(block)) => last_block = block,
+                    Ok(None) => {}
+                    Err(e) => warn!(error = %e, "Composite indexer could not update the index"),
+                }
+            }
+        })
+    }
+
+    /// Records the index if it has moved past `last_block`, returning the
+    /// block of the recorded value.
+    async fn update(&self, repository: &Repository, last_block: i64) -> TrackerResult<Option<i64>> {
+        let mut constituents = Vec::with_capacity(self.pools.len());
+        for pool in &self.pools {
+            if let Some(price) = repository.get_latest_price(pool.id).await? {
+                constituents.push(Constituent::from_price(
+                    pool.id,
+                    pool.quote_direction(),
+                    &price,
+                ));
+            }
+        }
+
+        let Some(composite) = aggregate(&constituents) else {
+            return Ok(None);
+        };
+        let block = i64::try_from(composite.block_number).unwrap_or(i64::MAX);
+        if block <= last_block {
+            return Ok(None);
+        }
+
+        repository.insert_composite_price(&composite).await?;
+        debug!(
+            block = composite.block_number,
+            price = composite.price,
+            pools = composite.constituents.len(),
+            "Recorded composite price"
+        );
+        Ok(Some(block))
+    }
+}
+
+/// Resolves the pools listed in `COMPOSITE_INDEX_POOLS`.
+///
+/// # Errors
+///
+/// Returns an error if a pool is not in the database.
+pub async fn resolve_pools(
+    repository: &Repository,
+    identifiers: &[String],
+) -> TrackerResult<Vec<PoolRecord>> {
+    let mut pools = Vec::with_capacity(identifiers.len());
+    for identifier in identifiers {
+        let pool = repository.find_pool(identifier).await?.ok_or_else(|| {
+            TrackerError::config(
+                format!("COMPOSITE_INDEX_POOLS lists unknown pool: {identifier}"),
+                None,
+            )
+        })?;
+        info!(
+            pool_id = pool.id,
+            name = pool.name.as_deref().unwrap_or("unknown"),
+            "Composite index constituent"
+        );
+        pools.push(pool);
+    }
+    Ok(pools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constituent(pool_id: i64, block: u64, price: f64, liquidity: f64) -> Constituent {
+        Constituent {
+            pool_id,
+            block_number: block,
+            block_timestamp: i64::try_from(block).unwrap() * 12,
+            price,
+            liquidity,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_weights_by_liquidity() {
+        let composite = aggregate(&[
+            constituent(1, 1_000, 3_000.0, 30_000_000.0),
+            constituent(2, 1_001, 3_010.0, 10_000_000.0),
+            // A thin pool pushed far off barely moves the index
+            constituent(3, 999, 6_000.0, 10_000.0),
+        ])
+        .unwrap();
+
+        assert_eq!(composite.block_number, 1_001);
+        assert_eq!(composite.block_timestamp, 12_012);
+        assert_eq!(composite.constituents.len(), 3);
+        assert!((composite.liquidity - 40_010_000.0).abs() < 1e-6);
+        assert!((composite.price - 3_003.248).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_aggregate_skips_stale_and_empty_constituents() {
+        let composite = aggregate(&[
+            constituent(1, 1_000, 3_000.0, 1_000.0),
+            // More than an hour older than the newest price
+            constituent(2, 600, 2_000.0, 1_000.0),
+            constituent(3, 1_000, 0.0, 1_000.0),
+            constituent(4, 1_000, 3_100.0, 0.0),
+        ])
+        .unwrap();
+        assert_eq!(composite.price, 3_000.0);
+        assert_eq!(composite.constituents.len(), 1);
+
+        assert!(aggregate(&[]).is_none());
+        assert!(aggregate(&[constituent(1, 1_000, 0.0, 1_000.0)]).is_none());
+    }
+
+    #[test]
+    fn test_constituent_follows_quote_direction() {
+        let row = PricePointRow {
+            event_id: None,
+            block_number: 1_000,
+            block_timestamp: 12_000,
+            tx_hash: Default::default(),
+            price: 2_000.0,
+            price_exact: None,
+            price_ewma: None,
+            source: "event".to_string(),
+            reserve0_human: 10.0,
+            reserve1_human: 20_000.0,
+        };
+
+        let direct = Constituent::from_price(1, QuoteDirection::Token1PerToken0, &row);
+        assert_eq!((direct.price, direct.liquidity), (2_000.0, 40_000.0));
+        let inverse = Constituent::from_price(1, QuoteDirection::Token0PerToken1, &row);
+        assert_eq!((inverse.price, inverse.liquidity), (0.0005, 20.0));
+    }
+
+    #[tokio::test]
+    async fn test_indexer_records_new_values_only() {
+        use crate::db::{create_pool, run_migrations};
+        use alloy::primitives::{FixedBytes, U256};
+
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        let repo = Repository::new(pool);
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pools = resolve_pools(&repo, &["WETH/USDT".to_string()])
+            .await
+            .unwrap();
+        assert!(resolve_pools(&repo, &["WETH/DAI".to_string()])
+            .await
+            .is_err());
+        let indexer = CompositeIndexer::new(pools);
+
+        assert_eq!(indexer.update(&repo, 0).await.unwrap(), None);
+        repo.insert_price_point(
+            pool_id,
+            100,
+            1_200,
+            FixedBytes::from([1; 32]),
+            3_000.0,
+            U256::from(1_u64),
+            U256::from(1_u64),
+            10.0,
+            30_000.0,
+            true,
+            "price-100",
+        )
+        .await
+        .unwrap();
+        assert_eq!(indexer.update(&repo, 0).await.unwrap(), Some(100));
+        assert_eq!(indexer.update(&repo, 100).await.unwrap(), None);
+
+        let latest = repo.get_latest_composite_price().await.unwrap().unwrap();
+        assert_eq!(latest.block_number, 100);
+        assert_eq!(latest.price, 3_000.0);
+        assert_eq!(latest.liquidity, 60_000.0);
+        assert_eq!(latest.pools, 1);
+        let constituents: Vec<Constituent> = serde_json::from_str(&latest.constituents).unwrap();
+        assert_eq!(constituents[0].pool_id, pool_id);
+    }
+}
@@ -0,0 +1,88 @@
+//! Per-chain defaults.
+//!
+//! How far behind the head a block is safe from reorgs depends on the chain:
+//! a few blocks on Ethereum, dozens on Polygon. [`ChainProfile::for_chain`]
+//! looks up the bundled defaults for a chain ID; on other chains the
+//! profile's defaults apply.
+
+/// Defaults bundled for a known chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainProfile {
+    /// Chain ID
+    pub chain_id: u64,
+    /// Chain name
+    pub name: &'static str,
+    /// `CONFIRMATION_DEPTH`: blocks to stay behind the head
+    pub confirmation_depth: u64,
+}
+
+/// Chains with bundled defaults.
+pub const CHAIN_PROFILES: &[ChainProfile] = &[
+    ChainProfile {
+        chain_id: 1,
+        name: "ethereum",
+        confirmation_depth: 12,
+    },
+    ChainProfile {
+        chain_id: 10,
+        name: "optimism",
+        confirmation_depth: 10,
+    },
+    ChainProfile {
+        chain_id: 56,
+        name: "bsc",
+        confirmation_depth: 15,
+    },
+    ChainProfile {
+        chain_id: 137,
+        name: "polygon",
+        confirmation_depth: 50,
+    },
+    ChainProfile {
+        chain_id: 8453,
+        name: "base",
+        confirmation_depth: 10,
+    },
+    ChainProfile {
+        chain_id: 42161,
+        name: "arbitrum",
+        confirmation_depth: 20,
+    },
+    ChainProfile {
+        chain_id: 11_155_111,
+        name: "sepolia",
+        confirmation_depth: 6,
+    },
+    ChainProfile {
+        chain_id: 31337,
+        name: "anvil",
+        confirmation_depth: 0,
+    },
+];
+
+impl ChainProfile {
+    /// Returns the bundled defaults for `chain_id`, if it's a known chain.
+    #[must_use]
+    pub fn for_chain(chain_id: u64) -> Option<Self> {
+        CHAIN_PROFILES
+            .iter()
+            .find(|profile| profile.chain_id == chain_id)
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_chains_have_profiles() {
+        assert_eq!(ChainProfile::for_chain(1).unwrap().confirmation_depth, 12);
+        assert_eq!(ChainProfile::for_chain(137).unwrap().name, "polygon");
+        assert_eq!(ChainProfile::for_chain(999_999), None);
+
+        let mut ids: Vec<_> = CHAIN_PROFILES.iter().map(|p| p.chain_id).collect();
+        ids.dedup();
+        assert_eq!(ids.len(), CHAIN_PROFILES.len());
+    }
+}
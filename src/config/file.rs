@@ -0,0 +1,772 @@
+//! TOML configuration files.
+//!
+//! A file is flattened into the environment variables it stands for, so
+//! [`Config::from_file`](super::Config::from_file) validates it with the same
+//! rules as the environment. Types, unknown keys and the `[chains.*]` and
+//! `[[pools]]` sections are checked here, with the line of the offending key.
+//!
+//! ```toml
+//! profile = "prod"
+//! chain = "mainnet"
+//! batch_size = 500
+//! api_cors_origins = ["https://app.example.com"]
+//! api_rate_limit_routes = { "/price" = 600, "/admin" = 30 }
+//!
+//! [chains.mainnet]
+//! chain_id = 1
+//! rpc_url = "https://eth-mainnet.g.alchemy.com/v2/KEY"
+//! confirmation_depth = 5
+//!
+//! [[pools]]
+//! name = "WETH/USDT"
+//! address = "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"
+//! protocol = "uniswap_v2"
+//! pool_type = "constant_product"
+//! quote_direction = "token1_per_token0"
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use toml_edit::{Document, Item, TableLike, Value};
+
+use crate::adapters::PoolType;
+use crate::error::{TrackerError, TrackerResult};
+use crate::pricing::QuoteDirection;
+use crate::protocol::DexProtocol;
+
+/// How the value of a top-level key is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// A string
+    Str,
+    /// A non-negative integer
+    Int,
+    /// `true` or `false`
+    Bool,
+    /// An array of strings, or a comma-separated string
+    List,
+    /// A table of path prefix to requests per minute, or a `prefix=rpm` string
+    Routes,
+}
+
+impl Kind {
+    const fn expected(self) -> &'static str {
+        match self {
+            Self::Str => "a string",
+            Self::Int => "a non-negative integer",
+            Self::Bool => "true or false",
+            Self::List => "an array of strings",
+            Self::Routes => "a table of path prefix = requests per minute",
+        }
+    }
+}
+
+/// Top-level keys: the lowercase names of the variables read by `Config`.
+const KEYS: &[(&str, Kind)] = &[
+    ("profile", Kind::Str),
+    ("rpc_url", Kind::Str),
+    ("alchemy_api_key", Kind::Str),
+    ("rpc_ws_url", Kind::Str),
+    ("rpc_archive_url", Kind::Str),
+    ("ws_stale_after_secs", Kind::Int),
+    ("confirmation_depth", Kind::Int),
+    ("confirmations", Kind::Int),
+    ("finalized_margin_blocks", Kind::Int),
+    ("anvil_fork_block", Kind::Int),
+    ("state_file", Kind::Str),
+    ("run_dir", Kind::Str),
+    ("database_url", Kind::Str),
+    ("watch_mode", Kind::Bool),
+    ("poll_interval_secs", Kind::Int),
+    ("batch_size", Kind::Int),
+    ("max_concurrent_pools", Kind::Int),
+    ("pool_address", Kind::Str),
+    ("pool_protocol", Kind::Str),
+    ("pool_type", Kind::Str),
+    ("quote_direction", Kind::Str),
+    ("chain_id", Kind::Int),
+    ("api_port", Kind::Int),
+    ("api_rate_limit_rpm", Kind::Int),
+    ("api_cors_origins", Kind::List),
+    ("api_cors_allow_credentials", Kind::Bool),
+    ("api_cors_max_age_secs", Kind::Int),
+    ("api_cors_strict", Kind::Bool),
+    ("api_rate_limit_routes", Kind::Routes),
+    ("api_auth_required_paths", Kind::List),
+    ("response_signing_key", Kind::Str),
+    ("redis_url", Kind::Str),
+    ("redis_key_prefix", Kind::Str),
+    ("price_stale_after_secs", Kind::Int),
+    ("health_max_lag_blocks", Kind::Int),
+    ("lag_alert_blocks", Kind::Int),
+    ("lag_alert_webhook_url", Kind::Str),
+    ("depeg_band_bps", Kind::Int),
+    ("depeg_min_blocks", Kind::Int),
+    ("depeg_webhook_url", Kind::Str),
+    ("price_preview", Kind::Str),
+    ("composite_index_pools", Kind::Str),
+    ("price_webhook_urls", Kind::Str),
+    ("price_webhook_secret", Kind::Str),
+    ("price_webhook_max_attempts", Kind::Int),
+    ("alert_rules_file", Kind::Str),
+    ("telegram_bot_token", Kind::Str),
+    ("discord_bot_token", Kind::Str),
+    ("smtp_url", Kind::Str),
+    ("smtp_from", Kind::Str),
+    ("migration_backup_dir", Kind::Str),
+    ("price_ewma_half_life_secs", Kind::Int),
+    ("price_mode", Kind::Str),
+    ("reserve_snapshot_secs", Kind::Int),
+    ("event_journal_dir", Kind::Str),
+    ("event_journal_segment_blocks", Kind::Int),
+    ("log_cache_dir", Kind::Str),
+    ("shutdown_drain_timeout_secs", Kind::Int),
+    ("retention_sync_events_days", Kind::Int),
+    ("retention_price_points_days", Kind::Int),
+    ("retention_candles_days", Kind::Int),
+    ("retention_interval_secs", Kind::Int),
+];
+
+/// Keys with their own handling besides [`KEYS`].
+const SECTION_KEYS: &[&str] = &["chain", "chains", "pools"];
+
+/// A chain defined in a `[chains.<name>]` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainConfig {
+    /// Section name, selected with `chain = "<name>"` or `CHAIN`
+    pub name: String,
+    /// Chain ID
+    pub chain_id: u64,
+    /// HTTP RPC URL
+    pub rpc_url: Option<String>,
+    /// WebSocket RPC URL
+    pub rpc_ws_url: Option<String>,
+    /// Blocks to stay behind the head (default: the chain's bundled depth)
+    pub confirmation_depth: Option<u64>,
+}
+
+/// A pool listed in a `[[pools]]` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Pool name, e.g. "WETH/USDT"
+    pub name: String,
+    /// Pair contract address
+    pub address: String,
+    /// Chain section the pool belongs to (any chain when unset)
+    pub chain: Option<String>,
+    /// Protocol that deployed the pool (default: Uniswap V2)
+    pub protocol: DexProtocol,
+    /// Pricing formula of the pool (default: constant product)
+    pub pool_type: PoolType,
+    /// Direction prices are reported in (default: token1 per token0)
+    pub quote_direction: QuoteDirection,
+}
+
+/// A parsed config file.
+#[derive(Debug, Default)]
+pub(super) struct ConfigFile {
+    /// Values keyed by environment variable name
+    pub(super) values: HashMap<String, String>,
+    /// Selected chain section
+    pub(super) chain: Option<String>,
+    /// All chain sections
+    pub(super) chains: Vec<ChainConfig>,
+    /// Pools of the selected chain
+    pub(super) pools: Vec<PoolConfig>,
+}
+
+impl ConfigFile {
+    /// Reads and parses `path`; `chain` (from `CHAIN`) overrides the file's
+    /// chain selection.
+    pub(super) fn load(path: &Path, chain: Option<String>) -> TrackerResult<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|e| {
+            TrackerError::config(
+                format!("Failed to read config file {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+        Self::parse(&raw, &path.display().to_string(), chain)
+    }
+
+    /// Parses a config file; `origin` names it in error messages.
+    pub(super) fn parse(raw: &str, origin: &str, chain: Option<String>) -> TrackerResult<Self> {
+        let doc = Document::parse(raw)
+            .map_err(|e| TrackerError::config(format!("Invalid TOML in {origin}:\n{e}"), None))?;
+        let at = Locator { raw, origin };
+
+        let mut file = Self::default();
+        let mut selected = chain;
+        let mut pools = Vec::new();
+        for (key, item) in doc.iter() {
+            match key {
+                "chain" => {
+                    let name = item
+                        .as_str()
+                        .ok_or_else(|| at.error(item, "chain must be a string"))?;
+                    // CHAIN from the environment wins
+                    selected.get_or_insert_with(|| name.to_string());
+                }
+                "chains" => file.chains = parse_chains(&at, item)?,
+                "pools" => pools = parse_pools(&at, item)?,
+                _ => {
+                    let kind = KEYS
+                        .iter()
+                        .find(|(name, _)| *name == key)
+                        .map(|(_, kind)| *kind)
+                        .ok_or_else(|| at.error(item, &unknown_key(key)))?;
+                    let value = to_env_value(item, kind).ok_or_else(|| {
+                        at.error(item, &format!("{key} must be {}", kind.expected()))
+                    })?;
+                    file.values.insert(key.to_ascii_uppercase(), value);
+                }
+            }
+        }
+
+        file.chain = match selected {
+            Some(name) => {
+                if !file.chains.iter().any(|c| c.name == name) {
+                    return Err(TrackerError::config(
+                        format!(
+                            "{origin}: chain '{name}' has no [chains.{name}] section (defined: {})",
+                            names(&file.chains)
+                        ),
+                        None,
+                    ));
+                }
+                Some(name)
+            }
+            None if file.chains.len() > 1 => {
+                return Err(TrackerError::config(
+                    format!(
+                        "{origin} defines several chains ({}); select one with chain = \"<name>\" or CHAIN",
+                        names(&file.chains)
+                    ),
+                    None,
+                ));
+            }
+            None => file.chains.first().map(|c| c.name.clone()),
+        };
+
+        if let Some(chain) = file
+            .chains
+            .iter()
+            .find(|c| file.chain.as_ref() == Some(&c.name))
+        {
+            let section = format!("[chains.{}]", chain.name);
+            let entries = [
+                ("CHAIN_ID", Some(chain.chain_id.to_string())),
+                ("RPC_URL", chain.rpc_url.clone()),
+                ("RPC_WS_URL", chain.rpc_ws_url.clone()),
+                (
+                    "CONFIRMATION_DEPTH",
+                    chain.confirmation_depth.map(|depth| depth.to_string()),
+                ),
+            ];
+            for (var, value) in entries {
+                if let Some(value) = value {
+                    set_once(&mut file.values, origin, var, value, &section)?;
+                }
+            }
+        }
+
+        for pool in &pools {
+            if let Some(chain) = &pool.chain {
+                if !file.chains.iter().any(|c| &c.name == chain) {
+                    return Err(TrackerError::config(
+                        format!(
+                            "{origin}: pool '{}' refers to chain '{chain}', which has no [chains.{chain}] section",
+                            pool.name
+                        ),
+                        None,
+                    ));
+                }
+            }
+        }
+        file.pools = pools
+            .into_iter()
+            .filter(|p| p.chain.is_none() || p.chain == file.chain)
+            .collect();
+        if let Some(first) = file.pools.first() {
+            let address = first.address.clone();
+            set_once(
+                &mut file.values,
+                origin,
+                "POOL_ADDRESS",
+                address,
+                "[[pools]]",
+            )?;
+            // Uniswap V2 is the default either way, so only another protocol
+            // can conflict with a top-level pool_protocol
+            if first.protocol != DexProtocol::default() {
+                let protocol = first.protocol.to_string();
+                set_once(
+                    &mut file.values,
+                    origin,
+                    "POOL_PROTOCOL",
+                    protocol,
+                    "[[pools]]",
+                )?;
+            }
+            if first.pool_type != PoolType::default() {
+                let pool_type = first.pool_type.to_string();
+                set_once(
+                    &mut file.values,
+                    origin,
+                    "POOL_TYPE",
+                    pool_type,
+                    "[[pools]]",
+                )?;
+            }
+            if first.quote_direction != QuoteDirection::default() {
+                let quote_direction = first.quote_direction.to_string();
+                set_once(
+                    &mut file.values,
+                    origin,
+                    "QUOTE_DIRECTION",
+                    quote_direction,
+                    "[[pools]]",
+                )?;
+            }
+        }
+
+        Ok(file)
+    }
+}
+
+/// Turns byte offsets into `file:line` for error messages.
+struct Locator<'a> {
+    raw: &'a str,
+    origin: &'a str,
+}
+
+impl Locator<'_> {
+    fn error(&self, item: &Item, message: &str) -> TrackerError {
+        self.error_at(item.span().map(|span| span.start), message)
+    }
+
+    fn error_at(&self, offset: Option<usize>, message: &str) -> TrackerError {
+        let location = offset.map_or_else(
+            || self.origin.to_string(),
+            |offset| {
+                let line = self.raw[..offset.min(self.raw.len())].matches('\n').count() + 1;
+                format!("{}:{line}", self.origin)
+            },
+        );
+        TrackerError::config(format!("{location}: {message}"), None)
+    }
+}
+
+fn parse_chains(at: &Locator<'_>, item: &Item) -> TrackerResult<Vec<ChainConfig>> {
+    let table = item
+        .as_table_like()
+        .ok_or_else(|| at.error(item, "chains must be [chains.<name>] sections"))?;
+
+    let mut chains = Vec::new();
+    for (name, section) in table.iter() {
+        let fields = section
+            .as_table_like()
+            .ok_or_else(|| at.error(section, &format!("[chains.{name}] must be a table")))?;
+        check_fields(
+            at,
+            fields,
+            &["chain_id", "rpc_url", "rpc_ws_url", "confirmation_depth"],
+            &format!("[chains.{name}]"),
+        )?;
+
+        let chain_id = match fields.get("chain_id") {
+            Some(id) => id
+                .as_integer()
+                .and_then(|id| u64::try_from(id).ok())
+                .filter(|id| *id > 0)
+                .ok_or_else(|| at.error(id, "chain_id must be a positive integer"))?,
+            None => {
+                return Err(at.error(section, &format!("[chains.{name}] is missing chain_id")));
+            }
+        };
+        let rpc_url = url_field(at, fields, "rpc_url", &["http://", "https://"])?;
+        let rpc_ws_url = url_field(at, fields, "rpc_ws_url", &["ws://", "wss://"])?;
+        let confirmation_depth = fields
+            .get("confirmation_depth")
+            .map(|depth| {
+                depth
+                    .as_integer()
+                    .and_then(|depth| u64::try_from(depth).ok())
+                    .ok_or_else(|| {
+                        at.error(depth, "confirmation_depth must be a non-negative integer")
+                    })
+            })
+            .transpose()?;
+
+        chains.push(ChainConfig {
+            name: name.to_string(),
+            chain_id,
+            rpc_url,
+            rpc_ws_url,
+            confirmation_depth,
+        });
+    }
+    Ok(chains)
+}
+
+fn parse_pools(at: &Locator<'_>, item: &Item) -> TrackerResult<Vec<PoolConfig>> {
+    let sections = item
+        .as_array_of_tables()
+        .ok_or_else(|| at.error(item, "pools must be [[pools]] sections"))?;
+
+    let mut pools: Vec<PoolConfig> = Vec::new();
+    for section in sections {
+        let offset = section.span().map(|span| span.start);
+        check_fields(
+            at,
+            section,
+            &[
+                "name",
+                "address",
+                "chain",
+                "protocol",
+                "pool_type",
+                "quote_direction",
+            ],
+            "[[pools]]",
+        )?;
+
+        let field = |key: &str| -> TrackerResult<Option<String>> {
+            section
+                .get(key)
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| at.error(value, &format!("pool {key} must be a string")))
+                })
+                .transpose()
+        };
+        let (Some(name), Some(address)) = (field("name")?, field("address")?) else {
+            return Err(at.error_at(offset, "[[pools]] entries need a name and an address"));
+        };
+        let chain = field("chain")?;
+        let protocol = match field("protocol")? {
+            Some(protocol) => protocol.parse::<DexProtocol>().map_err(|_| {
+                let value = section.get("protocol").and_then(Item::span).map(|s| s.start);
+                at.error_at(
+                    value,
+                    &format!(
+                        "pool '{name}' protocol must be uniswap_v2, sushiswap, pancakeswap or shibaswap, got: {protocol}"
+                    ),
+                )
+            })?,
+            None => DexProtocol::default(),
+        };
+        let pool_type = match field("pool_type")? {
+            Some(pool_type) => pool_type.parse::<PoolType>().map_err(|_| {
+                let value = section.get("pool_type").and_then(Item::span).map(|s| s.start);
+                at.error_at(
+                    value,
+                    &format!(
+                        "pool '{name}' pool_type must be constant_product or stable_swap:<A>[:<fee_bps>], got: {pool_type}"
+                    ),
+                )
+            })?,
+            None => PoolType::default(),
+        };
+        let quote_direction = match field("quote_direction")? {
+            Some(direction) => direction.parse::<QuoteDirection>().map_err(|_| {
+                let value = section
+                    .get("quote_direction")
+                    .and_then(Item::span)
+                    .map(|s| s.start);
+                at.error_at(
+                    value,
+                    &format!(
+                        "pool '{name}' quote_direction must be token1_per_token0 or token0_per_token1, got: {direction}"
+                    ),
+                )
+            })?,
+            None => QuoteDirection::default(),
+        };
+
+        if !is_address(&address) {
+            let value = section
+                .get("address")
+                .map_or(offset, |v| v.span().map(|s| s.start));
+            return Err(at.error_at(
+                value,
+                &format!("pool '{name}' address must be 0x followed by 40 hex characters, got: {address}"),
+            ));
+        }
+        if pools.iter().any(|p| p.name == name && p.chain == chain) {
+            return Err(at.error_at(offset, &format!("pool '{name}' is listed twice")));
+        }
+
+        pools.push(PoolConfig {
+            name,
+            address,
+            chain,
+            protocol,
+            pool_type,
+            quote_direction,
+        });
+    }
+    Ok(pools)
+}
+
+/// Rejects fields other than `allowed` in a section.
+fn check_fields(
+    at: &Locator<'_>,
+    section: &dyn TableLike,
+    allowed: &[&str],
+    context: &str,
+) -> TrackerResult<()> {
+    for (key, value) in section.iter() {
+        if !allowed.contains(&key) {
+            return Err(at.error(
+                value,
+                &format!(
+                    "unknown field '{key}' in {context} (expected: {})",
+                    allowed.join(", ")
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn url_field(
+    at: &Locator<'_>,
+    fields: &dyn TableLike,
+    key: &str,
+    schemes: &[&str],
+) -> TrackerResult<Option<String>> {
+    let Some(item) = fields.get(key) else {
+        return Ok(None);
+    };
+    match item.as_str() {
+        Some(url) if schemes.iter().any(|scheme| url.starts_with(scheme)) => {
+            Ok(Some(url.to_string()))
+        }
+        _ => Err(at.error(
+            item,
+            &format!(
+                "{key} must be a string starting with {}",
+                schemes.join(" or ")
+            ),
+        )),
+    }
+}
+
+/// Converts a value to the string its environment variable would hold.
+fn to_env_value(item: &Item, kind: Kind) -> Option<String> {
+    match kind {
+        Kind::Str => item.as_str().map(str::to_string),
+        Kind::Int => item.as_integer().filter(|n| *n >= 0).map(|n| n.to_string()),
+        Kind::Bool => item.as_bool().map(|b| b.to_string()),
+        Kind::List => item.as_str().map(str::to_string).or_else(|| {
+            item.as_array()?
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .map(|items| items.join(","))
+        }),
+        Kind::Routes => item.as_str().map(str::to_string).or_else(|| {
+            item.as_table_like()?
+                .iter()
+                .map(|(prefix, rpm)| {
+                    let rpm = rpm.as_value().and_then(Value::as_integer)?;
+                    (rpm >= 0).then(|| format!("{prefix}={rpm}"))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|routes| routes.join(","))
+        }),
+    }
+}
+
+/// Adds `var` from a section, refusing to overwrite a top-level key.
+fn set_once(
+    values: &mut HashMap<String, String>,
+    origin: &str,
+    var: &str,
+    value: String,
+    section: &str,
+) -> TrackerResult<()> {
+    if values.contains_key(var) {
+        return Err(TrackerError::config(
+            format!(
+                "{origin}: {} is set both at the top level and by {section}; keep one",
+                var.to_ascii_lowercase()
+            ),
+            None,
+        ));
+    }
+    values.insert(var.to_string(), value);
+    Ok(())
+}
+
+fn unknown_key(key: &str) -> String {
+    let closest = KEYS
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(SECTION_KEYS.iter().copied())
+        .map(|name| (edit_distance(key, name), name))
+        .min();
+    match closest {
+        Some((distance, name)) if distance <= 3 => {
+            format!("unknown key '{key}' (did you mean '{name}'?)")
+        }
+        _ => format!("unknown key '{key}'"),
+    }
+}
+
+/// Levenshtein distance, for suggesting a key after a typo.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(ca != *cb));
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+fn is_address(s: &str) -> bool {
+    s.len() == 42 && s.starts_with("0x") && s[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn names(chains: &[ChainConfig]) -> String {
+    chains
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"
+profile = "staging"
+chain = "mainnet"
+batch_size = 500
+api_cors_origins = ["https://a.example", "https://b.example"]
+api_rate_limit_routes = { "/price" = 600 }
+
+[chains.mainnet]
+chain_id = 1
+rpc_url = "https://mainnet.example/rpc"
+
+[chains.sepolia]
+chain_id = 11155111
+rpc_url = "https://sepolia.example/rpc"
+confirmation_depth = 3
+
+[[pools]]
+name = "WETH/USDT"
+address = "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"
+
+[[pools]]
+name = "WETH/USDC"
+address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+chain = "mainnet"
+
+[[pools]]
+name = "TEST/WETH"
+address = "0x1111111111111111111111111111111111111111"
+chain = "sepolia"
+protocol = "sushiswap"
+pool_type = "stable_swap:200"
+quote_direction = "token0_per_token1"
+"#;
+
+    #[test]
+    fn test_parse_flattens_into_variables() {
+        let file = ConfigFile::parse(FILE, "indexer.toml", None).unwrap();
+
+        assert_eq!(file.values["PROFILE"], "staging");
+        assert_eq!(file.values["BATCH_SIZE"], "500");
+        assert_eq!(
+            file.values["API_CORS_ORIGINS"],
+            "https://a.example,https://b.example"
+        );
+        assert_eq!(file.values["API_RATE_LIMIT_ROUTES"], "/price=600");
+        assert_eq!(file.values["RPC_URL"], "https://mainnet.example/rpc");
+        assert_eq!(file.values["CHAIN_ID"], "1");
+        assert!(!file.values.contains_key("CONFIRMATION_DEPTH"));
+        assert_eq!(
+            file.values["POOL_ADDRESS"],
+            "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"
+        );
+
+        assert_eq!(file.chain.as_deref(), Some("mainnet"));
+        assert_eq!(file.chains.len(), 2);
+        let pools: Vec<_> = file.pools.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(pools, ["WETH/USDT", "WETH/USDC"]);
+
+        // CHAIN selects another section and its pools
+        let file = ConfigFile::parse(FILE, "indexer.toml", Some("sepolia".into())).unwrap();
+        assert_eq!(file.values["CHAIN_ID"], "11155111");
+        assert_eq!(file.values["CONFIRMATION_DEPTH"], "3");
+        let pools: Vec<_> = file.pools.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(pools, ["WETH/USDT", "TEST/WETH"]);
+        assert_eq!(file.pools[0].protocol, DexProtocol::UniswapV2);
+        assert_eq!(file.pools[1].protocol, DexProtocol::SushiSwap);
+        assert_eq!(file.pools[0].pool_type, PoolType::ConstantProduct);
+        assert_eq!(
+            file.pools[1].pool_type,
+            PoolType::StableSwap {
+                amplification: 200,
+                fee_bps: 4
+            }
+        );
+        assert_eq!(
+            file.pools[1].quote_direction,
+            QuoteDirection::Token0PerToken1
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_the_problem() {
+        let error = |raw: &str| {
+            ConfigFile::parse(raw, "indexer.toml", None)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(error("batch_size = 1\nbatch_szie = 2")
+            .contains("indexer.toml:2: unknown key 'batch_szie' (did you mean 'batch_size'?)"));
+        assert!(error("batch_size = \"lots\"")
+            .contains("indexer.toml:1: batch_size must be a non-negative integer"));
+        assert!(error("batch_size = ").contains("Invalid TOML in indexer.toml"));
+        assert!(error("[chains.a]\nchain_id = 1\n[chains.b]\nchain_id = 2")
+            .contains("select one with chain"));
+        assert!(error("chain = \"x\"").contains("chain 'x' has no [chains.x] section"));
+        assert!(error("[chains.a]\nrpc_url = \"https://a\"").contains("missing chain_id"));
+        assert!(error("[chains.a]\nchain_id = 1\nrpc_url = \"a\"")
+            .contains("indexer.toml:3: rpc_url must be a string starting with"));
+        assert!(error("[[pools]]\nname = \"X\"\naddress = \"0x12\"")
+            .contains("indexer.toml:3: pool 'X' address"));
+        assert!(error("[[pools]]\nname = \"X\"\nadress = \"0x12\"")
+            .contains("unknown field 'adress' in [[pools]]"));
+        assert!(error(
+            "[[pools]]\nname = \"X\"\naddress = \"0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852\"\nprotocol = \"curve\""
+        )
+        .contains("indexer.toml:4: pool 'X' protocol must be"));
+        assert!(error("chain_id = 5\n[chains.a]\nchain_id = 1")
+            .contains("chain_id is set both at the top level and by [chains.a]"));
+        assert!(error("[chains.a]\nchain_id = 1\nconfirmation_depth = -1")
+            .contains("indexer.toml:3: confirmation_depth must be a non-negative integer"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("batch_size", "batch_size"), 0);
+        assert_eq!(edit_distance("batch_szie", "batch_size"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,156 @@
+//! Secrets read from files, and redaction for display.
+//!
+//! Docker secrets and Kubernetes secret volumes hand credentials to a
+//! container as files rather than environment variables. Each variable in
+//! [`SECRET_VARS`] can instead be given as `<NAME>_FILE`, the path of a file
+//! holding the value; surrounding whitespace such as a trailing newline is
+//! trimmed. Setting both the variable and its `_FILE` is an error, so a stale
+//! variable never silently shadows a mounted secret.
+//!
+//! [`redact_url`] hides the credentials in a URL for `--print-config`.
+
+use std::collections::HashMap;
+use std::env;
+
+use crate::error::{TrackerError, TrackerResult};
+
+/// Variables that may be read from the file named by `<NAME>_FILE`.
+pub const SECRET_VARS: &[&str] = &[
+    "RPC_URL",
+    "RPC_WS_URL",
+    "RPC_ARCHIVE_URL",
+    "ALCHEMY_API_KEY",
+    "DATABASE_URL",
+    "TELEGRAM_BOT_TOKEN",
+    "DISCORD_BOT_TOKEN",
+    "SMTP_URL",
+    "RESPONSE_SIGNING_KEY",
+    "PRICE_WEBHOOK_SECRET",
+    "REDIS_URL",
+];
+
+/// Placeholder for a redacted value.
+pub const REDACTED: &str = "***";
+
+/// Reads every `<NAME>_FILE` that is set, returning the values by variable
+/// name.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read, or a variable is set both
+/// directly and through `_FILE`.
+pub(crate) fn read_secret_files(
+    var: &dyn Fn(&str) -> Result<String, env::VarError>,
+) -> TrackerResult<HashMap<&'static str, String>> {
+    let mut secrets = HashMap::new();
+
+    for &name in SECRET_VARS {
+        let file_var = format!("{name}_FILE");
+        let Some(path) = var(&file_var).ok().filter(|p| !p.trim().is_empty()) else {
+            continue;
+        };
+        if var(name).is_ok_and(|value| !value.is_empty()) {
+            return Err(TrackerError::config(
+                format!("{name} and {file_var} are both set; use only one"),
+                None,
+            ));
+        }
+
+        let value = std::fs::read_to_string(path.trim()).map_err(|e| {
+            TrackerError::config(
+                format!("Failed to read {file_var} ({})", path.trim()),
+                Some(Box::new(e)),
+            )
+        })?;
+        secrets.insert(name, value.trim().to_string());
+    }
+
+    Ok(secrets)
+}
+
+/// Hides the credentials in a URL: a password, and the path and query of
+/// HTTP and WebSocket URLs, where RPC providers put API keys.
+///
+/// Unparseable values are redacted entirely.
+#[must_use]
+pub fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return REDACTED.to_string();
+    };
+
+    if parsed.password().is_some() {
+        let _ = parsed.set_password(Some(REDACTED));
+    }
+    if matches!(parsed.scheme(), "http" | "https" | "ws" | "wss")
+        && (parsed.path() != "/" || parsed.query().is_some())
+    {
+        parsed.set_path(REDACTED);
+        parsed.set_query(None);
+    }
+
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(env: Vec<(&'static str, String)>) -> impl Fn(&str) -> Result<String, env::VarError> {
+        move |key| {
+            env.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.clone())
+                .ok_or(env::VarError::NotPresent)
+        }
+    }
+
+    #[test]
+    fn test_read_secret_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alchemy_api_key");
+        std::fs::write(&path, "abc123\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let secrets =
+            read_secret_files(&lookup(vec![("ALCHEMY_API_KEY_FILE", path.clone())])).unwrap();
+        assert_eq!(
+            secrets.get("ALCHEMY_API_KEY").map(String::as_str),
+            Some("abc123")
+        );
+        assert_eq!(secrets.len(), 1);
+
+        let both = lookup(vec![
+            ("ALCHEMY_API_KEY_FILE", path),
+            ("ALCHEMY_API_KEY", "other".to_string()),
+        ]);
+        assert!(read_secret_files(&both).is_err());
+
+        let missing = lookup(vec![(
+            "DATABASE_URL_FILE",
+            dir.path().join("missing").to_string_lossy().to_string(),
+        )]);
+        assert!(read_secret_files(&missing).is_err());
+    }
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("https://eth-mainnet.g.alchemy.com/v2/abc123"),
+            "https://eth-mainnet.g.alchemy.com/***"
+        );
+        assert_eq!(
+            redact_url("wss://node.example:8546/?key=abc"),
+            "wss://node.example:8546/***"
+        );
+        assert_eq!(
+            redact_url("http://localhost:8545/"),
+            "http://localhost:8545/"
+        );
+        assert_eq!(redact_url("sqlite:./indexer.db"), "sqlite:./indexer.db");
+        assert_eq!(
+            redact_url("postgres://tracker:hunter2@db/prices"),
+            "postgres://tracker:***@db/prices"
+        );
+        assert_eq!(redact_url("not a url"), REDACTED);
+    }
+}
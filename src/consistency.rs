@@ -0,0 +1,214 @@
+//! Startup consistency check between the checkpoint, the stored rows and the
+//! chain.
+//!
+//! `watch` resumes from the pool's checkpoint, but the checkpoint and the
+//! rows can disagree:
+//!
+//! - **Rows past the checkpoint**: a batch's events and prices are written
+//!   before the checkpoint is saved, so a crash in between leaves confirmed
+//!   rows after the checkpoint block. Re-indexing rewrites them, but until
+//!   then they count as indexed data (and the smoothed price resumes from
+//!   them). The rows are marked unconfirmed, like after a reorg, so the
+//!   checkpoint is the one resume point.
+//! - **Checkpoint block reorged out**: the chain reorganized while the
+//!   indexer was stopped, and the checkpoint block's hash no longer matches
+//!   the chain. The fork point is searched among the blocks with stored
+//!   events, as [`ReorgDetector`] does for reorgs seen while running; rows
+//!   after it are marked unconfirmed, the checkpoint is rewound to it and the
+//!   reorg is recorded.
+//!
+//! Every repair is logged at WARN. [`verify_resume_point`] leaves the detector
+//! tracking the stored block hashes, so later reorgs deeper than the
+//! checkpoint are found too.
+
+use alloy::primitives::B256;
+use std::fmt;
+use tracing::{info, warn};
+
+use crate::db::checkpoint::{Checkpoint, CheckpointStore};
+use crate::db::models::ReorgRecord;
+use crate::db::repository::Repository;
+use crate::error::TrackerResult;
+use crate::reorg::{BlockRecord, ReorgDetector};
+use crate::rpc::Provider;
+
+/// A disagreement found at startup, and repaired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repair {
+    /// Confirmed rows were written past the checkpoint and were marked
+    /// unconfirmed
+    RowsPastCheckpoint {
+        /// Checkpoint block
+        checkpoint: u64,
+        /// Last block with confirmed rows
+        last_written: u64,
+    },
+    /// The checkpoint block was reorged out while stopped; the checkpoint
+    /// was rewound to the fork point
+    CheckpointReorged {
+        /// Checkpoint block before the repair
+        checkpoint: u64,
+        /// Last block still on the chain
+        fork_point: u64,
+    },
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RowsPastCheckpoint {
+                checkpoint,
+                last_written,
+            } => write!(
+                f,
+                "rows up to block {last_written} were written past checkpoint block \
+                 {checkpoint}; marked them unconfirmed for re-indexing"
+            ),
+            Self::CheckpointReorged {
+                checkpoint,
+                fork_point,
+            } => write!(
+                f,
+                "checkpoint block {checkpoint} is no longer on the chain; rewound to fork \
+                 point {fork_point}"
+            ),
+        }
+    }
+}
+
+/// Outcome of [`verify_resume_point`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StartupCheck {
+    /// Checkpoint after the repairs (`None` before the first run)
+    pub checkpoint: Option<Checkpoint>,
+    /// Repairs applied, in order
+    pub repairs: Vec<Repair>,
+}
+
+impl StartupCheck {
+    /// Returns true if the checkpoint and rows agreed with each other and
+    /// the chain.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.repairs.is_empty()
+    }
+}
+
+/// Checks the pool's checkpoint against its stored rows and the chain, and
+/// repairs any disagreement so indexing can resume from the returned
+/// checkpoint.
+///
+/// `detector` is seeded with the hashes of the newest blocks with stored
+/// events, up to its history limit, and the checkpoint block.
+///
+/// # Errors
+///
+/// Returns an error if a database query or an RPC request fails.
+pub async fn verify_resume_point(
+    provider: &Provider,
+    repository: &Repository,
+    pool_id: i64,
+    detector: &mut ReorgDetector,
+) -> TrackerResult<StartupCheck> {
+    let Some(mut checkpoint) = repository.load(pool_id).await? else {
+        return Ok(StartupCheck::default());
+    };
+    let mut repairs = Vec::new();
+
+    if let Some(last_written) = repository.get_last_written_block(pool_id).await? {
+        if last_written > checkpoint.block {
+            repository
+                .invalidate_from_block(pool_id, checkpoint.block + 1)
+                .await?;
+            repairs.push(Repair::RowsPastCheckpoint {
+                checkpoint: checkpoint.block,
+                last_written,
+            });
+        }
+    }
+
+    if let Some(hash) = checkpoint.block_hash {
+        let limit = i64::try_from(detector.max_tracked_blocks()).unwrap_or(i64::MAX);
+        for (block, stored) in repository
+            .get_event_block_hashes(pool_id, checkpoint.block, limit)
+            .await?
+        {
+            if let Ok(stored) = stored.parse::<B256>() {
+                detector.add_block(BlockRecord::new(block, stored, B256::ZERO, 0));
+            }
+        }
+        detector.add_block(BlockRecord::new(checkpoint.block, hash, B256::ZERO, 0));
+
+        if let Some(fork_point) = detector.detect_reorg(provider, checkpoint.block).await? {
+            repository
+                .invalidate_from_block(pool_id, fork_point + 1)
+                .await?;
+            detector.rewind(fork_point);
+            let fork_hash = detector
+                .last_block()
+                .filter(|block| block.number == fork_point)
+                .map(|block| block.hash);
+            repository
+                .insert_reorg(&ReorgRecord {
+                    pool_id,
+                    detected_at: chrono::Utc::now().timestamp(),
+                    fork_point,
+                    depth: checkpoint.block - fork_point,
+                })
+                .await?;
+            repairs.push(Repair::CheckpointReorged {
+                checkpoint: checkpoint.block,
+                fork_point,
+            });
+            checkpoint = Checkpoint {
+                reorg_count: checkpoint.reorg_count + 1,
+                ..checkpoint.at(fork_point, fork_hash)
+            };
+            repository.save(pool_id, &checkpoint).await?;
+        }
+    }
+
+    for repair in &repairs {
+        warn!(pool_id, "Startup consistency check: {repair}");
+    }
+    if repairs.is_empty() {
+        info!(
+            pool_id,
+            block = checkpoint.block,
+            "Checkpoint is consistent with stored rows and the chain"
+        );
+    }
+
+    Ok(StartupCheck {
+        checkpoint: Some(checkpoint),
+        repairs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repairs_describe_the_resume_point() {
+        let rows = Repair::RowsPastCheckpoint {
+            checkpoint: 100,
+            last_written: 104,
+        };
+        assert!(rows.to_string().contains("up to block 104"));
+        assert!(rows.to_string().contains("checkpoint block 100"));
+
+        let reorg = Repair::CheckpointReorged {
+            checkpoint: 100,
+            fork_point: 97,
+        };
+        assert!(reorg.to_string().contains("fork point 97"));
+
+        assert!(StartupCheck::default().is_consistent());
+        let check = StartupCheck {
+            checkpoint: Some(Checkpoint::default().at(97, None)),
+            repairs: vec![rows, reorg],
+        };
+        assert!(!check.is_consistent());
+    }
+}
@@ -0,0 +1,389 @@
+//! Daemon mode for running under a service manager.
+//!
+//! `watch --daemon` and `api --daemon` stay in the foreground (systemd,
+//! launchd and supervisord expect that and take care of detaching, restarts
+//! and logs) but additionally manage a run directory (`RUN_DIR`):
+//!
+//! - `<name>.pid` holds the process ID. Starting refuses if another instance
+//!   of the same command is still answering on its socket; a PID file left
+//!   by a crashed process is replaced.
+//! - `<name>.sock` is a Unix socket: every connection receives one JSON line
+//!   with a [`HealthReport`] and is closed. [`probe`] (the `health` command)
+//!   reads it, so liveness checks need no HTTP port.
+//!
+//! Both files are removed when the [`Daemon`] is dropped. The indexer
+//! reports progress through [`Liveness::record_progress`]; with a
+//! `stale_after` limit, a process that stops making progress reports
+//! `stale` while its socket still answers. Watch mode also reports how many
+//! duplicate logs it dropped.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use eth_uniswap_alloy::daemon::{probe, Daemon};
+//! use std::path::Path;
+//! use std::time::Duration;
+//!
+//! # async fn example() -> eth_uniswap_alloy::error::TrackerResult<()> {
+//! let daemon = Daemon::start(Path::new("./run"), "watch", Some(Duration::from_secs(120))).await?;
+//! daemon.liveness().record_progress(19_000_000);
+//!
+//! let report = probe(Path::new("./run"), "watch").await?;
+//! assert!(report.is_healthy());
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::error::{TrackerError, TrackerResult};
+
+/// Health of a daemon process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonStatus {
+    /// Running and making progress
+    Ok,
+    /// Running, but no progress within `stale_after`
+    Stale,
+}
+
+/// One line written to each health socket connection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Command name (`watch`, `api`)
+    pub name: String,
+    /// Process ID
+    pub pid: u32,
+    /// Overall status
+    pub status: DaemonStatus,
+    /// Seconds since the daemon started
+    pub uptime_secs: u64,
+    /// Last block the indexer reported, if any
+    pub last_block: Option<u64>,
+    /// Seconds since the last reported progress, if any
+    pub last_progress_secs: Option<u64>,
+    /// Fetched logs dropped as duplicates (see [`crate::dedup`])
+    #[serde(default)]
+    pub duplicates_dropped: u64,
+}
+
+impl HealthReport {
+    /// Returns true if the status is [`DaemonStatus::Ok`].
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.status == DaemonStatus::Ok
+    }
+}
+
+/// Progress tracker shared between the indexer and the health socket.
+#[derive(Debug)]
+pub struct Liveness {
+    started: Instant,
+    stale_after: Option<Duration>,
+    progress: Mutex<Option<(u64, Instant)>>,
+    duplicates_dropped: AtomicU64,
+}
+
+impl Liveness {
+    /// Creates a tracker; without `stale_after` the status is always `ok`.
+    #[must_use]
+    pub fn new(stale_after: Option<Duration>) -> Self {
+        Self {
+            started: Instant::now(),
+            stale_after,
+            progress: Mutex::new(None),
+            duplicates_dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Records that indexing has caught up to `block`.
+    pub fn record_progress(&self, block: u64) {
+        *self.progress.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((block, Instant::now()));
+    }
+
+    /// Records the total number of duplicate logs dropped so far.
+    pub fn record_duplicates_dropped(&self, total: u64) {
+        self.duplicates_dropped.store(total, Ordering::Relaxed);
+    }
+
+    /// Builds the current report for `name`.
+    #[must_use]
+    pub fn report(&self, name: &str) -> HealthReport {
+        let progress = *self.progress.lock().unwrap_or_else(PoisonError::into_inner);
+        let uptime = self.started.elapsed();
+        // Before the first progress, count from startup
+        let idle = progress.map_or(uptime, |(_, at)| at.elapsed());
+        let status = match self.stale_after {
+            Some(limit) if idle > limit => DaemonStatus::Stale,
+            _ => DaemonStatus::Ok,
+        };
+
+        HealthReport {
+            name: name.to_string(),
+            pid: std::process::id(),
+            status,
+            uptime_secs: uptime.as_secs(),
+            last_block: progress.map(|(block, _)| block),
+            last_progress_secs: progress.map(|(_, at)| at.elapsed().as_secs()),
+            duplicates_dropped: self.duplicates_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// PID file and health socket of a running command; removed on drop.
+#[derive(Debug)]
+pub struct Daemon {
+    pid_file: PathBuf,
+    socket_path: PathBuf,
+    liveness: Arc<Liveness>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl Daemon {
+    /// Creates `run_dir`, writes `<name>.pid` and starts serving
+    /// `<name>.sock`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if another instance is answering on the socket, the
+    /// files cannot be created, or the platform has no Unix sockets.
+    pub async fn start(
+        run_dir: &Path,
+        name: &str,
+        stale_after: Option<Duration>,
+    ) -> TrackerResult<Self> {
+        let (pid_file, socket_path) = paths(run_dir, name);
+        std::fs::create_dir_all(run_dir).map_err(|e| {
+            TrackerError::state(
+                format!("Failed to create run directory {}", run_dir.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        if let Ok(report) = probe(run_dir, name).await {
+            return Err(TrackerError::state(
+                format!("{name} is already running (pid {})", report.pid),
+                None,
+            ));
+        }
+        if pid_file.exists() {
+            warn!(path = %pid_file.display(), "Replacing stale PID file");
+        }
+
+        let liveness = Arc::new(Liveness::new(stale_after));
+        let server = serve(&socket_path, name.to_string(), Arc::clone(&liveness))?;
+        // Built before writing the PID file so a failure below cleans up
+        let daemon = Self {
+            pid_file,
+            socket_path,
+            liveness,
+            server,
+        };
+        std::fs::write(&daemon.pid_file, format!("{}\n", std::process::id())).map_err(|e| {
+            TrackerError::state(
+                format!("Failed to write PID file {}", daemon.pid_file.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        info!(
+            pid_file = %daemon.pid_file.display(),
+            socket = %daemon.socket_path.display(),
+            "Daemon mode enabled"
+        );
+        Ok(daemon)
+    }
+
+    /// The progress tracker reported on the socket.
+    #[must_use]
+    pub fn liveness(&self) -> Arc<Liveness> {
+        Arc::clone(&self.liveness)
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        self.server.abort();
+        let _ = std::fs::remove_file(&self.socket_path);
+        let _ = std::fs::remove_file(&self.pid_file);
+    }
+}
+
+/// Reads the health report of the `name` daemon in `run_dir`.
+///
+/// # Errors
+///
+/// Returns an error if nothing answers on the socket or the reply is not a
+/// health report.
+pub async fn probe(run_dir: &Path, name: &str) -> TrackerResult<HealthReport> {
+    let (_, socket_path) = paths(run_dir, name);
+    let reply = read_socket(&socket_path).await.map_err(|e| {
+        TrackerError::state(
+            format!("No {name} daemon answering on {}", socket_path.display()),
+            Some(Box::new(e)),
+        )
+    })?;
+    serde_json::from_str(reply.trim()).map_err(|e| {
+        TrackerError::decoding(
+            format!("Invalid health report from {}", socket_path.display()),
+            Some(Box::new(e)),
+        )
+    })
+}
+
+/// Waits for Ctrl+C (SIGINT) or, on Unix, SIGTERM from a service manager.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!(error = %e, "Failed to listen for SIGTERM"),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+fn paths(run_dir: &Path, name: &str) -> (PathBuf, PathBuf) {
+    (
+        run_dir.join(format!("{name}.pid")),
+        run_dir.join(format!("{name}.sock")),
+    )
+}
+
+#[cfg(unix)]
+fn serve(
+    socket_path: &Path,
+    name: String,
+    liveness: Arc<Liveness>,
+) -> TrackerResult<tokio::task::JoinHandle<()>> {
+    use tokio::io::AsyncWriteExt;
+
+    // A socket file left by a crashed process would make bind fail
+    let _ = std::fs::remove_file(socket_path);
+    let listener = tokio::net::UnixListener::bind(socket_path).map_err(|e| {
+        TrackerError::state(
+            format!("Failed to bind health socket {}", socket_path.display()),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let mut stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!(error = %e, "Health socket accept failed");
+                    continue;
+                }
+            };
+            let report = liveness.report(&name);
+            let Ok(mut line) = serde_json::to_string(&report) else {
+                continue;
+            };
+            line.push('\n');
+            if let Err(e) = stream.write_all(line.as_bytes()).await {
+                warn!(error = %e, "Failed to write health report");
+            }
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+fn serve(
+    _socket_path: &Path,
+    _name: String,
+    _liveness: Arc<Liveness>,
+) -> TrackerResult<tokio::task::JoinHandle<()>> {
+    Err(TrackerError::state(
+        "Daemon mode requires Unix domain sockets",
+        None,
+    ))
+}
+
+#[cfg(unix)]
+async fn read_socket(socket_path: &Path) -> std::io::Result<String> {
+    use tokio::io::AsyncReadExt;
+
+    let mut stream = tokio::net::UnixStream::connect(socket_path).await?;
+    let mut reply = String::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_string(&mut reply))
+        .await
+        .map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::TimedOut, "health socket timed out")
+        })??;
+    Ok(reply)
+}
+
+#[cfg(not(unix))]
+async fn read_socket(_socket_path: &Path) -> std::io::Result<String> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Unix domain sockets are not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_daemon_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let run_dir = dir.path().join("run");
+
+        let daemon = Daemon::start(&run_dir, "watch", Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        let pid = std::fs::read_to_string(run_dir.join("watch.pid")).unwrap();
+        assert_eq!(pid.trim(), std::process::id().to_string());
+
+        daemon.liveness().record_progress(123);
+        let report = probe(&run_dir, "watch").await.unwrap();
+        assert!(report.is_healthy());
+        assert_eq!(report.last_block, Some(123));
+
+        // A second instance must not take over
+        let second = Daemon::start(&run_dir, "watch", None).await;
+        assert!(second.is_err_and(|e| e.to_string().contains("already running")));
+
+        drop(daemon);
+        assert!(!run_dir.join("watch.pid").exists());
+        assert!(!run_dir.join("watch.sock").exists());
+        assert!(probe(&run_dir, "watch").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stale_pid_file_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("api.pid"), "999999\n").unwrap();
+        std::fs::write(dir.path().join("api.sock"), "").unwrap();
+
+        let daemon = Daemon::start(dir.path(), "api", None).await.unwrap();
+        assert!(probe(dir.path(), "api").await.unwrap().is_healthy());
+        drop(daemon);
+    }
+
+    #[test]
+    fn test_liveness_goes_stale_without_progress() {
+        let liveness = Liveness::new(Some(Duration::ZERO));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(liveness.report("watch").status, DaemonStatus::Stale);
+
+        let unlimited = Liveness::new(None);
+        assert!(unlimited.report("api").is_healthy());
+    }
+}
@@ -0,0 +1,160 @@
+//! Compressed archive of old sync events.
+//!
+//! Raw Sync events make up most of a long-running database, yet old ones are
+//! only read to rebuild prices or look up historical reserves. Archiving moves
+//! a pool's confirmed events into the `event_archive` table, one
+//! zstd-compressed JSON blob per segment of [`ARCHIVE_SEGMENT_BLOCKS`]
+//! consecutive blocks, and deletes their `sync_events` rows. Block hashes,
+//! transaction hashes and reserves repeat heavily within a segment, so a
+//! segment typically takes a fifth to a tenth of the space of its rows.
+//!
+//! Only whole segments below the cutoff are archived, so the recent history
+//! the indexer and the API read most stays in `sync_events`. Events written
+//! into an archived segment later (e.g. by a re-run backfill) stay raw until
+//! the next archive run merges them into the segment.
+//!
+//! Archived events are read back transparently by
+//! [`Repository::get_sync_event_at`], [`Repository::get_sync_events_in_blocks`],
+//! [`Repository::get_sync_event_records`] and [`Repository::get_events_page`],
+//! which back historical reserves, price paths, replays and the paginated
+//! event listing. The recent-events feed only covers raw rows.
+//!
+//! [`Repository::get_sync_event_at`]: super::repository::Repository::get_sync_event_at
+//! [`Repository::get_sync_events_in_blocks`]: super::repository::Repository::get_sync_events_in_blocks
+//! [`Repository::get_sync_event_records`]: super::repository::Repository::get_sync_event_records
+//! [`Repository::get_events_page`]: super::repository::Repository::get_events_page
+
+use super::models::SyncEventRecord;
+use crate::error::TrackerError;
+
+/// Blocks per archive segment.
+pub const ARCHIVE_SEGMENT_BLOCKS: u64 = 10_000;
+
+/// Default number of recent blocks kept raw by the `archive` command.
+pub const DEFAULT_ARCHIVE_KEEP_BLOCKS: u64 = 100_000;
+
+/// `event_archive.codec` of zstd-compressed JSON segments.
+pub const ARCHIVE_CODEC_ZSTD_JSON: &str = "zstd-json";
+
+/// zstd level segments are written with; archiving runs offline, so it
+/// trades speed for size.
+const ARCHIVE_ZSTD_LEVEL: i32 = 19;
+
+/// Outcome of an archive run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveReport {
+    /// Segments written or extended
+    pub segments: u64,
+    /// Sync events moved out of `sync_events`
+    pub events: u64,
+    /// Uncompressed size of the written segments, in bytes
+    pub raw_bytes: u64,
+    /// Compressed size of the written segments, in bytes
+    pub compressed_bytes: u64,
+}
+
+impl ArchiveReport {
+    /// Uncompressed size divided by compressed size (0 if nothing was
+    /// written).
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn compression_ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            0.0
+        } else {
+            self.raw_bytes as f64 / self.compressed_bytes as f64
+        }
+    }
+}
+
+/// Returns the first block of the segment holding `block`.
+#[must_use]
+pub const fn segment_start(block: u64) -> u64 {
+    block - block % ARCHIVE_SEGMENT_BLOCKS
+}
+
+/// Compresses a segment's events, returning the blob and its uncompressed
+/// size.
+///
+/// # Errors
+///
+/// Returns an error if the events can't be serialized or compressed.
+pub fn encode_segment(events: &[SyncEventRecord]) -> Result<(Vec<u8>, usize), TrackerError> {
+    let json = serde_json::to_vec(events).map_err(|e| {
+        TrackerError::database(
+            "Failed to encode archive segment".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+    let data = zstd::encode_all(json.as_slice(), ARCHIVE_ZSTD_LEVEL).map_err(|e| {
+        TrackerError::database(
+            "Failed to compress archive segment".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+    Ok((data, json.len()))
+}
+
+/// Decompresses a segment's events.
+///
+/// # Errors
+///
+/// Returns an error if the codec is unknown or the blob is corrupt.
+pub fn decode_segment(codec: &str, data: &[u8]) -> Result<Vec<SyncEventRecord>, TrackerError> {
+    if codec != ARCHIVE_CODEC_ZSTD_JSON {
+        return Err(TrackerError::database(
+            format!("Unknown archive codec '{codec}'"),
+            None,
+        ));
+    }
+    let json = zstd::decode_all(data).map_err(|e| {
+        TrackerError::database(
+            "Failed to decompress archive segment".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+    serde_json::from_slice(&json).map_err(|e| {
+        TrackerError::database(
+            "Failed to decode archive segment".to_string(),
+            Some(Box::new(e)),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{FixedBytes, U256};
+
+    #[test]
+    fn test_segment_round_trip_compresses() {
+        let events: Vec<_> = (0..500_u64)
+            .map(|i| {
+                SyncEventRecord::new(
+                    1,
+                    19_000_000 + i / 3,
+                    FixedBytes::from([u8::try_from(i / 3 % 256).unwrap(); 32]),
+                    1_706_745_600 + i / 3 * 12,
+                    FixedBytes::from([7u8; 32]),
+                    u32::try_from(i % 3).unwrap(),
+                    U256::from(45_000_000_000_000_000_000_u128 + u128::from(i)),
+                    U256::from(110_250_000_000_u64 - i),
+                    true,
+                )
+                .with_event_id(format!("sync-{i}"))
+            })
+            .collect();
+
+        let (data, raw_bytes) = encode_segment(&events).unwrap();
+        assert!(raw_bytes / data.len() >= 5, "{raw_bytes} -> {}", data.len());
+
+        let decoded = decode_segment(ARCHIVE_CODEC_ZSTD_JSON, &data).unwrap();
+        assert_eq!(decoded.len(), events.len());
+        assert_eq!(decoded[499].event_id.as_deref(), Some("sync-499"));
+        assert_eq!(decoded[499].reserve1, events[499].reserve1);
+
+        assert!(decode_segment("gzip-json", &data).is_err());
+        assert!(decode_segment(ARCHIVE_CODEC_ZSTD_JSON, &data[..10]).is_err());
+        assert_eq!(segment_start(19_004_999), 19_000_000);
+    }
+}
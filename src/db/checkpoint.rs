@@ -0,0 +1,280 @@
+//! Per-pool indexer checkpoints.
+//!
+//! A [`Checkpoint`] is where indexing of a pool resumes: the last indexed
+//! block and its hash (for reorg detection), the number of reorgs handled and
+//! the lifetime event count. Watch mode, the pipeline and backfills all read
+//! and advance it through one [`CheckpointStore`], keyed by pool ID, so there
+//! is a single resume point per pool.
+//!
+//! Two stores are built in:
+//!
+//! - every [`Storage`] is a checkpoint store over its indexer state; for the
+//!   built-in [`Repository`](super::repository::Repository) that is the SQLite
+//!   `indexer_state` table, which the CLI uses
+//! - [`JsonFileCheckpointStore`] keeps checkpoints in a JSON file, for
+//!   embedders whose storage has nowhere to keep them
+//!
+//! Older versions saved watch mode's progress to `state.json` as well as the
+//! database. [`import_legacy_state`] moves such a file's checkpoint into a
+//! store once, then renames the file so it is never read again.
+
+use alloy::primitives::B256;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::models::IndexerState;
+use super::storage::Storage;
+use crate::error::{TrackerError, TrackerResult};
+use crate::state::State;
+
+/// Where indexing of one pool resumes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Last indexed block
+    pub block: u64,
+    /// Hash of `block`, if known
+    pub block_hash: Option<B256>,
+    /// Chain reorganizations handled so far
+    pub reorg_count: u64,
+    /// Sync events indexed so far
+    pub events_processed: u64,
+}
+
+impl Checkpoint {
+    /// Moves the checkpoint to `block`.
+    #[must_use]
+    pub const fn at(mut self, block: u64, block_hash: Option<B256>) -> Self {
+        self.block = block;
+        self.block_hash = block_hash;
+        self
+    }
+
+    /// Returns the checkpoint as `pool_id`'s indexer state row.
+    #[must_use]
+    pub fn to_state(&self, pool_id: i64) -> IndexerState {
+        IndexerState::new(
+            pool_id,
+            self.block,
+            self.block_hash.unwrap_or_default(),
+            self.reorg_count,
+            self.events_processed,
+        )
+    }
+}
+
+/// Persists per-pool [`Checkpoint`]s.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Returns the pool's checkpoint, or `None` before the first run.
+    async fn load(&self, pool_id: i64) -> TrackerResult<Option<Checkpoint>>;
+
+    /// Creates or replaces the pool's checkpoint.
+    async fn save(&self, pool_id: i64, checkpoint: &Checkpoint) -> TrackerResult<()>;
+}
+
+#[async_trait]
+impl<S: Storage + ?Sized> CheckpointStore for S {
+    async fn load(&self, pool_id: i64) -> TrackerResult<Option<Checkpoint>> {
+        let Some(state) = self.get_state(pool_id).await? else {
+            return Ok(None);
+        };
+        let hash = state.block_hash()?;
+        Ok(Some(Checkpoint {
+            block: u64::try_from(state.last_indexed_block).unwrap_or(0),
+            block_hash: (hash != B256::ZERO).then_some(hash),
+            reorg_count: u64::try_from(state.reorg_count).unwrap_or(0),
+            events_processed: u64::try_from(state.total_events_processed).unwrap_or(0),
+        }))
+    }
+
+    async fn save(&self, pool_id: i64, checkpoint: &Checkpoint) -> TrackerResult<()> {
+        self.set_state(&checkpoint.to_state(pool_id)).await
+    }
+}
+
+/// Keeps checkpoints in a JSON file, as an object keyed by pool ID.
+///
+/// Saves write a temporary file and rename it over the old one, so a crash
+/// never leaves a truncated file behind.
+#[derive(Debug, Clone)]
+pub struct JsonFileCheckpointStore {
+    path: PathBuf,
+}
+
+impl JsonFileCheckpointStore {
+    /// Creates a store backed by the file at `path`, created on first save.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the backing file's path.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> TrackerResult<BTreeMap<i64, Checkpoint>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let json = fs::read_to_string(&self.path).map_err(|e| {
+            TrackerError::state("Failed to read checkpoint file", Some(Box::new(e)))
+        })?;
+        serde_json::from_str(&json).map_err(|e| {
+            TrackerError::state(
+                format!("Invalid checkpoint file {}", self.path.display()),
+                Some(Box::new(e)),
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for JsonFileCheckpointStore {
+    async fn load(&self, pool_id: i64) -> TrackerResult<Option<Checkpoint>> {
+        Ok(self.read()?.remove(&pool_id))
+    }
+
+    async fn save(&self, pool_id: i64, checkpoint: &Checkpoint) -> TrackerResult<()> {
+        let mut checkpoints = self.read()?;
+        checkpoints.insert(pool_id, *checkpoint);
+        let json = serde_json::to_string_pretty(&checkpoints).map_err(|e| {
+            TrackerError::state("Failed to serialize checkpoints", Some(Box::new(e)))
+        })?;
+
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .map_err(|e| TrackerError::state("Failed to write checkpoint file", Some(Box::new(e))))
+    }
+}
+
+/// Imports a legacy `state.json` into `store` as `pool_id`'s checkpoint.
+///
+/// The file's checkpoint is only imported if the store has none for the
+/// pool (or one at block 0); a store that already has one (e.g. a database restored from a
+/// snapshot) keeps it. Either way the file is renamed to `<path>.migrated`
+/// afterwards. Returns the imported checkpoint, or `None` if there was no
+/// file or the store kept its own.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be parsed or renamed, or the store
+/// fails.
+pub async fn import_legacy_state(
+    path: &Path,
+    pool_id: i64,
+    store: &dyn CheckpointStore,
+) -> TrackerResult<Option<Checkpoint>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let state = State::load(path)?;
+    let stored = store.load(pool_id).await?.map_or(0, |c| c.block);
+    let imported = if stored == 0 && state.get_last_block() > 0 {
+        let checkpoint = Checkpoint {
+            block: state.get_last_block(),
+            block_hash: state.last_block_hash(),
+            reorg_count: state.reorg_count(),
+            events_processed: 0,
+        };
+        store.save(pool_id, &checkpoint).await?;
+        Some(checkpoint)
+    } else {
+        None
+    };
+
+    let mut migrated = path.as_os_str().to_owned();
+    migrated.push(".migrated");
+    fs::rename(path, &migrated).map_err(|e| {
+        TrackerError::state("Failed to rename legacy state file", Some(Box::new(e)))
+    })?;
+    info!(
+        path = %path.display(),
+        imported_block = imported.map(|c| c.block),
+        "Legacy state file migrated"
+    );
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::db::repository::Repository;
+    use crate::events::Sync;
+    use alloy::primitives::Uint;
+
+    #[tokio::test]
+    async fn test_stores_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let json = JsonFileCheckpointStore::new(dir.path().join("checkpoints.json"));
+
+        let checkpoint = Checkpoint {
+            block: 100,
+            block_hash: Some(B256::from([7u8; 32])),
+            reorg_count: 2,
+            events_processed: 40,
+        };
+        for store in [&repo as &dyn CheckpointStore, &json] {
+            let fresh = store.load(pool_id).await.unwrap().unwrap_or_default();
+            assert_eq!(fresh.block, 0);
+            store.save(pool_id, &checkpoint).await.unwrap();
+            assert_eq!(store.load(pool_id).await.unwrap(), Some(checkpoint));
+        }
+
+        // Other pools' checkpoints are kept apart
+        json.save(pool_id + 1, &checkpoint.at(5, None))
+            .await
+            .unwrap();
+        assert_eq!(json.load(pool_id).await.unwrap(), Some(checkpoint));
+        assert_eq!(
+            json.load(pool_id + 1).await.unwrap(),
+            Some(checkpoint.at(5, None))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_legacy_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let mut state = State::new();
+        let sync = Sync {
+            reserve0: Uint::<112, 2>::from(1_000),
+            reserve1: Uint::<112, 2>::from(2_000),
+        };
+        state.update_from_sync_event(&sync, 19_000_000).unwrap();
+        state.set_block_hash(B256::from([1u8; 32]));
+        state.increment_reorg_count();
+        state.save(&path).unwrap();
+
+        let store = JsonFileCheckpointStore::new(dir.path().join("checkpoints.json"));
+        let imported = import_legacy_state(&path, 1, &store)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(imported.block, 19_000_000);
+        assert_eq!(imported.reorg_count, 1);
+        assert_eq!(store.load(1).await.unwrap(), Some(imported));
+        assert!(!path.exists());
+        assert!(dir.path().join("state.json.migrated").exists());
+
+        // A store with its own checkpoint keeps it
+        state.save(&path).unwrap();
+        let kept = Checkpoint::default().at(5, None);
+        store.save(1, &kept).await.unwrap();
+        assert_eq!(import_legacy_state(&path, 1, &store).await.unwrap(), None);
+        assert_eq!(store.load(1).await.unwrap(), Some(kept));
+        assert!(!path.exists());
+
+        assert_eq!(import_legacy_state(&path, 1, &store).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,512 @@
+//! Data migrations: resumable Rust steps that rewrite stored data.
+//!
+//! The SQL files in `migrations/` change the schema. Some changes also need
+//! existing rows rewritten with logic that lives in Rust, such as deriving
+//! stable IDs or recomputing a derived column after a formula fix. Those run
+//! as [`DataMigration`]s:
+//!
+//! - Each migration has a unique name and runs in batches. A batch returns
+//!   the rows it changed and a checkpoint (migration-defined, e.g. the last
+//!   row ID) for the next one, or `None` once it is done.
+//! - [`DataMigrator`] records the checkpoint in the `migrations_log` table
+//!   after every batch, so a run interrupted by a crash or shutdown resumes
+//!   from the last recorded batch. Completed migrations never run again.
+//! - The batch and its log entry are separate writes, so a batch may be
+//!   repeated after a crash between them. Batches must be idempotent.
+//!
+//! [`builtin`] lists the tracker's own migrations, which `watch` runs at
+//! startup and `db migrate` runs on demand. New migrations are appended to
+//! it with a new name; the names of shipped ones must never change.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use eth_uniswap_alloy::db::{create_pool, data_migrations, repository::Repository};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let repo = Repository::new(create_pool("sqlite:./indexer.db").await?);
+//! let outcomes = data_migrations::DataMigrator::new(&repo)
+//!     .with_migrations(data_migrations::builtin(1))
+//!     .run()
+//!     .await?;
+//! println!("{} data migrations ran", outcomes.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tracing::info;
+
+use super::models::DataMigrationRow;
+use super::repository::Repository;
+use crate::error::TrackerResult;
+
+/// Rows a batched built-in migration reads per batch.
+pub const DATA_MIGRATION_BATCH_ROWS: i64 = 5_000;
+
+/// Result of one batch of a data migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationBatch {
+    /// Rows the batch changed
+    pub rows: u64,
+    /// Where the next batch starts (`None` = the migration is complete)
+    pub checkpoint: Option<String>,
+}
+
+impl MigrationBatch {
+    /// The final batch of a migration.
+    #[must_use]
+    pub const fn done(rows: u64) -> Self {
+        Self {
+            rows,
+            checkpoint: None,
+        }
+    }
+
+    /// A batch with more to do, continuing at `checkpoint`.
+    #[must_use]
+    pub const fn more(rows: u64, checkpoint: String) -> Self {
+        Self {
+            rows,
+            checkpoint: Some(checkpoint),
+        }
+    }
+}
+
+/// A named, resumable rewrite of stored data.
+#[async_trait]
+pub trait DataMigration: Send + Sync {
+    /// Unique name recorded in `migrations_log`. Never rename a shipped
+    /// migration, or it runs again.
+    fn name(&self) -> &'static str;
+
+    /// One-line description for logs and `db migrate`.
+    fn description(&self) -> &'static str;
+
+    /// Runs one batch, starting at `checkpoint` (`None` = the first batch).
+    ///
+    /// Must be idempotent: the same batch may run twice after a crash.
+    async fn run_batch(
+        &self,
+        repository: &Repository,
+        checkpoint: Option<&str>,
+    ) -> TrackerResult<MigrationBatch>;
+}
+
+/// Where a data migration stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationProgress {
+    /// Never started
+    Pending,
+    /// Interrupted after some batches
+    InProgress {
+        /// Where the next batch starts
+        checkpoint: Option<String>,
+        /// Rows changed so far
+        rows_done: u64,
+    },
+    /// Finished
+    Completed {
+        /// Rows changed in total
+        rows_done: u64,
+        /// Unix timestamp of completion
+        completed_at: i64,
+    },
+}
+
+impl From<&DataMigrationRow> for MigrationProgress {
+    fn from(row: &DataMigrationRow) -> Self {
+        let rows_done = u64::try_from(row.rows_done).unwrap_or(0);
+        row.completed_at.map_or_else(
+            || Self::InProgress {
+                checkpoint: row.checkpoint.clone(),
+                rows_done,
+            },
+            |completed_at| Self::Completed {
+                rows_done,
+                completed_at,
+            },
+        )
+    }
+}
+
+/// Status of one registered migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Migration name
+    pub name: &'static str,
+    /// What the migration does
+    pub description: &'static str,
+    /// Where it stands
+    pub progress: MigrationProgress,
+}
+
+/// Outcome of a migration that ran to completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationOutcome {
+    /// Migration name
+    pub name: &'static str,
+    /// Rows changed in this run
+    pub rows: u64,
+    /// Batches run in this run
+    pub batches: u64,
+    /// Whether the run continued an interrupted one
+    pub resumed: bool,
+}
+
+/// Runs registered data migrations in order, recording their progress.
+pub struct DataMigrator<'a> {
+    repository: &'a Repository,
+    migrations: Vec<Box<dyn DataMigration>>,
+}
+
+impl<'a> DataMigrator<'a> {
+    /// Creates a migrator with no migrations registered.
+    #[must_use]
+    pub fn new(repository: &'a Repository) -> Self {
+        Self {
+            repository,
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Registers migrations, run after the ones already registered.
+    #[must_use]
+    pub fn with_migrations(mut self, migrations: Vec<Box<dyn DataMigration>>) -> Self {
+        self.migrations.extend(migrations);
+        self
+    }
+
+    /// Reports where each registered migration stands, in run order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `migrations_log` cannot be read.
+    pub async fn status(&self) -> TrackerResult<Vec<MigrationStatus>> {
+        let log = self.log().await?;
+        Ok(self
+            .migrations
+            .iter()
+            .map(|migration| MigrationStatus {
+                name: migration.name(),
+                description: migration.description(),
+                progress: log
+                    .get(migration.name())
+                    .map_or(MigrationProgress::Pending, MigrationProgress::from),
+            })
+            .collect())
+    }
+
+    /// Runs every migration that hasn't completed, resuming interrupted
+    /// ones from their checkpoint.
+    ///
+    /// Returns the migrations that ran, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failing batch's error. Batches recorded before it
+    /// are kept, and later migrations don't run.
+    pub async fn run(&self) -> TrackerResult<Vec<MigrationOutcome>> {
+        let log = self.log().await?;
+        let mut outcomes = Vec::new();
+
+        for migration in &self.migrations {
+            let name = migration.name();
+            let previous = log.get(name);
+            if previous.is_some_and(|row| row.completed_at.is_some()) {
+                continue;
+            }
+
+            let mut checkpoint = previous.and_then(|row| row.checkpoint.clone());
+            let mut outcome = MigrationOutcome {
+                name,
+                rows: 0,
+                batches: 0,
+                resumed: previous.is_some(),
+            };
+            info!(
+                migration = name,
+                checkpoint = checkpoint.as_deref(),
+                "Running data migration"
+            );
+
+            loop {
+                let batch = migration
+                    .run_batch(self.repository, checkpoint.as_deref())
+                    .await?;
+                let completed = batch.checkpoint.is_none();
+                self.repository
+                    .record_data_migration_batch(
+                        name,
+                        migration.description(),
+                        batch.checkpoint.as_deref(),
+                        batch.rows,
+                        completed,
+                    )
+                    .await?;
+                outcome.rows += batch.rows;
+                outcome.batches += 1;
+                if completed {
+                    break;
+                }
+                checkpoint = batch.checkpoint;
+            }
+
+            info!(
+                migration = name,
+                rows = outcome.rows,
+                batches = outcome.batches,
+                "Data migration complete"
+            );
+            outcomes.push(outcome);
+        }
+
+        Ok(outcomes)
+    }
+
+    async fn log(&self) -> TrackerResult<HashMap<String, DataMigrationRow>> {
+        Ok(self
+            .repository
+            .get_data_migrations()
+            .await?
+            .into_iter()
+            .map(|row| (row.name.clone(), row))
+            .collect())
+    }
+}
+
+/// The tracker's own data migrations, in run order.
+#[must_use]
+pub fn builtin(chain_id: u64) -> Vec<Box<dyn DataMigration>> {
+    vec![
+        Box::new(EventIds { chain_id }),
+        Box::new(ExactPrices),
+        Box::new(ReserveHuman),
+    ]
+}
+
+/// Gives rows indexed before deterministic IDs existed their stable IDs.
+struct EventIds {
+    chain_id: u64,
+}
+
+#[async_trait]
+impl DataMigration for EventIds {
+    fn name(&self) -> &'static str {
+        "0001_event_ids"
+    }
+
+    fn description(&self) -> &'static str {
+        "Backfill deterministic event IDs of sync events and price points"
+    }
+
+    async fn run_batch(
+        &self,
+        repository: &Repository,
+        _checkpoint: Option<&str>,
+    ) -> TrackerResult<MigrationBatch> {
+        // Only touches rows without an ID, so one batch is safe to repeat
+        let rows = repository.backfill_event_ids(self.chain_id).await?;
+        Ok(MigrationBatch::done(rows))
+    }
+}
+
+/// Gives price points indexed before exact prices existed their exact price.
+struct ExactPrices;
+
+#[async_trait]
+impl DataMigration for ExactPrices {
+    fn name(&self) -> &'static str {
+        "0002_exact_prices"
+    }
+
+    fn description(&self) -> &'static str {
+        "Backfill exact prices of price points"
+    }
+
+    async fn run_batch(
+        &self,
+        repository: &Repository,
+        _checkpoint: Option<&str>,
+    ) -> TrackerResult<MigrationBatch> {
+        let rows = repository.backfill_exact_prices().await?;
+        Ok(MigrationBatch::done(rows))
+    }
+}
+
+/// Recomputes human-readable reserves from the raw reserves, in batches of
+/// [`DATA_MIGRATION_BATCH_ROWS`] price points. The checkpoint is the last
+/// price point ID read.
+struct ReserveHuman;
+
+#[async_trait]
+impl DataMigration for ReserveHuman {
+    fn name(&self) -> &'static str {
+        "0003_reserve_human"
+    }
+
+    fn description(&self) -> &'static str {
+        "Recompute human-readable reserves of price points from raw reserves"
+    }
+
+    async fn run_batch(
+        &self,
+        repository: &Repository,
+        checkpoint: Option<&str>,
+    ) -> TrackerResult<MigrationBatch> {
+        let after_id = checkpoint.and_then(|c| c.parse().ok()).unwrap_or(0);
+        let (rows, last_id) = repository
+            .recompute_reserve_human(after_id, DATA_MIGRATION_BATCH_ROWS)
+            .await?;
+        Ok(last_id.map_or_else(
+            || MigrationBatch::done(rows),
+            |id| MigrationBatch::more(rows, id.to_string()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations};
+    use crate::error::TrackerError;
+    use alloy::primitives::{FixedBytes, U256};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    async fn setup() -> Repository {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        Repository::new(pool)
+    }
+
+    /// Counts to `end` one batch at a time, failing once at `fail_at`.
+    struct Counter {
+        end: u64,
+        fail_at: Option<u64>,
+        calls: Arc<AtomicU64>,
+    }
+
+    #[async_trait]
+    impl DataMigration for Counter {
+        fn name(&self) -> &'static str {
+            "test_counter"
+        }
+
+        fn description(&self) -> &'static str {
+            "Count batches"
+        }
+
+        async fn run_batch(
+            &self,
+            _repository: &Repository,
+            checkpoint: Option<&str>,
+        ) -> TrackerResult<MigrationBatch> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let next = checkpoint.map_or(0, |c| c.parse::<u64>().unwrap()) + 1;
+            if self.fail_at == Some(next) {
+                return Err(TrackerError::database("interrupted", None));
+            }
+            Ok(if next == self.end {
+                MigrationBatch::done(1)
+            } else {
+                MigrationBatch::more(1, next.to_string())
+            })
+        }
+    }
+
+    fn counter(fail_at: Option<u64>, calls: &Arc<AtomicU64>) -> Vec<Box<dyn DataMigration>> {
+        vec![Box::new(Counter {
+            end: 4,
+            fail_at,
+            calls: Arc::clone(calls),
+        })]
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_migration_resumes_from_checkpoint() {
+        let repo = setup().await;
+        let calls = Arc::new(AtomicU64::new(0));
+
+        let migrator = DataMigrator::new(&repo).with_migrations(counter(Some(3), &calls));
+        assert_eq!(
+            migrator.status().await.unwrap()[0].progress,
+            MigrationProgress::Pending
+        );
+        assert!(migrator.run().await.is_err());
+        assert_eq!(
+            migrator.status().await.unwrap()[0].progress,
+            MigrationProgress::InProgress {
+                checkpoint: Some("2".to_string()),
+                rows_done: 2
+            }
+        );
+
+        // Batches 3 and 4 run; 1 and 2 are not repeated
+        calls.store(0, Ordering::SeqCst);
+        let migrator = DataMigrator::new(&repo).with_migrations(counter(None, &calls));
+        let outcomes = migrator.run().await.unwrap();
+        assert_eq!(
+            outcomes,
+            vec![MigrationOutcome {
+                name: "test_counter",
+                rows: 2,
+                batches: 2,
+                resumed: true
+            }]
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(matches!(
+            migrator.status().await.unwrap()[0].progress,
+            MigrationProgress::Completed { rows_done: 4, .. }
+        ));
+
+        // Completed migrations never run again
+        assert!(migrator.run().await.unwrap().is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_builtin_migrations() {
+        let repo = setup().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // 1000 WETH and 2,000,000 USDT, stored with wrong human-readable values
+        let reserve0 = U256::from(10_u64).pow(U256::from(21));
+        let reserve1 = U256::from(2_000_000_000_000_u64);
+        for block in [100_u64, 101] {
+            repo.insert_price_point(
+                pool_id,
+                block,
+                1_706_745_600,
+                FixedBytes::from([u8::try_from(block % 256).unwrap(); 32]),
+                2_000.0,
+                reserve0,
+                reserve1,
+                1.0,
+                2.0,
+                true,
+                &format!("price-{block}"),
+            )
+            .await
+            .unwrap();
+        }
+
+        let migrator = DataMigrator::new(&repo).with_migrations(builtin(1));
+        let outcomes = migrator.run().await.unwrap();
+        let names: Vec<_> = outcomes.iter().map(|o| o.name).collect();
+        assert_eq!(
+            names,
+            vec!["0001_event_ids", "0002_exact_prices", "0003_reserve_human"]
+        );
+        assert_eq!(outcomes[2].rows, 2);
+
+        let latest = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+        assert!((latest.reserve0_human - 1_000.0).abs() < 1e-9);
+        assert!((latest.reserve1_human - 2_000_000.0).abs() < 1e-9);
+
+        assert!(migrator.run().await.unwrap().is_empty());
+    }
+}
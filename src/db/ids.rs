@@ -0,0 +1,147 @@
+//! Deterministic identifiers for indexed records.
+//!
+//! Autoincrement row IDs change whenever the database is rebuilt, which makes
+//! them useless to downstream consumers. Instead, every sync event and price
+//! point gets an ID derived from its on-chain coordinates:
+//!
+//! ```text
+//! keccak256(kind || chain_id || pool || block_hash || tx_hash || log_index)
+//! ```
+//!
+//! The first 16 bytes of the digest are formatted as an RFC 9562 `UUIDv8`, so
+//! re-indexing the same log on the same chain always yields the same ID, while
+//! the `kind` prefix keeps event and price point IDs from colliding.
+
+use alloy::primitives::{keccak256, Address, FixedBytes};
+
+/// The kind of record an ID is derived for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    /// A raw `Sync` event row
+    SyncEvent,
+    /// A computed price point row
+    PricePoint,
+}
+
+impl RecordKind {
+    /// Domain separation tag mixed into the hash.
+    const fn tag(self) -> &'static [u8] {
+        match self {
+            Self::SyncEvent => b"sync_event",
+            Self::PricePoint => b"price_point",
+        }
+    }
+}
+
+/// Derives a stable UUIDv8-formatted ID from a record's on-chain coordinates.
+///
+/// # Example
+///
+/// ```
+/// use alloy::primitives::{Address, FixedBytes};
+/// use eth_uniswap_alloy::db::ids::{derive_record_id, RecordKind};
+///
+/// let id = derive_record_id(
+///     RecordKind::SyncEvent,
+///     1,
+///     Address::ZERO,
+///     FixedBytes::from([1u8; 32]),
+///     FixedBytes::from([2u8; 32]),
+///     0,
+/// );
+/// assert_eq!(id.len(), 36);
+/// ```
+#[must_use]
+pub fn derive_record_id(
+    kind: RecordKind,
+    chain_id: u64,
+    pool_address: Address,
+    block_hash: FixedBytes<32>,
+    tx_hash: FixedBytes<32>,
+    log_index: u32,
+) -> String {
+    let tag = kind.tag();
+    let mut preimage = Vec::with_capacity(tag.len() + 8 + 20 + 32 + 32 + 4);
+    preimage.extend_from_slice(tag);
+    preimage.extend_from_slice(&chain_id.to_be_bytes());
+    preimage.extend_from_slice(pool_address.as_slice());
+    preimage.extend_from_slice(block_hash.as_slice());
+    preimage.extend_from_slice(tx_hash.as_slice());
+    preimage.extend_from_slice(&log_index.to_be_bytes());
+
+    let digest = keccak256(&preimage);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+
+    // Version 8 (custom) and RFC 9562 variant bits
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    format_uuid(&bytes)
+}
+
+/// Formats 16 bytes in the canonical 8-4-4-4-12 UUID layout.
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex = alloy::hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(kind: RecordKind, log_index: u32) -> String {
+        derive_record_id(
+            kind,
+            1,
+            "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"
+                .parse()
+                .unwrap(),
+            FixedBytes::from([1u8; 32]),
+            FixedBytes::from([2u8; 32]),
+            log_index,
+        )
+    }
+
+    #[test]
+    fn test_id_is_deterministic() {
+        assert_eq!(
+            sample(RecordKind::SyncEvent, 7),
+            sample(RecordKind::SyncEvent, 7)
+        );
+    }
+
+    #[test]
+    fn test_id_depends_on_inputs() {
+        assert_ne!(
+            sample(RecordKind::SyncEvent, 7),
+            sample(RecordKind::SyncEvent, 8)
+        );
+        assert_ne!(
+            sample(RecordKind::SyncEvent, 7),
+            sample(RecordKind::PricePoint, 7)
+        );
+    }
+
+    #[test]
+    fn test_id_is_uuid_v8() {
+        let id = sample(RecordKind::PricePoint, 0);
+        let parts: Vec<&str> = id.split('-').collect();
+        assert_eq!(
+            parts.iter().map(|p| p.len()).collect::<Vec<_>>(),
+            vec![8, 4, 4, 4, 12]
+        );
+        assert!(parts[2].starts_with('8'));
+        assert!(matches!(
+            parts[3].chars().next(),
+            Some('8' | '9' | 'a' | 'b')
+        ));
+    }
+}
@@ -3,30 +3,64 @@
 //! This module provides SQLite-based storage for:
 //! - Raw sync events from the blockchain (audit trail)
 //! - Computed price points (fast queries)
-//! - Indexer state (replaces state.json)
+//! - Indexer state (replaces state.json; see [`checkpoint`])
 //!
 //! # Architecture
 //!
+//! - `archive`: Compressed segments of old sync events, read back
+//!   transparently by the repository
+//! - `checkpoint`: The [`checkpoint::CheckpointStore`] trait holding each
+//!   pool's resume point, with SQLite and JSON file stores
+//! - `data_migrations`: Resumable Rust data migrations, with progress recorded
+//!   in `migrations_log`
+//! - `ids`: Deterministic record IDs that survive re-indexing
 //! - `models`: Data structures that map to database tables
 //! - `repository`: CRUD operations and business logic
-//! - Connection pooling with SQLite WAL mode for concurrency
-//! - Migration system for schema versioning
+//! - `snapshot`: Compressed, checksummed snapshots for moving a database
+//!   between machines
+//! - `storage`: The [`storage::Storage`] trait the indexer writes through, for
+//!   embedders bringing their own database
+//! - `types`: Validated [`types::PoolAddress`] and [`types::TxHash`] columns,
+//!   always stored as lowercase hex
+//! - Connection pooling with SQLite WAL mode for concurrency, plus a
+//!   separate read-only pool so API queries never compete with the
+//!   indexer's writes for connections
+//! - Migration system for schema versioning, with optional pre-migration
+//!   backups and a dry-run listing of pending DDL
 
+use sqlx::migrate::Migrator;
 use sqlx::{
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
     SqlitePool,
 };
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 use tracing::info;
 
 use crate::error::TrackerError;
 
+pub mod archive;
+pub mod checkpoint;
+pub mod data_migrations;
+pub mod ids;
 pub mod models;
 pub mod repository;
+pub mod snapshot;
+pub mod storage;
+pub mod types;
+
+/// Connections in a read-only pool (see [`connect_read_only`]).
+pub const READ_POOL_CONNECTIONS: u32 = 8;
+
+/// Embedded schema migrations from `migrations/`.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 /// Creates a SQLite connection pool with optimized settings.
 ///
+/// Pending migrations are applied before the pool is returned.
+///
 /// # Configuration
 ///
 /// - **WAL mode**: Enables concurrent readers during writes
@@ -47,6 +81,52 @@ pub mod repository;
 /// }
 /// ```
 pub async fn create_pool(database_url: &str) -> Result<SqlitePool, TrackerError> {
+    create_pool_with_backup(database_url, None).await
+}
+
+/// Creates a connection pool, backing up the database first if any
+/// migrations are pending.
+///
+/// With `backup_dir` set, a consistent copy of the database is written there
+/// (see [`backup_database`]) before the first pending migration runs, so a bad
+/// migration can be rolled back by restoring the copy. Nothing is written
+/// when the schema is already current.
+///
+/// # Errors
+///
+/// Returns an error if connecting, the backup, or any migration fails. A
+/// failed backup aborts startup before the schema is touched.
+pub async fn create_pool_with_backup(
+    database_url: &str,
+    backup_dir: Option<&Path>,
+) -> Result<SqlitePool, TrackerError> {
+    let pool = connect(database_url).await?;
+
+    if let Some(dir) = backup_dir {
+        let pending = pending_migrations(&pool).await?;
+        if let Some(target) = pending.last() {
+            backup_database(&pool, dir, target.version).await?;
+        }
+    }
+
+    info!("Running database migrations");
+    run_migrations(&pool).await?;
+    verify_database(&pool).await?;
+    info!("Database migrations complete");
+
+    Ok(pool)
+}
+
+/// Opens a connection pool without touching the schema, creating the
+/// database file if it doesn't exist.
+///
+/// Used by [`create_pool`] and by commands that manage the schema
+/// themselves.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid or the database cannot be opened.
+pub async fn connect(database_url: &str) -> Result<SqlitePool, TrackerError> {
     info!(database_url, "Connecting to database");
 
     let options = SqliteConnectOptions::from_str(database_url)
@@ -85,14 +165,71 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool, TrackerError>
             )
         })?;
 
-    info!("Running database migrations");
-    run_migrations(&pool).await?;
-    verify_database(&pool).await?;
-    info!("Database migrations complete");
-
     Ok(pool)
 }
 
+/// Opens a read-only connection pool on the database file at `path`.
+///
+/// Connections are opened read-only and with `PRAGMA query_only`, so they
+/// can never take the write lock. In WAL mode they read the last committed
+/// state while a writer holds its transaction open.
+///
+/// # Errors
+///
+/// Returns an error if the database cannot be opened.
+pub async fn connect_read_only(
+    path: &Path,
+    max_connections: u32,
+) -> Result<SqlitePool, TrackerError> {
+    info!(path = %path.display(), max_connections, "Opening read-only database pool");
+
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .pragma("query_only", "ON")
+        .busy_timeout(Duration::from_secs(30));
+
+    SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .min_connections(1)
+        .acquire_timeout(Duration::from_secs(5))
+        .connect_with(options)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                format!("Failed to open {} read-only", path.display()),
+                Some(Box::new(e)),
+            )
+        })
+}
+
+/// Opens an existing database read-only, by URL.
+///
+/// Used by `--migrate-dry-run`, which must inspect pending migrations
+/// without creating, migrating or otherwise writing the database.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid, the database file does not exist
+/// or it cannot be opened.
+pub async fn connect_existing(database_url: &str) -> Result<SqlitePool, TrackerError> {
+    let options = SqliteConnectOptions::from_str(database_url).map_err(|e| {
+        TrackerError::database(
+            format!("Failed to parse database URL: {database_url}"),
+            Some(Box::new(e)),
+        )
+    })?;
+    let path = options.get_filename();
+    if !path.is_file() {
+        return Err(TrackerError::database(
+            format!("Database file {} does not exist", path.display()),
+            None,
+        ));
+    }
+
+    connect_read_only(path, 1).await
+}
+
 /// Runs database migrations to ensure schema is up-to-date.
 ///
 /// This function applies all pending migrations from the `migrations/` directory.
@@ -111,17 +248,132 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool, TrackerError>
 /// }
 /// ```
 pub async fn run_migrations(pool: &SqlitePool) -> Result<(), TrackerError> {
-    sqlx::migrate!("./migrations")
-        .run(pool)
+    MIGRATOR.run(pool).await.map_err(|e| {
+        TrackerError::database(
+            "Failed to run database migrations".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    Ok(())
+}
+
+/// A migration that has not been applied yet.
+#[derive(Debug, Clone)]
+pub struct PendingMigration {
+    /// Migration version (timestamp prefix of the file name)
+    pub version: i64,
+    /// Human-readable description from the file name
+    pub description: String,
+    /// DDL that will run
+    pub sql: String,
+}
+
+/// Lists migrations that [`run_migrations`] would apply, in order.
+///
+/// # Errors
+///
+/// Returns an error if the migration history cannot be read.
+pub async fn pending_migrations(pool: &SqlitePool) -> Result<Vec<PendingMigration>, TrackerError> {
+    let (has_history,) = sqlx::query_as::<_, (bool,)>(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        TrackerError::database(
+            "Failed to check migration history".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    let applied: HashSet<i64> = if has_history {
+        sqlx::query_as::<_, (i64,)>("SELECT version FROM _sqlx_migrations WHERE success = 1")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to read migration history".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?
+            .into_iter()
+            .map(|(version,)| version)
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+        .map(|m| PendingMigration {
+            version: m.version,
+            description: m.description.to_string(),
+            sql: m.sql.to_string(),
+        })
+        .collect())
+}
+
+/// Writes a consistent copy of the database into `dir` using `VACUUM INTO`.
+///
+/// The file is named `<db>-<UTC timestamp>-pre-<version>.db`, where `version`
+/// is the migration the backup guards. In-memory databases are skipped.
+///
+/// Returns the backup path, or `None` if nothing was written.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or the copy fails.
+pub async fn backup_database(
+    pool: &SqlitePool,
+    dir: &Path,
+    version: i64,
+) -> Result<Option<PathBuf>, TrackerError> {
+    let (file,) =
+        sqlx::query_as::<_, (String,)>("SELECT file FROM pragma_database_list WHERE name = 'main'")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to locate database file".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+
+    if file.is_empty() {
+        info!("In-memory database, skipping pre-migration backup");
+        return Ok(None);
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| {
+        TrackerError::database(
+            format!("Failed to create backup directory {}", dir.display()),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    let stem = Path::new(&file)
+        .file_stem()
+        .map_or_else(|| "database".into(), |s| s.to_string_lossy());
+    let path = dir.join(format!(
+        "{stem}-{}-pre-{version}.db",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().as_ref())
+        .execute(pool)
         .await
         .map_err(|e| {
             TrackerError::database(
-                "Failed to run database migrations".to_string(),
+                format!("Failed to back up database to {}", path.display()),
                 Some(Box::new(e)),
             )
         })?;
 
-    Ok(())
+    info!(path = %path.display(), "Backed up database before migrating");
+    Ok(Some(path))
 }
 
 /// Verify that required tables exist after migrations.
@@ -218,4 +470,53 @@ mod tests {
 
         assert_eq!(result.0, 1, "Foreign keys should be enabled");
     }
+
+    #[tokio::test]
+    async fn test_pending_migrations() {
+        let pool = connect("sqlite::memory:").await.expect("Failed to connect");
+
+        let pending = pending_migrations(&pool).await.unwrap();
+        assert_eq!(pending.len(), MIGRATOR.iter().count());
+        assert!(pending[0].sql.contains("CREATE TABLE pools"));
+
+        run_migrations(&pool).await.unwrap();
+        assert!(pending_migrations(&pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_connect_existing_neither_creates_nor_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("indexer.db");
+        let url = format!("sqlite:{}", path.display());
+
+        assert!(connect_existing(&url).await.is_err());
+        assert!(!path.exists());
+
+        connect(&url).await.unwrap().close().await;
+        let pool = connect_existing(&url).await.unwrap();
+        assert_eq!(
+            pending_migrations(&pool).await.unwrap().len(),
+            MIGRATOR.iter().count()
+        );
+        assert!(run_migrations(&pool).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backup_only_when_migrations_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let url = format!("sqlite:{}", dir.path().join("indexer.db").display());
+
+        let pool = create_pool_with_backup(&url, Some(&backups))
+            .await
+            .expect("Failed to create pool");
+        pool.close().await;
+        assert_eq!(std::fs::read_dir(&backups).unwrap().count(), 1);
+
+        // Schema is current, so a restart takes no further backup
+        create_pool_with_backup(&url, Some(&backups))
+            .await
+            .expect("Failed to reopen pool");
+        assert_eq!(std::fs::read_dir(&backups).unwrap().count(), 1);
+    }
 }
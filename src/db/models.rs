@@ -5,6 +5,14 @@
 
 use alloy::primitives::{Address, FixedBytes, U256};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use super::types::{PoolAddress, TxHash};
+use crate::adapters::{PoolType, PriceAdapter};
+use crate::error::TrackerResult;
+use crate::pricing::QuoteDirection;
+use crate::protocol::DexProtocol;
+use crate::scheduler::TaskStatus;
 
 /// Represents a Uniswap V2 pool in the database.
 ///
@@ -14,8 +22,8 @@ use serde::{Deserialize, Serialize};
 pub struct PoolRecord {
     /// Database-assigned unique identifier
     pub id: i64,
-    /// Pool contract address (hex string with 0x prefix)
-    pub address: String,
+    /// Pool contract address
+    pub address: PoolAddress,
     /// Optional human-readable name (e.g., "USDC-WETH")
     pub name: Option<String>,
     /// Token0 contract address (hex string with 0x prefix)
@@ -30,8 +38,21 @@ pub struct PoolRecord {
     pub token1_symbol: Option<String>,
     /// Token1 decimal places (e.g., 18 for WETH)
     pub token1_decimals: i32,
+    /// Protocol that deployed the pool (see [`DexProtocol`])
+    pub protocol: String,
+    /// Pricing formula of the pool (see [`PoolType`])
+    pub pool_type: String,
+    /// Direction prices are reported in (see [`QuoteDirection`])
+    pub quote_direction: String,
     /// Unix timestamp when record was created
     pub created_at: i64,
+    /// Whether the pool is indexed (disabled pools are skipped)
+    pub enabled: bool,
+    /// First block to index when the pool has no checkpoint
+    pub start_block: Option<i64>,
+    /// Deviation in basis points from which a reverted single-block spike is
+    /// left out of candles and stats (`None` keeps every price)
+    pub spike_filter_bps: Option<i64>,
 }
 
 impl PoolRecord {
@@ -66,7 +87,7 @@ impl PoolRecord {
     ) -> Self {
         Self {
             id: 0, // Will be set by database
-            address: format!("{:?}", address),
+            address: address.into(),
             name,
             token0_address: format!("{:?}", token0_address),
             token0_symbol,
@@ -74,9 +95,52 @@ impl PoolRecord {
             token1_address: format!("{:?}", token1_address),
             token1_symbol,
             token1_decimals: token1_decimals as i32,
+            protocol: DexProtocol::default().as_str().to_string(),
+            pool_type: PoolType::default().to_string(),
+            quote_direction: QuoteDirection::default().to_string(),
             created_at: chrono::Utc::now().timestamp(),
+            enabled: true,
+            start_block: None,
+            spike_filter_bps: None,
         }
     }
+
+    /// Returns the block indexing starts at when the pool has no checkpoint.
+    #[must_use]
+    pub fn start_block(&self) -> Option<u64> {
+        self.start_block.and_then(|block| u64::try_from(block).ok())
+    }
+
+    /// Returns the pool's flash spike threshold in basis points, if spikes
+    /// are filtered (see [`crate::spikes`]).
+    #[must_use]
+    pub fn spike_filter(&self) -> Option<u32> {
+        self.spike_filter_bps
+            .and_then(|bps| u32::try_from(bps).ok())
+    }
+
+    /// Returns the pool's protocol; unknown names fall back to Uniswap V2.
+    #[must_use]
+    pub fn dex_protocol(&self) -> DexProtocol {
+        self.protocol.parse().unwrap_or_default()
+    }
+
+    /// Returns the pool's quote direction; unknown names fall back to the
+    /// stored direction.
+    #[must_use]
+    pub fn quote_direction(&self) -> QuoteDirection {
+        self.quote_direction.parse().unwrap_or_default()
+    }
+
+    /// Returns the adapter that prices the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored pool type is invalid.
+    pub fn price_adapter(&self) -> TrackerResult<Box<dyn PriceAdapter>> {
+        let pool_type: PoolType = self.pool_type.parse()?;
+        Ok(pool_type.adapter(self.dex_protocol().fee_bps()))
+    }
 }
 
 /// Represents a raw sync event from the blockchain.
@@ -87,6 +151,8 @@ impl PoolRecord {
 pub struct SyncEventRecord {
     /// Database-assigned unique identifier
     pub id: i64,
+    /// Deterministic ID derived from on-chain coordinates (see [`super::ids`])
+    pub event_id: Option<String>,
     /// Foreign key to pools table
     pub pool_id: i64,
     /// Block number where event occurred
@@ -96,7 +162,7 @@ pub struct SyncEventRecord {
     /// Unix timestamp of the block
     pub block_timestamp: i64,
     /// Transaction hash (hex string with 0x prefix)
-    pub tx_hash: String,
+    pub tx_hash: TxHash,
     /// Log index within the transaction
     pub log_index: i32,
     /// Reserve amount of token0 (stored as TEXT for U256 precision)
@@ -155,11 +221,12 @@ impl SyncEventRecord {
     ) -> Self {
         Self {
             id: 0, // Will be set by database
+            event_id: None,
             pool_id,
             block_number: block_number as i64,
             block_hash: format!("{:?}", block_hash),
             block_timestamp: block_timestamp as i64,
-            tx_hash: format!("{:?}", tx_hash),
+            tx_hash: tx_hash.into(),
             log_index: log_index as i32,
             reserve0: reserve0.to_string(),
             reserve1: reserve1.to_string(),
@@ -168,6 +235,13 @@ impl SyncEventRecord {
         }
     }
 
+    /// Attaches the deterministic event ID.
+    #[must_use]
+    pub fn with_event_id(mut self, event_id: impl Into<String>) -> Self {
+        self.event_id = Some(event_id.into());
+        self
+    }
+
     /// Converts reserve0 TEXT back to U256.
     pub fn reserve0_u256(&self) -> Result<U256, crate::error::TrackerError> {
         U256::from_str_radix(&self.reserve0, 10).map_err(|e| {
@@ -189,6 +263,83 @@ impl SyncEventRecord {
     }
 }
 
+/// Represents a raw swap event from the blockchain.
+///
+/// Maps to the `swap_events` table. Feeds the trader analytics; the
+/// recipient (`to`) is treated as the trader.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SwapEventRecord {
+    /// Database-assigned unique identifier
+    pub id: i64,
+    /// Foreign key to pools table
+    pub pool_id: i64,
+    /// Block number where event occurred
+    pub block_number: i64,
+    /// Unix timestamp of the block
+    pub block_timestamp: i64,
+    /// Transaction hash (hex string with 0x prefix)
+    pub tx_hash: TxHash,
+    /// Log index within the transaction
+    pub log_index: i32,
+    /// Address that called `swap` (hex string with 0x prefix)
+    pub sender: String,
+    /// Recipient of the output tokens (hex string with 0x prefix)
+    pub recipient: String,
+    /// Raw token0 paid into the pair (TEXT for U256 precision)
+    pub amount0_in: String,
+    /// Raw token1 paid into the pair (TEXT for U256 precision)
+    pub amount1_in: String,
+    /// Raw token0 paid out of the pair (TEXT for U256 precision)
+    pub amount0_out: String,
+    /// Raw token1 paid out of the pair (TEXT for U256 precision)
+    pub amount1_out: String,
+    /// Whether this event is from a finalized block
+    pub is_confirmed: bool,
+    /// Unix timestamp when record was created
+    pub created_at: i64,
+}
+
+impl SwapEventRecord {
+    /// Creates a swap event record from a decoded Swap event.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool_id` - Database ID of the pool
+    /// * `block_number` - Block number where event occurred
+    /// * `block_timestamp` - Unix timestamp of the block
+    /// * `tx_hash` - Transaction hash
+    /// * `log_index` - Index of the log in the transaction
+    /// * `swap` - The decoded event
+    /// * `is_confirmed` - Whether the block is considered final
+    #[must_use]
+    pub fn new(
+        pool_id: i64,
+        block_number: u64,
+        block_timestamp: u64,
+        tx_hash: FixedBytes<32>,
+        log_index: u32,
+        swap: &crate::events::Swap,
+        is_confirmed: bool,
+    ) -> Self {
+        Self {
+            id: 0, // Will be set by database
+            pool_id,
+            block_number: i64::try_from(block_number).unwrap_or(i64::MAX),
+            block_timestamp: i64::try_from(block_timestamp).unwrap_or(i64::MAX),
+            tx_hash: tx_hash.into(),
+            log_index: i32::try_from(log_index).unwrap_or(i32::MAX),
+            sender: format!("{:?}", swap.sender),
+            recipient: format!("{:?}", swap.to),
+            amount0_in: swap.amount0In.to_string(),
+            amount1_in: swap.amount1In.to_string(),
+            amount0_out: swap.amount0Out.to_string(),
+            amount1_out: swap.amount1Out.to_string(),
+            is_confirmed,
+            created_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
 /// Represents a computed price point.
 ///
 /// Maps to the `price_points` table. Stores human-readable
@@ -197,6 +348,8 @@ impl SyncEventRecord {
 pub struct PricePointRecord {
     /// Database-assigned unique identifier
     pub id: i64,
+    /// Deterministic ID derived from on-chain coordinates (see [`super::ids`])
+    pub event_id: Option<String>,
     /// Foreign key to pools table
     pub pool_id: i64,
     /// Block number where price was observed
@@ -204,9 +357,15 @@ pub struct PricePointRecord {
     /// Unix timestamp of the block
     pub block_timestamp: i64,
     /// Transaction hash (hex string with 0x prefix)
-    pub tx_hash: String,
-    /// Computed price (token1 per token0)
+    pub tx_hash: TxHash,
+    /// Computed price (token1 per token0), for display
     pub price: f64,
+    /// Exact price as a decimal string (`None` for rows not yet backfilled)
+    pub price_exact: Option<String>,
+    /// EWMA-smoothed price (`None` when smoothing is disabled)
+    pub price_ewma: Option<f64>,
+    /// Where the reserves came from ([`PRICE_SOURCE_EVENT`] or [`PRICE_SOURCE_CALL`])
+    pub source: String,
     /// Raw reserve of token0 (TEXT for U256 precision)
     pub reserve0_raw: String,
     /// Raw reserve of token1 (TEXT for U256 precision)
@@ -221,17 +380,32 @@ pub struct PricePointRecord {
     pub created_at: i64,
 }
 
+/// `price_points.source` of prices computed from a Sync event.
+pub const PRICE_SOURCE_EVENT: &str = "event";
+
+/// `price_points.source` of prices read with `getReserves()` while the pool
+/// had no Sync events (see [`crate::pipeline::Pipeline::record_snapshot`]).
+pub const PRICE_SOURCE_CALL: &str = "call";
+
 /// Lightweight price point row for API responses.
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct PricePointRow {
+    /// Deterministic price point ID
+    pub event_id: Option<String>,
     /// Block number where price was recorded
     pub block_number: i64,
     /// Block timestamp (unix seconds)
     pub block_timestamp: i64,
     /// Transaction hash
-    pub tx_hash: String,
+    pub tx_hash: TxHash,
     /// Price value
     pub price: f64,
+    /// Exact price as a decimal string
+    pub price_exact: Option<String>,
+    /// EWMA-smoothed price
+    pub price_ewma: Option<f64>,
+    /// Where the reserves came from (`event` or `call`)
+    pub source: String,
     /// Human-readable reserve0
     pub reserve0_human: f64,
     /// Human-readable reserve1
@@ -265,7 +439,7 @@ pub struct PoolRow {
     /// Optional pool name
     pub name: Option<String>,
     /// Pool address
-    pub address: String,
+    pub address: PoolAddress,
     /// Token0 symbol
     pub token0_symbol: Option<String>,
     /// Token0 address
@@ -278,27 +452,619 @@ pub struct PoolRow {
     pub token1_address: String,
     /// Token1 decimals
     pub token1_decimals: i64,
+    /// Protocol that deployed the pool
+    pub protocol: String,
+    /// Pricing formula of the pool
+    pub pool_type: String,
+    /// Direction prices are reported in
+    pub quote_direction: String,
+    /// Whether the pool is indexed
+    pub enabled: bool,
+    /// First block to index when the pool has no checkpoint
+    pub start_block: Option<i64>,
+    /// Flash spike threshold in basis points
+    pub spike_filter_bps: Option<i64>,
     /// Last indexed block
     pub last_indexed_block: i64,
     /// Total events processed
     pub total_events: i64,
+    /// Status of the pool's watch task (`None` if it never ran)
+    pub task_status: Option<String>,
+    /// Times the pool's watch task was restarted after failing
+    pub task_restarts: Option<i64>,
+    /// Error of the task's last failed pass, if it hasn't succeeded since
+    pub task_error: Option<String>,
+    /// Unix timestamp of the task's last status update
+    pub task_updated_at: Option<i64>,
+}
+
+impl PoolRow {
+    /// Returns the pool's flash spike threshold in basis points, if spikes
+    /// are filtered (see [`crate::spikes`]).
+    #[must_use]
+    pub fn spike_filter(&self) -> Option<u32> {
+        self.spike_filter_bps
+            .and_then(|bps| u32::try_from(bps).ok())
+    }
+
+    /// Returns the pool's quote direction; unknown names fall back to the
+    /// stored direction.
+    #[must_use]
+    pub fn quote_direction(&self) -> QuoteDirection {
+        self.quote_direction.parse().unwrap_or_default()
+    }
+
+    /// Returns the adapter that prices the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored pool type is invalid.
+    pub fn price_adapter(&self) -> TrackerResult<Box<dyn PriceAdapter>> {
+        let pool_type: PoolType = self.pool_type.parse()?;
+        let protocol: DexProtocol = self.protocol.parse().unwrap_or_default();
+        Ok(pool_type.adapter(protocol.fee_bps()))
+    }
 }
 
 /// Lightweight sync event row for API responses.
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct SyncEventRow {
+    /// Deterministic event ID
+    pub event_id: Option<String>,
     /// Block number where event occurred
     pub block_number: i64,
     /// Block timestamp (unix seconds)
     pub block_timestamp: i64,
     /// Transaction hash
-    pub tx_hash: String,
+    pub tx_hash: TxHash,
+    /// Log index within the block
+    pub log_index: i64,
     /// Reserve0 raw value
     pub reserve0: String,
     /// Reserve1 raw value
     pub reserve1: String,
 }
 
+impl From<&SyncEventRecord> for SyncEventRow {
+    fn from(record: &SyncEventRecord) -> Self {
+        Self {
+            event_id: record.event_id.clone(),
+            block_number: record.block_number,
+            block_timestamp: record.block_timestamp,
+            tx_hash: record.tx_hash,
+            log_index: i64::from(record.log_index),
+            reserve0: record.reserve0.clone(),
+            reserve1: record.reserve1.clone(),
+        }
+    }
+}
+
+/// Summary of the confirmed price points in a time range, which changes
+/// whenever a row in the range is added, confirmed or re-indexed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PriceHistoryVersion {
+    /// Number of confirmed price points
+    pub count: i64,
+    /// Highest block number
+    pub max_block: Option<i64>,
+    /// Highest row ID; re-indexed rows get new IDs
+    pub max_id: Option<i64>,
+    /// Latest block timestamp (unix seconds)
+    pub last_timestamp: Option<i64>,
+}
+
+/// One page of a listing, with the number of rows on all pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    /// Rows on this page
+    pub items: Vec<T>,
+    /// Rows matching the query across all pages
+    pub total: u64,
+}
+
+/// Inclusive block number bounds of a query; `None` leaves a side open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BlockRange {
+    /// First block
+    pub from: Option<u64>,
+    /// Last block
+    pub to: Option<u64>,
+}
+
+impl BlockRange {
+    /// Every block.
+    pub const ALL: Self = Self {
+        from: None,
+        to: None,
+    };
+
+    /// Creates a range, or `None` if `from` is past `to`.
+    #[must_use]
+    pub const fn new(from: Option<u64>, to: Option<u64>) -> Option<Self> {
+        match (from, to) {
+            (Some(from), Some(to)) if from > to => None,
+            _ => Some(Self { from, to }),
+        }
+    }
+
+    /// Whether both sides are open.
+    #[must_use]
+    pub const fn is_all(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    /// The bounds as SQL integers, open sides widened to `0` and `i64::MAX`.
+    #[must_use]
+    pub fn bounds(&self) -> (i64, i64) {
+        (
+            self.from
+                .map_or(0, |b| i64::try_from(b).unwrap_or(i64::MAX)),
+            self.to
+                .map_or(i64::MAX, |b| i64::try_from(b).unwrap_or(i64::MAX)),
+        )
+    }
+}
+
+/// OHLC candle aggregated from confirmed price points.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CandleRow {
+    /// Bucket start (unix seconds, aligned to the interval)
+    pub bucket_start: i64,
+    /// First price in the bucket
+    pub open: f64,
+    /// Highest price in the bucket
+    pub high: f64,
+    /// Lowest price in the bucket
+    pub low: f64,
+    /// Last price in the bucket
+    pub close: f64,
+    /// Number of price points in the bucket
+    pub samples: i64,
+    /// Sum of the bucket's prices, for exact averages across buckets
+    pub price_sum: f64,
+}
+
+/// How the prices in a time series bucket are reduced to one value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeseriesAgg {
+    /// Mean of the bucket's prices
+    #[default]
+    Avg,
+    /// Last price in the bucket
+    Last,
+    /// Highest price in the bucket
+    Max,
+}
+
+impl std::str::FromStr for TimeseriesAgg {
+    type Err = crate::error::TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "avg" => Ok(Self::Avg),
+            "last" => Ok(Self::Last),
+            "max" => Ok(Self::Max),
+            _ => Err(crate::error::TrackerError::config(
+                format!("Aggregation must be avg, last or max, got: {s}"),
+                None,
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for TimeseriesAgg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Avg => "avg",
+            Self::Last => "last",
+            Self::Max => "max",
+        })
+    }
+}
+
+/// Swap totals for one trader (swap recipient) over a time window.
+///
+/// Amounts are raw token units summed as floating point.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TraderTotalsRow {
+    /// Trader address (hex string with 0x prefix)
+    pub address: String,
+    /// Number of swaps
+    pub swaps: i64,
+    /// Raw token0 paid into the pair
+    pub amount0_in: f64,
+    /// Raw token1 paid into the pair
+    pub amount1_in: f64,
+    /// Raw token0 received from the pair
+    pub amount0_out: f64,
+    /// Raw token1 received from the pair
+    pub amount1_out: f64,
+}
+
+/// Swap activity for one UTC day.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DailyTradersRow {
+    /// Day start (unix seconds, midnight UTC)
+    pub day_start: i64,
+    /// Distinct swap recipients
+    pub unique_traders: i64,
+    /// Number of swaps
+    pub swaps: i64,
+    /// Raw token1 volume (in plus out)
+    pub volume1: f64,
+}
+
+/// Swap inputs and liquidity of a pool over a fee APR window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FeeWindowRow {
+    /// Number of swaps
+    pub swaps: i64,
+    /// Raw token0 paid into the pool
+    pub amount0_in: f64,
+    /// Raw token1 paid into the pool
+    pub amount1_in: f64,
+    /// Average liquidity in token1 (`reserve1 + reserve0 × price`) over the
+    /// window's price points
+    pub avg_liquidity: Option<f64>,
+    /// Timestamp of the window's first price point
+    pub first_timestamp: Option<i64>,
+}
+
+/// API key metadata from the `api_keys` table.
+///
+/// The key itself is never stored; only its hash.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ApiKeyRow {
+    /// Database-assigned unique identifier
+    pub id: i64,
+    /// Human-readable label (e.g. the client it was issued to)
+    pub name: String,
+    /// First characters of the key, for identification
+    pub key_prefix: String,
+    /// Per-key rate limit in requests per minute (`None` = server default)
+    pub rate_limit_rpm: Option<i64>,
+    /// Whether the key only sees the pools listed in `api_key_pools`
+    pub pool_scoped: bool,
+    /// Chain the key is valid on (`None` = any chain)
+    pub chain_id: Option<i64>,
+    /// Unix timestamp of creation
+    pub created_at: i64,
+    /// Unix timestamp of revocation (`None` = active)
+    pub revoked_at: Option<i64>,
+}
+
+/// Pools an API key may see.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PoolScope {
+    /// Every pool (unscoped keys and anonymous requests)
+    #[default]
+    All,
+    /// Only the pools with these IDs
+    Pools(BTreeSet<i64>),
+}
+
+impl PoolScope {
+    /// Whether the pool with ID `pool_id` is visible.
+    #[must_use]
+    pub fn allows(&self, pool_id: i64) -> bool {
+        match self {
+            Self::All => true,
+            Self::Pools(ids) => ids.contains(&pool_id),
+        }
+    }
+
+    /// Whether every pool is visible.
+    #[must_use]
+    pub const fn is_all(&self) -> bool {
+        matches!(self, Self::All)
+    }
+
+    /// The visible pool IDs as a JSON array, for `json_each()` filters in
+    /// queries (`None` = every pool).
+    pub(crate) fn json_ids(&self) -> Option<String> {
+        match self {
+            Self::All => None,
+            Self::Pools(ids) => Some(format!(
+                "[{}]",
+                ids.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )),
+        }
+    }
+}
+
+/// Progress of a data migration, from the `migrations_log` table.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DataMigrationRow {
+    /// Unique migration name
+    pub name: String,
+    /// What the migration does
+    pub description: String,
+    /// Where the next batch starts (`None` = from the start)
+    pub checkpoint: Option<String>,
+    /// Rows changed so far
+    pub rows_done: i64,
+    /// Unix timestamp of the first batch
+    pub started_at: i64,
+    /// Unix timestamp of the last batch
+    pub updated_at: i64,
+    /// Unix timestamp of completion (`None` = not finished)
+    pub completed_at: Option<i64>,
+}
+
+/// `incidents.kind` of a stablecoin depeg (see [`crate::depeg`]).
+pub const INCIDENT_KIND_DEPEG: &str = "depeg";
+
+/// A period in which a pool's price misbehaved, from the `incidents` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct IncidentRow {
+    /// Database ID
+    pub id: i64,
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Incident type (e.g. [`INCIDENT_KIND_DEPEG`])
+    pub kind: String,
+    /// Allowed deviation from normal, in basis points
+    pub band_bps: i64,
+    /// First block of the incident
+    pub started_block: i64,
+    /// Block timestamp of `started_block`
+    pub started_at: i64,
+    /// First block after the incident (`None` while ongoing)
+    pub ended_block: Option<i64>,
+    /// Block timestamp of `ended_block`
+    pub ended_at: Option<i64>,
+    /// Price farthest from normal during the incident
+    pub peak_price: f64,
+    /// Deviation of `peak_price`, in basis points
+    pub peak_deviation_bps: f64,
+}
+
+/// A recorded value of the composite price index, from `composite_prices`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CompositePriceRow {
+    /// Newest block among the constituents
+    pub block_number: i64,
+    /// Block timestamp of `block_number`
+    pub block_timestamp: i64,
+    /// Liquidity-weighted average price
+    pub price: f64,
+    /// Total quote-side liquidity of the constituents
+    pub liquidity: f64,
+    /// Constituents included in the price
+    pub pools: i64,
+    /// JSON array of the constituents (see [`crate::composite::Constituent`])
+    pub constituents: String,
+}
+
+/// A finished alert delivery, for insertion into `alert_deliveries`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertDeliveryRecord {
+    /// Rule ID, `lag_watchdog` or `depeg`
+    pub source: String,
+    /// Event name (e.g. `price_alert`)
+    pub event: String,
+    /// Channel name (e.g. `slack`)
+    pub channel: String,
+    /// Redacted destination
+    pub destination: String,
+    /// Whether an attempt succeeded
+    pub delivered: bool,
+    /// Attempts made
+    pub attempts: i64,
+    /// Last error of a failed delivery
+    pub error: Option<String>,
+    /// Unix timestamp of the outcome
+    pub created_at: i64,
+}
+
+/// An alert delivery from the `alert_deliveries` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AlertDeliveryRow {
+    /// Database ID
+    pub id: i64,
+    /// Rule ID, `lag_watchdog` or `depeg`
+    pub source: String,
+    /// Event name (e.g. `price_alert`)
+    pub event: String,
+    /// Channel name (e.g. `slack`)
+    pub channel: String,
+    /// Redacted destination
+    pub destination: String,
+    /// `delivered` or `failed`
+    pub status: String,
+    /// Attempts made
+    pub attempts: i64,
+    /// Last error of a failed delivery
+    pub error: Option<String>,
+    /// Unix timestamp of the outcome
+    pub created_at: i64,
+}
+
+/// A price webhook delivery that failed every attempt, for insertion into
+/// `price_webhook_dead_letters`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceWebhookDeadLetterRecord {
+    /// Redacted webhook URL
+    pub destination: String,
+    /// Hash of the full webhook URL
+    pub destination_key: String,
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Block of the price
+    pub block_number: i64,
+    /// JSON body that was posted
+    pub payload: String,
+    /// Attempts made
+    pub attempts: i64,
+    /// Last error
+    pub error: String,
+    /// Unix timestamp of the last attempt
+    pub created_at: i64,
+}
+
+/// A dead-lettered price webhook delivery from the
+/// `price_webhook_dead_letters` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PriceWebhookDeadLetterRow {
+    /// Database ID
+    pub id: i64,
+    /// Redacted webhook URL
+    pub destination: String,
+    /// Hash of the full webhook URL
+    pub destination_key: String,
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Block of the price
+    pub block_number: i64,
+    /// JSON body that was posted
+    pub payload: String,
+    /// Attempts made
+    pub attempts: i64,
+    /// Last error
+    pub error: String,
+    /// Unix timestamp of the last attempt
+    pub created_at: i64,
+}
+
+/// A chain reorganization handled by the indexer, for insertion into
+/// `reorgs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReorgRecord {
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Unix timestamp of detection
+    pub detected_at: i64,
+    /// Last block common to the old and new chain
+    pub fork_point: u64,
+    /// Indexed blocks rolled back
+    pub depth: u64,
+}
+
+/// Status of a pool's watch task, for upserting into `indexer_tasks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexerTaskRecord {
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Task status
+    pub status: TaskStatus,
+    /// Last block indexed by the task
+    pub last_block: Option<u64>,
+    /// Times the task was restarted after failing
+    pub restarts: u32,
+    /// Error of the last failed pass or task
+    pub last_error: Option<String>,
+    /// Unix timestamp of the update
+    pub updated_at: i64,
+}
+
+/// A handled reorg from the `reorgs` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReorgRow {
+    /// Database ID
+    pub id: i64,
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Unix timestamp of detection
+    pub detected_at: i64,
+    /// Last block common to the old and new chain
+    pub fork_point: i64,
+    /// Indexed blocks rolled back
+    pub depth: i64,
+    /// Sync events after the fork point marked unconfirmed
+    pub invalidated_events: i64,
+}
+
+/// Reorg totals of a pool.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReorgStatsRow {
+    /// Reorgs handled
+    pub reorgs: i64,
+    /// Deepest reorg, in blocks
+    pub max_depth: Option<i64>,
+    /// Average depth, in blocks
+    pub avg_depth: Option<f64>,
+    /// Sync events invalidated across all reorgs
+    pub invalidated_events: i64,
+    /// Unix timestamp of the latest reorg
+    pub last_detected_at: Option<i64>,
+}
+
+/// Result of one pass of following a primary database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FollowReport {
+    /// Sync event rows copied or refreshed
+    pub events_synced: u64,
+    /// Price point rows copied or refreshed
+    pub prices_synced: u64,
+    /// Highest `last_indexed_block` across pools after the pass
+    pub last_indexed_block: i64,
+}
+
+/// How replayed prices differ from the stored ones over a block range.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayDiff {
+    /// Rows whose price, exact price or smoothed price changed
+    pub changed: u64,
+    /// Replayed rows without a stored counterpart
+    pub added: u64,
+    /// Stored rows without a replayed counterpart
+    pub removed: u64,
+    /// Largest absolute change of `price` among changed rows
+    pub max_price_change: f64,
+}
+
+/// Keyset pagination cursor over sync events.
+///
+/// Events are totally ordered by `(block_number, log_index)` within a pool, so
+/// the position of the last row on a page is enough to fetch the next one
+/// without an `OFFSET` scan. Serialized as `"<block_number>:<log_index>"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventCursor {
+    /// Block number of the last returned event
+    pub block_number: u64,
+    /// Log index of the last returned event
+    pub log_index: u32,
+}
+
+impl EventCursor {
+    /// Creates a cursor pointing at the given event position.
+    #[must_use]
+    pub const fn new(block_number: u64, log_index: u32) -> Self {
+        Self {
+            block_number,
+            log_index,
+        }
+    }
+}
+
+impl std::fmt::Display for EventCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.block_number, self.log_index)
+    }
+}
+
+impl std::str::FromStr for EventCursor {
+    type Err = crate::error::TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            crate::error::TrackerError::decoding(
+                format!("Invalid cursor '{s}', expected <block_number>:<log_index>"),
+                None,
+            )
+        };
+
+        let (block, log) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            block_number: block.parse().map_err(|_| invalid())?,
+            log_index: log.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
 impl PricePointRecord {
     /// Creates a new price point record from blockchain data and computed values.
     ///
@@ -349,11 +1115,15 @@ impl PricePointRecord {
     ) -> Self {
         Self {
             id: 0, // Will be set by database
+            event_id: None,
             pool_id,
             block_number: block_number as i64,
             block_timestamp: block_timestamp as i64,
-            tx_hash: format!("{:?}", tx_hash),
+            tx_hash: tx_hash.into(),
             price,
+            price_exact: None,
+            price_ewma: None,
+            source: PRICE_SOURCE_EVENT.to_string(),
             reserve0_raw: reserve0.to_string(),
             reserve1_raw: reserve1.to_string(),
             reserve0_human,
@@ -362,6 +1132,35 @@ impl PricePointRecord {
             created_at: chrono::Utc::now().timestamp(),
         }
     }
+
+    /// Attaches the deterministic price point ID.
+    #[must_use]
+    pub fn with_event_id(mut self, event_id: impl Into<String>) -> Self {
+        self.event_id = Some(event_id.into());
+        self
+    }
+
+    /// Attaches the exact price (see [`crate::pricing::calculate_price_exact`]).
+    #[must_use]
+    pub fn with_price_exact(mut self, price_exact: alloy::primitives::U256) -> Self {
+        self.price_exact = Some(crate::pricing::format_exact_price(price_exact));
+        self
+    }
+
+    /// Attaches the smoothed price (see [`crate::smoothing::PriceEwma`]).
+    #[must_use]
+    pub const fn with_price_ewma(mut self, price_ewma: Option<f64>) -> Self {
+        self.price_ewma = price_ewma;
+        self
+    }
+
+    /// Marks the price as read with a `getReserves()` call instead of from a
+    /// Sync event.
+    #[must_use]
+    pub fn with_call_source(mut self) -> Self {
+        self.source = PRICE_SOURCE_CALL.to_string();
+        self
+    }
 }
 
 /// Represents the indexer's persistent state.
@@ -440,6 +1239,66 @@ impl PriceStats {
     }
 }
 
+/// Size of the database file and of each table, from `db stats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StorageStats {
+    /// Bytes per database page
+    pub page_size: i64,
+    /// Pages in the database file
+    pub page_count: i64,
+    /// Unused pages, reclaimed by `VACUUM`
+    pub freelist_count: i64,
+    /// Tables with their indexes, by name
+    pub tables: Vec<TableStats>,
+    /// Indexed block range of each pool
+    pub pools: Vec<PoolBlockRange>,
+}
+
+/// Rows, size and recent writes of one table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TableStats {
+    /// Table name
+    pub name: String,
+    /// Rows in the table
+    pub rows: i64,
+    /// Bytes of the table's pages, without its indexes (`None` when SQLite
+    /// was built without the `dbstat` table)
+    pub bytes: Option<i64>,
+    /// Rows written since the requested time, by `created_at` (`None` for
+    /// tables without it)
+    pub recent_rows: Option<i64>,
+    /// The table's indexes
+    pub indexes: Vec<IndexStats>,
+}
+
+/// Size of one index.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IndexStats {
+    /// Index name
+    pub name: String,
+    /// Bytes of the index's pages (`None` without the `dbstat` table)
+    pub bytes: Option<i64>,
+}
+
+/// Oldest and newest indexed block of a pool, counting archived events.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, sqlx::FromRow)]
+pub struct PoolBlockRange {
+    /// Pool ID
+    pub pool_id: i64,
+    /// Pool name
+    pub name: Option<String>,
+    /// Oldest block with a stored or archived Sync event
+    pub oldest_block: Option<i64>,
+    /// Newest block with a stored Sync event
+    pub newest_block: Option<i64>,
+    /// Checkpoint block
+    pub last_indexed_block: Option<i64>,
+    /// Sync events in `sync_events`
+    pub events: i64,
+    /// Sync events in the event archive
+    pub archived_events: i64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -493,6 +1352,17 @@ mod tests {
         assert_eq!(pool.name, Some("USDC-WETH".to_string()));
     }
 
+    #[test]
+    fn test_event_cursor_roundtrip() {
+        let cursor = EventCursor::new(19_000_000, 42);
+        assert_eq!(cursor.to_string(), "19000000:42");
+        assert_eq!("19000000:42".parse::<EventCursor>().unwrap(), cursor);
+
+        assert!("19000000".parse::<EventCursor>().is_err());
+        assert!("abc:1".parse::<EventCursor>().is_err());
+        assert!("1:-1".parse::<EventCursor>().is_err());
+    }
+
     #[test]
     fn test_indexer_state_block_hash_parsing() {
         let block_hash = FixedBytes::from([1u8; 32]);
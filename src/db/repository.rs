@@ -4,19 +4,123 @@
 //! and indexer state. Handles batch inserts, queries, and reorg recovery.
 
 use alloy::primitives::{Address, FixedBytes, U256};
-use sqlx::SqlitePool;
-use tracing::{debug, info, instrument};
+use futures_util::{stream, Stream, StreamExt, TryStreamExt};
+use sqlx::{Connection, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{debug, info, instrument, warn};
 
+use super::archive::{
+    decode_segment, encode_segment, segment_start, ArchiveReport, ARCHIVE_CODEC_ZSTD_JSON,
+    ARCHIVE_SEGMENT_BLOCKS,
+};
+use super::ids::{derive_record_id, RecordKind};
 use super::models::{
-    IndexerState, PoolRecord, PoolRow, PricePointRecord, PricePointRow, PriceStats, StatsRow,
-    SyncEventRecord, SyncEventRow,
+    AlertDeliveryRecord, AlertDeliveryRow, ApiKeyRow, BlockRange, CandleRow, CompositePriceRow,
+    DailyTradersRow, DataMigrationRow, EventCursor, FeeWindowRow, FollowReport, IncidentRow,
+    IndexStats, IndexerState, IndexerTaskRecord, Page, PoolBlockRange, PoolRecord, PoolRow,
+    PoolScope, PriceHistoryVersion, PricePointRecord, PricePointRow, PriceStats,
+    PriceWebhookDeadLetterRecord, PriceWebhookDeadLetterRow, ReorgRecord, ReorgRow, ReorgStatsRow,
+    ReplayDiff, StatsRow, StorageStats, SwapEventRecord, SyncEventRecord, SyncEventRow, TableStats,
+    TimeseriesAgg, TraderTotalsRow, PRICE_SOURCE_CALL,
 };
+use super::snapshot::SnapshotManifest;
+use super::types::PoolAddress;
+use super::{connect_read_only, READ_POOL_CONNECTIONS};
+use crate::adapters::PoolType;
+use crate::composite::CompositePrice;
 use crate::error::TrackerError;
+use crate::events::UNISWAP_V2_WETH_USDT_PAIR;
+use crate::pricing::QuoteDirection;
+use crate::protocol::DexProtocol;
+
+/// Bind parameters per multi-row `INSERT` of the batch inserts.
+///
+/// SQLite accepts up to 32,766 since 3.32, but statements of about a hundred
+/// rows bind and execute faster than ones at the limit (`benches/insert.rs`).
+const INSERT_MAX_PARAMS: usize = 999;
+
+/// Columns bound per row by the batch inserts, which write as many rows per
+/// multi-row `INSERT` as fit in [`INSERT_MAX_PARAMS`].
+const SYNC_EVENT_COLUMNS: usize = 11;
+const SWAP_EVENT_COLUMNS: usize = 13;
+const PRICE_POINT_COLUMNS: usize = 15;
+
+/// Rows read per query by [`Repository::stream_price_history`].
+const HISTORY_STREAM_CHUNK: i64 = 1_000;
+
+/// Width of the `price_rollups` buckets, maintained by triggers on
+/// `price_points`.
+const ROLLUP_SECS: i64 = 3_600;
+
+/// Start of the rollup hour holding `timestamp`.
+const fn hour_floor(timestamp: i64) -> i64 {
+    timestamp.div_euclid(ROLLUP_SECS) * ROLLUP_SECS
+}
+
+/// Start of the first rollup hour at or after `timestamp`.
+const fn hour_ceil(timestamp: i64) -> i64 {
+    hour_floor(timestamp.saturating_add(ROLLUP_SECS - 1))
+}
+
+/// `prices` CTE over a pool's confirmed price points (`id`, `block_number`,
+/// `block_timestamp`, `price`) with `block_timestamp` in `?2..=?3`.
+///
+/// With `spike_filter`, reverted single-block spikes of at least `?4` basis
+/// points are left out (see [`crate::spikes`]). The price before the
+/// window's first block is unknown, so that block is kept as is.
+fn confirmed_prices_cte(spike_filter: bool) -> String {
+    if !spike_filter {
+        return r#"
+            prices AS (
+                SELECT id, block_number, block_timestamp, price
+                FROM price_points
+                WHERE pool_id = ?1 AND is_confirmed = 1
+                  AND block_timestamp BETWEEN ?2 AND ?3
+            )"#
+        .to_string();
+    }
+    format!(
+        r#"
+            ordered AS (
+                SELECT id, block_number, block_timestamp, price,
+                       LAG(price) OVER (ORDER BY block_number, id) AS previous,
+                       LAST_VALUE(price) OVER (
+                           PARTITION BY block_number ORDER BY id
+                           ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+                       ) AS block_close,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY block_number ORDER BY id DESC
+                       ) AS from_last
+                FROM price_points
+                WHERE pool_id = ?1 AND is_confirmed = 1
+                  AND block_timestamp BETWEEN ?2 AND ?3
+            ),
+            opened AS (
+                SELECT *, FIRST_VALUE(previous) OVER (
+                    PARTITION BY block_number ORDER BY id
+                ) AS block_open
+                FROM ordered
+            ),
+            prices AS (
+                SELECT id, block_number, block_timestamp, price
+                FROM opened
+                WHERE NOT COALESCE(
+                    from_last > 1 AND block_open > 0
+                    AND ABS(block_close - block_open) * 10000 <= block_open * {revert_bps}
+                    AND ABS(price - block_open) * 10000 >= block_open * ?4,
+                    0
+                )
+            )"#,
+        revert_bps = crate::spikes::SPIKE_REVERT_BPS
+    )
+}
 
 /// Repository for database operations.
 ///
 /// Wraps a SQLite connection pool and provides type-safe methods
-/// for all database interactions.
+/// for all database interactions. Cloning is cheap and shares the pool.
+#[derive(Clone)]
 pub struct Repository {
     pool: SqlitePool,
 }
@@ -27,6 +131,37 @@ impl Repository {
         Self { pool }
     }
 
+    /// Opens a read-only handle on the same database for query traffic.
+    ///
+    /// The handle has its own pool of [`READ_POOL_CONNECTIONS`] connections,
+    /// so heavy reads cannot starve this repository's writers of
+    /// connections, and any write through it fails. An in-memory database
+    /// exists only on this repository's connections, so its reader shares
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database file cannot be located or opened.
+    pub async fn reader(&self) -> Result<Self, TrackerError> {
+        let (file,) = sqlx::query_as::<_, (String,)>(
+            "SELECT file FROM pragma_database_list WHERE name = 'main'",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to locate database file".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        if file.is_empty() {
+            return Ok(self.clone());
+        }
+        let pool = connect_read_only(Path::new(&file), READ_POOL_CONNECTIONS).await?;
+        Ok(Self::new(pool))
+    }
+
     // ==================== POOL OPERATIONS ====================
 
     /// Ensures a pool exists in the database, creating it if necessary.
@@ -70,19 +205,18 @@ impl Repository {
         token1_symbol: Option<String>,
         token1_decimals: u8,
     ) -> Result<i64, TrackerError> {
-        let address_str = format!("{:?}", address);
-
         // Check if pool already exists
-        let existing: Option<(i64,)> = sqlx::query_as("SELECT id FROM pools WHERE address = ?")
-            .bind(&address_str)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| {
-                TrackerError::database(
-                    "Failed to query existing pool".to_string(),
-                    Some(Box::new(e)),
-                )
-            })?;
+        let existing: Option<(i64,)> =
+            sqlx::query_as("SELECT id FROM pools WHERE lower(address) = ?")
+                .bind(PoolAddress::from(address))
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| {
+                    TrackerError::database(
+                        "Failed to query existing pool".to_string(),
+                        Some(Box::new(e)),
+                    )
+                })?;
 
         if let Some((pool_id,)) = existing {
             return Ok(pool_id);
@@ -109,7 +243,7 @@ impl Repository {
             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&record.address)
+        .bind(record.address)
         .bind(&record.name)
         .bind(&record.token0_address)
         .bind(&record.token0_symbol)
@@ -128,14 +262,15 @@ impl Repository {
     }
 
     /// Retrieves a pool by its address.
+    ///
+    /// Matching is case-insensitive, so checksummed and lowercase addresses
+    /// resolve to the same pool.
     pub async fn get_pool_by_address(
         &self,
         address: Address,
     ) -> Result<Option<PoolRecord>, TrackerError> {
-        let address_str = format!("{:?}", address);
-
-        let pool = sqlx::query_as::<_, PoolRecord>("SELECT * FROM pools WHERE address = ?")
-            .bind(&address_str)
+        let pool = sqlx::query_as::<_, PoolRecord>("SELECT * FROM pools WHERE lower(address) = ?")
+            .bind(PoolAddress::from(address))
             .fetch_optional(&self.pool)
             .await
             .map_err(|e| {
@@ -148,6 +283,27 @@ impl Repository {
         Ok(pool)
     }
 
+    /// Deletes a pool with all of its events, prices, candles, checkpoint,
+    /// reorgs and archived segments.
+    ///
+    /// Returns false if no pool has this ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub async fn delete_pool(&self, pool_id: i64) -> Result<bool, TrackerError> {
+        // Every table keyed by pool_id references pools(id) ON DELETE CASCADE
+        let result = sqlx::query("DELETE FROM pools WHERE id = ?")
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database("Failed to delete pool".to_string(), Some(Box::new(e)))
+            })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     // ==================== SYNC EVENT OPERATIONS ====================
 
     /// Inserts a single sync event into the database.
@@ -173,6 +329,7 @@ impl Repository {
     ///         U256::from(1000000000u64),
     ///         U256::from(500000000000000000u64),
     ///         false,
+    ///         "8f7c6a1e-4b2d-8e3f-9a10-5c6d7e8f9a0b",
     ///     ).await?;
     ///     
     ///     Ok(())
@@ -190,6 +347,7 @@ impl Repository {
         reserve0: U256,
         reserve1: U256,
         is_confirmed: bool,
+        event_id: &str,
     ) -> Result<i64, TrackerError> {
         let record = SyncEventRecord::new(
             pool_id,
@@ -201,16 +359,18 @@ impl Repository {
             reserve0,
             reserve1,
             is_confirmed,
-        );
+        )
+        .with_event_id(event_id);
 
         let result = sqlx::query(
             r#"
             INSERT INTO sync_events (
                 pool_id, block_number, block_hash, block_timestamp, tx_hash,
-                log_index, reserve0, reserve1, is_confirmed, created_at
+                log_index, reserve0, reserve1, is_confirmed, created_at, event_id
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT (pool_id, block_number, tx_hash, log_index) DO UPDATE SET
+                event_id = COALESCE(excluded.event_id, sync_events.event_id),
                 block_hash = excluded.block_hash,
                 block_timestamp = excluded.block_timestamp,
                 reserve0 = excluded.reserve0,
@@ -222,12 +382,13 @@ impl Repository {
         .bind(record.block_number)
         .bind(&record.block_hash)
         .bind(record.block_timestamp)
-        .bind(&record.tx_hash)
+        .bind(record.tx_hash)
         .bind(record.log_index)
         .bind(&record.reserve0)
         .bind(&record.reserve1)
         .bind(record.is_confirmed)
         .bind(record.created_at)
+        .bind(&record.event_id)
         .execute(&self.pool)
         .await
         .map_err(|e| {
@@ -239,8 +400,9 @@ impl Repository {
 
     /// Batch inserts multiple sync events in a single transaction.
     ///
-    /// More efficient than inserting events one at a time.
-    /// Recommended batch size: 100-1000 events.
+    /// Rows are written with multi-row `INSERT` statements of up to a few
+    /// thousand rows each, so large backfill batches take a handful of
+    /// statements instead of one per event.
     #[instrument(skip(self, events), fields(count = events.len(), duration_ms = tracing::field::Empty))]
     pub async fn batch_insert_sync_events(
         &self,
@@ -259,45 +421,7 @@ impl Repository {
         let mut tx = self.pool.begin().await.map_err(|e| {
             TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
         })?;
-
-        for event in events {
-            sqlx::query(
-                r#"
-                INSERT INTO sync_events (
-                    pool_id, block_number, block_hash, block_timestamp, tx_hash,
-                    log_index, reserve0, reserve1, is_confirmed, created_at
-                )
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                ON CONFLICT (pool_id, block_number, tx_hash, log_index) DO UPDATE SET
-                    block_hash = excluded.block_hash,
-                    block_timestamp = excluded.block_timestamp,
-                    reserve0 = excluded.reserve0,
-                    reserve1 = excluded.reserve1,
-                    is_confirmed = excluded.is_confirmed
-                "#,
-            )
-            .bind(event.pool_id)
-            .bind(event.block_number)
-            .bind(&event.block_hash)
-            .bind(event.block_timestamp)
-            .bind(&event.tx_hash)
-            .bind(event.log_index)
-            .bind(&event.reserve0)
-            .bind(&event.reserve1)
-            .bind(event.is_confirmed)
-            .bind(event.created_at)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| {
-                TrackerError::database(
-                    format!(
-                        "Failed to insert sync event at block {}",
-                        event.block_number
-                    ),
-                    Some(Box::new(e)),
-                )
-            })?;
-        }
+        Self::write_sync_events(&mut tx, &events).await?;
 
         tx.commit().await.map_err(|e| {
             TrackerError::database(
@@ -319,6 +443,36 @@ impl Repository {
         Ok(())
     }
 
+    // ==================== SWAP EVENT OPERATIONS ====================
+
+    /// Inserts multiple swap events in a single transaction, with multi-row
+    /// `INSERT` statements.
+    ///
+    /// Re-indexing a range replaces existing rows (same pool, block,
+    /// transaction and log index) rather than duplicating them.
+    pub async fn batch_insert_swap_events(
+        &self,
+        events: Vec<SwapEventRecord>,
+    ) -> Result<(), TrackerError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+        Self::write_swap_events(&mut tx, &events).await?;
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(())
+    }
+
     // ==================== PRICE POINT OPERATIONS ====================
 
     /// Inserts a single price point into the database.
@@ -335,6 +489,7 @@ impl Repository {
         reserve0_human: f64,
         reserve1_human: f64,
         is_confirmed: bool,
+        event_id: &str,
     ) -> Result<i64, TrackerError> {
         let record = PricePointRecord::new(
             pool_id,
@@ -347,30 +502,35 @@ impl Repository {
             reserve0_human,
             reserve1_human,
             is_confirmed,
-        );
+        )
+        .with_event_id(event_id);
 
         let result = sqlx::query(
             r#"
             INSERT INTO price_points (
                 pool_id, block_number, block_timestamp, tx_hash, price,
                 reserve0_raw, reserve1_raw, reserve0_human, reserve1_human,
-                is_confirmed, created_at
+                is_confirmed, created_at, event_id, price_exact, price_ewma, source
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT (pool_id, block_number, tx_hash) DO UPDATE SET
+                event_id = COALESCE(excluded.event_id, price_points.event_id),
                 block_timestamp = excluded.block_timestamp,
                 price = excluded.price,
+                price_exact = excluded.price_exact,
+                price_ewma = COALESCE(excluded.price_ewma, price_points.price_ewma),
                 reserve0_raw = excluded.reserve0_raw,
                 reserve1_raw = excluded.reserve1_raw,
                 reserve0_human = excluded.reserve0_human,
                 reserve1_human = excluded.reserve1_human,
-                is_confirmed = excluded.is_confirmed
+                is_confirmed = excluded.is_confirmed,
+                source = excluded.source
             "#,
         )
         .bind(record.pool_id)
         .bind(record.block_number)
         .bind(record.block_timestamp)
-        .bind(&record.tx_hash)
+        .bind(record.tx_hash)
         .bind(record.price)
         .bind(&record.reserve0_raw)
         .bind(&record.reserve1_raw)
@@ -378,6 +538,10 @@ impl Repository {
         .bind(record.reserve1_human)
         .bind(record.is_confirmed)
         .bind(record.created_at)
+        .bind(&record.event_id)
+        .bind(&record.price_exact)
+        .bind(record.price_ewma)
+        .bind(&record.source)
         .execute(&self.pool)
         .await
         .map_err(|e| {
@@ -400,10 +564,21 @@ impl Repository {
         Ok(result.last_insert_rowid())
     }
 
-    /// Batch inserts multiple price points in a single transaction.
+    /// Batch inserts multiple price points in a single transaction, with
+    /// multi-row `INSERT` statements.
     pub async fn batch_insert_price_points(
         &self,
         prices: Vec<PricePointRecord>,
+    ) -> Result<(), TrackerError> {
+        self.insert_price_point_rows("price_points", &prices).await
+    }
+
+    /// Upserts price points into `table`, which has the `price_points`
+    /// columns, in a single transaction.
+    async fn insert_price_point_rows(
+        &self,
+        table: &str,
+        prices: &[PricePointRecord],
     ) -> Result<(), TrackerError> {
         if prices.is_empty() {
             return Ok(());
@@ -412,50 +587,251 @@ impl Repository {
         let mut tx = self.pool.begin().await.map_err(|e| {
             TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
         })?;
+        Self::write_price_point_rows(&mut tx, table, prices).await?;
+        Self::rebuild_stale_rollups(&mut tx).await?;
 
-        for price in prices {
-            sqlx::query(
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Upserts sync events on `conn`, in multi-row statements.
+    async fn write_sync_events(
+        conn: &mut SqliteConnection,
+        events: &[SyncEventRecord],
+    ) -> Result<(), TrackerError> {
+        for chunk in events.chunks(INSERT_MAX_PARAMS / SYNC_EVENT_COLUMNS) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "INSERT INTO sync_events (pool_id, block_number, block_hash, block_timestamp, \
+                 tx_hash, log_index, reserve0, reserve1, is_confirmed, created_at, event_id) ",
+            );
+            query.push_values(chunk, |mut row, event| {
+                row.push_bind(event.pool_id)
+                    .push_bind(event.block_number)
+                    .push_bind(&event.block_hash)
+                    .push_bind(event.block_timestamp)
+                    .push_bind(event.tx_hash)
+                    .push_bind(event.log_index)
+                    .push_bind(&event.reserve0)
+                    .push_bind(&event.reserve1)
+                    .push_bind(event.is_confirmed)
+                    .push_bind(event.created_at)
+                    .push_bind(&event.event_id);
+            });
+            query.push(
+                r#"
+                ON CONFLICT (pool_id, block_number, tx_hash, log_index) DO UPDATE SET
+                    event_id = COALESCE(excluded.event_id, sync_events.event_id),
+                    block_hash = excluded.block_hash,
+                    block_timestamp = excluded.block_timestamp,
+                    reserve0 = excluded.reserve0,
+                    reserve1 = excluded.reserve1,
+                    is_confirmed = excluded.is_confirmed
+                "#,
+            );
+            query.build().execute(&mut *conn).await.map_err(|e| {
+                TrackerError::database(
+                    format!(
+                        "Failed to insert sync events {}",
+                        block_span(chunk, |e| e.block_number)
+                    ),
+                    Some(Box::new(e)),
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Upserts swap events on `conn`, in multi-row statements.
+    async fn write_swap_events(
+        conn: &mut SqliteConnection,
+        events: &[SwapEventRecord],
+    ) -> Result<(), TrackerError> {
+        for chunk in events.chunks(INSERT_MAX_PARAMS / SWAP_EVENT_COLUMNS) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "INSERT INTO swap_events (pool_id, block_number, block_timestamp, tx_hash, \
+                 log_index, sender, recipient, amount0_in, amount1_in, amount0_out, \
+                 amount1_out, is_confirmed, created_at) ",
+            );
+            query.push_values(chunk, |mut row, event| {
+                row.push_bind(event.pool_id)
+                    .push_bind(event.block_number)
+                    .push_bind(event.block_timestamp)
+                    .push_bind(event.tx_hash)
+                    .push_bind(event.log_index)
+                    .push_bind(&event.sender)
+                    .push_bind(&event.recipient)
+                    .push_bind(&event.amount0_in)
+                    .push_bind(&event.amount1_in)
+                    .push_bind(&event.amount0_out)
+                    .push_bind(&event.amount1_out)
+                    .push_bind(event.is_confirmed)
+                    .push_bind(event.created_at);
+            });
+            query.push(
                 r#"
-                INSERT INTO price_points (
-                    pool_id, block_number, block_timestamp, tx_hash, price,
-                    reserve0_raw, reserve1_raw, reserve0_human, reserve1_human,
-                    is_confirmed, created_at
+                ON CONFLICT (pool_id, block_number, tx_hash, log_index) DO UPDATE SET
+                    block_timestamp = excluded.block_timestamp,
+                    sender = excluded.sender,
+                    recipient = excluded.recipient,
+                    amount0_in = excluded.amount0_in,
+                    amount1_in = excluded.amount1_in,
+                    amount0_out = excluded.amount0_out,
+                    amount1_out = excluded.amount1_out,
+                    is_confirmed = excluded.is_confirmed
+                "#,
+            );
+            query.build().execute(&mut *conn).await.map_err(|e| {
+                TrackerError::database(
+                    format!(
+                        "Failed to insert swap events {}",
+                        block_span(chunk, |e| e.block_number)
+                    ),
+                    Some(Box::new(e)),
                 )
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Upserts price points into `table` on `conn`, in multi-row statements.
+    async fn write_price_point_rows(
+        conn: &mut SqliteConnection,
+        table: &str,
+        prices: &[PricePointRecord],
+    ) -> Result<(), TrackerError> {
+        for chunk in prices.chunks(INSERT_MAX_PARAMS / PRICE_POINT_COLUMNS) {
+            let mut query = QueryBuilder::<Sqlite>::new(format!(
+                "INSERT INTO {table} (pool_id, block_number, block_timestamp, tx_hash, \
+                 price, reserve0_raw, reserve1_raw, reserve0_human, reserve1_human, \
+                 is_confirmed, created_at, event_id, price_exact, price_ewma, source) "
+            ));
+            query.push_values(chunk, |mut row, price| {
+                row.push_bind(price.pool_id)
+                    .push_bind(price.block_number)
+                    .push_bind(price.block_timestamp)
+                    .push_bind(price.tx_hash)
+                    .push_bind(price.price)
+                    .push_bind(&price.reserve0_raw)
+                    .push_bind(&price.reserve1_raw)
+                    .push_bind(price.reserve0_human)
+                    .push_bind(price.reserve1_human)
+                    .push_bind(price.is_confirmed)
+                    .push_bind(price.created_at)
+                    .push_bind(&price.event_id)
+                    .push_bind(&price.price_exact)
+                    .push_bind(price.price_ewma)
+                    .push_bind(&price.source);
+            });
+            query.push(format!(
+                r#"
                 ON CONFLICT (pool_id, block_number, tx_hash) DO UPDATE SET
+                    event_id = COALESCE(excluded.event_id, {table}.event_id),
                     block_timestamp = excluded.block_timestamp,
                     price = excluded.price,
+                    price_exact = excluded.price_exact,
+                    price_ewma = COALESCE(excluded.price_ewma, {table}.price_ewma),
                     reserve0_raw = excluded.reserve0_raw,
                     reserve1_raw = excluded.reserve1_raw,
                     reserve0_human = excluded.reserve0_human,
                     reserve1_human = excluded.reserve1_human,
-                    is_confirmed = excluded.is_confirmed
-                "#,
-            )
-            .bind(price.pool_id)
-            .bind(price.block_number)
-            .bind(price.block_timestamp)
-            .bind(&price.tx_hash)
-            .bind(price.price)
-            .bind(&price.reserve0_raw)
-            .bind(&price.reserve1_raw)
-            .bind(price.reserve0_human)
-            .bind(price.reserve1_human)
-            .bind(price.is_confirmed)
-            .bind(price.created_at)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| {
+                    is_confirmed = excluded.is_confirmed,
+                    source = excluded.source
+                "#
+            ));
+            query.build().execute(&mut *conn).await.map_err(|e| {
                 TrackerError::database(
                     format!(
-                        "Failed to insert price point at block {}",
-                        price.block_number
+                        "Failed to insert price points {}",
+                        block_span(chunk, |p| p.block_number)
                     ),
                     Some(Box::new(e)),
                 )
             })?;
         }
 
+        Ok(())
+    }
+
+    /// Computes `price_exact` for price points written before exact prices
+    /// were stored, from their raw reserves and the pool's token decimals.
+    ///
+    /// Rows whose reserves cannot be parsed or priced are skipped. Returns
+    /// the number of rows updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be queried or updated.
+    pub async fn backfill_exact_prices(&self) -> Result<u64, TrackerError> {
+        let rows = sqlx::query_as::<_, (i64, String, String, i32, i32, String, String)>(
+            r#"
+            SELECT pp.id, pp.reserve0_raw, pp.reserve1_raw, p.token0_decimals, p.token1_decimals,
+                   p.pool_type, p.protocol
+            FROM price_points pp
+            JOIN pools p ON p.id = pp.pool_id
+            WHERE pp.price_exact IS NULL
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price points without exact prices".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        let mut updated = 0u64;
+        for (row_id, reserve0, reserve1, decimals0, decimals1, pool_type, protocol) in rows {
+            let exact = (|| {
+                let fee_bps = protocol
+                    .parse::<DexProtocol>()
+                    .unwrap_or_default()
+                    .fee_bps();
+                let adapter = pool_type.parse::<PoolType>().ok()?.adapter(fee_bps);
+                adapter
+                    .price_exact(
+                        reserve0.parse().ok()?,
+                        reserve1.parse().ok()?,
+                        u8::try_from(decimals0).ok()?,
+                        u8::try_from(decimals1).ok()?,
+                    )
+                    .ok()
+            })();
+            let Some(exact) = exact else {
+                warn!(row_id, "Skipping price point with unpriceable reserves");
+                continue;
+            };
+
+            sqlx::query("UPDATE price_points SET price_exact = ? WHERE id = ?")
+                .bind(crate::pricing::format_exact_price(exact))
+                .bind(row_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    TrackerError::database(
+                        "Failed to backfill exact price".to_string(),
+                        Some(Box::new(e)),
+                    )
+                })?;
+            updated += 1;
+        }
+
         tx.commit().await.map_err(|e| {
             TrackerError::database(
                 "Failed to commit transaction".to_string(),
@@ -463,7 +839,8 @@ impl Repository {
             )
         })?;
 
-        Ok(())
+        info!(updated, "Backfilled exact prices");
+        Ok(updated)
     }
 
     /// Gets the most recent N price points for a pool.
@@ -650,8 +1027,8 @@ impl Repository {
     ) -> Result<Option<PricePointRow>, TrackerError> {
         let price = sqlx::query_as::<_, PricePointRow>(
             r#"
-            SELECT block_number, block_timestamp, tx_hash, price,
-                   reserve0_human, reserve1_human
+            SELECT event_id, block_number, block_timestamp, tx_hash, price,
+                   price_exact, price_ewma, source, reserve0_human, reserve1_human
             FROM price_points
             WHERE pool_id = ? AND is_confirmed = 1
             ORDER BY block_number DESC
@@ -671,68 +1048,314 @@ impl Repository {
         Ok(price)
     }
 
-    /// Calculate 24-hour price change percentage.
-    pub async fn get_24h_price_change(&self, pool_id: i64) -> Result<f64, TrackerError> {
-        let now = chrono::Utc::now().timestamp();
-        let day_ago = now - 86_400;
-
-        let result = sqlx::query_as::<_, (Option<f64>, Option<f64>)>(
+    /// Get the latest confirmed price point for a pool, including raw reserves.
+    pub async fn get_latest_price_record(
+        &self,
+        pool_id: i64,
+    ) -> Result<Option<PricePointRecord>, TrackerError> {
+        let price = sqlx::query_as::<_, PricePointRecord>(
             r#"
-            SELECT 
-                (SELECT price FROM price_points 
-                 WHERE pool_id = ? AND is_confirmed = 1 
-                 ORDER BY block_number DESC LIMIT 1) as current_price,
-                (SELECT price FROM price_points 
-                 WHERE pool_id = ? AND is_confirmed = 1 
-                 AND block_timestamp <= ?
-                 ORDER BY block_number DESC LIMIT 1) as day_ago_price
+            SELECT * FROM price_points
+            WHERE pool_id = ? AND is_confirmed = 1
+            ORDER BY block_number DESC
+            LIMIT 1
             "#,
         )
         .bind(pool_id)
-        .bind(pool_id)
-        .bind(day_ago)
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(|e| {
             TrackerError::database(
-                "Failed to query 24h price change".to_string(),
+                "Failed to query latest price record".to_string(),
                 Some(Box::new(e)),
             )
         })?;
 
-        if let (Some(current), Some(old)) = (result.0, result.1) {
-            if old == 0.0 {
-                return Ok(0.0);
-            }
-            Ok(((current - old) / old) * 100.0)
-        } else {
-            Ok(0.0)
-        }
+        Ok(price)
     }
 
-    /// Get paginated price history.
-    pub async fn get_price_history_paginated(
+    /// Gets the last confirmed price at or before `block_number`; within a
+    /// block the last price wins.
+    ///
+    /// A single seek on `(pool_id, is_confirmed, block_number)`, so its cost
+    /// doesn't depend on how far back the block is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_price_at_block(
         &self,
         pool_id: i64,
-        from_ts: Option<i64>,
-        to_ts: Option<i64>,
-        limit: i64,
-        offset: i64,
-    ) -> Result<(Vec<PricePointRow>, u64), TrackerError> {
-        let from = from_ts.unwrap_or(0);
-        let to = to_ts.unwrap_or(i64::MAX);
-
-        let count = sqlx::query_as::<_, (i64,)>(
+        block_number: u64,
+    ) -> Result<Option<PricePointRow>, TrackerError> {
+        sqlx::query_as::<_, PricePointRow>(
             r#"
-                        SELECT COUNT(*) as count
-                        FROM price_points
-                        WHERE pool_id = ? AND is_confirmed = 1
-                            AND block_timestamp BETWEEN ? AND ?
-                        "#,
+            SELECT event_id, block_number, block_timestamp, tx_hash, price,
+                   price_exact, price_ewma, source, reserve0_human, reserve1_human
+            FROM price_points
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_number <= ?
+            ORDER BY block_number DESC, id DESC
+            LIMIT 1
+            "#,
         )
         .bind(pool_id)
-        .bind(from)
-        .bind(to)
+        .bind(i64::try_from(block_number).unwrap_or(i64::MAX))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price at block".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Gets the last confirmed price with a block timestamp at or before
+    /// `timestamp`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_price_at_timestamp(
+        &self,
+        pool_id: i64,
+        timestamp: i64,
+    ) -> Result<Option<PricePointRow>, TrackerError> {
+        sqlx::query_as::<_, PricePointRow>(
+            r#"
+            SELECT event_id, block_number, block_timestamp, tx_hash, price,
+                   price_exact, price_ewma, source, reserve0_human, reserve1_human
+            FROM price_points
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_timestamp <= ?
+            ORDER BY block_timestamp DESC, block_number DESC, id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(pool_id)
+        .bind(timestamp)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price at timestamp".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Gets the last confirmed price at or before each of `blocks`.
+    ///
+    /// Runs as one query: the requested blocks are merged into the pool's
+    /// price timeline (from the last price before the earliest request up to
+    /// the latest request) and a running window picks the nearest prior
+    /// price block for each; within that block the last price wins.
+    ///
+    /// Returns one entry per distinct requested block, in ascending order,
+    /// with `None` where no price precedes the block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_prices_at_blocks(
+        &self,
+        pool_id: i64,
+        blocks: &[u64],
+    ) -> Result<Vec<(u64, Option<PricePointRow>)>, TrackerError> {
+        use sqlx::{FromRow, Row};
+
+        let requested = serde_json::to_string(blocks).map_err(|e| {
+            TrackerError::database("Failed to encode block list".to_string(), Some(Box::new(e)))
+        })?;
+
+        let rows = sqlx::query(
+            r#"
+            WITH requested(block_number) AS (
+                SELECT DISTINCT CAST(value AS INTEGER) FROM json_each(?1)
+            ),
+            bounds AS (
+                SELECT
+                    (SELECT MAX(block_number) FROM price_points
+                     WHERE pool_id = ?2 AND is_confirmed = 1
+                       AND block_number <= (SELECT MIN(block_number) FROM requested)) AS lo,
+                    (SELECT MAX(block_number) FROM requested) AS hi
+            ),
+            timeline AS (
+                SELECT block_number, 1 AS is_request FROM requested
+                UNION ALL
+                SELECT pp.block_number, 0 FROM price_points pp, bounds
+                WHERE pp.pool_id = ?2 AND pp.is_confirmed = 1
+                  AND pp.block_number BETWEEN COALESCE(bounds.lo, 0) AND bounds.hi
+            ),
+            resolved AS (
+                SELECT block_number AS requested_block, is_request,
+                       MAX(CASE WHEN is_request = 0 THEN block_number END) OVER (
+                           ORDER BY block_number, is_request ROWS UNBOUNDED PRECEDING
+                       ) AS price_block
+                FROM timeline
+            )
+            SELECT r.requested_block, pp.event_id, pp.block_number, pp.block_timestamp,
+                   pp.tx_hash, pp.price, pp.price_exact, pp.price_ewma, pp.source,
+                   pp.reserve0_human, pp.reserve1_human
+            FROM resolved r
+            LEFT JOIN price_points pp ON pp.id = (
+                SELECT id FROM price_points
+                WHERE pool_id = ?2 AND is_confirmed = 1 AND block_number = r.price_block
+                ORDER BY id DESC
+                LIMIT 1
+            )
+            WHERE r.is_request = 1
+            ORDER BY r.requested_block
+            "#,
+        )
+        .bind(requested)
+        .bind(pool_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query prices at blocks".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        rows.iter()
+            .map(|row| {
+                let decode = |e| {
+                    TrackerError::database(
+                        "Failed to decode price at block".to_string(),
+                        Some(Box::new(e)),
+                    )
+                };
+                let block = row.try_get::<i64, _>("requested_block").map_err(decode)?;
+                let price = match row
+                    .try_get::<Option<i64>, _>("block_number")
+                    .map_err(decode)?
+                {
+                    Some(_) => Some(PricePointRow::from_row(row).map_err(decode)?),
+                    None => None,
+                };
+                Ok((u64::try_from(block).unwrap_or(0), price))
+            })
+            .collect()
+    }
+
+    /// Calculate 24-hour price change percentage.
+    ///
+    /// The price a day ago is the last one up to then: from price points
+    /// within its hour, else the close of the latest earlier hourly rollup
+    /// (or the price points themselves while the pool has stale rollups).
+    pub async fn get_24h_price_change(&self, pool_id: i64) -> Result<f64, TrackerError> {
+        let now = chrono::Utc::now().timestamp();
+        let day_ago = now - 86_400;
+
+        let result = sqlx::query_as::<_, (Option<f64>, Option<f64>)>(
+            r#"
+            SELECT
+                (SELECT price FROM price_points
+                 WHERE pool_id = ?1 AND is_confirmed = 1
+                 ORDER BY block_number DESC, id DESC LIMIT 1) as current_price,
+                COALESCE(
+                    (SELECT price FROM price_points
+                     WHERE pool_id = ?1 AND is_confirmed = 1
+                       AND block_timestamp BETWEEN ?2 AND ?3
+                     ORDER BY block_number DESC, id DESC LIMIT 1),
+                    (SELECT close FROM price_rollups
+                     WHERE pool_id = ?1 AND hour_start < ?2
+                       AND NOT EXISTS (SELECT 1 FROM price_rollups_stale WHERE pool_id = ?1)
+                     ORDER BY hour_start DESC LIMIT 1),
+                    (SELECT price FROM price_points
+                     WHERE pool_id = ?1 AND is_confirmed = 1 AND block_timestamp < ?2
+                     ORDER BY block_number DESC, id DESC LIMIT 1)
+                ) as day_ago_price
+            "#,
+        )
+        .bind(pool_id)
+        .bind(hour_floor(day_ago))
+        .bind(day_ago)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query 24h price change".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        if let (Some(current), Some(old)) = (result.0, result.1) {
+            if old == 0.0 {
+                return Ok(0.0);
+            }
+            Ok(((current - old) / old) * 100.0)
+        } else {
+            Ok(0.0)
+        }
+    }
+
+    /// Summarizes the confirmed price points of a pool between two block
+    /// timestamps and within `blocks` (inclusive), for cache validation of
+    /// history responses.
+    pub async fn get_price_history_version(
+        &self,
+        pool_id: i64,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+        blocks: BlockRange,
+    ) -> Result<PriceHistoryVersion, TrackerError> {
+        let (from_block, to_block) = blocks.bounds();
+        sqlx::query_as::<_, PriceHistoryVersion>(
+            r#"
+            SELECT COUNT(*) AS count, MAX(block_number) AS max_block, MAX(id) AS max_id,
+                   MAX(block_timestamp) AS last_timestamp
+            FROM price_points
+            WHERE pool_id = ? AND is_confirmed = 1
+              AND block_timestamp BETWEEN ? AND ?
+              AND block_number BETWEEN ? AND ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(from_ts.unwrap_or(0))
+        .bind(to_ts.unwrap_or(i64::MAX))
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price history version".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Get a page of confirmed price history between two block timestamps
+    /// and within `blocks`, newest first.
+    pub async fn get_price_history_paginated(
+        &self,
+        pool_id: i64,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+        blocks: BlockRange,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Page<PricePointRow>, TrackerError> {
+        let from = from_ts.unwrap_or(0);
+        let to = to_ts.unwrap_or(i64::MAX);
+        let (from_block, to_block) = blocks.bounds();
+
+        let count = sqlx::query_as::<_, (i64,)>(
+            r#"
+                        SELECT COUNT(*) as count
+                        FROM price_points
+                        WHERE pool_id = ? AND is_confirmed = 1
+                            AND block_timestamp BETWEEN ? AND ?
+                            AND block_number BETWEEN ? AND ?
+                        "#,
+        )
+        .bind(pool_id)
+        .bind(from)
+        .bind(to)
+        .bind(from_block)
+        .bind(to_block)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| {
@@ -742,11 +1365,12 @@ impl Repository {
 
         let prices = sqlx::query_as::<_, PricePointRow>(
             r#"
-            SELECT block_number, block_timestamp, tx_hash, price,
-                   reserve0_human, reserve1_human
+            SELECT event_id, block_number, block_timestamp, tx_hash, price,
+                   price_exact, price_ewma, source, reserve0_human, reserve1_human
             FROM price_points
             WHERE pool_id = ? AND is_confirmed = 1
               AND block_timestamp BETWEEN ? AND ?
+              AND block_number BETWEEN ? AND ?
             ORDER BY block_number DESC
             LIMIT ? OFFSET ?
             "#,
@@ -754,6 +1378,8 @@ impl Repository {
         .bind(pool_id)
         .bind(from)
         .bind(to)
+        .bind(from_block)
+        .bind(to_block)
         .bind(limit)
         .bind(offset)
         .fetch_all(&self.pool)
@@ -765,17 +1391,122 @@ impl Repository {
             )
         })?;
 
-        Ok((prices, count))
+        Ok(Page {
+            items: prices,
+            total: count,
+        })
+    }
+
+    /// Streams confirmed price history between two block timestamps and
+    /// within `blocks`, oldest first, without loading the whole range into
+    /// memory.
+    ///
+    /// Rows are read in keyset-paginated chunks of [`HISTORY_STREAM_CHUNK`],
+    /// so no read transaction stays open (and holds back WAL checkpoints)
+    /// while a slow client drains the stream. Prices confirmed while it runs
+    /// are included if they sort after the rows already read.
+    pub fn stream_price_history(
+        &self,
+        pool_id: i64,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+        blocks: BlockRange,
+    ) -> impl Stream<Item = Result<PricePointRow, TrackerError>> + Send + 'static {
+        self.stream_price_history_chunked(pool_id, from_ts, to_ts, blocks, HISTORY_STREAM_CHUNK)
+    }
+
+    fn stream_price_history_chunked(
+        &self,
+        pool_id: i64,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+        blocks: BlockRange,
+        chunk: i64,
+    ) -> impl Stream<Item = Result<PricePointRow, TrackerError>> + Send + 'static {
+        let repo = self.clone();
+        let from = from_ts.unwrap_or(0);
+        let to = to_ts.unwrap_or(i64::MAX);
+        let (from_block, to_block) = blocks.bounds();
+
+        // The cursor is the last row's (block_number, tx_hash), `None` once
+        // a short chunk ends the range
+        stream::try_unfold(Some((-1_i64, String::new())), move |cursor| {
+            let repo = repo.clone();
+            async move {
+                let Some((after_block, after_tx)) = cursor else {
+                    return Ok::<_, TrackerError>(None);
+                };
+                let rows = sqlx::query_as::<_, PricePointRow>(
+                    r#"
+                    SELECT event_id, block_number, block_timestamp, tx_hash, price,
+                           price_exact, price_ewma, source, reserve0_human, reserve1_human
+                    FROM price_points
+                    WHERE pool_id = ? AND is_confirmed = 1
+                      AND block_timestamp BETWEEN ? AND ?
+                      AND block_number BETWEEN ? AND ?
+                      AND (block_number, tx_hash) > (?, ?)
+                    ORDER BY block_number ASC, tx_hash ASC
+                    LIMIT ?
+                    "#,
+                )
+                .bind(pool_id)
+                .bind(from)
+                .bind(to)
+                .bind(from_block)
+                .bind(to_block)
+                .bind(after_block)
+                .bind(after_tx)
+                .bind(chunk)
+                .fetch_all(&repo.pool)
+                .await
+                .map_err(|e| {
+                    TrackerError::database(
+                        "Failed to stream price history".to_string(),
+                        Some(Box::new(e)),
+                    )
+                })?;
+
+                let next = match rows.last() {
+                    Some(last) if i64::try_from(rows.len()).is_ok_and(|len| len == chunk) => {
+                        Some((last.block_number, last.tx_hash.to_string()))
+                    }
+                    _ => None,
+                };
+                Ok(Some((stream::iter(rows).map(Ok::<_, TrackerError>), next)))
+            }
+        })
+        .try_flatten()
     }
 
-    /// Get statistics for a time period.
+    /// Get statistics for a time period, within `blocks`.
+    ///
+    /// Whole hours are read from the `price_rollups` maintained on write, so
+    /// only the partial first hour touches price points. With
+    /// `spike_filter_bps`, reverted single-block spikes are left out (see
+    /// [`crate::spikes`]); as a spike only shows against its neighbours, the
+    /// period is then aggregated from price points, as it is for a bounded
+    /// `blocks` range, which doesn't align with the hourly rollups, and while
+    /// any of the pool's rollups are stale. Such stats cost a scan of the
+    /// period's price points, however long it is.
     pub async fn get_stats_for_period(
         &self,
         pool_id: i64,
         from_timestamp: i64,
+        spike_filter_bps: Option<u32>,
+        blocks: BlockRange,
     ) -> Result<StatsRow, TrackerError> {
-        let stats = sqlx::query_as::<_, StatsRow>(
+        if spike_filter_bps.is_none() && blocks.is_all() && !self.has_stale_rollups(pool_id).await?
+        {
+            return self.get_rollup_stats(pool_id, from_timestamp).await;
+        }
+
+        let (from_block, to_block) = blocks.bounds();
+        let stats = sqlx::query_as::<_, StatsRow>(&format!(
             r#"
+            WITH {},
+            in_blocks AS (
+                SELECT * FROM prices WHERE block_number BETWEEN ?5 AND ?6
+            )
             SELECT 
                 COUNT(*) as total_events,
                 MIN(price) as min_price,
@@ -783,18 +1514,18 @@ impl Repository {
                 AVG(price) as avg_price,
                 MIN(block_timestamp) as first_timestamp,
                 MAX(block_timestamp) as last_timestamp,
-                (SELECT price FROM price_points 
-                 WHERE pool_id = ? AND block_timestamp >= ?
-                 ORDER BY block_number ASC LIMIT 1) as first_price
-            FROM price_points
-            WHERE pool_id = ? AND is_confirmed = 1
-              AND block_timestamp >= ?
+                (SELECT price FROM in_blocks
+                 ORDER BY block_number ASC, id ASC LIMIT 1) as first_price
+            FROM in_blocks
             "#,
-        )
-        .bind(pool_id)
-        .bind(from_timestamp)
+            confirmed_prices_cte(spike_filter_bps.is_some())
+        ))
         .bind(pool_id)
         .bind(from_timestamp)
+        .bind(i64::MAX)
+        .bind(spike_filter_bps)
+        .bind(from_block)
+        .bind(to_block)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| {
@@ -804,16 +1535,251 @@ impl Repository {
         Ok(stats)
     }
 
+    /// Whether any of a pool's hourly rollups wait for a rebuild.
+    async fn has_stale_rollups(&self, pool_id: i64) -> Result<bool, TrackerError> {
+        sqlx::query_as::<_, (bool,)>(
+            "SELECT EXISTS (SELECT 1 FROM price_rollups_stale WHERE pool_id = ?)",
+        )
+        .bind(pool_id)
+        .fetch_one(&self.pool)
+        .await
+        .map(|row| row.0)
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to check price rollups".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Get statistics since `from_timestamp` from the hourly rollups.
+    ///
+    /// Price points before the first whole hour are aggregated directly.
+    async fn get_rollup_stats(
+        &self,
+        pool_id: i64,
+        from_timestamp: i64,
+    ) -> Result<StatsRow, TrackerError> {
+        let first_hour = hour_ceil(from_timestamp);
+
+        sqlx::query_as::<_, StatsRow>(
+            r#"
+            WITH parts AS (
+                SELECT COUNT(*) AS samples, MIN(price) AS low, MAX(price) AS high,
+                       SUM(price) AS price_sum, MIN(block_timestamp) AS first_timestamp,
+                       MAX(block_timestamp) AS last_timestamp
+                FROM price_points
+                WHERE pool_id = ?1 AND is_confirmed = 1
+                  AND block_timestamp >= ?2 AND block_timestamp < ?3
+                UNION ALL
+                SELECT SUM(samples), MIN(low), MAX(high), SUM(price_sum),
+                       MIN(first_timestamp), MAX(last_timestamp)
+                FROM price_rollups
+                WHERE pool_id = ?1 AND hour_start >= ?3
+            )
+            SELECT
+                COALESCE(SUM(samples), 0) AS total_events,
+                MIN(low) AS min_price,
+                MAX(high) AS max_price,
+                SUM(price_sum) / SUM(samples) AS avg_price,
+                MIN(first_timestamp) AS first_timestamp,
+                MAX(last_timestamp) AS last_timestamp,
+                COALESCE(
+                    (SELECT price FROM price_points
+                     WHERE pool_id = ?1 AND is_confirmed = 1
+                       AND block_timestamp >= ?2 AND block_timestamp < ?3
+                     ORDER BY block_number ASC, id ASC LIMIT 1),
+                    (SELECT open FROM price_rollups
+                     WHERE pool_id = ?1 AND hour_start >= ?3
+                     ORDER BY hour_start ASC LIMIT 1)
+                ) AS first_price
+            FROM parts
+            "#,
+        )
+        .bind(pool_id)
+        .bind(from_timestamp)
+        .bind(first_hour)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| TrackerError::database("Failed to query stats".to_string(), Some(Box::new(e))))
+    }
+
+    /// Aggregate confirmed price points into OHLC candles.
+    ///
+    /// Buckets are aligned to multiples of `interval_secs` since the unix epoch.
+    /// Empty buckets are omitted. Returns the most recent `limit` candles,
+    /// oldest first, from the prices within both the timestamps and `blocks`.
+    /// With
+    /// `spike_filter_bps`, reverted single-block spikes are left out (see
+    /// [`crate::spikes`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `interval_secs` is not positive or the query fails.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_candles(
+        &self,
+        pool_id: i64,
+        interval_secs: i64,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+        blocks: BlockRange,
+        limit: i64,
+        spike_filter_bps: Option<u32>,
+    ) -> Result<Vec<CandleRow>, TrackerError> {
+        if interval_secs <= 0 {
+            return Err(TrackerError::state(
+                "Candle interval must be positive",
+                None,
+            ));
+        }
+        let (from_block, to_block) = blocks.bounds();
+
+        let candles = sqlx::query_as::<_, CandleRow>(&format!(
+            r#"
+            WITH {},
+            bucketed AS (
+                SELECT
+                    (block_timestamp / ?5) * ?5 AS bucket_start,
+                    price,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY block_timestamp / ?5
+                        ORDER BY block_number ASC, id ASC
+                    ) AS rn_first,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY block_timestamp / ?5
+                        ORDER BY block_number DESC, id DESC
+                    ) AS rn_last
+                FROM prices
+                WHERE block_number BETWEEN ?7 AND ?8
+            )
+            SELECT * FROM (
+                SELECT
+                    bucket_start,
+                    MAX(CASE WHEN rn_first = 1 THEN price END) AS open,
+                    MAX(price) AS high,
+                    MIN(price) AS low,
+                    MAX(CASE WHEN rn_last = 1 THEN price END) AS close,
+                    COUNT(*) AS samples,
+                    SUM(price) AS price_sum
+                FROM bucketed
+                GROUP BY bucket_start
+                ORDER BY bucket_start DESC
+                LIMIT ?6
+            )
+            ORDER BY bucket_start ASC
+            "#,
+            confirmed_prices_cte(spike_filter_bps.is_some())
+        ))
+        .bind(pool_id)
+        .bind(from_ts.unwrap_or(0))
+        .bind(to_ts.unwrap_or(i64::MAX))
+        .bind(spike_filter_bps)
+        .bind(interval_secs)
+        .bind(limit)
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query candles".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(candles)
+    }
+
+    /// Aggregate confirmed price points into one value per time bucket.
+    ///
+    /// Buckets are aligned like [`Self::get_candles`] and cover
+    /// `from_ts..=to_ts`, counting only prices within `blocks`; empty buckets
+    /// are omitted. Prices are converted to
+    /// `direction` before they are aggregated. Returns `(bucket_start, value)`
+    /// pairs, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bucket_secs` is not positive or the query fails.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_price_timeseries(
+        &self,
+        pool_id: i64,
+        bucket_secs: i64,
+        agg: TimeseriesAgg,
+        direction: QuoteDirection,
+        from_ts: i64,
+        to_ts: i64,
+        blocks: BlockRange,
+    ) -> Result<Vec<(i64, f64)>, TrackerError> {
+        if bucket_secs <= 0 {
+            return Err(TrackerError::state(
+                "Time series bucket must be positive",
+                None,
+            ));
+        }
+
+        let price = if direction.is_inverse() {
+            "CASE WHEN price > 0 THEN 1.0 / price ELSE 0.0 END"
+        } else {
+            "price"
+        };
+        let value = match agg {
+            TimeseriesAgg::Avg => "AVG(price)",
+            TimeseriesAgg::Last => "MAX(CASE WHEN rn_last = 1 THEN price END)",
+            TimeseriesAgg::Max => "MAX(price)",
+        };
+        let (from_block, to_block) = blocks.bounds();
+        let points = sqlx::query_as::<_, (i64, f64)>(&format!(
+            r#"
+            WITH bucketed AS (
+                SELECT
+                    (block_timestamp / ?) * ? AS bucket_start,
+                    {price} AS price,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY block_timestamp / ?
+                        ORDER BY block_number DESC, id DESC
+                    ) AS rn_last
+                FROM price_points
+                WHERE pool_id = ? AND is_confirmed = 1
+                  AND block_timestamp BETWEEN ? AND ?
+                  AND block_number BETWEEN ? AND ?
+            )
+            SELECT bucket_start, {value} AS value
+            FROM bucketed
+            GROUP BY bucket_start
+            ORDER BY bucket_start ASC
+            "#
+        ))
+        .bind(bucket_secs)
+        .bind(bucket_secs)
+        .bind(bucket_secs)
+        .bind(pool_id)
+        .bind(from_ts)
+        .bind(to_ts)
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query time series".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(points)
+    }
+
     /// Get all pools with indexer metadata.
     pub async fn get_all_pools(&self) -> Result<Vec<PoolRow>, TrackerError> {
         let pools = sqlx::query_as::<_, PoolRow>(
             r#"
             SELECT p.id, p.name, p.address, p.token0_symbol, p.token0_address, p.token0_decimals,
-                   p.token1_symbol, p.token1_address, p.token1_decimals,
+                   p.token1_symbol, p.token1_address, p.token1_decimals, p.protocol,
+                   p.pool_type, p.quote_direction, p.enabled, p.start_block, p.spike_filter_bps,
                    COALESCE(s.last_indexed_block, 0) as last_indexed_block,
-                   COALESCE(s.total_events_processed, 0) as total_events
+                   COALESCE(s.total_events_processed, 0) as total_events,
+                   t.status as task_status, t.restarts as task_restarts,
+                   t.last_error as task_error, t.updated_at as task_updated_at
             FROM pools p
             LEFT JOIN indexer_state s ON p.id = s.pool_id
+            LEFT JOIN indexer_tasks t ON p.id = t.pool_id
             "#,
         )
         .fetch_all(&self.pool)
@@ -825,33 +1791,542 @@ impl Repository {
         Ok(pools)
     }
 
-    /// Get recent sync events for a pool.
-    pub async fn get_recent_events(
+    /// Get a page of the pools visible in `scope`, in the order they were
+    /// added.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_pools_page(
         &self,
-        pool_id: i64,
+        scope: &PoolScope,
         limit: i64,
-    ) -> Result<Vec<SyncEventRow>, TrackerError> {
-        let events = sqlx::query_as::<_, SyncEventRow>(
+        offset: i64,
+    ) -> Result<Page<PoolRow>, TrackerError> {
+        let scope = scope.json_ids();
+        let pools = sqlx::query_as::<_, PoolRow>(
             r#"
-            SELECT block_number, block_timestamp, tx_hash, reserve0, reserve1
-            FROM sync_events
-            WHERE pool_id = ?
-            ORDER BY block_number DESC, log_index DESC
-            LIMIT ?
+            SELECT p.id, p.name, p.address, p.token0_symbol, p.token0_address, p.token0_decimals,
+                   p.token1_symbol, p.token1_address, p.token1_decimals, p.protocol,
+                   p.pool_type, p.quote_direction, p.enabled, p.start_block, p.spike_filter_bps,
+                   COALESCE(s.last_indexed_block, 0) as last_indexed_block,
+                   COALESCE(s.total_events_processed, 0) as total_events,
+                   t.status as task_status, t.restarts as task_restarts,
+                   t.last_error as task_error, t.updated_at as task_updated_at
+            FROM pools p
+            LEFT JOIN indexer_state s ON p.id = s.pool_id
+            LEFT JOIN indexer_tasks t ON p.id = t.pool_id
+            WHERE ?1 IS NULL OR p.id IN (SELECT value FROM json_each(?1))
+            ORDER BY p.id
+            LIMIT ?2 OFFSET ?3
             "#,
         )
-        .bind(pool_id)
+        .bind(&scope)
         .bind(limit)
+        .bind(offset)
         .fetch_all(&self.pool)
         .await
         .map_err(|e| {
-            TrackerError::database(
-                "Failed to query recent events".to_string(),
-                Some(Box::new(e)),
-            )
+            TrackerError::database("Failed to query pools".to_string(), Some(Box::new(e)))
         })?;
 
-        Ok(events)
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pools WHERE ?1 IS NULL OR id IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(&scope)
+        .fetch_one(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database("Failed to count pools".to_string(), Some(Box::new(e)))
+            })?;
+
+        Ok(Page {
+            items: pools,
+            total: u64::try_from(total).unwrap_or_default(),
+        })
+    }
+
+    /// Get recent sync events for a pool, within `blocks`.
+    pub async fn get_recent_events(
+        &self,
+        pool_id: i64,
+        blocks: BlockRange,
+        limit: i64,
+    ) -> Result<Vec<SyncEventRow>, TrackerError> {
+        let (from_block, to_block) = blocks.bounds();
+        let events = sqlx::query_as::<_, SyncEventRow>(
+            r#"
+            SELECT event_id, block_number, block_timestamp, tx_hash, log_index, reserve0, reserve1
+            FROM sync_events
+            WHERE pool_id = ? AND block_number BETWEEN ? AND ?
+            ORDER BY block_number DESC, log_index DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(from_block)
+        .bind(to_block)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query recent events".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(events)
+    }
+
+    /// Get the last confirmed sync event at or before `block_number`, i.e.
+    /// the event that set the pool's reserves as of that block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_sync_event_at(
+        &self,
+        pool_id: i64,
+        block_number: u64,
+    ) -> Result<Option<SyncEventRow>, TrackerError> {
+        let event = sqlx::query_as::<_, SyncEventRow>(
+            r#"
+            SELECT event_id, block_number, block_timestamp, tx_hash, log_index, reserve0, reserve1
+            FROM sync_events
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_number <= ?
+            ORDER BY block_number DESC, log_index DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(pool_id)
+        .bind(i64::try_from(block_number).unwrap_or(i64::MAX))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query sync event at block".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        // An archived event is only later if the block's raw rows were archived
+        let archived = self.get_archived_event_at(pool_id, block_number).await?;
+        Ok(match (event, archived) {
+            (Some(event), Some(archived))
+                if (archived.block_number, i64::from(archived.log_index))
+                    > (event.block_number, event.log_index) =>
+            {
+                Some(SyncEventRow::from(&archived))
+            }
+            (None, archived) => archived.as_ref().map(SyncEventRow::from),
+            (event, _) => event,
+        })
+    }
+
+    /// Get every confirmed sync event in `[from_block, to_block]`, in chain
+    /// order `(block_number, log_index)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_sync_events_in_blocks(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SyncEventRow>, TrackerError> {
+        let raw = sqlx::query_as::<_, SyncEventRow>(
+            r#"
+            SELECT event_id, block_number, block_timestamp, tx_hash, log_index, reserve0, reserve1
+            FROM sync_events
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_number BETWEEN ? AND ?
+            ORDER BY block_number ASC, log_index ASC
+            "#,
+        )
+        .bind(pool_id)
+        .bind(i64::try_from(from_block).unwrap_or(i64::MAX))
+        .bind(i64::try_from(to_block).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query sync events in block range".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        let archived = self
+            .get_archived_events(pool_id, from_block, to_block)
+            .await?;
+        if archived.is_empty() {
+            return Ok(raw);
+        }
+        let mut events: Vec<_> = raw;
+        events.extend(archived.iter().map(SyncEventRow::from));
+        events.sort_by_key(|e| (e.block_number, e.log_index));
+        events.dedup_by_key(|e| (e.block_number, e.log_index));
+        Ok(events)
+    }
+
+    /// Get a page of sync events using keyset pagination.
+    ///
+    /// Rows are ordered by `(block_number, log_index)`, ascending or descending.
+    /// When `after` is set, only events strictly past that position (in the
+    /// requested direction) are returned, so each page costs an index seek
+    /// regardless of how deep into the history the client is. `total` counts
+    /// all of the pool's events within `blocks`.
+    ///
+    /// Archived events are merged in, decompressing only the segments the
+    /// page reaches. An event written again into an archived segment counts
+    /// twice in `total` until the next archive run merges it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_events_page(
+        &self,
+        pool_id: i64,
+        blocks: BlockRange,
+        after: Option<EventCursor>,
+        limit: i64,
+        descending: bool,
+    ) -> Result<Page<SyncEventRow>, TrackerError> {
+        let query = match (after.is_some(), descending) {
+            (false, false) => {
+                r#"
+                SELECT event_id, block_number, block_timestamp, tx_hash, log_index, reserve0, reserve1
+                FROM sync_events
+                WHERE pool_id = ? AND block_number BETWEEN ? AND ?
+                ORDER BY block_number ASC, log_index ASC
+                LIMIT ?
+                "#
+            }
+            (false, true) => {
+                r#"
+                SELECT event_id, block_number, block_timestamp, tx_hash, log_index, reserve0, reserve1
+                FROM sync_events
+                WHERE pool_id = ? AND block_number BETWEEN ? AND ?
+                ORDER BY block_number DESC, log_index DESC
+                LIMIT ?
+                "#
+            }
+            (true, false) => {
+                r#"
+                SELECT event_id, block_number, block_timestamp, tx_hash, log_index, reserve0, reserve1
+                FROM sync_events
+                WHERE pool_id = ? AND block_number BETWEEN ? AND ?
+                  AND (block_number, log_index) > (?, ?)
+                ORDER BY block_number ASC, log_index ASC
+                LIMIT ?
+                "#
+            }
+            (true, true) => {
+                r#"
+                SELECT event_id, block_number, block_timestamp, tx_hash, log_index, reserve0, reserve1
+                FROM sync_events
+                WHERE pool_id = ? AND block_number BETWEEN ? AND ?
+                  AND (block_number, log_index) < (?, ?)
+                ORDER BY block_number DESC, log_index DESC
+                LIMIT ?
+                "#
+            }
+        };
+
+        let (from_block, to_block) = blocks.bounds();
+        let mut q = sqlx::query_as::<_, SyncEventRow>(query)
+            .bind(pool_id)
+            .bind(from_block)
+            .bind(to_block);
+        if let Some(cursor) = after {
+            let block = i64::try_from(cursor.block_number).map_err(|e| {
+                TrackerError::decoding("Cursor block number out of range", Some(Box::new(e)))
+            })?;
+            q = q.bind(block).bind(i64::from(cursor.log_index));
+        }
+
+        let mut events = q.bind(limit).fetch_all(&self.pool).await.map_err(|e| {
+            TrackerError::database("Failed to query events page".to_string(), Some(Box::new(e)))
+        })?;
+
+        let archived = self
+            .get_archived_events_page(pool_id, blocks, after, limit, descending)
+            .await?;
+        if !archived.is_empty() {
+            // Raw rows come first, so they win over archived copies of an event
+            events.extend(archived);
+            events.sort_by_key(|e| (e.block_number, e.log_index));
+            events.dedup_by_key(|e| (e.block_number, e.log_index));
+            if descending {
+                events.reverse();
+            }
+            events.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        }
+
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sync_events WHERE pool_id = ? AND block_number BETWEEN ? AND ?",
+        )
+        .bind(pool_id)
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to count events".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(Page {
+            items: events,
+            total: u64::try_from(total).unwrap_or_default()
+                + self.count_archived_events(pool_id, blocks).await?,
+        })
+    }
+
+    /// Get a pool by its database ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_pool_by_id(&self, id: i64) -> Result<Option<PoolRecord>, TrackerError> {
+        let pool = sqlx::query_as::<_, PoolRecord>("SELECT * FROM pools WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database("Failed to query pool by id".to_string(), Some(Box::new(e)))
+            })?;
+
+        Ok(pool)
+    }
+
+    /// Find a pool by a user-supplied identifier.
+    ///
+    /// Accepts a numeric database ID, a `0x` contract address, or a pool name
+    /// with `-` in place of `/` (e.g. `WETH-USDT`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_pool(&self, identifier: &str) -> Result<Option<PoolRecord>, TrackerError> {
+        if let Ok(id) = identifier.parse::<i64>() {
+            self.get_pool_by_id(id).await
+        } else if let Ok(address) = identifier.parse::<Address>() {
+            self.get_pool_by_address(address).await
+        } else {
+            self.get_pool_by_name(&identifier.replace('-', "/")).await
+        }
+    }
+
+    /// Records which protocol deployed a pool.
+    pub async fn set_pool_protocol(
+        &self,
+        pool_id: i64,
+        protocol: DexProtocol,
+    ) -> Result<(), TrackerError> {
+        sqlx::query("UPDATE pools SET protocol = ? WHERE id = ?")
+            .bind(protocol.as_str())
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to update pool protocol".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Records a pool's pricing formula.
+    pub async fn set_pool_type(
+        &self,
+        pool_id: i64,
+        pool_type: PoolType,
+    ) -> Result<(), TrackerError> {
+        sqlx::query("UPDATE pools SET pool_type = ? WHERE id = ?")
+            .bind(pool_type.to_string())
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database("Failed to update pool type".to_string(), Some(Box::new(e)))
+            })?;
+        Ok(())
+    }
+
+    /// Records the direction a pool's prices are reported in.
+    pub async fn set_pool_quote_direction(
+        &self,
+        pool_id: i64,
+        direction: QuoteDirection,
+    ) -> Result<(), TrackerError> {
+        sqlx::query("UPDATE pools SET quote_direction = ? WHERE id = ?")
+            .bind(direction.to_string())
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to update pool quote direction".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Enables or disables indexing of a pool.
+    ///
+    /// Returns false if no pool has this ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn set_pool_enabled(
+        &self,
+        pool_id: i64,
+        enabled: bool,
+    ) -> Result<bool, TrackerError> {
+        let result = sqlx::query("UPDATE pools SET enabled = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to update pool enabled flag".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Sets (or with `None`, clears) the block a pool without a checkpoint
+    /// starts indexing at.
+    ///
+    /// Returns false if no pool has this ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn set_pool_start_block(
+        &self,
+        pool_id: i64,
+        start_block: Option<u64>,
+    ) -> Result<bool, TrackerError> {
+        let result = sqlx::query("UPDATE pools SET start_block = ? WHERE id = ?")
+            .bind(start_block.map(|block| i64::try_from(block).unwrap_or(i64::MAX)))
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to update pool start block".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Sets (or with `None`, clears) the threshold in basis points from which
+    /// reverted single-block spikes are left out of a pool's candles and
+    /// stats.
+    ///
+    /// Returns false if no pool has this ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn set_pool_spike_filter(
+        &self,
+        pool_id: i64,
+        spike_filter_bps: Option<u32>,
+    ) -> Result<bool, TrackerError> {
+        let result = sqlx::query("UPDATE pools SET spike_filter_bps = ? WHERE id = ?")
+            .bind(spike_filter_bps)
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to update pool spike filter".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the pools to index, in the order they were added.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_enabled_pools(&self) -> Result<Vec<PoolRecord>, TrackerError> {
+        sqlx::query_as::<_, PoolRecord>("SELECT * FROM pools WHERE enabled = 1 ORDER BY id")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to query enabled pools".to_string(),
+                    Some(Box::new(e)),
+                )
+            })
+    }
+
+    /// Records the status of a pool's watch task, replacing the previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the write fails.
+    pub async fn upsert_indexer_task(&self, task: &IndexerTaskRecord) -> Result<(), TrackerError> {
+        sqlx::query(
+            r#"
+            INSERT INTO indexer_tasks (pool_id, status, last_block, restarts, last_error, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(pool_id) DO UPDATE SET
+                status = excluded.status,
+                last_block = COALESCE(excluded.last_block, indexer_tasks.last_block),
+                restarts = excluded.restarts,
+                last_error = excluded.last_error,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(task.pool_id)
+        .bind(task.status.as_str())
+        .bind(
+            task.last_block
+                .map(|block| i64::try_from(block).unwrap_or(i64::MAX)),
+        )
+        .bind(i64::from(task.restarts))
+        .bind(task.last_error.as_deref())
+        .bind(task.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to record indexer task status".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Returns whether a pool is indexed (true for unknown pools).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn is_pool_enabled(&self, pool_id: i64) -> Result<bool, TrackerError> {
+        let enabled: Option<bool> = sqlx::query_scalar("SELECT enabled FROM pools WHERE id = ?")
+            .bind(pool_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to query pool enabled flag".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(enabled.unwrap_or(true))
     }
 
     /// Ensure the default WETH/USDT pool exists for API testing.
@@ -885,7 +2360,7 @@ impl Repository {
             RETURNING id
             "#,
         )
-        .bind("0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852")
+        .bind(PoolAddress::from(UNISWAP_V2_WETH_USDT_PAIR))
         .bind("WETH/USDT")
         .bind("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2")
         .bind("WETH")
@@ -922,20 +2397,178 @@ impl Repository {
         Ok(pool_id)
     }
 
-    // ==================== INDEXER STATE OPERATIONS ====================
+    // ==================== ANALYTICS OPERATIONS ====================
 
-    /// Gets the indexer state for a specific pool.
+    /// Per-trader swap totals since `since_ts` and within `blocks`, by token1
+    /// volume descending.
     ///
-    /// Returns `None` if no state exists (first run).
-    pub async fn get_state(&self, pool_id: i64) -> Result<Option<IndexerState>, TrackerError> {
-        let state =
-            sqlx::query_as::<_, IndexerState>("SELECT * FROM indexer_state WHERE pool_id = ?")
-                .bind(pool_id)
-                .fetch_optional(&self.pool)
-                .await
-                .map_err(|e| {
-                    TrackerError::database(
-                        "Failed to query indexer state".to_string(),
+    /// The trader is the swap recipient. Only confirmed swaps are counted.
+    /// With `address`, only that trader's totals are returned.
+    pub async fn get_trader_totals(
+        &self,
+        pool_id: i64,
+        since_ts: i64,
+        blocks: BlockRange,
+        address: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<TraderTotalsRow>, TrackerError> {
+        let (from_block, to_block) = blocks.bounds();
+        sqlx::query_as::<_, TraderTotalsRow>(
+            r#"
+            SELECT recipient AS address,
+                   COUNT(*) AS swaps,
+                   SUM(CAST(amount0_in AS REAL)) AS amount0_in,
+                   SUM(CAST(amount1_in AS REAL)) AS amount1_in,
+                   SUM(CAST(amount0_out AS REAL)) AS amount0_out,
+                   SUM(CAST(amount1_out AS REAL)) AS amount1_out
+            FROM swap_events
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_timestamp >= ?
+              AND block_number BETWEEN ? AND ?
+              AND (? IS NULL OR recipient = ?)
+            GROUP BY recipient
+            ORDER BY SUM(CAST(amount1_in AS REAL) + CAST(amount1_out AS REAL)) DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(since_ts)
+        .bind(from_block)
+        .bind(to_block)
+        .bind(address)
+        .bind(address)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query trader totals".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Unique traders, swaps and token1 volume per UTC day since `since_ts`,
+    /// counting only swaps within `blocks`.
+    pub async fn get_daily_trader_counts(
+        &self,
+        pool_id: i64,
+        since_ts: i64,
+        blocks: BlockRange,
+    ) -> Result<Vec<DailyTradersRow>, TrackerError> {
+        let (from_block, to_block) = blocks.bounds();
+        sqlx::query_as::<_, DailyTradersRow>(
+            r#"
+            SELECT (block_timestamp / 86400) * 86400 AS day_start,
+                   COUNT(DISTINCT recipient) AS unique_traders,
+                   COUNT(*) AS swaps,
+                   SUM(CAST(amount1_in AS REAL) + CAST(amount1_out AS REAL)) AS volume1
+            FROM swap_events
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_timestamp >= ?
+              AND block_number BETWEEN ? AND ?
+            GROUP BY day_start
+            ORDER BY day_start
+            "#,
+        )
+        .bind(pool_id)
+        .bind(since_ts)
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query daily trader counts".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Swap inputs and average liquidity since `since_ts`, for fee APR
+    /// estimates.
+    ///
+    /// Only confirmed swaps and price points are counted.
+    pub async fn get_fee_window(
+        &self,
+        pool_id: i64,
+        since_ts: i64,
+    ) -> Result<FeeWindowRow, TrackerError> {
+        sqlx::query_as::<_, FeeWindowRow>(
+            r#"
+            SELECT s.swaps, s.amount0_in, s.amount1_in, p.avg_liquidity, p.first_timestamp
+            FROM (
+                SELECT COUNT(*) AS swaps,
+                       COALESCE(SUM(CAST(amount0_in AS REAL)), 0.0) AS amount0_in,
+                       COALESCE(SUM(CAST(amount1_in AS REAL)), 0.0) AS amount1_in
+                FROM swap_events
+                WHERE pool_id = ?1 AND is_confirmed = 1 AND block_timestamp >= ?2
+            ) s, (
+                SELECT AVG(reserve1_human + reserve0_human * price) AS avg_liquidity,
+                       MIN(block_timestamp) AS first_timestamp
+                FROM price_points
+                WHERE pool_id = ?1 AND is_confirmed = 1 AND block_timestamp >= ?2
+            ) p
+            "#,
+        )
+        .bind(pool_id)
+        .bind(since_ts)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query fee window".to_string(), Some(Box::new(e)))
+        })
+    }
+
+    /// Confirmed swaps since `since_ts` and within `blocks` in blocks with at
+    /// least three swaps, in block and log order, for sandwich detection.
+    pub async fn get_sandwich_candidates(
+        &self,
+        pool_id: i64,
+        since_ts: i64,
+        blocks: BlockRange,
+    ) -> Result<Vec<SwapEventRecord>, TrackerError> {
+        let (from_block, to_block) = blocks.bounds();
+        sqlx::query_as::<_, SwapEventRecord>(
+            r#"
+            SELECT * FROM swap_events
+            WHERE pool_id = ?1 AND is_confirmed = 1 AND block_timestamp >= ?2
+              AND block_number IN (
+                  SELECT block_number FROM swap_events
+                  WHERE pool_id = ?1 AND is_confirmed = 1 AND block_timestamp >= ?2
+                    AND block_number BETWEEN ?3 AND ?4
+                  GROUP BY block_number
+                  HAVING COUNT(*) >= 3
+              )
+            ORDER BY block_number, log_index
+            "#,
+        )
+        .bind(pool_id)
+        .bind(since_ts)
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query sandwich candidates".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    // ==================== INDEXER STATE OPERATIONS ====================
+
+    /// Gets the indexer state for a specific pool.
+    ///
+    /// Returns `None` if no state exists (first run).
+    pub async fn get_state(&self, pool_id: i64) -> Result<Option<IndexerState>, TrackerError> {
+        let state =
+            sqlx::query_as::<_, IndexerState>("SELECT * FROM indexer_state WHERE pool_id = ?")
+                .bind(pool_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| {
+                    TrackerError::database(
+                        "Failed to query indexer state".to_string(),
                         Some(Box::new(e)),
                     )
                 })?;
@@ -962,6 +2595,20 @@ impl Repository {
             total_events_processed,
         );
 
+        let mut conn = self.pool.acquire().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to acquire connection".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+        Self::write_state(&mut conn, &state).await
+    }
+
+    /// Creates or replaces a pool's indexer state on `conn`.
+    async fn write_state(
+        conn: &mut SqliteConnection,
+        state: &IndexerState,
+    ) -> Result<(), TrackerError> {
         sqlx::query(
             r#"
             INSERT INTO indexer_state (
@@ -983,7 +2630,7 @@ impl Repository {
         .bind(state.reorg_count)
         .bind(state.total_events_processed)
         .bind(state.last_updated_at)
-        .execute(&self.pool)
+        .execute(conn)
         .await
         .map_err(|e| {
             TrackerError::database(
@@ -995,6 +2642,140 @@ impl Repository {
         Ok(())
     }
 
+    /// Rebuilds the hourly rollups the `price_points` triggers marked stale
+    /// on `conn`, once for all rows a batch confirmed, invalidated or deleted.
+    async fn rebuild_stale_rollups(conn: &mut SqliteConnection) -> Result<(), TrackerError> {
+        for statement in [
+            "DELETE FROM price_rollups WHERE (pool_id, hour_start) IN \
+             (SELECT pool_id, hour_start FROM price_rollups_stale)",
+            "INSERT INTO price_rollups SELECT * FROM price_rollups_rebuilt",
+            "DELETE FROM price_rollups_stale",
+        ] {
+            sqlx::query(statement)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| {
+                    TrackerError::database(
+                        "Failed to rebuild price rollups".to_string(),
+                        Some(Box::new(e)),
+                    )
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one indexed batch of sync events, price points and swap events
+    /// and, with `state`, the pool's new indexer state in a single
+    /// transaction, so the state never moves past rows that aren't
+    /// committed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any write fails; nothing is written then.
+    pub async fn commit_batch(
+        &self,
+        events: &[SyncEventRecord],
+        prices: &[PricePointRecord],
+        swaps: &[SwapEventRecord],
+        state: Option<&IndexerState>,
+    ) -> Result<(), TrackerError> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        Self::write_sync_events(&mut tx, events).await?;
+        Self::write_price_point_rows(&mut tx, "price_points", prices).await?;
+        // A price rewritten by the upsert (two syncs in one transaction, a
+        // block re-landing after a reorg) marks its hour stale
+        Self::rebuild_stale_rollups(&mut tx).await?;
+        Self::write_swap_events(&mut tx, swaps).await?;
+        if let Some(state) = state {
+            Self::write_state(&mut tx, state).await?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the last block with a confirmed sync event or price point of a
+    /// pool, whatever the checkpoint says.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_last_written_block(&self, pool_id: i64) -> Result<Option<u64>, TrackerError> {
+        let block: Option<i64> = sqlx::query_scalar(
+            r#"
+            SELECT MAX(block_number) FROM (
+                SELECT MAX(block_number) AS block_number FROM sync_events
+                WHERE pool_id = ?1 AND is_confirmed = 1
+                UNION ALL
+                SELECT MAX(block_number) FROM price_points
+                WHERE pool_id = ?1 AND is_confirmed = 1
+            )
+            "#,
+        )
+        .bind(pool_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query last written block".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(block.and_then(|b| u64::try_from(b).ok()))
+    }
+
+    /// Get the hashes of up to `limit` of a pool's newest blocks at or before
+    /// `up_to_block` with confirmed sync events, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_event_block_hashes(
+        &self,
+        pool_id: i64,
+        up_to_block: u64,
+        limit: i64,
+    ) -> Result<Vec<(u64, String)>, TrackerError> {
+        let rows = sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT block_number, MAX(block_hash)
+            FROM sync_events
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_number <= ?
+            GROUP BY block_number
+            ORDER BY block_number DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(i64::try_from(up_to_block).unwrap_or(i64::MAX))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query event block hashes".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .rev()
+            .filter_map(|(block, hash)| Some((u64::try_from(block).ok()?, hash)))
+            .collect())
+    }
+
     // ==================== REORG OPERATIONS ====================
 
     /// Invalidates all data from a specific block onwards.
@@ -1054,6 +2835,23 @@ impl Repository {
             )
         })?;
 
+        Self::rebuild_stale_rollups(&mut tx).await?;
+
+        // Mark swap events as unconfirmed
+        sqlx::query(
+            "UPDATE swap_events SET is_confirmed = 0 WHERE pool_id = ? AND block_number >= ?",
+        )
+        .bind(pool_id)
+        .bind(from_block as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to invalidate swap events".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
         tx.commit().await.map_err(|e| {
             TrackerError::database(
                 "Failed to commit transaction".to_string(),
@@ -1104,6 +2902,22 @@ impl Repository {
             )
         })?;
 
+        Self::rebuild_stale_rollups(&mut tx).await?;
+
+        sqlx::query(
+            "UPDATE swap_events SET is_confirmed = 1 WHERE pool_id = ? AND block_number <= ? AND is_confirmed = 0",
+        )
+        .bind(pool_id)
+        .bind(up_to_block as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to confirm swap events".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
         tx.commit().await.map_err(|e| {
             TrackerError::database(
                 "Failed to commit transaction".to_string(),
@@ -1113,265 +2927,4436 @@ impl Repository {
 
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db::{create_pool, run_migrations};
+    // ==================== ID OPERATIONS ====================
 
-    async fn setup_test_db() -> Repository {
-        let pool = create_pool("sqlite::memory:")
-            .await
-            .expect("Failed to create pool");
-        run_migrations(&pool)
-            .await
-            .expect("Failed to run migrations");
-        Repository::new(pool)
-    }
+    /// Backfills deterministic IDs for rows written before `event_id` existed.
+    ///
+    /// Sync events are keyed by their own coordinates. Price points are keyed by
+    /// the last sync event of their transaction, which is the one whose reserves
+    /// they reflect. Rows whose stored hashes cannot be parsed are skipped.
+    ///
+    /// Returns the number of rows updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be queried or updated.
+    pub async fn backfill_event_ids(&self, chain_id: u64) -> Result<u64, TrackerError> {
+        let events = sqlx::query_as::<_, (i64, String, String, String, i64)>(
+            r#"
+            SELECT se.id, p.address, se.block_hash, se.tx_hash, se.log_index
+            FROM sync_events se
+            JOIN pools p ON p.id = se.pool_id
+            WHERE se.event_id IS NULL
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query events without IDs".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
 
-    #[tokio::test]
-    async fn test_ensure_pool_exists() {
-        let repo = setup_test_db().await;
+        let prices = sqlx::query_as::<_, (i64, String, String, String, i64)>(
+            r#"
+            SELECT pp.id, p.address, se.block_hash, pp.tx_hash, MAX(se.log_index)
+            FROM price_points pp
+            JOIN pools p ON p.id = pp.pool_id
+            JOIN sync_events se ON se.pool_id = pp.pool_id
+                AND se.block_number = pp.block_number
+                AND se.tx_hash = pp.tx_hash
+            WHERE pp.event_id IS NULL
+            GROUP BY pp.id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price points without IDs".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
 
-        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
-            .parse()
-            .unwrap();
-        let token0_addr: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
-            .parse()
-            .unwrap();
-        let token1_addr: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-            .parse()
-            .unwrap();
+        if events.is_empty() && prices.is_empty() {
+            return Ok(0);
+        }
 
-        let pool_id = repo
-            .ensure_pool_exists(
-                pool_addr,
-                Some("USDC-WETH".to_string()),
-                token0_addr,
-                Some("USDC".to_string()),
-                6,
-                token1_addr,
-                Some("WETH".to_string()),
-                18,
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        let mut updated = 0u64;
+        let batches = [
+            (RecordKind::SyncEvent, "sync_events", events),
+            (RecordKind::PricePoint, "price_points", prices),
+        ];
+
+        for (kind, table, rows) in batches {
+            for (row_id, pool_address, block_hash, tx_hash, log_index) in rows {
+                let Some(event_id) = Self::derive_stored_id(
+                    kind,
+                    chain_id,
+                    &pool_address,
+                    &block_hash,
+                    &tx_hash,
+                    log_index,
+                ) else {
+                    warn!(table, row_id, "Skipping row with unparseable hashes");
+                    continue;
+                };
+
+                sqlx::query(&format!("UPDATE {table} SET event_id = ? WHERE id = ?"))
+                    .bind(&event_id)
+                    .bind(row_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| {
+                        TrackerError::database(
+                            format!("Failed to backfill event ID in {table}"),
+                            Some(Box::new(e)),
+                        )
+                    })?;
+                updated += 1;
+            }
+        }
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
             )
-            .await
-            .expect("Failed to create pool");
+        })?;
 
-        assert!(pool_id > 0);
+        info!(updated, "Backfilled deterministic event IDs");
+        Ok(updated)
+    }
+
+    /// Derives a record ID from the string forms stored in the database.
+    fn derive_stored_id(
+        kind: RecordKind,
+        chain_id: u64,
+        pool_address: &str,
+        block_hash: &str,
+        tx_hash: &str,
+        log_index: i64,
+    ) -> Option<String> {
+        Some(derive_record_id(
+            kind,
+            chain_id,
+            pool_address.parse().ok()?,
+            block_hash.parse().ok()?,
+            tx_hash.parse().ok()?,
+            u32::try_from(log_index).ok()?,
+        ))
+    }
+
+    // ==================== DATA MIGRATION OPERATIONS ====================
+
+    /// Lists the progress of every data migration that has started, in the
+    /// order they started.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_data_migrations(&self) -> Result<Vec<DataMigrationRow>, TrackerError> {
+        sqlx::query_as::<_, DataMigrationRow>(
+            r#"
+            SELECT name, description, checkpoint, rows_done, started_at, updated_at, completed_at
+            FROM migrations_log
+            ORDER BY started_at, name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query data migrations".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Records a finished batch of a data migration: adds `rows` to its
+    /// count and moves its checkpoint, marking it complete if `completed`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn record_data_migration_batch(
+        &self,
+        name: &str,
+        description: &str,
+        checkpoint: Option<&str>,
+        rows: u64,
+        completed: bool,
+    ) -> Result<(), TrackerError> {
+        sqlx::query(
+            r#"
+            INSERT INTO migrations_log (name, description, checkpoint, rows_done, completed_at)
+            VALUES (?, ?, ?, ?, CASE WHEN ? THEN unixepoch() END)
+            ON CONFLICT(name) DO UPDATE SET
+                checkpoint = excluded.checkpoint,
+                rows_done = rows_done + excluded.rows_done,
+                updated_at = unixepoch(),
+                completed_at = excluded.completed_at
+            "#,
+        )
+        .bind(name)
+        .bind(description)
+        .bind(checkpoint)
+        .bind(i64::try_from(rows).unwrap_or(i64::MAX))
+        .bind(completed)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                format!("Failed to record progress of data migration {name}"),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Recomputes `reserve0_human` and `reserve1_human` of up to `limit`
+    /// price points after row `after_id` from their raw reserves and the
+    /// pool's token decimals.
+    ///
+    /// Returns the number of rows whose values changed and the last row ID
+    /// read (`None` once no rows are left).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be queried or updated.
+    pub async fn recompute_reserve_human(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<(u64, Option<i64>), TrackerError> {
+        let rows = sqlx::query_as::<_, (i64, String, String, f64, f64, i32, i32)>(
+            r#"
+            SELECT pp.id, pp.reserve0_raw, pp.reserve1_raw, pp.reserve0_human,
+                   pp.reserve1_human, p.token0_decimals, p.token1_decimals
+            FROM price_points pp
+            JOIN pools p ON p.id = pp.pool_id
+            WHERE pp.id > ?
+            ORDER BY pp.id
+            LIMIT ?
+            "#,
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price point reserves".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        let Some(last_id) = rows.last().map(|row| row.0) else {
+            return Ok((0, None));
+        };
+
+        let human = |raw: &str, decimals: i32| -> Option<f64> {
+            let amount = raw.parse::<U256>().ok()?;
+            crate::pricing::format_token_amount(amount, u8::try_from(decimals).ok()?)
+                .parse()
+                .ok()
+        };
+
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        let mut updated = 0u64;
+        for (row_id, raw0, raw1, stored0, stored1, decimals0, decimals1) in rows {
+            let (Some(human0), Some(human1)) = (human(&raw0, decimals0), human(&raw1, decimals1))
+            else {
+                warn!(row_id, "Skipping price point with unparseable reserves");
+                continue;
+            };
+            if human0.to_bits() == stored0.to_bits() && human1.to_bits() == stored1.to_bits() {
+                continue;
+            }
+
+            sqlx::query(
+                "UPDATE price_points SET reserve0_human = ?, reserve1_human = ? WHERE id = ?",
+            )
+            .bind(human0)
+            .bind(human1)
+            .bind(row_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to update human-readable reserves".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+            updated += 1;
+        }
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok((updated, Some(last_id)))
+    }
+
+    // ==================== INTEGRITY OPERATIONS ====================
+
+    /// Get the distinct blocks in `[from_block, to_block]` that have both a
+    /// confirmed sync event and a confirmed price point for a pool.
+    ///
+    /// Blocks missing either row are treated as not indexed by the
+    /// integrity checker.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_indexed_blocks(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<u64>, TrackerError> {
+        let rows = sqlx::query_as::<_, (i64,)>(
+            r#"
+            SELECT DISTINCT se.block_number
+            FROM sync_events se
+            WHERE se.pool_id = ? AND se.is_confirmed = 1
+              AND se.block_number BETWEEN ? AND ?
+              AND EXISTS (
+                  SELECT 1 FROM price_points pp
+                  WHERE pp.pool_id = se.pool_id
+                    AND pp.block_number = se.block_number
+                    AND pp.is_confirmed = 1
+              )
+            ORDER BY se.block_number
+            "#,
+        )
+        .bind(pool_id)
+        .bind(i64::try_from(from_block).unwrap_or(i64::MAX))
+        .bind(i64::try_from(to_block).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query indexed blocks".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(block,)| u64::try_from(block).ok())
+            .collect())
+    }
+
+    // ==================== CANDLE OPERATIONS ====================
+
+    /// Get confirmed price points after `after_block` with a block timestamp
+    /// of at least `from_ts`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_confirmed_prices_after(
+        &self,
+        pool_id: i64,
+        after_block: i64,
+        from_ts: i64,
+    ) -> Result<Vec<PricePointRow>, TrackerError> {
+        let prices = sqlx::query_as::<_, PricePointRow>(
+            r#"
+            SELECT event_id, block_number, block_timestamp, tx_hash, price,
+                   price_exact, price_ewma, source, reserve0_human, reserve1_human
+            FROM price_points
+            WHERE pool_id = ? AND is_confirmed = 1
+              AND block_number > ? AND block_timestamp >= ?
+            ORDER BY block_number ASC, id ASC
+            "#,
+        )
+        .bind(pool_id)
+        .bind(after_block)
+        .bind(from_ts)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query new price points".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(prices)
+    }
+
+    /// Inserts or replaces flushed candles in a single transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any write or the commit fails.
+    pub async fn upsert_candles(
+        &self,
+        pool_id: i64,
+        interval_secs: i64,
+        candles: &[CandleRow],
+    ) -> Result<(), TrackerError> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        for candle in candles {
+            sqlx::query(
+                r#"
+                INSERT INTO candles (
+                    pool_id, interval_secs, bucket_start, open, high, low, close,
+                    samples, price_sum
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (pool_id, interval_secs, bucket_start) DO UPDATE SET
+                    open = excluded.open,
+                    high = excluded.high,
+                    low = excluded.low,
+                    close = excluded.close,
+                    samples = excluded.samples,
+                    price_sum = excluded.price_sum,
+                    updated_at = unixepoch()
+                "#,
+            )
+            .bind(pool_id)
+            .bind(interval_secs)
+            .bind(candle.bucket_start)
+            .bind(candle.open)
+            .bind(candle.high)
+            .bind(candle.low)
+            .bind(candle.close)
+            .bind(candle.samples)
+            .bind(candle.price_sum)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                TrackerError::database("Failed to upsert candle".to_string(), Some(Box::new(e)))
+            })?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get flushed candles starting at or after `from_ts`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_stored_candles(
+        &self,
+        pool_id: i64,
+        interval_secs: i64,
+        from_ts: i64,
+    ) -> Result<Vec<CandleRow>, TrackerError> {
+        let candles = sqlx::query_as::<_, CandleRow>(
+            r#"
+            SELECT bucket_start, open, high, low, close, samples, price_sum
+            FROM candles
+            WHERE pool_id = ? AND interval_secs = ? AND bucket_start >= ?
+            ORDER BY bucket_start ASC
+            "#,
+        )
+        .bind(pool_id)
+        .bind(interval_secs)
+        .bind(from_ts)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query stored candles".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(candles)
+    }
+
+    // ==================== REPLAY OPERATIONS ====================
+
+    /// Get up to `limit` sync events at or after `from_block` and past
+    /// `after`, in chain order `(block_number, log_index)`, archived ones
+    /// included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_sync_event_records(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+        after: Option<EventCursor>,
+        limit: i64,
+    ) -> Result<Vec<SyncEventRecord>, TrackerError> {
+        let (after_block, after_log) = after.map_or((-1, -1), |cursor| {
+            (
+                i64::try_from(cursor.block_number).unwrap_or(i64::MAX),
+                i64::from(cursor.log_index),
+            )
+        });
+
+        let mut events = sqlx::query_as::<_, SyncEventRecord>(
+            r#"
+            SELECT id, event_id, pool_id, block_number, block_hash, block_timestamp, tx_hash,
+                   log_index, reserve0, reserve1, is_confirmed, created_at
+            FROM sync_events
+            WHERE pool_id = ? AND block_number >= ? AND (block_number, log_index) > (?, ?)
+            ORDER BY block_number ASC, log_index ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(i64::try_from(from_block).unwrap_or(i64::MAX))
+        .bind(after_block)
+        .bind(after_log)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query sync events".to_string(), Some(Box::new(e)))
+        })?;
+
+        // Archived segments past the cursor, until they hold a page
+        let start = after.map_or(from_block, |cursor| cursor.block_number.max(from_block));
+        let mut archived = Vec::new();
+        let mut segment = segment_start(start);
+        while archived.len() < usize::try_from(limit).unwrap_or(usize::MAX) {
+            let Some(next) = self.next_archive_segment(pool_id, segment).await? else {
+                break;
+            };
+            let to_block = next + ARCHIVE_SEGMENT_BLOCKS - 1;
+            archived.extend(
+                self.get_archived_events(pool_id, next.max(start), to_block)
+                    .await?
+                    .into_iter()
+                    .filter(|e| {
+                        (e.block_number, i64::from(e.log_index)) > (after_block, after_log)
+                    }),
+            );
+            segment = to_block + 1;
+        }
+        if archived.is_empty() {
+            return Ok(events);
+        }
+
+        events.extend(archived);
+        events.sort_by_key(|e| (e.block_number, e.log_index));
+        events.dedup_by_key(|e| (e.block_number, e.log_index));
+        events.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        Ok(events)
+    }
+
+    /// Get up to `limit` archived events within `blocks` and past `after`,
+    /// in the order of an [`get_events_page`](Self::get_events_page) page.
+    async fn get_archived_events_page(
+        &self,
+        pool_id: i64,
+        blocks: BlockRange,
+        after: Option<EventCursor>,
+        limit: i64,
+        descending: bool,
+    ) -> Result<Vec<SyncEventRow>, TrackerError> {
+        let (from, to) = blocks.bounds();
+        let (mut from, mut to) = (
+            u64::try_from(from).unwrap_or(0),
+            u64::try_from(to).unwrap_or(0),
+        );
+        let cursor = after.map(|cursor| {
+            if descending {
+                to = to.min(cursor.block_number);
+            } else {
+                from = from.max(cursor.block_number);
+            }
+            (
+                i64::try_from(cursor.block_number).unwrap_or(i64::MAX),
+                i64::from(cursor.log_index),
+            )
+        });
+        let past_cursor = |e: &SyncEventRecord| {
+            let position = (e.block_number, i64::from(e.log_index));
+            cursor.map_or(true, |cursor| {
+                if descending {
+                    position < cursor
+                } else {
+                    position > cursor
+                }
+            })
+        };
+
+        // Segments towards the end of the page, until they hold a page
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        let mut events = Vec::new();
+        let mut segment = segment_start(if descending { to } else { from });
+        while events.len() < limit {
+            let next = if descending {
+                self.previous_archive_segment(pool_id, segment).await?
+            } else {
+                self.next_archive_segment(pool_id, segment).await?
+            };
+            let Some(next) = next else {
+                break;
+            };
+            let end = next + ARCHIVE_SEGMENT_BLOCKS - 1;
+            if next > to || end < from {
+                break;
+            }
+
+            let mut page: Vec<_> = self
+                .get_archived_events(pool_id, next.max(from), end.min(to))
+                .await?
+                .iter()
+                .filter(|e| past_cursor(e))
+                .map(SyncEventRow::from)
+                .collect();
+            if descending {
+                page.reverse();
+                events.extend(page);
+                let Some(previous) = next.checked_sub(1) else {
+                    break;
+                };
+                segment = previous;
+            } else {
+                events.extend(page);
+                segment = end + 1;
+            }
+        }
+        Ok(events)
+    }
+
+    /// Count a pool's archived events within `blocks`.
+    ///
+    /// Segments wholly inside the range are counted from their metadata;
+    /// only the (at most two) segments it cuts through are decompressed.
+    async fn count_archived_events(
+        &self,
+        pool_id: i64,
+        blocks: BlockRange,
+    ) -> Result<u64, TrackerError> {
+        let map_err = |e: sqlx::Error| {
+            TrackerError::database(
+                "Failed to count archived events".to_string(),
+                Some(Box::new(e)),
+            )
+        };
+        let (from, to) = blocks.bounds();
+        let whole: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(SUM(event_count), 0) FROM event_archive
+            WHERE pool_id = ? AND first_block >= ? AND last_block <= ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let cut = sqlx::query_as::<_, (String, Vec<u8>)>(
+            r#"
+            SELECT codec, data FROM event_archive
+            WHERE pool_id = ?1 AND last_block >= ?2 AND first_block <= ?3
+              AND (first_block < ?2 OR last_block > ?3)
+            "#,
+        )
+        .bind(pool_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let mut total = u64::try_from(whole).unwrap_or_default();
+        for (codec, data) in cut {
+            total += decode_segment(&codec, &data)?
+                .iter()
+                .filter(|e| (from..=to).contains(&e.block_number))
+                .count() as u64;
+        }
+        Ok(total)
+    }
+
+    /// Get the start of a pool's last archive segment at or before
+    /// `to_segment`.
+    async fn previous_archive_segment(
+        &self,
+        pool_id: i64,
+        to_segment: u64,
+    ) -> Result<Option<u64>, TrackerError> {
+        let start: Option<i64> = sqlx::query_scalar(
+            "SELECT MAX(segment_start) FROM event_archive WHERE pool_id = ? AND segment_start <= ?",
+        )
+        .bind(pool_id)
+        .bind(i64::try_from(to_segment).unwrap_or(i64::MAX))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query archive segments".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+        Ok(start.and_then(|s| u64::try_from(s).ok()))
+    }
+
+    /// Get the start of a pool's first archive segment at or after
+    /// `from_segment`.
+    async fn next_archive_segment(
+        &self,
+        pool_id: i64,
+        from_segment: u64,
+    ) -> Result<Option<u64>, TrackerError> {
+        let start: Option<i64> = sqlx::query_scalar(
+            "SELECT MIN(segment_start) FROM event_archive WHERE pool_id = ? AND segment_start >= ?",
+        )
+        .bind(pool_id)
+        .bind(i64::try_from(from_segment).unwrap_or(i64::MAX))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query archive segments".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+        Ok(start.and_then(|s| u64::try_from(s).ok()))
+    }
+
+    /// Get the `getReserves()` snapshot prices at or after `from_block`,
+    /// oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_price_snapshots(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+    ) -> Result<Vec<PricePointRecord>, TrackerError> {
+        let snapshots = sqlx::query_as::<_, PricePointRecord>(
+            r#"
+            SELECT id, event_id, pool_id, block_number, block_timestamp, tx_hash, price,
+                   price_exact, price_ewma, source, reserve0_raw, reserve1_raw,
+                   reserve0_human, reserve1_human, is_confirmed, created_at
+            FROM price_points
+            WHERE pool_id = ? AND source = ? AND block_number >= ?
+            ORDER BY block_number ASC, id ASC
+            "#,
+        )
+        .bind(pool_id)
+        .bind(PRICE_SOURCE_CALL)
+        .bind(i64::try_from(from_block).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price snapshots".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(snapshots)
+    }
+
+    /// Deletes a pool's rows from the `price_points_replay` shadow table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub async fn clear_replay_prices(&self, pool_id: i64) -> Result<u64, TrackerError> {
+        let result = sqlx::query("DELETE FROM price_points_replay WHERE pool_id = ?")
+            .bind(pool_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to clear replayed prices".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Upserts recomputed prices into the `price_points_replay` shadow table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any write or the commit fails.
+    pub async fn insert_replay_prices(
+        &self,
+        prices: &[PricePointRecord],
+    ) -> Result<(), TrackerError> {
+        self.insert_price_point_rows("price_points_replay", prices)
+            .await
+    }
+
+    /// Compares a pool's replayed prices with its stored prices in
+    /// `[from_block, to_block]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database query fails.
+    pub async fn compare_replay_prices(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<ReplayDiff, TrackerError> {
+        let from = i64::try_from(from_block).unwrap_or(i64::MAX);
+        let to = i64::try_from(to_block).unwrap_or(i64::MAX);
+        let map_err = |e: sqlx::Error| {
+            TrackerError::database(
+                "Failed to compare replayed prices".to_string(),
+                Some(Box::new(e)),
+            )
+        };
+
+        let (changed, max_price_change) = sqlx::query_as::<_, (i64, Option<f64>)>(
+            r#"
+            SELECT COUNT(*), MAX(ABS(r.price - p.price))
+            FROM price_points_replay r
+            JOIN price_points p ON p.pool_id = r.pool_id
+                AND p.block_number = r.block_number AND p.tx_hash = r.tx_hash
+            WHERE r.pool_id = ?
+              AND (r.price <> p.price OR r.price_exact IS NOT p.price_exact
+                   OR r.price_ewma IS NOT p.price_ewma)
+            "#,
+        )
+        .bind(pool_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let added: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM price_points_replay r
+            WHERE r.pool_id = ? AND NOT EXISTS (
+                SELECT 1 FROM price_points p
+                WHERE p.pool_id = r.pool_id AND p.block_number = r.block_number
+                  AND p.tx_hash = r.tx_hash
+            )
+            "#,
+        )
+        .bind(pool_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let removed: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM price_points p
+            WHERE p.pool_id = ? AND p.block_number BETWEEN ? AND ? AND NOT EXISTS (
+                SELECT 1 FROM price_points_replay r
+                WHERE r.pool_id = p.pool_id AND r.block_number = p.block_number
+                  AND r.tx_hash = p.tx_hash
+            )
+            "#,
+        )
+        .bind(pool_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        Ok(ReplayDiff {
+            changed: u64::try_from(changed).unwrap_or_default(),
+            added: u64::try_from(added).unwrap_or_default(),
+            removed: u64::try_from(removed).unwrap_or_default(),
+            max_price_change: max_price_change.unwrap_or(0.0),
+        })
+    }
+
+    /// Replaces a pool's stored prices in `[from_block, to_block]` with its
+    /// replayed prices and empties the shadow table, in one transaction.
+    /// Returns the number of prices written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any statement or the commit fails; nothing is
+    /// changed then.
+    pub async fn apply_replay_prices(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<u64, TrackerError> {
+        let map_err = |e: sqlx::Error| {
+            TrackerError::database(
+                "Failed to apply replayed prices".to_string(),
+                Some(Box::new(e)),
+            )
+        };
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        sqlx::query("DELETE FROM price_points WHERE pool_id = ? AND block_number BETWEEN ? AND ?")
+            .bind(pool_id)
+            .bind(i64::try_from(from_block).unwrap_or(i64::MAX))
+            .bind(i64::try_from(to_block).unwrap_or(i64::MAX))
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+
+        let written = sqlx::query(
+            r#"
+            INSERT INTO price_points (pool_id, block_number, block_timestamp, tx_hash, price,
+                reserve0_raw, reserve1_raw, reserve0_human, reserve1_human, is_confirmed,
+                created_at, event_id, price_exact, price_ewma, source)
+            SELECT pool_id, block_number, block_timestamp, tx_hash, price,
+                reserve0_raw, reserve1_raw, reserve0_human, reserve1_human, is_confirmed,
+                created_at, event_id, price_exact, price_ewma, source
+            FROM price_points_replay
+            WHERE pool_id = ?
+            ORDER BY block_number ASC, id ASC
+            "#,
+        )
+        .bind(pool_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(map_err)?
+        .rows_affected();
+
+        Self::rebuild_stale_rollups(&mut tx).await?;
+
+        sqlx::query("DELETE FROM price_points_replay WHERE pool_id = ?")
+            .bind(pool_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(written)
+    }
+
+    /// Deletes a pool's flushed candles of one width starting at or after
+    /// `from_ts`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub async fn delete_stored_candles_from(
+        &self,
+        pool_id: i64,
+        interval_secs: i64,
+        from_ts: i64,
+    ) -> Result<u64, TrackerError> {
+        let result = sqlx::query(
+            "DELETE FROM candles WHERE pool_id = ? AND interval_secs = ? AND bucket_start >= ?",
+        )
+        .bind(pool_id)
+        .bind(interval_secs)
+        .bind(from_ts)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to delete candles".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ==================== ARCHIVE OPERATIONS ====================
+
+    /// Moves a pool's confirmed sync events in whole segments below
+    /// `before_block` into the compressed archive (see [`super::archive`]).
+    ///
+    /// Each segment is written and its rows deleted in one transaction;
+    /// events already archived in a segment are merged with the new ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails or a segment can't be encoded.
+    /// Segments archived before the failure stay archived.
+    pub async fn archive_sync_events(
+        &self,
+        pool_id: i64,
+        before_block: u64,
+    ) -> Result<ArchiveReport, TrackerError> {
+        let segment_blocks = i64::try_from(ARCHIVE_SEGMENT_BLOCKS).unwrap_or(i64::MAX);
+        let starts: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT block_number / ?1 * ?1
+            FROM sync_events
+            WHERE pool_id = ?2 AND is_confirmed = 1 AND block_number < ?3
+            ORDER BY 1
+            "#,
+        )
+        .bind(segment_blocks)
+        .bind(pool_id)
+        .bind(i64::try_from(before_block).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query archivable events".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        let mut report = ArchiveReport::default();
+        for start in starts.into_iter().filter_map(|s| u64::try_from(s).ok()) {
+            // The segment holding `before_block` isn't complete yet
+            if start + ARCHIVE_SEGMENT_BLOCKS > before_block {
+                break;
+            }
+            let (events, raw_bytes, compressed_bytes) =
+                self.archive_segment(pool_id, start).await?;
+            report.segments += 1;
+            report.events += events;
+            report.raw_bytes += raw_bytes;
+            report.compressed_bytes += compressed_bytes;
+        }
+
+        Ok(report)
+    }
+
+    /// Archives the confirmed events of the segment starting at `start`,
+    /// returning the events moved and the segment's raw and compressed size.
+    async fn archive_segment(
+        &self,
+        pool_id: i64,
+        start: u64,
+    ) -> Result<(u64, u64, u64), TrackerError> {
+        let map_err = |e| {
+            TrackerError::database(
+                "Failed to archive sync events".to_string(),
+                Some(Box::new(e)),
+            )
+        };
+        let first = i64::try_from(start).unwrap_or(i64::MAX);
+        let last = i64::try_from(start + ARCHIVE_SEGMENT_BLOCKS - 1).unwrap_or(i64::MAX);
+
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        let mut events = sqlx::query_as::<_, SyncEventRecord>(
+            r#"
+            SELECT id, event_id, pool_id, block_number, block_hash, block_timestamp, tx_hash,
+                   log_index, reserve0, reserve1, is_confirmed, created_at
+            FROM sync_events
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_number BETWEEN ? AND ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(first)
+        .bind(last)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(map_err)?;
+        let moved = events.len() as u64;
+
+        let existing = sqlx::query_as::<_, (String, Vec<u8>)>(
+            "SELECT codec, data FROM event_archive WHERE pool_id = ? AND segment_start = ?",
+        )
+        .bind(pool_id)
+        .bind(first)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_err)?;
+        if let Some((codec, data)) = existing {
+            events.extend(decode_segment(&codec, &data)?);
+        }
+
+        // Raw rows come first, so they win over archived copies of an event
+        events.sort_by_key(|e| (e.block_number, e.log_index));
+        events.dedup_by_key(|e| (e.block_number, e.log_index));
+        let (Some(first_event), Some(last_event)) = (events.first(), events.last()) else {
+            return Ok((0, 0, 0));
+        };
+        let (first_block, last_block, last_timestamp) = (
+            first_event.block_number,
+            last_event.block_number,
+            last_event.block_timestamp,
+        );
+        let (data, raw_bytes) = encode_segment(&events)?;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO event_archive (
+                pool_id, segment_start, segment_end, event_count, first_block, last_block,
+                last_timestamp, codec, raw_bytes, data
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(pool_id)
+        .bind(first)
+        .bind(last)
+        .bind(i64::try_from(events.len()).unwrap_or(i64::MAX))
+        .bind(first_block)
+        .bind(last_block)
+        .bind(last_timestamp)
+        .bind(ARCHIVE_CODEC_ZSTD_JSON)
+        .bind(i64::try_from(raw_bytes).unwrap_or(i64::MAX))
+        .bind(&data)
+        .execute(&mut *tx)
+        .await
+        .map_err(map_err)?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM sync_events
+            WHERE pool_id = ? AND is_confirmed = 1 AND block_number BETWEEN ? AND ?
+            "#,
+        )
+        .bind(pool_id)
+        .bind(first)
+        .bind(last)
+        .execute(&mut *tx)
+        .await
+        .map_err(map_err)?;
+
+        tx.commit().await.map_err(map_err)?;
+
+        Ok((moved, raw_bytes as u64, data.len() as u64))
+    }
+
+    /// Get a pool's archived sync events in `[from_block, to_block]`, in
+    /// chain order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or a segment can't be decoded.
+    pub async fn get_archived_events(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SyncEventRecord>, TrackerError> {
+        let (from, to) = (
+            i64::try_from(from_block).unwrap_or(i64::MAX),
+            i64::try_from(to_block).unwrap_or(i64::MAX),
+        );
+        let segments = sqlx::query_as::<_, (String, Vec<u8>)>(
+            r#"
+            SELECT codec, data FROM event_archive
+            WHERE pool_id = ? AND last_block >= ? AND first_block <= ?
+            ORDER BY segment_start
+            "#,
+        )
+        .bind(pool_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query archived events".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        let mut events = Vec::new();
+        for (codec, data) in segments {
+            events.extend(
+                decode_segment(&codec, &data)?
+                    .into_iter()
+                    .filter(|e| (from..=to).contains(&e.block_number)),
+            );
+        }
+        Ok(events)
+    }
+
+    /// Get the last archived sync event at or before `block_number`.
+    async fn get_archived_event_at(
+        &self,
+        pool_id: i64,
+        block_number: u64,
+    ) -> Result<Option<SyncEventRecord>, TrackerError> {
+        let block = i64::try_from(block_number).unwrap_or(i64::MAX);
+        let segment = sqlx::query_as::<_, (String, Vec<u8>)>(
+            r#"
+            SELECT codec, data FROM event_archive
+            WHERE pool_id = ? AND first_block <= ?
+            ORDER BY segment_start DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(pool_id)
+        .bind(block)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query archived events".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        let Some((codec, data)) = segment else {
+            return Ok(None);
+        };
+        Ok(decode_segment(&codec, &data)?
+            .into_iter()
+            .filter(|e| e.block_number <= block)
+            .max_by_key(|e| (e.block_number, e.log_index)))
+    }
+
+    // ==================== RETENTION OPERATIONS ====================
+
+    /// Delete confirmed sync events with a block timestamp before `before_ts`.
+    ///
+    /// The latest block of each pool is kept, whatever its age.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub async fn prune_sync_events_before(&self, before_ts: i64) -> Result<u64, TrackerError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM sync_events
+            WHERE is_confirmed = 1 AND block_timestamp < ?
+              AND block_number < (
+                  SELECT MAX(latest.block_number) FROM sync_events latest
+                  WHERE latest.pool_id = sync_events.pool_id
+              )
+            "#,
+        )
+        .bind(before_ts)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to prune sync events".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete confirmed price points with a block timestamp before
+    /// `before_ts`.
+    ///
+    /// The latest block of each pool is kept, whatever its age.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub async fn prune_price_points_before(&self, before_ts: i64) -> Result<u64, TrackerError> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM price_points
+            WHERE is_confirmed = 1 AND block_timestamp < ?
+              AND block_number < (
+                  SELECT MAX(latest.block_number) FROM price_points latest
+                  WHERE latest.pool_id = price_points.pool_id
+              )
+            "#,
+        )
+        .bind(before_ts)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to prune price points".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Self::rebuild_stale_rollups(&mut tx).await?;
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Roll 1m candles starting before `before_ts` up into candles of
+    /// `rollup_secs`, then delete all shorter candles before `before_ts`.
+    ///
+    /// A rolled-up bucket that already exists is merged (high/low widened,
+    /// close, samples and sums extended). `before_ts` should be aligned to
+    /// `rollup_secs` so each bucket is rolled up from complete data. Returns
+    /// the rolled-up candles written and the short candles deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any write or the commit fails; nothing is applied
+    /// in that case.
+    pub async fn roll_up_candles_before(
+        &self,
+        before_ts: i64,
+        rollup_secs: i64,
+    ) -> Result<(u64, u64), TrackerError> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        let written = sqlx::query(
+            r#"
+            WITH minutes AS (
+                SELECT pool_id, (bucket_start / ?2) * ?2 AS rollup_start,
+                       bucket_start, open, high, low, close, samples, price_sum
+                FROM candles
+                WHERE interval_secs = 60 AND bucket_start < ?1
+            )
+            INSERT INTO candles (
+                pool_id, interval_secs, bucket_start, open, high, low, close,
+                samples, price_sum
+            )
+            SELECT m.pool_id, ?2, m.rollup_start,
+                   (SELECT f.open FROM minutes f
+                    WHERE f.pool_id = m.pool_id AND f.rollup_start = m.rollup_start
+                    ORDER BY f.bucket_start ASC LIMIT 1),
+                   MAX(m.high), MIN(m.low),
+                   (SELECT l.close FROM minutes l
+                    WHERE l.pool_id = m.pool_id AND l.rollup_start = m.rollup_start
+                    ORDER BY l.bucket_start DESC LIMIT 1),
+                   SUM(m.samples), SUM(m.price_sum)
+            FROM minutes m
+            WHERE true
+            GROUP BY m.pool_id, m.rollup_start
+            ON CONFLICT (pool_id, interval_secs, bucket_start) DO UPDATE SET
+                high = MAX(candles.high, excluded.high),
+                low = MIN(candles.low, excluded.low),
+                close = excluded.close,
+                samples = candles.samples + excluded.samples,
+                price_sum = candles.price_sum + excluded.price_sum,
+                updated_at = unixepoch()
+            "#,
+        )
+        .bind(before_ts)
+        .bind(rollup_secs)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to roll up candles".to_string(), Some(Box::new(e)))
+        })?
+        .rows_affected();
+
+        let deleted =
+            sqlx::query("DELETE FROM candles WHERE interval_secs < ? AND bucket_start < ?")
+                .bind(rollup_secs)
+                .bind(before_ts)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    TrackerError::database("Failed to prune candles".to_string(), Some(Box::new(e)))
+                })?
+                .rows_affected();
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok((written, deleted))
+    }
+
+    /// Rebuild the database file to release space freed by deletes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the vacuum fails (e.g. another connection holds a
+    /// transaction open).
+    pub async fn vacuum(&self) -> Result<(), TrackerError> {
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database("Failed to vacuum database".to_string(), Some(Box::new(e)))
+            })?;
+
+        Ok(())
+    }
+
+    // ==================== STORAGE STATS ====================
+
+    /// Reports the size of the database, its tables and indexes, and each
+    /// pool's indexed block range, counting rows written since `since_ts`.
+    ///
+    /// Page sizes come from SQLite's `dbstat` table and are left out when
+    /// SQLite was built without it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails.
+    pub async fn get_storage_stats(&self, since_ts: i64) -> Result<StorageStats, TrackerError> {
+        let failed = |what: &str, e: sqlx::Error| {
+            TrackerError::database(format!("Failed to read {what}"), Some(Box::new(e)))
+        };
+        let mut conn = self.pool.acquire().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to acquire connection".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        let mut pragmas = [0_i64; 3];
+        for (value, name) in pragmas
+            .iter_mut()
+            .zip(["page_size", "page_count", "freelist_count"])
+        {
+            *value = sqlx::query_scalar(&format!("PRAGMA {name}"))
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| failed(name, e))?;
+        }
+        let [page_size, page_count, freelist_count] = pragmas;
+
+        let sizes = match sqlx::query_as::<_, (String, i64)>(
+            "SELECT name, SUM(pgsize) FROM dbstat GROUP BY name",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        {
+            Ok(rows) => Some(rows.into_iter().collect::<HashMap<_, _>>()),
+            Err(e) => {
+                debug!(error = %e, "dbstat unavailable, leaving out sizes");
+                None
+            }
+        };
+        let size = |name: &str| sizes.as_ref().map(|s| s.get(name).copied().unwrap_or(0));
+
+        let objects = sqlx::query_as::<_, (String, String, String)>(
+            r#"
+            SELECT type, name, tbl_name FROM sqlite_master
+            WHERE type IN ('table', 'index') AND name NOT LIKE 'sqlite_%'
+            ORDER BY tbl_name, type DESC, name
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| failed("schema", e))?;
+
+        let mut tables: Vec<TableStats> = Vec::new();
+        for (kind, name, table) in objects {
+            if kind == "index" {
+                if let Some(stats) = tables.iter_mut().find(|t| t.name == table) {
+                    stats.indexes.push(IndexStats {
+                        bytes: size(&name),
+                        name,
+                    });
+                }
+                continue;
+            }
+
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{name}\""))
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| failed(&name, e))?;
+            let has_created_at: bool = sqlx::query_scalar(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = 'created_at'",
+            )
+            .bind(&name)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| failed(&name, e))?;
+            let recent_rows = if has_created_at {
+                let recent: i64 = sqlx::query_scalar(&format!(
+                    "SELECT COUNT(*) FROM \"{name}\" WHERE created_at >= ?1"
+                ))
+                .bind(since_ts)
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| failed(&name, e))?;
+                Some(recent)
+            } else {
+                None
+            };
+
+            tables.push(TableStats {
+                bytes: size(&name),
+                name,
+                rows,
+                recent_rows,
+                indexes: Vec::new(),
+            });
+        }
+
+        let pools = sqlx::query_as::<_, PoolBlockRange>(
+            r#"
+            SELECT p.id AS pool_id, p.name,
+                   (SELECT MIN(block) FROM (
+                        SELECT MIN(first_block) AS block FROM event_archive WHERE pool_id = p.id
+                        UNION ALL
+                        SELECT MIN(block_number) FROM sync_events WHERE pool_id = p.id
+                   )) AS oldest_block,
+                   (SELECT MAX(block_number) FROM sync_events WHERE pool_id = p.id)
+                       AS newest_block,
+                   (SELECT last_indexed_block FROM indexer_state WHERE pool_id = p.id)
+                       AS last_indexed_block,
+                   (SELECT COUNT(*) FROM sync_events WHERE pool_id = p.id) AS events,
+                   (SELECT COALESCE(SUM(event_count), 0) FROM event_archive
+                    WHERE pool_id = p.id) AS archived_events
+            FROM pools p
+            ORDER BY p.id
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| failed("pool block ranges", e))?;
+
+        Ok(StorageStats {
+            page_size,
+            page_count,
+            freelist_count,
+            tables,
+            pools,
+        })
+    }
+
+    // ==================== INCIDENT OPERATIONS ====================
+
+    /// Opens an incident and returns its ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_incident(
+        &self,
+        pool_id: i64,
+        kind: &str,
+        band_bps: u32,
+        started_block: u64,
+        started_at: i64,
+        peak_price: f64,
+        peak_deviation_bps: f64,
+    ) -> Result<i64, TrackerError> {
+        let (id,) = sqlx::query_as::<_, (i64,)>(
+            r#"
+            INSERT INTO incidents (
+                pool_id, kind, band_bps, started_block, started_at,
+                peak_price, peak_deviation_bps
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING id
+            "#,
+        )
+        .bind(pool_id)
+        .bind(kind)
+        .bind(i64::from(band_bps))
+        .bind(i64::try_from(started_block).unwrap_or(i64::MAX))
+        .bind(started_at)
+        .bind(peak_price)
+        .bind(peak_deviation_bps)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to insert incident".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(id)
+    }
+
+    /// Records a new peak of an ongoing incident.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn update_incident_peak(
+        &self,
+        id: i64,
+        peak_price: f64,
+        peak_deviation_bps: f64,
+    ) -> Result<(), TrackerError> {
+        sqlx::query("UPDATE incidents SET peak_price = ?, peak_deviation_bps = ? WHERE id = ?")
+            .bind(peak_price)
+            .bind(peak_deviation_bps)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database("Failed to update incident".to_string(), Some(Box::new(e)))
+            })?;
+        Ok(())
+    }
+
+    /// Closes an ongoing incident.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn end_incident(
+        &self,
+        id: i64,
+        ended_block: u64,
+        ended_at: i64,
+    ) -> Result<(), TrackerError> {
+        sqlx::query(
+            "UPDATE incidents SET ended_block = ?, ended_at = ? WHERE id = ? AND ended_block IS NULL",
+        )
+        .bind(i64::try_from(ended_block).unwrap_or(i64::MAX))
+        .bind(ended_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to close incident".to_string(), Some(Box::new(e)))
+        })?;
+        Ok(())
+    }
+
+    /// Get a pool's ongoing incident of a kind, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_open_incident(
+        &self,
+        pool_id: i64,
+        kind: &str,
+    ) -> Result<Option<IncidentRow>, TrackerError> {
+        sqlx::query_as::<_, IncidentRow>(
+            r#"
+            SELECT id, pool_id, kind, band_bps, started_block, started_at,
+                   ended_block, ended_at, peak_price, peak_deviation_bps
+            FROM incidents
+            WHERE pool_id = ? AND kind = ? AND ended_block IS NULL
+            ORDER BY started_block DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(pool_id)
+        .bind(kind)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query open incident".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Get a page of a pool's incidents, newest first, optionally of one kind.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_incidents_page(
+        &self,
+        pool_id: i64,
+        kind: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Page<IncidentRow>, TrackerError> {
+        let map_err =
+            |e| TrackerError::database("Failed to query incidents".to_string(), Some(Box::new(e)));
+
+        let items = sqlx::query_as::<_, IncidentRow>(
+            r#"
+            SELECT id, pool_id, kind, band_bps, started_block, started_at,
+                   ended_block, ended_at, peak_price, peak_deviation_bps
+            FROM incidents
+            WHERE pool_id = ?1 AND (?2 IS NULL OR kind = ?2)
+            ORDER BY started_block DESC, id DESC
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )
+        .bind(pool_id)
+        .bind(kind)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM incidents WHERE pool_id = ?1 AND (?2 IS NULL OR kind = ?2)",
+        )
+        .bind(pool_id)
+        .bind(kind)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        Ok(Page {
+            items,
+            total: u64::try_from(total).unwrap_or(0),
+        })
+    }
+
+    /// Get the latest incidents of all pools, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_recent_incidents(&self, limit: i64) -> Result<Vec<IncidentRow>, TrackerError> {
+        sqlx::query_as::<_, IncidentRow>(
+            r#"
+            SELECT id, pool_id, kind, band_bps, started_block, started_at,
+                   ended_block, ended_at, peak_price, peak_deviation_bps
+            FROM incidents
+            ORDER BY started_at DESC, id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query incidents".to_string(), Some(Box::new(e)))
+        })
+    }
+
+    // ==================== COMPOSITE INDEX ====================
+
+    /// Records a value of the composite price index.
+    ///
+    /// A value for an already recorded block is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the constituents can't be encoded or the insert
+    /// fails.
+    pub async fn insert_composite_price(
+        &self,
+        composite: &CompositePrice,
+    ) -> Result<(), TrackerError> {
+        let constituents = serde_json::to_string(&composite.constituents).map_err(|e| {
+            TrackerError::database(
+                "Failed to encode composite constituents".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO composite_prices (
+                block_number, block_timestamp, price, liquidity, pools, constituents
+            )
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(block_number) DO NOTHING
+            "#,
+        )
+        .bind(i64::try_from(composite.block_number).unwrap_or(i64::MAX))
+        .bind(composite.block_timestamp)
+        .bind(composite.price)
+        .bind(composite.liquidity)
+        .bind(i64::try_from(composite.constituents.len()).unwrap_or(i64::MAX))
+        .bind(constituents)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to insert composite price".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the latest value of the composite price index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_latest_composite_price(
+        &self,
+    ) -> Result<Option<CompositePriceRow>, TrackerError> {
+        sqlx::query_as::<_, CompositePriceRow>(
+            r#"
+            SELECT block_number, block_timestamp, price, liquidity, pools, constituents
+            FROM composite_prices
+            ORDER BY block_number DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query latest composite price".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Get a page of composite price index values, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_composite_prices_page(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Page<CompositePriceRow>, TrackerError> {
+        let map_err = |e| {
+            TrackerError::database(
+                "Failed to query composite prices".to_string(),
+                Some(Box::new(e)),
+            )
+        };
+
+        let items = sqlx::query_as::<_, CompositePriceRow>(
+            r#"
+            SELECT block_number, block_timestamp, price, liquidity, pools, constituents
+            FROM composite_prices
+            ORDER BY block_number DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM composite_prices")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(map_err)?;
+
+        Ok(Page {
+            items,
+            total: u64::try_from(total).unwrap_or(0),
+        })
+    }
+
+    // ==================== REORG HISTORY ====================
+
+    /// Records a handled reorg and returns its ID.
+    ///
+    /// Call after [`invalidate_from_block`](Self::invalidate_from_block) and
+    /// before re-indexing: the pool's sync events after the fork point are
+    /// counted as the reorg's invalidated events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails.
+    pub async fn insert_reorg(&self, reorg: &ReorgRecord) -> Result<i64, TrackerError> {
+        let (id,) = sqlx::query_as::<_, (i64,)>(
+            r#"
+            INSERT INTO reorgs (pool_id, detected_at, fork_point, depth, invalidated_events)
+            SELECT ?1, ?2, ?3, ?4, COUNT(*)
+            FROM sync_events
+            WHERE pool_id = ?1 AND block_number > ?3
+            RETURNING id
+            "#,
+        )
+        .bind(reorg.pool_id)
+        .bind(reorg.detected_at)
+        .bind(i64::try_from(reorg.fork_point).unwrap_or(i64::MAX))
+        .bind(i64::try_from(reorg.depth).unwrap_or(i64::MAX))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to record reorg".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(id)
+    }
+
+    /// Get a page of recorded reorgs of the pools visible in `scope`, newest
+    /// first, optionally of one pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_reorgs_page(
+        &self,
+        scope: &PoolScope,
+        pool_id: Option<i64>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Page<ReorgRow>, TrackerError> {
+        let map_err =
+            |e| TrackerError::database("Failed to query reorgs".to_string(), Some(Box::new(e)));
+        let scope = scope.json_ids();
+
+        let items = sqlx::query_as::<_, ReorgRow>(
+            r#"
+            SELECT id, pool_id, detected_at, fork_point, depth, invalidated_events
+            FROM reorgs
+            WHERE (?1 IS NULL OR pool_id = ?1)
+              AND (?4 IS NULL OR pool_id IN (SELECT value FROM json_each(?4)))
+            ORDER BY detected_at DESC, id DESC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(pool_id)
+        .bind(limit)
+        .bind(offset)
+        .bind(&scope)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM reorgs
+            WHERE (?1 IS NULL OR pool_id = ?1)
+              AND (?2 IS NULL OR pool_id IN (SELECT value FROM json_each(?2)))
+            "#,
+        )
+        .bind(pool_id)
+        .bind(&scope)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        Ok(Page {
+            items,
+            total: u64::try_from(total).unwrap_or(0),
+        })
+    }
+
+    /// Get the ID of the latest reorg of a pool recorded after `after_id`,
+    /// and the earliest fork point among those reorgs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_reorgs_after(
+        &self,
+        pool_id: i64,
+        after_id: i64,
+    ) -> Result<Option<(i64, i64)>, TrackerError> {
+        let (latest, fork_point) = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+            "SELECT MAX(id), MIN(fork_point) FROM reorgs WHERE pool_id = ? AND id > ?",
+        )
+        .bind(pool_id)
+        .bind(after_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query reorgs".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(latest.zip(fork_point))
+    }
+
+    /// Get a pool's reorg totals.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_reorg_stats(&self, pool_id: i64) -> Result<ReorgStatsRow, TrackerError> {
+        sqlx::query_as::<_, ReorgStatsRow>(
+            r#"
+            SELECT COUNT(*) AS reorgs,
+                   MAX(depth) AS max_depth,
+                   AVG(depth) AS avg_depth,
+                   COALESCE(SUM(invalidated_events), 0) AS invalidated_events,
+                   MAX(detected_at) AS last_detected_at
+            FROM reorgs
+            WHERE pool_id = ?
+            "#,
+        )
+        .bind(pool_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query reorg stats".to_string(), Some(Box::new(e)))
+        })
+    }
+
+    // ==================== ALERT DELIVERY OPERATIONS ====================
+
+    /// Records the outcome of an alert delivery.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails.
+    pub async fn insert_alert_delivery(
+        &self,
+        delivery: &AlertDeliveryRecord,
+    ) -> Result<(), TrackerError> {
+        sqlx::query(
+            r#"
+            INSERT INTO alert_deliveries (
+                source, event, channel, destination, status, attempts, error, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&delivery.source)
+        .bind(&delivery.event)
+        .bind(&delivery.channel)
+        .bind(&delivery.destination)
+        .bind(if delivery.delivered {
+            "delivered"
+        } else {
+            "failed"
+        })
+        .bind(delivery.attempts)
+        .bind(&delivery.error)
+        .bind(delivery.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to insert alert delivery".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Get the most recent alert deliveries, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_recent_alert_deliveries(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AlertDeliveryRow>, TrackerError> {
+        sqlx::query_as::<_, AlertDeliveryRow>(
+            r#"
+            SELECT id, source, event, channel, destination, status, attempts, error, created_at
+            FROM alert_deliveries
+            ORDER BY created_at DESC, id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query alert deliveries".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    // ==================== PRICE WEBHOOK OPERATIONS ====================
+
+    /// Records a price webhook delivery that failed every attempt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails.
+    pub async fn insert_price_webhook_dead_letter(
+        &self,
+        letter: &PriceWebhookDeadLetterRecord,
+    ) -> Result<(), TrackerError> {
+        sqlx::query(
+            r#"
+            INSERT INTO price_webhook_dead_letters (
+                destination, destination_key, pool_id, block_number, payload,
+                attempts, error, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&letter.destination)
+        .bind(&letter.destination_key)
+        .bind(letter.pool_id)
+        .bind(letter.block_number)
+        .bind(&letter.payload)
+        .bind(letter.attempts)
+        .bind(&letter.error)
+        .bind(letter.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to insert price webhook dead letter".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Get dead-lettered price webhook deliveries with an ID above
+    /// `after_id`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_price_webhook_dead_letters(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<PriceWebhookDeadLetterRow>, TrackerError> {
+        sqlx::query_as::<_, PriceWebhookDeadLetterRow>(
+            r#"
+            SELECT id, destination, destination_key, pool_id, block_number, payload,
+                   attempts, error, created_at
+            FROM price_webhook_dead_letters
+            WHERE id > ?
+            ORDER BY id ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to query price webhook dead letters".to_string(),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Deletes a dead-lettered price webhook delivery, e.g. once replayed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub async fn delete_price_webhook_dead_letter(&self, id: i64) -> Result<(), TrackerError> {
+        sqlx::query("DELETE FROM price_webhook_dead_letters WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    "Failed to delete price webhook dead letter".to_string(),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(())
+    }
+
+    // ==================== API KEY OPERATIONS ====================
+
+    /// Stores a new API key by its hash.
+    ///
+    /// Returns the new key's ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails (including a duplicate hash).
+    pub async fn insert_api_key(
+        &self,
+        name: &str,
+        key_prefix: &str,
+        key_hash: &str,
+        rate_limit_rpm: Option<u32>,
+    ) -> Result<i64, TrackerError> {
+        let (id,) = sqlx::query_as::<_, (i64,)>(
+            r#"
+            INSERT INTO api_keys (name, key_prefix, key_hash, rate_limit_rpm)
+            VALUES (?, ?, ?, ?)
+            RETURNING id
+            "#,
+        )
+        .bind(name)
+        .bind(key_prefix)
+        .bind(key_hash)
+        .bind(rate_limit_rpm.map(i64::from))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to insert API key".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(id)
+    }
+
+    /// Looks up an active (non-revoked) API key by hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_active_api_key(
+        &self,
+        key_hash: &str,
+    ) -> Result<Option<ApiKeyRow>, TrackerError> {
+        sqlx::query_as::<_, ApiKeyRow>(
+            r#"
+            SELECT id, name, key_prefix, rate_limit_rpm, pool_scoped, chain_id, created_at,
+                   revoked_at
+            FROM api_keys
+            WHERE key_hash = ? AND revoked_at IS NULL
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to query API key".to_string(), Some(Box::new(e)))
+        })
+    }
+
+    /// Lists all API keys, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKeyRow>, TrackerError> {
+        sqlx::query_as::<_, ApiKeyRow>(
+            r#"
+            SELECT id, name, key_prefix, rate_limit_rpm, pool_scoped, chain_id, created_at,
+                   revoked_at
+            FROM api_keys
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to list API keys".to_string(), Some(Box::new(e)))
+        })
+    }
+
+    /// Revokes active API keys matching an ID or key prefix.
+    ///
+    /// Returns the number of keys revoked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    pub async fn revoke_api_key(&self, id_or_prefix: &str) -> Result<u64, TrackerError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE api_keys SET revoked_at = unixepoch()
+            WHERE revoked_at IS NULL AND (CAST(id AS TEXT) = ?1 OR key_prefix = ?1)
+            "#,
+        )
+        .bind(id_or_prefix)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database("Failed to revoke API key".to_string(), Some(Box::new(e)))
+        })?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Restricts an API key to `pool_ids` (`None` = every pool) and to the
+    /// chain `chain_id` (`None` = any chain), replacing its previous scope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key or a pool doesn't exist, or the update
+    /// fails.
+    pub async fn set_api_key_scope(
+        &self,
+        key_id: i64,
+        pool_ids: Option<&[i64]>,
+        chain_id: Option<u64>,
+    ) -> Result<(), TrackerError> {
+        let map_err = |e| {
+            TrackerError::database("Failed to set API key scope".to_string(), Some(Box::new(e)))
+        };
+        let chain_id = chain_id
+            .map(i64::try_from)
+            .transpose()
+            .map_err(|_| TrackerError::state("Chain ID out of range", None))?;
+
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+        let updated = sqlx::query("UPDATE api_keys SET pool_scoped = ?, chain_id = ? WHERE id = ?")
+            .bind(pool_ids.is_some())
+            .bind(chain_id)
+            .bind(key_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        if updated.rows_affected() == 0 {
+            return Err(TrackerError::state(
+                format!("API key {key_id} not found"),
+                None,
+            ));
+        }
+        sqlx::query("DELETE FROM api_key_pools WHERE api_key_id = ?")
+            .bind(key_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        for pool_id in pool_ids.unwrap_or_default() {
+            sqlx::query("INSERT OR IGNORE INTO api_key_pools (api_key_id, pool_id) VALUES (?, ?)")
+                .bind(key_id)
+                .bind(pool_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+        }
+        tx.commit().await.map_err(map_err)?;
+
+        Ok(())
+    }
+
+    /// Returns the pools an API key may see.
+    ///
+    /// A scoped key whose pools were all removed sees none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_api_key_scope(&self, key: &ApiKeyRow) -> Result<PoolScope, TrackerError> {
+        if !key.pool_scoped {
+            return Ok(PoolScope::All);
+        }
+        let pool_ids: Vec<i64> =
+            sqlx::query_scalar("SELECT pool_id FROM api_key_pools WHERE api_key_id = ?")
+                .bind(key.id)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| {
+                    TrackerError::database(
+                        "Failed to query API key scope".to_string(),
+                        Some(Box::new(e)),
+                    )
+                })?;
+
+        Ok(PoolScope::Pools(pool_ids.into_iter().collect()))
+    }
+
+    // ==================== SNAPSHOT OPERATIONS ====================
+
+    /// Writes a compressed, checksummed snapshot of the database to `path`.
+    ///
+    /// Safe to run while the indexer is writing; see [`super::snapshot`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` exists or the snapshot cannot be written.
+    pub async fn create_snapshot(&self, path: &Path) -> Result<SnapshotManifest, TrackerError> {
+        super::snapshot::write_snapshot(&self.pool, path).await
+    }
+
+    // ==================== STANDBY OPERATIONS ====================
+
+    /// Mirrors a primary's database into this one.
+    ///
+    /// The primary's database file is attached read-only (WAL mode lets it keep
+    /// writing) and copied in a single transaction:
+    ///
+    /// - `pools` and `indexer_state` are upserted in full (one row per pool)
+    /// - `sync_events` and `price_points` rows newer than the local maximum
+    ///   ID are copied, and the last [`FOLLOW_REWIND_BLOCKS`] blocks are
+    ///   re-copied so reorg rewrites on the primary propagate
+    ///
+    /// Rows keep the primary's IDs, so a promoted standby serves the same
+    /// cursors and deterministic IDs. A primary on an older schema is
+    /// supported (only shared columns are copied); a newer one is rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the primary database does not exist, has a newer
+    /// schema, or any copy fails (in which case nothing is applied).
+    pub async fn follow_primary(&self, primary_path: &Path) -> Result<FollowReport, TrackerError> {
+        if !primary_path.is_file() {
+            return Err(TrackerError::database(
+                format!("Primary database not found at {}", primary_path.display()),
+                None,
+            ));
+        }
+
+        let mut conn = self.pool.acquire().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to acquire connection".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        sqlx::query("ATTACH DATABASE ? AS upstream")
+            .bind(read_only_uri(primary_path))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    format!(
+                        "Failed to attach primary database {}",
+                        primary_path.display()
+                    ),
+                    Some(Box::new(e)),
+                )
+            })?;
+
+        let result = Self::copy_from_upstream(&mut conn).await;
+
+        // Always detach so the pooled connection doesn't keep the primary open
+        if let Err(e) = sqlx::query("DETACH DATABASE upstream")
+            .execute(&mut *conn)
+            .await
+        {
+            warn!(error = %e, "Failed to detach primary database");
+        }
+
+        result
+    }
+
+    /// Copies mirrored tables from the attached `upstream` schema.
+    async fn copy_from_upstream(conn: &mut SqliteConnection) -> Result<FollowReport, TrackerError> {
+        let local_version = Self::schema_version(conn, "main").await?;
+        let upstream_version = Self::schema_version(conn, "upstream").await?;
+        if upstream_version > local_version {
+            return Err(TrackerError::database(
+                format!(
+                    "Primary schema version {upstream_version} is newer than this instance ({local_version}); upgrade the standby first"
+                ),
+                None,
+            ));
+        }
+
+        let mut tx = conn.begin().await.map_err(|e| {
+            TrackerError::database("Failed to start transaction".to_string(), Some(Box::new(e)))
+        })?;
+
+        let mut report = FollowReport::default();
+
+        for (table, conflict_key) in [("pools", "id"), ("indexer_state", "pool_id")] {
+            let columns = Self::shared_columns(&mut tx, table).await?;
+            let updates = columns
+                .iter()
+                .filter(|c| c.as_str() != conflict_key)
+                .map(|c| format!("{c} = excluded.{c}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let columns = columns.join(", ");
+
+            // `WHERE true` disambiguates the upsert clause from a join
+            sqlx::query(&format!(
+                "INSERT INTO main.{table} ({columns}) SELECT {columns} FROM upstream.{table} WHERE true \
+                 ON CONFLICT ({conflict_key}) DO UPDATE SET {updates}"
+            ))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                TrackerError::database(format!("Failed to copy {table} from primary"), Some(Box::new(e)))
+            })?;
+        }
+
+        for table in ["sync_events", "price_points"] {
+            let (max_id, rewind_from) = sqlx::query_as::<_, (i64, i64)>(&format!(
+                "SELECT COALESCE(MAX(id), 0), COALESCE(MAX(block_number), 0) - ? FROM main.{table}"
+            ))
+            .bind(FOLLOW_REWIND_BLOCKS)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    format!("Failed to read {table} watermark"),
+                    Some(Box::new(e)),
+                )
+            })?;
+
+            let columns = Self::shared_columns(&mut tx, table).await?.join(", ");
+            let copied = sqlx::query(&format!(
+                "INSERT OR REPLACE INTO main.{table} ({columns}) \
+                 SELECT {columns} FROM upstream.{table} WHERE id > ? OR block_number >= ?"
+            ))
+            .bind(max_id)
+            .bind(rewind_from)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                TrackerError::database(
+                    format!("Failed to copy {table} from primary"),
+                    Some(Box::new(e)),
+                )
+            })?
+            .rows_affected();
+
+            if table == "sync_events" {
+                report.events_synced = copied;
+            } else {
+                report.prices_synced = copied;
+
+                // INSERT OR REPLACE deletes without firing the delete trigger,
+                // so the copied hours are rebuilt instead of folded in twice
+                sqlx::query(
+                    "INSERT OR IGNORE INTO main.price_rollups_stale (pool_id, hour_start) \
+                     SELECT DISTINCT pool_id, (block_timestamp / 3600) * 3600 FROM main.price_points \
+                     WHERE is_confirmed = 1 AND (id > ? OR block_number >= ?)",
+                )
+                .bind(max_id)
+                .bind(rewind_from)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    TrackerError::database(
+                        "Failed to mark copied price rollups".to_string(),
+                        Some(Box::new(e)),
+                    )
+                })?;
+                Self::rebuild_stale_rollups(&mut tx).await?;
+            }
+        }
+
+        report.last_indexed_block = sqlx::query_as::<_, (i64,)>(
+            "SELECT COALESCE(MAX(last_indexed_block), 0) FROM main.indexer_state",
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to read indexer state".to_string(),
+                Some(Box::new(e)),
+            )
+        })?
+        .0;
+
+        tx.commit().await.map_err(|e| {
+            TrackerError::database(
+                "Failed to commit transaction".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+
+        debug!(?report, "Followed primary database");
+        Ok(report)
+    }
+
+    /// Latest applied migration version in an attached schema.
+    async fn schema_version(
+        conn: &mut SqliteConnection,
+        schema: &str,
+    ) -> Result<i64, TrackerError> {
+        sqlx::query_as::<_, (i64,)>(&format!(
+            "SELECT COALESCE(MAX(version), 0) FROM {schema}._sqlx_migrations WHERE success = 1"
+        ))
+        .fetch_one(conn)
+        .await
+        .map(|row| row.0)
+        .map_err(|e| {
+            TrackerError::database(
+                format!("Failed to read schema version of {schema} database"),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Columns of `table` present in both the local and upstream schemas.
+    async fn shared_columns(
+        conn: &mut SqliteConnection,
+        table: &str,
+    ) -> Result<Vec<String>, TrackerError> {
+        let rows = sqlx::query_as::<_, (String,)>(
+            r#"
+            SELECT l.name FROM pragma_table_info(?1, 'main') l
+            JOIN pragma_table_info(?1, 'upstream') u ON u.name = l.name
+            ORDER BY l.cid
+            "#,
+        )
+        .bind(table)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| {
+            TrackerError::database(format!("Failed to read {table} columns"), Some(Box::new(e)))
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name,)| format!("\"{name}\""))
+            .collect())
+    }
+}
+
+/// Blocks re-copied on every follow pass so reorg rewrites on the primary
+/// reach the standby.
+pub const FOLLOW_REWIND_BLOCKS: i64 = 64;
+
+/// Builds a read-only `file:` URI for a database path.
+fn read_only_uri(path: &Path) -> String {
+    let escaped = path
+        .to_string_lossy()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    format!("file:{escaped}?mode=ro")
+}
+
+/// Describes the blocks of a batch for error messages, e.g. "at blocks 10-42".
+fn block_span<T>(rows: &[T], block: impl Fn(&T) -> i64) -> String {
+    match (rows.first(), rows.last()) {
+        (Some(first), Some(last)) if block(first) != block(last) => {
+            format!("at blocks {}-{}", block(first), block(last))
+        }
+        (Some(row), _) => format!("at block {}", block(row)),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations};
+    use crate::scheduler::TaskStatus;
+
+    async fn setup_test_db() -> Repository {
+        let pool = create_pool("sqlite::memory:")
+            .await
+            .expect("Failed to create pool");
+        run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+        Repository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_ensure_pool_exists() {
+        let repo = setup_test_db().await;
+
+        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+            .parse()
+            .unwrap();
+        let token0_addr: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+            .parse()
+            .unwrap();
+        let token1_addr: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+            .parse()
+            .unwrap();
+
+        let pool_id = repo
+            .ensure_pool_exists(
+                pool_addr,
+                Some("USDC-WETH".to_string()),
+                token0_addr,
+                Some("USDC".to_string()),
+                6,
+                token1_addr,
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .expect("Failed to create pool");
+
+        assert!(pool_id > 0);
 
         // Calling again should return same ID
         let pool_id2 = repo
             .ensure_pool_exists(
                 pool_addr,
                 Some("USDC-WETH".to_string()),
-                token0_addr,
+                token0_addr,
+                Some("USDC".to_string()),
+                6,
+                token1_addr,
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .expect("Failed to get pool");
+
+        assert_eq!(pool_id, pool_id2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_pool_removes_its_rows() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        repo.insert_sync_event(
+            pool_id,
+            100,
+            FixedBytes::from([1u8; 32]),
+            1_706_745_600,
+            FixedBytes::from([2u8; 32]),
+            0,
+            U256::from(1_000_000_000_u64),
+            U256::from(500_000_000_000_000_000_u64),
+            true,
+            "sync-100",
+        )
+        .await
+        .unwrap();
+
+        assert!(repo.delete_pool(pool_id).await.unwrap());
+        assert!(!repo.delete_pool(pool_id).await.unwrap());
+        let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sync_events")
+            .fetch_one(&repo.pool)
+            .await
+            .unwrap();
+        assert_eq!(events, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pool_indexing_settings() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_name("WETH/USDT").await.unwrap().unwrap();
+        assert!(pool.enabled);
+        assert_eq!(pool.start_block(), None);
+        assert!(repo.is_pool_enabled(pool_id).await.unwrap());
+
+        assert!(repo.set_pool_enabled(pool_id, false).await.unwrap());
+        assert!(repo
+            .set_pool_start_block(pool_id, Some(10_000_835))
+            .await
+            .unwrap());
+        assert!(!repo.is_pool_enabled(pool_id).await.unwrap());
+        assert!(repo.get_enabled_pools().await.unwrap().is_empty());
+        let pools = repo.get_all_pools().await.unwrap();
+        assert!(!pools[0].enabled);
+        assert_eq!(pools[0].start_block, Some(10_000_835));
+
+        assert!(repo.set_pool_start_block(pool_id, None).await.unwrap());
+        let pool = repo.get_pool_by_name("WETH/USDT").await.unwrap().unwrap();
+        assert_eq!(pool.start_block(), None);
+        assert!(!repo.set_pool_enabled(pool_id + 1, true).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_indexer_task_status() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        assert_eq!(repo.get_all_pools().await.unwrap()[0].task_status, None);
+
+        let mut task = IndexerTaskRecord {
+            pool_id,
+            status: TaskStatus::Running,
+            last_block: Some(19_000_000),
+            restarts: 0,
+            last_error: None,
+            updated_at: 1_706_745_600,
+        };
+        repo.upsert_indexer_task(&task).await.unwrap();
+        task.status = TaskStatus::Restarting;
+        task.last_block = None;
+        task.restarts = 2;
+        task.last_error = Some("RPC timeout".to_string());
+        repo.upsert_indexer_task(&task).await.unwrap();
+
+        let pool = &repo
+            .get_pools_page(&PoolScope::All, 10, 0)
+            .await
+            .unwrap()
+            .items[0];
+        assert_eq!(pool.task_status.as_deref(), Some("restarting"));
+        assert_eq!(pool.task_restarts, Some(2));
+        assert_eq!(pool.task_error.as_deref(), Some("RPC timeout"));
+        let last_block: Option<i64> =
+            sqlx::query_scalar("SELECT last_block FROM indexer_tasks WHERE pool_id = ?")
+                .bind(pool_id)
+                .fetch_one(&repo.pool)
+                .await
+                .unwrap();
+        assert_eq!(last_block, Some(19_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_pool_addresses_are_stored_lowercase() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        let stored: String = sqlx::query_scalar("SELECT address FROM pools WHERE id = ?")
+            .bind(pool_id)
+            .fetch_one(&repo.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, "0x0d4a11d5eeaac28ec3f61d100daf4d40471f1852");
+
+        let pool = repo
+            .find_pool("0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.id, pool_id);
+        assert_eq!(pool.address, PoolAddress::from(UNISWAP_V2_WETH_USDT_PAIR));
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_query_sync_event() {
+        let repo = setup_test_db().await;
+
+        // Create pool first
+        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+            .parse()
+            .unwrap();
+        let pool_id = repo
+            .ensure_pool_exists(
+                pool_addr,
+                Some("USDC-WETH".to_string()),
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                    .parse()
+                    .unwrap(),
+                Some("USDC".to_string()),
+                6,
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                    .parse()
+                    .unwrap(),
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .unwrap();
+
+        // Insert sync event
+        let event_id = repo
+            .insert_sync_event(
+                pool_id,
+                19000000,
+                FixedBytes::from([1u8; 32]),
+                1706745600,
+                FixedBytes::from([2u8; 32]),
+                0,
+                U256::from(1000000000u64),
+                U256::from(500000000000000000u64),
+                false,
+                "sync-19000000-0",
+            )
+            .await
+            .expect("Failed to insert sync event");
+
+        assert!(event_id > 0);
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_query_price_point() {
+        let repo = setup_test_db().await;
+
+        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+            .parse()
+            .unwrap();
+        let pool_id = repo
+            .ensure_pool_exists(
+                pool_addr,
+                Some("USDC-WETH".to_string()),
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                    .parse()
+                    .unwrap(),
+                Some("USDC".to_string()),
+                6,
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                    .parse()
+                    .unwrap(),
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .unwrap();
+
+        // Insert price point
+        let price_id = repo
+            .insert_price_point(
+                pool_id,
+                19000000,
+                1706745600,
+                FixedBytes::from([2u8; 32]),
+                3500.0,
+                U256::from(1000000000u64),
+                U256::from(500000000000000000u64),
+                1000.0,
+                0.5,
+                false,
+                "price-19000000",
+            )
+            .await
+            .expect("Failed to insert price point");
+
+        assert!(price_id > 0);
+
+        // Query recent prices
+        let prices = repo
+            .get_recent_prices(pool_id, 10)
+            .await
+            .expect("Failed to query prices");
+
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[0].price, 3500.0);
+    }
+
+    #[tokio::test]
+    async fn test_price_history_version_tracks_changes() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let insert = |block: u64, confirmed: bool| {
+            let repo = &repo;
+            async move {
+                repo.insert_price_point(
+                    pool_id,
+                    block,
+                    1_706_745_600 + (block % 100) * 12,
+                    FixedBytes::from([2u8; 32]),
+                    3500.0,
+                    U256::from(1_000_000_000_u64),
+                    U256::from(500_000_000_000_000_000_u64),
+                    1000.0,
+                    0.5,
+                    confirmed,
+                    &format!("price-{block}"),
+                )
+                .await
+                .unwrap();
+            }
+        };
+
+        let empty = repo
+            .get_price_history_version(pool_id, None, None, BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(empty, PriceHistoryVersion::default());
+
+        insert(19_000_000, true).await;
+        let first = repo
+            .get_price_history_version(pool_id, None, None, BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(first.count, 1);
+        assert_eq!(first.max_block, Some(19_000_000));
+
+        // Unconfirmed prices aren't served, so they don't change the version
+        insert(19_000_001, false).await;
+        let unconfirmed = repo
+            .get_price_history_version(pool_id, None, None, BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(unconfirmed, first);
+
+        insert(19_000_001, true).await;
+        let confirmed = repo
+            .get_price_history_version(pool_id, None, None, BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(confirmed.count, 2);
+        assert_eq!(confirmed.last_timestamp, Some(1_706_745_612));
+
+        // Outside the range nothing changed
+        let range = repo
+            .get_price_history_version(pool_id, None, Some(1_706_745_600), BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(range, first);
+    }
+
+    #[tokio::test]
+    async fn test_state_management() {
+        let repo = setup_test_db().await;
+
+        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+            .parse()
+            .unwrap();
+        let pool_id = repo
+            .ensure_pool_exists(
+                pool_addr,
+                Some("USDC-WETH".to_string()),
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                    .parse()
+                    .unwrap(),
+                Some("USDC".to_string()),
+                6,
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                    .parse()
+                    .unwrap(),
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .unwrap();
+
+        // No state initially
+        let state = repo.get_state(pool_id).await.expect("Failed to get state");
+        assert!(state.is_none());
+
+        // Update state
+        let block_hash = FixedBytes::from([1u8; 32]);
+        repo.update_state(pool_id, 19000000, block_hash, 0, 100)
+            .await
+            .expect("Failed to update state");
+
+        // Verify state
+        let state = repo
+            .get_state(pool_id)
+            .await
+            .expect("Failed to get state")
+            .expect("State should exist");
+
+        assert_eq!(state.last_indexed_block, 19000000);
+        assert_eq!(state.total_events_processed, 100);
+        assert_eq!(state.reorg_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_from_block() {
+        let repo = setup_test_db().await;
+
+        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+            .parse()
+            .unwrap();
+        let pool_id = repo
+            .ensure_pool_exists(
+                pool_addr,
+                Some("USDC-WETH".to_string()),
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                    .parse()
+                    .unwrap(),
+                Some("USDC".to_string()),
+                6,
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                    .parse()
+                    .unwrap(),
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .unwrap();
+
+        // Insert events at different blocks
+        for block in 19000000..19000010 {
+            repo.insert_sync_event(
+                pool_id,
+                block,
+                FixedBytes::from([1u8; 32]),
+                1706745600,
+                FixedBytes::from([2u8; 32]),
+                0,
+                U256::from(1000000000u64),
+                U256::from(500000000000000000u64),
+                true,
+                &format!("sync-{block}"),
+            )
+            .await
+            .expect("Failed to insert sync event");
+        }
+
+        // Invalidate from block 19000005
+        repo.invalidate_from_block(pool_id, 19000005)
+            .await
+            .expect("Failed to invalidate");
+
+        // Blocks 19000005+ should be unconfirmed
+        // This is tested implicitly by verifying the update succeeded
+    }
+
+    #[tokio::test]
+    async fn test_last_written_block_and_event_block_hashes() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        assert_eq!(repo.get_last_written_block(pool_id).await.unwrap(), None);
+
+        for block in 100..110_u64 {
+            repo.insert_sync_event(
+                pool_id,
+                block,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                1_706_745_600,
+                FixedBytes::from([2u8; 32]),
+                0,
+                U256::from(1_000_000_000_u64),
+                U256::from(500_000_000_000_000_000_u64),
+                true,
+                &format!("sync-{block}"),
+            )
+            .await
+            .unwrap();
+        }
+        assert_eq!(
+            repo.get_last_written_block(pool_id).await.unwrap(),
+            Some(109)
+        );
+
+        let hashes = repo.get_event_block_hashes(pool_id, 105, 3).await.unwrap();
+        let blocks: Vec<u64> = hashes.iter().map(|(block, _)| *block).collect();
+        assert_eq!(blocks, vec![103, 104, 105]);
+        assert_eq!(
+            hashes[2].1.parse::<FixedBytes<32>>().unwrap(),
+            FixedBytes::from([105u8; 32])
+        );
+
+        // Unconfirmed rows no longer count as written
+        repo.invalidate_from_block(pool_id, 107).await.unwrap();
+        assert_eq!(
+            repo.get_last_written_block(pool_id).await.unwrap(),
+            Some(106)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_archived_events_are_read_back() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // Two events every 2,500 blocks from block 0 to 24,999
+        for block in (0..25_000_u64).step_by(2_500) {
+            for log_index in 0..2 {
+                repo.insert_sync_event(
+                    pool_id,
+                    block,
+                    FixedBytes::from([1u8; 32]),
+                    1_706_745_600 + block * 12,
+                    FixedBytes::from([2u8; 32]),
+                    log_index,
+                    U256::from(block + 1),
+                    U256::from(u64::from(log_index)),
+                    true,
+                    &format!("sync-{block}-{log_index}"),
+                )
+                .await
+                .unwrap();
+            }
+        }
+        let before = repo
+            .get_sync_events_in_blocks(pool_id, 0, 30_000)
+            .await
+            .unwrap();
+
+        // Only the two complete segments below block 25,000 are archived
+        let report = repo.archive_sync_events(pool_id, 25_000).await.unwrap();
+        assert_eq!((report.segments, report.events), (2, 16));
+        assert!(report.compressed_bytes > 0);
+        let listed = repo
+            .get_events_page(pool_id, BlockRange::ALL, None, 100, false)
+            .await
+            .unwrap();
+        assert_eq!((listed.total, listed.items.len()), (20, 20));
+
+        // Listings page through the archive in both directions
+        let page = repo
+            .get_events_page(
+                pool_id,
+                BlockRange::ALL,
+                Some(EventCursor::new(22_500, 0)),
+                3,
+                true,
+            )
+            .await
+            .unwrap();
+        let positions: Vec<_> = page
+            .items
+            .iter()
+            .map(|e| (e.block_number, e.log_index))
+            .collect();
+        assert_eq!(positions, vec![(20_000, 1), (20_000, 0), (17_500, 1)]);
+        let range = BlockRange::new(Some(5_000), Some(12_500)).unwrap();
+        let page = repo
+            .get_events_page(pool_id, range, None, 100, false)
+            .await
+            .unwrap();
+        assert_eq!((page.total, page.items.len()), (8, 8));
+
+        let after = repo
+            .get_sync_events_in_blocks(pool_id, 0, 30_000)
+            .await
+            .unwrap();
+        assert_eq!(after.len(), before.len());
+        assert!(after
+            .iter()
+            .zip(&before)
+            .all(|(a, b)| (a.block_number, a.log_index, &a.event_id)
+                == (b.block_number, b.log_index, &b.event_id)));
+
+        let at = repo
+            .get_sync_event_at(pool_id, 12_000)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((at.block_number, at.log_index), (10_000, 1));
+        let at = repo
+            .get_sync_event_at(pool_id, 21_000)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(at.block_number, 20_000);
+
+        // Keyset pages cross from the archive into raw rows
+        let page = repo
+            .get_sync_event_records(pool_id, 5_000, Some(EventCursor::new(17_500, 0)), 3)
+            .await
+            .unwrap();
+        let positions: Vec<_> = page.iter().map(|e| (e.block_number, e.log_index)).collect();
+        assert_eq!(positions, vec![(17_500, 1), (20_000, 0), (20_000, 1)]);
+
+        // A late event in an archived segment is merged on the next run
+        repo.insert_sync_event(
+            pool_id,
+            1_000,
+            FixedBytes::from([1u8; 32]),
+            1_706_757_600,
+            FixedBytes::from([3u8; 32]),
+            0,
+            U256::from(7u64),
+            U256::from(7u64),
+            true,
+            "sync-late",
+        )
+        .await
+        .unwrap();
+        let report = repo.archive_sync_events(pool_id, 25_000).await.unwrap();
+        assert_eq!((report.segments, report.events), (1, 1));
+        let archived = repo.get_archived_events(pool_id, 0, 9_999).await.unwrap();
+        assert_eq!(archived.len(), 9);
+        assert_eq!(archived[2].event_id.as_deref(), Some("sync-late"));
+    }
+
+    #[tokio::test]
+    async fn test_reorg_history() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let other_id = repo
+            .ensure_pool_exists(
+                "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+                    .parse()
+                    .unwrap(),
+                Some("USDC-WETH".to_string()),
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                    .parse()
+                    .unwrap(),
+                Some("USDC".to_string()),
+                6,
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                    .parse()
+                    .unwrap(),
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .unwrap();
+
+        for block in 100..110 {
+            repo.insert_sync_event(
+                pool_id,
+                block,
+                FixedBytes::from([1u8; 32]),
+                1_706_745_600,
+                FixedBytes::from([2u8; 32]),
+                0,
+                U256::from(1u64),
+                U256::from(2u64),
+                true,
+                &format!("sync-{block}"),
+            )
+            .await
+            .unwrap();
+        }
+
+        let stats = repo.get_reorg_stats(pool_id).await.unwrap();
+        assert_eq!(stats, ReorgStatsRow::default());
+
+        let reorg = |pool_id, detected_at, fork_point, depth| ReorgRecord {
+            pool_id,
+            detected_at,
+            fork_point,
+            depth,
+        };
+        repo.insert_reorg(&reorg(pool_id, 1_000, 106, 3))
+            .await
+            .unwrap();
+        repo.insert_reorg(&reorg(pool_id, 2_000, 108, 1))
+            .await
+            .unwrap();
+        repo.insert_reorg(&reorg(other_id, 3_000, 50, 2))
+            .await
+            .unwrap();
+
+        let page = repo
+            .get_reorgs_page(&PoolScope::All, Some(pool_id), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items[0].fork_point, 108);
+        assert_eq!(page.items[0].invalidated_events, 1);
+        assert_eq!(page.items[1].invalidated_events, 3);
+
+        let page = repo
+            .get_reorgs_page(&PoolScope::All, None, 1, 0)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items[0].pool_id, other_id);
+
+        let stats = repo.get_reorg_stats(pool_id).await.unwrap();
+        assert_eq!(stats.reorgs, 2);
+        assert_eq!(stats.max_depth, Some(3));
+        assert_eq!(stats.avg_depth, Some(2.0));
+        assert_eq!(stats.invalidated_events, 4);
+        assert_eq!(stats.last_detected_at, Some(2_000));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_event_ids_is_deterministic() {
+        let repo = setup_test_db().await;
+
+        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
+            .parse()
+            .unwrap();
+        let pool_id = repo
+            .ensure_pool_exists(
+                pool_addr,
+                Some("USDC-WETH".to_string()),
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                    .parse()
+                    .unwrap(),
                 Some("USDC".to_string()),
                 6,
-                token1_addr,
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+                    .parse()
+                    .unwrap(),
+                Some("WETH".to_string()),
+                18,
+            )
+            .await
+            .unwrap();
+
+        // Simulate rows written before event IDs existed
+        let block_hash = FixedBytes::from([1u8; 32]);
+        let tx_hash = FixedBytes::from([2u8; 32]);
+        let event = SyncEventRecord::new(
+            pool_id,
+            19_000_000,
+            block_hash,
+            1_706_745_600,
+            tx_hash,
+            3,
+            U256::from(1_000_000_000_u64),
+            U256::from(500_000_000_000_000_000_u64),
+            true,
+        );
+        let price = PricePointRecord::new(
+            pool_id,
+            19_000_000,
+            1_706_745_600,
+            tx_hash,
+            3500.0,
+            U256::from(1_000_000_000_u64),
+            U256::from(500_000_000_000_000_000_u64),
+            1000.0,
+            0.5,
+            true,
+        );
+        repo.batch_insert_sync_events(vec![event]).await.unwrap();
+        repo.batch_insert_price_points(vec![price]).await.unwrap();
+
+        let updated = repo.backfill_event_ids(1).await.unwrap();
+        assert_eq!(updated, 2);
+        assert_eq!(repo.backfill_event_ids(1).await.unwrap(), 0);
+
+        let events = repo
+            .get_recent_events(pool_id, BlockRange::ALL, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            events[0].event_id.as_deref(),
+            Some(
+                derive_record_id(RecordKind::SyncEvent, 1, pool_addr, block_hash, tx_hash, 3)
+                    .as_str()
+            )
+        );
+
+        let latest = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+        assert_eq!(
+            latest.event_id.as_deref(),
+            Some(
+                derive_record_id(RecordKind::PricePoint, 1, pool_addr, block_hash, tx_hash, 3)
+                    .as_str()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_exact_prices() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // 3 WETH / 10,000 USDT, written without an exact price
+        let price = PricePointRecord::new(
+            pool_id,
+            19_000_000,
+            1_706_745_600,
+            FixedBytes::from([2u8; 32]),
+            3_333.333_333_333_333,
+            U256::from(3u128 * 10u128.pow(18)),
+            U256::from(10_000u128 * 10u128.pow(6)),
+            3.0,
+            10_000.0,
+            true,
+        );
+        repo.batch_insert_price_points(vec![price]).await.unwrap();
+
+        assert_eq!(repo.backfill_exact_prices().await.unwrap(), 1);
+        assert_eq!(repo.backfill_exact_prices().await.unwrap(), 0);
+
+        let latest = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+        assert_eq!(
+            latest.price_exact.as_deref(),
+            Some("3333.333333333333333333")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_inserts_span_several_statements() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // More rows than fit in one statement of either table
+        let rows = INSERT_MAX_PARAMS / SYNC_EVENT_COLUMNS + 10;
+        let records = |reserve0: u64| {
+            (0..rows as u64)
+                .map(|i| {
+                    let block = 19_000_000 + i / 4;
+                    let tx_hash = FixedBytes::from(U256::from(i).to_be_bytes::<32>());
+                    let event = SyncEventRecord::new(
+                        pool_id,
+                        block,
+                        FixedBytes::from([1u8; 32]),
+                        1_706_745_600,
+                        tx_hash,
+                        (i % 4) as u32,
+                        U256::from(reserve0),
+                        U256::from(500_000_000_000_000_000_u64),
+                        true,
+                    );
+                    let price = PricePointRecord::new(
+                        pool_id,
+                        block,
+                        1_706_745_600,
+                        tx_hash,
+                        3500.0,
+                        U256::from(reserve0),
+                        U256::from(500_000_000_000_000_000_u64),
+                        1000.0,
+                        0.5,
+                        true,
+                    );
+                    (event, price)
+                })
+                .unzip::<_, _, Vec<_>, Vec<_>>()
+        };
+
+        let (events, prices) = records(1_000_000_000);
+        repo.batch_insert_sync_events(events).await.unwrap();
+        repo.batch_insert_price_points(prices).await.unwrap();
+
+        // Re-inserting the same rows updates them in place
+        let (events, prices) = records(2_000_000_000);
+        repo.batch_insert_sync_events(events).await.unwrap();
+        repo.batch_insert_price_points(prices).await.unwrap();
+
+        let updated_events: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sync_events WHERE reserve0 = '2000000000'")
+                .fetch_one(&repo.pool)
+                .await
+                .unwrap();
+        let updated_prices: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM price_points WHERE reserve0_raw = '2000000000'",
+        )
+        .fetch_one(&repo.pool)
+        .await
+        .unwrap();
+        assert_eq!(updated_events, i64::try_from(rows).unwrap());
+        assert_eq!(updated_prices, i64::try_from(rows).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_commit_batch_writes_rows_and_state_together() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let hash = FixedBytes::from([3u8; 32]);
+        let event = SyncEventRecord::new(
+            pool_id,
+            19_000_000,
+            hash,
+            1_706_745_600,
+            hash,
+            0,
+            U256::from(1_000_u64),
+            U256::from(2_000_u64),
+            true,
+        );
+        let price = |pool_id| {
+            PricePointRecord::new(
+                pool_id,
+                19_000_000,
+                1_706_745_600,
+                hash,
+                2.0,
+                U256::from(1_000_u64),
+                U256::from(2_000_u64),
+                1.0,
+                2.0,
+                true,
+            )
+        };
+        let state = IndexerState::new(pool_id, 19_000_000, hash, 0, 1);
+
+        // A failing write (unknown pool) leaves neither rows nor state behind;
+        // the state stays at the block 0 seeded with the pool
+        let failed = repo
+            .commit_batch(&[event.clone()], &[price(pool_id + 100)], &[], Some(&state))
+            .await;
+        assert!(failed.is_err());
+        let seeded = repo.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(seeded.last_indexed_block, 0);
+        let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sync_events")
+            .fetch_one(&repo.pool)
+            .await
+            .unwrap();
+        assert_eq!(events, 0);
+
+        repo.commit_batch(&[event], &[price(pool_id)], &[], Some(&state))
+            .await
+            .unwrap();
+        let stored = repo.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(stored.last_indexed_block, 19_000_000);
+        assert!(repo.get_latest_price(pool_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rewritten_prices_rebuild_their_rollups() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let hash = FixedBytes::from([4u8; 32]);
+        let price = |price: f64| {
+            PricePointRecord::new(
+                pool_id,
+                19_000_000,
+                1_706_745_600,
+                hash,
+                price,
+                U256::from(1_000_u64),
+                U256::from(2_000_u64),
+                1.0,
+                2.0,
+                true,
+            )
+        };
+        let rollup = || async {
+            sqlx::query_as::<_, (f64, i64)>(
+                "SELECT close, (SELECT COUNT(*) FROM price_rollups_stale) FROM price_rollups",
+            )
+            .fetch_one(&repo.pool)
+            .await
+            .unwrap()
+        };
+
+        // Two syncs in one transaction upsert the same price point
+        repo.commit_batch(&[], &[price(2000.0), price(2010.0)], &[], None)
+            .await
+            .unwrap();
+        assert_eq!(rollup().await, (2010.0, 0));
+
+        repo.batch_insert_price_points(vec![price(2020.0)])
+            .await
+            .unwrap();
+        assert_eq!(rollup().await, (2020.0, 0));
+        assert!(!repo.has_stale_rollups(pool_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_storage_stats_counts_rows_and_block_ranges() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let hash = FixedBytes::from([5u8; 32]);
+        for block in [19_000_000_u64, 19_000_010] {
+            let event = SyncEventRecord::new(
+                pool_id,
+                block,
+                hash,
+                1_706_745_600,
+                hash,
+                0,
+                U256::from(1_000_u64),
+                U256::from(2_000_u64),
+                true,
+            );
+            repo.batch_insert_sync_events(vec![event]).await.unwrap();
+        }
+        repo.update_state(pool_id, 19_000_010, hash, 0, 2)
+            .await
+            .unwrap();
+
+        let stats = repo.get_storage_stats(0).await.unwrap();
+        assert!(stats.page_size > 0);
+        assert!(stats.page_count > 0);
+
+        let sync_events = stats
+            .tables
+            .iter()
+            .find(|t| t.name == "sync_events")
+            .unwrap();
+        assert_eq!(sync_events.rows, 2);
+        assert_eq!(sync_events.recent_rows, Some(2));
+        assert!(!sync_events.indexes.is_empty());
+        // Rows written before the window aren't recent
+        let later = repo.get_storage_stats(i64::MAX).await.unwrap();
+        let sync_events = later
+            .tables
+            .iter()
+            .find(|t| t.name == "sync_events")
+            .unwrap();
+        assert_eq!(sync_events.recent_rows, Some(0));
+
+        let pool = stats.pools.iter().find(|p| p.pool_id == pool_id).unwrap();
+        assert_eq!(pool.oldest_block, Some(19_000_000));
+        assert_eq!(pool.newest_block, Some(19_000_010));
+        assert_eq!(pool.last_indexed_block, Some(19_000_010));
+        assert_eq!(pool.events, 2);
+        assert_eq!(pool.archived_events, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_price_history_reads_in_chunks() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // Two prices in each of five blocks, one of them outside the range
+        for block in 0..5_u64 {
+            for tx in [1_u8, 2] {
+                let price = PricePointRecord::new(
+                    pool_id,
+                    19_000_000 + block,
+                    1_706_745_600 + block * 12,
+                    FixedBytes::from([tx; 32]),
+                    2_000.0 + f64::from(tx),
+                    U256::from(1_000_u64),
+                    U256::from(2_000_u64),
+                    1.0,
+                    2.0,
+                    true,
+                );
+                repo.batch_insert_price_points(vec![price]).await.unwrap();
+            }
+        }
+
+        let rows: Vec<PricePointRow> = repo
+            .stream_price_history_chunked(
+                pool_id,
+                None,
+                Some(1_706_745_600 + 36),
+                BlockRange::ALL,
+                3,
+            )
+            .try_collect()
+            .await
+            .unwrap();
+        let keys: Vec<(i64, f64)> = rows.iter().map(|r| (r.block_number, r.price)).collect();
+        let expected: Vec<(i64, f64)> = (19_000_000..19_000_004)
+            .flat_map(|block| [(block, 2_001.0), (block, 2_002.0)])
+            .collect();
+        assert_eq!(keys, expected);
+
+        // A range ending on a chunk boundary ends with an empty chunk
+        let rows: Vec<PricePointRow> = repo
+            .stream_price_history_chunked(pool_id, None, None, BlockRange::ALL, 5)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_events_keyset_pagination() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // Two events per block across three blocks
+        for block in 19_000_000..19_000_003_u64 {
+            for log_index in [4_u32, 9] {
+                repo.insert_sync_event(
+                    pool_id,
+                    block,
+                    FixedBytes::from([1u8; 32]),
+                    1_706_745_600,
+                    FixedBytes::from([2u8; 32]),
+                    log_index,
+                    U256::from(1_000_000_000_u64),
+                    U256::from(500_000_000_000_000_000_u64),
+                    true,
+                    &format!("sync-{block}-{log_index}"),
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        let first = repo
+            .get_events_page(pool_id, BlockRange::ALL, None, 4, false)
+            .await
+            .unwrap();
+        assert_eq!(first.total, 6);
+        let first = first.items;
+        assert_eq!(first.len(), 4);
+        let last = first.last().unwrap();
+        assert_eq!((last.block_number, last.log_index), (19_000_001, 9));
+
+        let cursor = EventCursor::new(19_000_001, 9);
+        let second = repo
+            .get_events_page(pool_id, BlockRange::ALL, Some(cursor), 4, false)
+            .await
+            .unwrap();
+        let positions: Vec<_> = second
+            .items
+            .iter()
+            .map(|e| (e.block_number, e.log_index))
+            .collect();
+        assert_eq!(positions, vec![(19_000_002, 4), (19_000_002, 9)]);
+
+        let cursor = EventCursor::new(19_000_001, 4);
+        let older = repo
+            .get_events_page(pool_id, BlockRange::ALL, Some(cursor), 10, true)
+            .await
+            .unwrap();
+        let positions: Vec<_> = older
+            .items
+            .iter()
+            .map(|e| (e.block_number, e.log_index))
+            .collect();
+        assert_eq!(positions, vec![(19_000_000, 9), (19_000_000, 4)]);
+    }
+
+    #[tokio::test]
+    async fn test_block_range_filters() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        for block in 100..110_u64 {
+            #[allow(clippy::cast_precision_loss)]
+            let price = 2_000.0 + (block - 100) as f64;
+            let timestamp = 1_706_745_600 + block * 12;
+            repo.insert_price_point(
+                pool_id,
+                block,
+                timestamp,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                true,
+                &format!("range-{block}"),
+            )
+            .await
+            .unwrap();
+            repo.insert_sync_event(
+                pool_id,
+                block,
+                FixedBytes::from([1u8; 32]),
+                timestamp,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                0,
+                U256::from(1_000_000_000_u64),
+                U256::from(500_000_000_000_000_000_u64),
+                true,
+                &format!("range-sync-{block}"),
+            )
+            .await
+            .unwrap();
+        }
+        let blocks = BlockRange::new(Some(103), Some(106)).unwrap();
+
+        let page = repo
+            .get_price_history_paginated(pool_id, None, None, blocks, 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 4);
+        let numbers: Vec<_> = page.items.iter().map(|p| p.block_number).collect();
+        assert_eq!(numbers, vec![106, 105, 104, 103]);
+
+        // Timestamp and block bounds combine
+        let to_ts = 1_706_745_600 + 104 * 12;
+        let version = repo
+            .get_price_history_version(pool_id, None, Some(to_ts), blocks)
+            .await
+            .unwrap();
+        assert_eq!((version.count, version.max_block), (2, Some(104)));
+
+        let rows: Vec<PricePointRow> = repo
+            .stream_price_history_chunked(
+                pool_id,
+                None,
+                None,
+                BlockRange::new(Some(108), None).unwrap(),
+                1,
+            )
+            .try_collect()
+            .await
+            .unwrap();
+        let numbers: Vec<_> = rows.iter().map(|p| p.block_number).collect();
+        assert_eq!(numbers, vec![108, 109]);
+
+        let stats = repo
+            .get_stats_for_period(pool_id, 0, None, blocks)
+            .await
+            .unwrap();
+        assert_eq!(stats.total_events, 4);
+        assert_eq!(
+            (stats.min_price, stats.max_price, stats.first_price),
+            (2_003.0, 2_006.0, Some(2_003.0))
+        );
+
+        let events = repo
+            .get_recent_events(pool_id, BlockRange::new(None, Some(101)).unwrap(), 10)
+            .await
+            .unwrap();
+        let numbers: Vec<_> = events.iter().map(|e| e.block_number).collect();
+        assert_eq!(numbers, vec![101, 100]);
+
+        let page = repo
+            .get_events_page(pool_id, blocks, Some(EventCursor::new(104, 0)), 10, false)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 4);
+        let numbers: Vec<_> = page.items.iter().map(|e| e.block_number).collect();
+        assert_eq!(numbers, vec![105, 106]);
+
+        assert_eq!(BlockRange::new(Some(5), Some(4)), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_pools_page() {
+        let repo = setup_test_db().await;
+        for byte in 1..=3_u8 {
+            repo.ensure_pool_exists(
+                Address::repeat_byte(byte),
+                Some(format!("POOL-{byte}")),
+                Address::repeat_byte(0xa0),
+                Some("USDC".to_string()),
+                6,
+                Address::repeat_byte(0xc0),
                 Some("WETH".to_string()),
                 18,
             )
             .await
-            .expect("Failed to get pool");
+            .unwrap();
+        }
+
+        let page = repo.get_pools_page(&PoolScope::All, 2, 1).await.unwrap();
+        assert_eq!(page.total, 3);
+        let names: Vec<_> = page.items.iter().map(|p| p.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("POOL-2"), Some("POOL-3")]);
+
+        assert!(repo
+            .get_pools_page(&PoolScope::All, 2, 3)
+            .await
+            .unwrap()
+            .items
+            .is_empty());
+
+        // Scoped keys only page through their pools
+        let scope = PoolScope::Pools([1, 3].into_iter().collect());
+        let page = repo.get_pools_page(&scope, 10, 0).await.unwrap();
+        assert_eq!(page.total, 2);
+        let ids: Vec<_> = page.items.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        let none = PoolScope::Pools(std::collections::BTreeSet::new());
+        assert_eq!(repo.get_pools_page(&none, 10, 0).await.unwrap().total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_sync_event_at() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        for (block, log_index, reserve0) in [
+            (19_000_000_u64, 1_u32, 100_u64),
+            (19_000_005, 2, 200),
+            (19_000_005, 7, 300),
+        ] {
+            repo.insert_sync_event(
+                pool_id,
+                block,
+                FixedBytes::from([1u8; 32]),
+                1_706_745_600,
+                FixedBytes::from([2u8; 32]),
+                log_index,
+                U256::from(reserve0),
+                U256::from(1_000_u64),
+                true,
+                &format!("sync-{block}-{log_index}"),
+            )
+            .await
+            .unwrap();
+        }
+
+        assert!(repo
+            .get_sync_event_at(pool_id, 18_999_999)
+            .await
+            .unwrap()
+            .is_none());
+
+        let at = |block| repo.get_sync_event_at(pool_id, block);
+        assert_eq!(at(19_000_004).await.unwrap().unwrap().reserve0, "100");
+        // Last event within the block wins
+        assert_eq!(at(19_000_005).await.unwrap().unwrap().reserve0, "300");
+        assert_eq!(at(19_000_100).await.unwrap().unwrap().reserve0, "300");
+    }
+
+    #[tokio::test]
+    async fn test_get_prices_at_blocks() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        let prices = [
+            (100_u64, 1_u8, 1_000.0),
+            (105, 2, 1_050.0),
+            (105, 3, 1_055.0),
+        ]
+        .into_iter()
+        .map(|(block, tx, price)| {
+            PricePointRecord::new(
+                pool_id,
+                block,
+                1_706_745_600 + block,
+                FixedBytes::from([tx; 32]),
+                price,
+                U256::from(1u64),
+                U256::from(1u64),
+                1.0,
+                price,
+                true,
+            )
+        })
+        .collect();
+        repo.batch_insert_price_points(prices).await.unwrap();
+
+        let found = repo
+            .get_prices_at_blocks(pool_id, &[200, 99, 104, 105, 100, 104])
+            .await
+            .unwrap();
+        let summary: Vec<_> = found
+            .iter()
+            .map(|(block, price)| (*block, price.as_ref().map(|p| p.price)))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (99, None),
+                (100, Some(1_000.0)),
+                (104, Some(1_000.0)),
+                // Last price within the block wins
+                (105, Some(1_055.0)),
+                (200, Some(1_055.0)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_price_at_block_and_timestamp() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        let prices = [
+            (100_u64, 1_u8, 1_000.0),
+            (105, 2, 1_050.0),
+            (105, 3, 1_055.0),
+        ]
+        .into_iter()
+        .map(|(block, tx, price)| {
+            PricePointRecord::new(
+                pool_id,
+                block,
+                1_706_745_600 + block,
+                FixedBytes::from([tx; 32]),
+                price,
+                U256::from(1u64),
+                U256::from(1u64),
+                1.0,
+                price,
+                true,
+            )
+        })
+        .collect();
+        repo.batch_insert_price_points(prices).await.unwrap();
+
+        for (block, expected) in [
+            (99, None),
+            (104, Some(1_000.0)),
+            // Last price within the block wins
+            (105, Some(1_055.0)),
+            (u64::MAX, Some(1_055.0)),
+        ] {
+            let price = repo.get_price_at_block(pool_id, block).await.unwrap();
+            assert_eq!(price.map(|p| p.price), expected, "block {block}");
+        }
+
+        let price = repo
+            .get_price_at_timestamp(pool_id, 1_706_745_600 + 103)
+            .await
+            .unwrap();
+        assert_eq!(price.map(|p| p.block_number), Some(100));
+        let price = repo
+            .get_price_at_timestamp(pool_id, 1_706_745_600 + 200)
+            .await
+            .unwrap();
+        assert_eq!(price.map(|p| p.price), Some(1_055.0));
+        let price = repo
+            .get_price_at_timestamp(pool_id, 1_706_745_600)
+            .await
+            .unwrap();
+        assert!(price.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_candles_ohlc() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // Two one-minute buckets: [100, 120, 90] then [110]
+        let points = [
+            (1, 60, 100.0),
+            (2, 75, 120.0),
+            (3, 119, 90.0),
+            (4, 130, 110.0),
+        ];
+        for (block, ts, price) in points {
+            repo.insert_price_point(
+                pool_id,
+                block,
+                ts,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                true,
+                &format!("price-{block}"),
+            )
+            .await
+            .unwrap();
+        }
 
-        assert_eq!(pool_id, pool_id2);
+        let candles = repo
+            .get_candles(pool_id, 60, None, None, BlockRange::ALL, 10, None)
+            .await
+            .unwrap();
+        assert_eq!(candles.len(), 2);
+
+        let first = &candles[0];
+        assert_eq!(first.bucket_start, 60);
+        assert_eq!(
+            (first.open, first.high, first.low, first.close),
+            (100.0, 120.0, 90.0, 90.0)
+        );
+        assert_eq!(first.samples, 3);
+
+        assert_eq!(candles[1].bucket_start, 120);
+        assert_eq!(candles[1].close, 110.0);
+
+        // More buckets than the limit keeps the most recent
+        let latest = repo
+            .get_candles(pool_id, 60, None, None, BlockRange::ALL, 1, None)
+            .await
+            .unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].bucket_start, 120);
+        let recent = repo
+            .get_candles(pool_id, 30, None, None, BlockRange::ALL, 2, None)
+            .await
+            .unwrap();
+        let starts: Vec<i64> = recent.iter().map(|c| c.bucket_start).collect();
+        assert_eq!(starts, [90, 120]);
+
+        // Blocks 2 and 3 only
+        let blocks = BlockRange::new(Some(2), Some(3)).unwrap();
+        let ranged = repo
+            .get_candles(pool_id, 60, None, None, blocks, 10, None)
+            .await
+            .unwrap();
+        assert_eq!(ranged.len(), 1);
+        assert_eq!(
+            (ranged[0].open, ranged[0].close, ranged[0].samples),
+            (120.0, 90.0, 2)
+        );
+
+        assert!(repo
+            .get_candles(pool_id, 0, None, None, BlockRange::ALL, 10, None)
+            .await
+            .is_err());
     }
 
     #[tokio::test]
-    async fn test_insert_and_query_sync_event() {
+    async fn test_stats_from_rollups_match_price_points() {
         let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
 
-        // Create pool first
-        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
-            .parse()
-            .unwrap();
-        let pool_id = repo
-            .ensure_pool_exists(
-                pool_addr,
-                Some("USDC-WETH".to_string()),
-                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
-                    .parse()
-                    .unwrap(),
-                Some("USDC".to_string()),
-                6,
-                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-                    .parse()
-                    .unwrap(),
-                Some("WETH".to_string()),
-                18,
+        // Three hours of prices, the last block still unconfirmed
+        for block in 0..30_u64 {
+            #[allow(clippy::cast_precision_loss)]
+            let price = 2_000.0 + (block % 7) as f64 * 10.0;
+            repo.insert_price_point(
+                pool_id,
+                block,
+                block * 400,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                block < 29,
+                &format!("rollup-{block}"),
             )
             .await
             .unwrap();
+        }
+        repo.confirm_up_to_block(pool_id, 29).await.unwrap();
+        // A reorg drops the last two blocks
+        repo.invalidate_from_block(pool_id, 28).await.unwrap();
 
-        // Insert sync event
-        let event_id = repo
-            .insert_sync_event(
+        // Retention then drops the blocks before 5,000
+        for (pruned, total_events) in [(false, 28), (true, 15)] {
+            if pruned {
+                assert_eq!(repo.prune_price_points_before(5_000).await.unwrap(), 13);
+            }
+            // The hours touched were rebuilt along with each batch
+            assert!(!repo.has_stale_rollups(pool_id).await.unwrap());
+
+            // A spike filter no price can trip aggregates the price points
+            for from in [0, 1_000, 3_600, 5_000] {
+                let rollups = repo
+                    .get_stats_for_period(pool_id, from, None, BlockRange::ALL)
+                    .await
+                    .unwrap();
+                let raw = repo
+                    .get_stats_for_period(pool_id, from, Some(u32::MAX), BlockRange::ALL)
+                    .await
+                    .unwrap();
+                assert_eq!(rollups.total_events, raw.total_events, "from {from}");
+                assert_eq!(
+                    (rollups.min_price, rollups.max_price, rollups.first_price),
+                    (raw.min_price, raw.max_price, raw.first_price)
+                );
+                assert_eq!(
+                    (rollups.first_timestamp, rollups.last_timestamp),
+                    (raw.first_timestamp, raw.last_timestamp)
+                );
+                assert!((rollups.avg_price - raw.avg_price).abs() < 1e-9);
+            }
+            let stats = repo
+                .get_stats_for_period(pool_id, 0, None, BlockRange::ALL)
+                .await
+                .unwrap();
+            assert_eq!(
+                (stats.total_events, stats.last_timestamp),
+                (total_events, 27 * 400)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_24h_price_change_from_rollups() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let now = u64::try_from(chrono::Utc::now().timestamp()).unwrap();
+
+        // 2,000 two days ago, 2,200 25 hours ago, 2,420 now
+        for (block, age, price) in [(1, 172_800, 2_000.0), (2, 90_000, 2_200.0), (3, 0, 2_420.0)] {
+            repo.insert_price_point(
                 pool_id,
-                19000000,
-                FixedBytes::from([1u8; 32]),
-                1706745600,
-                FixedBytes::from([2u8; 32]),
-                0,
-                U256::from(1000000000u64),
-                U256::from(500000000000000000u64),
-                false,
+                block,
+                now - age,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                true,
+                &format!("change-{block}"),
             )
             .await
-            .expect("Failed to insert sync event");
+            .unwrap();
+        }
 
-        assert!(event_id > 0);
+        let change = repo.get_24h_price_change(pool_id).await.unwrap();
+        assert!((change - 10.0).abs() < 1e-9, "{change}");
     }
 
     #[tokio::test]
-    async fn test_insert_and_query_price_point() {
+    async fn test_spike_filter_in_candles_and_stats() {
         let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
 
-        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
-            .parse()
-            .unwrap();
-        let pool_id = repo
-            .ensure_pool_exists(
-                pool_addr,
-                Some("USDC-WETH".to_string()),
-                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
-                    .parse()
-                    .unwrap(),
-                Some("USDC".to_string()),
-                6,
-                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-                    .parse()
-                    .unwrap(),
-                Some("WETH".to_string()),
-                18,
+        // Block 2 is pushed to 3,000 and back; block 3 moves for good
+        let points = [
+            (1, 60, 2_000.0),
+            (2, 72, 3_000.0),
+            (2, 72, 2_000.5),
+            (3, 84, 2_400.0),
+            (3, 84, 2_100.0),
+        ];
+        for (i, (block, ts, price)) in points.into_iter().enumerate() {
+            repo.insert_price_point(
+                pool_id,
+                block,
+                ts,
+                FixedBytes::from([u8::try_from(i + 1).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                true,
+                &format!("price-{i}"),
             )
             .await
             .unwrap();
+        }
 
-        // Insert price point
-        let price_id = repo
-            .insert_price_point(
-                pool_id,
-                19000000,
-                1706745600,
-                FixedBytes::from([2u8; 32]),
-                3500.0,
-                U256::from(1000000000u64),
-                U256::from(500000000000000000u64),
-                1000.0,
-                0.5,
-                false,
-            )
+        let unfiltered = repo
+            .get_stats_for_period(pool_id, 0, None, BlockRange::ALL)
             .await
-            .expect("Failed to insert price point");
+            .unwrap();
+        assert_eq!(unfiltered.total_events, 5);
+        assert_eq!(unfiltered.max_price, 3_000.0);
 
-        assert!(price_id > 0);
+        let stats = repo
+            .get_stats_for_period(pool_id, 0, Some(500), BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(stats.total_events, 4);
+        assert_eq!(stats.max_price, 2_400.0);
+        assert_eq!(stats.first_price, Some(2_000.0));
 
-        // Query recent prices
-        let prices = repo
-            .get_recent_prices(pool_id, 10)
+        let candles = repo
+            .get_candles(pool_id, 60, None, None, BlockRange::ALL, 10, Some(500))
             .await
-            .expect("Failed to query prices");
+            .unwrap();
+        assert_eq!(candles.len(), 1);
+        assert_eq!(
+            (candles[0].high, candles[0].close, candles[0].samples),
+            (2_400.0, 2_100.0, 4)
+        );
 
-        assert_eq!(prices.len(), 1);
-        assert_eq!(prices[0].price, 3500.0);
+        // A window starting inside the spike block has no price to compare to
+        let stats = repo
+            .get_stats_for_period(pool_id, 72, Some(500), BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(stats.total_events, 4);
     }
 
     #[tokio::test]
-    async fn test_state_management() {
+    async fn test_get_price_timeseries() {
         let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
 
-        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
-            .parse()
-            .unwrap();
-        let pool_id = repo
-            .ensure_pool_exists(
-                pool_addr,
-                Some("USDC-WETH".to_string()),
-                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
-                    .parse()
-                    .unwrap(),
-                Some("USDC".to_string()),
-                6,
-                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-                    .parse()
-                    .unwrap(),
-                Some("WETH".to_string()),
-                18,
+        // Two one-minute buckets: [100, 120, 90] then [110]
+        for (block, ts, price) in [
+            (1, 60, 100.0),
+            (2, 75, 120.0),
+            (3, 119, 90.0),
+            (4, 130, 110.0),
+        ] {
+            repo.insert_price_point(
+                pool_id,
+                block,
+                ts,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                true,
+                &format!("price-{block}"),
             )
             .await
             .unwrap();
+        }
 
-        // No state initially
-        let state = repo.get_state(pool_id).await.expect("Failed to get state");
-        assert!(state.is_none());
+        let stored = QuoteDirection::default();
+        let series =
+            |agg| repo.get_price_timeseries(pool_id, 60, agg, stored, 0, 1_000, BlockRange::ALL);
+        assert_eq!(
+            series(TimeseriesAgg::Avg).await.unwrap(),
+            vec![(60, 310.0 / 3.0), (120, 110.0)]
+        );
+        assert_eq!(
+            series(TimeseriesAgg::Last).await.unwrap(),
+            vec![(60, 90.0), (120, 110.0)]
+        );
+        assert_eq!(
+            series(TimeseriesAgg::Max).await.unwrap(),
+            vec![(60, 120.0), (120, 110.0)]
+        );
 
-        // Update state
-        let block_hash = FixedBytes::from([1u8; 32]);
-        repo.update_state(pool_id, 19000000, block_hash, 0, 100)
+        let tail = repo
+            .get_price_timeseries(
+                pool_id,
+                60,
+                TimeseriesAgg::Max,
+                stored,
+                100,
+                1_000,
+                BlockRange::ALL,
+            )
             .await
-            .expect("Failed to update state");
+            .unwrap();
+        assert_eq!(tail, vec![(60, 90.0), (120, 110.0)]);
 
-        // Verify state
-        let state = repo
-            .get_state(pool_id)
+        let from_block_2 = repo
+            .get_price_timeseries(
+                pool_id,
+                60,
+                TimeseriesAgg::Avg,
+                stored,
+                0,
+                1_000,
+                BlockRange::new(Some(2), None).unwrap(),
+            )
             .await
-            .expect("Failed to get state")
-            .expect("State should exist");
+            .unwrap();
+        assert_eq!(from_block_2, vec![(60, 105.0), (120, 110.0)]);
 
-        assert_eq!(state.last_indexed_block, 19000000);
-        assert_eq!(state.total_events_processed, 100);
-        assert_eq!(state.reorg_count, 0);
+        // The inverse's maximum is the reciprocal of the minimum
+        let inverse = repo
+            .get_price_timeseries(
+                pool_id,
+                60,
+                TimeseriesAgg::Max,
+                stored.inverted(true),
+                0,
+                1_000,
+                BlockRange::ALL,
+            )
+            .await
+            .unwrap();
+        assert_eq!(inverse, vec![(60, 1.0 / 90.0), (120, 1.0 / 110.0)]);
     }
 
     #[tokio::test]
-    async fn test_invalidate_from_block() {
+    async fn test_reader_sees_writes_but_cannot_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite:{}", dir.path().join("indexer.db").display());
+        let repo = Repository::new(create_pool(&url).await.unwrap());
+        let reader = repo.reader().await.unwrap();
+
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        assert_eq!(reader.get_all_pools().await.unwrap()[0].id, pool_id);
+
+        let write = reader
+            .set_pool_protocol(pool_id, DexProtocol::SushiSwap)
+            .await;
+        assert!(write.is_err());
+
+        // In-memory databases can't be reopened, so the reader shares them
         let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let reader = repo.reader().await.unwrap();
+        assert_eq!(reader.get_all_pools().await.unwrap()[0].id, pool_id);
+    }
 
-        let pool_addr: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"
-            .parse()
+    #[tokio::test]
+    async fn test_follow_primary_copies_new_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let primary_path = dir.path().join("primary.db");
+        let primary = Repository::new(
+            create_pool(&format!("sqlite:{}", primary_path.display()))
+                .await
+                .unwrap(),
+        );
+        let pool_id = primary.ensure_default_pool().await.unwrap();
+
+        let insert = |block: u64| {
+            let primary = &primary;
+            async move {
+                primary
+                    .insert_sync_event(
+                        pool_id,
+                        block,
+                        FixedBytes::from([1u8; 32]),
+                        1_700_000_000,
+                        FixedBytes::from([u8::try_from(block % 256).unwrap(); 32]),
+                        0,
+                        U256::from(1_u64),
+                        U256::from(2_u64),
+                        true,
+                        &format!("event-{block}"),
+                    )
+                    .await
+                    .unwrap();
+                primary
+                    .update_state(pool_id, block, FixedBytes::from([1u8; 32]), 0, block)
+                    .await
+                    .unwrap();
+            }
+        };
+
+        insert(100).await;
+        insert(101).await;
+
+        let standby = setup_test_db().await;
+        let report = standby.follow_primary(&primary_path).await.unwrap();
+        assert_eq!(report.events_synced, 2);
+        assert_eq!(report.last_indexed_block, 101);
+
+        insert(102).await;
+        let report = standby.follow_primary(&primary_path).await.unwrap();
+        assert_eq!(report.last_indexed_block, 102);
+
+        let events = standby
+            .get_recent_events(pool_id, BlockRange::ALL, 10)
+            .await
             .unwrap();
-        let pool_id = repo
-            .ensure_pool_exists(
-                pool_addr,
-                Some("USDC-WETH".to_string()),
-                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
-                    .parse()
-                    .unwrap(),
-                Some("USDC".to_string()),
-                6,
-                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-                    .parse()
-                    .unwrap(),
-                Some("WETH".to_string()),
-                18,
-            )
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            standby.get_pool_by_id(pool_id).await.unwrap().unwrap().name,
+            Some("WETH/USDT".to_string())
+        );
+
+        assert!(standby
+            .follow_primary(&dir.path().join("missing.db"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        let repo = setup_test_db().await;
+
+        let id = repo
+            .insert_api_key("dashboard", "abcd1234", "hash-1", Some(30))
             .await
             .unwrap();
+        let key = repo.find_active_api_key("hash-1").await.unwrap().unwrap();
+        assert_eq!(key.id, id);
+        assert_eq!(key.rate_limit_rpm, Some(30));
+        assert!(repo.find_active_api_key("hash-2").await.unwrap().is_none());
 
-        // Insert events at different blocks
-        for block in 19000000..19000010 {
-            repo.insert_sync_event(
-                pool_id,
-                block,
-                FixedBytes::from([1u8; 32]),
-                1706745600,
-                FixedBytes::from([2u8; 32]),
-                0,
-                U256::from(1000000000u64),
-                U256::from(500000000000000000u64),
-                true,
-            )
+        assert_eq!(repo.revoke_api_key("abcd1234").await.unwrap(), 1);
+        assert!(repo.find_active_api_key("hash-1").await.unwrap().is_none());
+        assert_eq!(repo.revoke_api_key(&id.to_string()).await.unwrap(), 0);
+
+        let keys = repo.list_api_keys().await.unwrap();
+        assert!(keys[0].revoked_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_api_key_scope() {
+        let repo = setup_test_db().await;
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let id = repo
+            .insert_api_key("team-a", "abcd1234", "hash-1", None)
             .await
-            .expect("Failed to insert sync event");
-        }
+            .unwrap();
 
-        // Invalidate from block 19000005
-        repo.invalidate_from_block(pool_id, 19000005)
+        let key = repo.find_active_api_key("hash-1").await.unwrap().unwrap();
+        assert_eq!(repo.get_api_key_scope(&key).await.unwrap(), PoolScope::All);
+
+        repo.set_api_key_scope(id, Some(&[pool_id]), Some(1))
             .await
-            .expect("Failed to invalidate");
+            .unwrap();
+        let key = repo.find_active_api_key("hash-1").await.unwrap().unwrap();
+        assert_eq!(key.chain_id, Some(1));
+        let scope = repo.get_api_key_scope(&key).await.unwrap();
+        assert!(scope.allows(pool_id));
+        assert!(!scope.allows(pool_id + 1));
 
-        // Blocks 19000005+ should be unconfirmed
-        // This is tested implicitly by verifying the update succeeded
+        // Removing its last pool leaves the key seeing nothing, not everything
+        repo.delete_pool(pool_id).await.unwrap();
+        let scope = repo.get_api_key_scope(&key).await.unwrap();
+        assert_eq!(scope, PoolScope::Pools(std::collections::BTreeSet::new()));
+
+        assert!(repo.set_api_key_scope(id + 1, None, None).await.is_err());
     }
 }
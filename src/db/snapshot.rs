@@ -0,0 +1,629 @@
+//! Compressed, checksummed database snapshots.
+//!
+//! A snapshot is a gzip file holding a consistent copy of the database taken
+//! with `VACUUM INTO`, so it can be taken while the indexer and API are
+//! running. The gzip header comment carries a JSON [`SnapshotManifest`] with
+//! the schema version and the Keccak-256 checksum and size of the
+//! uncompressed database; [`restore_snapshot`] verifies both before anything
+//! is written to the target path.
+//!
+//! Snapshots taken at an older schema version are restored as-is and migrated
+//! on the next start. Snapshots from a newer schema are rejected.
+//!
+//! A published snapshot can seed a new deployment with [`bootstrap_from_url`],
+//! which downloads it and restores it the same way; pinning the published
+//! checksum guards against a tampered or truncated download.
+
+use alloy::primitives::Keccak256;
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression, GzBuilder};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{Connection, SqlitePool};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use super::MIGRATOR;
+use crate::error::TrackerError;
+
+/// Snapshot layout version, bumped on incompatible changes.
+pub const SNAPSHOT_FORMAT: u32 = 1;
+
+/// Read/write chunk size while hashing and (de)compressing.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Metadata stored in a snapshot's gzip header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Snapshot layout version ([`SNAPSHOT_FORMAT`])
+    pub format: u32,
+    /// When the snapshot was taken
+    pub created_at: DateTime<Utc>,
+    /// Latest migration applied to the snapshotted database
+    pub schema_version: i64,
+    /// Size of the uncompressed database in bytes
+    pub database_bytes: u64,
+    /// Keccak-256 of the uncompressed database (hex)
+    pub checksum: String,
+}
+
+/// Writes a compressed snapshot of the database behind `pool` to `path`.
+///
+/// # Errors
+///
+/// Returns an error if `path` already exists, or the copy, compression or
+/// write fails.
+pub async fn write_snapshot(
+    pool: &SqlitePool,
+    path: &Path,
+) -> Result<SnapshotManifest, TrackerError> {
+    if path.exists() {
+        return Err(TrackerError::database(
+            format!("Snapshot {} already exists", path.display()),
+            None,
+        ));
+    }
+
+    let (schema_version,) = sqlx::query_as::<_, (i64,)>(
+        "SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success = 1",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        TrackerError::database(
+            "Failed to read schema version".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    // VACUUM INTO gives a consistent, compacted copy without blocking writers
+    let copy = sibling(path, "vacuum");
+    let _ = std::fs::remove_file(&copy);
+    sqlx::query("VACUUM INTO ?")
+        .bind(copy.to_string_lossy().as_ref())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                format!("Failed to copy database to {}", copy.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+
+    let result = compress(&copy, path, schema_version);
+    let _ = std::fs::remove_file(&copy);
+    let manifest = result?;
+
+    info!(
+        path = %path.display(),
+        bytes = manifest.database_bytes,
+        checksum = %manifest.checksum,
+        "Wrote database snapshot"
+    );
+    Ok(manifest)
+}
+
+/// Reads a snapshot's manifest without decompressing it.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is not a snapshot.
+pub fn read_manifest(path: &Path) -> Result<SnapshotManifest, TrackerError> {
+    let file = File::open(path).map_err(|e| {
+        TrackerError::database(
+            format!("Failed to open snapshot {}", path.display()),
+            Some(Box::new(e)),
+        )
+    })?;
+    let decoder = GzDecoder::new(BufReader::new(file));
+
+    let comment = decoder
+        .header()
+        .and_then(flate2::GzHeader::comment)
+        .ok_or_else(|| {
+            TrackerError::database(
+                format!("{} is not a database snapshot", path.display()),
+                None,
+            )
+        })?;
+
+    let manifest: SnapshotManifest = serde_json::from_slice(comment).map_err(|e| {
+        TrackerError::database(
+            format!("Invalid snapshot manifest in {}", path.display()),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    if manifest.format != SNAPSHOT_FORMAT {
+        return Err(TrackerError::database(
+            format!(
+                "Unsupported snapshot format {} (expected {SNAPSHOT_FORMAT})",
+                manifest.format
+            ),
+            None,
+        ));
+    }
+
+    Ok(manifest)
+}
+
+/// Restores the snapshot at `snapshot` to the database file `database_path`.
+///
+/// The database is decompressed next to the target and only moved into place
+/// once its checksum, size and `PRAGMA integrity_check` pass. An existing
+/// database (and its WAL files) is replaced only with `overwrite`. The
+/// database must not be open while it is restored.
+///
+/// # Errors
+///
+/// Returns an error if the snapshot is unreadable, fails verification, comes
+/// from a newer schema, or the target exists without `overwrite`.
+pub async fn restore_snapshot(
+    snapshot: &Path,
+    database_path: &Path,
+    overwrite: bool,
+) -> Result<SnapshotManifest, TrackerError> {
+    let manifest = read_manifest(snapshot)?;
+
+    let latest_known = MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0);
+    if manifest.schema_version > latest_known {
+        return Err(TrackerError::database(
+            format!(
+                "Snapshot schema version {} is newer than this build supports ({latest_known})",
+                manifest.schema_version
+            ),
+            None,
+        ));
+    }
+
+    if database_path.exists() && !overwrite {
+        return Err(TrackerError::database(
+            format!(
+                "{} already exists; pass --force to replace it",
+                database_path.display()
+            ),
+            None,
+        ));
+    }
+
+    let staged = sibling(database_path, "restore");
+    if let Err(e) = stage(snapshot, &staged, &manifest).await {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(append(database_path, suffix));
+    }
+    std::fs::rename(&staged, database_path).map_err(|e| {
+        TrackerError::database(
+            format!(
+                "Failed to move restored database to {}",
+                database_path.display()
+            ),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    info!(
+        snapshot = %snapshot.display(),
+        path = %database_path.display(),
+        schema_version = manifest.schema_version,
+        "Restored database snapshot"
+    );
+    Ok(manifest)
+}
+
+/// Timeout for downloading a published snapshot.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(3_600);
+
+/// Downloads the snapshot at `url` and restores it to `database_path`.
+///
+/// The download is streamed to a temporary file next to the target and
+/// removed afterwards. With `expected_checksum`, the snapshot's manifest
+/// must carry that checksum (case-insensitive hex) before anything is
+/// restored; [`restore_snapshot`] then verifies the data against it.
+///
+/// # Errors
+///
+/// Returns an error if the target exists without `overwrite`, the download
+/// fails, the checksum doesn't match, or the restore fails.
+pub async fn bootstrap_from_url(
+    url: &str,
+    database_path: &Path,
+    overwrite: bool,
+    expected_checksum: Option<&str>,
+) -> Result<SnapshotManifest, TrackerError> {
+    // Fail before a long download rather than after it
+    if database_path.exists() && !overwrite {
+        return Err(TrackerError::database(
+            format!(
+                "{} already exists; pass --force to replace it",
+                database_path.display()
+            ),
+            None,
+        ));
+    }
+
+    let download = sibling(database_path, "download");
+    let result = async {
+        let bytes = download_snapshot(url, &download).await?;
+        info!(url, bytes, "Downloaded database snapshot");
+
+        if let Some(expected) = expected_checksum {
+            let manifest = read_manifest(&download)?;
+            if !manifest
+                .checksum
+                .eq_ignore_ascii_case(expected.trim_start_matches("0x"))
+            {
+                return Err(TrackerError::database(
+                    format!(
+                        "Snapshot checksum {} does not match the expected {expected}",
+                        manifest.checksum
+                    ),
+                    None,
+                ));
+            }
+        }
+
+        restore_snapshot(&download, database_path, overwrite).await
+    }
+    .await;
+
+    let _ = std::fs::remove_file(&download);
+    result
+}
+
+/// Streams the body of `url` into `path`, returning the bytes written.
+async fn download_snapshot(url: &str, path: &Path) -> Result<u64, TrackerError> {
+    let download_error = |e: reqwest::Error| {
+        TrackerError::rpc(format!("Failed to download {url}"), Some(Box::new(e)))
+    };
+    let write_error = |e: std::io::Error| {
+        TrackerError::database(
+            format!("Failed to write {}", path.display()),
+            Some(Box::new(e)),
+        )
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(download_error)?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(download_error)?;
+
+    let mut file = tokio::fs::File::create(path).await.map_err(write_error)?;
+    let mut written = 0u64;
+    while let Some(chunk) = response.chunk().await.map_err(download_error)? {
+        file.write_all(&chunk).await.map_err(write_error)?;
+        written += chunk.len() as u64;
+    }
+    file.sync_all().await.map_err(write_error)?;
+
+    Ok(written)
+}
+
+/// Resolves the database file behind a `sqlite:` URL.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid or names an in-memory database.
+pub fn database_path(database_url: &str) -> Result<PathBuf, TrackerError> {
+    let options = SqliteConnectOptions::from_str(database_url).map_err(|e| {
+        TrackerError::database(
+            format!("Failed to parse database URL: {database_url}"),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    // sqlx names anonymous in-memory databases `file:sqlx-in-memory-N`
+    let path = options.get_filename();
+    if path.as_os_str().is_empty()
+        || path.to_string_lossy().starts_with("file:")
+        || database_url.contains("mode=memory")
+    {
+        return Err(TrackerError::database(
+            format!("{database_url} is an in-memory database"),
+            None,
+        ));
+    }
+    Ok(path.to_path_buf())
+}
+
+/// Hashes the uncompressed database, then compresses it to `path` with the
+/// manifest in the gzip header.
+fn compress(
+    database: &Path,
+    path: &Path,
+    schema_version: i64,
+) -> Result<SnapshotManifest, TrackerError> {
+    let (checksum, database_bytes) = hash_file(database)?;
+    let manifest = SnapshotManifest {
+        format: SNAPSHOT_FORMAT,
+        created_at: Utc::now(),
+        schema_version,
+        database_bytes,
+        checksum,
+    };
+    let comment = serde_json::to_vec(&manifest).map_err(|e| {
+        TrackerError::database(
+            "Failed to encode snapshot manifest".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+
+    let write_error = |e: std::io::Error| {
+        TrackerError::database(
+            format!("Failed to write snapshot {}", path.display()),
+            Some(Box::new(e)),
+        )
+    };
+
+    let mut input = open(database)?;
+    let output = File::create(path).map_err(write_error)?;
+    let mut encoder: GzEncoder<BufWriter<File>> = GzBuilder::new()
+        .comment(comment)
+        .write(BufWriter::new(output), Compression::default());
+    std::io::copy(&mut input, &mut encoder).map_err(write_error)?;
+    encoder
+        .finish()
+        .and_then(|mut writer| writer.flush())
+        .map_err(write_error)?;
+
+    Ok(manifest)
+}
+
+/// Decompresses a snapshot to `staged` and verifies it against `manifest`.
+async fn stage(
+    snapshot: &Path,
+    staged: &Path,
+    manifest: &SnapshotManifest,
+) -> Result<(), TrackerError> {
+    let read_error = |e: std::io::Error| {
+        TrackerError::database(
+            format!("Failed to decompress snapshot {}", snapshot.display()),
+            Some(Box::new(e)),
+        )
+    };
+
+    let mut decoder = GzDecoder::new(open(snapshot)?);
+    let output = File::create(staged).map_err(|e| {
+        TrackerError::database(
+            format!("Failed to create {}", staged.display()),
+            Some(Box::new(e)),
+        )
+    })?;
+    let mut writer = BufWriter::new(output);
+
+    let mut hasher = Keccak256::new();
+    let mut total = 0u64;
+    let mut buf = vec![0u8; CHUNK_BYTES];
+    loop {
+        let n = decoder.read(&mut buf).map_err(read_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n]).map_err(read_error)?;
+        total += n as u64;
+    }
+    writer.flush().map_err(read_error)?;
+    drop(writer);
+
+    let checksum = alloy::hex::encode(hasher.finalize());
+    if total != manifest.database_bytes || checksum != manifest.checksum {
+        return Err(TrackerError::database(
+            format!(
+                "Snapshot {} failed verification: expected {} bytes with checksum {}, got {total} bytes with checksum {checksum}",
+                snapshot.display(),
+                manifest.database_bytes,
+                manifest.checksum
+            ),
+            None,
+        ));
+    }
+
+    let mut conn = SqliteConnection::connect_with(
+        &SqliteConnectOptions::new().filename(staged).read_only(true),
+    )
+    .await
+    .map_err(|e| {
+        TrackerError::database(
+            "Failed to open restored database".to_string(),
+            Some(Box::new(e)),
+        )
+    })?;
+    let (status,) = sqlx::query_as::<_, (String,)>("PRAGMA integrity_check")
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| {
+            TrackerError::database(
+                "Failed to check restored database".to_string(),
+                Some(Box::new(e)),
+            )
+        })?;
+    let _ = conn.close().await;
+
+    if status != "ok" {
+        return Err(TrackerError::database(
+            format!("Restored database failed integrity check: {status}"),
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the Keccak-256 (hex) and size of a file.
+fn hash_file(path: &Path) -> Result<(String, u64), TrackerError> {
+    let mut input = open(path)?;
+    let mut hasher = Keccak256::new();
+    let mut total = 0u64;
+    let mut buf = vec![0u8; CHUNK_BYTES];
+    loop {
+        let n = input.read(&mut buf).map_err(|e| {
+            TrackerError::database(
+                format!("Failed to read {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        total += n as u64;
+    }
+    Ok((alloy::hex::encode(hasher.finalize()), total))
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, TrackerError> {
+    File::open(path).map(BufReader::new).map_err(|e| {
+        TrackerError::database(
+            format!("Failed to open {}", path.display()),
+            Some(Box::new(e)),
+        )
+    })
+}
+
+/// `path` with `suffix` appended to the file name.
+fn append(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Temporary file next to `path`.
+fn sibling(path: &Path, purpose: &str) -> PathBuf {
+    append(path, &format!(".{purpose}-tmp"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::db::repository::Repository;
+
+    async fn seeded_pool(dir: &Path) -> SqlitePool {
+        let url = format!("sqlite:{}", dir.join("source.db").display());
+        let pool = create_pool(&url).await.unwrap();
+        Repository::new(pool.clone())
+            .ensure_default_pool()
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("indexer.db.gz");
+        let target = dir.path().join("restored.db");
+
+        let written = Repository::new(seeded_pool(dir.path()).await)
+            .create_snapshot(&snapshot)
+            .await
+            .unwrap();
+        assert_eq!(read_manifest(&snapshot).unwrap(), written);
+        assert!(written.schema_version > 0);
+
+        let restored = restore_snapshot(&snapshot, &target, false).await.unwrap();
+        assert_eq!(restored, written);
+
+        let url = format!("sqlite:{}", target.display());
+        let repository = Repository::new(create_pool(&url).await.unwrap());
+        assert!(repository
+            .get_pool_by_name("WETH/USDT")
+            .await
+            .unwrap()
+            .is_some());
+
+        // The target exists now
+        assert!(restore_snapshot(&snapshot, &target, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_checksum_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("indexer.db.gz");
+        let tampered = dir.path().join("tampered.db.gz");
+        let target = dir.path().join("restored.db");
+
+        let mut manifest = write_snapshot(&seeded_pool(dir.path()).await, &snapshot)
+            .await
+            .unwrap();
+
+        // Same data, wrong checksum in the header
+        manifest.checksum = "00".repeat(32);
+        let mut data = Vec::new();
+        GzDecoder::new(File::open(&snapshot).unwrap())
+            .read_to_end(&mut data)
+            .unwrap();
+        let mut encoder = GzBuilder::new()
+            .comment(serde_json::to_vec(&manifest).unwrap())
+            .write(File::create(&tampered).unwrap(), Compression::fast());
+        encoder.write_all(&data).unwrap();
+        encoder.finish().unwrap();
+
+        let result = restore_snapshot(&tampered, &target, false).await;
+        assert!(result.is_err_and(|e| e.to_string().contains("failed verification")));
+        assert!(!target.exists());
+        assert!(!sibling(&target, "restore").exists());
+    }
+
+    /// Serves `body` at `/snapshot.db.gz` on a local port.
+    async fn serve(body: Vec<u8>) -> String {
+        let app = axum::Router::new().route(
+            "/snapshot.db.gz",
+            axum::routing::get(move || async move { body }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{addr}/snapshot.db.gz")
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_from_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("indexer.db.gz");
+        let target = dir.path().join("bootstrapped.db");
+
+        let written = write_snapshot(&seeded_pool(dir.path()).await, &snapshot)
+            .await
+            .unwrap();
+        let url = serve(std::fs::read(&snapshot).unwrap()).await;
+
+        // A pinned checksum that doesn't match stops before the restore
+        let result = bootstrap_from_url(&url, &target, false, Some(&"ab".repeat(32))).await;
+        assert!(result.is_err_and(|e| e.to_string().contains("does not match")));
+        assert!(!target.exists());
+        assert!(!sibling(&target, "download").exists());
+
+        let checksum = format!("0x{}", written.checksum.to_uppercase());
+        let restored = bootstrap_from_url(&url, &target, false, Some(&checksum))
+            .await
+            .unwrap();
+        assert_eq!(restored, written);
+        assert!(target.exists());
+        assert!(!sibling(&target, "download").exists());
+    }
+
+    #[test]
+    fn test_database_path() {
+        assert_eq!(
+            database_path("sqlite:./indexer.db").unwrap(),
+            PathBuf::from("./indexer.db")
+        );
+        assert!(database_path("sqlite::memory:").is_err());
+    }
+}
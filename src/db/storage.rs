@@ -0,0 +1,234 @@
+//! Storage abstraction for embedding the indexer.
+//!
+//! [`Storage`] covers the writes and reads the indexer itself performs:
+//! resolving pools, inserting sync events, swap events and price points, reading and
+//! writing per-pool indexer state, committing a batch of rows together with
+//! the state that covers them, and moving the confirmation boundary on
+//! reorgs and finality. The built-in [`Repository`] implements it; embedders
+//! with an existing database (e.g. Postgres) can
+//! implement it over their own schema instead.
+//!
+//! The API server's read queries (history, stats, candles) are not part of
+//! the trait and still require the built-in repository.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use eth_uniswap_alloy::db::{create_pool, repository::Repository, storage::Storage};
+//!
+//! async fn last_block(storage: &dyn Storage) -> Result<i64, Box<dyn std::error::Error>> {
+//!     let pool = storage.find_pool("WETH/USDT").await?.ok_or("pool not tracked")?;
+//!     Ok(storage.get_state(pool.id).await?.map_or(0, |s| s.last_indexed_block))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let repo = Repository::new(create_pool("sqlite:./indexer.db").await?);
+//! println!("Indexed up to block {}", last_block(&repo).await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+
+use super::models::{
+    IndexerState, PoolRecord, PricePointRecord, ReorgRecord, SwapEventRecord, SyncEventRecord,
+};
+use super::repository::Repository;
+use crate::error::TrackerResult;
+
+/// Persistence operations required by the indexer.
+///
+/// Implementations must be idempotent for inserts: re-indexing a block range
+/// writes the same events and price points again (same `event_id`), and
+/// those must replace, not duplicate, the existing rows.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Looks up a pool by database ID, contract address or name.
+    async fn find_pool(&self, identifier: &str) -> TrackerResult<Option<PoolRecord>>;
+
+    /// Inserts or replaces sync events.
+    async fn insert_sync_events(&self, events: Vec<SyncEventRecord>) -> TrackerResult<()>;
+
+    /// Inserts or replaces price points.
+    async fn insert_price_points(&self, prices: Vec<PricePointRecord>) -> TrackerResult<()>;
+
+    /// Inserts or replaces swap events.
+    ///
+    /// Swaps only feed trader analytics, so storages that don't need them
+    /// can keep the default, which discards them.
+    async fn insert_swap_events(&self, events: Vec<SwapEventRecord>) -> TrackerResult<()> {
+        let _ = events;
+        Ok(())
+    }
+
+    /// Writes the rows of one indexed batch and, with `state`, the pool's
+    /// new indexer state.
+    ///
+    /// The state must never cover rows that aren't stored. The default
+    /// writes the rows first and the state last, so a failure in between
+    /// leaves the state behind the rows, which re-indexing rewrites; the
+    /// built-in repository commits everything in one transaction.
+    async fn write_batch(
+        &self,
+        events: Vec<SyncEventRecord>,
+        prices: Vec<PricePointRecord>,
+        swaps: Vec<SwapEventRecord>,
+        state: Option<&IndexerState>,
+    ) -> TrackerResult<()> {
+        self.insert_sync_events(events).await?;
+        self.insert_price_points(prices).await?;
+        self.insert_swap_events(swaps).await?;
+        if let Some(state) = state {
+            self.set_state(state).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the indexer state for a pool, or `None` before the first run.
+    async fn get_state(&self, pool_id: i64) -> TrackerResult<Option<IndexerState>>;
+
+    /// Creates or replaces the indexer state for `state.pool_id`.
+    async fn set_state(&self, state: &IndexerState) -> TrackerResult<()>;
+
+    /// Marks events and prices at or after `from_block` as unconfirmed
+    /// (chain reorganization).
+    async fn invalidate_from_block(&self, pool_id: i64, from_block: u64) -> TrackerResult<()>;
+
+    /// Marks events and prices up to and including `up_to_block` as confirmed.
+    async fn confirm_up_to_block(&self, pool_id: i64, up_to_block: u64) -> TrackerResult<()>;
+
+    /// Records a handled reorg, after its blocks were invalidated.
+    ///
+    /// The reorg history only feeds `GET /api/v1/reorgs`, so storages that
+    /// don't need it can keep the default, which discards it.
+    async fn record_reorg(&self, reorg: &ReorgRecord) -> TrackerResult<()> {
+        let _ = reorg;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for Repository {
+    async fn find_pool(&self, identifier: &str) -> TrackerResult<Option<PoolRecord>> {
+        Self::find_pool(self, identifier).await
+    }
+
+    async fn insert_sync_events(&self, events: Vec<SyncEventRecord>) -> TrackerResult<()> {
+        self.batch_insert_sync_events(events).await
+    }
+
+    async fn insert_price_points(&self, prices: Vec<PricePointRecord>) -> TrackerResult<()> {
+        self.batch_insert_price_points(prices).await
+    }
+
+    async fn insert_swap_events(&self, events: Vec<SwapEventRecord>) -> TrackerResult<()> {
+        self.batch_insert_swap_events(events).await
+    }
+
+    async fn write_batch(
+        &self,
+        events: Vec<SyncEventRecord>,
+        prices: Vec<PricePointRecord>,
+        swaps: Vec<SwapEventRecord>,
+        state: Option<&IndexerState>,
+    ) -> TrackerResult<()> {
+        self.commit_batch(&events, &prices, &swaps, state).await
+    }
+
+    async fn get_state(&self, pool_id: i64) -> TrackerResult<Option<IndexerState>> {
+        Self::get_state(self, pool_id).await
+    }
+
+    async fn set_state(&self, state: &IndexerState) -> TrackerResult<()> {
+        self.update_state(
+            state.pool_id,
+            u64::try_from(state.last_indexed_block).unwrap_or(0),
+            state.block_hash()?,
+            u64::try_from(state.reorg_count).unwrap_or(0),
+            u64::try_from(state.total_events_processed).unwrap_or(0),
+        )
+        .await
+    }
+
+    async fn invalidate_from_block(&self, pool_id: i64, from_block: u64) -> TrackerResult<()> {
+        Self::invalidate_from_block(self, pool_id, from_block).await
+    }
+
+    async fn confirm_up_to_block(&self, pool_id: i64, up_to_block: u64) -> TrackerResult<()> {
+        Self::confirm_up_to_block(self, pool_id, up_to_block).await
+    }
+
+    async fn record_reorg(&self, reorg: &ReorgRecord) -> TrackerResult<()> {
+        self.insert_reorg(reorg).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use alloy::primitives::{FixedBytes, U256};
+
+    #[tokio::test]
+    async fn test_repository_as_storage_round_trip() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let storage: &dyn Storage = &repo;
+        let pool = storage.find_pool("WETH-USDT").await.unwrap().unwrap();
+        assert_eq!(pool.id, pool_id);
+
+        let hash = FixedBytes::from([7u8; 32]);
+        let event = SyncEventRecord::new(
+            pool_id,
+            100,
+            hash,
+            1_700_000_000,
+            hash,
+            0,
+            U256::from(1u64),
+            U256::from(2u64),
+            true,
+        )
+        .with_event_id("e1");
+        let price = PricePointRecord::new(
+            pool_id,
+            100,
+            1_700_000_000,
+            hash,
+            2.0,
+            U256::from(1u64),
+            U256::from(2u64),
+            1.0,
+            2.0,
+            true,
+        )
+        .with_event_id("p1");
+
+        // Inserting twice must not duplicate rows
+        for _ in 0..2 {
+            storage
+                .insert_sync_events(vec![event.clone()])
+                .await
+                .unwrap();
+            storage
+                .insert_price_points(vec![price.clone()])
+                .await
+                .unwrap();
+        }
+        assert_eq!(repo.get_recent_prices(pool_id, 10).await.unwrap().len(), 1);
+
+        storage
+            .set_state(&IndexerState::new(pool_id, 100, hash, 1, 1))
+            .await
+            .unwrap();
+        let state = storage.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(state.last_indexed_block, 100);
+        assert_eq!(state.block_hash().unwrap(), hash);
+
+        storage.invalidate_from_block(pool_id, 100).await.unwrap();
+        assert!(repo.get_latest_price(pool_id).await.unwrap().is_none());
+        storage.confirm_up_to_block(pool_id, 100).await.unwrap();
+        assert!(repo.get_latest_price(pool_id).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,187 @@
+//! Validated column types for addresses and hashes.
+//!
+//! Pool addresses and transaction hashes are stored as `0x`-prefixed
+//! lowercase hex. Keeping them as plain strings in the models let a
+//! checksummed address from a config file or a URL miss a lowercase row, and
+//! let malformed values reach the database. [`PoolAddress`] and [`TxHash`]
+//! wrap the parsed value instead:
+//!
+//! - `FromStr` accepts any case (with or without a valid checksum) and
+//!   rejects anything that isn't a 20- or 32-byte hex value
+//! - `Display`, serde and the `SQLite` encoding all use the canonical
+//!   lowercase form, so binding one in a query always matches stored rows
+//! - Decoding a column validates it, so a corrupt row fails loudly instead of
+//!   propagating
+
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Type};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::TrackerError;
+
+/// Defines a hex newtype over an alloy primitive with canonical lowercase
+/// text, serde and `SQLite` representations.
+macro_rules! hex_column {
+    ($(#[$meta:meta])* $name:ident($inner:ty), $what:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(pub $inner);
+
+        impl $name {
+            /// Returns the wrapped value.
+            #[must_use]
+            pub const fn get(self) -> $inner {
+                self.0
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(value: $inner) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                // Debug of alloy primitives is 0x-prefixed lowercase hex
+                write!(f, "{:?}", self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = TrackerError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.trim().parse::<$inner>().map(Self).map_err(|e| {
+                    TrackerError::decoding(format!("Invalid {} '{s}'", $what), Some(Box::new(e)))
+                })
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                other.parse::<Self>().is_ok_and(|other| other == *self)
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                *self == **other
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl Type<Sqlite> for $name {
+            fn type_info() -> SqliteTypeInfo {
+                <String as Type<Sqlite>>::type_info()
+            }
+
+            fn compatible(ty: &SqliteTypeInfo) -> bool {
+                <String as Type<Sqlite>>::compatible(ty)
+            }
+        }
+
+        impl<'q> Encode<'q, Sqlite> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut Vec<SqliteArgumentValue<'q>>,
+            ) -> Result<IsNull, BoxDynError> {
+                <String as Encode<'q, Sqlite>>::encode(self.to_string(), buf)
+            }
+        }
+
+        impl<'r> Decode<'r, Sqlite> for $name {
+            fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+                let s = <&str as Decode<'r, Sqlite>>::decode(value)?;
+                Ok(s.parse()?)
+            }
+        }
+    };
+}
+
+hex_column!(
+    /// A pool contract address, stored as lowercase hex.
+    PoolAddress(Address),
+    "pool address"
+);
+
+hex_column!(
+    /// A transaction hash, stored as lowercase hex.
+    TxHash(B256),
+    "transaction hash"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+
+    const CHECKSUMMED: &str = "0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852";
+
+    #[test]
+    fn test_parse_is_case_insensitive_and_canonical() {
+        let address: PoolAddress = CHECKSUMMED.parse().unwrap();
+        let lower: PoolAddress = CHECKSUMMED.to_lowercase().parse().unwrap();
+        assert_eq!(address, lower);
+        assert_eq!(address.to_string(), CHECKSUMMED.to_lowercase());
+        assert_eq!(address, CHECKSUMMED);
+
+        assert!("0x1234".parse::<PoolAddress>().is_err());
+        assert!("not-a-hash".parse::<TxHash>().is_err());
+        let hash: TxHash = format!("0x{}", "AB".repeat(32)).parse().unwrap();
+        assert_eq!(hash.to_string(), format!("0x{}", "ab".repeat(32)));
+
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, format!("\"{}\"", CHECKSUMMED.to_lowercase()));
+        assert_eq!(serde_json::from_str::<PoolAddress>(&json).unwrap(), address);
+        assert!(serde_json::from_str::<TxHash>("\"0x12\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_round_trip_and_validation() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        let address: PoolAddress = CHECKSUMMED.parse().unwrap();
+
+        let stored: String = sqlx::query_scalar("SELECT ?")
+            .bind(address)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, CHECKSUMMED.to_lowercase());
+
+        // Checksummed text in a row still decodes to the same address
+        let decoded: PoolAddress = sqlx::query_scalar("SELECT ?")
+            .bind(CHECKSUMMED)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(decoded, address);
+
+        let corrupt: Result<TxHash, _> =
+            sqlx::query_scalar("SELECT '0xdead'").fetch_one(&pool).await;
+        assert!(corrupt.is_err());
+    }
+}
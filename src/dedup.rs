@@ -0,0 +1,226 @@
+//! Deduplication of fetched logs.
+//!
+//! In hybrid mode a pass can be triggered by a WebSocket block header and by
+//! the polling fallback, and a failed pass retries its whole range, so the
+//! same log can be fetched more than once. Writes are already idempotent
+//! (rows are keyed by `event_id` and inserted with `ON CONFLICT`), but a log
+//! that reaches the price stage twice is reported, smoothed and alerted on
+//! twice.
+//!
+//! [`LogDeduplicator`] drops logs it has already admitted, keyed on
+//! `(block_hash, tx_hash, log_index)`, before they are decoded. Keys are kept
+//! in a small LRU, so memory stays bounded and only recent logs (the ones that
+//! can realistically be fetched again) are remembered. Keying on the block
+//! hash means a log re-included in a different block after a reorg is new.
+
+use alloy::primitives::B256;
+use alloy::rpc::types::Log;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, PoisonError};
+use tracing::debug;
+
+/// Default number of log keys remembered.
+pub const DEFAULT_DEDUP_CAPACITY: usize = 10_000;
+
+/// Identity of a log on chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LogKey {
+    /// Hash of the block containing the log
+    pub block_hash: B256,
+    /// Hash of the emitting transaction
+    pub tx_hash: B256,
+    /// Index of the log within the block
+    pub log_index: u64,
+}
+
+impl LogKey {
+    /// Returns the key of a mined log, or `None` for a pending one.
+    #[must_use]
+    pub fn of(log: &Log) -> Option<Self> {
+        Some(Self {
+            block_hash: log.block_hash?,
+            tx_hash: log.transaction_hash?,
+            log_index: log.log_index?,
+        })
+    }
+}
+
+/// Counters of a [`LogDeduplicator`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupStats {
+    /// Logs checked
+    pub checked: u64,
+    /// Logs dropped as duplicates
+    pub duplicates_dropped: u64,
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    /// Key to the generation of its latest entry in `order`
+    keys: HashMap<LogKey, u64>,
+    /// Keys from least to most recently used; entries whose generation is
+    /// stale were used again later and are skipped on eviction
+    order: VecDeque<(LogKey, u64)>,
+    generation: u64,
+    stats: DedupStats,
+}
+
+impl Lru {
+    fn touch(&mut self, key: LogKey) {
+        self.generation += 1;
+        self.keys.insert(key, self.generation);
+        self.order.push_back((key, self.generation));
+    }
+
+    fn evict(&mut self, capacity: usize) {
+        while self.keys.len() > capacity {
+            let Some((key, generation)) = self.order.pop_front() else {
+                break;
+            };
+            if self.keys.get(&key) == Some(&generation) {
+                self.keys.remove(&key);
+            }
+        }
+        // Repeated hits leave stale entries behind; drop them in one sweep
+        if self.order.len() > capacity.saturating_mul(2) {
+            let keys = &self.keys;
+            self.order
+                .retain(|(key, generation)| keys.get(key) == Some(generation));
+        }
+    }
+}
+
+/// Drops logs that were already admitted, remembering the most recently
+/// seen `capacity` keys.
+#[derive(Debug)]
+pub struct LogDeduplicator {
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+impl LogDeduplicator {
+    /// Creates a deduplicator remembering up to `capacity` keys (at least 1).
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// Returns the logs not seen before, in order, and the keys this call
+    /// admitted.
+    ///
+    /// Logs without a key (pending logs) are always admitted.
+    pub fn admit(&self, logs: Vec<Log>) -> (Vec<Log>, Vec<LogKey>) {
+        let mut lru = self.lru.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut admitted = Vec::with_capacity(logs.len());
+        let mut keys = Vec::with_capacity(logs.len());
+        let mut dropped = 0;
+
+        for log in logs {
+            lru.stats.checked += 1;
+            let Some(key) = LogKey::of(&log) else {
+                admitted.push(log);
+                continue;
+            };
+            let duplicate = lru.keys.contains_key(&key);
+            lru.touch(key);
+            if duplicate {
+                dropped += 1;
+                continue;
+            }
+            keys.push(key);
+            admitted.push(log);
+        }
+
+        lru.stats.duplicates_dropped += dropped;
+        lru.evict(self.capacity);
+        if dropped > 0 {
+            debug!(
+                dropped,
+                total = lru.stats.duplicates_dropped,
+                "Dropped duplicate logs"
+            );
+        }
+        (admitted, keys)
+    }
+
+    /// Forgets keys admitted by a run that failed before writing them, so
+    /// the retried logs are admitted again.
+    pub fn forget(&self, keys: &[LogKey]) {
+        let mut lru = self.lru.lock().unwrap_or_else(PoisonError::into_inner);
+        for key in keys {
+            lru.keys.remove(key);
+        }
+    }
+
+    /// Returns the counters so far.
+    #[must_use]
+    pub fn stats(&self) -> DedupStats {
+        self.lru
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stats
+    }
+}
+
+impl Default for LogDeduplicator {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEDUP_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(block: u8, index: u64) -> Log {
+        Log {
+            block_hash: Some(B256::from([block; 32])),
+            transaction_hash: Some(B256::from([block.wrapping_add(100); 32])),
+            log_index: Some(index),
+            ..Log::default()
+        }
+    }
+
+    #[test]
+    fn test_drops_duplicates_and_evicts_least_recently_used() {
+        let dedup = LogDeduplicator::new(3);
+
+        let (admitted, keys) = dedup.admit(vec![log(1, 0), log(1, 1), log(1, 0)]);
+        assert_eq!(admitted.len(), 2);
+        assert_eq!(keys.len(), 2);
+
+        // A pending log has no key and always passes
+        let (admitted, _) = dedup.admit(vec![log(1, 1), Log::default(), Log::default()]);
+        assert_eq!(admitted.len(), 2);
+
+        // (1, 1) was just used, so (1, 0) is evicted first
+        dedup.admit(vec![log(2, 0), log(3, 0)]);
+        let (admitted, _) = dedup.admit(vec![log(1, 1), log(1, 0)]);
+        assert_eq!(admitted.len(), 1);
+        assert_eq!(admitted[0].log_index, Some(0));
+
+        // The same log in another block (after a reorg) is new
+        let (admitted, _) = dedup.admit(vec![log(9, 1)]);
+        assert_eq!(admitted.len(), 1);
+
+        assert_eq!(
+            dedup.stats(),
+            DedupStats {
+                checked: 11,
+                duplicates_dropped: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_forget_admits_again() {
+        let dedup = LogDeduplicator::default();
+        let (_, keys) = dedup.admit(vec![log(1, 0), log(1, 1)]);
+        dedup.forget(&keys);
+        assert_eq!(dedup.admit(vec![log(1, 0), log(1, 1)]).0.len(), 2);
+        assert_eq!(dedup.stats().duplicates_dropped, 0);
+    }
+}
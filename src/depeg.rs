@@ -0,0 +1,524 @@
+//! Stablecoin depeg detection.
+//!
+//! A stable-stable pool should trade near 1.0. When `DEPEG_BAND_BPS` is set,
+//! the API server runs a [`DepegMonitor`] over the pool's confirmed prices,
+//! checking for new ones every [`DEPEG_CHECK_INTERVAL`]. A price further than
+//! the band from 1.0 starts an excursion; once the price has stayed outside
+//! the band for `DEPEG_MIN_BLOCKS` blocks:
+//!
+//! - a `depeg` row is opened in the `incidents` table, starting at the
+//!   excursion's first block, and its peak deviation is kept up to date
+//! - the monitor logs at ERROR and sends a `depeg_started` [`DepegAlert`] to
+//!   `DEPEG_WEBHOOK_URL`, if set, which may be any alert channel (see
+//!   [`crate::alerts::notify`])
+//!
+//! The first price back inside the band closes the incident and sends
+//! `depeg_ended`. Excursions shorter than `DEPEG_MIN_BLOCKS` leave no trace.
+//!
+//! Blocks are counted between Sync events, so a pool that stops trading
+//! outside the band opens its incident with its next trade. An incident left
+//! open by a restart is resumed from its first block.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::alerts::{Destination, Notification, Notifiers};
+use crate::db::models::{IncidentRow, PricePointRow, INCIDENT_KIND_DEPEG};
+use crate::db::repository::Repository;
+use crate::error::{TrackerError, TrackerResult};
+
+/// Interval between checks for new confirmed prices.
+pub const DEPEG_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Depeg incident transitions sent to the alert destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepegEvent {
+    /// The price stayed outside the band long enough to open an incident
+    DepegStarted,
+    /// The price returned inside the band
+    DepegEnded,
+}
+
+/// Alert sent to `DEPEG_WEBHOOK_URL`, posted as-is to webhooks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepegAlert {
+    /// What happened
+    pub event: DepegEvent,
+    /// Pool name
+    pub pool: String,
+    /// Incident ID, as listed by `/api/v1/pools/{id}/incidents`
+    pub incident_id: i64,
+    /// Price that triggered the alert
+    pub price: f64,
+    /// Deviation of `price` from 1.0, in basis points
+    pub deviation_bps: f64,
+    /// Configured band, in basis points
+    pub band_bps: u32,
+    /// First block outside the band
+    pub started_block: u64,
+    /// Block of `price`
+    pub block_number: u64,
+    /// When the alert was raised
+    pub triggered_at: DateTime<Utc>,
+}
+
+/// What a new price changed about the pool's depeg state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepegTransition {
+    /// An incident opens
+    Started {
+        /// First block outside the band
+        started_block: u64,
+        /// Block timestamp of `started_block`
+        started_at: i64,
+        /// Price farthest from 1.0 so far
+        peak_price: f64,
+        /// Deviation of `peak_price`, in basis points
+        peak_deviation_bps: f64,
+    },
+    /// The ongoing incident reached a new peak
+    Peak {
+        /// Price farthest from 1.0 so far
+        peak_price: f64,
+        /// Deviation of `peak_price`, in basis points
+        peak_deviation_bps: f64,
+    },
+    /// The ongoing incident ends
+    Ended {
+        /// First block back inside the band
+        ended_block: u64,
+        /// Block timestamp of `ended_block`
+        ended_at: i64,
+    },
+}
+
+/// A run of prices outside the band.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Excursion {
+    started_block: u64,
+    started_at: i64,
+    peak_price: f64,
+    peak_deviation_bps: f64,
+    /// Whether the excursion lasted long enough to be an incident
+    incident: bool,
+}
+
+/// Deviation of a price from 1.0, in basis points.
+#[must_use]
+pub fn deviation_bps(price: f64) -> f64 {
+    (price - 1.0).abs() * 10_000.0
+}
+
+/// Tracks a pool's prices against the band around 1.0.
+#[derive(Debug)]
+pub struct DepegDetector {
+    band_bps: u32,
+    min_blocks: u64,
+    excursion: Option<Excursion>,
+}
+
+impl DepegDetector {
+    /// Creates a detector opening incidents after `min_blocks` blocks more
+    /// than `band_bps` away from 1.0.
+    #[must_use]
+    pub const fn new(band_bps: u32, min_blocks: u64) -> Self {
+        Self {
+            band_bps,
+            min_blocks,
+            excursion: None,
+        }
+    }
+
+    /// Continues an incident left open by a previous run.
+    #[must_use]
+    pub fn resume(mut self, incident: &IncidentRow) -> Self {
+        self.excursion = Some(Excursion {
+            started_block: u64::try_from(incident.started_block).unwrap_or(0),
+            started_at: incident.started_at,
+            peak_price: incident.peak_price,
+            peak_deviation_bps: incident.peak_deviation_bps,
+            incident: true,
+        });
+        self
+    }
+
+    /// Records a confirmed price, returning what it changed.
+    pub fn observe(&mut self, block: u64, timestamp: i64, price: f64) -> Option<DepegTransition> {
+        let deviation = deviation_bps(price);
+        if deviation <= f64::from(self.band_bps) {
+            let excursion = self.excursion.take()?;
+            return excursion.incident.then_some(DepegTransition::Ended {
+                ended_block: block,
+                ended_at: timestamp,
+            });
+        }
+
+        let excursion = self.excursion.get_or_insert(Excursion {
+            started_block: block,
+            started_at: timestamp,
+            peak_price: price,
+            peak_deviation_bps: deviation,
+            incident: false,
+        });
+        let new_peak = deviation > excursion.peak_deviation_bps;
+        if new_peak {
+            excursion.peak_price = price;
+            excursion.peak_deviation_bps = deviation;
+        }
+
+        if !excursion.incident && block.saturating_sub(excursion.started_block) >= self.min_blocks {
+            excursion.incident = true;
+            return Some(DepegTransition::Started {
+                started_block: excursion.started_block,
+                started_at: excursion.started_at,
+                peak_price: excursion.peak_price,
+                peak_deviation_bps: excursion.peak_deviation_bps,
+            });
+        }
+
+        (excursion.incident && new_peak).then_some(DepegTransition::Peak {
+            peak_price: excursion.peak_price,
+            peak_deviation_bps: excursion.peak_deviation_bps,
+        })
+    }
+}
+
+/// Records depeg incidents of one pool and alerts on them.
+#[derive(Debug)]
+pub struct DepegMonitor {
+    pool_id: i64,
+    pool_name: String,
+    band_bps: u32,
+    min_blocks: u64,
+    destination: Option<Destination>,
+    notifiers: Notifiers,
+}
+
+impl DepegMonitor {
+    /// Creates a monitor for a pool, notifying `destination` if given.
+    #[must_use]
+    pub const fn new(
+        pool_id: i64,
+        pool_name: String,
+        band_bps: u32,
+        min_blocks: u64,
+        destination: Option<Destination>,
+        notifiers: Notifiers,
+    ) -> Self {
+        Self {
+            pool_id,
+            pool_name,
+            band_bps,
+            min_blocks,
+            destination,
+            notifiers,
+        }
+    }
+
+    /// Spawns a task that checks new confirmed prices every
+    /// [`DEPEG_CHECK_INTERVAL`].
+    ///
+    /// Prices from the start of an open incident, or else from the latest
+    /// price on, are checked; earlier history isn't scanned.
+    #[must_use]
+    pub fn spawn(self, repository: Repository) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let (mut detector, mut last_block) = match self.start(&repository).await {
+                Ok(start) => start,
+                Err(e) => {
+                    error!(error = %e, "Depeg monitor failed to start");
+                    return;
+                }
+            };
+
+            let mut ticker = tokio::time::interval(DEPEG_CHECK_INTERVAL);
+            let mut incident_id = None;
+            loop {
+                ticker.tick().await;
+                let prices = match repository
+                    .get_confirmed_prices_after(self.pool_id, last_block, 0)
+                    .await
+                {
+                    Ok(prices) => prices,
+                    Err(e) => {
+                        warn!(error = %e, "Depeg check could not read new prices");
+                        continue;
+                    }
+                };
+                for price in prices {
+                    if let Err(e) = self
+                        .process(&repository, &mut detector, &mut incident_id, &price)
+                        .await
+                    {
+                        // Retried from this price on the next tick
+                        warn!(error = %e, "Depeg check could not record an incident");
+                        break;
+                    }
+                    last_block = price.block_number;
+                }
+            }
+        })
+    }
+
+    /// Returns the detector and the block after which to read prices.
+    async fn start(&self, repository: &Repository) -> TrackerResult<(DepegDetector, i64)> {
+        let detector = DepegDetector::new(self.band_bps, self.min_blocks);
+        if let Some(incident) = repository
+            .get_open_incident(self.pool_id, INCIDENT_KIND_DEPEG)
+            .await?
+        {
+            info!(incident_id = incident.id, "Resuming open depeg incident");
+            return Ok((detector.resume(&incident), incident.started_block));
+        }
+
+        let latest = repository.get_latest_price(self.pool_id).await?;
+        Ok((detector, latest.map_or(0, |p| p.block_number)))
+    }
+
+    /// Returns the ID of the open incident, looking it up after a resume.
+    async fn incident_id(&self, repository: &Repository, known: Option<i64>) -> TrackerResult<i64> {
+        if let Some(id) = known {
+            return Ok(id);
+        }
+        repository
+            .get_open_incident(self.pool_id, INCIDENT_KIND_DEPEG)
+            .await?
+            .map(|incident| incident.id)
+            .ok_or_else(|| TrackerError::database("No open depeg incident to update", None))
+    }
+
+    async fn process(
+        &self,
+        repository: &Repository,
+        detector: &mut DepegDetector,
+        incident_id: &mut Option<i64>,
+        price: &PricePointRow,
+    ) -> TrackerResult<()> {
+        let block = u64::try_from(price.block_number).unwrap_or(0);
+        let Some(transition) = detector.observe(block, price.block_timestamp, price.price) else {
+            return Ok(());
+        };
+
+        let (event, id, started_block) = match transition {
+            DepegTransition::Started {
+                started_block,
+                started_at,
+                peak_price,
+                peak_deviation_bps,
+            } => {
+                let id = repository
+                    .insert_incident(
+                        self.pool_id,
+                        INCIDENT_KIND_DEPEG,
+                        self.band_bps,
+                        started_block,
+                        started_at,
+                        peak_price,
+                        peak_deviation_bps,
+                    )
+                    .await?;
+                *incident_id = Some(id);
+                error!(
+                    pool = %self.pool_name,
+                    incident_id = id,
+                    price = price.price,
+                    started_block,
+                    band_bps = self.band_bps,
+                    "Pool price depegged"
+                );
+                (DepegEvent::DepegStarted, id, started_block)
+            }
+            DepegTransition::Peak {
+                peak_price,
+                peak_deviation_bps,
+            } => {
+                let id = self.incident_id(repository, *incident_id).await?;
+                repository
+                    .update_incident_peak(id, peak_price, peak_deviation_bps)
+                    .await?;
+                *incident_id = Some(id);
+                return Ok(());
+            }
+            DepegTransition::Ended {
+                ended_block,
+                ended_at,
+            } => {
+                let id = self.incident_id(repository, *incident_id).await?;
+                repository.end_incident(id, ended_block, ended_at).await?;
+                *incident_id = None;
+                info!(
+                    pool = %self.pool_name,
+                    incident_id = id,
+                    price = price.price,
+                    "Pool price back inside the depeg band"
+                );
+                (DepegEvent::DepegEnded, id, ended_block)
+            }
+        };
+
+        if let Some(destination) = &self.destination {
+            let alert = DepegAlert {
+                event,
+                pool: self.pool_name.clone(),
+                incident_id: id,
+                price: price.price,
+                deviation_bps: deviation_bps(price.price),
+                band_bps: self.band_bps,
+                started_block,
+                block_number: block,
+                triggered_at: Utc::now(),
+            };
+            let (name, text) = match event {
+                DepegEvent::DepegStarted => (
+                    "depeg_started",
+                    format!(
+                        "{} depegged: price {:.4} is {:.0} bps from 1.0 (band {} bps) since block {}",
+                        alert.pool, alert.price, alert.deviation_bps, alert.band_bps, started_block
+                    ),
+                ),
+                DepegEvent::DepegEnded => (
+                    "depeg_ended",
+                    format!(
+                        "{} is back inside its {} bps band at price {:.4} (block {})",
+                        alert.pool, alert.band_bps, alert.price, block
+                    ),
+                ),
+            };
+            let notification = Notification::new(name, text, &alert);
+            if let Err(e) = self
+                .notifiers
+                .deliver(INCIDENT_KIND_DEPEG, destination, &notification)
+                .await
+            {
+                warn!(error = %e, "Depeg alert delivery failed");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incident_opens_after_min_blocks_and_closes_inside_band() {
+        // ±50 bps, incidents after 3 blocks outside
+        let mut detector = DepegDetector::new(50, 3);
+
+        assert_eq!(detector.observe(100, 1_200, 1.001), None);
+        // A short excursion leaves no trace
+        assert_eq!(detector.observe(101, 1_212, 0.99), None);
+        assert_eq!(detector.observe(102, 1_224, 1.0), None);
+
+        assert_eq!(detector.observe(110, 1_320, 0.99), None);
+        assert_eq!(detector.observe(111, 1_332, 0.98), None);
+        assert_eq!(
+            detector.observe(113, 1_356, 0.985),
+            Some(DepegTransition::Started {
+                started_block: 110,
+                started_at: 1_320,
+                peak_price: 0.98,
+                peak_deviation_bps: deviation_bps(0.98),
+            })
+        );
+        assert_eq!(detector.observe(114, 1_368, 0.99), None);
+        assert_eq!(
+            detector.observe(115, 1_380, 0.97),
+            Some(DepegTransition::Peak {
+                peak_price: 0.97,
+                peak_deviation_bps: deviation_bps(0.97),
+            })
+        );
+        assert_eq!(
+            detector.observe(120, 1_440, 0.999),
+            Some(DepegTransition::Ended {
+                ended_block: 120,
+                ended_at: 1_440,
+            })
+        );
+        assert_eq!(detector.observe(121, 1_452, 1.0), None);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_records_incidents() {
+        use crate::db::{create_pool, run_migrations};
+        use alloy::primitives::{FixedBytes, U256};
+
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        let repo = Repository::new(pool);
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        let monitor = DepegMonitor::new(
+            pool_id,
+            "USDC/USDT".to_string(),
+            50,
+            1,
+            None,
+            Notifiers::new().unwrap(),
+        );
+        let (mut detector, last_block) = monitor.start(&repo).await.unwrap();
+        assert_eq!(last_block, 0);
+
+        let mut incident_id = None;
+        for (block, price) in [(1_u64, 1.0), (2, 0.98), (3, 0.97), (4, 1.0)] {
+            repo.insert_price_point(
+                pool_id,
+                block,
+                1_000 + block * 12,
+                FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                price,
+                U256::from(1_u64),
+                U256::from(1_u64),
+                1.0,
+                1.0,
+                true,
+                &format!("price-{block}"),
+            )
+            .await
+            .unwrap();
+            let row = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+            monitor
+                .process(&repo, &mut detector, &mut incident_id, &row)
+                .await
+                .unwrap();
+
+            if block == 3 {
+                // Open, so a restart would resume it
+                let open = repo
+                    .get_open_incident(pool_id, INCIDENT_KIND_DEPEG)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(open.started_block, 2);
+                assert_eq!(open.peak_price, 0.97);
+            }
+        }
+
+        let page = repo
+            .get_incidents_page(pool_id, Some(INCIDENT_KIND_DEPEG), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        let incident = &page.items[0];
+        assert_eq!((incident.started_block, incident.ended_block), (2, Some(4)));
+        assert_eq!(incident.band_bps, 50);
+        assert!(repo
+            .get_open_incident(pool_id, INCIDENT_KIND_DEPEG)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            repo.get_incidents_page(pool_id, Some("other"), 10, 0)
+                .await
+                .unwrap()
+                .total,
+            0
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Discord bot (enabled with the `discord` cargo feature).
+//!
+//! With `DISCORD_BOT_TOKEN` set, the API server connects a bot that:
+//!
+//! - answers `!price <pool>` (e.g. `!price weth-usdt`) in the channels it can
+//!   read with the pool's latest price and 24h change, from the same price
+//!   cache as `/api/v1/price/latest/{pool}`, which the live price loop (or
+//!   the Redis relay) keeps current
+//! - delivers alerts to `discord:<channel id>` destinations through
+//!   [`DiscordNotifier`], so alert rules, the lag watchdog and the depeg
+//!   monitor can post threshold alerts to a channel (see
+//!   [`crate::alerts::notify`])
+//!
+//! Reading commands needs the bot's Message Content intent, enabled in the
+//! Discord developer portal. Messages from bots, including itself, are
+//! ignored.
+
+use async_trait::async_trait;
+use serenity::all::{ChannelId, Client, Context, EventHandler, GatewayIntents, Message, Ready};
+use serenity::http::Http;
+use std::fmt;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::alerts::{Notification, Notifier};
+use crate::app_state::AppState;
+use crate::error::{TrackerError, TrackerResult};
+
+/// Prefix of bot commands.
+pub const COMMAND_PREFIX: char = '!';
+
+/// Reply to `!help`.
+const HELP: &str = "Commands: `!price <pool>`, e.g. `!price weth-usdt`";
+
+/// A command the bot answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `!price <pool>`, with `-` in the pool name read as `/`
+    Price(String),
+    /// `!help`, or `!price` without a pool
+    Help,
+}
+
+impl Command {
+    /// Parses a message, returning `None` for anything that isn't a command.
+    #[must_use]
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut words = content
+            .trim()
+            .strip_prefix(COMMAND_PREFIX)?
+            .split_whitespace();
+        match words.next()?.to_ascii_lowercase().as_str() {
+            "price" => Some(
+                words
+                    .next()
+                    .map_or(Self::Help, |pool| Self::Price(pool.replace('-', "/"))),
+            ),
+            "help" => Some(Self::Help),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the reply to `command`.
+///
+/// Pools are looked up by ID, address or name, names also in upper case, so
+/// `weth/usdt` finds `WETH/USDT`.
+///
+/// # Errors
+///
+/// Returns an error if the database query fails.
+pub async fn answer(state: &AppState, command: &Command) -> TrackerResult<String> {
+    let Command::Price(query) = command else {
+        return Ok(HELP.to_string());
+    };
+
+    let pool = match state.reader.find_pool(query).await? {
+        Some(pool) => Some(pool),
+        None => state.reader.find_pool(&query.to_uppercase()).await?,
+    };
+    let Some(pool) = pool else {
+        return Ok(format!("Unknown pool `{query}`. {HELP}"));
+    };
+    let name = pool
+        .name
+        .clone()
+        .unwrap_or_else(|| pool.address.to_string());
+    let Some(cached) = state.latest_price(pool.id).await? else {
+        return Ok(format!("No price for **{name}** yet"));
+    };
+
+    let price = pool.quote_direction().apply(cached.latest.price);
+    let change = cached
+        .change_24h
+        .map_or_else(String::new, |change| format!(" ({change:+.2}% 24h)"));
+    Ok(format!(
+        "**{name}** {price:.4}{change} at block {}",
+        cached.latest.block_number
+    ))
+}
+
+/// Answers commands from Discord messages.
+struct Handler {
+    state: AppState,
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, _ctx: Context, ready: Ready) {
+        info!(user = %ready.user.name, guilds = ready.guilds.len(), "Discord bot connected");
+    }
+
+    async fn message(&self, ctx: Context, message: Message) {
+        if message.author.bot {
+            return;
+        }
+        let Some(command) = Command::parse(&message.content) else {
+            return;
+        };
+
+        let reply = match answer(&self.state, &command).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                warn!(?command, error = %e, "Discord command failed");
+                "Price lookup failed, try again later".to_string()
+            }
+        };
+        if let Err(e) = message.channel_id.say(&ctx.http, reply).await {
+            warn!(channel = %message.channel_id, error = %e, "Discord reply failed");
+        }
+    }
+}
+
+/// Spawns the bot, which answers commands until its connection fails for
+/// good (the client reconnects after transient failures itself).
+#[must_use]
+pub fn spawn(state: AppState, token: String) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let intents = GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::DIRECT_MESSAGES
+            | GatewayIntents::MESSAGE_CONTENT;
+        let client = Client::builder(&token, intents)
+            .event_handler(Handler { state })
+            .await;
+        let result = match client {
+            Ok(mut client) => client.start().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!(error = %e, "Discord bot stopped");
+        }
+    })
+}
+
+/// Sends notifications as Discord bot messages to a channel.
+#[derive(Clone)]
+pub struct DiscordNotifier {
+    http: Arc<Http>,
+}
+
+impl DiscordNotifier {
+    /// Creates a notifier sending as the bot with `bot_token`.
+    #[must_use]
+    pub fn new(bot_token: &str) -> Self {
+        Self {
+            http: Arc::new(Http::new(bot_token)),
+        }
+    }
+}
+
+impl fmt::Debug for DiscordNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscordNotifier").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn notify(&self, target: &str, notification: &Notification) -> TrackerResult<()> {
+        let channel = target.parse::<NonZeroU64>().map_err(|e| {
+            TrackerError::config(
+                format!("Invalid Discord channel ID: {target}"),
+                Some(Box::new(e)),
+            )
+        })?;
+        ChannelId::new(channel.get())
+            .say(&self.http, &notification.text)
+            .await
+            .map_err(|e| {
+                TrackerError::rpc(
+                    format!("Discord delivery of {} failed", notification.event),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, repository::Repository};
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            Command::parse("!price weth-usdt"),
+            Some(Command::Price("weth/usdt".to_string()))
+        );
+        assert_eq!(
+            Command::parse("  !PRICE WETH/USDT extra"),
+            Some(Command::Price("WETH/USDT".to_string()))
+        );
+        assert_eq!(Command::parse("!price"), Some(Command::Help));
+        assert_eq!(Command::parse("!help"), Some(Command::Help));
+
+        for ignored in ["price weth-usdt", "!", "!prices weth-usdt", "hello"] {
+            assert_eq!(Command::parse(ignored), None, "{ignored}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_answer_looks_up_pools_case_insensitively() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        repository.ensure_default_pool().await.unwrap();
+        let state = AppState::new(repository);
+
+        let reply = answer(&state, &Command::Price("weth/usdt".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(reply, "No price for **WETH/USDT** yet");
+
+        let reply = answer(&state, &Command::Price("nope".to_string()))
+            .await
+            .unwrap();
+        assert!(reply.starts_with("Unknown pool `nope`"));
+        assert_eq!(answer(&state, &Command::Help).await.unwrap(), HELP);
+    }
+}
@@ -0,0 +1,266 @@
+//! Largest-Triangle-Three-Buckets downsampling for charts.
+//!
+//! `?points=N` on `/api/v1/price/history/{pool}` reduces a range of any size
+//! to at most `N` price points that still look like the full series when
+//! plotted: LTTB keeps the first and last point and splits the rest into
+//! `N - 2` buckets of consecutive points. From each bucket it keeps the point
+//! forming the largest triangle with the point kept from the previous bucket
+//! and the average of the next bucket, which preserves spikes and turning
+//! points that averaging would flatten.
+//!
+//! [`Lttb`] works on a stream: given the number of points up front, it only
+//! holds two buckets at a time, so downsampling a year of per-event history
+//! needs memory for the output, not the input.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::downsample::Lttb;
+//!
+//! let series = [1.0, 1.0, 9.0, 1.0, 1.0, 1.0, 1.0];
+//! let mut lttb = Lttb::new(series.len(), 4);
+//! for (x, y) in series.iter().enumerate() {
+//!     lttb.push(x as f64, *y, x);
+//! }
+//! // The spike at index 2 survives
+//! assert_eq!(lttb.finish(), [0, 2, 3, 6]);
+//! ```
+
+/// Fewest points LTTB reduces to: the first, the last and one bucket.
+pub const MIN_POINTS: usize = 3;
+
+/// A point with its coordinates.
+struct Point<T> {
+    x: f64,
+    y: f64,
+    item: T,
+}
+
+/// Streaming LTTB downsampler over `len` points, keeping at most `threshold`.
+///
+/// Points must be pushed in `x` order. With `threshold` of at least `len`, or
+/// below [`MIN_POINTS`], every point is kept.
+pub struct Lttb<T> {
+    len: usize,
+    buckets: usize,
+    pushed: usize,
+    selected: Vec<T>,
+    anchor: (f64, f64),
+    /// Bucket waiting for the next one's average
+    pending: Vec<Point<T>>,
+    /// Bucket being filled
+    filling: Vec<Point<T>>,
+    /// Index of the point that starts the next bucket
+    next_start: usize,
+    filling_bucket: usize,
+}
+
+impl<T> Lttb<T> {
+    /// Creates a downsampler for a series of `len` points.
+    #[must_use]
+    pub fn new(len: usize, threshold: usize) -> Self {
+        let buckets = if threshold >= len || threshold < MIN_POINTS {
+            0
+        } else {
+            threshold - 2
+        };
+        let mut lttb = Self {
+            len,
+            buckets,
+            pushed: 0,
+            selected: Vec::with_capacity(threshold.min(len)),
+            anchor: (0.0, 0.0),
+            pending: Vec::new(),
+            filling: Vec::new(),
+            next_start: 0,
+            filling_bucket: 0,
+        };
+        lttb.next_start = lttb.bucket_start(1);
+        lttb
+    }
+
+    /// Index of the first point of `bucket`.
+    fn bucket_start(&self, bucket: usize) -> usize {
+        let middle = (self.len.saturating_sub(2)) as u128;
+        let offset = bucket as u128 * middle / self.buckets.max(1) as u128;
+        1 + usize::try_from(offset).unwrap_or(usize::MAX)
+    }
+
+    /// Adds the next point of the series.
+    ///
+    /// Points past the `len` given to [`Lttb::new`] are ignored.
+    pub fn push(&mut self, x: f64, y: f64, item: T) {
+        let index = self.pushed;
+        if index >= self.len {
+            return;
+        }
+        self.pushed += 1;
+
+        if self.buckets == 0 {
+            self.selected.push(item);
+            return;
+        }
+        if index == 0 {
+            self.anchor = (x, y);
+            self.selected.push(item);
+            return;
+        }
+        if index == self.len - 1 {
+            self.close(Point { x, y, item });
+            return;
+        }
+
+        if index >= self.next_start {
+            if !self.pending.is_empty() {
+                let next = average(&self.filling);
+                let bucket = std::mem::take(&mut self.pending);
+                self.select(bucket, next);
+            }
+            self.pending = std::mem::take(&mut self.filling);
+            self.filling_bucket += 1;
+            self.next_start = self.bucket_start(self.filling_bucket + 1);
+        }
+        self.filling.push(Point { x, y, item });
+    }
+
+    /// Selects from the remaining buckets and keeps `last`.
+    fn close(&mut self, last: Point<T>) {
+        if !self.pending.is_empty() {
+            let next = if self.filling.is_empty() {
+                (last.x, last.y)
+            } else {
+                average(&self.filling)
+            };
+            let bucket = std::mem::take(&mut self.pending);
+            self.select(bucket, next);
+        }
+        if !self.filling.is_empty() {
+            let bucket = std::mem::take(&mut self.filling);
+            self.select(bucket, (last.x, last.y));
+        }
+        self.selected.push(last.item);
+    }
+
+    /// Keeps the point of `bucket` forming the largest triangle with the
+    /// last kept point and `next`.
+    fn select(&mut self, bucket: Vec<Point<T>>, next: (f64, f64)) {
+        let (ax, ay) = self.anchor;
+        let (cx, cy) = next;
+        let area = |p: &Point<T>| ((ax - cx) * (p.y - ay) - (ax - p.x) * (cy - ay)).abs();
+        let best = bucket
+            .into_iter()
+            .reduce(|best, p| if area(&p) > area(&best) { p } else { best });
+        if let Some(best) = best {
+            self.anchor = (best.x, best.y);
+            self.selected.push(best.item);
+        }
+    }
+
+    /// Returns the kept items in push order.
+    ///
+    /// If fewer than `len` points were pushed (the series shrank while it was
+    /// read), the last one pushed is kept as the last point.
+    #[must_use]
+    pub fn finish(mut self) -> Vec<T> {
+        if self.buckets > 0 && self.pushed < self.len {
+            if let Some(last) = self.filling.pop().or_else(|| self.pending.pop()) {
+                self.close(last);
+            }
+        }
+        self.selected
+    }
+}
+
+/// Centroid of a bucket.
+#[allow(clippy::cast_precision_loss)]
+fn average<T>(bucket: &[Point<T>]) -> (f64, f64) {
+    let n = bucket.len().max(1) as f64;
+    let (sx, sy) = bucket
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), p| (sx + p.x, sy + p.y));
+    (sx / n, sy / n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downsample(ys: &[f64], threshold: usize) -> Vec<usize> {
+        let mut lttb = Lttb::new(ys.len(), threshold);
+        for (x, y) in ys.iter().enumerate() {
+            lttb.push(x as f64, *y, x);
+        }
+        lttb.finish()
+    }
+
+    #[test]
+    fn test_keeps_every_point_under_threshold() {
+        assert_eq!(downsample(&[1.0, 2.0, 3.0], 3), [0, 1, 2]);
+        assert_eq!(downsample(&[1.0, 2.0, 3.0], 10), [0, 1, 2]);
+        assert_eq!(downsample(&[1.0, 2.0, 3.0, 4.0], 2), [0, 1, 2, 3]);
+        assert!(downsample(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_keeps_extremes_and_endpoints() {
+        // A dip and a spike in an otherwise flat series
+        let mut ys = vec![10.0; 100];
+        ys[23] = 2.0;
+        ys[71] = 30.0;
+
+        let kept = downsample(&ys, 10);
+        assert_eq!(kept.len(), 10);
+        assert_eq!(kept.first(), Some(&0));
+        assert_eq!(kept.last(), Some(&99));
+        assert!(kept.contains(&23));
+        assert!(kept.contains(&71));
+        assert!(kept.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_matches_batch_lttb() {
+        let ys: Vec<f64> = (0..57)
+            .map(|i| (f64::from(i) * 0.7).sin() * 100.0 + f64::from(i))
+            .collect();
+
+        // Textbook LTTB over the whole series
+        let threshold = 12;
+        let every = (ys.len() - 2) as f64 / (threshold - 2) as f64;
+        let mut expected = vec![0];
+        let mut a = 0;
+        for i in 0..threshold - 2 {
+            let start = (i as f64 * every) as usize + 1;
+            let end = ((i + 1) as f64 * every) as usize + 1;
+            let (next_start, next_end) = if i + 1 < threshold - 2 {
+                (end, ((i + 2) as f64 * every) as usize + 1)
+            } else {
+                (ys.len() - 1, ys.len())
+            };
+            let n = (next_end - next_start) as f64;
+            let cx = (next_start..next_end).map(|j| j as f64).sum::<f64>() / n;
+            let cy = ys[next_start..next_end].iter().sum::<f64>() / n;
+            let area = |j: usize| {
+                ((a as f64 - cx) * (ys[j] - ys[a]) - (a as f64 - j as f64) * (cy - ys[a])).abs()
+            };
+            a = (start..end)
+                .reduce(|best, j| if area(j) > area(best) { j } else { best })
+                .unwrap();
+            expected.push(a);
+        }
+        expected.push(ys.len() - 1);
+
+        assert_eq!(downsample(&ys, threshold), expected);
+    }
+
+    #[test]
+    fn test_short_stream_keeps_last_pushed_point() {
+        let mut lttb = Lttb::new(100, 5);
+        for x in 0..40 {
+            lttb.push(f64::from(x), f64::from(x % 7), x);
+        }
+        let kept = lttb.finish();
+        assert_eq!(kept.first(), Some(&0));
+        assert_eq!(kept.last(), Some(&39));
+        assert!(kept.len() <= 5);
+    }
+}
@@ -16,6 +16,21 @@
 //! All errors implement [`std::error::Error`] and include rich context via
 //! the source error chain.
 //!
+//! # Classification
+//!
+//! [`TrackerError::code`] gives every variant a stable, machine-readable code
+//! (also used as the `error` field of API responses), and
+//! [`TrackerError::is_retryable`] tells whether repeating the operation may
+//! succeed, so callers don't have to match on messages:
+//!
+//! | Code | Retryable |
+//! |------|-----------|
+//! | `config_error`, `decoding_error`, `state_error`, `math_error` | no |
+//! | `rpc_error` | yes, unless the provider rejected the request itself |
+//! | `database_error` | only if SQLite was busy or locked, or the pool timed out |
+//! | `websocket_connection_error`, `websocket_subscription_error`, `websocket_disconnected` | yes |
+//! | `max_reconnect_attempts_exceeded` | no |
+//!
 //! # Example
 //!
 //! ```
@@ -382,8 +397,104 @@ impl TrackerError {
             last_error: last_error.into(),
         }
     }
+
+    /// Returns the stable, machine-readable code of this error.
+    ///
+    /// Codes are part of the public interface (API responses, JSON output)
+    /// and never change once published.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eth_uniswap_alloy::error::TrackerError;
+    ///
+    /// assert_eq!(TrackerError::rpc("timeout", None).code(), "rpc_error");
+    /// ```
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::ConfigError { .. } => "config_error",
+            Self::RpcError { .. } => "rpc_error",
+            Self::DecodingError { .. } => "decoding_error",
+            Self::StateError { .. } => "state_error",
+            Self::MathError { .. } => "math_error",
+            Self::DatabaseError { .. } => "database_error",
+            Self::WebSocketConnectionError { .. } => "websocket_connection_error",
+            Self::WebSocketSubscriptionError { .. } => "websocket_subscription_error",
+            Self::WebSocketDisconnected { .. } => "websocket_disconnected",
+            Self::MaxReconnectAttemptsExceeded { .. } => "max_reconnect_attempts_exceeded",
+        }
+    }
+
+    /// Returns true if repeating the failed operation later may succeed.
+    ///
+    /// RPC errors are retryable unless the source shows the provider
+    /// rejected the request itself (e.g. invalid params); database errors
+    /// only if the source shows SQLite was busy or locked, or the connection
+    /// pool timed out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eth_uniswap_alloy::error::TrackerError;
+    ///
+    /// assert!(TrackerError::rpc("rate limited", None).is_retryable());
+    /// assert!(!TrackerError::config("RPC_URL missing", None).is_retryable());
+    /// ```
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RpcError { source, .. } => source.as_ref().map_or(true, |source| {
+                source
+                    .downcast_ref::<alloy::transports::TransportError>()
+                    .map_or(true, is_transient_rpc)
+            }),
+            Self::DatabaseError { source, .. } => source
+                .as_ref()
+                .and_then(|source| source.downcast_ref::<sqlx::Error>())
+                .is_some_and(is_transient_sqlx),
+            Self::WebSocketConnectionError { .. }
+            | Self::WebSocketSubscriptionError { .. }
+            | Self::WebSocketDisconnected { .. } => true,
+            Self::ConfigError { .. }
+            | Self::DecodingError { .. }
+            | Self::StateError { .. }
+            | Self::MathError { .. }
+            | Self::MaxReconnectAttemptsExceeded { .. } => false,
+        }
+    }
+}
+
+/// Transport failures, rate limits and empty responses are worth retrying;
+/// requests the provider rejected or that failed to (de)serialize are not.
+fn is_transient_rpc(err: &alloy::transports::TransportError) -> bool {
+    use alloy::transports::RpcError;
+
+    match err {
+        RpcError::Transport(kind) => kind.is_retry_err(),
+        RpcError::ErrorResp(payload) => payload.is_retry_err(),
+        RpcError::NullResp => true,
+        _ => false,
+    }
+}
+
+/// SQLite busy/locked (including extended codes) and pool exhaustion clear
+/// up on their own; constraint violations and decode errors do not.
+fn is_transient_sqlx(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+        sqlx::Error::Database(db) => db
+            .code()
+            .and_then(|code| code.parse::<i32>().ok())
+            .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED)),
+        _ => false,
+    }
 }
 
+/// Primary SQLite result codes for a busy or locked database.
+const SQLITE_BUSY: i32 = 5;
+const SQLITE_LOCKED: i32 = 6;
+
 impl fmt::Display for TrackerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -496,6 +607,48 @@ mod tests {
         assert_eq!(err.to_string(), "Configuration error: failed to load");
     }
 
+    #[test]
+    fn test_codes_are_stable() {
+        assert_eq!(TrackerError::config("x", None).code(), "config_error");
+        assert_eq!(TrackerError::database("x", None).code(), "database_error");
+        assert_eq!(
+            TrackerError::websocket_disconnected("x").code(),
+            "websocket_disconnected"
+        );
+        assert_eq!(
+            TrackerError::max_reconnect_exceeded(3, "x").code(),
+            "max_reconnect_attempts_exceeded"
+        );
+    }
+
+    #[test]
+    fn test_retryable_classification() {
+        assert!(TrackerError::rpc("timeout", None).is_retryable());
+        assert!(TrackerError::websocket_disconnected("closed").is_retryable());
+        assert!(!TrackerError::decoding("bad log", None).is_retryable());
+        assert!(!TrackerError::max_reconnect_exceeded(5, "refused").is_retryable());
+
+        // The provider rejecting the request is final; rate limits are not
+        let response = |payload: &str| {
+            let error: alloy::transports::TransportError =
+                alloy::transports::RpcError::ErrorResp(serde_json::from_str(payload).unwrap());
+            TrackerError::rpc("getLogs", Some(Box::new(error)))
+        };
+        assert!(!response(r#"{"code":-32602,"message":"invalid params"}"#).is_retryable());
+        assert!(response(r#"{"code":429,"message":"Too Many Requests"}"#).is_retryable());
+
+        // Database errors only when the cause is known to be transient
+        assert!(
+            TrackerError::database("insert", Some(Box::new(sqlx::Error::PoolTimedOut)))
+                .is_retryable()
+        );
+        assert!(
+            !TrackerError::database("insert", Some(Box::new(sqlx::Error::RowNotFound)))
+                .is_retryable()
+        );
+        assert!(!TrackerError::database("insert", None).is_retryable());
+    }
+
     #[test]
     fn test_error_trait() {
         let err = TrackerError::rpc("test", None);
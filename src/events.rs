@@ -68,10 +68,15 @@
 //! # }
 //! ```
 
-use alloy::primitives::{address, Address};
-use alloy::rpc::types::Filter;
+use alloy::primitives::{address, Address, Log as PrimitiveLog};
+use alloy::rpc::types::{Filter, Log};
 use alloy::sol;
 use alloy::sol_types::SolEvent;
+use alloy::transports::TransportError;
+use std::future::Future;
+use tracing::debug;
+
+use crate::error::{TrackerError, TrackerResult};
 
 // Generate Uniswap V2 Pair contract interface using the sol! macro.
 // The macro creates type-safe Rust bindings with automatic ABI encoding/decoding.
@@ -87,6 +92,27 @@ sol! {
         /// - `reserve0`: Updated reserve for token0
         /// - `reserve1`: Updated reserve for token1
         event Sync(uint112 reserve0, uint112 reserve1);
+
+        /// Emitted for every swap through the pair.
+        ///
+        /// # Fields
+        /// - `sender`: Address that called `swap` (usually a router)
+        /// - `amount0In` / `amount1In`: Tokens paid into the pair
+        /// - `amount0Out` / `amount1Out`: Tokens paid out of the pair
+        /// - `to`: Recipient of the output tokens
+        event Swap(address indexed sender, uint amount0In, uint amount1In, uint amount0Out, uint amount1Out, address indexed to);
+
+        /// Returns the current reserves and the timestamp of the last update.
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast);
+
+        /// Returns the factory that deployed the pair.
+        function factory() external view returns (address);
+
+        /// Returns the pair's first token (the lower address).
+        function token0() external view returns (address);
+
+        /// Returns the pair's second token.
+        function token1() external view returns (address);
     }
 }
 
@@ -106,6 +132,7 @@ sol! {
 }
 
 // Re-export the generated types for easier access
+pub use IUniswapV2Pair::Swap;
 pub use IUniswapV2Pair::Sync;
 
 /// Uniswap V2 WETH/USDT Pair contract address on Ethereum mainnet.
@@ -159,12 +186,13 @@ pub const USDT_ADDRESS: Address = address!("dAC17F958D2ee523a2206206994597C13D83
 /// # Ok(())
 /// # }
 /// ```
-pub async fn fetch_token_decimals<P>(
+pub async fn fetch_token_decimals<T, P>(
     provider: &P,
     token_address: Address,
 ) -> crate::error::TrackerResult<u8>
 where
-    P: alloy::providers::Provider,
+    T: alloy::transports::Transport + Clone,
+    P: alloy::providers::Provider<T>,
 {
     use crate::error::TrackerError;
 
@@ -188,6 +216,116 @@ where
     Ok(decimals)
 }
 
+/// Fetch the symbol of an ERC20 token.
+///
+/// ## Errors
+///
+/// Returns error if the RPC call fails or `symbol()` doesn't return a string
+/// (a few early tokens return `bytes32`).
+pub async fn fetch_token_symbol(
+    provider: &crate::rpc::Provider,
+    token_address: Address,
+) -> TrackerResult<String> {
+    let symbol = IERC20::new(token_address, provider)
+        .symbol()
+        .call()
+        .await
+        .map_err(|e| {
+            TrackerError::rpc(
+                format!("Failed to fetch symbol for token {token_address}: {e}"),
+                Some(Box::new(e)),
+            )
+        })?
+        ._0;
+
+    Ok(symbol)
+}
+
+/// Fetch a pair's two tokens via `token0()` and `token1()`.
+///
+/// ## Errors
+///
+/// Returns error if either call fails, e.g. no pair exists at
+/// `pair_address`.
+pub async fn fetch_pair_tokens(
+    provider: &crate::rpc::Provider,
+    pair_address: Address,
+) -> TrackerResult<(Address, Address)> {
+    let pair = IUniswapV2Pair::new(pair_address, provider);
+    let to_error = |e: alloy::contract::Error| {
+        TrackerError::rpc(
+            format!("Failed to fetch the tokens of pair {pair_address}: {e}"),
+            Some(Box::new(e)),
+        )
+    };
+    let token0 = pair.token0().call().await.map_err(to_error)?._0;
+    let token1 = pair.token1().call().await.map_err(to_error)?._0;
+
+    Ok((token0, token1))
+}
+
+/// Fetch a pair's reserves as of a block via `getReserves()`.
+///
+/// Blocks older than the node's state history (typically 128 blocks on
+/// non-archive nodes) require an archive node.
+///
+/// ## Errors
+///
+/// Returns error if the call fails, e.g. the node has pruned the block's state
+/// or no pair exists at `pair_address`.
+pub async fn fetch_reserves_at(
+    provider: &crate::rpc::Provider,
+    pair_address: Address,
+    block_number: u64,
+) -> TrackerResult<(alloy::primitives::U256, alloy::primitives::U256)> {
+    let reserves = IUniswapV2Pair::new(pair_address, provider)
+        .getReserves()
+        .block(block_number.into())
+        .call()
+        .await
+        .map_err(|e| {
+            TrackerError::rpc(
+                format!("Failed to fetch reserves of {pair_address} at block {block_number}: {e}"),
+                Some(Box::new(e)),
+            )
+        })?;
+
+    Ok((
+        alloy::primitives::U256::from(reserves.reserve0),
+        alloy::primitives::U256::from(reserves.reserve1),
+    ))
+}
+
+/// Fetch a pair's reserves at a block tag (e.g. `latest` or `pending`)
+/// via `getReserves()`.
+///
+/// ## Errors
+///
+/// Returns error if the call fails, e.g. the node doesn't support the tag or
+/// no pair exists at `pair_address`.
+pub async fn fetch_reserves_at_tag(
+    provider: &crate::rpc::Provider,
+    pair_address: Address,
+    tag: alloy::rpc::types::BlockNumberOrTag,
+) -> TrackerResult<(alloy::primitives::U256, alloy::primitives::U256)> {
+    let reserves = IUniswapV2Pair::new(pair_address, provider)
+        .getReserves()
+        .block(tag.into())
+        .call()
+        .await
+        .map_err(|e| {
+            TrackerError::rpc(
+                format!("Failed to fetch reserves of {pair_address} at the {tag} block: {e}"),
+                Some(Box::new(e)),
+            )
+        })?;
+
+    Ok((
+        alloy::primitives::U256::from(reserves.reserve0),
+        alloy::primitives::U256::from(reserves.reserve1),
+    ))
+}
+
 /// Create a typed filter for Sync events from the WETH/USDT pair.
 ///
 /// This function creates an Alloy `Filter` that will match Sync events
@@ -269,6 +407,160 @@ pub fn create_sync_filter_for_pair(
         .to_block(to_block)
 }
 
+/// Create a filter for both Sync and Swap events from a pair.
+///
+/// Every swap emits a Sync followed by a Swap in the same transaction, so
+/// one `eth_getLogs` call returns both. Tell them apart with [`is_swap_log`].
+#[must_use]
+pub fn create_pair_events_filter(pair_address: Address, from_block: u64, to_block: u64) -> Filter {
+    Filter::new()
+        .address(pair_address)
+        .event_signature(vec![Sync::SIGNATURE_HASH, Swap::SIGNATURE_HASH])
+        .from_block(from_block)
+        .to_block(to_block)
+}
+
+/// Error messages of providers refusing an `eth_getLogs` response as too
+/// large, or its block range as too wide (lowercase).
+const TOO_LARGE_ERRORS: &[&str] = &[
+    // Alchemy
+    "log response size exceeded",
+    // Infura, geth
+    "query returned more than",
+    "response too large",
+    "response is too big",
+    "response size exceeded",
+    // Range limits (Alchemy free tier, QuickNode, Ankr, ...)
+    "block range",
+    "range is too large",
+    "is limited to a",
+];
+
+/// Returns true if `error` means the requested range holds too many logs for
+/// one `eth_getLogs` response, so a smaller range may succeed.
+#[must_use]
+pub fn is_response_too_large(error: &str) -> bool {
+    let error = error.to_lowercase();
+    TOO_LARGE_ERRORS
+        .iter()
+        .any(|pattern| error.contains(pattern))
+}
+
+/// Fetches `from_block..=to_block` with `fetch`, halving ranges the provider
+/// refuses as too large (see [`is_response_too_large`]) until they fit.
+///
+/// Results come back in block order, as if fetched in one call. A single
+/// block that is still too large, and any other error, is returned as is.
+///
+/// # Errors
+///
+/// Returns the first error that splitting the range doesn't resolve.
+pub async fn fetch_split<T, E, F, Fut>(
+    from_block: u64,
+    to_block: u64,
+    mut fetch: F,
+) -> Result<Vec<T>, E>
+where
+    E: std::fmt::Display,
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    let mut items = Vec::new();
+    // Ranges still to fetch, the next one last
+    let mut pending = vec![(from_block, to_block)];
+    while let Some((from, to)) = pending.pop() {
+        match fetch(from, to).await {
+            Ok(batch) => items.extend(batch),
+            Err(e) if from < to && is_response_too_large(&e.to_string()) => {
+                let middle = from + (to - from) / 2;
+                debug!(from, to, middle, "Response too large, splitting range");
+                pending.push((middle + 1, to));
+                pending.push((from, middle));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(items)
+}
+
+/// Fetches the logs matching `filter` in `from_block..=to_block`, splitting
+/// the range while the provider refuses it as too large (see
+/// [`fetch_split`]).
+///
+/// `filter`'s own block range is replaced.
+///
+/// # Errors
+///
+/// Returns an error if a request fails for any other reason, or a single
+/// block holds more logs than the provider returns at once.
+pub async fn get_logs_split(
+    provider: &crate::rpc::Provider,
+    filter: &Filter,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<Log>, TransportError> {
+    use alloy::providers::Provider as _;
+
+    fetch_split(from_block, to_block, |from, to| {
+        let filter = filter.clone().from_block(from).to_block(to);
+        async move { provider.get_logs(&filter).await }
+    })
+    .await
+}
+
+/// Returns true if `log` is a Swap event.
+#[must_use]
+pub fn is_swap_log(log: &Log) -> bool {
+    log.topic0() == Some(&Swap::SIGNATURE_HASH)
+}
+
+/// Decode a Swap event from an RPC log.
+///
+/// Returns the decoded event and the block number it was emitted in.
+///
+/// # Errors
+///
+/// Returns an error if the log has no block number or is not a Swap event.
+pub fn decode_swap_event(log: &Log) -> TrackerResult<(Swap, u64)> {
+    let block_number = log
+        .block_number
+        .ok_or_else(|| TrackerError::decoding("Log missing block number", None))?;
+
+    let primitive_log = PrimitiveLog {
+        address: log.address(),
+        data: log.data().clone(),
+    };
+
+    let swap_event = Swap::decode_log(&primitive_log, true)
+        .map_err(|e| TrackerError::decoding(format!("Failed to decode Swap event: {e}"), None))?;
+
+    Ok((swap_event.data, block_number))
+}
+
+/// Decode a Sync event from an RPC log.
+///
+/// Returns the decoded event and the block number it was emitted in.
+///
+/// # Errors
+///
+/// Returns an error if the log has no block number or is not a Sync event.
+pub fn decode_sync_event(log: &Log) -> TrackerResult<(Sync, u64)> {
+    let block_number = log
+        .block_number
+        .ok_or_else(|| TrackerError::decoding("Log missing block number", None))?;
+
+    // Convert RPC Log to Primitive Log for decoding
+    let primitive_log = PrimitiveLog {
+        address: log.address(),
+        data: log.data().clone(),
+    };
+
+    let sync_event = Sync::decode_log(&primitive_log, true)
+        .map_err(|e| TrackerError::decoding(format!("Failed to decode Sync event: {e}"), None))?;
+
+    Ok((sync_event.data, block_number))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,6 +597,77 @@ mod tests {
         let _ = filter;
     }
 
+    #[test]
+    fn test_is_response_too_large() {
+        for message in [
+            "server returned an error response: error code -32602: Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range and no limit on the response size, or you can request any block range with a cap of 10K logs in the response.",
+            "server returned an error response: error code -32005: query returned more than 10000 results",
+            "server returned an error response: error code -32602: Under the Free tier plan, you can make eth_getLogs requests with up to a 10 block range.",
+            "eth_getLogs is limited to a 10,000 range",
+        ] {
+            assert!(is_response_too_large(message), "{message}");
+        }
+        assert!(!is_response_too_large(
+            "server returned an error response: error code 429: Your app has exceeded its compute units per second capacity"
+        ));
+        assert!(!is_response_too_large("error sending request for url"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_split_bisects_oversized_ranges() {
+        // One "log" per block, at most 3 per response
+        let mut calls = Vec::new();
+        let blocks = fetch_split(10, 19, |from, to| {
+            calls.push((from, to));
+            async move {
+                if to - from + 1 > 3 {
+                    Err("query returned more than 3 results")
+                } else {
+                    Ok((from..=to).collect::<Vec<u64>>())
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(blocks, (10..=19).collect::<Vec<_>>());
+        assert_eq!(
+            calls,
+            [
+                (10, 19),
+                (10, 14),
+                (10, 12),
+                (13, 14),
+                (15, 19),
+                (15, 17),
+                (18, 19)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_split_returns_unrelated_and_single_block_errors() {
+        let mut calls = 0;
+        let result: Result<Vec<u64>, _> = fetch_split(1, 100, |_, _| {
+            calls += 1;
+            async { Err("connection reset") }
+        })
+        .await;
+        assert_eq!(result, Err("connection reset"));
+        assert_eq!(calls, 1);
+
+        // A block too large on its own can't be split further
+        let result: Result<Vec<u64>, _> = fetch_split(1, 4, |from, to| async move {
+            if from <= 3 && 3 <= to {
+                Err("response too large")
+            } else {
+                Ok(vec![from])
+            }
+        })
+        .await;
+        assert_eq!(result, Err("response too large"));
+    }
+
     #[test]
     fn test_constants() {
         // Verify addresses are well-formed (not zero)
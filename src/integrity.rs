@@ -0,0 +1,419 @@
+//! Gap detection and self-healing for indexed data.
+//!
+//! The indexer can miss blocks: an RPC error mid-batch, a crash between
+//! writes, or a reorg invalidation that was never re-indexed. The integrity
+//! checker compares the blocks in which the pair emitted `Sync` events on
+//! chain with the blocks that have confirmed `sync_events` and `price_points`
+//! rows, and reports every missing run as a [`BlockGap`].
+//!
+//! Gaps are measured in event-bearing blocks: two missing blocks are part of
+//! the same gap when no indexed event block lies between them, however many
+//! empty blocks separate them. Healing re-fetches the logs for each gap and
+//! writes them through [`Storage`]; inserts are idempotent, so rows already
+//! present in a gap are simply rewritten.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use eth_uniswap_alloy::db::{create_pool, repository::Repository};
+//! use eth_uniswap_alloy::events::UNISWAP_V2_WETH_USDT_PAIR;
+//! use eth_uniswap_alloy::integrity;
+//! use eth_uniswap_alloy::rpc::create_provider;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let provider = create_provider("https://eth-mainnet.g.alchemy.com/v2/KEY").await?;
+//! let repo = Repository::new(create_pool("sqlite:./indexer.db").await?);
+//! let pool = repo.find_pool("WETH/USDT").await?.ok_or("pool not tracked")?;
+//!
+//! let logs =
+//!     integrity::scan_chain(&provider, UNISWAP_V2_WETH_USDT_PAIR, 19_000_000, 19_001_000).await?;
+//! let report = integrity::check(&repo, pool.id, 19_000_000, 19_001_000, &logs).await?;
+//! if !report.is_clean() {
+//!     integrity::heal(&repo, &pool, 1, &logs, &report.gaps).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::Log;
+use std::collections::BTreeSet;
+use tracing::{debug, info};
+
+use crate::db::ids::{derive_record_id, RecordKind};
+use crate::db::models::{PoolRecord, PricePointRecord, SyncEventRecord};
+use crate::db::repository::Repository;
+use crate::db::storage::Storage;
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::{create_sync_filter_for_pair, decode_sync_event, get_logs_split};
+use crate::pricing::{exact_price_to_f64, format_token_amount};
+
+/// Blocks per `eth_getLogs` request (Alchemy free tier limit).
+pub const SCAN_BATCH_BLOCKS: u64 = 10;
+
+/// A run of event-bearing blocks missing from the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockGap {
+    /// First missing block
+    pub from_block: u64,
+    /// Last missing block
+    pub to_block: u64,
+    /// Number of event-bearing blocks missing in the range
+    pub missing_blocks: u64,
+}
+
+impl BlockGap {
+    /// Whether `block` lies within the gap's range.
+    #[must_use]
+    pub const fn contains(&self, block: u64) -> bool {
+        self.from_block <= block && block <= self.to_block
+    }
+}
+
+/// Result of an integrity check over a block range.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// First block checked
+    pub from_block: u64,
+    /// Last block checked
+    pub to_block: u64,
+    /// Blocks in the range in which the pair emitted Sync events
+    pub chain_blocks: u64,
+    /// Missing runs, in block order
+    pub gaps: Vec<BlockGap>,
+}
+
+impl IntegrityReport {
+    /// Whether no gaps were found.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.gaps.is_empty()
+    }
+
+    /// Total event-bearing blocks missing across all gaps.
+    #[must_use]
+    pub fn missing_blocks(&self) -> u64 {
+        self.gaps.iter().map(|g| g.missing_blocks).sum()
+    }
+}
+
+/// Groups the `expected` blocks absent from `indexed` into gaps.
+#[must_use]
+pub fn find_gaps(expected: &BTreeSet<u64>, indexed: &BTreeSet<u64>) -> Vec<BlockGap> {
+    let mut gaps: Vec<BlockGap> = Vec::new();
+    let mut in_gap = false;
+
+    for &block in expected {
+        if indexed.contains(&block) {
+            in_gap = false;
+            continue;
+        }
+        match gaps.last_mut() {
+            Some(gap) if in_gap => {
+                gap.to_block = block;
+                gap.missing_blocks += 1;
+            }
+            _ => gaps.push(BlockGap {
+                from_block: block,
+                to_block: block,
+                missing_blocks: 1,
+            }),
+        }
+        in_gap = true;
+    }
+
+    gaps
+}
+
+/// Fetches the pair's Sync logs for `[from_block, to_block]` in batches of
+/// [`SCAN_BATCH_BLOCKS`].
+///
+/// # Errors
+///
+/// Returns an error if any `eth_getLogs` request fails.
+pub async fn scan_chain(
+    provider: &crate::rpc::Provider,
+    pair: Address,
+    from_block: u64,
+    to_block: u64,
+) -> TrackerResult<Vec<Log>> {
+    let mut logs = Vec::new();
+    let mut start = from_block;
+
+    while start <= to_block {
+        let end = start.saturating_add(SCAN_BATCH_BLOCKS - 1).min(to_block);
+        let filter = create_sync_filter_for_pair(pair, start, end);
+        let batch = get_logs_split(provider, &filter, start, end)
+            .await
+            .map_err(|e| {
+                TrackerError::rpc(
+                    format!("Failed to fetch Sync events for blocks {start}-{end}: {e}"),
+                    None,
+                )
+            })?;
+        debug!(start, end, count = batch.len(), "Scanned blocks");
+        logs.extend(batch);
+
+        let Some(next) = end.checked_add(1) else {
+            break;
+        };
+        start = next;
+    }
+
+    Ok(logs)
+}
+
+/// Compares on-chain `logs` for `[from_block, to_block]` with the pool's
+/// indexed blocks.
+///
+/// # Errors
+///
+/// Returns an error if the database query fails.
+pub async fn check(
+    repository: &Repository,
+    pool_id: i64,
+    from_block: u64,
+    to_block: u64,
+    logs: &[Log],
+) -> TrackerResult<IntegrityReport> {
+    let expected: BTreeSet<u64> = logs
+        .iter()
+        .filter_map(|log| log.block_number)
+        .filter(|block| (from_block..=to_block).contains(block))
+        .collect();
+    let indexed: BTreeSet<u64> = repository
+        .get_indexed_blocks(pool_id, from_block, to_block)
+        .await?
+        .into_iter()
+        .collect();
+
+    let report = IntegrityReport {
+        from_block,
+        to_block,
+        chain_blocks: expected.len() as u64,
+        gaps: find_gaps(&expected, &indexed),
+    };
+    info!(
+        from_block,
+        to_block,
+        gaps = report.gaps.len(),
+        missing_blocks = report.missing_blocks(),
+        "Integrity check complete"
+    );
+
+    Ok(report)
+}
+
+/// Writes the sync events and price points for every log inside `gaps`.
+///
+/// Returns the number of events written.
+///
+/// # Errors
+///
+/// Returns an error if a log cannot be decoded or priced, or a write fails.
+pub async fn heal(
+    storage: &dyn Storage,
+    pool: &PoolRecord,
+    chain_id: u64,
+    logs: &[Log],
+    gaps: &[BlockGap],
+) -> TrackerResult<usize> {
+    let pool_address = pool.address.get();
+
+    let mut events = Vec::new();
+    let mut prices = Vec::new();
+    for log in logs {
+        if !log
+            .block_number
+            .is_some_and(|block| gaps.iter().any(|gap| gap.contains(block)))
+        {
+            continue;
+        }
+        let (event, price) = records_for_log(log, pool, pool_address, chain_id)?;
+        events.push(event);
+        prices.push(price);
+    }
+
+    let healed = events.len();
+    storage.insert_sync_events(events).await?;
+    storage.insert_price_points(prices).await?;
+    info!(healed, gaps = gaps.len(), "Backfilled missing blocks");
+
+    Ok(healed)
+}
+
+/// Builds the confirmed sync event and price point rows for a Sync log.
+fn records_for_log(
+    log: &Log,
+    pool: &PoolRecord,
+    pool_address: Address,
+    chain_id: u64,
+) -> TrackerResult<(SyncEventRecord, PricePointRecord)> {
+    let (sync_event, block_number) = decode_sync_event(log)?;
+    let block_hash = log.block_hash.unwrap_or_default();
+    let block_timestamp = log.block_timestamp.unwrap_or(0);
+    let tx_hash = log.transaction_hash.unwrap_or_default();
+    let log_index = u32::try_from(log.log_index.unwrap_or(0)).unwrap_or(u32::MAX);
+
+    let decimals = |d: i32| {
+        u8::try_from(d).map_err(|e| {
+            TrackerError::decoding(format!("Invalid token decimals: {d}"), Some(Box::new(e)))
+        })
+    };
+    let decimals0 = decimals(pool.token0_decimals)?;
+    let decimals1 = decimals(pool.token1_decimals)?;
+
+    let reserve0 = U256::from(sync_event.reserve0);
+    let reserve1 = U256::from(sync_event.reserve1);
+    let price_exact = pool
+        .price_adapter()?
+        .price_exact(reserve0, reserve1, decimals0, decimals1)?;
+    let human = |amount: U256, decimals: u8| {
+        format_token_amount(amount, decimals)
+            .parse::<f64>()
+            .unwrap_or(0.0)
+    };
+
+    let event = SyncEventRecord::new(
+        pool.id,
+        block_number,
+        block_hash,
+        block_timestamp,
+        tx_hash,
+        log_index,
+        reserve0,
+        reserve1,
+        true,
+    )
+    .with_event_id(derive_record_id(
+        RecordKind::SyncEvent,
+        chain_id,
+        pool_address,
+        block_hash,
+        tx_hash,
+        log_index,
+    ));
+    let price = PricePointRecord::new(
+        pool.id,
+        block_number,
+        block_timestamp,
+        tx_hash,
+        exact_price_to_f64(price_exact),
+        reserve0,
+        reserve1,
+        human(reserve0, decimals0),
+        human(reserve1, decimals1),
+        true,
+    )
+    .with_event_id(derive_record_id(
+        RecordKind::PricePoint,
+        chain_id,
+        pool_address,
+        block_hash,
+        tx_hash,
+        log_index,
+    ))
+    .with_price_exact(price_exact);
+
+    Ok((event, price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::events::Sync;
+    use alloy::primitives::{aliases::U112, B256};
+    use alloy::sol_types::SolEvent;
+
+    fn blocks(items: &[u64]) -> BTreeSet<u64> {
+        items.iter().copied().collect()
+    }
+
+    fn sync_log(pool_address: Address, block: u64) -> Log {
+        let sync = Sync {
+            reserve0: U112::from(1_000u64) * U112::from(10u64).pow(U112::from(18u64)),
+            reserve1: U112::from(2_000_000u64) * U112::from(10u64).pow(U112::from(6u64)),
+        };
+        Log {
+            inner: alloy::primitives::Log {
+                address: pool_address,
+                data: sync.encode_log_data(),
+            },
+            block_hash: Some(B256::with_last_byte(1)),
+            block_number: Some(block),
+            block_timestamp: Some(1_700_000_000 + block),
+            transaction_hash: Some(B256::from(U256::from(block))),
+            transaction_index: Some(0),
+            log_index: Some(0),
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn test_find_gaps_groups_consecutive_missing_blocks() {
+        let expected = blocks(&[10, 12, 15, 20, 21, 30]);
+        let indexed = blocks(&[10, 21]);
+
+        assert_eq!(
+            find_gaps(&expected, &indexed),
+            vec![
+                BlockGap {
+                    from_block: 12,
+                    to_block: 20,
+                    missing_blocks: 3
+                },
+                BlockGap {
+                    from_block: 30,
+                    to_block: 30,
+                    missing_blocks: 1
+                },
+            ]
+        );
+        assert!(find_gaps(&expected, &expected).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_and_heal_fill_gaps() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        let logs: Vec<Log> = [100, 105, 110]
+            .into_iter()
+            .map(|block| sync_log(pool_address, block))
+            .collect();
+
+        // Only the middle block was indexed
+        heal(
+            &repo,
+            &pool,
+            1,
+            &logs,
+            &[BlockGap {
+                from_block: 105,
+                to_block: 105,
+                missing_blocks: 1,
+            }],
+        )
+        .await
+        .unwrap();
+
+        let report = check(&repo, pool_id, 100, 110, &logs).await.unwrap();
+        assert_eq!(report.chain_blocks, 3);
+        assert_eq!(report.gaps.len(), 2);
+        assert_eq!(report.missing_blocks(), 2);
+
+        let healed = heal(&repo, &pool, 1, &logs, &report.gaps).await.unwrap();
+        assert_eq!(healed, 2);
+
+        let report = check(&repo, pool_id, 100, 110, &logs).await.unwrap();
+        assert!(report.is_clean());
+
+        let latest = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+        assert_eq!(latest.block_number, 110);
+        assert_eq!(latest.price_exact.as_deref(), Some("2000"));
+    }
+}
@@ -0,0 +1,384 @@
+//! Write-ahead journal of fetched logs.
+//!
+//! With `EVENT_JOURNAL_DIR` set, `watch` appends every batch of logs it
+//! fetches to an append-only journal, and flushes it to disk, before the
+//! batch enters the price and write stages of the [`crate::pipeline`]. A
+//! crash between the fetch and the database commit then loses nothing: on
+//! restart each pool's task replays the journaled batches past its
+//! checkpoint into the database before fetching from the node again. Rows
+//! are keyed by deterministic IDs, so replaying a batch that was in fact
+//! committed rewrites the same rows.
+//!
+//! Each pool has its own directory of segment files, one per
+//! `EVENT_JOURNAL_SEGMENT_BLOCKS` blocks, holding one JSON [`JournalEntry`]
+//! per line:
+//!
+//! ```text
+//! <EVENT_JOURNAL_DIR>/pool-1/000019000000.jsonl
+//! <EVENT_JOURNAL_DIR>/pool-1/000019010000.jsonl
+//! ```
+//!
+//! A batch crossing a segment boundary is split into one entry per segment.
+//! Once the checkpoint has moved past a segment's last block, the segment is
+//! deleted, and the last block it covered is recorded in the pool's
+//! `truncated` file so replay knows those blocks are committed. A line torn by a crash during the append is skipped on replay;
+//! its batch was never committed, so it is fetched again.
+
+use alloy::rpc::types::Log;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use crate::error::{TrackerError, TrackerResult};
+
+/// Default number of blocks per segment file.
+pub const DEFAULT_SEGMENT_BLOCKS: u64 = 10_000;
+
+/// Extension of segment files.
+const SEGMENT_EXTENSION: &str = "jsonl";
+
+/// Name of the file holding the last block of the deleted segments.
+const WATERMARK_FILE: &str = "truncated";
+
+/// Logs fetched for one block range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// First block of the range
+    pub from_block: u64,
+    /// Last block of the range
+    pub to_block: u64,
+    /// Every log the node returned for the range, in log order
+    pub logs: Vec<Log>,
+}
+
+/// Append-only journal of fetched logs, segmented by block range.
+#[derive(Debug, Clone)]
+pub struct EventJournal {
+    dir: PathBuf,
+    segment_blocks: u64,
+}
+
+impl EventJournal {
+    /// Creates a journal in `dir` with `segment_blocks` blocks per segment
+    /// (at least 1). The directory is created on the first append.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>, segment_blocks: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_blocks: segment_blocks.max(1),
+        }
+    }
+
+    /// Returns the journal's directory.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn pool_dir(&self, pool_id: i64) -> PathBuf {
+        self.dir.join(format!("pool-{pool_id}"))
+    }
+
+    fn segment_start(&self, block: u64) -> u64 {
+        block - block % self.segment_blocks
+    }
+
+    /// Appends the logs fetched for `from_block..=to_block` and flushes them
+    /// to disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a segment can't be written or synced.
+    pub async fn append(
+        &self,
+        pool_id: i64,
+        from_block: u64,
+        to_block: u64,
+        logs: &[Log],
+    ) -> TrackerResult<()> {
+        let pool_dir = self.pool_dir(pool_id);
+        tokio::fs::create_dir_all(&pool_dir)
+            .await
+            .map_err(|e| io_error("create", &pool_dir, e))?;
+
+        let mut start = from_block;
+        while start <= to_block {
+            let segment = self.segment_start(start);
+            let end = (segment + self.segment_blocks - 1).min(to_block);
+            let entry = JournalEntry {
+                from_block: start,
+                to_block: end,
+                logs: logs
+                    .iter()
+                    .filter(|log| {
+                        log.block_number
+                            .is_some_and(|block| (start..=end).contains(&block))
+                    })
+                    .cloned()
+                    .collect(),
+            };
+            let mut line = serde_json::to_vec(&entry).map_err(|e| {
+                TrackerError::decoding("Failed to encode journal entry", Some(Box::new(e)))
+            })?;
+            line.push(b'\n');
+
+            let path = pool_dir.join(format!("{segment:012}.{SEGMENT_EXTENSION}"));
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await
+                .map_err(|e| io_error("open", &path, e))?;
+            file.write_all(&line)
+                .await
+                .map_err(|e| io_error("append to", &path, e))?;
+            file.sync_data()
+                .await
+                .map_err(|e| io_error("sync", &path, e))?;
+
+            start = end + 1;
+        }
+        Ok(())
+    }
+
+    /// Returns the journaled ranges after `after_block`, oldest first,
+    /// clipped to start after it.
+    ///
+    /// Ranges journaled more than once (a batch fetched again after a failed
+    /// pass) are returned once. Replay stops at the first gap, since the
+    /// blocks in it were never fetched. Blocks in segments deleted by
+    /// [`truncate`](Self::truncate) were committed, so replay never starts
+    /// before the last of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a segment can't be read.
+    pub async fn replay(&self, pool_id: i64, after_block: u64) -> TrackerResult<Vec<JournalEntry>> {
+        let after_block = self
+            .watermark(pool_id)
+            .await?
+            .map_or(after_block, |watermark| after_block.max(watermark));
+        let mut entries = Vec::new();
+        for (segment, path) in self.segments(pool_id).await? {
+            if segment + self.segment_blocks <= after_block + 1 {
+                continue;
+            }
+            let contents = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| io_error("read", &path, e))?;
+            let lines: Vec<&str> = contents.lines().collect();
+            for (i, line) in lines.iter().enumerate() {
+                match serde_json::from_str::<JournalEntry>(line) {
+                    Ok(entry) => entries.push(entry),
+                    // Only the last append can be cut short
+                    Err(e) if i + 1 == lines.len() => {
+                        warn!(path = %path.display(), error = %e, "Skipping torn journal entry");
+                    }
+                    Err(e) => {
+                        return Err(TrackerError::decoding(
+                            format!("Corrupt journal entry in {}", path.display()),
+                            Some(Box::new(e)),
+                        ))
+                    }
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.from_block);
+
+        let mut next = after_block + 1;
+        let mut replay = Vec::new();
+        for mut entry in entries {
+            if entry.to_block < next {
+                continue;
+            }
+            if entry.from_block > next {
+                debug!(pool_id, block = next, "Journal has a gap; replay stops");
+                break;
+            }
+            entry.from_block = next;
+            entry
+                .logs
+                .retain(|log| log.block_number.is_some_and(|block| block >= next));
+            next = entry.to_block + 1;
+            replay.push(entry);
+        }
+        Ok(replay)
+    }
+
+    /// Deletes the segments whose blocks are all at or below `through_block`,
+    /// returning how many were deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a segment can't be listed or deleted.
+    pub async fn truncate(&self, pool_id: i64, through_block: u64) -> TrackerResult<usize> {
+        let expired: Vec<(u64, PathBuf)> = self
+            .segments(pool_id)
+            .await?
+            .into_iter()
+            .filter(|(segment, _)| segment + self.segment_blocks <= through_block + 1)
+            .collect();
+        let Some((last, _)) = expired.last() else {
+            return Ok(0);
+        };
+
+        // Recorded first, so a crash mid-truncate leaves segments that replay
+        // skips rather than a gap it stops at
+        let watermark = last + self.segment_blocks - 1;
+        if self.watermark(pool_id).await? < Some(watermark) {
+            let pool_dir = self.pool_dir(pool_id);
+            let path = pool_dir.join(WATERMARK_FILE);
+            let staged = pool_dir.join(format!("{WATERMARK_FILE}.tmp"));
+            tokio::fs::write(&staged, watermark.to_string())
+                .await
+                .map_err(|e| io_error("write", &staged, e))?;
+            tokio::fs::rename(&staged, &path)
+                .await
+                .map_err(|e| io_error("write", &path, e))?;
+        }
+
+        let mut removed = 0;
+        for (_, path) in expired {
+            tokio::fs::remove_file(&path)
+                .await
+                .map_err(|e| io_error("delete", &path, e))?;
+            removed += 1;
+        }
+        if removed > 0 {
+            debug!(pool_id, through_block, removed, "Truncated event journal");
+        }
+        Ok(removed)
+    }
+
+    /// Returns the last block of the pool's deleted segments, if any were.
+    async fn watermark(&self, pool_id: i64) -> TrackerResult<Option<u64>> {
+        let path = self.pool_dir(pool_id).join(WATERMARK_FILE);
+        let contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error("read", &path, e)),
+        };
+        contents.trim().parse().map(Some).map_err(|e| {
+            TrackerError::decoding(
+                format!("Corrupt journal watermark in {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Lists a pool's segments as `(first block, path)`.
+    async fn segments(&self, pool_id: i64) -> TrackerResult<Vec<(u64, PathBuf)>> {
+        let pool_dir = self.pool_dir(pool_id);
+        let mut dir = match tokio::fs::read_dir(&pool_dir).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error("list", &pool_dir, e)),
+        };
+
+        let mut segments = Vec::new();
+        while let Some(file) = dir
+            .next_entry()
+            .await
+            .map_err(|e| io_error("list", &pool_dir, e))?
+        {
+            let path = file.path();
+            let segment = path
+                .extension()
+                .filter(|ext| *ext == SEGMENT_EXTENSION)
+                .and_then(|_| path.file_stem()?.to_str()?.parse::<u64>().ok());
+            if let Some(segment) = segment {
+                segments.push((segment, path));
+            }
+        }
+        segments.sort();
+        Ok(segments)
+    }
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> TrackerError {
+    TrackerError::state(
+        format!("Failed to {action} event journal {}", path.display()),
+        Some(Box::new(e)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(block: u64) -> Log {
+        Log {
+            block_number: Some(block),
+            ..Log::default()
+        }
+    }
+
+    fn blocks(entry: &JournalEntry) -> Vec<u64> {
+        entry.logs.iter().filter_map(|l| l.block_number).collect()
+    }
+
+    #[tokio::test]
+    async fn test_append_splits_segments_and_replays_past_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = EventJournal::new(dir.path(), 100);
+
+        journal
+            .append(1, 90, 109, &[log(95), log(101), log(105)])
+            .await
+            .unwrap();
+        journal.append(1, 110, 119, &[log(112)]).await.unwrap();
+        assert!(dir.path().join("pool-1/000000000000.jsonl").exists());
+        assert!(dir.path().join("pool-1/000000000100.jsonl").exists());
+
+        let entries = journal.replay(1, 100).await.unwrap();
+        let ranges: Vec<(u64, u64)> = entries.iter().map(|e| (e.from_block, e.to_block)).collect();
+        assert_eq!(ranges, [(101, 109), (110, 119)]);
+        assert_eq!(blocks(&entries[0]), [101, 105]);
+        assert_eq!(blocks(&entries[1]), [112]);
+
+        // Other pools have their own journal
+        assert!(journal.replay(2, 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_skips_duplicates_and_stops_at_gaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = EventJournal::new(dir.path(), 1_000);
+
+        journal.append(1, 10, 19, &[log(12)]).await.unwrap();
+        // The same batch fetched again after a failed pass
+        journal.append(1, 10, 19, &[log(12)]).await.unwrap();
+        journal.append(1, 20, 29, &[]).await.unwrap();
+        journal.append(1, 40, 49, &[log(41)]).await.unwrap();
+
+        let entries = journal.replay(1, 9).await.unwrap();
+        let ranges: Vec<(u64, u64)> = entries.iter().map(|e| (e.from_block, e.to_block)).collect();
+        assert_eq!(ranges, [(10, 19), (20, 29)]);
+    }
+
+    #[tokio::test]
+    async fn test_torn_entry_is_skipped_and_truncate_drops_old_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = EventJournal::new(dir.path(), 10);
+
+        journal.append(1, 0, 9, &[log(3)]).await.unwrap();
+        journal.append(1, 10, 19, &[log(15)]).await.unwrap();
+        let segment = dir.path().join("pool-1/000000000010.jsonl");
+        let mut contents = std::fs::read_to_string(&segment).unwrap();
+        contents.push_str("{\"from_block\":20,\"to_b");
+        std::fs::write(&segment, contents).unwrap();
+
+        let entries = journal.replay(1, 0).await.unwrap();
+        assert_eq!(entries.len(), 2);
+
+        // Deleted segments count as committed rather than as a gap
+        assert_eq!(journal.truncate(1, 15).await.unwrap(), 1);
+        assert_eq!(journal.truncate(1, 15).await.unwrap(), 0);
+        let entries = journal.replay(1, 0).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].from_block, entries[0].to_block), (10, 19));
+        assert_eq!(journal.truncate(1, 19).await.unwrap(), 1);
+        assert!(journal.replay(1, 0).await.unwrap().is_empty());
+    }
+}
@@ -127,15 +127,51 @@
 #![forbid(unsafe_code)]
 
 // Module declarations will go here as we build them
+pub mod adapters;
+pub mod alerts;
+pub mod analytics;
 pub mod api;
 pub mod app_state;
+pub mod backfill;
+pub mod candles;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod cli;
+pub mod composite;
 pub mod config;
+pub mod consistency;
+pub mod daemon;
 pub mod db;
+pub mod dedup;
+pub mod depeg;
+#[cfg(feature = "discord")]
+pub mod discord;
+pub mod downsample;
 pub mod error;
 pub mod events;
+pub mod integrity;
+pub mod journal;
+pub mod log_cache;
 pub mod observability;
+pub mod pipeline;
+pub mod pool_registry;
+pub mod preview;
+pub mod price_cache;
+pub mod price_webhooks;
 pub mod pricing;
+pub mod protocol;
+#[cfg(feature = "redis")]
+pub mod redis_bus;
 pub mod reorg;
+pub mod replay;
+pub mod retention;
+pub mod routing;
 pub mod rpc;
+pub mod scheduler;
+pub mod smoothing;
+pub mod spikes;
+pub mod standby;
 pub mod state;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod watchdog;
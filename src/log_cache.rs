@@ -0,0 +1,500 @@
+//! On-disk cache of fetched logs.
+//!
+//! With `LOG_CACHE_DIR` set, `backfill` looks up every `eth_getLogs` range
+//! it fetches in a local cache before calling the RPC provider, and stores
+//! what the provider returns. Backfilling the same ranges again, as happens
+//! in development after resetting the database, then makes no `eth_getLogs`
+//! calls at all.
+//!
+//! Entries are keyed by the filter (addresses and topics, not the block
+//! range) and the exact block range, one JSON file each:
+//!
+//! ```text
+//! <LOG_CACHE_DIR>/<filter key>/000019000000-000019001999.json
+//! <LOG_CACHE_DIR>/stats.json
+//! ```
+//!
+//! A range is only stored once it is finalized: logs of later blocks can
+//! still be reorganized away. Ranges are only found again when they are
+//! fetched with the same `BATCH_SIZE` and shard layout. `stats.json` keeps
+//! the hit and miss counts of past runs for `cache stats`.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use alloy::primitives::keccak256;
+use alloy::providers::Provider as _;
+use alloy::rpc::types::{BlockNumberOrTag, BlockTransactionsKind, Filter, Log};
+use alloy::transports::TransportError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::get_logs_split;
+use crate::rpc::Provider;
+
+/// File of the hit and miss counts of past runs.
+const STATS_FILE: &str = "stats.json";
+
+/// Extension of entry files.
+const ENTRY_EXTENSION: &str = "json";
+
+/// Cache hits and misses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheCounters {
+    /// Ranges served from the cache
+    pub hits: u64,
+    /// Ranges fetched from the RPC provider
+    pub misses: u64,
+}
+
+impl CacheCounters {
+    /// Share of lookups served from the cache (`None` before any lookup).
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups > 0).then(|| self.hits as f64 / lookups as f64)
+    }
+}
+
+/// What a cache directory holds, for `cache stats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CacheSummary {
+    /// Cache directory
+    pub dir: PathBuf,
+    /// Cached ranges
+    pub entries: u64,
+    /// Size of the cached ranges
+    pub bytes: u64,
+    /// Hits and misses of past runs
+    #[serde(flatten)]
+    pub counters: CacheCounters,
+    /// Share of lookups served from the cache
+    pub hit_ratio: Option<f64>,
+}
+
+/// Cache of `eth_getLogs` responses for finalized block ranges.
+#[derive(Debug)]
+pub struct LogCache {
+    dir: PathBuf,
+    /// Last block whose logs may be stored (`None`: nothing is stored)
+    finalized_block: Option<u64>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl LogCache {
+    /// Creates a cache in `dir` that serves cached ranges but stores none
+    /// until [`with_finalized_block`](Self::with_finalized_block) is set. The
+    /// directory is created on the first store.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            finalized_block: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Stores ranges ending at or before `block`.
+    #[must_use]
+    pub const fn with_finalized_block(mut self, block: u64) -> Self {
+        self.finalized_block = Some(block);
+        self
+    }
+
+    /// Creates a cache in `dir` that stores ranges up to the chain's
+    /// finalized block, as reported by `provider`.
+    ///
+    /// Chains without a finalized block tag only get cached ranges served.
+    pub async fn open(dir: impl Into<PathBuf>, provider: &Provider) -> Self {
+        let cache = Self::new(dir);
+        match provider
+            .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+            .await
+        {
+            Ok(Some(block)) => cache.with_finalized_block(block.header.number),
+            Ok(None) => cache,
+            Err(e) => {
+                warn!(
+                    "Finalized block unavailable, not storing logs in the cache: {}",
+                    e
+                );
+                cache
+            }
+        }
+    }
+
+    /// Returns the cache's directory.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Hits and misses of this run.
+    #[must_use]
+    pub fn counters(&self) -> CacheCounters {
+        CacheCounters {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn entry_path(&self, filter: &Filter, from_block: u64, to_block: u64) -> PathBuf {
+        self.dir
+            .join(filter_key(filter))
+            .join(format!("{from_block:012}-{to_block:012}.{ENTRY_EXTENSION}"))
+    }
+
+    /// Returns the cached logs of `filter` over `from_block..=to_block`, and
+    /// counts the lookup as a hit or miss.
+    ///
+    /// An unreadable entry counts as a miss; it is overwritten by the next
+    /// store.
+    pub async fn get(&self, filter: &Filter, from_block: u64, to_block: u64) -> Option<Vec<Log>> {
+        let path = self.entry_path(filter, from_block, to_block);
+        let logs = match tokio::fs::read(&path).await {
+            Ok(contents) => match serde_json::from_slice(&contents) {
+                Ok(logs) => Some(logs),
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Ignoring corrupt log cache entry");
+                    None
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read log cache entry");
+                None
+            }
+        };
+        let counter = if logs.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        logs
+    }
+
+    /// Stores the logs of `filter` over `from_block..=to_block`, returning
+    /// whether the range was finalized and so stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry can't be written.
+    pub async fn put(
+        &self,
+        filter: &Filter,
+        from_block: u64,
+        to_block: u64,
+        logs: &[Log],
+    ) -> TrackerResult<bool> {
+        if !self
+            .finalized_block
+            .is_some_and(|finalized| to_block <= finalized)
+        {
+            return Ok(false);
+        }
+        let path = self.entry_path(filter, from_block, to_block);
+        let contents = serde_json::to_vec(logs).map_err(|e| {
+            TrackerError::decoding("Failed to encode log cache entry", Some(Box::new(e)))
+        })?;
+        write_atomic(&path, &contents).await?;
+        Ok(true)
+    }
+
+    /// Fetches the logs of `filter` over `from_block..=to_block` from the
+    /// cache, or from `provider` (see [`get_logs_split`]), storing them if
+    /// the range is finalized.
+    ///
+    /// Failing to store the logs only logs a warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the logs aren't cached and the RPC call fails.
+    pub async fn get_logs(
+        &self,
+        provider: &Provider,
+        filter: &Filter,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Log>, TransportError> {
+        if let Some(logs) = self.get(filter, from_block, to_block).await {
+            debug!(from_block, to_block, logs = logs.len(), "Log cache hit");
+            return Ok(logs);
+        }
+        let logs = get_logs_split(provider, filter, from_block, to_block).await?;
+        if let Err(e) = self.put(filter, from_block, to_block, &logs).await {
+            warn!("Failed to store logs in the cache: {}", e);
+        }
+        Ok(logs)
+    }
+
+    /// Adds this run's hits and misses to the counts in `stats.json`,
+    /// returning the new totals.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `stats.json` can't be written.
+    pub async fn save_counters(&self) -> TrackerResult<CacheCounters> {
+        let run = self.counters();
+        let saved = read_counters(&self.dir).await;
+        let total = CacheCounters {
+            hits: saved.hits + run.hits,
+            misses: saved.misses + run.misses,
+        };
+        let contents = serde_json::to_vec(&total).map_err(|e| {
+            TrackerError::decoding("Failed to encode log cache stats", Some(Box::new(e)))
+        })?;
+        write_atomic(&self.dir.join(STATS_FILE), &contents).await?;
+        Ok(total)
+    }
+}
+
+/// Counts the entries and bytes cached in `dir`, with the hits and misses of
+/// past runs.
+///
+/// # Errors
+///
+/// Returns an error if the directory can't be listed.
+pub async fn summarize(dir: &Path) -> TrackerResult<CacheSummary> {
+    let mut summary = CacheSummary {
+        dir: dir.to_path_buf(),
+        counters: read_counters(dir).await,
+        ..CacheSummary::default()
+    };
+    summary.hit_ratio = summary.counters.hit_ratio();
+    for filter_dir in filter_dirs(dir).await? {
+        for (_, bytes) in entries(&filter_dir).await? {
+            summary.entries += 1;
+            summary.bytes += bytes;
+        }
+    }
+    Ok(summary)
+}
+
+/// Deletes everything cached in `dir`, with the saved hit and miss counts,
+/// returning what was deleted.
+///
+/// Only the cache's own files are deleted, so other files in `dir` are kept.
+///
+/// # Errors
+///
+/// Returns an error if the directory can't be listed or a file deleted.
+pub async fn clear(dir: &Path) -> TrackerResult<CacheSummary> {
+    let summary = summarize(dir).await?;
+    for filter_dir in filter_dirs(dir).await? {
+        for (path, _) in entries(&filter_dir).await? {
+            tokio::fs::remove_file(&path)
+                .await
+                .map_err(|e| io_error("delete", &path, e))?;
+        }
+        // Left in place if something else was put in it
+        let _ = tokio::fs::remove_dir(&filter_dir).await;
+    }
+    let stats = dir.join(STATS_FILE);
+    match tokio::fs::remove_file(&stats).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(io_error("delete", &stats, e)),
+    }
+    Ok(summary)
+}
+
+/// Cache key of a filter: a hash of its addresses and topics, without the
+/// block range.
+///
+/// Addresses and topic alternatives are sorted first, since their order
+/// doesn't change which logs match.
+fn filter_key(filter: &Filter) -> String {
+    let mut value = serde_json::to_value(filter).unwrap_or(Value::Null);
+    if let Some(object) = value.as_object_mut() {
+        for range in ["fromBlock", "toBlock", "blockHash"] {
+            object.remove(range);
+        }
+        if let Some(addresses) = object.get_mut("address").and_then(Value::as_array_mut) {
+            addresses.sort_by_key(ToString::to_string);
+        }
+        if let Some(topics) = object.get_mut("topics").and_then(Value::as_array_mut) {
+            for alternatives in topics.iter_mut().filter_map(Value::as_array_mut) {
+                alternatives.sort_by_key(ToString::to_string);
+            }
+        }
+    }
+    let hash = keccak256(value.to_string().as_bytes());
+    alloy::hex::encode(&hash[..8])
+}
+
+/// Whether `name` is a directory name written by [`filter_key`].
+fn is_filter_key(name: &str) -> bool {
+    name.len() == 16 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Reads the saved hit and miss counts; missing or unreadable counts are
+/// zero.
+async fn read_counters(dir: &Path) -> CacheCounters {
+    tokio::fs::read(dir.join(STATS_FILE))
+        .await
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Lists the per-filter directories of the cache in `dir`.
+async fn filter_dirs(dir: &Path) -> TrackerResult<Vec<PathBuf>> {
+    let mut listing = match tokio::fs::read_dir(dir).await {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error("list", dir, e)),
+    };
+
+    let mut dirs = Vec::new();
+    while let Some(entry) = listing
+        .next_entry()
+        .await
+        .map_err(|e| io_error("list", dir, e))?
+    {
+        let is_dir = entry.file_type().await.is_ok_and(|t| t.is_dir());
+        if is_dir && entry.file_name().to_str().is_some_and(is_filter_key) {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+/// Lists the entry files of a filter directory as `(path, size)`.
+async fn entries(filter_dir: &Path) -> TrackerResult<Vec<(PathBuf, u64)>> {
+    let mut listing = tokio::fs::read_dir(filter_dir)
+        .await
+        .map_err(|e| io_error("list", filter_dir, e))?;
+
+    let mut entries = Vec::new();
+    while let Some(entry) = listing
+        .next_entry()
+        .await
+        .map_err(|e| io_error("list", filter_dir, e))?
+    {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == ENTRY_EXTENSION) {
+            let bytes = entry.metadata().await.map_or(0, |m| m.len());
+            entries.push((path, bytes));
+        }
+    }
+    Ok(entries)
+}
+
+/// Writes `contents` to `path` through a temporary file, so a crash never
+/// leaves a partial entry.
+async fn write_atomic(path: &Path, contents: &[u8]) -> TrackerResult<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| io_error("create", parent, e))?;
+    }
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, contents)
+        .await
+        .map_err(|e| io_error("write", &tmp, e))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .map_err(|e| io_error("write", path, e))
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> TrackerError {
+    TrackerError::state(
+        format!("Failed to {action} log cache {}", path.display()),
+        Some(Box::new(e)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256};
+
+    fn log(block: u64) -> Log {
+        Log {
+            block_number: Some(block),
+            ..Log::default()
+        }
+    }
+
+    fn filter() -> Filter {
+        Filter::new()
+            .address(address!("0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"))
+            .event_signature(vec![
+                b256!("1c411e9a96e071241c2f21f7726b17ae89e3cab4c78be50e062b03a9fffbbad1"),
+                b256!("d78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822"),
+            ])
+    }
+
+    #[tokio::test]
+    async fn test_stores_finalized_ranges_and_counts_hits() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = LogCache::new(dir.path()).with_finalized_block(199);
+        let filter = filter().from_block(100).to_block(199);
+
+        assert!(cache.get(&filter, 100, 199).await.is_none());
+        assert!(cache.put(&filter, 100, 199, &[log(150)]).await.unwrap());
+        let logs = cache.get(&filter, 100, 199).await.unwrap();
+        assert_eq!(logs[0].block_number, Some(150));
+
+        // Only the exact range is cached
+        assert!(cache.get(&filter, 100, 149).await.is_none());
+        // Unfinalized ranges are not stored
+        assert!(!cache.put(&filter, 200, 299, &[log(250)]).await.unwrap());
+        assert!(cache.get(&filter, 200, 299).await.is_none());
+
+        assert_eq!(cache.counters(), CacheCounters { hits: 1, misses: 3 });
+    }
+
+    #[test]
+    fn test_filter_key_ignores_block_range_and_topic_order() {
+        let signatures = |first, second| {
+            Filter::new()
+                .address(address!("0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"))
+                .event_signature(vec![first, second])
+        };
+        let sync = b256!("1c411e9a96e071241c2f21f7726b17ae89e3cab4c78be50e062b03a9fffbbad1");
+        let swap = b256!("d78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822");
+
+        let key = filter_key(&signatures(sync, swap).from_block(1).to_block(10));
+        assert_eq!(key, filter_key(&signatures(swap, sync).from_block(50)));
+        assert!(is_filter_key(&key));
+        assert_ne!(key, filter_key(&Filter::new().event_signature(sync)));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "keep me").unwrap();
+        let cache = LogCache::new(dir.path()).with_finalized_block(1_000);
+        cache.put(&filter(), 0, 9, &[log(5)]).await.unwrap();
+        cache.put(&filter(), 10, 19, &[]).await.unwrap();
+        cache.get(&filter(), 0, 9).await.unwrap();
+        cache.get(&filter(), 20, 29).await;
+        cache.save_counters().await.unwrap();
+        let totals = LogCache::new(dir.path()).save_counters().await.unwrap();
+        assert_eq!(totals, CacheCounters { hits: 1, misses: 1 });
+
+        let summary = summarize(dir.path()).await.unwrap();
+        assert_eq!(summary.entries, 2);
+        assert!(summary.bytes > 0);
+        assert_eq!(summary.hit_ratio, Some(0.5));
+
+        let cleared = clear(dir.path()).await.unwrap();
+        assert_eq!(cleared.entries, 2);
+        let summary = summarize(dir.path()).await.unwrap();
+        assert_eq!(summary.entries, 0);
+        assert_eq!(summary.counters, CacheCounters::default());
+        assert!(dir.path().join("notes.txt").exists());
+
+        // A missing directory is an empty cache
+        let missing = dir.path().join("missing");
+        assert_eq!(summarize(&missing).await.unwrap().entries, 0);
+        assert_eq!(clear(&missing).await.unwrap().entries, 0);
+    }
+}
@@ -9,7 +9,7 @@
 //!     ↓
 //! CLI Layer (src/cli.rs)
 //!     ↓
-//! 1. Config Layer (src/config.rs)    → Load environment variables
+//! 1. Config Layer (src/config/)      → Load environment variables and config files
 //! 2. RPC Layer (src/rpc.rs)          → Create Ethereum provider
 //! 3. Events Layer (src/events.rs)    → Fetch & decode Sync events
 //! 4. State Layer (src/state.rs)      → Update reserves & validate
@@ -25,7 +25,7 @@
 //!
 //! All errors bubble up with context via `TrackerResult<T>`.
 
-use eth_uniswap_alloy::{cli, observability};
+use eth_uniswap_alloy::{cli, config::Profile, observability};
 use tracing::error;
 
 /// Entry point for the Uniswap V2 event indexer.
@@ -33,7 +33,7 @@ use tracing::error;
 /// Initializes:
 /// - Tokio async runtime (via `#[tokio::main]`)
 /// - Production-grade structured logging with tracing
-/// - Environment-based filtering (RUST_LOG, LOG_JSON, LOG_FILE)
+/// - Environment-based filtering (RUST_LOG, LOG_JSON, LOG_FILE, PROFILE)
 ///
 /// Then delegates to the CLI module for all business logic.
 #[tokio::main]
@@ -41,19 +41,25 @@ async fn main() {
     // Initialize structured logging FIRST (before any other operations)
     // Configuration can be controlled via environment variables:
     // - RUST_LOG: Set log level (e.g., "debug", "info", "trace")
-    // - LOG_JSON: Enable JSON output for production ("true" or "false")
+    // - LOG_JSON: Enable JSON output for production ("true" or "false",
+    //   default: true for the staging and prod profiles)
     // - LOG_FILE: Write logs to file with daily rotation
     //
     // Examples:
     //   RUST_LOG=debug cargo run -- watch
     //   RUST_LOG=eth_uniswap_alloy=trace,sqlx=warn cargo run
     //   LOG_JSON=true LOG_FILE=./logs/indexer.log cargo run
+    // Load .env first so PROFILE and LOG_* can be set there too
+    dotenvy::dotenv().ok();
+
+    // An invalid PROFILE is reported when the config is loaded
+    let profile = Profile::from_env().unwrap_or_default();
     let log_level = std::env::var("RUST_LOG").ok();
     let log_file = std::env::var("LOG_FILE").ok().map(std::path::PathBuf::from);
     let json_output = std::env::var("LOG_JSON")
-        .unwrap_or_else(|_| "false".to_string())
-        .parse::<bool>()
-        .unwrap_or(false);
+        .ok()
+        .and_then(|s| s.parse::<bool>().ok())
+        .unwrap_or_else(|| profile.defaults().log_json);
 
     if let Err(e) = observability::init_tracing(log_level, log_file, json_output) {
         eprintln!("Failed to initialize tracing: {e}");
@@ -124,11 +124,13 @@ pub fn init_tracing(
         EnvFilter::new("eth_uniswap_alloy=info,warn")
     };
 
-    // Console layer (stdout)
+    // Console layer (stderr, keeping stdout for command output such as
+    // `--output json`)
     let console_layer = if json_output {
         // Production: JSON output for log aggregation (ELK, Datadog, etc.)
         fmt::layer()
             .json()
+            .with_writer(std::io::stderr)
             .with_current_span(true)
             .with_span_list(true)
             .with_target(true)
@@ -139,6 +141,7 @@ pub fn init_tracing(
         // Development: Human-readable colored output
         fmt::layer()
             .pretty()
+            .with_writer(std::io::stderr)
             .with_target(true)
             .with_file(true)
             .with_line_number(true)
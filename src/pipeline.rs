@@ -0,0 +1,1157 @@
+//! Producer/consumer pipeline for indexing a block range.
+//!
+//! Watch mode indexes new blocks through three stages connected by bounded
+//! channels:
+//!
+//! ```text
+//! fetch ──(decoded logs)──▶ price ──(records)──▶ write
+//! ```
+//!
+//! - **Fetch** requests the logs of each block batch, decodes them and builds
+//!   the event and price rows, which only depend on the event itself.
+//! - **Price** applies events to the in-memory [`State`] in order, adds the
+//!   smoothed prices and reports each price.
+//! - **Write** coalesces whatever record batches are queued into one write
+//!   through [`Storage`], committed together with the pool's advanced
+//!   [`Checkpoint`] (see [`Storage::write_batch`]).
+//!
+//! The stages run concurrently, so RPC requests for the next batches overlap
+//! with database writes for earlier ones. Each channel holds at most
+//! `capacity` batches: when the writer falls behind, the price stage and then
+//! the fetcher wait for it instead of buffering the whole range in memory.
+//! The first error in any stage stops the pipeline and restores `state` and
+//! the smoothed price to where they were before the run, so the whole range
+//! can be retried; rows already written are rewritten idempotently.
+//!
+//! Decoding and pricing are CPU-bound, so with
+//! [`Pipeline::with_decode_workers`] a large batch (backfills fetch thousands
+//! of logs per request) is split into chunks decoded on the blocking thread
+//! pool in parallel, then reassembled in log order for the price stage.
+//!
+//! With [`Pipeline::with_journal`], the fetcher appends each batch's logs to
+//! the write-ahead [`EventJournal`] before decoding them, so a crash before
+//! the write stage commits them loses nothing.
+//!
+//! With [`PriceMode::Block`], only the last price of each block is written;
+//! every Sync event is still stored in `sync_events`. A block's logs are
+//! always fetched in one batch, so the price stage drops the earlier prices of
+//! a block as later ones arrive.
+//!
+//! [`Pipeline::record_snapshot`] writes a price outside of the stages, from
+//! reserves read with `getReserves()` while the pool has no events.
+//! [`Pipeline::reprice_event`] and [`Pipeline::reprice_snapshot`] rebuild
+//! stored prices the same way, for [`crate::replay`].
+
+use alloy::primitives::{Address, B256, U256};
+use alloy::rpc::types::Log;
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::adapters::PriceAdapter;
+use crate::db::checkpoint::{Checkpoint, CheckpointStore};
+use crate::db::ids::{derive_record_id, RecordKind};
+use crate::db::models::{PoolRecord, PricePointRecord, SwapEventRecord, SyncEventRecord};
+use crate::db::storage::Storage;
+use crate::dedup::LogDeduplicator;
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::{decode_swap_event, decode_sync_event, is_swap_log, Sync};
+use crate::journal::EventJournal;
+use crate::pricing::{exact_price_to_f64, format_token_amount};
+use crate::smoothing::PriceEwma;
+use crate::state::State;
+
+/// Default number of batches each channel holds.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 4;
+
+/// Most record batches the writer coalesces into one write.
+const MAX_COALESCED_BATCHES: usize = 16;
+
+/// Fewest logs worth a decode task of their own; smaller batches are decoded
+/// inline.
+const MIN_LOGS_PER_DECODE_TASK: usize = 2_048;
+
+/// Which Sync events become price points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriceMode {
+    /// One price point per Sync event
+    #[default]
+    Event,
+    /// One price point per block, at its last Sync event
+    Block,
+}
+
+impl fmt::Display for PriceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Event => "event",
+            Self::Block => "block",
+        })
+    }
+}
+
+impl FromStr for PriceMode {
+    type Err = TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "event" => Ok(Self::Event),
+            "block" => Ok(Self::Block),
+            other => Err(TrackerError::config(
+                format!("Unknown price mode '{other}', expected event or block"),
+                None,
+            )),
+        }
+    }
+}
+
+/// A price computed by the price stage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceUpdate {
+    /// Block of the Sync event
+    pub block_number: u64,
+    /// Hash of that block
+    pub block_hash: B256,
+    /// Timestamp of that block
+    pub block_timestamp: u64,
+    /// Price of token0 in token1
+    pub price: f64,
+    /// Raw token0 reserve
+    pub reserve0: U256,
+    /// Raw token1 reserve
+    pub reserve1: U256,
+}
+
+/// Sync log decoded by the fetch stage.
+struct DecodedLog {
+    event: Sync,
+    block_number: u64,
+    block_hash: B256,
+    block_timestamp: u64,
+    /// Row of the event itself
+    record: SyncEventRecord,
+    /// Price row without the smoothed price, or why the reserves can't be
+    /// priced (reported after the state rejects invalid reserves)
+    price_point: TrackerResult<PricePointRecord>,
+}
+
+/// What decode tasks need to build rows.
+#[derive(Clone)]
+struct RecordContext {
+    pool_id: i64,
+    pool_address: Address,
+    chain_id: u64,
+    decimals: (u8, u8),
+    adapter: Arc<dyn PriceAdapter>,
+}
+
+/// Logs of one batch decoded by the fetch stage.
+#[derive(Default)]
+struct FetchedBatch {
+    syncs: Vec<DecodedLog>,
+    swaps: Vec<SwapEventRecord>,
+}
+
+/// Rows built by the price stage from one fetched batch.
+#[derive(Default)]
+struct RecordBatch {
+    events: Vec<SyncEventRecord>,
+    prices: Vec<PricePointRecord>,
+    swaps: Vec<SwapEventRecord>,
+    /// Last event's block and block hash
+    last_block: Option<(u64, B256)>,
+}
+
+/// Indexes block ranges of one pool through the fetch, price and write
+/// stages.
+pub struct Pipeline<'a> {
+    storage: &'a dyn Storage,
+    pool: &'a PoolRecord,
+    context: RecordContext,
+    capacity: usize,
+    decode_workers: usize,
+    advance_state: bool,
+    dedup: Option<&'a LogDeduplicator>,
+    journal: Option<&'a EventJournal>,
+    price_mode: PriceMode,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Creates a pipeline writing `pool`'s events to `storage`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token decimals or pool type are invalid.
+    pub fn new(
+        storage: &'a dyn Storage,
+        pool: &'a PoolRecord,
+        chain_id: u64,
+    ) -> TrackerResult<Self> {
+        let pool_address = pool.address.get();
+        let decimals = |d: i32| {
+            u8::try_from(d).map_err(|e| {
+                TrackerError::decoding(format!("Invalid token decimals: {d}"), Some(Box::new(e)))
+            })
+        };
+
+        Ok(Self {
+            storage,
+            pool,
+            context: RecordContext {
+                pool_id: pool.id,
+                pool_address,
+                chain_id,
+                decimals: (
+                    decimals(pool.token0_decimals)?,
+                    decimals(pool.token1_decimals)?,
+                ),
+                adapter: Arc::from(pool.price_adapter()?),
+            },
+            capacity: DEFAULT_CHANNEL_CAPACITY,
+            decode_workers: 1,
+            advance_state: true,
+            dedup: None,
+            journal: None,
+            price_mode: PriceMode::Event,
+        })
+    }
+
+    /// Returns the address of the indexed pair.
+    #[must_use]
+    pub const fn pool_address(&self) -> Address {
+        self.context.pool_address
+    }
+
+    /// Sets how many batches each channel holds before the upstream stage
+    /// waits (at least 1).
+    #[must_use]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Sets how many blocking tasks decode a large batch in parallel (at
+    /// least 1; the default decodes every batch inline).
+    #[must_use]
+    pub fn with_decode_workers(mut self, workers: usize) -> Self {
+        self.decode_workers = workers.max(1);
+        self
+    }
+
+    /// Drops fetched logs `dedup` has already admitted, so a log fetched
+    /// twice is only priced once (see [`crate::dedup`]).
+    #[must_use]
+    pub const fn with_dedup(mut self, dedup: &'a LogDeduplicator) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
+    /// Appends every fetched batch to `journal` before it is decoded (see
+    /// [`crate::journal`]).
+    #[must_use]
+    pub const fn with_journal(mut self, journal: &'a EventJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Sets which Sync events become price points.
+    #[must_use]
+    pub const fn with_price_mode(mut self, price_mode: PriceMode) -> Self {
+        self.price_mode = price_mode;
+        self
+    }
+
+    /// Returns which Sync events become price points.
+    #[must_use]
+    pub const fn price_mode(&self) -> PriceMode {
+        self.price_mode
+    }
+
+    /// Writes rows without advancing the stored checkpoint; the caller
+    /// commits progress itself (see [`crate::backfill`]).
+    #[must_use]
+    pub const fn without_state_updates(mut self) -> Self {
+        self.advance_state = false;
+        self
+    }
+
+    /// Indexes the `(from_block, to_block)` batches in order.
+    ///
+    /// `fetch` returns the Sync (and optionally Swap) logs of one batch.
+    /// `state` and `price_ewma`
+    /// are advanced by every event, and `on_price` is called with each price
+    /// once it is computed (before it is written). Returns the number of
+    /// events indexed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from fetching, decoding, pricing or writing.
+    /// `state` and `price_ewma` are then left as they were before the call.
+    pub async fn run<F, Fut>(
+        &self,
+        batches: impl IntoIterator<Item = (u64, u64)>,
+        mut fetch: F,
+        state: &mut State,
+        price_ewma: &mut Option<PriceEwma>,
+        mut on_price: impl FnMut(&PriceUpdate),
+    ) -> TrackerResult<usize>
+    where
+        F: FnMut(u64, u64) -> Fut,
+        Fut: Future<Output = TrackerResult<Vec<Log>>>,
+    {
+        let snapshot = (state.clone(), price_ewma.clone());
+        let admitted = Mutex::new(Vec::new());
+        let (log_tx, mut log_rx) = mpsc::channel::<FetchedBatch>(self.capacity);
+        let (record_tx, mut record_rx) = mpsc::channel::<RecordBatch>(self.capacity);
+
+        let admitted_keys = &admitted;
+        let fetcher = async move {
+            for (from_block, to_block) in batches {
+                debug!("Fetching batch: blocks {} to {}", from_block, to_block);
+                let mut logs = fetch(from_block, to_block).await?;
+                if let Some(journal) = self.journal {
+                    journal
+                        .append(self.pool.id, from_block, to_block, &logs)
+                        .await?;
+                }
+                if let Some(dedup) = self.dedup {
+                    let (fresh, keys) = dedup.admit(logs);
+                    admitted_keys
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .extend(keys);
+                    logs = fresh;
+                }
+                let decoded = self.decode(logs).await?;
+
+                // A closed channel means a later stage failed; its error wins
+                if !(decoded.syncs.is_empty() && decoded.swaps.is_empty())
+                    && log_tx.send(decoded).await.is_err()
+                {
+                    break;
+                }
+            }
+            Ok::<_, TrackerError>(())
+        };
+
+        let (priced_state, smoothing) = (&mut *state, &mut *price_ewma);
+        let price_mode = self.price_mode;
+        let pricer = async move {
+            let mut indexed = 0;
+            while let Some(fetched) = log_rx.recv().await {
+                let mut batch = RecordBatch {
+                    swaps: fetched.swaps,
+                    ..RecordBatch::default()
+                };
+                for decoded in fetched.syncs {
+                    let update =
+                        Self::record(decoded, priced_state, smoothing, price_mode, &mut batch)?;
+                    on_price(&update);
+                    indexed += 1;
+                }
+                if record_tx.send(batch).await.is_err() {
+                    break;
+                }
+            }
+            Ok::<_, TrackerError>(indexed)
+        };
+
+        let writer = async move {
+            let mut checkpoint = if self.advance_state {
+                self.storage.load(self.pool.id).await?.unwrap_or_default()
+            } else {
+                Checkpoint::default()
+            };
+
+            while let Some(mut batch) = record_rx.recv().await {
+                // Fold in whatever queued up while the last write ran
+                for _ in 1..MAX_COALESCED_BATCHES {
+                    let Ok(next) = record_rx.try_recv() else {
+                        break;
+                    };
+                    batch.events.extend(next.events);
+                    batch.prices.extend(next.prices);
+                    batch.swaps.extend(next.swaps);
+                    batch.last_block = next.last_block.or(batch.last_block);
+                }
+
+                // Every Swap comes with a Sync in the same transaction, so a
+                // batch with swaps always has a last block
+                let Some((block_number, block_hash)) = batch.last_block else {
+                    continue;
+                };
+                let count = batch.events.len();
+                debug!(events = count, block_number, "Writing batch");
+
+                // The checkpoint is committed with the rows it covers
+                let mut next = checkpoint.at(block_number, Some(block_hash));
+                next.events_processed += count as u64;
+                let state = self.advance_state.then(|| next.to_state(self.pool.id));
+                self.storage
+                    .write_batch(batch.events, batch.prices, batch.swaps, state.as_ref())
+                    .await?;
+                checkpoint = next;
+            }
+            Ok::<_, TrackerError>(())
+        };
+
+        match tokio::try_join!(fetcher, pricer, writer) {
+            Ok(((), indexed, ())) => Ok(indexed),
+            Err(e) => {
+                (*state, *price_ewma) = snapshot;
+                if let Some(dedup) = self.dedup {
+                    dedup.forget(
+                        &admitted
+                            .into_inner()
+                            .unwrap_or_else(PoisonError::into_inner),
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Decodes a fetched batch, in parallel chunks if it is large enough.
+    async fn decode(&self, logs: Vec<Log>) -> TrackerResult<FetchedBatch> {
+        let tasks = self
+            .decode_workers
+            .min(logs.len() / MIN_LOGS_PER_DECODE_TASK);
+        if tasks <= 1 {
+            return decode_logs(&self.context, logs);
+        }
+
+        let chunk_len = logs.len().div_ceil(tasks);
+        let mut logs = logs.into_iter();
+        let handles: Vec<_> = (0..tasks)
+            .map(|_| {
+                let chunk: Vec<Log> = logs.by_ref().take(chunk_len).collect();
+                let context = self.context.clone();
+                tokio::task::spawn_blocking(move || decode_logs(&context, chunk))
+            })
+            .collect();
+
+        // Chunks are consecutive, so appending them keeps log order
+        let mut decoded = FetchedBatch::default();
+        for handle in handles {
+            let chunk = handle.await.map_err(|e| {
+                TrackerError::state("Log decoding task failed", Some(Box::new(e)))
+            })??;
+            decoded.syncs.extend(chunk.syncs);
+            decoded.swaps.extend(chunk.swaps);
+        }
+        Ok(decoded)
+    }
+
+    /// Applies one event to `state` and appends its rows to `batch`.
+    fn record(
+        decoded: DecodedLog,
+        state: &mut State,
+        price_ewma: &mut Option<PriceEwma>,
+        price_mode: PriceMode,
+        batch: &mut RecordBatch,
+    ) -> TrackerResult<PriceUpdate> {
+        let DecodedLog {
+            event,
+            block_number,
+            block_hash,
+            block_timestamp,
+            record,
+            price_point,
+        } = decoded;
+
+        state.update_from_sync_event(&event, block_number)?;
+        let (reserve0, reserve1) = state.get_reserves();
+        let price_point = smooth(price_point?, price_ewma);
+        let price = price_point.price;
+
+        batch.events.push(record);
+        if price_mode == PriceMode::Block {
+            drop_block_price(&mut batch.prices, price_point.block_number);
+        }
+        batch.prices.push(price_point);
+        batch.last_block = Some((block_number, block_hash));
+
+        Ok(PriceUpdate {
+            block_number,
+            block_hash,
+            block_timestamp,
+            price,
+            reserve0,
+            reserve1,
+        })
+    }
+
+    /// Writes the price at reserves read with `getReserves()` at a block in
+    /// which the pool had no Sync event.
+    ///
+    /// The row is flagged as `source = call` and has an all-zero transaction
+    /// hash. `price_ewma` is advanced like for an event; the indexer state,
+    /// which follows Sync events only, is left alone.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reserves can't be priced or the write fails.
+    pub async fn record_snapshot(
+        &self,
+        block_number: u64,
+        block_hash: B256,
+        block_timestamp: u64,
+        (reserve0, reserve1): (U256, U256),
+        price_ewma: &mut Option<PriceEwma>,
+    ) -> TrackerResult<PriceUpdate> {
+        let price_point = self
+            .context
+            .price_point(
+                block_number,
+                block_timestamp,
+                B256::ZERO,
+                (reserve0, reserve1),
+            )?
+            .with_event_id(self.context.record_id(
+                RecordKind::PricePoint,
+                block_hash,
+                B256::ZERO,
+                0,
+            ))
+            .with_call_source();
+        let before = price_ewma.clone();
+        let price_point = smooth(price_point, price_ewma);
+        let price = price_point.price;
+
+        if let Err(e) = self.storage.insert_price_points(vec![price_point]).await {
+            *price_ewma = before;
+            return Err(e);
+        }
+
+        Ok(PriceUpdate {
+            block_number,
+            block_hash,
+            block_timestamp,
+            price,
+            reserve0,
+            reserve1,
+        })
+    }
+}
+
+impl Pipeline<'_> {
+    /// Rebuilds the price point of a stored Sync event with the pool's
+    /// current adapter, advancing `price_ewma` like the price stage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored hashes or reserves are malformed or the
+    /// reserves can't be priced.
+    pub fn reprice_event(
+        &self,
+        event: &SyncEventRecord,
+        price_ewma: &mut Option<PriceEwma>,
+    ) -> TrackerResult<PricePointRecord> {
+        let block_hash = parse_stored(&event.block_hash, "block hash")?;
+        let tx_hash = event.tx_hash.get();
+        let log_index = u32::try_from(event.log_index).unwrap_or(u32::MAX);
+        let price_point = self
+            .context
+            .price_point(
+                u64::try_from(event.block_number).unwrap_or_default(),
+                u64::try_from(event.block_timestamp).unwrap_or_default(),
+                tx_hash,
+                (
+                    parse_stored(&event.reserve0, "reserve0")?,
+                    parse_stored(&event.reserve1, "reserve1")?,
+                ),
+            )?
+            .with_event_id(self.context.record_id(
+                RecordKind::PricePoint,
+                block_hash,
+                tx_hash,
+                log_index,
+            ));
+
+        let mut price_point = smooth(price_point, price_ewma);
+        price_point.is_confirmed = event.is_confirmed;
+        Ok(price_point)
+    }
+
+    /// Rebuilds a stored `getReserves()` snapshot (see
+    /// [`record_snapshot`](Self::record_snapshot)) from its raw reserves,
+    /// advancing `price_ewma` like the price stage. The row keeps its ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored reserves are malformed or can't be
+    /// priced.
+    pub fn reprice_snapshot(
+        &self,
+        snapshot: &PricePointRecord,
+        price_ewma: &mut Option<PriceEwma>,
+    ) -> TrackerResult<PricePointRecord> {
+        let mut price_point = self
+            .context
+            .price_point(
+                u64::try_from(snapshot.block_number).unwrap_or_default(),
+                u64::try_from(snapshot.block_timestamp).unwrap_or_default(),
+                B256::ZERO,
+                (
+                    parse_stored(&snapshot.reserve0_raw, "reserve0")?,
+                    parse_stored(&snapshot.reserve1_raw, "reserve1")?,
+                ),
+            )?
+            .with_call_source();
+        price_point.event_id.clone_from(&snapshot.event_id);
+
+        let mut price_point = smooth(price_point, price_ewma);
+        price_point.is_confirmed = snapshot.is_confirmed;
+        Ok(price_point)
+    }
+}
+
+/// Parses a hash or amount stored as text.
+/// Drops the last of `prices` if it is from `block_number`, before the
+/// block's next price is appended in [`PriceMode::Block`].
+pub(crate) fn drop_block_price(prices: &mut Vec<PricePointRecord>, block_number: i64) {
+    if prices
+        .last()
+        .is_some_and(|last| last.block_number == block_number)
+    {
+        prices.pop();
+    }
+}
+
+fn parse_stored<T: std::str::FromStr>(value: &str, what: &str) -> TrackerResult<T>
+where
+    T::Err: std::error::Error + Send + std::marker::Sync + 'static,
+{
+    value.parse().map_err(|e| {
+        TrackerError::decoding(format!("Invalid stored {what}: {value}"), Some(Box::new(e)))
+    })
+}
+
+impl RecordContext {
+    /// Derives a stable ID, so re-indexing produces the same identifiers.
+    fn record_id(
+        &self,
+        kind: RecordKind,
+        block_hash: B256,
+        tx_hash: B256,
+        log_index: u32,
+    ) -> String {
+        derive_record_id(
+            kind,
+            self.chain_id,
+            self.pool_address,
+            block_hash,
+            tx_hash,
+            log_index,
+        )
+    }
+
+    /// Prices `reserves` and builds the price point row, without the
+    /// smoothed price.
+    fn price_point(
+        &self,
+        block_number: u64,
+        block_timestamp: u64,
+        tx_hash: B256,
+        (reserve0, reserve1): (U256, U256),
+    ) -> TrackerResult<PricePointRecord> {
+        let (decimals0, decimals1) = self.decimals;
+        let price_exact = self
+            .adapter
+            .price_exact(reserve0, reserve1, decimals0, decimals1)?;
+        let price = exact_price_to_f64(price_exact);
+        let human = |amount: U256, decimals: u8| {
+            format_token_amount(amount, decimals)
+                .parse::<f64>()
+                .unwrap_or(0.0)
+        };
+
+        Ok(PricePointRecord::new(
+            self.pool_id,
+            block_number,
+            block_timestamp,
+            tx_hash,
+            price,
+            reserve0,
+            reserve1,
+            human(reserve0, decimals0),
+            human(reserve1, decimals1),
+            true, // Past the confirmation depth
+        )
+        .with_price_exact(price_exact))
+    }
+}
+
+/// Advances `price_ewma` with a price and attaches the smoothed value.
+fn smooth(price_point: PricePointRecord, price_ewma: &mut Option<PriceEwma>) -> PricePointRecord {
+    let smoothed = price_ewma.as_mut().map(|ewma| {
+        let block_number = u64::try_from(price_point.block_number).unwrap_or(0);
+        ewma.update(block_number, price_point.block_timestamp, price_point.price)
+    });
+    price_point.with_price_ewma(smoothed)
+}
+
+/// Decodes `logs` and builds their event rows, keeping log order.
+fn decode_logs(context: &RecordContext, logs: Vec<Log>) -> TrackerResult<FetchedBatch> {
+    let mut decoded = FetchedBatch::default();
+    for log in logs {
+        let block_timestamp = log.block_timestamp.unwrap_or(0);
+        let tx_hash = log.transaction_hash.unwrap_or_default();
+        let log_index = u32::try_from(log.log_index.unwrap_or(0)).unwrap_or(u32::MAX);
+
+        if is_swap_log(&log) {
+            let (swap, block_number) = decode_swap_event(&log)?;
+            decoded.swaps.push(SwapEventRecord::new(
+                context.pool_id,
+                block_number,
+                block_timestamp,
+                tx_hash,
+                log_index,
+                &swap,
+                true, // Past the confirmation depth
+            ));
+            continue;
+        }
+
+        let (event, block_number) = decode_sync_event(&log)?;
+        let block_hash = log.block_hash.unwrap_or_default();
+        let reserves = (U256::from(event.reserve0), U256::from(event.reserve1));
+        let record = SyncEventRecord::new(
+            context.pool_id,
+            block_number,
+            block_hash,
+            block_timestamp,
+            tx_hash,
+            log_index,
+            reserves.0,
+            reserves.1,
+            true, // Past the confirmation depth
+        )
+        .with_event_id(context.record_id(
+            RecordKind::SyncEvent,
+            block_hash,
+            tx_hash,
+            log_index,
+        ));
+        let price_point = context
+            .price_point(block_number, block_timestamp, tx_hash, reserves)
+            .map(|p| {
+                p.with_event_id(context.record_id(
+                    RecordKind::PricePoint,
+                    block_hash,
+                    tx_hash,
+                    log_index,
+                ))
+            });
+
+        decoded.syncs.push(DecodedLog {
+            event,
+            block_number,
+            block_hash,
+            block_timestamp,
+            record,
+            price_point,
+        });
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::db::models::BlockRange;
+    use crate::db::repository::Repository;
+    use alloy::primitives::aliases::U112;
+    use alloy::sol_types::SolEvent;
+
+    fn sync_log(pool_address: Address, block: u64, usdt: u64) -> Log {
+        let sync = Sync {
+            reserve0: U112::from(1_000u64) * U112::from(10u64).pow(U112::from(18u64)),
+            reserve1: U112::from(usdt) * U112::from(10u64).pow(U112::from(6u64)),
+        };
+        Log {
+            inner: alloy::primitives::Log {
+                address: pool_address,
+                data: sync.encode_log_data(),
+            },
+            block_hash: Some(B256::with_last_byte(u8::try_from(block % 256).unwrap())),
+            block_number: Some(block),
+            block_timestamp: Some(1_700_000_000 + block),
+            transaction_hash: Some(B256::from(U256::from(block))),
+            transaction_index: Some(0),
+            log_index: Some(0),
+            removed: false,
+        }
+    }
+
+    /// A swap buying `weth` WETH for `usdt` USDT, logged after `sync_log`.
+    fn swap_log(pool_address: Address, block: u64, trader: Address, weth: u64, usdt: u64) -> Log {
+        let swap = crate::events::Swap {
+            sender: Address::repeat_byte(0x77),
+            amount0In: U256::ZERO,
+            amount1In: U256::from(usdt) * U256::from(10u64).pow(U256::from(6u64)),
+            amount0Out: U256::from(weth) * U256::from(10u64).pow(U256::from(18u64)),
+            amount1Out: U256::ZERO,
+            to: trader,
+        };
+        let mut log = sync_log(pool_address, block, 0);
+        log.inner.data = swap.encode_log_data();
+        log.log_index = Some(1);
+        log
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_writes_swap_events() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+        let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0));
+
+        let mut state = State::new();
+        let indexed = Pipeline::new(&repo, &pool, 1)
+            .unwrap()
+            .run(
+                [(100, 109)],
+                |_, _| {
+                    let logs = vec![
+                        sync_log(pool_address, 101, 2_000_000),
+                        swap_log(pool_address, 101, alice, 1, 2_000),
+                        sync_log(pool_address, 102, 2_001_000),
+                        swap_log(pool_address, 102, bob, 1, 3_000),
+                        sync_log(pool_address, 103, 2_003_000),
+                        swap_log(pool_address, 103, alice, 1, 2_000),
+                    ];
+                    async move { Ok(logs) }
+                },
+                &mut state,
+                &mut None,
+                |_| {},
+            )
+            .await
+            .unwrap();
+
+        // Swaps are stored but don't count as price events
+        assert_eq!(indexed, 3);
+        let traders = repo
+            .get_trader_totals(pool_id, 0, BlockRange::ALL, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(traders.len(), 2);
+        assert_eq!(traders[0].address, format!("{alice:?}"));
+        assert_eq!(traders[0].swaps, 2);
+        assert!((traders[0].amount1_in - 4_000e6).abs() < 1.0);
+
+        let alice_only = format!("{alice:?}");
+        let filtered = repo
+            .get_trader_totals(pool_id, 0, BlockRange::ALL, Some(&alice_only), 10)
+            .await
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+
+        let daily = repo
+            .get_daily_trader_counts(pool_id, 0, BlockRange::ALL)
+            .await
+            .unwrap();
+        assert_eq!(daily.len(), 1);
+        assert_eq!((daily[0].unique_traders, daily[0].swaps), (2, 3));
+
+        // A block range keeps only the swaps of blocks 102 and 103
+        let blocks = BlockRange::new(Some(102), None).unwrap();
+        let ranged = repo
+            .get_trader_totals(pool_id, 0, blocks, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            ranged.iter().map(|t| t.swaps).collect::<Vec<_>>(),
+            vec![1, 1]
+        );
+        let daily = repo
+            .get_daily_trader_counts(pool_id, 0, blocks)
+            .await
+            .unwrap();
+        assert_eq!((daily[0].unique_traders, daily[0].swaps), (2, 2));
+
+        let window = repo.get_fee_window(pool_id, 0).await.unwrap();
+        assert_eq!(window.swaps, 3);
+        assert!((window.amount1_in - 7_000e6).abs() < 1.0);
+        assert!(window.avg_liquidity.unwrap() > 0.0);
+
+        // Swaps past a reorg point stop counting
+        repo.invalidate_from_block(pool_id, 103).await.unwrap();
+        let traders = repo
+            .get_trader_totals(pool_id, 0, BlockRange::ALL, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(traders.iter().map(|t| t.swaps).sum::<i64>(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_prices_duplicate_logs_once() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+        let dedup = LogDeduplicator::default();
+        let pipeline = Pipeline::new(&repo, &pool, 1).unwrap().with_dedup(&dedup);
+        let mut state = State::new();
+        let mut updates = Vec::new();
+
+        // The second pass refetches block 101 (e.g. after a hybrid-mode poll)
+        for (from, to) in [(100, 101), (101, 102)] {
+            pipeline
+                .run(
+                    [(from, to)],
+                    |from, to| {
+                        let logs = (from..=to)
+                            .map(|block| sync_log(pool_address, block, 2_000_000))
+                            .collect();
+                        async move { Ok(logs) }
+                    },
+                    &mut state,
+                    &mut None,
+                    |update| updates.push(update.block_number),
+                )
+                .await
+                .unwrap();
+        }
+        assert_eq!(updates, vec![100, 101, 102]);
+        assert_eq!(dedup.stats().duplicates_dropped, 1);
+
+        // A failed run forgets what it admitted, so the retry prices it
+        let result = pipeline
+            .run(
+                [(103, 103), (104, 104)],
+                |from, _| {
+                    let logs = vec![sync_log(pool_address, from, 2_000_000)];
+                    async move {
+                        if from == 104 {
+                            Err(TrackerError::rpc("unavailable", None))
+                        } else {
+                            Ok(logs)
+                        }
+                    }
+                },
+                &mut state,
+                &mut None,
+                |_| {},
+            )
+            .await;
+        assert!(result.is_err());
+        let (admitted, _) = dedup.admit(vec![sync_log(pool_address, 103, 2_000_000)]);
+        assert_eq!(admitted.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_indexes_batches_in_order() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        let mut state = State::new();
+        let mut ewma = Some(PriceEwma::new(60));
+        let mut updates = Vec::new();
+
+        // Capacity 1 makes every stage wait on the next one
+        let pipeline = Pipeline::new(&repo, &pool, 1).unwrap().with_capacity(1);
+        let indexed = pipeline
+            .run(
+                [(100, 109), (110, 119), (120, 129)],
+                |from, _to| {
+                    let logs = if from == 110 {
+                        Vec::new()
+                    } else {
+                        vec![
+                            sync_log(pool_address, from + 1, 2_000_000),
+                            sync_log(pool_address, from + 5, 2_100_000),
+                        ]
+                    };
+                    async move { Ok(logs) }
+                },
+                &mut state,
+                &mut ewma,
+                |update| updates.push(update.block_number),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(indexed, 4);
+        assert_eq!(updates, vec![101, 105, 121, 125]);
+        assert_eq!(state.get_last_block(), 125);
+
+        let stored = repo.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(stored.last_indexed_block, 125);
+        assert_eq!(stored.total_events_processed, 4);
+        assert_eq!(
+            repo.get_indexed_blocks(pool_id, 100, 129).await.unwrap(),
+            vec![101, 105, 121, 125]
+        );
+        let latest = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+        assert!((latest.price - 2_100.0).abs() < 1e-9);
+        assert!(latest.price_ewma.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_block_price_mode_keeps_last_price_per_block() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        let pipeline = Pipeline::new(&repo, &pool, 1)
+            .unwrap()
+            .with_price_mode(PriceMode::Block);
+        let mut updates = Vec::new();
+        let indexed = pipeline
+            .run(
+                [(100, 101)],
+                |_, _| {
+                    let mut second = sync_log(pool_address, 100, 2_100_000);
+                    second.log_index = Some(1);
+                    let logs = vec![
+                        sync_log(pool_address, 100, 2_000_000),
+                        second,
+                        sync_log(pool_address, 101, 2_200_000),
+                    ];
+                    async move { Ok(logs) }
+                },
+                &mut State::new(),
+                &mut None,
+                |update| updates.push(update.block_number),
+            )
+            .await
+            .unwrap();
+
+        // Every event is indexed and reported, but only blocks get prices
+        assert_eq!(indexed, 3);
+        assert_eq!(updates, vec![100, 100, 101]);
+        let stored = repo.get_state(pool_id).await.unwrap().unwrap();
+        assert_eq!(stored.total_events_processed, 3);
+        let prices: Vec<_> = repo
+            .get_recent_prices(pool_id, 10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| (p.block_number, p.price.round()))
+            .collect();
+        assert_eq!(prices, vec![(101, 2_200.0), (100, 2_100.0)]);
+        assert_eq!("block".parse::<PriceMode>().unwrap(), PriceMode::Block);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_stops_on_fetch_error() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+
+        let mut state = State::new();
+        let mut ewma = Some(PriceEwma::new(60));
+        let result = Pipeline::new(&repo, &pool, 1)
+            .unwrap()
+            .run(
+                [(100, 109), (110, 119)],
+                |from, _to| {
+                    let result = if from == 100 {
+                        Ok(vec![sync_log(pool_address, 101, 2_000_000)])
+                    } else {
+                        Err(TrackerError::rpc("rate limited", None))
+                    };
+                    async move { result }
+                },
+                &mut state,
+                &mut ewma,
+                |_| {},
+            )
+            .await;
+
+        assert!(result.is_err_and(|e| e.to_string().contains("rate limited")));
+
+        // Block 101 was priced, but the failed run is rolled back so the
+        // range can be retried from the start
+        assert_eq!(state.get_last_block(), 0);
+        assert!(ewma.unwrap().value().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parallel_decoding_keeps_log_order() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        let pool_address: Address = pool.address.get();
+        let logs: Vec<Log> = (1..=3 * MIN_LOGS_PER_DECODE_TASK as u64)
+            .flat_map(|block| {
+                [
+                    sync_log(pool_address, block, 2_000_000 + block),
+                    swap_log(pool_address, block, Address::repeat_byte(0xa1), 1, 2_000),
+                ]
+            })
+            .collect();
+
+        let serial = Pipeline::new(&repo, &pool, 1)
+            .unwrap()
+            .decode(logs.clone())
+            .await
+            .unwrap();
+        let parallel = Pipeline::new(&repo, &pool, 1)
+            .unwrap()
+            .with_decode_workers(4)
+            .decode(logs)
+            .await
+            .unwrap();
+
+        let ids = |batch: &FetchedBatch| -> Vec<String> {
+            batch
+                .syncs
+                .iter()
+                .map(|s| {
+                    let price_id = s.price_point.as_ref().unwrap().event_id.clone();
+                    s.record.event_id.clone().unwrap() + &price_id.unwrap()
+                })
+                .collect()
+        };
+        assert_eq!(parallel.syncs.len(), 3 * MIN_LOGS_PER_DECODE_TASK);
+        assert_eq!(ids(&parallel), ids(&serial));
+        let blocks: Vec<i64> = parallel.swaps.iter().map(|s| s.block_number).collect();
+        assert!(blocks.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(blocks.len(), serial.swaps.len());
+    }
+
+    #[tokio::test]
+    async fn test_record_snapshot_writes_call_price() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+
+        let reserves = (
+            U256::from(1_000u64) * U256::from(10u64).pow(U256::from(18u64)),
+            U256::from(2_000_000u64) * U256::from(10u64).pow(U256::from(6u64)),
+        );
+        let mut ewma = Some(PriceEwma::new(60));
+        let update = Pipeline::new(&repo, &pool, 1)
+            .unwrap()
+            .record_snapshot(
+                200,
+                B256::repeat_byte(0x20),
+                1_700_000_200,
+                reserves,
+                &mut ewma,
+            )
+            .await
+            .unwrap();
+        assert!((update.price - 2_000.0).abs() < 1e-9);
+        assert!(ewma.unwrap().value().is_some());
+
+        let latest = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+        assert_eq!(latest.block_number, 200);
+        assert_eq!(latest.source, "call");
+        assert_eq!(latest.tx_hash.get(), B256::ZERO);
+        assert!(latest.event_id.is_some());
+        assert_eq!(latest.price_exact.as_deref(), Some("2000"));
+    }
+}
@@ -0,0 +1,279 @@
+//! Bulk pool registration.
+//!
+//! `pools add --file pools.csv` registers every pool listed in a CSV file,
+//! one per line:
+//!
+//! ```text
+//! address,name,protocol,pool_type,quote_direction,start_block
+//! 0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852,WETH/USDT,,,,10093341
+//! 0x06da0fd433C1A5d7a4faa01111c044910A184553,,sushiswap,,token0_per_token1
+//! ```
+//!
+//! Only the address is required. The header line, blank lines and lines
+//! starting with `#` are skipped. The pair's tokens, their symbols and
+//! decimals are read from the chain; a missing name defaults to
+//! `SYMBOL0/SYMBOL1`, and missing protocol, pool type and quote direction to
+//! the same defaults as `watch`. A start block (e.g. the pair's creation
+//! block) is where indexing begins while the pool has no checkpoint.
+//!
+//! Registering an address that is already in the `pools` table leaves its
+//! tokens alone and only applies the columns given in the file, so a file can
+//! be re-run after editing it.
+
+use alloy::primitives::Address;
+use tracing::{info, warn};
+
+use crate::adapters::PoolType;
+use crate::db::repository::Repository;
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::{fetch_pair_tokens, fetch_token_decimals, fetch_token_symbol};
+use crate::pricing::QuoteDirection;
+use crate::protocol::DexProtocol;
+use crate::rpc::Provider;
+
+/// Columns of a pools file, in order.
+pub const POOLS_CSV_COLUMNS: [&str; 6] = [
+    "address",
+    "name",
+    "protocol",
+    "pool_type",
+    "quote_direction",
+    "start_block",
+];
+
+/// One pool listed in a pools file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSpec {
+    /// Pair contract address
+    pub address: Address,
+    /// Display name (default: `SYMBOL0/SYMBOL1`)
+    pub name: Option<String>,
+    /// Protocol that deployed the pair
+    pub protocol: Option<DexProtocol>,
+    /// Pricing formula of the pair
+    pub pool_type: Option<PoolType>,
+    /// Direction prices are reported in
+    pub quote_direction: Option<QuoteDirection>,
+    /// First block to index while the pool has no checkpoint
+    pub start_block: Option<u64>,
+}
+
+/// An ERC20 token of a pair, as read from the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// Token contract address
+    pub address: Address,
+    /// Token symbol, or a shortened address if `symbol()` isn't a string
+    pub symbol: String,
+    /// Token decimals
+    pub decimals: u8,
+}
+
+/// Outcome of registering one pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registration {
+    /// The pool was added with this ID
+    Added(i64),
+    /// The pool was already registered with this ID
+    Existing(i64),
+}
+
+impl Registration {
+    /// Returns the pool's database ID.
+    #[must_use]
+    pub const fn pool_id(self) -> i64 {
+        match self {
+            Self::Added(id) | Self::Existing(id) => id,
+        }
+    }
+}
+
+/// Parses a pools file.
+///
+/// # Errors
+///
+/// Returns an error naming the line of the first malformed entry.
+pub fn parse_pools_csv(text: &str) -> TrackerResult<Vec<PoolSpec>> {
+    let mut specs = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if index == 0 && fields.first() == Some(&POOLS_CSV_COLUMNS[0]) {
+            continue;
+        }
+        let line_error = |message: String| {
+            TrackerError::config(format!("pools file line {}: {message}", index + 1), None)
+        };
+        if fields.len() > POOLS_CSV_COLUMNS.len() {
+            return Err(line_error(format!(
+                "expected at most {} columns ({}), got {}",
+                POOLS_CSV_COLUMNS.len(),
+                POOLS_CSV_COLUMNS.join(","),
+                fields.len()
+            )));
+        }
+        let field = |i: usize| fields.get(i).copied().filter(|f| !f.is_empty());
+
+        let address = field(0)
+            .ok_or_else(|| line_error("missing pool address".to_string()))?
+            .parse::<Address>()
+            .map_err(|e| line_error(format!("invalid pool address: {e}")))?;
+        let protocol = field(2)
+            .map(str::parse::<DexProtocol>)
+            .transpose()
+            .map_err(|e| line_error(e.to_string()))?;
+        let pool_type = field(3)
+            .map(str::parse::<PoolType>)
+            .transpose()
+            .map_err(|e| line_error(e.to_string()))?;
+        let quote_direction = field(4)
+            .map(str::parse::<QuoteDirection>)
+            .transpose()
+            .map_err(|e| line_error(e.to_string()))?;
+        let start_block = field(5)
+            .map(str::parse::<u64>)
+            .transpose()
+            .map_err(|e| line_error(format!("invalid start block: {e}")))?;
+
+        specs.push(PoolSpec {
+            address,
+            name: field(1).map(str::to_string),
+            protocol,
+            pool_type,
+            quote_direction,
+            start_block,
+        });
+    }
+    Ok(specs)
+}
+
+/// Reads a pair's tokens and their metadata from the chain.
+///
+/// # Errors
+///
+/// Returns an error if the pair's tokens or their decimals can't be read.
+pub async fn detect_tokens(
+    provider: &Provider,
+    pair: Address,
+) -> TrackerResult<(TokenInfo, TokenInfo)> {
+    let (token0, token1) = fetch_pair_tokens(provider, pair).await?;
+    Ok((
+        token_info(provider, token0).await?,
+        token_info(provider, token1).await?,
+    ))
+}
+
+async fn token_info(provider: &Provider, address: Address) -> TrackerResult<TokenInfo> {
+    let decimals = fetch_token_decimals(provider, address).await?;
+    let symbol = match fetch_token_symbol(provider, address).await {
+        Ok(symbol) if !symbol.trim().is_empty() => symbol.trim().to_string(),
+        Ok(_) | Err(_) => {
+            let hex = format!("{address:?}");
+            warn!("Token {} has no string symbol, using its address", address);
+            hex[..10].to_string()
+        }
+    };
+    Ok(TokenInfo {
+        address,
+        symbol,
+        decimals,
+    })
+}
+
+/// Registers a pool, detecting its tokens if it is new, and applies the
+/// protocol, pool type, quote direction and start block given in `spec`.
+///
+/// # Errors
+///
+/// Returns an error if the tokens can't be read or a write fails.
+pub async fn register_pool(
+    repository: &Repository,
+    provider: &Provider,
+    spec: &PoolSpec,
+) -> TrackerResult<Registration> {
+    let registration = if let Some(pool) = repository.get_pool_by_address(spec.address).await? {
+        Registration::Existing(pool.id)
+    } else {
+        let (token0, token1) = detect_tokens(provider, spec.address).await?;
+        let name = spec
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{}/{}", token0.symbol, token1.symbol));
+        let pool_id = repository
+            .ensure_pool_exists(
+                spec.address,
+                Some(name.clone()),
+                token0.address,
+                Some(token0.symbol),
+                token0.decimals,
+                token1.address,
+                Some(token1.symbol),
+                token1.decimals,
+            )
+            .await?;
+        info!(pool_id, name = %name, address = %spec.address, "Registered pool");
+        Registration::Added(pool_id)
+    };
+
+    let pool_id = registration.pool_id();
+    if let Some(protocol) = spec.protocol {
+        repository.set_pool_protocol(pool_id, protocol).await?;
+    }
+    if let Some(pool_type) = spec.pool_type {
+        repository.set_pool_type(pool_id, pool_type).await?;
+    }
+    if let Some(direction) = spec.quote_direction {
+        repository
+            .set_pool_quote_direction(pool_id, direction)
+            .await?;
+    }
+    if let Some(block) = spec.start_block {
+        repository
+            .set_pool_start_block(pool_id, Some(block))
+            .await?;
+    }
+    Ok(registration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pools_csv() {
+        let text = "\
+address,name,protocol,pool_type,quote_direction,start_block
+# mainnet pairs
+0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852,WETH/USDT,,,
+
+0x06da0fd433C1A5d7a4faa01111c044910A184553, ,sushiswap,,token0_per_token1,10794229
+0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc
+";
+        let specs = parse_pools_csv(text).unwrap();
+        assert_eq!(specs.len(), 3);
+        assert_eq!(specs[0].name.as_deref(), Some("WETH/USDT"));
+        assert_eq!(specs[0].protocol, None);
+        assert_eq!(specs[1].name, None);
+        assert_eq!(specs[1].protocol, Some(DexProtocol::SushiSwap));
+        assert_eq!(
+            specs[1].quote_direction,
+            Some(QuoteDirection::Token0PerToken1)
+        );
+        assert_eq!(specs[1].start_block, Some(10_794_229));
+        assert_eq!(specs[2].pool_type, None);
+        assert_eq!(specs[2].start_block, None);
+
+        let err = parse_pools_csv("0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852\n0x1234\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("line 2"), "{err}");
+        assert!(parse_pools_csv("0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852,a,curve").is_err());
+        assert!(parse_pools_csv("0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852,a,,,,-1").is_err());
+        assert!(
+            parse_pools_csv("0x0d4a11d5EEaaC28EC3F61d100daF4d40471f1852,a,,,,1,extra").is_err()
+        );
+    }
+}
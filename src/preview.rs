@@ -0,0 +1,180 @@
+//! Provisional prices from unconfirmed blocks.
+//!
+//! Watch mode stays `CONFIRMATION_DEPTH` blocks behind the chain head, so indexed
+//! prices lag the chain by design. With `PRICE_PREVIEW` set, the API server
+//! also calls `getReserves()` on every tracked pool at the `latest` block (the
+//! head, which may still be reorged out) or `pending` block (the node's view
+//! of the next block, mempool included) every [`PREVIEW_INTERVAL`], and
+//! broadcasts changed prices to the WebSocket stream as `price_preview`
+//! messages with `is_confirmed: false`.
+//!
+//! Previews are never stored; clients pick them with
+//! `/api/v1/stream/{pool}?channel=preview` (or `all`), so subscribers of the
+//! default `confirmed` channel only ever see indexed prices.
+
+use alloy::primitives::U256;
+use alloy::rpc::types::BlockNumberOrTag;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::api::models::{PriceStreamMessage, ReservesInfo};
+use crate::app_state::AppState;
+use crate::db::models::PoolRow;
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::fetch_reserves_at_tag;
+use crate::pricing::{exact_price_to_f64, format_token_amount};
+use crate::rpc::{get_latest_block, Provider};
+
+/// Interval between preview price reads.
+pub const PREVIEW_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Unconfirmed block previews are read at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewBlock {
+    /// The chain head, before any confirmations
+    Latest,
+    /// The block the node would build next, from its mempool
+    Pending,
+}
+
+impl PreviewBlock {
+    /// Returns the block tag to call `getReserves()` at.
+    #[must_use]
+    pub const fn tag(self) -> BlockNumberOrTag {
+        match self {
+            Self::Latest => BlockNumberOrTag::Latest,
+            Self::Pending => BlockNumberOrTag::Pending,
+        }
+    }
+
+    /// Returns the number of the previewed block, given the chain head.
+    #[must_use]
+    pub const fn block_number(self, head: u64) -> u64 {
+        match self {
+            Self::Latest => head,
+            Self::Pending => head.saturating_add(1),
+        }
+    }
+}
+
+impl fmt::Display for PreviewBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Latest => "latest",
+            Self::Pending => "pending",
+        })
+    }
+}
+
+impl FromStr for PreviewBlock {
+    type Err = TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "latest" => Ok(Self::Latest),
+            "pending" => Ok(Self::Pending),
+            other => Err(TrackerError::config(
+                format!("Unknown preview block '{other}', expected latest or pending"),
+                None,
+            )),
+        }
+    }
+}
+
+/// Spawns a task broadcasting preview prices of every tracked pool.
+#[must_use]
+pub fn spawn(state: AppState, provider: Arc<Provider>, block: PreviewBlock) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(PREVIEW_INTERVAL);
+        let mut last_sent: HashMap<i64, (U256, U256)> = HashMap::new();
+        loop {
+            ticker.tick().await;
+            let pools = match state.reader.get_all_pools().await {
+                Ok(pools) => pools,
+                Err(e) => {
+                    warn!(error = %e, "Price preview could not list pools");
+                    continue;
+                }
+            };
+            let head = match get_latest_block(&provider).await {
+                Ok(head) => head,
+                Err(e) => {
+                    debug!(error = %e, "Price preview could not read the chain head");
+                    continue;
+                }
+            };
+
+            for pool in pools {
+                match preview(&provider, &pool, block, head).await {
+                    Ok((reserves, msg)) => {
+                        // Only changes are worth a message
+                        if last_sent.insert(pool.id, reserves) != Some(reserves) {
+                            state.broadcast_price_update(msg);
+                        }
+                    }
+                    Err(e) => debug!(pool_id = pool.id, error = %e, "Price preview failed"),
+                }
+            }
+        }
+    })
+}
+
+/// Reads and prices one pool's reserves at the preview block.
+async fn preview(
+    provider: &Provider,
+    pool: &PoolRow,
+    block: PreviewBlock,
+    head: u64,
+) -> TrackerResult<((U256, U256), PriceStreamMessage)> {
+    let (reserve0, reserve1) =
+        fetch_reserves_at_tag(provider, pool.address.get(), block.tag()).await?;
+
+    let decimals0 = u8::try_from(pool.token0_decimals).unwrap_or(18);
+    let decimals1 = u8::try_from(pool.token1_decimals).unwrap_or(18);
+    let price = exact_price_to_f64(
+        pool.price_adapter()?
+            .price_exact(reserve0, reserve1, decimals0, decimals1)?,
+    );
+    let human = |raw: U256, decimals: u8| format_token_amount(raw, decimals).parse().unwrap_or(0.0);
+
+    let msg = PriceStreamMessage {
+        event_type: "price_preview".to_string(),
+        pool: pool
+            .name
+            .clone()
+            .unwrap_or_else(|| pool.address.to_string()),
+        price: pool.quote_direction().apply(price),
+        price_ewma: None,
+        block_number: block.block_number(head),
+        timestamp: chrono::Utc::now(),
+        reserves: ReservesInfo {
+            weth: human(reserve0, decimals0),
+            usdt: human(reserve1, decimals1),
+        },
+        is_confirmed: false,
+    };
+    Ok(((reserve0, reserve1), msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_block() {
+        for block in [PreviewBlock::Latest, PreviewBlock::Pending] {
+            assert_eq!(block.to_string().parse::<PreviewBlock>().unwrap(), block);
+        }
+        assert!("safe".parse::<PreviewBlock>().is_err());
+        assert_eq!(PreviewBlock::Latest.block_number(100), 100);
+        assert_eq!(PreviewBlock::Pending.block_number(100), 101);
+        assert_eq!(PreviewBlock::Pending.tag(), BlockNumberOrTag::Pending);
+    }
+}
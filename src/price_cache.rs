@@ -0,0 +1,259 @@
+//! In-memory cache of the latest price per pool.
+//!
+//! `/price/current`, `/price/latest` and the stats endpoint need a pool's
+//! latest price, and the first two its 24h change, on every request. The API
+//! server's price poll loop refreshes both for every pool as the indexer
+//! writes new prices, so requests are answered from memory. The database is
+//! only queried for a pool without a fresh entry: right after startup, or
+//! when the poll loop has not refreshed it within [`PRICE_CACHE_TTL`].
+//!
+//! Hits and misses are counted and reported by `/api/v1/health`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::PricePointRow;
+use crate::db::repository::Repository;
+use crate::error::TrackerResult;
+
+/// How long an entry is served without being refreshed; several poll loop
+/// ticks, so entries only expire when the loop stalls.
+pub const PRICE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// A pool's latest price and 24h change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPrice {
+    /// Latest price point
+    pub latest: PricePointRow,
+    /// Change over the last 24 hours in percent, if it could be computed
+    pub change_24h: Option<f64>,
+}
+
+impl CachedPrice {
+    /// Reads a pool's latest price and 24h change from the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the latest price cannot be read. A failed 24h
+    /// change query only leaves `change_24h` empty.
+    pub async fn load(repository: &Repository, pool_id: i64) -> TrackerResult<Option<Self>> {
+        let Some(latest) = repository.get_latest_price(pool_id).await? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            latest,
+            change_24h: repository.get_24h_price_change(pool_id).await.ok(),
+        }))
+    }
+}
+
+/// Cache hit and miss counts since startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PriceCacheStats {
+    /// Lookups answered from memory
+    pub hits: u64,
+    /// Lookups that went to the database
+    pub misses: u64,
+    /// Pools currently cached
+    pub entries: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    price: CachedPrice,
+    updated_at: Instant,
+}
+
+/// Latest price per pool, refreshed by the price poll loop.
+#[derive(Debug)]
+pub struct PriceCache {
+    entries: RwLock<HashMap<i64, Entry>>,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for PriceCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceCache {
+    /// Creates an empty cache with entries valid for [`PRICE_CACHE_TTL`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_ttl(PRICE_CACHE_TTL)
+    }
+
+    /// Creates an empty cache with entries valid for `ttl`.
+    #[must_use]
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns a pool's cached price if it is fresh, counting a hit or miss.
+    #[must_use]
+    pub fn get(&self, pool_id: i64) -> Option<CachedPrice> {
+        let price = self.entries.read().ok().and_then(|entries| {
+            entries
+                .get(&pool_id)
+                .filter(|entry| entry.updated_at.elapsed() <= self.ttl)
+                .map(|entry| entry.price.clone())
+        });
+
+        let counter = if price.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        price
+    }
+
+    /// Stores a pool's price.
+    pub fn insert(&self, pool_id: i64, price: CachedPrice) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(
+                pool_id,
+                Entry {
+                    price,
+                    updated_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    /// Reloads a pool's price from the database and caches it.
+    ///
+    /// Returns `None`, and drops any cached entry, if the pool has no
+    /// prices.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the latest price cannot be read. A failed 24h
+    /// change query only leaves `change_24h` empty.
+    pub async fn refresh(
+        &self,
+        repository: &Repository,
+        pool_id: i64,
+    ) -> TrackerResult<Option<CachedPrice>> {
+        let Some(price) = CachedPrice::load(repository, pool_id).await? else {
+            if let Ok(mut entries) = self.entries.write() {
+                entries.remove(&pool_id);
+            }
+            return Ok(None);
+        };
+
+        self.insert(pool_id, price.clone());
+        Ok(Some(price))
+    }
+
+    /// Returns a pool's price from the cache, loading it on a miss.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a miss cannot be loaded from the database.
+    pub async fn get_or_load(
+        &self,
+        repository: &Repository,
+        pool_id: i64,
+    ) -> TrackerResult<Option<CachedPrice>> {
+        match self.get(pool_id) {
+            Some(price) => Ok(Some(price)),
+            None => self.refresh(repository, pool_id).await,
+        }
+    }
+
+    /// Returns hit and miss counts since startup.
+    #[must_use]
+    pub fn stats(&self) -> PriceCacheStats {
+        PriceCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.read().map_or(0, |entries| entries.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations};
+    use alloy::primitives::{FixedBytes, U256};
+
+    async fn repository_with_price(price: f64) -> (Repository, i64) {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        let repo = Repository::new(pool);
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        insert_price(&repo, pool_id, 19_000_000, price).await;
+        (repo, pool_id)
+    }
+
+    async fn insert_price(repo: &Repository, pool_id: i64, block: u64, price: f64) {
+        repo.insert_price_point(
+            pool_id,
+            block,
+            1_706_745_600,
+            FixedBytes::from([2u8; 32]),
+            price,
+            U256::from(1_000_000_000_u64),
+            U256::from(500_000_000_000_000_000_u64),
+            1000.0,
+            0.5,
+            true,
+            &format!("price-{block}"),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_misses_load_once_then_hit() {
+        let (repo, pool_id) = repository_with_price(3500.0).await;
+        let cache = PriceCache::new();
+
+        let price = cache.get_or_load(&repo, pool_id).await.unwrap().unwrap();
+        assert_eq!(price.latest.price, 3500.0);
+
+        // Served from memory until refreshed, even though the database moved on
+        insert_price(&repo, pool_id, 19_000_001, 3600.0).await;
+        let price = cache.get_or_load(&repo, pool_id).await.unwrap().unwrap();
+        assert_eq!(price.latest.price, 3500.0);
+
+        let price = cache.refresh(&repo, pool_id).await.unwrap().unwrap();
+        assert_eq!(price.latest.price, 3600.0);
+
+        assert_eq!(
+            cache.stats(),
+            PriceCacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+        assert!(cache.get_or_load(&repo, 99).await.unwrap().is_none());
+        assert_eq!(cache.stats().misses, 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_reloaded() {
+        let (repo, pool_id) = repository_with_price(3500.0).await;
+        let cache = PriceCache::with_ttl(Duration::ZERO);
+
+        cache.refresh(&repo, pool_id).await.unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(cache.get(pool_id).is_none());
+        assert_eq!(cache.stats().hits, 0);
+    }
+}
@@ -0,0 +1,505 @@
+//! Webhook push of every confirmed price.
+//!
+//! Alerts post when a rule matches; price webhooks post every confirmed
+//! price of every pool. When `PRICE_WEBHOOK_URLS` is set, the API server runs
+//! one [`PriceWebhooks`] task per URL that checks for new confirmed prices
+//! every [`PRICE_WEBHOOK_CHECK_INTERVAL`] and posts each as a [`PricePush`],
+//! oldest first. Pools are followed from their latest price when the task
+//! first sees them; earlier history isn't pushed.
+//!
+//! Each delivery is signed with HMAC-SHA256 keyed by `PRICE_WEBHOOK_SECRET`
+//! over `{timestamp}.{body}`, sent as:
+//!
+//! - [`TIMESTAMP_HEADER`]: unix seconds of the attempt, for rejecting replays
+//! - [`SIGNATURE_HEADER`]: `sha256=<hex HMAC>`
+//!
+//! A failed delivery is retried with exponential backoff, up to
+//! `PRICE_WEBHOOK_MAX_ATTEMPTS` attempts; the URL's later prices wait, so
+//! each receiver sees its prices in order. A delivery that fails every
+//! attempt is written to the `price_webhook_dead_letters` table, from which
+//! `webhooks replay` redelivers it. Deliveries are at least once: receivers
+//! should de-duplicate on `event_id`.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::price_webhooks::sign;
+//!
+//! let signature = sign(b"whsec_test", 1_700_000_000, r#"{"price":1.0}"#);
+//! assert!(signature.starts_with("sha256="));
+//! ```
+
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::alerts::webhook::DELIVERY_TIMEOUT;
+use crate::config::secrets::redact_url;
+use crate::db::models::{PoolRow, PricePointRow, PriceWebhookDeadLetterRecord};
+use crate::db::repository::Repository;
+use crate::error::{TrackerError, TrackerResult};
+
+/// Interval between checks for new confirmed prices.
+pub const PRICE_WEBHOOK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default number of attempts per delivery.
+pub const DEFAULT_PRICE_WEBHOOK_ATTEMPTS: u32 = 5;
+
+/// Default delay before the first retry; each further retry waits twice as
+/// long.
+pub const DEFAULT_PRICE_WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Request header carrying the unix timestamp the signature covers.
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+
+/// Request header carrying the HMAC-SHA256 signature.
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// Dead letters replayed per database query.
+const REPLAY_PAGE: i64 = 500;
+
+/// JSON body posted for each confirmed price.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricePush {
+    /// Always `price_confirmed`
+    pub event: String,
+    /// Deterministic price point ID, stable across retries
+    pub event_id: Option<String>,
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Pool name (its address if unnamed)
+    pub pool: String,
+    /// Price, in the pool's quote direction
+    pub price: f64,
+    /// EWMA-smoothed price (absent when smoothing is disabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_ewma: Option<f64>,
+    /// Block number
+    pub block_number: u64,
+    /// Block timestamp
+    pub timestamp: DateTime<Utc>,
+    /// Transaction hash
+    pub tx_hash: String,
+    /// Human-readable reserve0
+    pub reserve0: f64,
+    /// Human-readable reserve1
+    pub reserve1: f64,
+}
+
+impl PricePush {
+    /// Creates the push of `pool`'s confirmed price `price`.
+    #[must_use]
+    pub fn new(pool: &PoolRow, price: &PricePointRow) -> Self {
+        let direction = pool.quote_direction();
+        Self {
+            event: "price_confirmed".to_string(),
+            event_id: price.event_id.clone(),
+            pool_id: pool.id,
+            pool: pool
+                .name
+                .clone()
+                .unwrap_or_else(|| pool.address.to_string()),
+            price: direction.apply(price.price),
+            price_ewma: price.price_ewma.map(|ewma| direction.apply(ewma)),
+            block_number: u64::try_from(price.block_number).unwrap_or(0),
+            timestamp: DateTime::from_timestamp(price.block_timestamp, 0).unwrap_or_else(Utc::now),
+            tx_hash: price.tx_hash.to_string(),
+            reserve0: price.reserve0_human,
+            reserve1: price.reserve1_human,
+        }
+    }
+}
+
+/// Returns the [`SIGNATURE_HEADER`] value for `body` sent at `timestamp`.
+#[must_use]
+pub fn sign(secret: &[u8], timestamp: i64, body: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    signature(&key, timestamp, body)
+}
+
+fn signature(key: &hmac::Key, timestamp: i64, body: &str) -> String {
+    let tag = hmac::sign(key, format!("{timestamp}.{body}").as_bytes());
+    format!("sha256={}", alloy::hex::encode(tag.as_ref()))
+}
+
+/// Identifies a webhook URL in dead letters without storing it.
+#[must_use]
+pub fn destination_key(url: &str) -> String {
+    alloy::hex::encode(&alloy::primitives::keccak256(url.as_bytes())[..8])
+}
+
+/// Outcome of [`PriceWebhooks::replay`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ReplayReport {
+    /// Dead letters delivered and deleted
+    pub delivered: u64,
+    /// Dead letters that failed again and were kept
+    pub failed: u64,
+    /// Dead letters for URLs no longer configured, kept
+    pub skipped: u64,
+}
+
+/// Posts confirmed prices to webhook URLs.
+#[derive(Clone)]
+pub struct PriceWebhooks {
+    urls: Vec<String>,
+    key: hmac::Key,
+    client: reqwest::Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl fmt::Debug for PriceWebhooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // URLs often embed secrets
+        let urls: Vec<String> = self.urls.iter().map(|url| redact_url(url)).collect();
+        f.debug_struct("PriceWebhooks")
+            .field("urls", &urls)
+            .field("max_attempts", &self.max_attempts)
+            .field("retry_delay", &self.retry_delay)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PriceWebhooks {
+    /// Creates webhooks posting to `urls`, signed with `secret`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be constructed.
+    pub fn new(urls: Vec<String>, secret: &str) -> TrackerResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .map_err(|e| {
+                TrackerError::config("Failed to build webhook HTTP client", Some(Box::new(e)))
+            })?;
+
+        Ok(Self {
+            urls,
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()),
+            client,
+            max_attempts: DEFAULT_PRICE_WEBHOOK_ATTEMPTS,
+            retry_delay: DEFAULT_PRICE_WEBHOOK_RETRY_DELAY,
+        })
+    }
+
+    /// Makes up to `max_attempts` attempts per delivery (at least 1),
+    /// waiting `retry_delay` before the first retry and doubling it after.
+    #[must_use]
+    pub fn with_retry(mut self, max_attempts: u32, retry_delay: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Returns the configured URLs.
+    #[must_use]
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Spawns one task per URL that pushes new confirmed prices every
+    /// [`PRICE_WEBHOOK_CHECK_INTERVAL`].
+    #[must_use]
+    pub fn spawn(self, repository: Repository) -> Vec<JoinHandle<()>> {
+        self.urls
+            .iter()
+            .map(|url| {
+                let (webhooks, repository, url) = (self.clone(), repository.clone(), url.clone());
+                tokio::spawn(async move { webhooks.follow(&repository, &url).await })
+            })
+            .collect()
+    }
+
+    /// Pushes every new confirmed price to `url`; never returns.
+    async fn follow(&self, repository: &Repository, url: &str) {
+        let mut last_blocks: HashMap<i64, i64> = HashMap::new();
+        let mut ticker = tokio::time::interval(PRICE_WEBHOOK_CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let pools = match repository.get_all_pools().await {
+                Ok(pools) => pools,
+                Err(e) => {
+                    warn!(error = %e, "Price webhook could not read pools");
+                    continue;
+                }
+            };
+
+            for pool in &pools {
+                let last_block = match last_blocks.get(&pool.id) {
+                    Some(&block) => block,
+                    None => match repository.get_latest_price(pool.id).await {
+                        Ok(latest) => {
+                            let block = latest.map_or(0, |p| p.block_number);
+                            last_blocks.insert(pool.id, block);
+                            block
+                        }
+                        Err(e) => {
+                            warn!(pool_id = pool.id, error = %e, "Price webhook could not start");
+                            continue;
+                        }
+                    },
+                };
+                let prices = match repository
+                    .get_confirmed_prices_after(pool.id, last_block, 0)
+                    .await
+                {
+                    Ok(prices) => prices,
+                    Err(e) => {
+                        warn!(pool_id = pool.id, error = %e, "Price webhook could not read new prices");
+                        continue;
+                    }
+                };
+                for price in &prices {
+                    self.push(repository, url, pool, price).await;
+                    last_blocks.insert(pool.id, price.block_number);
+                }
+            }
+        }
+    }
+
+    /// Delivers one price to `url`, dead-lettering it if every attempt
+    /// fails.
+    pub async fn push(
+        &self,
+        repository: &Repository,
+        url: &str,
+        pool: &PoolRow,
+        price: &PricePointRow,
+    ) {
+        let body = match serde_json::to_string(&PricePush::new(pool, price)) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!(pool_id = pool.id, error = %e, "Failed to encode a price push");
+                return;
+            }
+        };
+
+        let (attempts, result) = self.deliver(url, &body).await;
+        let Err(e) = result else {
+            return;
+        };
+        warn!(
+            destination = %redact_url(url),
+            pool_id = pool.id,
+            block = price.block_number,
+            attempts,
+            error = %e,
+            "Price webhook delivery failed, dead-lettering it"
+        );
+        let letter = PriceWebhookDeadLetterRecord {
+            destination: redact_url(url),
+            destination_key: destination_key(url),
+            pool_id: pool.id,
+            block_number: price.block_number,
+            payload: body,
+            attempts: i64::from(attempts),
+            error: e.to_string(),
+            created_at: Utc::now().timestamp(),
+        };
+        if let Err(e) = repository.insert_price_webhook_dead_letter(&letter).await {
+            warn!(error = %e, "Failed to record a price webhook dead letter");
+        }
+    }
+
+    /// Redelivers dead letters to the configured URLs they were meant for,
+    /// deleting those that get through.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dead letters cannot be read or deleted.
+    pub async fn replay(&self, repository: &Repository) -> TrackerResult<ReplayReport> {
+        let urls: HashMap<String, &str> = self
+            .urls
+            .iter()
+            .map(|url| (destination_key(url), url.as_str()))
+            .collect();
+
+        let mut report = ReplayReport::default();
+        let mut after_id = 0;
+        loop {
+            let letters = repository
+                .get_price_webhook_dead_letters(after_id, REPLAY_PAGE)
+                .await?;
+            if letters.is_empty() {
+                return Ok(report);
+            }
+            for letter in letters {
+                after_id = letter.id;
+                let Some(url) = urls.get(&letter.destination_key) else {
+                    report.skipped += 1;
+                    continue;
+                };
+                match self.deliver(url, &letter.payload).await {
+                    (_, Ok(())) => {
+                        repository
+                            .delete_price_webhook_dead_letter(letter.id)
+                            .await?;
+                        report.delivered += 1;
+                    }
+                    (attempts, Err(e)) => {
+                        debug!(id = letter.id, attempts, error = %e, "Dead letter replay failed");
+                        report.failed += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Posts `body` to `url` until it gets through or the attempts run out,
+    /// returning the attempts made and the outcome.
+    async fn deliver(&self, url: &str, body: &str) -> (u32, TrackerResult<()>) {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match self.post(url, body).await {
+                Ok(()) => return (attempts, Ok(())),
+                Err(e) if attempts < self.max_attempts => {
+                    let delay = self.retry_delay * 2u32.saturating_pow(attempts - 1);
+                    debug!(
+                        destination = %redact_url(url),
+                        attempts,
+                        error = %e,
+                        "Price webhook delivery failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return (attempts, Err(e)),
+            }
+        }
+    }
+
+    /// Makes one signed delivery attempt.
+    async fn post(&self, url: &str, body: &str) -> TrackerResult<()> {
+        let timestamp = Utc::now().timestamp();
+        self.client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(SIGNATURE_HEADER, signature(&self.key, timestamp, body))
+            .body(body.to_string())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| {
+                TrackerError::rpc(
+                    format!("Price webhook delivery to {} failed", redact_url(url)),
+                    Some(Box::new(e)),
+                )
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::pricing::QuoteDirection;
+    use alloy::primitives::B256;
+
+    fn price(block_number: i64) -> PricePointRow {
+        PricePointRow {
+            event_id: Some(format!("price-{block_number}")),
+            block_number,
+            block_timestamp: 1_706_745_600,
+            tx_hash: B256::repeat_byte(0xab).into(),
+            price: 2_000.0,
+            price_exact: Some("2000".to_string()),
+            price_ewma: None,
+            source: "event".to_string(),
+            reserve0_human: 1_000.0,
+            reserve1_human: 2_000_000.0,
+        }
+    }
+
+    #[test]
+    fn test_sign_matches_reference_hmac() {
+        // python3 -c 'import hmac, hashlib; print(hmac.new(b"whsec_test",
+        //   b"1700000000.{\"price\":1.0}", hashlib.sha256).hexdigest())'
+        assert_eq!(
+            sign(b"whsec_test", 1_700_000_000, r#"{"price":1.0}"#),
+            "sha256=b902d288e2cceb63ec971bf2738d87696b6947f5ce54a4bc832c72e12e68712e"
+        );
+        // The timestamp is covered
+        assert_ne!(
+            sign(b"whsec_test", 1_700_000_001, r#"{"price":1.0}"#),
+            sign(b"whsec_test", 1_700_000_000, r#"{"price":1.0}"#)
+        );
+    }
+
+    #[test]
+    fn test_destination_key_hides_url() {
+        let url = "https://hooks.example.com/prices?token=secret";
+        let key = destination_key(url);
+        assert_eq!(key.len(), 16);
+        assert_eq!(key, destination_key(url));
+        assert_ne!(key, destination_key("https://hooks.example.com/prices"));
+        assert!(!key.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_push_dead_letters_after_retries_and_replays() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repository.ensure_default_pool().await.unwrap();
+        repository
+            .set_pool_quote_direction(pool_id, QuoteDirection::Token0PerToken1)
+            .await
+            .unwrap();
+        let pool = repository
+            .get_all_pools()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|pool| pool.id == pool_id)
+            .unwrap();
+
+        // Nothing listens on the discard port
+        let url = "http://127.0.0.1:9/prices?token=secret";
+        let webhooks = PriceWebhooks::new(vec![url.to_string()], "whsec_test")
+            .unwrap()
+            .with_retry(2, Duration::ZERO);
+        webhooks.push(&repository, url, &pool, &price(100)).await;
+
+        let letters = repository
+            .get_price_webhook_dead_letters(0, 10)
+            .await
+            .unwrap();
+        assert_eq!(letters.len(), 1);
+        let letter = &letters[0];
+        assert_eq!(letter.attempts, 2);
+        assert_eq!(letter.block_number, 100);
+        assert_eq!(letter.destination_key, destination_key(url));
+        assert!(!letter.destination.contains("secret"));
+        let push: PricePush = serde_json::from_str(&letter.payload).unwrap();
+        assert_eq!(push.event_id.as_deref(), Some("price-100"));
+        assert!((push.price - 0.0005).abs() < 1e-12);
+
+        // Still failing: kept; for an unknown URL: skipped
+        let report = webhooks.replay(&repository).await.unwrap();
+        assert_eq!(
+            report,
+            ReplayReport {
+                delivered: 0,
+                failed: 1,
+                skipped: 0
+            }
+        );
+        let other = PriceWebhooks::new(vec!["http://127.0.0.1:9/other".to_string()], "k").unwrap();
+        assert_eq!(other.replay(&repository).await.unwrap().skipped, 1);
+        assert_eq!(
+            repository
+                .get_price_webhook_dead_letters(0, 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Impermanent loss of constant-product LP positions.
+//!
+//! A constant-product pool keeps `reserve0 × reserve1 = k`, so a position's
+//! liquidity `L = √(amount0 × amount1)` fixes what it holds at any price `P`
+//! (token0 in token1), fees aside:
+//!
+//! ```text
+//! amount0 = L / √P
+//! amount1 = L × √P
+//! ```
+//!
+//! Compared with holding the deposited tokens, the position is worth
+//!
+//! ```text
+//! value / hold_value = 2√r / (1 + r),  where r = P_now / P_entry
+//! ```
+//!
+//! which is below 1 for any price move in either direction. The shortfall is
+//! the impermanent loss. Fees earned by the position aren't included; see
+//! [`crate::analytics::fees`] for those.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::pricing::impermanent_loss::LpPosition;
+//!
+//! // 1 WETH + 2,000 USDT deposited at 2,000; WETH doubles
+//! let position = LpPosition::deposit(2_000.0, Some(1.0), None).unwrap();
+//! let now = position.value_at(4_000.0).unwrap();
+//! assert!((now.impermanent_loss + 0.0572).abs() < 1e-4);
+//! ```
+
+use crate::error::{TrackerError, TrackerResult};
+
+/// An LP position in a constant-product pool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LpPosition {
+    /// Price when the position was opened (token0 in token1)
+    pub entry_price: f64,
+    /// Token0 deposited
+    pub amount0: f64,
+    /// Token1 deposited
+    pub amount1: f64,
+}
+
+/// An [`LpPosition`] valued at a later price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionValue {
+    /// Current price divided by the entry price
+    pub price_ratio: f64,
+    /// Token0 the position holds now
+    pub amount0: f64,
+    /// Token1 the position holds now
+    pub amount1: f64,
+    /// Position value in token1
+    pub value: f64,
+    /// Value in token1 of the deposited tokens, had they been held
+    pub hold_value: f64,
+    /// `value / hold_value - 1`: zero or negative
+    pub impermanent_loss: f64,
+}
+
+impl LpPosition {
+    /// Creates a position opened at `entry_price` by depositing `amount0` of
+    /// token0 or `amount1` of token1.
+    ///
+    /// The other side is matched at the entry price, as the pool requires.
+    /// Without either amount, the position holds one token0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the price or amount isn't positive, or both
+    /// amounts are given.
+    pub fn deposit(
+        entry_price: f64,
+        amount0: Option<f64>,
+        amount1: Option<f64>,
+    ) -> TrackerResult<Self> {
+        check_positive("entry price", entry_price)?;
+        let (amount0, amount1) = match (amount0, amount1) {
+            (Some(_), Some(_)) => return Err(TrackerError::math(
+                "Give amount0 or amount1, not both: the other side is matched at the entry price",
+                None,
+            )),
+            (Some(amount0), None) => (amount0, amount0 * entry_price),
+            (None, Some(amount1)) => (amount1 / entry_price, amount1),
+            (None, None) => (1.0, entry_price),
+        };
+        check_positive("amount", amount0)?;
+        check_positive("amount", amount1)?;
+
+        Ok(Self {
+            entry_price,
+            amount0,
+            amount1,
+        })
+    }
+
+    /// Returns the position's liquidity `√(amount0 × amount1)`.
+    #[must_use]
+    pub fn liquidity(&self) -> f64 {
+        (self.amount0 * self.amount1).sqrt()
+    }
+
+    /// Values the position at `price`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `price` isn't positive.
+    pub fn value_at(&self, price: f64) -> TrackerResult<PositionValue> {
+        check_positive("price", price)?;
+        let liquidity = self.liquidity();
+        let amount0 = liquidity / price.sqrt();
+        let amount1 = liquidity * price.sqrt();
+        let value = amount0.mul_add(price, amount1);
+        let hold_value = self.amount0.mul_add(price, self.amount1);
+
+        Ok(PositionValue {
+            price_ratio: price / self.entry_price,
+            amount0,
+            amount1,
+            value,
+            hold_value,
+            impermanent_loss: value / hold_value - 1.0,
+        })
+    }
+}
+
+/// Returns the impermanent loss of a position after the price moved by
+/// `price_ratio` (current price over entry price), as a fraction.
+#[must_use]
+pub fn impermanent_loss(price_ratio: f64) -> f64 {
+    2.0 * price_ratio.sqrt() / (1.0 + price_ratio) - 1.0
+}
+
+fn check_positive(what: &str, value: f64) -> TrackerResult<()> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(TrackerError::math(
+            format!("The {what} must be positive, got {value}"),
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loss_matches_closed_form() {
+        let position = LpPosition::deposit(2_000.0, None, Some(4_000.0)).unwrap();
+        assert!((position.amount0 - 2.0).abs() < 1e-12);
+
+        for price in [500.0, 1_000.0, 2_000.0, 3_000.0, 8_000.0] {
+            let now = position.value_at(price).unwrap();
+            assert!((now.impermanent_loss - impermanent_loss(price / 2_000.0)).abs() < 1e-12);
+            assert!(now.impermanent_loss <= 0.0);
+            // Holdings stay on the constant-product curve
+            assert!((now.amount0 * now.amount1 / 8_000.0 - 1.0).abs() < 1e-12);
+        }
+
+        // 2x and 0.5x moves lose the same ~5.72%
+        assert!((impermanent_loss(2.0) - impermanent_loss(0.5)).abs() < 1e-12);
+        assert!(impermanent_loss(1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_deposit_rejects_invalid_positions() {
+        assert!(LpPosition::deposit(2_000.0, Some(1.0), Some(2_000.0)).is_err());
+        assert!(LpPosition::deposit(0.0, Some(1.0), None).is_err());
+        assert!(LpPosition::deposit(2_000.0, Some(-1.0), None).is_err());
+        assert!(LpPosition::deposit(2_000.0, None, None)
+            .unwrap()
+            .value_at(f64::NAN)
+            .is_err());
+    }
+}
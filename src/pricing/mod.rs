@@ -0,0 +1,813 @@
+//! Pricing module for calculating ETH/USDT price from Uniswap V2 reserves.
+//!
+//! This module provides utilities to calculate the ETH price in USDT terms
+//! based on the reserve balances in the Uniswap V2 WETH/USDT pair.
+//!
+//! # Price Calculation
+//!
+//! The price is calculated as:
+//! ```text
+//! price = (usdt_reserve / usdt_decimals) / (weth_reserve / weth_decimals)
+//! price = (usdt_reserve * weth_decimals) / (weth_reserve * usdt_decimals)
+//! price = (usdt_reserve * 10^18) / (weth_reserve * 10^6)
+//! price = (usdt_reserve * 10^12) / weth_reserve
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use alloy::primitives::U256;
+//! use eth_uniswap_alloy::pricing::calculate_eth_price;
+//!
+//! // Example with reserves: 1000 WETH, 2,000,000 USDT
+//! // Expected price: ~2000 USDT per ETH
+//! let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+//! let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+//!
+//! let price = calculate_eth_price(weth_reserve, usdt_reserve).unwrap();
+//! assert!((price - 2000.0).abs() < 0.01);
+//! ```
+//!
+//! Impermanent loss of LP positions is computed in [`impermanent_loss`].
+
+pub mod impermanent_loss;
+
+use crate::error::{TrackerError, TrackerResult};
+use alloy::primitives::U256;
+
+/// Decimal places of exact prices: an exact price is `price * 10^18`.
+pub const PRICE_DECIMALS: u8 = 18;
+
+/// Calculate the exact price of token0 in token1 as an 18-decimal fixed-point
+/// integer.
+///
+/// This is the canonical pricing path: the result is stored as TEXT
+/// alongside each price point and converted to `f64` only for display.
+/// Digits beyond the 18th decimal place are truncated, so prices below
+/// 10^-18 are zero.
+///
+/// # Formula
+///
+/// ```text
+/// price_exact = floor(reserve1 * 10^(18 + decimals0 - decimals1) / reserve0)
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if either reserve is zero, or the decimals are so far
+/// apart that the scale factor or scaled reserve overflows a `U256`.
+///
+/// # Examples
+///
+/// ```
+/// use alloy::primitives::U256;
+/// use eth_uniswap_alloy::pricing::{calculate_price_exact, format_exact_price};
+///
+/// let weth_reserve = U256::from(3u128 * 10u128.pow(18));
+/// let usdt_reserve = U256::from(10_000u128 * 10u128.pow(6));
+///
+/// let price = calculate_price_exact(weth_reserve, usdt_reserve, 18, 6).unwrap();
+/// assert_eq!(format_exact_price(price), "3333.333333333333333333");
+/// ```
+pub fn calculate_price_exact(
+    reserve0: U256,
+    reserve1: U256,
+    decimals0: u8,
+    decimals1: u8,
+) -> TrackerResult<U256> {
+    if reserve0.is_zero() {
+        return Err(TrackerError::math(
+            "Token0 reserve is zero, cannot calculate price",
+            None,
+        ));
+    }
+    if reserve1.is_zero() {
+        return Err(TrackerError::math(
+            "Token1 reserve is zero, cannot calculate price",
+            None,
+        ));
+    }
+
+    // Scale so that the integer quotient carries PRICE_DECIMALS decimals
+    let exponent = i32::from(PRICE_DECIMALS) + i32::from(decimals0) - i32::from(decimals1);
+    let overflow = || {
+        TrackerError::math(
+            format!("Overflow when scaling reserves by 10^{exponent}"),
+            None,
+        )
+    };
+    let scale = U256::from(10u8)
+        .checked_pow(U256::from(exponent.unsigned_abs()))
+        .ok_or_else(overflow)?;
+
+    let price = if exponent >= 0 {
+        reserve1.checked_mul(scale).ok_or_else(overflow)? / reserve0
+    } else {
+        reserve1 / reserve0.checked_mul(scale).ok_or_else(overflow)?
+    };
+
+    Ok(price)
+}
+
+/// Formats an exact price (see [`calculate_price_exact`]) as a decimal string.
+#[must_use]
+pub fn format_exact_price(price: U256) -> String {
+    format_token_amount(price, PRICE_DECIMALS)
+}
+
+/// Converts an exact price to the nearest `f64`, for display and aggregates.
+#[must_use]
+pub fn exact_price_to_f64(price: U256) -> f64 {
+    // Parsing the decimal string rounds correctly even beyond u128 range
+    format_exact_price(price).parse().unwrap_or(f64::INFINITY)
+}
+
+/// Calculate the ETH price in USDT from reserve balances with dynamic decimal adjustment.
+///
+/// This function calculates how many units of token1 one unit of token0 is worth
+/// based on the current reserves in a Uniswap V2 pair, properly adjusting for
+/// decimal differences between the tokens.
+///
+/// The price is computed exactly by [`calculate_price_exact`] and then
+/// rounded to `f64`; use that function directly when the digits matter.
+///
+/// # Formula
+///
+/// ```text
+/// price = (reserve1 / 10^decimals1) / (reserve0 / 10^decimals0)
+/// price = (reserve1 * 10^decimals0) / (reserve0 * 10^decimals1)
+/// price = (reserve1 * 10^(decimals0 - decimals1)) / reserve0
+/// ```
+///
+/// # Arguments
+///
+/// * `reserve0` - Reserve balance of token0 in smallest units
+/// * `reserve1` - Reserve balance of token1 in smallest units
+/// * `decimals0` - Number of decimals for token0 (e.g., 18 for WETH)
+/// * `decimals1` - Number of decimals for token1 (e.g., 6 for USDT)
+///
+/// # Returns
+///
+/// Returns the price as token1 per token0 (e.g., USDT per WETH).
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - Either reserve is zero (division by zero)
+/// - Overflow occurs during calculation
+///
+/// # Examples
+///
+/// ```
+/// use alloy::primitives::U256;
+/// use eth_uniswap_alloy::pricing::calculate_price;
+///
+/// // Example: WETH (18 decimals) / USDT (6 decimals)
+/// // Reserves: 1000 WETH, 2,000,000 USDT
+/// // Expected price: ~2000 USDT per WETH
+/// let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+/// let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+///
+/// let price = calculate_price(weth_reserve, usdt_reserve, 18, 6).unwrap();
+/// assert!((price - 2000.0).abs() < 0.01);
+/// ```
+pub fn calculate_price(
+    reserve0: U256,
+    reserve1: U256,
+    decimals0: u8,
+    decimals1: u8,
+) -> TrackerResult<f64> {
+    calculate_price_exact(reserve0, reserve1, decimals0, decimals1).map(exact_price_to_f64)
+}
+
+/// Calculate the ETH price in USDT from reserve balances (backward compatible).
+///
+/// This is a convenience wrapper around `calculate_price` with hardcoded decimals
+/// for the WETH/USDT pair (18 and 6 decimals respectively).
+///
+/// # Arguments
+///
+/// * `weth_reserve` - The WETH reserve balance in wei (18 decimals)  
+/// * `usdt_reserve` - The USDT reserve balance in smallest units (6 decimals)
+///
+/// # Returns
+///
+/// Returns the ETH price in USDT as a floating-point number.
+///
+/// # Examples
+///
+/// ```
+/// use alloy::primitives::U256;
+/// use eth_uniswap_alloy::pricing::calculate_eth_price;
+///
+/// let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+/// let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+///
+/// let price = calculate_eth_price(weth_reserve, usdt_reserve).unwrap();
+/// assert!((price - 2000.0).abs() < 0.01);
+/// ```
+pub fn calculate_eth_price(weth_reserve: U256, usdt_reserve: U256) -> TrackerResult<f64> {
+    calculate_price(weth_reserve, usdt_reserve, 18, 6)
+}
+
+/// Uniswap V2 swap fee in basis points (0.3%).
+pub const SWAP_FEE_BPS: u32 = 30;
+
+/// Result of simulating an exact-input swap against pool reserves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapQuote {
+    /// Output amount in the output token's smallest units
+    pub amount_out: U256,
+    /// Spot price before the trade (output tokens per input token)
+    pub spot_price: f64,
+    /// Effective price of the trade (output tokens per input token)
+    pub execution_price: f64,
+    /// How much worse the execution price is than the spot price, in
+    /// percent (includes the swap fee)
+    pub price_impact_pct: f64,
+}
+
+/// Simulates swapping `amount_in` against constant-product (`x * y = k`)
+/// reserves, as the Uniswap V2 router's `getAmountOut` does.
+///
+/// Uses the Uniswap V2 fee; see [`quote_exact_input_with_fee`] for forks
+/// with a different one.
+///
+/// # Formula
+///
+/// ```text
+/// amount_in_with_fee = amount_in * 997
+/// amount_out = amount_in_with_fee * reserve_out
+///            / (reserve_in * 1000 + amount_in_with_fee)
+/// ```
+///
+/// # Arguments
+///
+/// * `amount_in` - Input amount in the input token's smallest units
+/// * `reserve_in` - Reserve of the input token
+/// * `reserve_out` - Reserve of the output token
+/// * `decimals_in` - Decimals of the input token
+/// * `decimals_out` - Decimals of the output token
+///
+/// # Errors
+///
+/// Returns an error if `amount_in` or either reserve is zero, or if the
+/// calculation overflows.
+///
+/// # Examples
+///
+/// ```
+/// use alloy::primitives::U256;
+/// use eth_uniswap_alloy::pricing::quote_exact_input;
+///
+/// // Sell 10 WETH into 1000 WETH / 2,000,000 USDT
+/// let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+/// let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+/// let amount_in = U256::from(10u128 * 10u128.pow(18));
+///
+/// let quote = quote_exact_input(amount_in, weth_reserve, usdt_reserve, 18, 6).unwrap();
+/// assert!((quote.spot_price - 2000.0).abs() < 0.01);
+/// assert!(quote.execution_price < quote.spot_price);
+/// assert!((quote.price_impact_pct - 1.29).abs() < 0.01);
+/// ```
+pub fn quote_exact_input(
+    amount_in: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    decimals_in: u8,
+    decimals_out: u8,
+) -> TrackerResult<SwapQuote> {
+    quote_exact_input_with_fee(
+        amount_in,
+        reserve_in,
+        reserve_out,
+        decimals_in,
+        decimals_out,
+        SWAP_FEE_BPS,
+    )
+}
+
+/// Like [`quote_exact_input`], charging a swap fee of `fee_bps` basis points
+/// (see [`DexProtocol::fee_bps`](crate::protocol::DexProtocol::fee_bps)).
+///
+/// # Errors
+///
+/// Returns an error if `amount_in` or either reserve is zero, `fee_bps` is
+/// 10000 or more, or the calculation overflows.
+pub fn quote_exact_input_with_fee(
+    amount_in: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    decimals_in: u8,
+    decimals_out: u8,
+    fee_bps: u32,
+) -> TrackerResult<SwapQuote> {
+    if fee_bps >= 10_000 {
+        return Err(TrackerError::math(
+            format!("Swap fee of {fee_bps} bps leaves nothing to swap"),
+            None,
+        ));
+    }
+    if amount_in.is_zero() {
+        return Err(TrackerError::math("Input amount is zero", None));
+    }
+    let spot_price = calculate_price(reserve_in, reserve_out, decimals_in, decimals_out)?;
+
+    let overflow = || TrackerError::math("Overflow when simulating swap", None);
+    let fee_multiplier = U256::from(10_000 - fee_bps);
+    let amount_in_with_fee = amount_in.checked_mul(fee_multiplier).ok_or_else(overflow)?;
+    let numerator = amount_in_with_fee
+        .checked_mul(reserve_out)
+        .ok_or_else(overflow)?;
+    let denominator = reserve_in
+        .checked_mul(U256::from(10_000))
+        .and_then(|r| r.checked_add(amount_in_with_fee))
+        .ok_or_else(overflow)?;
+    let amount_out = numerator / denominator;
+
+    let execution_price = if amount_out.is_zero() {
+        0.0
+    } else {
+        calculate_price(amount_in, amount_out, decimals_in, decimals_out)?
+    };
+
+    Ok(SwapQuote {
+        amount_out,
+        spot_price,
+        execution_price,
+        price_impact_pct: (1.0 - execution_price / spot_price) * 100.0,
+    })
+}
+
+/// Parses a decimal token amount (e.g. `"1.5"`) into smallest units.
+///
+/// # Errors
+///
+/// Returns an error if the string is not a plain non-negative decimal number,
+/// has more fractional digits than `decimals`, or does not fit in a `U256`.
+///
+/// # Examples
+///
+/// ```
+/// use alloy::primitives::U256;
+/// use eth_uniswap_alloy::pricing::parse_token_amount;
+///
+/// assert_eq!(parse_token_amount("1.5", 6).unwrap(), U256::from(1_500_000u64));
+/// assert!(parse_token_amount("0.0000001", 6).is_err());
+/// ```
+pub fn parse_token_amount(amount: &str, decimals: u8) -> TrackerResult<U256> {
+    let invalid =
+        |reason: &str| TrackerError::math(format!("Invalid amount {amount:?}: {reason}"), None);
+
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid("empty"));
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid("expected a decimal number"));
+    }
+    if fraction.len() > usize::from(decimals) {
+        return Err(invalid(&format!("more than {decimals} decimal places")));
+    }
+
+    let digits = format!("{whole}{fraction:0<width$}", width = usize::from(decimals));
+    U256::from_str_radix(&digits, 10)
+        .map_err(|e| TrackerError::math(format!("Invalid amount {amount:?}"), Some(Box::new(e))))
+}
+
+/// Formats an amount in smallest units as a decimal string
+/// (the inverse of [`parse_token_amount`]).
+///
+/// # Examples
+///
+/// ```
+/// use alloy::primitives::U256;
+/// use eth_uniswap_alloy::pricing::format_token_amount;
+///
+/// assert_eq!(format_token_amount(U256::from(1_500_000u64), 6), "1.5");
+/// ```
+#[must_use]
+pub fn format_token_amount(amount: U256, decimals: u8) -> String {
+    let digits = format!("{amount:0>width$}", width = usize::from(decimals) + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - usize::from(decimals));
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{whole}.{fraction}")
+    }
+}
+
+/// Which way a pool's prices are quoted.
+///
+/// Prices are stored as token1 per token0 (USDT per WETH for the default
+/// pair). A pool can be configured to report the inverse, and API requests
+/// flip either default with `?invert=true`; the conversion happens when a
+/// price is reported, never in storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum QuoteDirection {
+    /// Price of token0 in token1, as stored
+    #[default]
+    Token1PerToken0,
+    /// Price of token1 in token0
+    Token0PerToken1,
+}
+
+impl QuoteDirection {
+    /// Returns the direction, flipped if `invert` is set.
+    #[must_use]
+    pub const fn inverted(self, invert: bool) -> Self {
+        match (self, invert) {
+            (_, false) => self,
+            (Self::Token1PerToken0, true) => Self::Token0PerToken1,
+            (Self::Token0PerToken1, true) => Self::Token1PerToken0,
+        }
+    }
+
+    /// Whether stored prices are reported as their reciprocal.
+    #[must_use]
+    pub const fn is_inverse(self) -> bool {
+        matches!(self, Self::Token0PerToken1)
+    }
+
+    /// Converts a stored price; a zero price stays zero.
+    #[must_use]
+    pub fn apply(self, price: f64) -> f64 {
+        if self.is_inverse() && price > 0.0 {
+            price.recip()
+        } else {
+            price
+        }
+    }
+
+    /// Converts a stored exact price string (see [`format_exact_price`]).
+    ///
+    /// The inverse is computed from the 18-decimal exact price and truncated
+    /// to 18 decimals again. Returns `None` for a zero or unparseable price.
+    #[must_use]
+    pub fn apply_exact(self, price_exact: &str) -> Option<String> {
+        if !self.is_inverse() {
+            return Some(price_exact.to_string());
+        }
+        let price = parse_token_amount(price_exact, PRICE_DECIMALS).ok()?;
+        let one = U256::from(10u8).pow(U256::from(2 * u32::from(PRICE_DECIMALS)));
+        one.checked_div(price).map(format_exact_price)
+    }
+
+    /// Converts a stored price's percentage change between two observations.
+    #[must_use]
+    pub fn apply_change_pct(self, pct: f64) -> f64 {
+        if self.is_inverse() && pct > -100.0 {
+            (100.0 / (1.0 + pct / 100.0)) - 100.0
+        } else {
+            pct
+        }
+    }
+
+    /// Converts a stored `(low, high)` range; the inverse swaps the bounds.
+    #[must_use]
+    pub fn apply_range(self, low: f64, high: f64) -> (f64, f64) {
+        if self.is_inverse() {
+            (self.apply(high), self.apply(low))
+        } else {
+            (low, high)
+        }
+    }
+}
+
+impl std::str::FromStr for QuoteDirection {
+    type Err = TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "token1_per_token0" => Ok(Self::Token1PerToken0),
+            "token0_per_token1" => Ok(Self::Token0PerToken1),
+            _ => Err(TrackerError::config(
+                format!("Quote direction must be token1_per_token0 or token0_per_token1, got: {s}"),
+                None,
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for QuoteDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Token1PerToken0 => "token1_per_token0",
+            Self::Token0PerToken1 => "token0_per_token1",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_direction() {
+        let stored = QuoteDirection::default();
+        let inverse = stored.inverted(true);
+        assert_eq!(inverse, QuoteDirection::Token0PerToken1);
+        assert_eq!(inverse.inverted(true), stored);
+        assert_eq!(stored.inverted(false), stored);
+        assert_eq!(
+            "token0_per_token1".parse::<QuoteDirection>().unwrap(),
+            inverse
+        );
+        assert!("usdt_per_weth".parse::<QuoteDirection>().is_err());
+
+        assert!((stored.apply(2_000.0) - 2_000.0).abs() < f64::EPSILON);
+        assert!((inverse.apply(2_000.0) - 0.0005).abs() < f64::EPSILON);
+        assert!(inverse.apply(0.0).abs() < f64::EPSILON);
+        assert_eq!(inverse.apply_exact("2000").as_deref(), Some("0.0005"));
+        assert_eq!(
+            inverse.apply_exact("3").as_deref(),
+            Some("0.333333333333333333")
+        );
+        assert_eq!(inverse.apply_exact("0"), None);
+        assert_eq!(stored.apply_exact("3").as_deref(), Some("3"));
+
+        // 2000 -> 2500 is +25%; 0.0005 -> 0.0004 is -20%
+        assert!((inverse.apply_change_pct(25.0) + 20.0).abs() < 1e-9);
+        assert_eq!(inverse.apply_range(1_000.0, 2_000.0), (0.0005, 0.001));
+    }
+
+    #[test]
+    fn test_calculate_eth_price_basic() {
+        // 1000 WETH, 2,000,000 USDT -> price = 2000 USDT per ETH
+        let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 2000.0).abs() < 0.01,
+            "Expected price ~2000, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_eth_price_high_price() {
+        // 100 WETH, 500,000 USDT -> price = 5000 USDT per ETH
+        let weth_reserve = U256::from(100u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(500_000u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 5000.0).abs() < 0.01,
+            "Expected price ~5000, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_eth_price_low_price() {
+        // 10000 WETH, 10,000,000 USDT -> price = 1000 USDT per ETH
+        let weth_reserve = U256::from(10_000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(10_000_000u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 1000.0).abs() < 0.01,
+            "Expected price ~1000, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_eth_price_fractional() {
+        // 1 WETH, 2500 USDT -> price = 2500 USDT per ETH
+        let weth_reserve = U256::from(10u128.pow(18));
+        let usdt_reserve = U256::from(2500u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 2500.0).abs() < 0.01,
+            "Expected price ~2500, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_eth_price_zero_weth() {
+        let weth_reserve = U256::ZERO;
+        let usdt_reserve = U256::from(1_000_000u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_err());
+        if let Err(e) = result {
+            assert!(e.to_string().contains("Token0 reserve is zero"));
+        }
+    }
+
+    #[test]
+    fn test_calculate_eth_price_zero_usdt() {
+        let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::ZERO;
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_err());
+        if let Err(e) = result {
+            assert!(e.to_string().contains("Token1 reserve is zero"));
+        }
+    }
+
+    #[test]
+    fn test_calculate_eth_price_small_reserves() {
+        // Very small reserves: 0.1 WETH, 300 USDT -> price = 3000 USDT per ETH
+        let weth_reserve = U256::from(10u128.pow(17)); // 0.1 WETH
+        let usdt_reserve = U256::from(300u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 3000.0).abs() < 0.01,
+            "Expected price ~3000, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_eth_price_large_reserves() {
+        // Large reserves: 100,000 WETH, 400,000,000 USDT -> price = 4000 USDT per ETH
+        let weth_reserve = U256::from(100_000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(400_000_000u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 4000.0).abs() < 0.01,
+            "Expected price ~4000, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_eth_price_realistic_scenario() {
+        // Realistic scenario: 50,000 WETH, 175,000,000 USDT -> price = 3500 USDT per ETH
+        let weth_reserve = U256::from(50_000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(175_000_000u128 * 10u128.pow(6));
+
+        let result = calculate_eth_price(weth_reserve, usdt_reserve);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 3500.0).abs() < 0.01,
+            "Expected price ~3500, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_price_generic_positive_diff() {
+        // Test with 18 and 6 decimals (like WETH/USDT)
+        // 1000 token0, 2,000,000 token1 -> price = 2000
+        let reserve0 = U256::from(1000u128 * 10u128.pow(18));
+        let reserve1 = U256::from(2_000_000u128 * 10u128.pow(6));
+
+        let result = calculate_price(reserve0, reserve1, 18, 6);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 2000.0).abs() < 0.01,
+            "Expected price ~2000, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_price_generic_negative_diff() {
+        // Test with 6 and 18 decimals (reverse case)
+        // 1,000,000 token0 (6 decimals), 500 token1 (18 decimals)
+        // price = (500 * 10^18) / [(1,000,000 * 10^6) * 10^(18-6)]
+        // price = (500 * 10^18) / [(1,000,000 * 10^6) * 10^12]
+        // price = (500 * 10^18) / (1,000,000 * 10^18) = 0.0005
+        let reserve0 = U256::from(1_000_000u128 * 10u128.pow(6));
+        let reserve1 = U256::from(500u128 * 10u128.pow(18));
+
+        let result = calculate_price(reserve0, reserve1, 6, 18);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 0.0005).abs() < 0.000001,
+            "Expected price ~0.0005, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_price_generic_equal_decimals() {
+        // Test with same decimals (18 and 18)
+        // 1000 token0, 2000 token1 -> price = 2.0
+        let reserve0 = U256::from(1000u128 * 10u128.pow(18));
+        let reserve1 = U256::from(2000u128 * 10u128.pow(18));
+
+        let result = calculate_price(reserve0, reserve1, 18, 18);
+        assert!(result.is_ok());
+        let price = result.unwrap_or(0.0);
+        assert!(
+            (price - 2.0).abs() < 0.01,
+            "Expected price ~2.0, got {price}"
+        );
+    }
+
+    #[test]
+    fn test_quote_exact_input_matches_router_formula() {
+        // 1000 WETH / 2,000,000 USDT, sell 10 WETH
+        let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+        let amount_in = U256::from(10u128 * 10u128.pow(18));
+
+        let quote = quote_exact_input(amount_in, weth_reserve, usdt_reserve, 18, 6).unwrap();
+
+        // 10 * 997 * 2,000,000 / (1000 * 1000 + 10 * 997) = 19,743.160687...
+        assert_eq!(quote.amount_out, U256::from(19_743_160_687u64));
+        assert!((quote.spot_price - 2000.0).abs() < 1e-9);
+        assert!((quote.execution_price - 1_974.316_068_7).abs() < 1e-6);
+        assert!((quote.price_impact_pct - 1.284_196_565).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_quote_exact_input_small_trade_costs_only_the_fee() {
+        let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+        let amount_in = U256::from(10u128.pow(15)); // 0.001 WETH
+
+        let quote = quote_exact_input(amount_in, weth_reserve, usdt_reserve, 18, 6).unwrap();
+        assert!((quote.price_impact_pct - 0.3).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_quote_exact_input_with_fee_charges_the_given_fee() {
+        let weth_reserve = U256::from(1000u128 * 10u128.pow(18));
+        let usdt_reserve = U256::from(2_000_000u128 * 10u128.pow(6));
+        let amount_in = U256::from(10u128.pow(15)); // 0.001 WETH
+
+        // PancakeSwap charges 0.25%
+        let quote =
+            quote_exact_input_with_fee(amount_in, weth_reserve, usdt_reserve, 18, 6, 25).unwrap();
+        assert!((quote.price_impact_pct - 0.25).abs() < 0.001);
+
+        assert!(
+            quote_exact_input_with_fee(amount_in, weth_reserve, usdt_reserve, 18, 6, 10_000)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_quote_exact_input_rejects_zero_amount() {
+        let reserve = U256::from(10u128.pow(18));
+        assert!(quote_exact_input(U256::ZERO, reserve, reserve, 18, 18).is_err());
+    }
+
+    #[test]
+    fn test_token_amount_round_trip() {
+        assert_eq!(
+            parse_token_amount("2.5", 18).unwrap(),
+            U256::from(25u128 * 10u128.pow(17))
+        );
+        assert_eq!(
+            parse_token_amount("100", 6).unwrap(),
+            U256::from(100_000_000u64)
+        );
+        assert_eq!(parse_token_amount(".5", 1).unwrap(), U256::from(5u64));
+        assert!(parse_token_amount("", 6).is_err());
+        assert!(parse_token_amount("-1", 6).is_err());
+        assert!(parse_token_amount("1e6", 6).is_err());
+
+        assert_eq!(format_token_amount(U256::from(100_000_000u64), 6), "100");
+        assert_eq!(format_token_amount(U256::from(5u64), 6), "0.000005");
+        assert_eq!(format_token_amount(U256::from(42u64), 0), "42");
+    }
+
+    #[test]
+    fn test_calculate_price_exact_keeps_all_digits() {
+        // 3 WETH / 10,000 USDT is a repeating decimal
+        let price = calculate_price_exact(
+            U256::from(3u128 * 10u128.pow(18)),
+            U256::from(10_000u128 * 10u128.pow(6)),
+            18,
+            6,
+        )
+        .unwrap();
+        assert_eq!(format_exact_price(price), "3333.333333333333333333");
+    }
+
+    #[test]
+    fn test_calculate_price_exact_extreme_ratio() {
+        // Reserves near the uint112 limit against a single wei: far beyond
+        // what f64 division of u128-converted reserves could represent exactly
+        let huge = (U256::from(1u8) << 112) - U256::from(1u8);
+        let price = calculate_price_exact(U256::from(1u8), huge, 18, 18).unwrap();
+        assert_eq!(price, huge * U256::from(10u128.pow(18)));
+        assert!((exact_price_to_f64(price) - 5.192_296_858_534_828e33).abs() < 1e18);
+
+        let tiny = calculate_price_exact(huge, U256::from(1u8), 18, 18).unwrap();
+        assert_eq!(tiny, U256::ZERO);
+    }
+}
@@ -0,0 +1,223 @@
+//! Uniswap V2 forks.
+//!
+//! SushiSwap, PancakeSwap and ShibaSwap deploy the Uniswap V2 pair contract
+//! unchanged, so their `Sync` and `Swap` events decode the same way. What
+//! differs is the factory that creates their pairs and, for some forks, the
+//! swap fee. A [`DexProtocol`] carries both:
+//!
+//! - [`DexProtocol::fee_bps`] feeds swap quotes
+//!   ([`quote_exact_input_with_fee`](crate::pricing::quote_exact_input_with_fee))
+//! - [`DexProtocol::find_pair`] looks up a token pair in the protocol's factory
+//! - [`detect`] tells which protocol deployed a pair from its `factory()`
+//!
+//! The protocol of the indexed pool is set with `POOL_PROTOCOL` (or
+//! `protocol = "..."` in a `[[pools]]` section) and stored in `pools.protocol`.
+//!
+//! Factory addresses are known for Ethereum mainnet only.
+
+use alloy::primitives::{address, Address};
+use alloy::sol;
+use std::str::FromStr;
+
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::IUniswapV2Pair;
+use crate::rpc::Provider;
+
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV2Factory {
+        /// Returns the pair of `tokenA` and `tokenB`, or the zero address.
+        function getPair(address tokenA, address tokenB) external view returns (address pair);
+    }
+}
+
+/// Ethereum mainnet chain ID.
+const MAINNET: u64 = 1;
+
+/// A Uniswap V2-compatible exchange.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DexProtocol {
+    /// Uniswap V2 (0.3% fee)
+    #[default]
+    UniswapV2,
+    /// SushiSwap (0.3% fee)
+    SushiSwap,
+    /// PancakeSwap V2 on Ethereum (0.25% fee)
+    PancakeSwap,
+    /// ShibaSwap (0.3% fee)
+    ShibaSwap,
+}
+
+impl DexProtocol {
+    /// All supported protocols.
+    pub const ALL: [Self; 4] = [
+        Self::UniswapV2,
+        Self::SushiSwap,
+        Self::PancakeSwap,
+        Self::ShibaSwap,
+    ];
+
+    /// Returns the protocol's name as accepted by `POOL_PROTOCOL` and stored
+    /// in the database.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::UniswapV2 => "uniswap_v2",
+            Self::SushiSwap => "sushiswap",
+            Self::PancakeSwap => "pancakeswap",
+            Self::ShibaSwap => "shibaswap",
+        }
+    }
+
+    /// Returns the swap fee in basis points.
+    #[must_use]
+    pub const fn fee_bps(self) -> u32 {
+        match self {
+            Self::UniswapV2 | Self::SushiSwap | Self::ShibaSwap => 30,
+            Self::PancakeSwap => 25,
+        }
+    }
+
+    /// Returns the factory that creates the protocol's pairs on `chain_id`,
+    /// if known.
+    #[must_use]
+    pub const fn factory(self, chain_id: u64) -> Option<Address> {
+        if chain_id != MAINNET {
+            return None;
+        }
+        Some(match self {
+            Self::UniswapV2 => address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"),
+            Self::SushiSwap => address!("C0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac"),
+            Self::PancakeSwap => address!("1097053Fd2ea711dad45caCcc45EfF7548fCB362"),
+            Self::ShibaSwap => address!("115934131916C8b277DD010Ee02de363c09d037c"),
+        })
+    }
+
+    /// Returns the protocol whose factory on `chain_id` is `factory`.
+    #[must_use]
+    pub fn from_factory(chain_id: u64, factory: Address) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|protocol| protocol.factory(chain_id) == Some(factory))
+    }
+
+    /// Looks up the pair of `token_a` and `token_b` in the protocol's factory.
+    ///
+    /// Returns `None` if the protocol has no pair for the tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the factory on `chain_id` is unknown or the call
+    /// fails.
+    pub async fn find_pair(
+        self,
+        provider: &Provider,
+        chain_id: u64,
+        token_a: Address,
+        token_b: Address,
+    ) -> TrackerResult<Option<Address>> {
+        let factory = self.factory(chain_id).ok_or_else(|| {
+            TrackerError::config(format!("No known {self} factory on chain {chain_id}"), None)
+        })?;
+
+        let pair = IUniswapV2Factory::new(factory, provider)
+            .getPair(token_a, token_b)
+            .call()
+            .await
+            .map_err(|e| {
+                TrackerError::rpc(
+                    format!("Failed to look up {self} pair of {token_a} and {token_b}: {e}"),
+                    Some(Box::new(e)),
+                )
+            })?
+            .pair;
+
+        Ok((!pair.is_zero()).then_some(pair))
+    }
+}
+
+/// Returns the protocol that deployed `pair`, or `None` if its factory isn't
+/// one of the known ones.
+///
+/// # Errors
+///
+/// Returns an error if the pair's `factory()` call fails, e.g. `pair` is not
+/// a Uniswap V2-style pair.
+pub async fn detect(
+    provider: &Provider,
+    chain_id: u64,
+    pair: Address,
+) -> TrackerResult<Option<DexProtocol>> {
+    let factory = IUniswapV2Pair::new(pair, provider)
+        .factory()
+        .call()
+        .await
+        .map_err(|e| {
+            TrackerError::rpc(
+                format!("Failed to fetch the factory of {pair}: {e}"),
+                Some(Box::new(e)),
+            )
+        })?
+        ._0;
+
+    Ok(DexProtocol::from_factory(chain_id, factory))
+}
+
+impl FromStr for DexProtocol {
+    type Err = TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase().replace(['-', ' '], "_");
+        match normalized.as_str() {
+            "" | "uniswap_v2" | "uniswapv2" | "uniswap" => Ok(Self::UniswapV2),
+            "sushiswap" | "sushi" => Ok(Self::SushiSwap),
+            "pancakeswap" | "pancakeswap_v2" | "pancake" => Ok(Self::PancakeSwap),
+            "shibaswap" | "shiba" => Ok(Self::ShibaSwap),
+            _ => Err(TrackerError::config(
+                format!(
+                    "Protocol must be uniswap_v2, sushiswap, pancakeswap or shibaswap, got: {}",
+                    s.trim()
+                ),
+                None,
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for DexProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        for protocol in DexProtocol::ALL {
+            assert_eq!(protocol.as_str().parse::<DexProtocol>().unwrap(), protocol);
+        }
+        assert_eq!(
+            "Uniswap-V2".parse::<DexProtocol>().unwrap(),
+            DexProtocol::UniswapV2
+        );
+        assert_eq!(
+            "sushi".parse::<DexProtocol>().unwrap(),
+            DexProtocol::SushiSwap
+        );
+        assert!("curve".parse::<DexProtocol>().is_err());
+    }
+
+    #[test]
+    fn test_factories_identify_protocols() {
+        for protocol in DexProtocol::ALL {
+            let factory = protocol.factory(1).unwrap();
+            assert_eq!(DexProtocol::from_factory(1, factory), Some(protocol));
+            assert_eq!(protocol.factory(137), None);
+        }
+        assert_eq!(DexProtocol::from_factory(1, Address::ZERO), None);
+        assert_eq!(DexProtocol::PancakeSwap.fee_bps(), 25);
+    }
+}
@@ -0,0 +1,331 @@
+//! Shared prices over Redis (enabled with the `redis` cargo feature).
+//!
+//! Lets a tier of API servers sit in front of a single indexer. With
+//! `REDIS_URL` set:
+//!
+//! - `watch` publishes each new confirmed price on the `{prefix}:prices`
+//!   channel, and after every pass stores the pool's latest price and 24h
+//!   change under `{prefix}:latest:{pool_id}`
+//! - `api` relays the channel into its price cache, alert engine and
+//!   WebSocket/gRPC subscribers, so every instance streams a price as soon as
+//!   it is indexed instead of on its own database poll
+//! - latest-price lookups the local cache can't answer read the shared key
+//!   before falling back to the database
+//!
+//! Shared keys expire after [`SHARED_PRICE_TTL`], so a stopped indexer's
+//! last prices aren't served as current; instances then read the database as
+//! they would without Redis. Prices published while an API server is
+//! reconnecting aren't replayed to it.
+
+use futures_util::{Stream, StreamExt};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::api::models::PriceStreamMessage;
+use crate::app_state::AppState;
+use crate::db::models::PoolRecord;
+use crate::db::repository::Repository;
+use crate::error::{TrackerError, TrackerResult};
+use crate::price_cache::CachedPrice;
+
+/// How long a shared latest price is served without the indexer refreshing
+/// it; several watch passes.
+pub const SHARED_PRICE_TTL: Duration = Duration::from_secs(120);
+
+/// Longest wait between attempts to resubscribe after losing Redis.
+const MAX_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(30);
+
+/// A new confirmed price, as published by the indexer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceAnnouncement {
+    /// Database ID of the pool
+    pub pool_id: i64,
+    /// Latest price and 24h change, as stored in the shared cache
+    pub price: CachedPrice,
+    /// Stream message, in the pool's quote direction
+    pub message: PriceStreamMessage,
+}
+
+/// Connection to Redis for publishing, relaying and caching prices.
+#[derive(Clone)]
+pub struct RedisBus {
+    client: redis::Client,
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisBus {
+    /// Connects to `url`, naming channels and keys with `prefix`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid or Redis can't be reached.
+    pub async fn connect(url: &str, prefix: &str) -> TrackerResult<Self> {
+        let client = redis::Client::open(url).map_err(|e| {
+            TrackerError::config("REDIS_URL is not a valid Redis URL", Some(Box::new(e)))
+        })?;
+        let connection = ConnectionManager::new(client.clone())
+            .await
+            .map_err(|e| redis_error("connect to Redis", e))?;
+        Ok(Self {
+            client,
+            connection,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    /// Returns the channel new prices are published on.
+    #[must_use]
+    pub fn channel(&self) -> String {
+        format!("{}:prices", self.prefix)
+    }
+
+    /// Returns the key holding a pool's latest price.
+    #[must_use]
+    pub fn latest_key(&self, pool_id: i64) -> String {
+        format!("{}:latest:{pool_id}", self.prefix)
+    }
+
+    /// Stores a pool's latest price for [`SHARED_PRICE_TTL`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if Redis rejects the write.
+    pub async fn store_latest(&self, pool_id: i64, price: &CachedPrice) -> TrackerResult<()> {
+        let value = encode(price)?;
+        self.connection
+            .clone()
+            .set_ex::<_, _, ()>(self.latest_key(pool_id), value, SHARED_PRICE_TTL.as_secs())
+            .await
+            .map_err(|e| redis_error("store the latest price", e))
+    }
+
+    /// Returns a pool's shared latest price, if the indexer stored a fresh
+    /// one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if Redis can't be read or the value is malformed.
+    pub async fn latest(&self, pool_id: i64) -> TrackerResult<Option<CachedPrice>> {
+        let value: Option<String> = self
+            .connection
+            .clone()
+            .get(self.latest_key(pool_id))
+            .await
+            .map_err(|e| redis_error("read the latest price", e))?;
+        value.as_deref().map(decode).transpose()
+    }
+
+    /// Stores `announcement`'s price and publishes it to subscribers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if Redis rejects either command.
+    pub async fn publish(&self, announcement: &PriceAnnouncement) -> TrackerResult<()> {
+        self.store_latest(announcement.pool_id, &announcement.price)
+            .await?;
+        self.connection
+            .clone()
+            .publish::<_, _, ()>(self.channel(), encode(announcement)?)
+            .await
+            .map_err(|e| redis_error("publish a price", e))
+    }
+
+    /// Shares a pool's latest confirmed price after an indexer pass:
+    /// refreshes the shared key, and publishes the price if its block is
+    /// newer than `last_published`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the price can't be read or shared.
+    pub async fn share_latest(
+        &self,
+        repository: &Repository,
+        pool: &PoolRecord,
+        last_published: &mut Option<i64>,
+    ) -> TrackerResult<()> {
+        let Some(price) = CachedPrice::load(repository, pool.id).await? else {
+            return Ok(());
+        };
+        let block = price.latest.block_number;
+        if last_published.is_some_and(|last| block <= last) {
+            return self.store_latest(pool.id, &price).await;
+        }
+
+        let name = pool
+            .name
+            .clone()
+            .unwrap_or_else(|| pool.address.to_string());
+        let message = PriceStreamMessage::confirmed(name, pool.quote_direction(), &price.latest);
+        self.publish(&PriceAnnouncement {
+            pool_id: pool.id,
+            price,
+            message,
+        })
+        .await?;
+        debug!(pool_id = pool.id, block, "Published price to Redis");
+        *last_published = Some(block);
+        Ok(())
+    }
+
+    /// Subscribes to published prices. Malformed messages are skipped; the
+    /// stream ends when the subscription is lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the subscription can't be made.
+    pub async fn subscribe(&self) -> TrackerResult<impl Stream<Item = PriceAnnouncement>> {
+        let mut pubsub = self
+            .client
+            .get_async_pubsub()
+            .await
+            .map_err(|e| redis_error("connect to Redis", e))?;
+        pubsub
+            .subscribe(self.channel())
+            .await
+            .map_err(|e| redis_error("subscribe to prices", e))?;
+
+        Ok(pubsub.into_on_message().filter_map(|msg| async move {
+            let decoded = msg
+                .get_payload::<String>()
+                .map_err(|e| redis_error("read a published price", e))
+                .and_then(|payload| decode(&payload));
+            match decoded {
+                Ok(announcement) => Some(announcement),
+                Err(e) => {
+                    warn!(error = %e, "Skipping malformed price from Redis");
+                    None
+                }
+            }
+        }))
+    }
+}
+
+impl fmt::Debug for RedisBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The client's connection info may hold a password
+        f.debug_struct("RedisBus")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Relays published prices into the API server: the price cache, the alert
+/// engine and stream subscribers. Resubscribes with backoff whenever the
+/// subscription is lost; never returns.
+pub async fn relay_prices(state: AppState, bus: RedisBus) {
+    let mut delay = Duration::from_secs(1);
+
+    loop {
+        match bus.subscribe().await {
+            Ok(prices) => {
+                info!(channel = %bus.channel(), "Relaying prices from Redis");
+                delay = Duration::from_secs(1);
+                tokio::pin!(prices);
+                while let Some(announcement) = prices.next().await {
+                    apply(&state, announcement);
+                }
+                warn!("Redis price subscription ended, resubscribing");
+            }
+            Err(e) => warn!(error = %e, "Failed to subscribe to Redis prices"),
+        }
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RESUBSCRIBE_DELAY);
+    }
+}
+
+fn apply(state: &AppState, announcement: PriceAnnouncement) {
+    let PriceAnnouncement {
+        pool_id,
+        price,
+        message,
+    } = announcement;
+    state.prices.insert(pool_id, price);
+    if let Some(alerts) = &state.alerts {
+        alerts.on_price(&message.pool, message.price, message.block_number);
+    }
+    state.broadcast_price_update(message);
+}
+
+fn encode<T: Serialize>(value: &T) -> TrackerResult<String> {
+    serde_json::to_string(value)
+        .map_err(|e| TrackerError::state("Failed to encode a shared price", Some(Box::new(e))))
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(value: &str) -> TrackerResult<T> {
+    serde_json::from_str(value)
+        .map_err(|e| TrackerError::state("Malformed shared price", Some(Box::new(e))))
+}
+
+fn redis_error(action: &str, e: redis::RedisError) -> TrackerError {
+    TrackerError::database(format!("Failed to {action}"), Some(Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::PricePointRow;
+    use crate::db::{create_pool, repository::Repository};
+    use crate::pricing::QuoteDirection;
+    use alloy::primitives::B256;
+
+    fn announcement() -> PriceAnnouncement {
+        let latest = PricePointRow {
+            event_id: Some("price-1".to_string()),
+            block_number: 19_000_000,
+            block_timestamp: 1_706_745_600,
+            tx_hash: B256::repeat_byte(0xab).into(),
+            price: 2_000.0,
+            price_exact: Some("2000".to_string()),
+            price_ewma: None,
+            source: "event".to_string(),
+            reserve0_human: 1_000.0,
+            reserve1_human: 2_000_000.0,
+        };
+        let message = PriceStreamMessage::confirmed(
+            "WETH/USDT".to_string(),
+            QuoteDirection::default(),
+            &latest,
+        );
+        PriceAnnouncement {
+            pool_id: 1,
+            price: CachedPrice {
+                latest,
+                change_24h: Some(1.5),
+            },
+            message,
+        }
+    }
+
+    #[test]
+    fn test_announcements_round_trip() {
+        let sent = announcement();
+        let received: PriceAnnouncement = decode(&encode(&sent).unwrap()).unwrap();
+        assert_eq!(received.pool_id, 1);
+        assert_eq!(received.price.latest.block_number, 19_000_000);
+        assert_eq!(received.price.change_24h, Some(1.5));
+        assert_eq!(received.message.block_number, 19_000_000);
+        assert!(received.message.is_confirmed);
+
+        assert!(decode::<PriceAnnouncement>("{\"pool_id\":1}").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_relayed_prices_reach_cache_and_subscribers() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let state = AppState::new(repository);
+        let mut rx = state.price_broadcast.subscribe();
+
+        apply(&state, announcement());
+
+        assert_eq!(state.prices.get(1).unwrap().change_24h, Some(1.5));
+        let message = rx.recv().await.unwrap();
+        assert_eq!(message.pool, "WETH/USDT");
+        assert_eq!(message.price, 2_000.0);
+    }
+}
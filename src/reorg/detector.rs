@@ -2,9 +2,10 @@
 
 use alloy::primitives::B256;
 use alloy::providers::Provider;
-use alloy::rpc::types::{Block, BlockTransactionsKind};
+use alloy::rpc::types::{Block, BlockNumberOrTag, BlockTransactionsKind};
 use alloy::transports::http::{Client, Http};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use tracing::{debug, info, warn};
 
 use crate::error::{TrackerError, TrackerResult};
@@ -57,19 +58,46 @@ impl BlockRecord {
     }
 }
 
+/// Most recent block records kept for finding fork points. Watch mode tracks
+/// one block per pass, so this reaches back well past finality.
+pub const MAX_TRACKED_BLOCKS: usize = 128;
+
+/// Blocks the finalized block trails the head by on Ethereum (two epochs).
+pub const FINALITY_LAG_BLOCKS: u64 = 64;
+
+/// Returns how many blocks to track on a chain whose confirmation depth is
+/// `confirmation_depth`: [`MAX_TRACKED_BLOCKS`], or twice the depth on chains
+/// that reorg deeper.
+#[must_use]
+pub fn history_limit(confirmation_depth: u64) -> usize {
+    usize::try_from(confirmation_depth.saturating_mul(2))
+        .unwrap_or(usize::MAX)
+        .max(MAX_TRACKED_BLOCKS)
+}
+
 /// Chain reorganization detector.
 ///
 /// Detects when the Ethereum chain has reorganized by tracking block hashes
 /// and verifying parent hash linkage. When a reorg is detected, performs
-/// binary search to find the exact fork point.
+/// binary search over the tracked blocks to find the fork point.
 ///
 /// ## Algorithm
 ///
-/// 1. Store the last known block record (number, hash, parent_hash)
+/// 1. Store the records (number, hash, parent_hash) of the last
+///    [`MAX_TRACKED_BLOCKS`] tracked blocks (see
+///    [`ReorgDetector::with_history_limit`])
 /// 2. When fetching a new block, verify its parent_hash matches our last_hash
-/// 3. If mismatch: binary search between last_known and current to find fork point
+/// 3. If mismatch: binary search the tracked blocks for the newest one whose
+///    hash is still on-chain
 /// 4. Return the fork point block number for state invalidation
 ///
+/// Blocks below the finalized block can't reorg, so
+/// [`ReorgDetector::prune_finalized`] forgets them, keeping a safety margin.
+///
+/// The fork point is the newest *tracked* block still on the chain, so when
+/// blocks are tracked sparsely it may be below the actual fork; re-indexing
+/// from there is safe, only slower.
+///
 /// ## Example
 ///
 /// ```rust,ignore
@@ -94,18 +122,28 @@ impl BlockRecord {
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReorgDetector {
-    /// Last known canonical block
-    last_block: Option<BlockRecord>,
+    /// Recently tracked canonical blocks, oldest first
+    #[serde(default)]
+    history: VecDeque<BlockRecord>,
+
+    /// Most blocks kept in `history`
+    #[serde(default = "default_history_limit")]
+    history_limit: usize,
 
     /// Total number of reorgs detected
     reorg_count: u64,
 }
 
+const fn default_history_limit() -> usize {
+    MAX_TRACKED_BLOCKS
+}
+
 impl ReorgDetector {
     /// Create a new reorg detector with no tracked blocks.
     pub fn new() -> Self {
         Self {
-            last_block: None,
+            history: VecDeque::new(),
+            history_limit: MAX_TRACKED_BLOCKS,
             reorg_count: 0,
         }
     }
@@ -113,26 +151,115 @@ impl ReorgDetector {
     /// Create a detector with an initial block record.
     pub fn with_block(block: BlockRecord) -> Self {
         Self {
-            last_block: Some(block),
+            history: VecDeque::from([block]),
+            history_limit: MAX_TRACKED_BLOCKS,
             reorg_count: 0,
         }
     }
 
+    /// Track up to `limit` blocks (at least one) instead of
+    /// [`MAX_TRACKED_BLOCKS`], so fork points of deeper reorgs can be found.
+    #[must_use]
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = limit.max(1);
+        while self.history.len() > self.history_limit {
+            self.history.pop_front();
+        }
+        self
+    }
+
     /// Add a new block to the tracker (assumes it's canonical).
     ///
     /// Call this after successfully processing a block to update
-    /// the chain tip that we'll verify against future blocks.
+    /// the chain tip that we'll verify against future blocks. Tracked
+    /// blocks at or above its number are replaced.
     pub fn add_block(&mut self, block: BlockRecord) {
         debug!(
             "Tracking block {} (hash: {}, parent: {})",
             block.number, block.hash, block.parent_hash
         );
-        self.last_block = Some(block);
+        self.rewind(block.number.saturating_sub(1));
+        self.history.push_back(block);
+        if self.history.len() > self.history_limit {
+            self.history.pop_front();
+        }
+    }
+
+    /// Forget tracked blocks after `fork_point`, keeping the ones still on
+    /// the chain after a reorg.
+    pub fn rewind(&mut self, fork_point: u64) {
+        while self
+            .history
+            .back()
+            .is_some_and(|block| block.number > fork_point)
+        {
+            self.history.pop_back();
+        }
+    }
+
+    /// Forget tracked blocks more than `margin` blocks below `finalized`,
+    /// which can never be reorged. The last tracked block is always kept.
+    ///
+    /// Returns how many blocks were forgotten.
+    pub fn forget_finalized(&mut self, finalized: u64, margin: u64) -> usize {
+        let cutoff = finalized.saturating_sub(margin);
+        let before = self.history.len();
+        while self.history.len() > 1
+            && self
+                .history
+                .front()
+                .is_some_and(|block| block.number < cutoff)
+        {
+            self.history.pop_front();
+        }
+        before - self.history.len()
+    }
+
+    /// Forget tracked blocks more than `margin` blocks below the chain's
+    /// finalized block (see [`ReorgDetector::forget_finalized`]).
+    ///
+    /// The finalized block is only fetched once the history reaches further
+    /// back than finality and the margin, so most calls make no request. A
+    /// node without the `finalized` tag leaves the history as it is.
+    ///
+    /// Returns how many blocks were forgotten.
+    pub async fn prune_finalized(&mut self, provider: &ConcreteProvider, margin: u64) -> usize {
+        let (Some(oldest), Some(newest)) = (self.history.front(), self.history.back()) else {
+            return 0;
+        };
+        if newest.number - oldest.number <= margin + 2 * FINALITY_LAG_BLOCKS {
+            return 0;
+        }
+
+        let finalized = match provider
+            .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+            .await
+        {
+            Ok(Some(block)) => block.header.number,
+            Ok(None) => return 0,
+            Err(e) => {
+                debug!("Finalized block unavailable, keeping tracked blocks: {}", e);
+                return 0;
+            }
+        };
+        let pruned = self.forget_finalized(finalized, margin);
+        if pruned > 0 {
+            debug!(
+                "Forgot {} tracked blocks finalized before block {}",
+                pruned, finalized
+            );
+        }
+        pruned
+    }
+
+    /// Get the most blocks tracked at once.
+    pub fn max_tracked_blocks(&self) -> usize {
+        self.history_limit
     }
 
     /// Get the last tracked block record.
     pub fn last_block(&self) -> Option<&BlockRecord> {
-        self.last_block.as_ref()
+        self.history.back()
     }
 
     /// Get the total number of detected reorgs.
@@ -165,8 +292,8 @@ impl ReorgDetector {
         provider: &ConcreteProvider,
         current_block_number: u64,
     ) -> TrackerResult<Option<u64>> {
-        let last_known = match &self.last_block {
-            Some(block) => block,
+        let last_known = match self.last_block() {
+            Some(block) => block.clone(),
             None => {
                 debug!("No last block tracked, cannot detect reorg");
                 return Ok(None);
@@ -184,12 +311,12 @@ impl ReorgDetector {
                 );
                 self.reorg_count += 1;
 
-                // For 1-block reorg, fork point is the parent of our last known block
-                let fork_point = if last_known.number > 0 {
-                    last_known.number - 1
-                } else {
-                    0
-                };
+                let fork_point = self.find_fork_point(provider).await?;
+                info!(
+                    "Fork point found at block {}. Reorg depth: {} blocks",
+                    fork_point,
+                    last_known.number - fork_point
+                );
 
                 return Ok(Some(fork_point));
             }
@@ -209,8 +336,7 @@ impl ReorgDetector {
             );
             self.reorg_count += 1;
 
-            // Binary search to find the exact fork point
-            let fork_point = self.find_fork_point(provider, 0, last_known.number).await?;
+            let fork_point = self.find_fork_point(provider).await?;
 
             info!(
                 "Fork point found at block {}. Reorg depth: {} blocks",
@@ -225,48 +351,40 @@ impl ReorgDetector {
         Ok(None)
     }
 
-    /// Binary search to find the fork point (last common block) between two chain states.
-    ///
-    /// Assumes that blocks from `low` to `high` have been previously indexed, and finds
-    /// the highest block number where the on-chain hash still matches our recorded hash.
-    ///
-    /// ## Algorithm
+    /// Binary search for the newest tracked block that is still on-chain,
+    /// once the last tracked block is known to have been reorged out.
     ///
-    /// Standard binary search on block numbers, checking if on-chain hash matches
-    /// our stored hash at the midpoint. The fork point is the last matching block.
-    async fn find_fork_point(
-        &self,
-        provider: &ConcreteProvider,
-        mut low: u64,
-        mut high: u64,
-    ) -> TrackerResult<u64> {
-        debug!("Binary search for fork point between {} and {}", low, high);
-
-        let mut fork_point = low;
-
-        while low <= high {
+    /// Tracked blocks before the fork still have their hashes on-chain and
+    /// those after it don't, so the matches are a prefix of the history. If
+    /// none match, the reorg is deeper than the tracked history and the block
+    /// before the oldest tracked one is returned.
+    async fn find_fork_point(&self, provider: &ConcreteProvider) -> TrackerResult<u64> {
+        // Candidates are history[low..high]; the last tracked block is gone
+        let mut low = 0;
+        let mut high = self.history.len().saturating_sub(1);
+        debug!("Binary search for fork point among {} tracked blocks", high);
+
+        while low < high {
             let mid = low + (high - low) / 2;
+            let tracked = &self.history[mid];
+            let on_chain = self.fetch_block(provider, tracked.number).await?;
 
-            let on_chain = self.fetch_block(provider, mid).await?;
-
-            // Check if we have this block in our history
-            // For simplicity, we're assuming the hash verification happens at the boundary.
-            // In a full implementation, you'd check against a persistent block hash store.
-            //
-            // For now, we'll just find where the chain diverged by checking consecutive blocks.
-            let on_chain_next = self.fetch_block(provider, mid + 1).await?;
-
-            if on_chain_next.parent_hash == on_chain.hash {
-                // Chain is continuous at this point
-                fork_point = mid;
+            if on_chain.hash == tracked.hash {
                 low = mid + 1;
             } else {
-                // Divergence found before mid
-                high = mid.saturating_sub(1);
+                high = mid;
             }
         }
 
-        Ok(fork_point)
+        if let Some(block) = low.checked_sub(1).map(|i| &self.history[i]) {
+            return Ok(block.number);
+        }
+        let oldest = self.history.front().map_or(0, |block| block.number);
+        warn!(
+            "Reorg reaches past the oldest tracked block {}, assuming it forked just before",
+            oldest
+        );
+        Ok(oldest.saturating_sub(1))
     }
 
     /// Fetch a block from the provider with error handling.
@@ -301,7 +419,7 @@ impl Default for ReorgDetector {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::primitives::b256;
+    use alloy::primitives::{b256, U256};
 
     #[test]
     fn test_block_record_creation() {
@@ -339,6 +457,61 @@ mod tests {
         assert_eq!(detector.last_block().unwrap().number, 19_000_000);
     }
 
+    #[test]
+    fn test_history_is_bounded_and_rewinds() {
+        let record =
+            |number: u64| BlockRecord::new(number, B256::from(U256::from(number)), B256::ZERO, 0);
+        let mut detector = ReorgDetector::new();
+        for number in 0..MAX_TRACKED_BLOCKS as u64 + 10 {
+            detector.add_block(record(number));
+        }
+        assert_eq!(detector.history.len(), MAX_TRACKED_BLOCKS);
+        assert_eq!(detector.history.front().unwrap().number, 10);
+
+        // Re-indexed blocks replace the ones they reorged out
+        detector.add_block(record(100));
+        assert_eq!(detector.last_block().unwrap().number, 100);
+        assert_eq!(detector.history.len(), 91);
+
+        detector.rewind(50);
+        assert_eq!(detector.last_block().unwrap().number, 50);
+        detector.rewind(0);
+        assert!(detector.last_block().is_none());
+    }
+
+    #[test]
+    fn test_forget_finalized_keeps_margin_and_last_block() {
+        let record =
+            |number: u64| BlockRecord::new(number, B256::from(U256::from(number)), B256::ZERO, 0);
+        let mut detector = ReorgDetector::new();
+        for number in (0..100).step_by(10) {
+            detector.add_block(record(number));
+        }
+
+        assert_eq!(detector.forget_finalized(60, 15), 5);
+        assert_eq!(detector.history.front().unwrap().number, 50);
+        assert_eq!(detector.forget_finalized(60, 15), 0);
+
+        // The last tracked block stays, even when finalized
+        assert_eq!(detector.forget_finalized(1_000, 0), 4);
+        assert_eq!(detector.last_block().unwrap().number, 90);
+    }
+
+    #[test]
+    fn test_history_limit_follows_confirmation_depth() {
+        assert_eq!(history_limit(12), MAX_TRACKED_BLOCKS);
+        assert_eq!(history_limit(100), 200);
+
+        let record =
+            |number: u64| BlockRecord::new(number, B256::from(U256::from(number)), B256::ZERO, 0);
+        let mut detector = ReorgDetector::new().with_history_limit(history_limit(100));
+        for number in 0..300 {
+            detector.add_block(record(number));
+        }
+        assert_eq!(detector.history.len(), 200);
+        assert_eq!(detector.with_history_limit(0).history.len(), 1);
+    }
+
     #[test]
     fn test_reorg_count_tracking() {
         let mut detector = ReorgDetector::new();
@@ -45,4 +45,6 @@
 
 pub mod detector;
 
-pub use detector::{BlockRecord, ReorgDetector};
+pub use detector::{
+    history_limit, BlockRecord, ReorgDetector, FINALITY_LAG_BLOCKS, MAX_TRACKED_BLOCKS,
+};
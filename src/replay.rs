@@ -0,0 +1,347 @@
+//! Recomputing prices from stored Sync events.
+//!
+//! Price points are derived data: each one can be rebuilt from its
+//! `sync_events` row and the pool's token decimals and pool type. After a
+//! pricing bug is fixed or the price formula changes, [`replay`] recomputes a
+//! pool's prices without fetching anything from RPC:
+//!
+//! 1. The pool's sync events from the start block onwards are read in chunks
+//!    of [`REPLAY_CHUNK_EVENTS`] and priced like the indexer's price stage,
+//!    smoothed prices included (continued from the last price before the
+//!    start block). `getReserves()` snapshots in the range are re-priced from
+//!    their stored reserves.
+//! 2. The prices are written to the `price_points_replay` shadow table and
+//!    compared with `price_points`.
+//! 3. Unless it is a shadow run, the replayed block range of `price_points`
+//!    is replaced in one transaction, so readers see either the old or the
+//!    new prices, and the pool's flushed 1m and 5m candles from the start
+//!    block on are rebuilt. Daily roll-ups are left as they are.
+//!
+//! With [`PriceMode::Block`] only the last price of each block is rebuilt,
+//! like the indexer writes them in that mode.
+//!
+//! The start block is the first stored sync event at or after the requested
+//! block, so prices whose events were pruned are never deleted. Prices the
+//! indexer writes past the last replayed event while a replay runs are kept
+//! as written. A running API server picks up the new candles on restart.
+
+use tracing::info;
+
+use crate::candles::CANDLE_INTERVALS;
+use crate::db::models::{BlockRange, EventCursor, PoolRecord, ReplayDiff};
+use crate::db::repository::Repository;
+use crate::error::TrackerResult;
+use crate::pipeline::{drop_block_price, Pipeline, PriceMode};
+use crate::smoothing::PriceEwma;
+
+/// Sync events read and priced per chunk.
+pub const REPLAY_CHUNK_EVENTS: i64 = 10_000;
+
+/// Outcome of a replay.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayReport {
+    /// First and last replayed block (`None` if the pool has no stored
+    /// events in the range)
+    pub blocks: Option<(u64, u64)>,
+    /// Sync events priced
+    pub events: u64,
+    /// `getReserves()` snapshots re-priced
+    pub snapshots: u64,
+    /// How the replayed prices differ from the stored ones
+    pub diff: ReplayDiff,
+    /// Whether the stored prices were replaced (false for a shadow run)
+    pub applied: bool,
+}
+
+/// Recomputes a pool's prices from its sync events at or after
+/// `from_block` (default: all of them).
+///
+/// With `apply` unset, the prices are only written to the shadow table,
+/// where they stay until the next replay of the pool.
+///
+/// # Errors
+///
+/// Returns an error if a stored event can't be priced or a database query
+/// fails. `price_points` is unchanged then.
+pub async fn replay(
+    repository: &Repository,
+    pool: &PoolRecord,
+    chain_id: u64,
+    ewma_half_life_secs: Option<u64>,
+    price_mode: PriceMode,
+    from_block: Option<u64>,
+    apply: bool,
+) -> TrackerResult<ReplayReport> {
+    let pipeline = Pipeline::new(repository, pool, chain_id)?.with_price_mode(price_mode);
+    repository.clear_replay_prices(pool.id).await?;
+
+    let mut events = repository
+        .get_sync_event_records(pool.id, from_block.unwrap_or(0), None, REPLAY_CHUNK_EVENTS)
+        .await?;
+    let Some(first) = events.first() else {
+        return Ok(ReplayReport::default());
+    };
+    let start = u64::try_from(first.block_number).unwrap_or_default();
+    let start_timestamp = first.block_timestamp;
+
+    let mut price_ewma = match ewma_half_life_secs {
+        Some(half_life) => Some(ewma_before(repository, pool.id, start, half_life).await?),
+        None => None,
+    };
+    let mut snapshots = repository
+        .get_price_snapshots(pool.id, start)
+        .await?
+        .into_iter()
+        .peekable();
+
+    let mut report = ReplayReport::default();
+    let mut end = start;
+    // In block mode, the last block's price until the next chunk can't have
+    // a later one
+    let mut carried = None;
+    while let Some(last) = events.last() {
+        let after = EventCursor::new(
+            u64::try_from(last.block_number).unwrap_or_default(),
+            u32::try_from(last.log_index).unwrap_or_default(),
+        );
+
+        let mut prices: Vec<_> = carried.take().into_iter().collect();
+        for event in &events {
+            while let Some(snapshot) = snapshots.next_if(|s| s.block_number < event.block_number) {
+                prices.push(pipeline.reprice_snapshot(&snapshot, &mut price_ewma)?);
+                report.snapshots += 1;
+            }
+            let price = pipeline.reprice_event(event, &mut price_ewma)?;
+            if price_mode == PriceMode::Block {
+                drop_block_price(&mut prices, price.block_number);
+            }
+            prices.push(price);
+            report.events += 1;
+        }
+        if price_mode == PriceMode::Block {
+            carried = prices.pop();
+        }
+        end = after.block_number;
+        repository.insert_replay_prices(&prices).await?;
+
+        events = repository
+            .get_sync_event_records(pool.id, start, Some(after), REPLAY_CHUNK_EVENTS)
+            .await?;
+    }
+
+    // Snapshots taken after the last event, while the pool was quiet
+    let mut prices: Vec<_> = carried.into_iter().collect();
+    for snapshot in snapshots {
+        prices.push(pipeline.reprice_snapshot(&snapshot, &mut price_ewma)?);
+        report.snapshots += 1;
+        end = end.max(u64::try_from(snapshot.block_number).unwrap_or_default());
+    }
+    repository.insert_replay_prices(&prices).await?;
+
+    report.blocks = Some((start, end));
+    report.diff = repository
+        .compare_replay_prices(pool.id, start, end)
+        .await?;
+
+    if apply {
+        let written = repository.apply_replay_prices(pool.id, start, end).await?;
+        rebuild_candles(repository, pool, start_timestamp).await?;
+        report.applied = true;
+        info!(
+            pool_id = pool.id,
+            from_block = start,
+            to_block = end,
+            written,
+            "Replayed prices"
+        );
+    }
+
+    Ok(report)
+}
+
+/// Creates the EWMA continuing from the last smoothed price before
+/// `block`, if there is one.
+async fn ewma_before(
+    repository: &Repository,
+    pool_id: i64,
+    block: u64,
+    half_life_secs: u64,
+) -> TrackerResult<PriceEwma> {
+    let previous = match block.checked_sub(1) {
+        Some(before) => repository
+            .get_prices_at_blocks(pool_id, &[before])
+            .await?
+            .into_iter()
+            .next()
+            .and_then(|(_, price)| price),
+        None => None,
+    };
+
+    Ok(previous
+        .and_then(|p| {
+            Some(PriceEwma::resume(
+                half_life_secs,
+                u64::try_from(p.block_number).ok()?,
+                p.block_timestamp,
+                p.price_ewma?,
+            ))
+        })
+        .unwrap_or_else(|| PriceEwma::new(half_life_secs)))
+}
+
+/// Rebuilds the flushed 1m and 5m candles from the bucket containing
+/// `from_ts` onwards from `price_points`, leaving out flash spikes if the
+/// pool filters them.
+async fn rebuild_candles(
+    repository: &Repository,
+    pool: &PoolRecord,
+    from_ts: i64,
+) -> TrackerResult<()> {
+    for interval in CANDLE_INTERVALS {
+        let bucket_start = from_ts.div_euclid(interval) * interval;
+        repository
+            .delete_stored_candles_from(pool.id, interval, bucket_start)
+            .await?;
+        let candles = repository
+            .get_candles(
+                pool.id,
+                interval,
+                Some(bucket_start),
+                None,
+                BlockRange::ALL,
+                i64::MAX,
+                pool.spike_filter(),
+            )
+            .await?;
+        repository
+            .upsert_candles(pool.id, interval, &candles)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_pool, run_migrations};
+    use alloy::primitives::{FixedBytes, U256};
+
+    /// Reserves of 1000 WETH and `usdt` USDT.
+    fn reserves(usdt: u64) -> (U256, U256) {
+        (
+            U256::from(1_000_u64) * U256::from(10_u64).pow(U256::from(18_u64)),
+            U256::from(usdt) * U256::from(1_000_000_u64),
+        )
+    }
+
+    async fn setup() -> (Repository, PoolRecord) {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        let repo = Repository::new(pool);
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // Three events priced 2000, 2100 and 2200, stored with a wrong price
+        for (block, usdt) in [(100_u64, 2_000_000), (101, 2_100_000), (102, 2_200_000)] {
+            let (reserve0, reserve1) = reserves(usdt);
+            let tx_hash = FixedBytes::from([u8::try_from(block - 99).unwrap(); 32]);
+            repo.insert_sync_event(
+                pool_id,
+                block,
+                FixedBytes::from([9u8; 32]),
+                1_706_745_600 + block * 12,
+                tx_hash,
+                0,
+                reserve0,
+                reserve1,
+                true,
+                &format!("sync-{block}"),
+            )
+            .await
+            .unwrap();
+            repo.insert_price_point(
+                pool_id,
+                block,
+                1_706_745_600 + block * 12,
+                tx_hash,
+                1.0,
+                reserve0,
+                reserve1,
+                1_000.0,
+                1.0,
+                true,
+                &format!("price-{block}"),
+            )
+            .await
+            .unwrap();
+        }
+
+        let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+        (repo, pool)
+    }
+
+    async fn prices(repo: &Repository, pool_id: i64) -> Vec<f64> {
+        let mut rows = repo.get_recent_prices(pool_id, 10).await.unwrap();
+        rows.reverse();
+        rows.iter().map(|p| p.price).collect()
+    }
+
+    #[tokio::test]
+    async fn test_shadow_replay_leaves_prices_alone() {
+        let (repo, pool) = setup().await;
+
+        let report = replay(&repo, &pool, 1, None, PriceMode::Event, None, false)
+            .await
+            .unwrap();
+        assert_eq!(report.blocks, Some((100, 102)));
+        assert_eq!(report.events, 3);
+        assert!(!report.applied);
+        assert_eq!(report.diff.changed, 3);
+        assert_eq!((report.diff.added, report.diff.removed), (0, 0));
+        assert!((report.diff.max_price_change - 2_199.0).abs() < 1e-9);
+
+        assert_eq!(prices(&repo, pool.id).await, vec![1.0, 1.0, 1.0]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_replaces_prices_and_candles() {
+        let (repo, pool) = setup().await;
+
+        let report = replay(&repo, &pool, 1, Some(60), PriceMode::Event, Some(101), true)
+            .await
+            .unwrap();
+        assert_eq!(report.blocks, Some((101, 102)));
+        assert!(report.applied);
+        assert_eq!(prices(&repo, pool.id).await, vec![1.0, 2_100.0, 2_200.0]);
+
+        let latest = repo.get_latest_price(pool.id).await.unwrap().unwrap();
+        assert!(latest.price_ewma.is_some());
+        assert!(latest.event_id.is_some());
+
+        let candles = repo.get_stored_candles(pool.id, 60, 0).await.unwrap();
+        assert_eq!(candles.last().unwrap().close, 2_200.0);
+
+        // The shadow table was emptied, so a second replay finds no changes
+        let again = replay(
+            &repo,
+            &pool,
+            1,
+            Some(60),
+            PriceMode::Event,
+            Some(101),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(again.diff, ReplayDiff::default());
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_events_is_a_no_op() {
+        let (repo, pool) = setup().await;
+        let report = replay(&repo, &pool, 1, None, PriceMode::Event, Some(200), true)
+            .await
+            .unwrap();
+        assert_eq!(report, ReplayReport::default());
+        assert_eq!(prices(&repo, pool.id).await, vec![1.0, 1.0, 1.0]);
+    }
+}
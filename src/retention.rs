@@ -0,0 +1,266 @@
+//! Retention policy and pruning of old rows.
+//!
+//! Each kind of data has its own retention period (unset = keep forever):
+//!
+//! - **Sync events**: raw `sync_events` rows older than the period are
+//!   deleted. Historical reserves for pruned blocks fall back to the archive
+//!   node.
+//! - **Price points**: `price_points` rows older than the period are deleted.
+//! - **Candles**: 1m candles older than the period are first rolled up into
+//!   daily candles, which are kept forever; then the 1m and 5m candles are
+//!   deleted.
+//!
+//! The latest sync event and price point of every pool are never pruned, so a
+//! quiet pool keeps its current price. Cutoffs for candles are aligned to UTC
+//! midnight so each day is rolled up once, from complete data.
+//!
+//! Pruning runs from the `prune` command or, when a policy is configured, as
+//! a background task in the API server. A run that deletes anything ends with
+//! `VACUUM` so the file actually shrinks.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::db::repository::Repository;
+use crate::error::TrackerResult;
+
+/// Width of the rolled-up candles kept forever, in seconds.
+pub const DAILY_CANDLE_SECS: i64 = 86_400;
+
+/// Default interval between background pruning runs (1 hour).
+pub const DEFAULT_PRUNE_INTERVAL_SECS: u64 = 3_600;
+
+/// How long each kind of data is kept, in days (`None` = forever).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Retention for raw sync events
+    pub sync_events_days: Option<u32>,
+    /// Retention for price points
+    pub price_points_days: Option<u32>,
+    /// Retention for 1m/5m candles (daily roll-ups are kept forever)
+    pub candles_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Returns true if any kind of data has a retention period.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.sync_events_days.is_some()
+            || self.price_points_days.is_some()
+            || self.candles_days.is_some()
+    }
+
+    /// Unix timestamp before which rows of a kind kept `days` are pruned.
+    const fn cutoff(now: i64, days: u32) -> i64 {
+        now - days as i64 * DAILY_CANDLE_SECS
+    }
+}
+
+/// Rows affected by one pruning run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Sync events deleted
+    pub sync_events_deleted: u64,
+    /// Price points deleted
+    pub price_points_deleted: u64,
+    /// Daily candles written from 1m candles
+    pub daily_candles_written: u64,
+    /// 1m and 5m candles deleted
+    pub candles_deleted: u64,
+    /// Whether the database was vacuumed
+    pub vacuumed: bool,
+}
+
+impl PruneReport {
+    /// Total rows deleted.
+    #[must_use]
+    pub const fn rows_deleted(&self) -> u64 {
+        self.sync_events_deleted + self.price_points_deleted + self.candles_deleted
+    }
+}
+
+/// Applies `policy` as of `now` (unix seconds).
+///
+/// With `vacuum`, the database is vacuumed if any rows were deleted.
+///
+/// # Errors
+///
+/// Returns an error if any delete, the candle roll-up or the vacuum fails.
+/// Steps that completed before the failure stay applied.
+pub async fn prune(
+    repository: &Repository,
+    policy: &RetentionPolicy,
+    now: i64,
+    vacuum: bool,
+) -> TrackerResult<PruneReport> {
+    let mut report = PruneReport::default();
+
+    if let Some(days) = policy.sync_events_days {
+        report.sync_events_deleted = repository
+            .prune_sync_events_before(RetentionPolicy::cutoff(now, days))
+            .await?;
+    }
+
+    if let Some(days) = policy.price_points_days {
+        report.price_points_deleted = repository
+            .prune_price_points_before(RetentionPolicy::cutoff(now, days))
+            .await?;
+    }
+
+    if let Some(days) = policy.candles_days {
+        let cutoff =
+            RetentionPolicy::cutoff(now, days).div_euclid(DAILY_CANDLE_SECS) * DAILY_CANDLE_SECS;
+        let (written, deleted) = repository
+            .roll_up_candles_before(cutoff, DAILY_CANDLE_SECS)
+            .await?;
+        report.daily_candles_written = written;
+        report.candles_deleted = deleted;
+    }
+
+    if vacuum && report.rows_deleted() > 0 {
+        repository.vacuum().await?;
+        report.vacuumed = true;
+    }
+
+    info!(
+        sync_events = report.sync_events_deleted,
+        price_points = report.price_points_deleted,
+        daily_candles = report.daily_candles_written,
+        candles = report.candles_deleted,
+        vacuumed = report.vacuumed,
+        "Pruned old rows"
+    );
+    Ok(report)
+}
+
+/// Spawns a task that prunes every `interval` (first run immediately).
+///
+/// Failed runs are logged and retried at the next interval.
+#[must_use]
+pub fn spawn_pruner(
+    repository: Arc<Repository>,
+    policy: RetentionPolicy,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = prune(&repository, &policy, chrono::Utc::now().timestamp(), true).await
+            {
+                warn!(error = %e, "Pruning failed");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::db::models::{CandleRow, PricePointRecord};
+    use alloy::primitives::{FixedBytes, U256};
+
+    const DAY: i64 = DAILY_CANDLE_SECS;
+
+    fn candle(bucket_start: i64, open: f64, close: f64) -> CandleRow {
+        CandleRow {
+            bucket_start,
+            open,
+            high: open.max(close),
+            low: open.min(close),
+            close,
+            samples: 2,
+            price_sum: open + close,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_latest_price_and_recent_rows() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+        let now = 100 * DAY;
+
+        // Two old prices and one recent one
+        let prices = [(1_u64, 10 * DAY), (2, 11 * DAY), (3, 99 * DAY)]
+            .into_iter()
+            .map(|(block, ts)| {
+                PricePointRecord::new(
+                    pool_id,
+                    block,
+                    u64::try_from(ts).unwrap(),
+                    FixedBytes::from([u8::try_from(block).unwrap(); 32]),
+                    2_000.0,
+                    U256::from(1u64),
+                    U256::from(1u64),
+                    1.0,
+                    2_000.0,
+                    true,
+                )
+            })
+            .collect();
+        repo.batch_insert_price_points(prices).await.unwrap();
+
+        let policy = RetentionPolicy {
+            price_points_days: Some(30),
+            ..RetentionPolicy::default()
+        };
+        let report = prune(&repo, &policy, now, false).await.unwrap();
+        assert_eq!(report.price_points_deleted, 2);
+        assert!(!report.vacuumed);
+
+        // Everything is old now, but the latest price survives
+        let report = prune(&repo, &policy, 1_000 * DAY, false).await.unwrap();
+        assert_eq!(report.price_points_deleted, 0);
+        let latest = repo.get_latest_price(pool_id).await.unwrap().unwrap();
+        assert_eq!(latest.block_number, 3);
+    }
+
+    #[tokio::test]
+    async fn test_prune_rolls_up_candles_into_days() {
+        let repo = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repo.ensure_default_pool().await.unwrap();
+
+        // Day 10: two 1m candles and a 5m candle; day 50: one 1m candle
+        repo.upsert_candles(
+            pool_id,
+            60,
+            &[
+                candle(10 * DAY + 60, 100.0, 110.0),
+                candle(10 * DAY + 600, 90.0, 95.0),
+                candle(50 * DAY, 200.0, 200.0),
+            ],
+        )
+        .await
+        .unwrap();
+        repo.upsert_candles(pool_id, 300, &[candle(10 * DAY, 100.0, 95.0)])
+            .await
+            .unwrap();
+
+        let policy = RetentionPolicy {
+            candles_days: Some(30),
+            ..RetentionPolicy::default()
+        };
+        let report = prune(&repo, &policy, 50 * DAY + 3_600, true).await.unwrap();
+        assert_eq!(report.daily_candles_written, 1);
+        assert_eq!(report.candles_deleted, 3);
+
+        let days = repo.get_stored_candles(pool_id, DAY, 0).await.unwrap();
+        assert_eq!(days.len(), 1);
+        let day = &days[0];
+        assert_eq!(day.bucket_start, 10 * DAY);
+        assert!((day.open - 100.0).abs() < f64::EPSILON);
+        assert!((day.high - 110.0).abs() < f64::EPSILON);
+        assert!((day.low - 90.0).abs() < f64::EPSILON);
+        assert!((day.close - 95.0).abs() < f64::EPSILON);
+        assert_eq!(day.samples, 4);
+
+        // The recent minute candle is untouched
+        assert_eq!(
+            repo.get_stored_candles(pool_id, 60, 0).await.unwrap().len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! Token routing graph over registered pools.
+//!
+//! Every pool is an edge between its two tokens, identified by address.
+//! [`TokenGraph::route`] finds the path with the fewest hops from one token
+//! to another (at most [`MAX_HOPS`]), and each [`Hop`] turns the pool's stored
+//! price into the rate between the tokens it connects.
+//!
+//! This is how a pool quoted in a token other than a dollar (e.g. WETH/WBTC)
+//! is denominated in USD: its quote token is routed to one of
+//! [`USD_STABLECOINS`] through the other indexed pools, and the price is
+//! multiplied by the rate of every hop on the way.
+//!
+//! [`TokenGraph::routes`] lists every route between two tokens instead, and
+//! [`best_route`] simulates a swap along each at the pools' latest reserves,
+//! picking the one with the largest output; `/api/v1/route` serves it.
+
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::adapters::PriceAdapter;
+use crate::db::models::PoolRecord;
+use crate::error::TrackerError;
+use crate::pricing::{QuoteDirection, SwapQuote};
+
+/// Most pools a route may pass through.
+pub const MAX_HOPS: usize = 3;
+
+/// Symbols of the tokens counted as one US dollar.
+pub const USD_STABLECOINS: &[&str] = &["USDT", "USDC", "DAI"];
+
+/// Unit a price can be denominated in instead of the pool's quote token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Denomination {
+    /// US dollars, through a USD stablecoin pool
+    Usd,
+}
+
+impl FromStr for Denomination {
+    type Err = TrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "usd" => Ok(Self::Usd),
+            _ => Err(TrackerError::config(
+                format!("Denomination must be usd, got: {s}"),
+                None,
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Denomination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Usd => "usd",
+        })
+    }
+}
+
+/// A token of a registered pool.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Token {
+    /// Lowercase hex address
+    pub address: String,
+    /// Symbol, or the address if the pool has none
+    pub symbol: String,
+    /// Decimal places
+    pub decimals: u8,
+}
+
+impl Token {
+    /// Returns a pool's token0 or token1.
+    #[must_use]
+    pub fn of_pool(pool: &PoolRecord, token0: bool) -> Self {
+        let (address, symbol, decimals) = if token0 {
+            (
+                &pool.token0_address,
+                &pool.token0_symbol,
+                pool.token0_decimals,
+            )
+        } else {
+            (
+                &pool.token1_address,
+                &pool.token1_symbol,
+                pool.token1_decimals,
+            )
+        };
+        Self {
+            address: address.to_ascii_lowercase(),
+            symbol: symbol.clone().unwrap_or_else(|| address.clone()),
+            decimals: u8::try_from(decimals).unwrap_or(18),
+        }
+    }
+
+    /// Whether `query` names the token, by symbol or address (any case).
+    #[must_use]
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim();
+        self.symbol.eq_ignore_ascii_case(query) || self.address.eq_ignore_ascii_case(query)
+    }
+
+    /// Returns the token a pool's prices are quoted in, in `direction`.
+    #[must_use]
+    pub fn quote_of(pool: &PoolRecord, direction: QuoteDirection) -> Self {
+        Self::of_pool(pool, direction.is_inverse())
+    }
+
+    /// Whether the token is one of [`USD_STABLECOINS`].
+    #[must_use]
+    pub fn is_usd(&self) -> bool {
+        USD_STABLECOINS
+            .iter()
+            .any(|usd| usd.eq_ignore_ascii_case(&self.symbol))
+    }
+}
+
+/// One pool on a route, from one of its tokens to the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hop {
+    /// Pool database ID
+    pub pool_id: i64,
+    /// Pool name, or its address if it has none
+    pub pool: String,
+    /// Token the hop starts from
+    pub from: Token,
+    /// Token the hop ends at
+    pub to: Token,
+    /// Whether `from` is the pool's token0
+    from_token0: bool,
+}
+
+impl Hop {
+    /// Converts the pool's stored price (token1 per token0) into the rate of
+    /// `to` per `from`. Returns `None` for a zero price.
+    #[must_use]
+    pub fn rate(&self, stored_price: f64) -> Option<f64> {
+        if stored_price <= 0.0 {
+            None
+        } else if self.from_token0 {
+            Some(stored_price)
+        } else {
+            Some(stored_price.recip())
+        }
+    }
+}
+
+/// Tokens connected by the pools that trade them.
+#[derive(Debug, Default)]
+pub struct TokenGraph {
+    /// Tokens in the order their pools were registered
+    tokens: Vec<Token>,
+    edges: HashMap<String, Vec<Hop>>,
+}
+
+impl TokenGraph {
+    /// Builds the graph of the given pools.
+    #[must_use]
+    pub fn new(pools: &[PoolRecord]) -> Self {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut edges: HashMap<String, Vec<Hop>> = HashMap::new();
+        for pool in pools {
+            let name = pool
+                .name
+                .clone()
+                .unwrap_or_else(|| pool.address.to_string());
+            let token0 = Token::of_pool(pool, true);
+            let token1 = Token::of_pool(pool, false);
+            for token in [&token0, &token1] {
+                if !tokens.iter().any(|known| known.address == token.address) {
+                    tokens.push(token.clone());
+                }
+            }
+            for (from, to, from_token0) in [
+                (token0.clone(), token1.clone(), true),
+                (token1, token0, false),
+            ] {
+                edges.entry(from.address.clone()).or_default().push(Hop {
+                    pool_id: pool.id,
+                    pool: name.clone(),
+                    from,
+                    to,
+                    from_token0,
+                });
+            }
+        }
+        Self { tokens, edges }
+    }
+
+    /// Returns the token named by `query` (symbol or address); of tokens
+    /// sharing a symbol, the first registered.
+    #[must_use]
+    pub fn token(&self, query: &str) -> Option<&Token> {
+        self.tokens.iter().find(|token| token.matches(query))
+    }
+
+    /// Returns the shortest route from `from` to a token matching `target`,
+    /// empty if `from` matches itself, or `None` if no route of at most
+    /// [`MAX_HOPS`] pools exists.
+    #[must_use]
+    pub fn route(&self, from: &Token, target: impl Fn(&Token) -> bool) -> Option<Vec<Hop>> {
+        if target(from) {
+            return Some(Vec::new());
+        }
+
+        let mut visited = HashSet::from([from.address.clone()]);
+        let mut queue = VecDeque::from([(from.address.clone(), Vec::<Hop>::new())]);
+        while let Some((address, route)) = queue.pop_front() {
+            if route.len() == MAX_HOPS {
+                continue;
+            }
+            for hop in self.edges.get(&address).into_iter().flatten() {
+                if !visited.insert(hop.to.address.clone()) {
+                    continue;
+                }
+                let mut next = route.clone();
+                next.push(hop.clone());
+                if target(&hop.to) {
+                    return Some(next);
+                }
+                queue.push_back((hop.to.address.clone(), next));
+            }
+        }
+        None
+    }
+
+    /// Returns the shortest route from `from` to a token of `denomination`.
+    #[must_use]
+    pub fn route_to(&self, from: &Token, denomination: Denomination) -> Option<Vec<Hop>> {
+        match denomination {
+            Denomination::Usd => self.route(from, Token::is_usd),
+        }
+    }
+
+    /// Returns every route of at most [`MAX_HOPS`] pools from `from` to `to`
+    /// that passes through each token once.
+    #[must_use]
+    pub fn routes(&self, from: &Token, to: &Token) -> Vec<Vec<Hop>> {
+        let mut routes = Vec::new();
+        self.extend(
+            &from.address,
+            &to.address,
+            &mut vec![from.address.clone()],
+            &mut Vec::new(),
+            &mut routes,
+        );
+        routes
+    }
+
+    fn extend(
+        &self,
+        at: &str,
+        to: &str,
+        visited: &mut Vec<String>,
+        route: &mut Vec<Hop>,
+        routes: &mut Vec<Vec<Hop>>,
+    ) {
+        if route.len() == MAX_HOPS {
+            return;
+        }
+        for hop in self.edges.get(at).into_iter().flatten() {
+            if visited.contains(&hop.to.address) {
+                continue;
+            }
+            route.push(hop.clone());
+            if hop.to.address == to {
+                routes.push(route.clone());
+            } else {
+                visited.push(hop.to.address.clone());
+                self.extend(&hop.to.address, to, visited, route, routes);
+                visited.pop();
+            }
+            route.pop();
+        }
+    }
+}
+
+/// A pool's latest reserves and pricing formula, for simulating swaps.
+pub struct PoolLiquidity {
+    /// Raw reserve of token0
+    pub reserve0: U256,
+    /// Raw reserve of token1
+    pub reserve1: U256,
+    /// Block of the reserves
+    pub block_number: u64,
+    /// The pool's pricing formula
+    pub adapter: Box<dyn PriceAdapter>,
+}
+
+/// A swap simulated through one pool of a route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HopQuote {
+    /// The pool and direction
+    pub hop: Hop,
+    /// Amount sold into the pool, in `hop.from`'s smallest units
+    pub amount_in: U256,
+    /// Block of the reserves the swap was simulated at
+    pub block_number: u64,
+    /// Swap fee in basis points
+    pub fee_bps: u32,
+    /// The simulated swap
+    pub quote: SwapQuote,
+}
+
+/// A swap simulated along a whole route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteQuote {
+    /// One swap per pool, in order
+    pub hops: Vec<HopQuote>,
+    /// Amount received from the last pool, in its output token's smallest units
+    pub amount_out: U256,
+}
+
+impl RouteQuote {
+    /// Output tokens per input token before the swaps.
+    #[must_use]
+    pub fn spot_price(&self) -> f64 {
+        self.hops.iter().map(|h| h.quote.spot_price).product()
+    }
+
+    /// Output tokens per input token received.
+    #[must_use]
+    pub fn execution_price(&self) -> f64 {
+        self.hops.iter().map(|h| h.quote.execution_price).product()
+    }
+
+    /// How much worse the execution price is than the spot price, in percent
+    /// (includes every pool's fee).
+    #[must_use]
+    pub fn price_impact_pct(&self) -> f64 {
+        let spot = self.spot_price();
+        if spot > 0.0 {
+            (1.0 - self.execution_price() / spot) * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Simulates selling `amount_in` along `route`, feeding each pool's output
+/// into the next. Returns `None` if a pool has no reserves in `liquidity` or
+/// a swap fails.
+#[must_use]
+pub fn quote_route(
+    route: &[Hop],
+    amount_in: U256,
+    liquidity: &HashMap<i64, PoolLiquidity>,
+) -> Option<RouteQuote> {
+    let mut amount = amount_in;
+    let mut hops = Vec::with_capacity(route.len());
+    for hop in route {
+        let pool = liquidity.get(&hop.pool_id)?;
+        let (reserve_in, reserve_out) = if hop.from_token0 {
+            (pool.reserve0, pool.reserve1)
+        } else {
+            (pool.reserve1, pool.reserve0)
+        };
+        let quote = pool
+            .adapter
+            .quote(
+                amount,
+                reserve_in,
+                reserve_out,
+                hop.from.decimals,
+                hop.to.decimals,
+            )
+            .ok()?;
+        if quote.amount_out.is_zero() {
+            return None;
+        }
+        hops.push(HopQuote {
+            hop: hop.clone(),
+            amount_in: amount,
+            block_number: pool.block_number,
+            fee_bps: pool.adapter.fee_bps(),
+            quote,
+        });
+        amount = quote.amount_out;
+    }
+    Some(RouteQuote {
+        hops,
+        amount_out: amount,
+    })
+}
+
+/// Simulates selling `amount_in` along every route and returns the one with
+/// the largest output, or `None` if no route can be simulated.
+#[must_use]
+pub fn best_route(
+    routes: &[Vec<Hop>],
+    amount_in: U256,
+    liquidity: &HashMap<i64, PoolLiquidity>,
+) -> Option<RouteQuote> {
+    routes
+        .iter()
+        .filter_map(|route| quote_route(route, amount_in, liquidity))
+        .max_by_key(|quote| quote.amount_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Address;
+
+    fn pool(id: i64, token0: (u8, &str), token1: (u8, &str)) -> PoolRecord {
+        let mut pool = PoolRecord::new(
+            Address::repeat_byte(u8::try_from(id).unwrap()),
+            Some(format!("{}/{}", token0.1, token1.1)),
+            Address::repeat_byte(token0.0),
+            Some(token0.1.to_string()),
+            18,
+            Address::repeat_byte(token1.0),
+            Some(token1.1.to_string()),
+            18,
+        );
+        pool.id = id;
+        pool
+    }
+
+    #[test]
+    fn test_route_to_usd_takes_the_fewest_hops() {
+        let pools = [
+            pool(1, (0xee, "WETH"), (0xbb, "WBTC")),
+            pool(2, (0xee, "WETH"), (0x11, "USDT")),
+            pool(3, (0xbb, "WBTC"), (0xcc, "LINK")),
+            pool(4, (0xcc, "LINK"), (0x22, "USDC")),
+            pool(5, (0xdd, "PEPE"), (0xcc, "LINK")),
+        ];
+        let graph = TokenGraph::new(&pools);
+        let wbtc = Token::of_pool(&pools[0], false);
+
+        let route = graph.route_to(&wbtc, Denomination::Usd).unwrap();
+        assert_eq!(route.len(), 2);
+        assert_eq!(route[0].pool, "WETH/WBTC");
+        assert_eq!(
+            (route[0].from.symbol.as_str(), route[0].to.symbol.as_str()),
+            ("WBTC", "WETH")
+        );
+        assert_eq!(route[1].pool, "WETH/USDT");
+
+        // Quoted in USD already: nothing to convert
+        let usdt = Token::of_pool(&pools[1], false);
+        assert_eq!(graph.route_to(&usdt, Denomination::Usd), Some(Vec::new()));
+
+        // PEPE -> LINK -> USDC
+        let pepe = Token::of_pool(&pools[4], true);
+        assert_eq!(graph.route_to(&pepe, Denomination::Usd).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_route_gives_up_beyond_max_hops() {
+        let pools = [
+            pool(1, (0x01, "A"), (0x02, "B")),
+            pool(2, (0x02, "B"), (0x03, "C")),
+            pool(3, (0x03, "C"), (0x04, "D")),
+            pool(4, (0x04, "D"), (0x05, "DAI")),
+        ];
+        let graph = TokenGraph::new(&pools);
+        let a = Token::of_pool(&pools[0], true);
+        let b = Token::of_pool(&pools[0], false);
+        assert!(graph.route_to(&a, Denomination::Usd).is_none());
+        assert_eq!(graph.route_to(&b, Denomination::Usd).unwrap().len(), 3);
+        assert!(TokenGraph::default()
+            .route_to(&a, Denomination::Usd)
+            .is_none());
+    }
+
+    #[test]
+    fn test_hop_rate_follows_direction() {
+        let pools = [pool(1, (0xee, "WETH"), (0x11, "USDT"))];
+        let graph = TokenGraph::new(&pools);
+        let weth = Token::of_pool(&pools[0], true);
+        let usdt = Token::of_pool(&pools[0], false);
+
+        let forward = &graph.route_to(&weth, Denomination::Usd).unwrap()[0];
+        assert_eq!(forward.rate(2_000.0), Some(2_000.0));
+        let backward = &graph.route(&usdt, |t| t.symbol == "WETH").unwrap()[0];
+        assert_eq!(backward.rate(2_000.0), Some(0.0005));
+        assert_eq!(backward.rate(0.0), None);
+    }
+
+    #[test]
+    fn test_best_route_picks_the_largest_output() {
+        use crate::adapters::ConstantProduct;
+
+        let pools = [
+            // A thin direct pool and a deep two-hop route
+            pool(1, (0xbb, "WBTC"), (0x22, "USDC")),
+            pool(2, (0xee, "WETH"), (0xbb, "WBTC")),
+            pool(3, (0xee, "WETH"), (0x22, "USDC")),
+        ];
+        let graph = TokenGraph::new(&pools);
+        let wbtc = graph.token("wbtc").unwrap().clone();
+        let usdc = graph
+            .token(&format!("{:?}", Address::repeat_byte(0x22)))
+            .unwrap()
+            .clone();
+        assert_eq!(usdc.symbol, "USDC");
+        assert!(graph.token("DAI").is_none());
+
+        let routes = graph.routes(&wbtc, &usdc);
+        assert_eq!(routes.len(), 2);
+        assert!(graph.routes(&wbtc, &wbtc).is_empty());
+
+        let units = |amount: u64| U256::from(amount) * U256::from(10_u64).pow(U256::from(18));
+        let liquidity = |id: i64, reserve0: u64, reserve1: u64| {
+            (
+                id,
+                PoolLiquidity {
+                    reserve0: units(reserve0),
+                    reserve1: units(reserve1),
+                    block_number: 100,
+                    adapter: Box::new(ConstantProduct { fee_bps: 30 }),
+                },
+            )
+        };
+        let mut reserves: HashMap<i64, PoolLiquidity> = HashMap::from([
+            liquidity(1, 1, 60_000),
+            liquidity(2, 20_000, 1_000),
+            liquidity(3, 20_000, 60_000_000),
+        ]);
+
+        let best = best_route(&routes, units(1), &reserves).unwrap();
+        assert_eq!(best.hops.len(), 2);
+        assert_eq!(best.hops[0].hop.pool, "WETH/WBTC");
+        assert_eq!(best.hops[1].amount_in, best.hops[0].quote.amount_out);
+        assert!(best.execution_price() > 59_000.0);
+        assert!(best.price_impact_pct() > 0.6 && best.price_impact_pct() < 1.0);
+
+        // Without reserves for the deep pools only the direct route is left
+        reserves.remove(&3);
+        let direct = best_route(&routes, units(1), &reserves).unwrap();
+        assert_eq!(direct.hops.len(), 1);
+        assert!(direct.execution_price() < 30_000.0);
+        reserves.clear();
+        assert!(best_route(&routes, units(1), &reserves).is_none());
+    }
+
+    #[test]
+    fn test_denomination_parses() {
+        assert_eq!("usd".parse::<Denomination>().unwrap(), Denomination::Usd);
+        assert_eq!("USD".parse::<Denomination>().unwrap(), Denomination::Usd);
+        assert!("eur".parse::<Denomination>().is_err());
+        assert_eq!(Denomination::Usd.to_string(), "usd");
+    }
+}
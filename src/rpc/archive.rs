@@ -0,0 +1,179 @@
+//! Routing of historical state reads to an archive node.
+//!
+//! Logs and block headers are kept by every node, but full nodes prune the
+//! state of older blocks: `eth_call` (e.g. `getReserves()`) only works for
+//! roughly the last [`FULL_NODE_STATE_BLOCKS`] blocks. Older state needs an
+//! archive node.
+//!
+//! [`StateRouter`] picks the provider for a state read at a given block: the
+//! primary `RPC_URL` for recent blocks or when it is an archive node (see
+//! [`ProviderCapabilities::archive`](super::ProviderCapabilities::archive)),
+//! otherwise `RPC_ARCHIVE_URL`. Without either, reads of older blocks fail
+//! fast with a [`TrackerError::ConfigError`] naming the missing setting,
+//! instead of a "missing trie node" error from the provider.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use eth_uniswap_alloy::events::{fetch_reserves_at, UNISWAP_V2_WETH_USDT_PAIR};
+//! use eth_uniswap_alloy::rpc::{archive::StateRouter, create_provider};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let full_node = create_provider("http://localhost:8545").await?;
+//! let archive = create_provider("https://eth-mainnet.g.alchemy.com/v2/KEY").await?;
+//! let router = StateRouter::new(Arc::new(full_node), Some(false)).with_archive(Arc::new(archive));
+//!
+//! let provider = router.provider_at(10_000_000).await?;
+//! let reserves = fetch_reserves_at(provider, UNISWAP_V2_WETH_USDT_PAIR, 10_000_000).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use super::http::{get_latest_block, Provider};
+use crate::error::{TrackerError, TrackerResult};
+
+/// Blocks behind the head whose state a full node still serves.
+pub const FULL_NODE_STATE_BLOCKS: u64 = 128;
+
+/// Picks the provider for state reads at historical blocks.
+#[derive(Debug, Clone)]
+pub struct StateRouter {
+    primary: Arc<Provider>,
+    /// Whether `primary` is an archive node (`None` if unknown)
+    primary_archive: Option<bool>,
+    archive: Option<Arc<Provider>>,
+}
+
+impl StateRouter {
+    /// Routes every read to `primary`, except older blocks when
+    /// `primary_archive` is `Some(false)`.
+    #[must_use]
+    pub const fn new(primary: Arc<Provider>, primary_archive: Option<bool>) -> Self {
+        Self {
+            primary,
+            primary_archive,
+            archive: None,
+        }
+    }
+
+    /// Reads state older than [`FULL_NODE_STATE_BLOCKS`] from `archive`,
+    /// unless the primary provider is known to be an archive node.
+    #[must_use]
+    pub fn with_archive(mut self, archive: Arc<Provider>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Whether `block` may be older than the primary provider's state.
+    fn needs_head(&self) -> bool {
+        self.primary_archive != Some(true)
+            && (self.archive.is_some() || self.primary_archive == Some(false))
+    }
+
+    /// Returns the provider to read state at `block` from, with the chain
+    /// head at `head`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a configuration error if `block` is more than
+    /// [`FULL_NODE_STATE_BLOCKS`] behind `head`, the primary provider is not
+    /// an archive node and no archive provider is configured.
+    pub fn route(&self, block: u64, head: u64) -> TrackerResult<&Provider> {
+        let age = head.saturating_sub(block);
+        if age <= FULL_NODE_STATE_BLOCKS || !self.needs_head() {
+            return Ok(&self.primary);
+        }
+        match &self.archive {
+            Some(archive) => Ok(archive),
+            None => Err(TrackerError::config(
+                format!(
+                    "Reading state at block {block} ({age} blocks behind the head) needs an \
+                     archive node, but RPC_URL only keeps the last {FULL_NODE_STATE_BLOCKS} \
+                     blocks; set RPC_ARCHIVE_URL to an archive endpoint"
+                ),
+                None,
+            )),
+        }
+    }
+
+    /// Returns the provider to read state at `block` from, looking up the
+    /// chain head only if the choice depends on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the head can't be fetched, or as
+    /// [`route`](Self::route).
+    pub async fn provider_at(&self, block: u64) -> TrackerResult<&Provider> {
+        if !self.needs_head() {
+            return Ok(&self.primary);
+        }
+        let head = get_latest_block(&self.primary).await?;
+        self.route(block, head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::create_provider;
+
+    async fn provider() -> Arc<Provider> {
+        Arc::new(create_provider("http://localhost:8545").await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_route_sends_old_blocks_to_archive() {
+        let primary = provider().await;
+        let archive = provider().await;
+        let head = 1_000_000;
+
+        let router =
+            StateRouter::new(Arc::clone(&primary), Some(false)).with_archive(Arc::clone(&archive));
+        let recent = router.route(head - FULL_NODE_STATE_BLOCKS, head).unwrap();
+        assert!(std::ptr::eq(recent, primary.as_ref()));
+        let old = router
+            .route(head - FULL_NODE_STATE_BLOCKS - 1, head)
+            .unwrap();
+        assert!(std::ptr::eq(old, archive.as_ref()));
+
+        // Unknown archive status: the configured archive is safer
+        let router =
+            StateRouter::new(Arc::clone(&primary), None).with_archive(Arc::clone(&archive));
+        assert!(std::ptr::eq(
+            router.route(1, head).unwrap(),
+            archive.as_ref()
+        ));
+
+        // An archive primary serves everything
+        let router = StateRouter::new(Arc::clone(&primary), Some(true)).with_archive(archive);
+        assert!(std::ptr::eq(
+            router.route(1, head).unwrap(),
+            primary.as_ref()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_route_fails_fast_without_archive() {
+        let primary = provider().await;
+
+        let router = StateRouter::new(Arc::clone(&primary), Some(false));
+        assert!(router.route(900, 1_000).is_ok());
+        let err = router.route(1, 1_000).unwrap_err();
+        assert!(matches!(err, TrackerError::ConfigError { .. }));
+        assert!(err.to_string().contains("RPC_ARCHIVE_URL"));
+
+        // Unknown: try the primary rather than refuse
+        let router = StateRouter::new(Arc::clone(&primary), None);
+        assert!(std::ptr::eq(
+            router.route(1, 1_000).unwrap(),
+            primary.as_ref()
+        ));
+        assert!(std::ptr::eq(
+            router.provider_at(1).await.unwrap(),
+            primary.as_ref()
+        ));
+    }
+}
@@ -0,0 +1,315 @@
+//! Provider capability detection.
+//!
+//! RPC providers differ in what they serve: how many blocks one
+//! `eth_getLogs` may span (10 on Alchemy's free tier, any range on a local
+//! node), whether JSON-RPC batches are accepted, whether state older than
+//! the last few thousand blocks is kept (archive nodes), and whether the
+//! WebSocket endpoint can be reached. [`probe`] asks the provider once at
+//! startup:
+//!
+//! - `watch` and `backfill` split block ranges into `eth_getLogs` calls no
+//!   larger than the detected range
+//! - `watch` polls over HTTP in hybrid mode when the WebSocket endpoint
+//!   can't be reached
+//! - the API server reports the result in `/health`
+//!
+//! A probe that fails or times out leaves its capability unknown. For the
+//! log range, [`KNOWN_PROVIDERS`] then supplies the documented limit of
+//! hosted providers, matched by host name.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use eth_uniswap_alloy::rpc::{capabilities, create_provider};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let url = "https://eth-mainnet.g.alchemy.com/v2/KEY";
+//! let provider = create_provider(url).await?;
+//! let capabilities = capabilities::probe(&provider, url, None).await;
+//! println!("Fetching at most {} blocks per call", capabilities.log_range(2_000));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use alloy::providers::Provider as _;
+use alloy::rpc::types::Filter;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+use super::http::Provider;
+use super::websocket::WebSocketProvider;
+
+/// How long each probe request may take.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `eth_getLogs` spans tried, largest first; the first accepted one is the
+/// detected range.
+const LOG_RANGE_CANDIDATES: [u64; 7] = [100_000, 10_000, 2_000, 1_000, 500, 100, 10];
+
+/// Blocks behind the head whose state only archive nodes still serve.
+///
+/// Full nodes prune historical state after at most a few days; this is
+/// several months.
+const ARCHIVE_PROBE_DEPTH: u64 = 1_000_000;
+
+/// Documented limits of a hosted RPC provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownProvider {
+    /// Provider name
+    pub name: &'static str,
+    /// Suffix of the RPC host name
+    pub host_suffix: &'static str,
+    /// Most blocks one `eth_getLogs` call may span on the smallest plan
+    pub max_log_range: Option<u64>,
+    /// Most logs one `eth_getLogs` response may hold
+    pub max_logs_per_response: Option<usize>,
+}
+
+/// Hosted providers with published `eth_getLogs` limits.
+///
+/// Limits vary by plan and change over time, so these are only used for
+/// capabilities the probe could not detect.
+pub const KNOWN_PROVIDERS: &[KnownProvider] = &[
+    KnownProvider {
+        name: "Alchemy",
+        host_suffix: "alchemy.com",
+        max_log_range: Some(10),
+        max_logs_per_response: Some(10_000),
+    },
+    KnownProvider {
+        name: "Infura",
+        host_suffix: "infura.io",
+        max_log_range: None,
+        max_logs_per_response: Some(10_000),
+    },
+    KnownProvider {
+        name: "QuickNode",
+        host_suffix: "quiknode.pro",
+        max_log_range: Some(10_000),
+        max_logs_per_response: None,
+    },
+];
+
+/// Looks up the hosted provider serving `rpc_url`.
+#[must_use]
+pub fn known_provider(rpc_url: &str) -> Option<&'static KnownProvider> {
+    let url = reqwest::Url::parse(rpc_url).ok()?;
+    let host = url.host_str()?;
+    KNOWN_PROVIDERS.iter().find(|known| {
+        host == known.host_suffix || host.ends_with(&format!(".{}", known.host_suffix))
+    })
+}
+
+/// What an RPC provider supports, as detected by [`probe`].
+///
+/// `None` means unknown: the probe failed or timed out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProviderCapabilities {
+    /// Hosted provider matched by host name
+    pub provider: Option<String>,
+    /// `web3_clientVersion`
+    pub client_version: Option<String>,
+    /// Largest `eth_getLogs` block span accepted
+    pub max_log_range: Option<u64>,
+    /// Most logs one `eth_getLogs` response may hold, from [`KNOWN_PROVIDERS`]
+    pub max_logs_per_response: Option<usize>,
+    /// Whether JSON-RPC batch requests are answered
+    pub batch_requests: Option<bool>,
+    /// Whether state [`ARCHIVE_PROBE_DEPTH`] blocks old is served
+    pub archive: Option<bool>,
+    /// Whether the WebSocket endpoint accepted a connection (`None` without
+    /// `RPC_WS_URL`)
+    pub websocket: Option<bool>,
+    /// When the provider was probed
+    pub probed_at: DateTime<Utc>,
+}
+
+impl ProviderCapabilities {
+    /// Capabilities of a provider that wasn't probed, from the registry only.
+    #[must_use]
+    pub fn unprobed(rpc_url: &str) -> Self {
+        let known = known_provider(rpc_url);
+        Self {
+            provider: known.map(|k| k.name.to_string()),
+            client_version: None,
+            max_log_range: known.and_then(|k| k.max_log_range),
+            max_logs_per_response: known.and_then(|k| k.max_logs_per_response),
+            batch_requests: None,
+            archive: None,
+            websocket: None,
+            probed_at: Utc::now(),
+        }
+    }
+
+    /// Blocks per `eth_getLogs` call: `requested`, capped at the detected
+    /// range.
+    #[must_use]
+    pub fn log_range(&self, requested: u64) -> u64 {
+        self.max_log_range
+            .map_or(requested, |max| requested.min(max))
+            .max(1)
+    }
+}
+
+/// Probes `provider` (served at `rpc_url`) and, if given, the WebSocket
+/// endpoint at `ws_url`.
+///
+/// Never fails: capabilities that can't be detected are left unknown, or
+/// taken from [`KNOWN_PROVIDERS`].
+pub async fn probe(
+    provider: &Provider,
+    rpc_url: &str,
+    ws_url: Option<&str>,
+) -> ProviderCapabilities {
+    let mut capabilities = ProviderCapabilities::unprobed(rpc_url);
+
+    let head = timed(provider.get_block_number()).await;
+    let (client_version, max_log_range, batch_requests, archive, websocket) = tokio::join!(
+        timed(provider.get_client_version()),
+        async {
+            match head {
+                Some(head) => probe_log_range(provider, head).await,
+                None => None,
+            }
+        },
+        probe_batch(rpc_url),
+        async {
+            let head = head?;
+            let block = head.saturating_sub(ARCHIVE_PROBE_DEPTH);
+            let balance = provider.get_balance(Address::ZERO).block_id(block.into());
+            Some(timed(balance).await.is_some())
+        },
+        async {
+            let url = ws_url?;
+            let connect = WebSocketProvider::connect(url.to_string());
+            Some(timed(connect).await.is_some())
+        },
+    );
+
+    capabilities.client_version = client_version;
+    capabilities.max_log_range = max_log_range.or(capabilities.max_log_range);
+    capabilities.batch_requests = batch_requests;
+    capabilities.archive = archive;
+    capabilities.websocket = websocket;
+
+    info!(
+        provider = capabilities.provider.as_deref().unwrap_or("unknown"),
+        client = capabilities.client_version.as_deref().unwrap_or("unknown"),
+        max_log_range = ?capabilities.max_log_range,
+        batch_requests = ?capabilities.batch_requests,
+        archive = ?capabilities.archive,
+        websocket = ?capabilities.websocket,
+        "Probed RPC provider"
+    );
+    capabilities
+}
+
+/// Runs a call with [`PROBE_TIMEOUT`], discarding errors.
+async fn timed<T, E>(call: impl std::future::IntoFuture<Output = Result<T, E>>) -> Option<T> {
+    tokio::time::timeout(PROBE_TIMEOUT, call.into_future())
+        .await
+        .ok()?
+        .ok()
+}
+
+/// Largest span of [`LOG_RANGE_CANDIDATES`] that `eth_getLogs` accepts,
+/// ending at `head`.
+///
+/// The filter matches no contract, so responses stay empty whatever the
+/// span; chains shorter than a span are queried from genesis.
+async fn probe_log_range(provider: &Provider, head: u64) -> Option<u64> {
+    for span in LOG_RANGE_CANDIDATES {
+        let from = head.saturating_sub(span - 1);
+        let filter = Filter::new()
+            .address(Address::ZERO)
+            .from_block(from)
+            .to_block(head);
+        match tokio::time::timeout(PROBE_TIMEOUT, provider.get_logs(&filter)).await {
+            Ok(Ok(_)) => return Some(span),
+            Ok(Err(e)) => debug!(span, error = %e, "eth_getLogs span rejected"),
+            Err(_) => debug!(span, "eth_getLogs span timed out"),
+        }
+    }
+    None
+}
+
+/// Whether `rpc_url` answers a JSON-RPC batch of two calls.
+async fn probe_batch(rpc_url: &str) -> Option<bool> {
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .ok()?;
+    let batch = json!([
+        {"jsonrpc": "2.0", "id": 1, "method": "eth_blockNumber", "params": []},
+        {"jsonrpc": "2.0", "id": 2, "method": "eth_chainId", "params": []},
+    ]);
+    let response = client.post(rpc_url).json(&batch).send().await.ok()?;
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    Some(is_batch_answer(&body, 2))
+}
+
+/// Whether `body` answers all `calls` of a batch.
+fn is_batch_answer(body: &Value, calls: usize) -> bool {
+    body.as_array().is_some_and(|answers| {
+        answers.len() == calls && answers.iter().all(|answer| answer.get("result").is_some())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_provider_matches_host_suffix() {
+        let alchemy = known_provider("https://eth-mainnet.g.alchemy.com/v2/KEY").unwrap();
+        assert_eq!(alchemy.name, "Alchemy");
+        assert_eq!(
+            known_provider("https://mainnet.infura.io/v3/KEY").map(|k| k.name),
+            Some("Infura")
+        );
+        // Only whole host labels match
+        assert!(known_provider("https://notalchemy.com/v2/KEY").is_none());
+        assert!(known_provider("http://localhost:8545").is_none());
+        assert!(known_provider("not a url").is_none());
+    }
+
+    #[test]
+    fn test_log_range_caps_requested_span() {
+        let mut capabilities = ProviderCapabilities::unprobed("http://localhost:8545");
+        assert_eq!(capabilities.max_log_range, None);
+        assert_eq!(capabilities.log_range(2_000), 2_000);
+
+        capabilities.max_log_range = Some(500);
+        assert_eq!(capabilities.log_range(2_000), 500);
+        assert_eq!(capabilities.log_range(10), 10);
+        assert_eq!(capabilities.log_range(0), 1);
+
+        // The registry applies until a probe says otherwise
+        let alchemy = ProviderCapabilities::unprobed("https://eth-mainnet.g.alchemy.com/v2/KEY");
+        assert_eq!(alchemy.log_range(2_000), 10);
+    }
+
+    #[test]
+    fn test_is_batch_answer() {
+        let answers = json!([
+            {"jsonrpc": "2.0", "id": 1, "result": "0x10"},
+            {"jsonrpc": "2.0", "id": 2, "result": "0x1"},
+        ]);
+        assert!(is_batch_answer(&answers, 2));
+        assert!(!is_batch_answer(&answers, 3));
+
+        // Providers without batches answer with a single error object
+        let rejected = json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600}});
+        assert!(!is_batch_answer(&rejected, 2));
+        let partial = json!([
+            {"jsonrpc": "2.0", "id": 1, "result": "0x10"},
+            {"jsonrpc": "2.0", "id": 2, "error": {"code": -32005}},
+        ]);
+        assert!(!is_batch_answer(&partial, 2));
+    }
+}
@@ -19,6 +19,7 @@
 
 use crate::error::{TrackerError, TrackerResult};
 use alloy::providers::{Provider as AlloProvider, ProviderBuilder, RootProvider};
+use alloy::rpc::types::{BlockNumberOrTag, BlockTransactionsKind};
 use alloy::transports::http::{Client, Http};
 use tracing::{debug, info, instrument, warn};
 
@@ -155,6 +156,96 @@ pub async fn get_latest_block(provider: &Provider) -> TrackerResult<u64> {
     Ok(block_number)
 }
 
+/// Get the unix timestamp of block `number`.
+///
+/// # Errors
+///
+/// Returns an error if the header request fails or the block is missing.
+pub async fn get_block_timestamp(provider: &Provider, number: u64) -> TrackerResult<u64> {
+    provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(number),
+            BlockTransactionsKind::Hashes,
+        )
+        .await
+        .map_err(|e| {
+            TrackerError::rpc(format!("Failed to fetch block {number}"), Some(Box::new(e)))
+        })?
+        .map(|block| block.header.timestamp)
+        .ok_or_else(|| TrackerError::rpc(format!("Block {number} not found"), None))
+}
+
+/// Find the last block mined at or before a unix timestamp.
+///
+/// Bisects block headers between genesis and the head, so it costs about
+/// `log2(head)` header requests (27 on mainnet). Headers are kept by every
+/// node, so no archive node is needed.
+///
+/// # Returns
+///
+/// The block number, or `None` if `timestamp` precedes the genesis block.
+///
+/// # Errors
+///
+/// Returns an error if a header request fails or a block is missing.
+#[instrument(skip(provider))]
+pub async fn find_block_at_timestamp(
+    provider: &Provider,
+    timestamp: u64,
+) -> TrackerResult<Option<u64>> {
+    let header = |tag: BlockNumberOrTag| async move {
+        provider
+            .get_block_by_number(tag, BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|e| {
+                TrackerError::rpc(format!("Failed to fetch block {tag}"), Some(Box::new(e)))
+            })?
+            .map(|block| (block.header.number, block.header.timestamp))
+            .ok_or_else(|| TrackerError::rpc(format!("Block {tag} not found"), None))
+    };
+
+    let (head, head_timestamp) = header(BlockNumberOrTag::Latest).await?;
+    if head_timestamp <= timestamp {
+        return Ok(Some(head));
+    }
+    let (_, genesis_timestamp) = header(BlockNumberOrTag::Number(0)).await?;
+    if genesis_timestamp > timestamp {
+        return Ok(None);
+    }
+
+    let block = bisect_timestamp(0, head, timestamp, |number| async move {
+        header(BlockNumberOrTag::Number(number))
+            .await
+            .map(|(_, ts)| ts)
+    })
+    .await?;
+    debug!(block, timestamp, "Found block at timestamp");
+    Ok(Some(block))
+}
+
+/// Narrows `lo..hi` to the last block whose timestamp is at most
+/// `timestamp`, given that `lo`'s is and `hi`'s isn't.
+async fn bisect_timestamp<F, Fut>(
+    mut lo: u64,
+    mut hi: u64,
+    timestamp: u64,
+    mut timestamp_of: F,
+) -> TrackerResult<u64>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: std::future::Future<Output = TrackerResult<u64>>,
+{
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if timestamp_of(mid).await? <= timestamp {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok(lo)
+}
+
 /// Check if the provider connection is healthy by fetching the latest block.
 ///
 /// This is a convenience function that attempts to fetch the latest block
@@ -208,6 +299,32 @@ pub async fn check_connection(provider: &Provider) -> TrackerResult<()> {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_bisect_timestamp() {
+        // 12 second blocks, with two blocks sharing a timestamp
+        let timestamps = [0_u64, 12, 24, 24, 36, 48, 60];
+        let timestamp_of = |n: u64| async move { Ok(timestamps[usize::try_from(n).unwrap()]) };
+
+        let last = 6;
+        assert_eq!(
+            bisect_timestamp(0, last, 30, timestamp_of).await.unwrap(),
+            3
+        );
+        assert_eq!(
+            bisect_timestamp(0, last, 24, timestamp_of).await.unwrap(),
+            3
+        );
+        assert_eq!(
+            bisect_timestamp(0, last, 12, timestamp_of).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            bisect_timestamp(0, last, 59, timestamp_of).await.unwrap(),
+            5
+        );
+        assert_eq!(bisect_timestamp(0, last, 5, timestamp_of).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     #[ignore = "Requires valid RPC_URL environment variable"]
     async fn test_create_provider_integration() {
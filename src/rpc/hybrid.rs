@@ -39,11 +39,22 @@
 //! }
 //! ```
 
+use std::time::Duration;
+
 use eyre::Result;
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
 use tracing::{info, warn};
 
 use super::http;
-use super::websocket::{ReconnectingWebSocket, WebSocketProvider};
+use super::websocket::{ReconnectingWebSocket, WebSocketProvider, DEFAULT_STALE_AFTER};
+
+/// Pause between reconnect rounds in `Hybrid` mode once the reconnect
+/// backoff is exhausted.
+pub const WS_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Block numbers buffered for a slow consumer of [`HybridProviderManager::into_block_notifier`].
+const BLOCK_CHANNEL_CAPACITY: usize = 16;
 
 /// Provider mode selection strategy.
 ///
@@ -148,11 +159,12 @@ impl HybridProviderManager {
                             Some(reconnecting)
                         }
                         Err(e) => {
+                            // Keep the endpoint so a later `ws()` call can retry
                             warn!(
-                                "WebSocket connection failed in hybrid mode: {}. Will use HTTP only.",
+                                "WebSocket connection failed in hybrid mode: {}. Using HTTP until it connects.",
                                 e
                             );
-                            None
+                            Some(reconnecting)
                         }
                     }
                 }
@@ -313,6 +325,14 @@ impl HybridProviderManager {
         ws.reconnect().await
     }
 
+    /// Sets how long the WebSocket may go without a block header before it
+    /// is treated as disconnected and reconnected.
+    #[must_use]
+    pub fn with_ws_stale_after(mut self, stale_after: Duration) -> Self {
+        self.ws_provider = self.ws_provider.map(|ws| ws.with_stale_after(stale_after));
+        self
+    }
+
     /// Returns the current provider mode.
     pub fn mode(&self) -> ProviderMode {
         self.mode
@@ -329,6 +349,76 @@ impl HybridProviderManager {
     pub fn ws_url(&self) -> Option<&str> {
         self.ws_provider.as_ref().map(|ws| ws.url())
     }
+
+    /// Consumes the manager and forwards the number of every new block
+    /// header from the WebSocket subscription over a channel.
+    ///
+    /// Returns `None` in `Http` mode or when no WebSocket URL is configured.
+    /// When the subscription drops, or no header arrives within the stale
+    /// timeout, the task reconnects and subscribes again;
+    /// nothing is sent in the meantime, so callers should poll over HTTP
+    /// while the channel is quiet. In `WebSocket` mode the channel closes once
+    /// reconnecting fails; in `Hybrid` mode the task keeps retrying every
+    /// [`WS_RETRY_DELAY`]. The task stops when the receiver is dropped.
+    #[must_use]
+    pub fn into_block_notifier(mut self) -> Option<mpsc::Receiver<u64>> {
+        if self.mode == ProviderMode::Http || self.ws_provider.is_none() {
+            return None;
+        }
+
+        let stale_after = self
+            .ws_provider
+            .as_ref()
+            .map_or(DEFAULT_STALE_AFTER, ReconnectingWebSocket::stale_after);
+        let (tx, rx) = mpsc::channel(BLOCK_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                let mut stale = false;
+                match self.ws().await {
+                    Ok(ws) => match ws.subscribe_blocks().await {
+                        Ok(mut blocks) => loop {
+                            match tokio::time::timeout(stale_after, blocks.next()).await {
+                                Ok(Some(header)) => {
+                                    if tx.send(header.number).await.is_err() {
+                                        return;
+                                    }
+                                }
+                                Ok(None) => {
+                                    warn!("WebSocket block subscription ended");
+                                    break;
+                                }
+                                Err(_) => {
+                                    stale = true;
+                                    break;
+                                }
+                            }
+                        },
+                        Err(e) => warn!("WebSocket block subscription failed: {}", e),
+                    },
+                    Err(e) => warn!("WebSocket unavailable: {}", e),
+                }
+
+                if tx.is_closed() {
+                    return;
+                }
+                if stale {
+                    if let Some(ws) = self.ws_provider.as_mut() {
+                        // Logged with the running count by `mark_stale`
+                        let _ = ws.mark_stale();
+                    }
+                }
+                if let Err(e) = self.reconnect_ws().await {
+                    if self.mode == ProviderMode::WebSocket {
+                        warn!("Giving up on WebSocket: {}", e);
+                        return;
+                    }
+                    tokio::time::sleep(WS_RETRY_DELAY).await;
+                }
+            }
+        });
+
+        Some(rx)
+    }
 }
 
 #[cfg(test)]
@@ -9,6 +9,10 @@
 //! - **WebSocket Provider** ([`websocket`]): Real-time subscriptions with push notifications
 //! - **Hybrid Provider** ([`hybrid`]): Intelligent manager that uses both
 //!
+//! [`capabilities`] probes what a provider supports (`eth_getLogs` range,
+//! batches, archive state, WebSockets) so callers can adapt to it, and
+//! [`archive`] sends state reads of old blocks to an archive node.
+//!
 //! # Architecture
 //!
 //! ```text
@@ -79,11 +83,18 @@
 //! # }
 //! ```
 
+pub mod archive;
+pub mod capabilities;
 pub mod http;
 pub mod hybrid;
 pub mod websocket;
 
 // Re-export commonly used types
-pub use http::{check_connection, create_provider, get_latest_block, Provider};
+pub use archive::StateRouter;
+pub use capabilities::ProviderCapabilities;
+pub use http::{
+    check_connection, create_provider, find_block_at_timestamp, get_block_timestamp,
+    get_latest_block, Provider,
+};
 pub use hybrid::{HybridProviderManager, ProviderMode};
 pub use websocket::{ReconnectingWebSocket, WebSocketProvider};
@@ -9,6 +9,8 @@
 //! - Push-based notifications (sub-second latency)
 //! - Automatic reconnection with exponential backoff
 //! - Graceful fallback to HTTP polling if unavailable
+//! - Liveness tracking: a connection whose block subscription has been quiet
+//!   for longer than the stale timeout reports itself as disconnected
 //!
 //! # Subscription Strategies
 //!
@@ -35,8 +37,48 @@ use alloy::{
 };
 use eyre::Result;
 use futures_util::stream::StreamExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 use tracing::{debug, error, info, instrument, warn};
 
+use crate::error::TrackerError;
+
+/// Default time without a new block header after which a connection is
+/// considered stale (five mainnet blocks).
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// Time since the last sign of life on a connection.
+///
+/// Cloned into subscription streams, which touch it on every item.
+#[derive(Debug, Clone)]
+struct Liveness {
+    since: Instant,
+    /// Milliseconds after `since` of the last activity
+    last_ms: Arc<AtomicU64>,
+}
+
+impl Liveness {
+    fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            last_ms: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn touch(&self) {
+        let elapsed = u64::try_from(self.since.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.last_ms.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    fn idle(&self) -> Duration {
+        self.since
+            .elapsed()
+            .saturating_sub(Duration::from_millis(self.last_ms.load(Ordering::Relaxed)))
+    }
+}
+
 /// WebSocket provider for real-time blockchain subscriptions.
 ///
 /// Wraps an Alloy WebSocket provider with automatic reconnection
@@ -65,6 +107,8 @@ use tracing::{debug, error, info, instrument, warn};
 pub struct WebSocketProvider {
     provider: alloy::providers::RootProvider<BoxTransport>,
     url: String,
+    liveness: Liveness,
+    stale_after: Duration,
 }
 
 impl WebSocketProvider {
@@ -124,9 +168,26 @@ impl WebSocketProvider {
         Ok(Self {
             provider,
             url: ws_url,
+            liveness: Liveness::new(),
+            stale_after: DEFAULT_STALE_AFTER,
         })
     }
 
+    /// Sets how long the connection may go without a block header before
+    /// [`is_connected`](Self::is_connected) reports it as stale.
+    #[must_use]
+    pub const fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Time since the connection was opened, a subscription was made, or
+    /// the last block header arrived, whichever is latest.
+    #[must_use]
+    pub fn idle(&self) -> Duration {
+        self.liveness.idle()
+    }
+
     /// Returns a reference to the underlying Alloy provider.
     ///
     /// Useful for making direct RPC calls through the WebSocket connection.
@@ -180,7 +241,9 @@ impl WebSocketProvider {
             eyre::eyre!("Block subscription failed: {}", e)
         })?;
 
-        let stream = sub.into_stream();
+        self.liveness.touch();
+        let liveness = self.liveness.clone();
+        let stream = sub.into_stream().inspect(move |_| liveness.touch());
 
         info!("Block subscription active");
         Ok(stream)
@@ -247,11 +310,13 @@ impl WebSocketProvider {
 
     /// Checks if the WebSocket connection is still alive.
     ///
-    /// Note: This doesn't actively ping the server, just checks local state.
+    /// Alloy doesn't expose the socket state, so liveness is judged from
+    /// traffic: the connection is stale once no block header has arrived
+    /// (and no subscription was made) for longer than the stale timeout.
+    /// Without a block subscription, a connection goes stale after the
+    /// timeout even if the socket is open.
     pub fn is_connected(&self) -> bool {
-        // Note: Alloy doesn't expose connection state directly
-        // We rely on stream ending to detect disconnection
-        true
+        self.idle() < self.stale_after
     }
 }
 
@@ -284,6 +349,8 @@ pub struct ReconnectingWebSocket {
     max_reconnect_attempts: u32,
     initial_delay_secs: u64,
     max_delay_secs: u64,
+    stale_after: Duration,
+    stale_disconnects: u64,
 }
 
 impl ReconnectingWebSocket {
@@ -295,14 +362,9 @@ impl ReconnectingWebSocket {
     /// - Initial delay: 1 second
     /// - Max delay: 60 seconds
     /// - Exponential backoff with 25% jitter
+    /// - Stale after: [`DEFAULT_STALE_AFTER`]
     pub fn new(url: String) -> Self {
-        Self {
-            url,
-            provider: None,
-            max_reconnect_attempts: 10,
-            initial_delay_secs: 1,
-            max_delay_secs: 60,
-        }
+        Self::with_settings(url, 10, 1, 60)
     }
 
     /// Creates a new reconnecting WebSocket with custom settings.
@@ -330,9 +392,29 @@ impl ReconnectingWebSocket {
             max_reconnect_attempts,
             initial_delay_secs,
             max_delay_secs,
+            stale_after: DEFAULT_STALE_AFTER,
+            stale_disconnects: 0,
         }
     }
 
+    /// Sets the stale timeout applied to every connection (see
+    /// [`WebSocketProvider::with_stale_after`]).
+    #[must_use]
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self.provider = self
+            .provider
+            .take()
+            .map(|provider| provider.with_stale_after(stale_after));
+        self
+    }
+
+    /// Returns the stale timeout.
+    #[must_use]
+    pub const fn stale_after(&self) -> Duration {
+        self.stale_after
+    }
+
     /// Connects to the WebSocket with automatic retry on failure.
     ///
     /// Uses exponential backoff with jitter:
@@ -354,7 +436,7 @@ impl ReconnectingWebSocket {
         loop {
             match WebSocketProvider::connect(self.url.clone()).await {
                 Ok(provider) => {
-                    self.provider = Some(provider);
+                    self.provider = Some(provider.with_stale_after(self.stale_after));
                     info!("WebSocket connection established");
                     return Ok(());
                 }
@@ -423,9 +505,37 @@ impl ReconnectingWebSocket {
         self.provider.as_mut()
     }
 
-    /// Checks if currently connected.
+    /// Checks if currently connected and not stale.
     pub fn is_connected(&self) -> bool {
-        self.provider.is_some()
+        self.provider
+            .as_ref()
+            .is_some_and(WebSocketProvider::is_connected)
+    }
+
+    /// Drops a connection whose stream went quiet and counts it.
+    ///
+    /// Returns the [`TrackerError::WebSocketDisconnected`] describing it, for
+    /// the caller to log or propagate before reconnecting.
+    pub fn mark_stale(&mut self) -> TrackerError {
+        let idle = self.provider.take().map_or(Duration::ZERO, |p| p.idle());
+        self.stale_disconnects += 1;
+        let error = TrackerError::websocket_disconnected(format!(
+            "no block header for {}s (stale after {}s)",
+            idle.as_secs(),
+            self.stale_after.as_secs()
+        ));
+        warn!(
+            error = %error,
+            stale_disconnects = self.stale_disconnects,
+            "WebSocket stream stale"
+        );
+        error
+    }
+
+    /// Number of connections dropped as stale so far.
+    #[must_use]
+    pub const fn stale_disconnects(&self) -> u64 {
+        self.stale_disconnects
     }
 
     /// Returns the WebSocket URL.
@@ -482,6 +592,29 @@ mod tests {
         assert_eq!(reconnecting.max_delay_secs, 30);
     }
 
+    #[tokio::test]
+    async fn test_liveness_tracks_last_activity() {
+        let liveness = Liveness::new();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(liveness.idle() >= Duration::from_millis(50));
+
+        // Streams touch a clone
+        liveness.clone().touch();
+        assert!(liveness.idle() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_mark_stale_counts_disconnects() {
+        let mut reconnecting = ReconnectingWebSocket::new("wss://test.com".to_string())
+            .with_stale_after(Duration::from_secs(30));
+        assert_eq!(reconnecting.stale_after(), Duration::from_secs(30));
+
+        let error = reconnecting.mark_stale();
+        assert!(matches!(error, TrackerError::WebSocketDisconnected { .. }));
+        assert_eq!(reconnecting.stale_disconnects(), 1);
+        assert!(!reconnecting.is_connected());
+    }
+
     #[tokio::test]
     async fn test_reconnection_logic_with_invalid_url() {
         let mut reconnecting = ReconnectingWebSocket::with_settings(
@@ -0,0 +1,615 @@
+//! Watch scheduler: one supervised task per enabled pool.
+//!
+//! `watch` runs a task per enabled pool, each resuming from the pool's own
+//! checkpoint with its own reorg detector and price state, so a slow or
+//! failing pool doesn't hold the others back:
+//!
+//! - **Ticks**: the watch loop calls [`Scheduler::tick`] every polling
+//!   interval (or new block). A tick starts tasks for pools enabled since the
+//!   last one, stops the tasks of disabled pools after their current pass,
+//!   and wakes every task for a pass. Ticks that arrive while a task is still
+//!   busy are coalesced into one pass.
+//! - **Staggering**: of N pools, the n-th waits n/N of the interval after
+//!   each tick, so passes spread over the interval instead of hitting the RPC
+//!   provider at once.
+//! - **Concurrency limit**: at most `MAX_CONCURRENT_POOLS` passes run at the
+//!   same time; the other tasks wait for a free slot.
+//! - **Supervision**: a failed pass is retried on the next tick. A task that
+//!   exits with an error (e.g. its startup consistency check failed) or
+//!   panics is restarted on a later tick, after a backoff doubling from one
+//!   interval up to [`MAX_RESTART_BACKOFF`].
+//!
+//! - **Shutdown**: [`Scheduler::drain`] stops every task once its pass in
+//!   progress has written its batches and committed its checkpoint, waiting
+//!   at most a drain timeout; [`Scheduler::shutdown`] then aborts whatever is
+//!   still running.
+//!
+//! Each task's [`TaskStatus`] is written to the `indexer_tasks` table, which
+//! `/api/v1/pools` reports next to the pool's checkpoint.
+
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::db::models::{IndexerTaskRecord, PoolRecord};
+use crate::db::repository::Repository;
+use crate::error::TrackerResult;
+
+/// Default number of pools indexed at the same time.
+pub const DEFAULT_MAX_CONCURRENT_POOLS: usize = 4;
+
+/// Longest wait before restarting a failed task.
+pub const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Default time [`Scheduler::drain`] waits for passes in progress.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Status of a pool's watch task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// Spawned, checking its resume point
+    Starting,
+    /// The last pass succeeded
+    Running,
+    /// The last pass failed; retried on the next tick
+    Failing,
+    /// The task exited with an error and waits to be restarted
+    Restarting,
+    /// The pool was disabled and its task stopped
+    Disabled,
+    /// `watch` shut down
+    Stopped,
+}
+
+impl TaskStatus {
+    /// Returns the name stored in `indexer_tasks.status`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Starting => "starting",
+            Self::Running => "running",
+            Self::Failing => "failing",
+            Self::Restarting => "restarting",
+            Self::Disabled => "disabled",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Body of a pool task, run again on every restart.
+type PoolTask =
+    dyn Fn(PoolRecord, TaskContext) -> BoxFuture<'static, TrackerResult<()>> + Send + Sync;
+
+/// Writes a task's status to `indexer_tasks`.
+#[derive(Clone)]
+struct StatusReporter {
+    repository: Repository,
+    pool_id: i64,
+    restarts: u32,
+}
+
+impl StatusReporter {
+    /// Records the status; a failed write is logged, since the status is
+    /// informational and must not stop indexing.
+    async fn report(
+        &self,
+        status: TaskStatus,
+        last_block: Option<u64>,
+        last_error: Option<String>,
+    ) {
+        let record = IndexerTaskRecord {
+            pool_id: self.pool_id,
+            status,
+            last_block,
+            restarts: self.restarts,
+            last_error,
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        if let Err(e) = self.repository.upsert_indexer_task(&record).await {
+            warn!(pool_id = self.pool_id, error = %e, "Failed to record task status");
+        }
+    }
+}
+
+/// Handle a pool task uses to wait for its passes and report on them.
+pub struct TaskContext {
+    offset: Duration,
+    ticks: watch::Receiver<u64>,
+    permits: Arc<Semaphore>,
+    stop: Arc<AtomicBool>,
+    reporter: StatusReporter,
+}
+
+impl TaskContext {
+    /// Returns the ID of the task's pool.
+    #[must_use]
+    pub const fn pool_id(&self) -> i64 {
+        self.reporter.pool_id
+    }
+
+    /// Waits for the next tick, the task's stagger offset and a free pass
+    /// slot. The slot is released when the returned permit is dropped.
+    ///
+    /// Returns `None` once the task should exit: its pool was disabled, the
+    /// scheduler is draining or the scheduler is gone.
+    pub async fn next_pass(&mut self) -> Option<OwnedSemaphorePermit> {
+        if self.ticks.changed().await.is_err() || self.stopping() {
+            return None;
+        }
+        tokio::time::sleep(self.offset).await;
+        if self.stopping() {
+            return None;
+        }
+        let permit = Arc::clone(&self.permits).acquire_owned().await.ok()?;
+        (!self.stopping()).then_some(permit)
+    }
+
+    /// Records the outcome of a pass that indexed up to `last_block`.
+    pub async fn report_pass(&self, result: &TrackerResult<()>, last_block: u64) {
+        match result {
+            Ok(()) => {
+                self.reporter
+                    .report(TaskStatus::Running, Some(last_block), None)
+                    .await;
+            }
+            Err(e) => {
+                self.reporter
+                    .report(TaskStatus::Failing, Some(last_block), Some(e.to_string()))
+                    .await;
+            }
+        }
+    }
+
+    fn stopping(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+}
+
+/// A pool's task and its restart bookkeeping.
+struct Supervised {
+    handle: Option<JoinHandle<TrackerResult<()>>>,
+    stop: Arc<AtomicBool>,
+    restarts: u32,
+    restart_at: Option<Instant>,
+}
+
+/// Runs and supervises one task per enabled pool.
+pub struct Scheduler {
+    repository: Repository,
+    task: Arc<PoolTask>,
+    interval: Duration,
+    permits: Arc<Semaphore>,
+    ticks: watch::Sender<u64>,
+    tasks: HashMap<i64, Supervised>,
+}
+
+impl Scheduler {
+    /// Creates a scheduler running `task` for each enabled pool, woken every
+    /// `interval` (used for staggering and restart backoff).
+    #[must_use]
+    pub fn new<F, Fut>(repository: Repository, interval: Duration, task: F) -> Self
+    where
+        F: Fn(PoolRecord, TaskContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = TrackerResult<()>> + Send + 'static,
+    {
+        Self {
+            repository,
+            task: Arc::new(move |pool, context| Box::pin(task(pool, context))),
+            interval,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_POOLS)),
+            ticks: watch::channel(0).0,
+            tasks: HashMap::new(),
+        }
+    }
+
+    /// Sets how many passes may run at the same time (at least one).
+    #[must_use]
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.permits = Arc::new(Semaphore::new(max_concurrent.max(1)));
+        self
+    }
+
+    /// Returns the number of live tasks.
+    #[must_use]
+    pub fn running(&self) -> usize {
+        self.tasks
+            .values()
+            .filter(|task| task.handle.is_some())
+            .count()
+    }
+
+    /// Starts, stops and restarts tasks to match the enabled pools, then
+    /// wakes every task for a pass.
+    ///
+    /// If the pools can't be read, the running tasks are woken as they are.
+    pub async fn tick(&mut self) {
+        self.collect_finished().await;
+        match self.repository.get_enabled_pools().await {
+            Ok(pools) => self.reconcile(pools).await,
+            Err(e) => warn!(error = %e, "Failed to read enabled pools, keeping current tasks"),
+        }
+        self.ticks.send_modify(|tick| *tick = tick.wrapping_add(1));
+    }
+
+    /// Stops every task after its pass in progress, waiting until `timeout`
+    /// has passed for them to exit.
+    ///
+    /// No new passes start, and idle tasks exit at once. Each pass commits
+    /// its batches with its checkpoint, so a pass cut short by the timeout
+    /// (see [`Scheduler::shutdown`]) only loses uncommitted work. Returns
+    /// whether every task exited in time.
+    pub async fn drain(&mut self, timeout: Duration) -> bool {
+        for task in self.tasks.values() {
+            task.stop.store(true, Ordering::Relaxed);
+        }
+        // Wake idle tasks, so they see the stop flag
+        self.ticks.send_modify(|tick| *tick = tick.wrapping_add(1));
+
+        let deadline = Instant::now() + timeout;
+        let mut drained = true;
+        for (pool_id, task) in &mut self.tasks {
+            let Some(handle) = task.handle.as_mut() else {
+                continue;
+            };
+            match tokio::time::timeout_at(deadline, handle).await {
+                Ok(result) => {
+                    task.handle = None;
+                    match result {
+                        Ok(Ok(())) => info!(pool_id, "Pool task drained"),
+                        Ok(Err(e)) => warn!(pool_id, error = %e, "Pool task failed while draining"),
+                        Err(e) => warn!(pool_id, "Pool task panicked while draining: {}", e),
+                    }
+                }
+                Err(_) => {
+                    drained = false;
+                    warn!(pool_id, "Pool task still busy at the drain timeout");
+                }
+            }
+        }
+        drained
+    }
+
+    /// Aborts every task still running and records each task as stopped.
+    pub async fn shutdown(mut self) {
+        for (pool_id, task) in self.tasks.drain() {
+            if let Some(handle) = task.handle {
+                handle.abort();
+                let _ = handle.await;
+            }
+            StatusReporter {
+                repository: self.repository.clone(),
+                pool_id,
+                restarts: task.restarts,
+            }
+            .report(TaskStatus::Stopped, None, None)
+            .await;
+        }
+    }
+
+    /// Reaps finished tasks: stopped ones are forgotten, failed ones are
+    /// scheduled for a restart.
+    async fn collect_finished(&mut self) {
+        let finished: Vec<i64> = self
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.handle.as_ref().is_some_and(JoinHandle::is_finished))
+            .map(|(pool_id, _)| *pool_id)
+            .collect();
+
+        for pool_id in finished {
+            let Some(task) = self.tasks.get_mut(&pool_id) else {
+                continue;
+            };
+            let Some(handle) = task.handle.take() else {
+                continue;
+            };
+            let failure = match handle.await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(e) => Some(format!("task panicked: {e}")),
+            };
+            let reporter = StatusReporter {
+                repository: self.repository.clone(),
+                pool_id,
+                restarts: task.restarts,
+            };
+
+            match failure {
+                Some(message) if !task.stop.load(Ordering::Relaxed) => {
+                    task.restarts += 1;
+                    let backoff = restart_backoff(self.interval, task.restarts);
+                    task.restart_at = Some(Instant::now() + backoff);
+                    error!(
+                        pool_id,
+                        restarts = task.restarts,
+                        backoff_secs = backoff.as_secs(),
+                        "Pool task failed: {}",
+                        message
+                    );
+                    StatusReporter {
+                        restarts: task.restarts,
+                        ..reporter
+                    }
+                    .report(TaskStatus::Restarting, None, Some(message))
+                    .await;
+                }
+                _ => {
+                    self.tasks.remove(&pool_id);
+                    info!(pool_id, "Pool disabled, stopped its task");
+                    reporter.report(TaskStatus::Disabled, None, None).await;
+                }
+            }
+        }
+    }
+
+    /// Stops the tasks of disabled pools and starts those of enabled pools
+    /// without a live task (once any restart backoff has passed).
+    async fn reconcile(&mut self, pools: Vec<PoolRecord>) {
+        let enabled: HashSet<i64> = pools.iter().map(|pool| pool.id).collect();
+        let mut waiting_disabled = Vec::new();
+        for (pool_id, task) in &self.tasks {
+            if enabled.contains(pool_id) {
+                continue;
+            }
+            if task.handle.is_some() {
+                // Exits at its next wake-up, after any pass in progress
+                task.stop.store(true, Ordering::Relaxed);
+            } else {
+                waiting_disabled.push(*pool_id);
+            }
+        }
+        for pool_id in waiting_disabled {
+            if let Some(task) = self.tasks.remove(&pool_id) {
+                info!(pool_id, "Pool disabled, cancelled its restart");
+                StatusReporter {
+                    repository: self.repository.clone(),
+                    pool_id,
+                    restarts: task.restarts,
+                }
+                .report(TaskStatus::Disabled, None, None)
+                .await;
+            }
+        }
+
+        let count = u32::try_from(pools.len()).unwrap_or(u32::MAX).max(1);
+        let now = Instant::now();
+        for (slot, pool) in pools.into_iter().enumerate() {
+            let (restarts, due) = self.tasks.get(&pool.id).map_or((0, true), |task| {
+                (
+                    task.restarts,
+                    task.handle.is_none() && task.restart_at.map_or(true, |at| at <= now),
+                )
+            });
+            if !due {
+                continue;
+            }
+            let offset = self.interval * u32::try_from(slot).unwrap_or(u32::MAX) / count;
+            self.spawn(pool, offset, restarts).await;
+        }
+    }
+
+    async fn spawn(&mut self, pool: PoolRecord, offset: Duration, restarts: u32) {
+        let pool_id = pool.id;
+        let stop = Arc::new(AtomicBool::new(false));
+        let reporter = StatusReporter {
+            repository: self.repository.clone(),
+            pool_id,
+            restarts,
+        };
+        reporter.report(TaskStatus::Starting, None, None).await;
+        info!(
+            pool_id,
+            name = pool.name.as_deref().unwrap_or("unknown"),
+            offset_ms = offset.as_millis(),
+            restarts,
+            "Starting pool task"
+        );
+
+        let context = TaskContext {
+            offset,
+            ticks: self.ticks.subscribe(),
+            permits: Arc::clone(&self.permits),
+            stop: Arc::clone(&stop),
+            reporter,
+        };
+        let handle = tokio::spawn((self.task)(pool, context));
+        self.tasks.insert(
+            pool_id,
+            Supervised {
+                handle: Some(handle),
+                stop,
+                restarts,
+                restart_at: None,
+            },
+        );
+    }
+}
+
+/// Returns how long to wait before the `restarts`-th restart: one interval,
+/// doubling with every restart, at most [`MAX_RESTART_BACKOFF`].
+#[must_use]
+pub fn restart_backoff(interval: Duration, restarts: u32) -> Duration {
+    let factor = 1_u32 << restarts.saturating_sub(1).min(16);
+    interval.saturating_mul(factor).min(MAX_RESTART_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use crate::error::TrackerError;
+    use alloy::primitives::Address;
+    use std::sync::atomic::AtomicUsize;
+
+    const INTERVAL: Duration = Duration::from_millis(20);
+
+    async fn status(repository: &Repository, pool_id: i64) -> Option<String> {
+        repository
+            .get_all_pools()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|pool| pool.id == pool_id)
+            .and_then(|pool| pool.task_status)
+    }
+
+    async fn tick_and_wait(scheduler: &mut Scheduler) {
+        scheduler.tick().await;
+        tokio::time::sleep(INTERVAL * 3).await;
+    }
+
+    #[test]
+    fn test_restart_backoff_doubles_up_to_the_cap() {
+        let interval = Duration::from_secs(12);
+        assert_eq!(restart_backoff(interval, 1), interval);
+        assert_eq!(restart_backoff(interval, 2), interval * 2);
+        assert_eq!(restart_backoff(interval, 3), interval * 4);
+        assert_eq!(restart_backoff(interval, 10), MAX_RESTART_BACKOFF);
+        assert_eq!(restart_backoff(interval, u32::MAX), MAX_RESTART_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_supervises_one_task_per_enabled_pool() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let healthy = repository.ensure_default_pool().await.unwrap();
+        let broken = repository
+            .ensure_pool_exists(
+                Address::repeat_byte(0x11),
+                Some("BROKEN".to_string()),
+                Address::repeat_byte(0x22),
+                Some("AAA".to_string()),
+                18,
+                Address::repeat_byte(0x33),
+                Some("BBB".to_string()),
+                6,
+            )
+            .await
+            .unwrap();
+
+        let passes = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&passes);
+        let mut scheduler =
+            Scheduler::new(repository.clone(), INTERVAL, move |pool, mut context| {
+                let passes = Arc::clone(&counted);
+                async move {
+                    if pool.name.as_deref() == Some("BROKEN") {
+                        return Err(TrackerError::rpc("eth_getLogs timed out", None));
+                    }
+                    while let Some(_permit) = context.next_pass().await {
+                        passes.fetch_add(1, Ordering::SeqCst);
+                        context.report_pass(&Ok(()), 19_000_000).await;
+                    }
+                    Ok(())
+                }
+            })
+            .with_max_concurrent(1);
+
+        tick_and_wait(&mut scheduler).await;
+        assert_eq!(passes.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            status(&repository, healthy).await.as_deref(),
+            Some("running")
+        );
+
+        // The failed task is restarted once its backoff has passed
+        tick_and_wait(&mut scheduler).await;
+        assert_eq!(scheduler.tasks[&broken].restarts, 1);
+        assert_eq!(
+            status(&repository, broken).await.as_deref(),
+            Some("restarting")
+        );
+        tick_and_wait(&mut scheduler).await;
+        tick_and_wait(&mut scheduler).await;
+        assert!(scheduler.tasks[&broken].restarts >= 2);
+        assert_eq!(passes.load(Ordering::SeqCst), 4);
+
+        // A disabled pool's task exits at its next wake-up
+        repository.set_pool_enabled(healthy, false).await.unwrap();
+        tick_and_wait(&mut scheduler).await;
+        tick_and_wait(&mut scheduler).await;
+        assert!(!scheduler.tasks.contains_key(&healthy));
+        assert_eq!(
+            status(&repository, healthy).await.as_deref(),
+            Some("disabled")
+        );
+        assert_eq!(passes.load(Ordering::SeqCst), 4);
+
+        repository.set_pool_enabled(broken, false).await.unwrap();
+        tick_and_wait(&mut scheduler).await;
+        assert_eq!(scheduler.running(), 0);
+
+        repository.set_pool_enabled(healthy, true).await.unwrap();
+        tick_and_wait(&mut scheduler).await;
+        assert_eq!(scheduler.running(), 1);
+        scheduler.shutdown().await;
+        assert_eq!(
+            status(&repository, healthy).await.as_deref(),
+            Some("stopped")
+        );
+    }
+
+    /// Starts a scheduler whose passes take `pass`, counting finished
+    /// passes in `done`, and lets the first pass begin.
+    async fn start_slow_passes(
+        repository: &Repository,
+        pass: Duration,
+        done: &Arc<AtomicUsize>,
+    ) -> Scheduler {
+        let done = Arc::clone(done);
+        let mut scheduler = Scheduler::new(repository.clone(), INTERVAL, move |_, mut context| {
+            let done = Arc::clone(&done);
+            async move {
+                while let Some(_permit) = context.next_pass().await {
+                    tokio::time::sleep(pass).await;
+                    done.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(())
+            }
+        });
+        scheduler.tick().await;
+        tokio::time::sleep(INTERVAL / 2).await;
+        scheduler
+    }
+
+    #[tokio::test]
+    async fn test_drain_finishes_passes_in_progress() {
+        let repository = Repository::new(create_pool("sqlite::memory:").await.unwrap());
+        let pool_id = repository.ensure_default_pool().await.unwrap();
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        // A pass in progress completes before the task exits
+        let mut scheduler = start_slow_passes(&repository, INTERVAL * 2, &finished).await;
+        assert!(scheduler.drain(Duration::from_secs(5)).await);
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+        assert_eq!(scheduler.running(), 0);
+        scheduler.shutdown().await;
+
+        // A pass outlasting the timeout is aborted by the shutdown
+        let mut scheduler =
+            start_slow_passes(&repository, Duration::from_secs(60), &finished).await;
+        assert!(!scheduler.drain(INTERVAL).await);
+        assert_eq!(scheduler.running(), 1);
+        scheduler.shutdown().await;
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            status(&repository, pool_id).await.as_deref(),
+            Some("stopped")
+        );
+    }
+}
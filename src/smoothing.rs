@@ -0,0 +1,155 @@
+//! Exponentially weighted moving average (EWMA) of pool prices.
+//!
+//! When `PRICE_EWMA_HALF_LIFE_SECS` is set, the indexer feeds every new price
+//! into a [`PriceEwma`] and stores the smoothed value next to the spot price
+//! (`price_points.price_ewma`). The latest price endpoint and the price stream
+//! publish it alongside the spot price, so consumers share one smoothed series.
+//!
+//! Smoothing is time-weighted: a price observed `dt` seconds after the
+//! previous one moves the average by `1 - 0.5^(dt / half_life)` of the gap,
+//! so the result doesn't depend on how many swaps a pool sees. Several prices
+//! in one block replace each other; only the block's last price counts.
+
+use std::collections::VecDeque;
+
+/// Smoothed values kept for rewinding after a reorg (well past any
+/// realistic reorg depth).
+const MAX_HISTORY: usize = 256;
+
+/// Smoothed value as of one block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct EwmaPoint {
+    block_number: u64,
+    timestamp: i64,
+    value: f64,
+}
+
+/// Time-weighted EWMA of one pool's price.
+#[derive(Debug, Clone)]
+pub struct PriceEwma {
+    half_life_secs: f64,
+    /// Recent smoothed values, oldest first
+    history: VecDeque<EwmaPoint>,
+}
+
+impl PriceEwma {
+    /// Creates an empty average; the first price seeds it.
+    #[must_use]
+    pub fn new(half_life_secs: u64) -> Self {
+        Self {
+            #[allow(clippy::cast_precision_loss)]
+            half_life_secs: half_life_secs.max(1) as f64,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Creates an average resuming from a stored smoothed value.
+    #[must_use]
+    pub fn resume(half_life_secs: u64, block_number: u64, timestamp: i64, value: f64) -> Self {
+        let mut ewma = Self::new(half_life_secs);
+        ewma.history.push_back(EwmaPoint {
+            block_number,
+            timestamp,
+            value,
+        });
+        ewma
+    }
+
+    /// Current smoothed value, if any price has been seen.
+    #[must_use]
+    pub fn value(&self) -> Option<f64> {
+        self.history.back().map(|point| point.value)
+    }
+
+    /// Feeds the price observed at `block_number` and returns the new
+    /// smoothed value.
+    ///
+    /// A later price in a block that was already fed replaces the earlier
+    /// one.
+    pub fn update(&mut self, block_number: u64, timestamp: i64, price: f64) -> f64 {
+        while self
+            .history
+            .back()
+            .is_some_and(|point| point.block_number >= block_number)
+        {
+            self.history.pop_back();
+        }
+
+        let value = self.history.back().map_or(price, |prev| {
+            #[allow(clippy::cast_precision_loss)]
+            let elapsed = timestamp.saturating_sub(prev.timestamp).max(0) as f64;
+            let alpha = 1.0 - 0.5_f64.powf(elapsed / self.half_life_secs);
+            alpha.mul_add(price - prev.value, prev.value)
+        });
+
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(EwmaPoint {
+            block_number,
+            timestamp,
+            value,
+        });
+        value
+    }
+
+    /// Drops values from blocks after `fork_point`, so re-indexed blocks
+    /// continue from the average as of the fork point.
+    pub fn rewind(&mut self, fork_point: u64) {
+        while self
+            .history
+            .back()
+            .is_some_and(|point| point.block_number > fork_point)
+        {
+            self.history.pop_back();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_price_seeds_average() {
+        let mut ewma = PriceEwma::new(60);
+        assert_eq!(ewma.value(), None);
+        assert!((ewma.update(100, 1_000, 2_000.0) - 2_000.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_half_life_closes_half_the_gap() {
+        let mut ewma = PriceEwma::new(60);
+        ewma.update(100, 1_000, 2_000.0);
+
+        let value = ewma.update(105, 1_060, 2_100.0);
+        assert!((value - 2_050.0).abs() < 1e-9);
+
+        // A price in the same second doesn't move the average
+        let value = ewma.update(106, 1_060, 3_000.0);
+        assert!((value - 2_050.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_later_price_in_block_replaces_earlier() {
+        let mut ewma = PriceEwma::new(60);
+        ewma.update(100, 1_000, 2_000.0);
+        ewma.update(105, 1_060, 5_000.0);
+
+        let value = ewma.update(105, 1_060, 2_100.0);
+        assert!((value - 2_050.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rewind_drops_orphaned_blocks() {
+        let mut ewma = PriceEwma::resume(60, 100, 1_000, 2_000.0);
+        ewma.update(105, 1_060, 2_100.0);
+        ewma.update(107, 1_084, 9_000.0);
+
+        ewma.rewind(105);
+        assert!((ewma.value().unwrap() - 2_050.0).abs() < 1e-9);
+
+        ewma.rewind(99);
+        assert_eq!(ewma.value(), None);
+    }
+}
@@ -0,0 +1,148 @@
+//! Flash-loan spike filter for candles and stats.
+//!
+//! A flash loan can push a pool's price far away and back within a single
+//! block: the price points of the block's middle transactions sit at the
+//! manipulated price, and the last one returns to where the block opened.
+//! Nobody could trade at those prices outside the attacking transactions, but
+//! they still widen candle wicks and shift averages.
+//!
+//! When a pool has a `spike_filter_bps` threshold, a price point is left out
+//! of candles and stats if:
+//!
+//! - it is not the last price point of its block
+//! - it is at least `spike_filter_bps` basis points away from the price
+//!   before the block
+//! - the block's last price is within [`SPIKE_REVERT_BPS`] of the price
+//!   before the block, i.e. the spike fully reverted
+//!
+//! The block's closing price is always kept, so a move that sticks is never
+//! filtered. The price points themselves stay in `price_points`; only the
+//! in-memory candle book, the stored candles and the stats queries skip
+//! them. The repository applies the same rule in SQL.
+//!
+//! # Example
+//!
+//! ```
+//! use eth_uniswap_alloy::spikes::is_flash_spike;
+//!
+//! // Pushed from 2,000 to 3,000 and back to 2,001 in one block
+//! assert!(is_flash_spike(2_000.0, 3_000.0, 2_001.0, 500));
+//! // The price stayed at 3,000: a real move
+//! assert!(!is_flash_spike(2_000.0, 3_000.0, 3_000.0, 500));
+//! ```
+
+use crate::db::models::PricePointRow;
+
+/// Largest distance, in basis points, between a block's closing price and
+/// the price before it for the block's spike to count as reverted.
+pub const SPIKE_REVERT_BPS: u32 = 10;
+
+/// Returns whether `price`, inside a block that opened at `open` and closed
+/// at `close`, is a reverted spike of at least `threshold_bps`.
+#[must_use]
+pub fn is_flash_spike(open: f64, price: f64, close: f64, threshold_bps: u32) -> bool {
+    open > 0.0
+        && (close - open).abs() * 10_000.0 <= open * f64::from(SPIKE_REVERT_BPS)
+        && (price - open).abs() * 10_000.0 >= open * f64::from(threshold_bps)
+}
+
+/// Removes reverted single-block spikes from confirmed prices in block
+/// order.
+///
+/// `previous` is the price before the first block; without it the first
+/// block is kept as is. With no `threshold_bps` every price is kept.
+#[must_use]
+pub fn remove_flash_spikes(
+    prices: Vec<PricePointRow>,
+    previous: Option<f64>,
+    threshold_bps: Option<u32>,
+) -> Vec<PricePointRow> {
+    let Some(threshold_bps) = threshold_bps else {
+        return prices;
+    };
+
+    let mut kept = Vec::with_capacity(prices.len());
+    let mut open = previous;
+    let mut start = 0;
+    while start < prices.len() {
+        let block = prices[start].block_number;
+        let end = prices[start..]
+            .iter()
+            .position(|p| p.block_number != block)
+            .map_or(prices.len(), |len| start + len);
+        let close = prices[end - 1].price;
+
+        for (i, price) in prices[start..end].iter().enumerate() {
+            let spike = start + i + 1 < end
+                && open.is_some_and(|open| is_flash_spike(open, price.price, close, threshold_bps));
+            if !spike {
+                kept.push(price.clone());
+            }
+        }
+
+        open = Some(close);
+        start = end;
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(block_number: i64, price: f64) -> PricePointRow {
+        PricePointRow {
+            event_id: None,
+            block_number,
+            block_timestamp: block_number * 12,
+            tx_hash: Default::default(),
+            price,
+            price_exact: None,
+            price_ewma: None,
+            source: "event".to_string(),
+            reserve0_human: 0.0,
+            reserve1_human: 0.0,
+        }
+    }
+
+    fn prices(rows: &[PricePointRow]) -> Vec<f64> {
+        rows.iter().map(|p| p.price).collect()
+    }
+
+    #[test]
+    fn test_removes_reverted_spikes_only() {
+        let rows = vec![
+            price(1, 2_000.0),
+            // Flash loan: out to 3,000 and 2,500, back to 2,000.5
+            price(2, 3_000.0),
+            price(2, 2_500.0),
+            price(2, 2_000.5),
+            // A real move within one block stays
+            price(3, 2_400.0),
+            price(3, 2_100.0),
+            // A small wiggle below the threshold stays
+            price(4, 2_150.0),
+            price(4, 2_100.0),
+        ];
+
+        let kept = remove_flash_spikes(rows.clone(), None, Some(500));
+        assert_eq!(
+            prices(&kept),
+            [2_000.0, 2_000.5, 2_400.0, 2_100.0, 2_150.0, 2_100.0]
+        );
+        assert_eq!(
+            remove_flash_spikes(rows.clone(), None, None).len(),
+            rows.len()
+        );
+    }
+
+    #[test]
+    fn test_first_block_needs_previous_price() {
+        let rows = vec![price(2, 3_000.0), price(2, 2_000.0)];
+        assert_eq!(remove_flash_spikes(rows.clone(), None, Some(500)).len(), 2);
+        assert_eq!(
+            prices(&remove_flash_spikes(rows, Some(2_000.0), Some(500))),
+            [2_000.0]
+        );
+    }
+}
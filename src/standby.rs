@@ -0,0 +1,118 @@
+//! Warm standby coordination.
+//!
+//! A standby instance follows a primary's database (see
+//! [`Repository::follow_primary`](crate::db::repository::Repository::follow_primary))
+//! while serving the read API. A single admin call promotes it: following
+//! stops, and the instance starts indexing from the last block the primary
+//! recorded, so a primary can be upgraded or replaced without an indexing gap.
+
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+use crate::db::models::FollowReport;
+
+/// Last successful follow pass.
+#[derive(Debug, Clone, Copy)]
+pub struct FollowStatus {
+    /// Outcome of the pass
+    pub report: FollowReport,
+    /// When the pass completed
+    pub synced_at: DateTime<Utc>,
+}
+
+/// Shared promotion flag and follow progress for a standby instance.
+#[derive(Debug, Default)]
+pub struct StandbyControl {
+    promoted: AtomicBool,
+    promotion: Notify,
+    last_follow: Mutex<Option<FollowStatus>>,
+}
+
+impl StandbyControl {
+    /// Creates a control in standby (not promoted) state.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests promotion to primary.
+    ///
+    /// Returns `false` if the instance was already promoted.
+    pub fn promote(&self) -> bool {
+        let first = !self.promoted.swap(true, Ordering::SeqCst);
+        if first {
+            // notify_one stores a permit, so a waiter that isn't parked yet
+            // still observes the promotion
+            self.promotion.notify_one();
+        }
+        first
+    }
+
+    /// Whether promotion has been requested.
+    #[must_use]
+    pub fn is_promoted(&self) -> bool {
+        self.promoted.load(Ordering::SeqCst)
+    }
+
+    /// Waits until promotion is requested.
+    pub async fn promoted(&self) {
+        if self.is_promoted() {
+            return;
+        }
+        self.promotion.notified().await;
+    }
+
+    /// Records a successful follow pass.
+    pub fn record_follow(&self, report: FollowReport) {
+        if let Ok(mut last) = self.last_follow.lock() {
+            *last = Some(FollowStatus {
+                report,
+                synced_at: Utc::now(),
+            });
+        }
+    }
+
+    /// Returns the last successful follow pass, if any.
+    #[must_use]
+    pub fn last_follow(&self) -> Option<FollowStatus> {
+        self.last_follow.lock().ok().and_then(|last| *last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_promote_once_and_wake_waiter() {
+        let control = std::sync::Arc::new(StandbyControl::new());
+        let waiter = {
+            let control = control.clone();
+            tokio::spawn(async move { control.promoted().await })
+        };
+
+        assert!(control.promote());
+        assert!(!control.promote());
+        assert!(control.is_promoted());
+
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter should wake on promotion")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_record_follow() {
+        let control = StandbyControl::new();
+        assert!(control.last_follow().is_none());
+
+        control.record_follow(FollowReport {
+            last_indexed_block: 42,
+            ..FollowReport::default()
+        });
+        assert_eq!(control.last_follow().unwrap().report.last_indexed_block, 42);
+    }
+}
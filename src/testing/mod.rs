@@ -0,0 +1,954 @@
+//! Deterministic mock RPC provider for offline tests.
+//!
+//! Only compiled with the `testing` feature. [`MockProvider`] serves a
+//! [`MockChain`] over JSON-RPC on a local port, so the indexer's real HTTP
+//! and WebSocket providers (and the `watch` binary itself) can run against it
+//! without Anvil or network access. It answers the calls the crate makes:
+//!
+//! - `eth_chainId`, `net_version` and `eth_blockNumber`
+//! - `eth_getBlockByNumber`
+//! - `eth_getLogs`, filtered by block range or hash, address and topics
+//! - `eth_subscribe("newHeads")` and `eth_unsubscribe` over WebSocket
+//!
+//! Other methods fail with JSON-RPC error -32601, as a node without them
+//! would.
+//!
+//! Every block from 0 to the head exists. Hashes are derived from the block
+//! number and the fork it belongs to, so the same fixture always serves the
+//! same chain; [`MockProvider::reorg`] moves the blocks after a fork point
+//! onto a new fork with new hashes. Logs come from a fixture file:
+//!
+//! ```json
+//! {
+//!   "chain_id": 1,
+//!   "head": 19000010,
+//!   "head_timestamp": 1706745720,
+//!   "syncs": [
+//!     { "block": 19000005, "reserve0": "45500000000000000000", "reserve1": "111475000000" }
+//!   ],
+//!   "logs": []
+//! }
+//! ```
+//!
+//! `syncs` are Sync events of `pair` (the WETH/USDT pair by default); `logs`
+//! are raw RPC logs for anything else, and only need `address`, `topics`,
+//! `data` and `blockNumber`. Block hashes, timestamps, transaction hashes and
+//! log indexes are filled in.
+//!
+//! [`ReorgSimulation`] serves chain A, then switches to a chain B forking
+//! from it at a fixed block, for end-to-end reorg tests. Its fixture wraps
+//! chain A's and lists chain B's blocks after the fork point:
+//!
+//! ```json
+//! {
+//!   "chain_a": { "head": 19000006, "syncs": [] },
+//!   "fork_point": 19000007,
+//!   "chain_b": { "head": 19000012, "syncs": [] }
+//! }
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use alloy::providers::Provider;
+//! use eth_uniswap_alloy::testing::{MockChain, MockProvider};
+//!
+//! # async fn example() -> eth_uniswap_alloy::error::TrackerResult<()> {
+//! let mock = MockProvider::start(MockChain::new(1, 19_000_000)).await?;
+//! let provider = mock.provider().await?;
+//! assert_eq!(provider.get_block_number().await.unwrap(), 19_000_000);
+//!
+//! mock.mine(1);
+//! assert_eq!(provider.get_block_number().await.unwrap(), 19_000_001);
+//! # Ok(())
+//! # }
+//! ```
+
+use alloy::primitives::{aliases::U112, keccak256, Address, B256, U64};
+use alloy::rpc::types::{
+    Block, BlockNumberOrTag, BlockTransactions, Filter, FilterBlockOption, Log,
+};
+use alloy::sol_types::SolEvent;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::error::{TrackerError, TrackerResult};
+use crate::events::{Sync, UNISWAP_V2_WETH_USDT_PAIR};
+use crate::rpc::{create_provider, Provider};
+
+mod recording;
+
+pub use recording::{RecordedCall, Recording, RecordingProxy, ReplayProvider};
+
+/// Seconds between mock blocks.
+pub const BLOCK_TIME: u64 = 12;
+
+/// JSON-RPC error code for unsupported methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code for malformed parameters.
+const INVALID_PARAMS: i64 = -32602;
+
+/// A Sync event in a fixture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockSync {
+    /// Block the event is in
+    pub block: u64,
+    /// Reserve of token0, in its smallest unit
+    pub reserve0: U112,
+    /// Reserve of token1, in its smallest unit
+    pub reserve1: U112,
+}
+
+/// Fixture file contents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockFixture {
+    /// Chain ID reported by `eth_chainId`
+    #[serde(default = "default_chain_id")]
+    pub chain_id: u64,
+    /// Latest block number
+    pub head: u64,
+    /// Timestamp of the head block; earlier blocks are [`BLOCK_TIME`] apart
+    #[serde(default = "default_head_timestamp")]
+    pub head_timestamp: u64,
+    /// Pair that `syncs` are emitted by
+    #[serde(default = "default_pair")]
+    pub pair: Address,
+    /// Sync events of `pair`
+    #[serde(default)]
+    pub syncs: Vec<MockSync>,
+    /// Other logs, as returned by `eth_getLogs`
+    #[serde(default)]
+    pub logs: Vec<Log>,
+}
+
+/// A competing chain in a fixture: the blocks after a fork point.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForkFixture {
+    /// Latest block number of the fork
+    pub head: u64,
+    /// Sync events after the fork point
+    #[serde(default)]
+    pub syncs: Vec<MockSync>,
+    /// Other logs after the fork point
+    #[serde(default)]
+    pub logs: Vec<Log>,
+}
+
+/// Reorg scenario fixture: chain A, and chain B forking from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReorgFixture {
+    /// Chain served first
+    pub chain_a: MockFixture,
+    /// Last block chain B shares with chain A
+    pub fork_point: u64,
+    /// Chain B's blocks after the fork point
+    pub chain_b: ForkFixture,
+}
+
+const fn default_chain_id() -> u64 {
+    1
+}
+
+const fn default_head_timestamp() -> u64 {
+    1_706_745_600
+}
+
+const fn default_pair() -> Address {
+    UNISWAP_V2_WETH_USDT_PAIR
+}
+
+/// A deterministic chain: blocks `0..=head`, the forks they are on, and
+/// their logs.
+#[derive(Debug, Clone)]
+pub struct MockChain {
+    chain_id: u64,
+    head: u64,
+    /// Timestamp of block 0
+    genesis_timestamp: u64,
+    pair: Address,
+    /// First block of each fork, by fork ID (fork 0 starts at genesis)
+    forks: Vec<u64>,
+    /// Logs by block, in log index order
+    logs: BTreeMap<u64, Vec<Log>>,
+}
+
+impl MockChain {
+    /// Creates a chain of empty blocks up to `head`.
+    #[must_use]
+    pub fn new(chain_id: u64, head: u64) -> Self {
+        Self::from_fixture(MockFixture {
+            chain_id,
+            head,
+            head_timestamp: default_head_timestamp(),
+            pair: default_pair(),
+            syncs: Vec::new(),
+            logs: Vec::new(),
+        })
+    }
+
+    /// Builds the chain described by `fixture`.
+    #[must_use]
+    pub fn from_fixture(fixture: MockFixture) -> Self {
+        let mut chain = Self {
+            chain_id: fixture.chain_id,
+            head: fixture.head,
+            genesis_timestamp: fixture
+                .head_timestamp
+                .saturating_sub(fixture.head * BLOCK_TIME),
+            pair: fixture.pair,
+            forks: vec![0],
+            logs: BTreeMap::new(),
+        };
+        for sync in fixture.syncs {
+            chain.push_sync(sync);
+        }
+        for log in fixture.logs {
+            chain.push_log(log);
+        }
+        chain
+    }
+
+    /// Loads a fixture file (see the [module docs](self)).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't a valid fixture.
+    pub fn load(path: impl AsRef<Path>) -> TrackerResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            TrackerError::config(
+                format!("Failed to read mock chain fixture {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+        let fixture = serde_json::from_str(&contents).map_err(|e| {
+            TrackerError::config(
+                format!("Invalid mock chain fixture {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+        Ok(Self::from_fixture(fixture))
+    }
+
+    /// Returns the latest block number.
+    #[must_use]
+    pub const fn head(&self) -> u64 {
+        self.head
+    }
+
+    /// Returns the hash of block `number` on the current chain.
+    #[must_use]
+    pub fn block_hash(&self, number: u64) -> B256 {
+        let fork = self.forks.iter().rposition(|&start| start <= number);
+        keccak256(format!("mock-block:{}:{number}", fork.unwrap_or(0)))
+    }
+
+    /// Returns the timestamp of block `number`.
+    #[must_use]
+    pub const fn block_timestamp(&self, number: u64) -> u64 {
+        self.genesis_timestamp + number * BLOCK_TIME
+    }
+
+    /// Returns block `number` as `eth_getBlockByNumber` serves it, or `None`
+    /// past the head.
+    #[must_use]
+    pub fn block(&self, number: u64) -> Option<Block> {
+        if number > self.head {
+            return None;
+        }
+        let mut block: Block = Block::default();
+        block.header.hash = self.block_hash(number);
+        block.header.inner.number = number;
+        block.header.inner.parent_hash = number
+            .checked_sub(1)
+            .map_or(B256::ZERO, |parent| self.block_hash(parent));
+        block.header.inner.timestamp = self.block_timestamp(number);
+        block.transactions = BlockTransactions::Hashes(Vec::new());
+        Some(block)
+    }
+
+    /// Adds a Sync event of the pair at the end of its block.
+    pub fn push_sync(&mut self, sync: MockSync) {
+        let data = Sync {
+            reserve0: sync.reserve0,
+            reserve1: sync.reserve1,
+        }
+        .encode_log_data();
+        self.push_log(Log {
+            inner: alloy::primitives::Log {
+                address: self.pair,
+                data,
+            },
+            block_number: Some(sync.block),
+            ..Log::default()
+        });
+    }
+
+    /// Adds a log at the end of its block (`block_number`, or the head if
+    /// unset).
+    pub fn push_log(&mut self, mut log: Log) {
+        let block = *log.block_number.get_or_insert(self.head);
+        log.removed = false;
+        self.logs.entry(block).or_default().push(log);
+    }
+
+    /// Mines `count` empty blocks, returning the new head.
+    pub fn mine(&mut self, count: u64) -> u64 {
+        self.head += count;
+        self.head
+    }
+
+    /// Replaces the blocks after `fork_point` with blocks of a new fork,
+    /// keeping the head. Their logs are dropped; add the new fork's with
+    /// [`MockChain::push_sync`] or [`MockChain::push_log`].
+    pub fn reorg(&mut self, fork_point: u64) {
+        self.forks.push(fork_point + 1);
+        self.logs.split_off(&(fork_point + 1));
+    }
+
+    /// Switches to the competing chain `branch`, which shares this chain's
+    /// blocks up to `fork_point`: later blocks get new hashes, their logs are
+    /// replaced by the branch's and the head moves to the branch's head.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the branch doesn't extend past `fork_point` or
+    /// has logs at or before it.
+    pub fn fork(&mut self, fork_point: u64, branch: &ForkFixture) -> TrackerResult<()> {
+        let log_blocks = branch
+            .syncs
+            .iter()
+            .map(|sync| Some(sync.block))
+            .chain(branch.logs.iter().map(|log| log.block_number));
+        if branch.head <= fork_point
+            || log_blocks
+                .into_iter()
+                .any(|block| block.map_or(true, |b| b <= fork_point))
+        {
+            return Err(TrackerError::config(
+                format!("Fork must extend past block {fork_point}, with logs only after it"),
+                None,
+            ));
+        }
+
+        self.reorg(fork_point);
+        self.head = branch.head;
+        for &sync in &branch.syncs {
+            self.push_sync(sync);
+        }
+        for log in &branch.logs {
+            self.push_log(log.clone());
+        }
+        Ok(())
+    }
+
+    /// Returns the logs matching `filter`, with block and transaction
+    /// details filled in.
+    #[must_use]
+    pub fn logs(&self, filter: &Filter) -> Vec<Log> {
+        let (from, to) = match filter.block_option {
+            FilterBlockOption::AtBlockHash(hash) => match self.block_number_of(hash) {
+                Some(number) => (number, number),
+                None => return Vec::new(),
+            },
+            FilterBlockOption::Range {
+                from_block,
+                to_block,
+            } => (self.resolve(from_block), self.resolve(to_block)),
+        };
+        if from > to {
+            return Vec::new();
+        }
+
+        self.logs
+            .range(from..=to.min(self.head))
+            .flat_map(|(&number, logs)| {
+                logs.iter()
+                    .enumerate()
+                    .map(move |(index, log)| self.complete(number, index, log))
+            })
+            .filter(|log| {
+                filter.address.matches(&log.address())
+                    && filter.topics.iter().enumerate().all(|(i, topics)| {
+                        topics.is_empty() || log.topics().get(i).is_some_and(|t| topics.matches(t))
+                    })
+            })
+            .collect()
+    }
+
+    fn complete(&self, block: u64, index: usize, log: &Log) -> Log {
+        let block_hash = self.block_hash(block);
+        let index = index as u64;
+        let mut log = log.clone();
+        log.block_hash = Some(block_hash);
+        log.block_timestamp = Some(self.block_timestamp(block));
+        log.transaction_hash
+            .get_or_insert_with(|| keccak256(format!("mock-tx:{block_hash}:{index}")));
+        log.transaction_index.get_or_insert(index);
+        log.log_index.get_or_insert(index);
+        log
+    }
+
+    const fn resolve(&self, tag: Option<BlockNumberOrTag>) -> u64 {
+        match tag {
+            Some(BlockNumberOrTag::Number(number)) => number,
+            Some(BlockNumberOrTag::Earliest) => 0,
+            _ => self.head,
+        }
+    }
+
+    fn block_number_of(&self, hash: B256) -> Option<u64> {
+        // Only blocks with logs are worth a filter by hash
+        self.logs
+            .keys()
+            .copied()
+            .find(|&number| self.block_hash(number) == hash)
+    }
+}
+
+/// State shared by the mock server's handlers.
+#[derive(Debug)]
+struct Shared {
+    chain: Mutex<MockChain>,
+    /// New head numbers, for `newHeads` subscriptions
+    heads: broadcast::Sender<u64>,
+    /// Requests served, by method
+    requests: Mutex<HashMap<String, u64>>,
+}
+
+impl Shared {
+    fn chain(&self) -> MutexGuard<'_, MockChain> {
+        lock(&self.chain)
+    }
+}
+
+/// A [`MockChain`] served over JSON-RPC (HTTP and WebSocket) on a local
+/// port. The server stops when the provider is dropped.
+#[derive(Debug)]
+pub struct MockProvider {
+    shared: Arc<Shared>,
+    addr: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl MockProvider {
+    /// Serves `chain` on a free local port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no local port can be bound.
+    pub async fn start(chain: MockChain) -> TrackerResult<Self> {
+        let shared = Arc::new(Shared {
+            chain: Mutex::new(chain),
+            heads: broadcast::channel(64).0,
+            requests: Mutex::new(HashMap::new()),
+        });
+        let app = Router::new()
+            .route("/", post(http_request).get(ws_upgrade))
+            .with_state(Arc::clone(&shared));
+        let (addr, server) = listen(app, 0, "mock").await?;
+
+        Ok(Self {
+            shared,
+            addr,
+            server,
+        })
+    }
+
+    /// Serves the chain in a fixture file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fixture is invalid or no port can be bound.
+    pub async fn from_fixture(path: impl AsRef<Path>) -> TrackerResult<Self> {
+        Self::start(MockChain::load(path)?).await
+    }
+
+    /// Returns the URL to use as `RPC_URL`.
+    #[must_use]
+    pub fn http_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns the URL to use as `RPC_WS_URL`.
+    #[must_use]
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Creates an HTTP provider connected to the mock.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider can't be created.
+    pub async fn provider(&self) -> TrackerResult<Provider> {
+        create_provider(&self.http_url()).await
+    }
+
+    /// Returns the latest block number.
+    #[must_use]
+    pub fn head(&self) -> u64 {
+        self.shared.chain().head()
+    }
+
+    /// Returns the hash of block `number` on the current chain.
+    #[must_use]
+    pub fn block_hash(&self, number: u64) -> B256 {
+        self.shared.chain().block_hash(number)
+    }
+
+    /// Adds a Sync event of the pair.
+    pub fn push_sync(&self, block: u64, reserve0: U112, reserve1: U112) {
+        self.shared.chain().push_sync(MockSync {
+            block,
+            reserve0,
+            reserve1,
+        });
+    }
+
+    /// Mines `count` blocks, announcing each to `newHeads` subscribers.
+    pub fn mine(&self, count: u64) {
+        let head = self.shared.chain().mine(count);
+        for number in head + 1 - count..=head {
+            // No subscribers is fine
+            let _ = self.shared.heads.send(number);
+        }
+    }
+
+    /// Replaces the blocks after `fork_point` with a new fork (see
+    /// [`MockChain::reorg`]).
+    pub fn reorg(&self, fork_point: u64) {
+        self.shared.chain().reorg(fork_point);
+    }
+
+    /// Switches to the competing chain `branch` (see [`MockChain::fork`]),
+    /// announcing its head to `newHeads` subscribers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the branch is invalid; the chain is unchanged.
+    pub fn fork(&self, fork_point: u64, branch: &ForkFixture) -> TrackerResult<()> {
+        let head = {
+            let mut chain = self.shared.chain();
+            chain.fork(fork_point, branch)?;
+            chain.head()
+        };
+        let _ = self.shared.heads.send(head);
+        Ok(())
+    }
+
+    /// Returns how many times `method` has been called.
+    #[must_use]
+    pub fn requests(&self, method: &str) -> u64 {
+        lock(&self.shared.requests)
+            .get(method)
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl Drop for MockProvider {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Reorg simulation: serves chain A until [`ReorgSimulation::switch`], then
+/// chain B, which shares A's blocks up to the fork point.
+///
+/// Drives end-to-end reorg tests that Anvil can't: index chain A, switch,
+/// and check that the stored rows after the fork point are replaced by chain
+/// B's. Chain A's logs in blocks past its head appear as those blocks are
+/// mined, so a test can index it block by block before switching.
+#[derive(Debug)]
+pub struct ReorgSimulation {
+    mock: MockProvider,
+    fork_point: u64,
+    chain_b: ForkFixture,
+}
+
+impl ReorgSimulation {
+    /// Serves `chain_a`, ready to switch to `chain_b` forking after
+    /// `fork_point`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if chain B is invalid (see [`MockChain::fork`]) or
+    /// no local port can be bound.
+    pub async fn start(
+        chain_a: MockChain,
+        fork_point: u64,
+        chain_b: ForkFixture,
+    ) -> TrackerResult<Self> {
+        // Catch an invalid chain B now rather than at the switch
+        chain_a.clone().fork(fork_point, &chain_b)?;
+        Ok(Self {
+            mock: MockProvider::start(chain_a).await?,
+            fork_point,
+            chain_b,
+        })
+    }
+
+    /// Loads a [`ReorgFixture`] file and serves its chain A.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fixture is invalid or no port can be bound.
+    pub async fn from_fixture(path: impl AsRef<Path>) -> TrackerResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            TrackerError::config(
+                format!("Failed to read reorg fixture {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+        let fixture: ReorgFixture = serde_json::from_str(&contents).map_err(|e| {
+            TrackerError::config(
+                format!("Invalid reorg fixture {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+        Self::start(
+            MockChain::from_fixture(fixture.chain_a),
+            fixture.fork_point,
+            fixture.chain_b,
+        )
+        .await
+    }
+
+    /// Returns the mock serving the chains.
+    #[must_use]
+    pub const fn mock(&self) -> &MockProvider {
+        &self.mock
+    }
+
+    /// Returns the last block the chains share.
+    #[must_use]
+    pub const fn fork_point(&self) -> u64 {
+        self.fork_point
+    }
+
+    /// Switches from chain A to chain B.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if chain B no longer forks from the served chain,
+    /// e.g. after a manual reorg below the fork point.
+    pub fn switch(&self) -> TrackerResult<()> {
+        self.mock.fork(self.fork_point, &self.chain_b)
+    }
+}
+
+/// Serves `app` on `port` of localhost (any free port for 0) in the
+/// background, returning the bound address.
+async fn listen(app: Router, port: u16, name: &str) -> TrackerResult<(SocketAddr, JoinHandle<()>)> {
+    let bind_error = |e: std::io::Error| {
+        TrackerError::rpc(
+            format!("Failed to bind the {name} RPC server"),
+            Some(Box::new(e)),
+        )
+    };
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))
+        .await
+        .map_err(bind_error)?;
+    let addr = listener.local_addr().map_err(bind_error)?;
+
+    debug!(addr = %addr, "{name} RPC server listening");
+    let name = name.to_string();
+    let server = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            debug!(error = %e, "{name} RPC server stopped");
+        }
+    });
+    Ok((addr, server))
+}
+
+async fn http_request(State(shared): State<Arc<Shared>>, Json(body): Json<Value>) -> Json<Value> {
+    Json(match body {
+        Value::Array(batch) => batch
+            .into_iter()
+            .map(|request| respond(&shared, &request))
+            .collect(),
+        request => respond(&shared, &request),
+    })
+}
+
+async fn ws_upgrade(State(shared): State<Arc<Shared>>, ws: WebSocketUpgrade) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| ws_session(shared, socket))
+}
+
+/// Answers requests on one WebSocket connection and pushes new heads to its
+/// `newHeads` subscriptions.
+async fn ws_session(shared: Arc<Shared>, mut socket: WebSocket) {
+    let mut heads = shared.heads.subscribe();
+    let mut subscriptions: Vec<U64> = Vec::new();
+    let mut next_id = 1u64;
+
+    loop {
+        let reply = tokio::select! {
+            message = socket.recv() => {
+                let Some(Ok(Message::Text(text))) = message else {
+                    match message {
+                        Some(Ok(_)) => continue,
+                        _ => return,
+                    }
+                };
+                let Ok(request) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+                match request["method"].as_str() {
+                    Some("eth_subscribe") => {
+                        count(&shared, "eth_subscribe");
+                        if request["params"][0] == "newHeads" {
+                            let id = U64::from(next_id);
+                            next_id += 1;
+                            subscriptions.push(id);
+                            success(&request, &json!(id))
+                        } else {
+                            failure(&request, INVALID_PARAMS, "only newHeads subscriptions are supported")
+                        }
+                    }
+                    Some("eth_unsubscribe") => {
+                        count(&shared, "eth_unsubscribe");
+                        let id = serde_json::from_value::<U64>(request["params"][0].clone()).ok();
+                        let before = subscriptions.len();
+                        subscriptions.retain(|s| Some(*s) != id);
+                        success(&request, &json!(subscriptions.len() < before))
+                    }
+                    _ => respond(&shared, &request),
+                }
+            }
+            head = heads.recv() => {
+                let Ok(number) = head else { continue };
+                let Some(block) = shared.chain().block(number) else { continue };
+                for id in &subscriptions {
+                    let notification = json!({
+                        "jsonrpc": "2.0",
+                        "method": "eth_subscription",
+                        "params": { "subscription": id, "result": block.header },
+                    });
+                    if socket.send(Message::Text(notification.to_string())).await.is_err() {
+                        return;
+                    }
+                }
+                continue;
+            }
+        };
+
+        if socket.send(Message::Text(reply.to_string())).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Answers one JSON-RPC request.
+fn respond(shared: &Shared, request: &Value) -> Value {
+    let method = request["method"].as_str().unwrap_or_default();
+    count(shared, method);
+    let params = request["params"].clone();
+
+    let result = {
+        let chain = shared.chain();
+        match method {
+            "eth_chainId" => Ok(json!(U64::from(chain.chain_id))),
+            "net_version" => Ok(json!(chain.chain_id.to_string())),
+            "eth_blockNumber" => Ok(json!(U64::from(chain.head()))),
+            "eth_getBlockByNumber" => serde_json::from_value::<(BlockNumberOrTag, bool)>(params)
+                .map(|(number, _)| json!(chain.block(chain.resolve(Some(number)))))
+                .map_err(|e| (INVALID_PARAMS, e.to_string())),
+            "eth_getLogs" => serde_json::from_value::<(Filter,)>(params)
+                .map(|(filter,)| json!(chain.logs(&filter)))
+                .map_err(|e| (INVALID_PARAMS, e.to_string())),
+            _ => Err((METHOD_NOT_FOUND, format!("{method} is not supported"))),
+        }
+    };
+
+    match result {
+        Ok(result) => success(request, &result),
+        Err((code, message)) => failure(request, code, &message),
+    }
+}
+
+fn success(request: &Value, result: &Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+}
+
+fn failure(request: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn count(shared: &Shared, method: &str) {
+    *lock(&shared.requests)
+        .entry(method.to_string())
+        .or_default() += 1;
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panicking test thread can't leave the chain half-updated
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{create_sync_filter_for_pair, decode_sync_event};
+    use crate::rpc::websocket::WebSocketProvider;
+    use alloy::providers::Provider as _;
+    use alloy::rpc::types::BlockTransactionsKind;
+    use futures_util::StreamExt;
+
+    fn chain() -> MockChain {
+        let mut chain = MockChain::new(1, 100);
+        for block in [10, 20, 20, 30] {
+            chain.push_sync(MockSync {
+                block,
+                reserve0: U112::from(1_000u64),
+                reserve1: U112::from(block),
+            });
+        }
+        chain
+    }
+
+    #[test]
+    fn test_blocks_link_and_reorg_rehashes_after_fork() {
+        let mut chain = chain();
+        let block = chain.block(50).unwrap();
+        assert_eq!(block.header.parent_hash, chain.block_hash(49));
+        assert_eq!(
+            block.header.timestamp - chain.block(49).unwrap().header.timestamp,
+            BLOCK_TIME
+        );
+        assert!(chain.block(101).is_none());
+
+        let before: Vec<_> = (0..=100).map(|n| chain.block_hash(n)).collect();
+        chain.reorg(20);
+        assert_eq!(chain.block_hash(20), before[20]);
+        assert_ne!(chain.block_hash(21), before[21]);
+        assert_ne!(chain.block_hash(100), before[100]);
+
+        let filter = create_sync_filter_for_pair(UNISWAP_V2_WETH_USDT_PAIR, 0, 100);
+        let blocks: Vec<_> = chain.logs(&filter).iter().map(|l| l.block_number).collect();
+        assert_eq!(blocks, [Some(10), Some(20), Some(20)]);
+    }
+
+    #[test]
+    fn test_fork_replaces_blocks_and_logs_after_fork_point() {
+        let mut chain = chain();
+        let shared = chain.block_hash(15);
+        let replaced = chain.block_hash(25);
+        let branch = ForkFixture {
+            head: 120,
+            syncs: vec![MockSync {
+                block: 110,
+                reserve0: U112::from(1_000u64),
+                reserve1: U112::from(110u64),
+            }],
+            logs: Vec::new(),
+        };
+        assert!(chain.clone().fork(110, &branch).is_err());
+        assert!(chain.clone().fork(120, &branch).is_err());
+
+        chain.fork(15, &branch).unwrap();
+        assert_eq!(chain.head(), 120);
+        assert_eq!(chain.block_hash(15), shared);
+        assert_ne!(chain.block_hash(25), replaced);
+        let filter = create_sync_filter_for_pair(UNISWAP_V2_WETH_USDT_PAIR, 0, 120);
+        let blocks: Vec<_> = chain.logs(&filter).iter().map(|l| l.block_number).collect();
+        assert_eq!(blocks, [Some(10), Some(110)]);
+    }
+
+    #[test]
+    fn test_logs_are_filtered_and_completed() {
+        let chain = chain();
+        let logs = chain.logs(&create_sync_filter_for_pair(
+            UNISWAP_V2_WETH_USDT_PAIR,
+            15,
+            30,
+        ));
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[1].log_index, Some(1));
+        assert_eq!(logs[1].block_hash, Some(chain.block_hash(20)));
+        assert_ne!(logs[0].transaction_hash, logs[1].transaction_hash);
+        assert_eq!(
+            decode_sync_event(&logs[2]).unwrap().0.reserve1,
+            U112::from(30u64)
+        );
+
+        let other_pair = create_sync_filter_for_pair(Address::repeat_byte(1), 0, 100);
+        assert!(chain.logs(&other_pair).is_empty());
+    }
+
+    #[test]
+    fn test_fixture_parses_decimal_reserves() {
+        let fixture: MockFixture = serde_json::from_str(
+            r#"{"head": 19000010, "syncs": [{"block": 19000005, "reserve0": "45500000000000000000", "reserve1": "111475000000"}]}"#,
+        )
+        .unwrap();
+        let chain = MockChain::from_fixture(fixture);
+        assert_eq!(chain.block_timestamp(19_000_010), default_head_timestamp());
+        let logs = chain.logs(&Filter::new().from_block(0u64));
+        assert_eq!(logs[0].block_number, Some(19_000_005));
+    }
+
+    #[tokio::test]
+    async fn test_serves_provider_calls() {
+        let mock = MockProvider::start(chain()).await.unwrap();
+        let provider = mock.provider().await.unwrap();
+
+        assert_eq!(provider.get_chain_id().await.unwrap(), 1);
+        assert_eq!(provider.get_block_number().await.unwrap(), 100);
+        let block = provider
+            .get_block_by_number(20u64.into(), BlockTransactionsKind::Hashes)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(block.header.hash, mock.block_hash(20));
+        let logs = provider
+            .get_logs(&create_sync_filter_for_pair(
+                UNISWAP_V2_WETH_USDT_PAIR,
+                0,
+                100,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(logs.len(), 4);
+        assert!(provider.get_gas_price().await.is_err());
+        assert_eq!(mock.requests("eth_getLogs"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_new_heads_reach_subscribers() {
+        let mock = MockProvider::start(chain()).await.unwrap();
+        let ws = WebSocketProvider::connect(mock.ws_url()).await.unwrap();
+        let heads = ws.subscribe_blocks().await.unwrap();
+        tokio::pin!(heads);
+
+        mock.mine(2);
+        assert_eq!(heads.next().await.unwrap().number, 101);
+        let head = heads.next().await.unwrap();
+        assert_eq!(head.number, 102);
+        assert_eq!(head.hash, mock.block_hash(102));
+    }
+}
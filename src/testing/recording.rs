@@ -0,0 +1,511 @@
+//! Recording and replay of real RPC responses.
+//!
+//! [`RecordingProxy`] forwards JSON-RPC requests to a real node and captures
+//! every response; [`Recording::save`] writes them to a fixture file that
+//! [`ReplayProvider`] serves back. A command run once against a node through
+//! the proxy can then be re-run offline, with the same responses, in tests or
+//! local development without an API key:
+//!
+//! ```bash
+//! # Record `price` against the node in RPC_URL, pinning the head block
+//! eth-uniswap-alloy fixture record price.json --head 19000000 --port 8546
+//! RPC_URL=http://127.0.0.1:8546 eth-uniswap-alloy price
+//!
+//! # Serve the recording, then repeat the run offline
+//! eth-uniswap-alloy fixture serve price.json --port 8546
+//! RPC_URL=http://127.0.0.1:8546 eth-uniswap-alloy price
+//! ```
+//!
+//! Requests are matched on method and params, ignoring the order of
+//! alternatives in an `eth_getLogs` topic list. A request made several times
+//! gets its recorded responses in order, then the last one again, so a
+//! polling loop replays as it ran. Pinning the head answers `eth_blockNumber`
+//! and `latest` block lookups with a fixed block, so the recording covers a
+//! fixed block range whenever it's made. Only HTTP is recorded; WebSocket
+//! subscriptions aren't replayed.
+
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::{failure, listen, lock, success};
+use crate::error::{TrackerError, TrackerResult};
+use crate::rpc::{create_provider, Provider};
+
+/// JSON-RPC error code for a request the node couldn't be asked, or a replay
+/// has no response for.
+const NO_RESPONSE: i64 = -32000;
+
+/// One recorded request and the node's response to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordedCall {
+    /// JSON-RPC method
+    pub method: String,
+    /// Request params
+    #[serde(default)]
+    pub params: Value,
+    /// Response result, unless the node returned an error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Response error object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+}
+
+impl RecordedCall {
+    fn matches(&self, method: &str, params: &Value) -> bool {
+        self.method == method && &self.params == params
+    }
+
+    fn reply(&self, request: &Value) -> Value {
+        self.error.as_ref().map_or_else(
+            || success(request, self.result.as_ref().unwrap_or(&Value::Null)),
+            |error| json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+        )
+    }
+}
+
+/// RPC fixture: the calls captured by a [`RecordingProxy`], in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Recording {
+    /// Head block pinned while recording, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<u64>,
+    /// Recorded calls
+    pub calls: Vec<RecordedCall>,
+}
+
+impl Recording {
+    /// Loads a recording from a fixture file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't a valid recording.
+    pub fn load(path: impl AsRef<Path>) -> TrackerResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            TrackerError::config(
+                format!("Failed to read RPC recording {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })?;
+        serde_json::from_str(&contents).map_err(|e| {
+            TrackerError::config(
+                format!("Invalid RPC recording {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })
+    }
+
+    /// Writes the recording to a fixture file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> TrackerResult<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self).map_err(|e| {
+            TrackerError::config("Failed to serialize RPC recording", Some(Box::new(e)))
+        })?;
+        std::fs::write(path, contents + "\n").map_err(|e| {
+            TrackerError::config(
+                format!("Failed to write RPC recording {}", path.display()),
+                Some(Box::new(e)),
+            )
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ProxyShared {
+    upstream: String,
+    client: reqwest::Client,
+    head: Option<u64>,
+    calls: Mutex<Vec<RecordedCall>>,
+}
+
+/// JSON-RPC proxy recording a real node's responses.
+#[derive(Debug)]
+pub struct RecordingProxy {
+    shared: Arc<ProxyShared>,
+    addr: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl RecordingProxy {
+    /// Proxies `upstream` on `port` of localhost (any free port for 0),
+    /// answering as if `head` were the latest block when given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the port can't be bound.
+    pub async fn start(upstream: &str, head: Option<u64>, port: u16) -> TrackerResult<Self> {
+        let shared = Arc::new(ProxyShared {
+            upstream: upstream.to_string(),
+            client: reqwest::Client::new(),
+            head,
+            calls: Mutex::new(Vec::new()),
+        });
+        let app = Router::new()
+            .route("/", post(proxy_request))
+            .with_state(Arc::clone(&shared));
+        let (addr, server) = listen(app, port, "recording").await?;
+        Ok(Self {
+            shared,
+            addr,
+            server,
+        })
+    }
+
+    /// Returns the URL to use as `RPC_URL`.
+    #[must_use]
+    pub fn http_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns the calls recorded so far.
+    #[must_use]
+    pub fn recording(&self) -> Recording {
+        Recording {
+            head: self.shared.head,
+            calls: lock(&self.shared.calls).clone(),
+        }
+    }
+}
+
+impl Drop for RecordingProxy {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn proxy_request(
+    State(shared): State<Arc<ProxyShared>>,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    Json(match body {
+        Value::Array(batch) => {
+            let mut replies = Vec::with_capacity(batch.len());
+            for request in batch {
+                replies.push(record(&shared, &request).await);
+            }
+            Value::Array(replies)
+        }
+        request => record(&shared, &request).await,
+    })
+}
+
+/// Answers one request from the node, recording the response.
+async fn record(shared: &ProxyShared, request: &Value) -> Value {
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let params = canonical_params(&method, &request["params"]);
+
+    let pinned = shared.head.filter(|_| method == "eth_blockNumber");
+    let reply = if let Some(head) = pinned {
+        success(request, &json!(format!("{head:#x}")))
+    } else {
+        let mut forwarded = request.clone();
+        if let Some(head) = shared.head {
+            pin_latest(&method, &mut forwarded["params"], head);
+        }
+        match forward(shared, &forwarded).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                // Nothing to record: the node never answered
+                warn!(method = %method, error = %e, "Failed to forward RPC request");
+                return failure(request, NO_RESPONSE, &e.to_string());
+            }
+        }
+    };
+
+    debug!(method = %method, "Recorded RPC response");
+    lock(&shared.calls).push(RecordedCall {
+        method,
+        params,
+        result: reply.get("result").cloned(),
+        error: reply.get("error").cloned(),
+    });
+    reply
+}
+
+/// Returns `params` with the alternatives in each `eth_getLogs` topic list
+/// sorted, since their order doesn't change the logs returned.
+fn canonical_params(method: &str, params: &Value) -> Value {
+    let mut params = params.clone();
+    let topics = params
+        .get_mut(0)
+        .and_then(|filter| filter.get_mut("topics"))
+        .and_then(Value::as_array_mut);
+    if let (true, Some(topics)) = (method == "eth_getLogs", topics) {
+        for alternatives in topics.iter_mut().filter_map(Value::as_array_mut) {
+            alternatives.sort_by_key(ToString::to_string);
+        }
+    }
+    params
+}
+
+/// Replaces a `latest` block tag in the params of `method` with `head`.
+fn pin_latest(method: &str, params: &mut Value, head: u64) {
+    let tag = match method {
+        "eth_getBlockByNumber" => params.get_mut(0),
+        "eth_call" | "eth_getBalance" | "eth_getCode" => params.get_mut(1),
+        "eth_getLogs" => params
+            .get_mut(0)
+            .and_then(|filter| filter.get_mut("toBlock")),
+        _ => None,
+    };
+    if let Some(tag) = tag.filter(|tag| *tag == "latest") {
+        *tag = json!(format!("{head:#x}"));
+    }
+}
+
+async fn forward(shared: &ProxyShared, request: &Value) -> Result<Value, reqwest::Error> {
+    shared
+        .client
+        .post(&shared.upstream)
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+#[derive(Debug)]
+struct ReplayShared {
+    /// Recorded calls not yet replayed
+    calls: Mutex<Vec<RecordedCall>>,
+}
+
+/// JSON-RPC server answering with the responses of a [`Recording`].
+#[derive(Debug)]
+pub struct ReplayProvider {
+    shared: Arc<ReplayShared>,
+    addr: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl ReplayProvider {
+    /// Serves `recording` on `port` of localhost (any free port for 0).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the port can't be bound.
+    pub async fn start(recording: Recording, port: u16) -> TrackerResult<Self> {
+        let shared = Arc::new(ReplayShared {
+            calls: Mutex::new(recording.calls),
+        });
+        let app = Router::new()
+            .route("/", post(replay_request))
+            .with_state(Arc::clone(&shared));
+        let (addr, server) = listen(app, port, "replay").await?;
+        Ok(Self {
+            shared,
+            addr,
+            server,
+        })
+    }
+
+    /// Serves the recording in a fixture file on any free port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fixture is invalid or no port can be bound.
+    pub async fn from_fixture(path: impl AsRef<Path>) -> TrackerResult<Self> {
+        Self::start(Recording::load(path)?, 0).await
+    }
+
+    /// Returns the URL to use as `RPC_URL`.
+    #[must_use]
+    pub fn http_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Creates an HTTP provider connected to the replay.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider can't be created.
+    pub async fn provider(&self) -> TrackerResult<Provider> {
+        create_provider(&self.http_url()).await
+    }
+
+    /// Returns how many recorded calls haven't been replayed yet, not
+    /// counting the last response to each request, which is kept.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        let calls = lock(&self.shared.calls);
+        calls
+            .iter()
+            .enumerate()
+            .filter(|(i, call)| {
+                calls[i + 1..]
+                    .iter()
+                    .any(|later| later.matches(&call.method, &call.params))
+            })
+            .count()
+    }
+}
+
+impl Drop for ReplayProvider {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn replay_request(
+    State(shared): State<Arc<ReplayShared>>,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    Json(match body {
+        Value::Array(batch) => batch
+            .iter()
+            .map(|request| replay(&shared, request))
+            .collect(),
+        request => replay(&shared, &request),
+    })
+}
+
+/// Answers one request with its next recorded response.
+fn replay(shared: &ReplayShared, request: &Value) -> Value {
+    let method = request["method"].as_str().unwrap_or_default();
+    let params = &canonical_params(method, &request["params"]);
+
+    let mut calls = lock(&shared.calls);
+    let mut matching = calls
+        .iter()
+        .enumerate()
+        .filter(|(_, call)| call.matches(method, params))
+        .map(|(i, _)| i);
+    let Some(first) = matching.next() else {
+        drop(calls);
+        return failure(
+            request,
+            NO_RESPONSE,
+            &format!("No recorded response to {method} {params}"),
+        );
+    };
+    // The last response to a request is kept for later repeats
+    let call = if matching.next().is_some() {
+        calls.remove(first)
+    } else {
+        calls[first].clone()
+    };
+    drop(calls);
+    call.reply(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{create_sync_filter_for_pair, UNISWAP_V2_WETH_USDT_PAIR};
+    use crate::testing::{MockChain, MockProvider, MockSync};
+    use alloy::primitives::aliases::U112;
+    use alloy::providers::Provider as _;
+    use alloy::rpc::types::BlockTransactionsKind;
+
+    async fn mock() -> MockProvider {
+        let mut chain = MockChain::new(1, 100);
+        chain.push_sync(MockSync {
+            block: 90,
+            reserve0: U112::from(1_000u64),
+            reserve1: U112::from(2_000u64),
+        });
+        MockProvider::start(chain).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replay_serves_recorded_responses() {
+        let mock = mock().await;
+        let proxy = RecordingProxy::start(&mock.http_url(), None, 0)
+            .await
+            .unwrap();
+        let provider = create_provider(&proxy.http_url()).await.unwrap();
+        let filter = create_sync_filter_for_pair(UNISWAP_V2_WETH_USDT_PAIR, 0, 100);
+
+        let head = provider.get_block_number().await.unwrap();
+        let logs = provider.get_logs(&filter).await.unwrap();
+        let block = provider
+            .get_block_by_number(90u64.into(), BlockTransactionsKind::Hashes)
+            .await
+            .unwrap();
+        assert!(provider.get_gas_price().await.is_err());
+        mock.mine(1);
+        assert_eq!(provider.get_block_number().await.unwrap(), 101);
+
+        let recording = proxy.recording();
+        drop((proxy, mock));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.json");
+        recording.save(&path).unwrap();
+        assert_eq!(Recording::load(&path).unwrap(), recording);
+
+        let replay = ReplayProvider::from_fixture(&path).await.unwrap();
+        let provider = replay.provider().await.unwrap();
+        assert_eq!(provider.get_block_number().await.unwrap(), head);
+        assert_eq!(provider.get_logs(&filter).await.unwrap(), logs);
+        let replayed = provider
+            .get_block_by_number(90u64.into(), BlockTransactionsKind::Hashes)
+            .await
+            .unwrap();
+        assert_eq!(
+            replayed.map(|b| b.header.hash),
+            block.map(|b| b.header.hash)
+        );
+        // Recorded errors are replayed too
+        assert!(provider.get_gas_price().await.is_err());
+        // Repeats get the responses in order, then the last one
+        assert_eq!(replay.remaining(), 0);
+        assert_eq!(provider.get_block_number().await.unwrap(), 101);
+        assert_eq!(provider.get_block_number().await.unwrap(), 101);
+
+        let unrecorded = create_sync_filter_for_pair(UNISWAP_V2_WETH_USDT_PAIR, 0, 50);
+        assert!(provider.get_logs(&unrecorded).await.is_err());
+    }
+
+    #[test]
+    fn test_topic_alternatives_match_in_any_order() {
+        let params = |topics: Value| json!([{ "address": "0x01", "topics": topics }]);
+        let call = RecordedCall {
+            method: "eth_getLogs".to_string(),
+            params: canonical_params("eth_getLogs", &params(json!([["0xaa", "0xbb"], null]))),
+            result: Some(json!([])),
+            error: None,
+        };
+
+        let reordered = canonical_params("eth_getLogs", &params(json!([["0xbb", "0xaa"], null])));
+        assert!(call.matches("eth_getLogs", &reordered));
+        let narrower = canonical_params("eth_getLogs", &params(json!([["0xaa"], null])));
+        assert!(!call.matches("eth_getLogs", &narrower));
+    }
+
+    #[tokio::test]
+    async fn test_recording_pins_head() {
+        let mock = mock().await;
+        let proxy = RecordingProxy::start(&mock.http_url(), Some(95), 0)
+            .await
+            .unwrap();
+        let provider = create_provider(&proxy.http_url()).await.unwrap();
+
+        assert_eq!(provider.get_block_number().await.unwrap(), 95);
+        let latest = provider
+            .get_block_by_number(
+                alloy::rpc::types::BlockNumberOrTag::Latest,
+                BlockTransactionsKind::Hashes,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.header.number, 95);
+        assert_eq!(proxy.recording().head, Some(95));
+    }
+}
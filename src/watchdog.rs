@@ -0,0 +1,255 @@
+//! Block lag watchdog.
+//!
+//! A stalled WebSocket subscription or a wedged poll loop doesn't crash the
+//! indexer; it just stops writing. When `LAG_ALERT_BLOCKS` is set, the API
+//! server runs a [`LagWatchdog`] that compares the pool's last indexed block
+//! with `eth_blockNumber` every [`LAG_CHECK_INTERVAL`]. While the lag is above
+//! the threshold:
+//!
+//! - every check logs at ERROR
+//! - crossing the threshold sends a `lag_exceeded` [`LagAlert`] to
+//!   `LAG_ALERT_WEBHOOK_URL`, if set, and falling back below it sends
+//!   `lag_recovered`; the destination may be any alert channel (see
+//!   [`crate::alerts::notify`])
+//! - the checks and alerts are counted in [`LagWatchdogStats`], reported by
+//!   `/api/v1/health`
+//!
+//! A failed chain head lookup is logged and skipped; it says nothing about
+//! the indexer.
+
+use alloy::providers::Provider as _;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::alerts::{Destination, Notification, Notifiers};
+use crate::db::repository::Repository;
+use crate::rpc::Provider;
+
+/// Interval between lag checks.
+pub const LAG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Lag threshold crossings sent to the alert destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LagEvent {
+    /// The lag rose above the threshold
+    LagExceeded,
+    /// The lag fell back to the threshold or below
+    LagRecovered,
+}
+
+/// Alert sent to `LAG_ALERT_WEBHOOK_URL`, posted as-is to webhooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LagAlert {
+    /// What happened
+    pub event: LagEvent,
+    /// Last block the indexer wrote
+    pub indexed_block: u64,
+    /// Latest block on chain
+    pub chain_head: u64,
+    /// Blocks between the two
+    pub lag_blocks: u64,
+    /// Configured threshold
+    pub max_lag_blocks: u64,
+    /// When the check ran
+    pub triggered_at: DateTime<Utc>,
+}
+
+/// Counters of the watchdog since startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LagWatchdogStats {
+    /// Completed lag checks
+    pub checks: u64,
+    /// Times the lag crossed the threshold
+    pub alerts: u64,
+    /// Lag at the last check (`None` before the first one)
+    pub lag_blocks: Option<u64>,
+    /// Whether the lag is above the threshold
+    pub lagging: bool,
+    /// When the last check completed
+    pub last_check_at: Option<DateTime<Utc>>,
+}
+
+/// Alerts when the indexer falls too far behind the chain head.
+#[derive(Debug)]
+pub struct LagWatchdog {
+    max_lag_blocks: u64,
+    destination: Option<Destination>,
+    notifiers: Notifiers,
+    stats: Mutex<LagWatchdogStats>,
+}
+
+impl LagWatchdog {
+    /// Creates a watchdog alerting above `max_lag_blocks`, notifying
+    /// `destination` if given.
+    #[must_use]
+    pub fn new(
+        max_lag_blocks: u64,
+        destination: Option<Destination>,
+        notifiers: Notifiers,
+    ) -> Self {
+        Self {
+            max_lag_blocks,
+            destination,
+            notifiers,
+            stats: Mutex::new(LagWatchdogStats::default()),
+        }
+    }
+
+    /// Records a check and returns the alert to post if the lag crossed the
+    /// threshold in either direction.
+    pub fn observe(
+        &self,
+        indexed_block: u64,
+        chain_head: u64,
+        now: DateTime<Utc>,
+    ) -> Option<LagAlert> {
+        let lag_blocks = chain_head.saturating_sub(indexed_block);
+        let lagging = lag_blocks > self.max_lag_blocks;
+        if lagging {
+            error!(
+                indexed_block,
+                chain_head,
+                lag_blocks,
+                max_lag_blocks = self.max_lag_blocks,
+                "Indexer is lagging behind the chain head"
+            );
+        }
+
+        let Ok(mut stats) = self.stats.lock() else {
+            warn!("Lag watchdog state poisoned, skipping check");
+            return None;
+        };
+        let was_lagging = stats.lagging;
+        stats.checks += 1;
+        stats.lag_blocks = Some(lag_blocks);
+        stats.lagging = lagging;
+        stats.last_check_at = Some(now);
+
+        let event = match (was_lagging, lagging) {
+            (false, true) => {
+                stats.alerts += 1;
+                LagEvent::LagExceeded
+            }
+            (true, false) => {
+                info!(lag_blocks, "Indexer caught up with the chain head");
+                LagEvent::LagRecovered
+            }
+            _ => return None,
+        };
+
+        Some(LagAlert {
+            event,
+            indexed_block,
+            chain_head,
+            lag_blocks,
+            max_lag_blocks: self.max_lag_blocks,
+            triggered_at: now,
+        })
+    }
+
+    /// Returns the counters since startup.
+    #[must_use]
+    pub fn stats(&self) -> LagWatchdogStats {
+        self.stats.lock().map(|stats| *stats).unwrap_or_default()
+    }
+
+    /// Spawns a task that checks `pool_id`'s lag every
+    /// [`LAG_CHECK_INTERVAL`] (first check immediately).
+    #[must_use]
+    pub fn spawn(
+        self: Arc<Self>,
+        reader: Repository,
+        provider: Arc<Provider>,
+        pool_id: i64,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(LAG_CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                self.check(&reader, &provider, pool_id).await;
+            }
+        })
+    }
+
+    async fn check(&self, reader: &Repository, provider: &Provider, pool_id: i64) {
+        let indexed_block = match reader.get_state(pool_id).await {
+            Ok(state) => state.map_or(0, |s| u64::try_from(s.last_indexed_block).unwrap_or(0)),
+            Err(e) => {
+                warn!(error = %e, "Lag check could not read the indexer state");
+                return;
+            }
+        };
+        let chain_head = match provider.get_block_number().await {
+            Ok(head) => head,
+            Err(e) => {
+                warn!(error = %e, "Lag check could not read the chain head");
+                return;
+            }
+        };
+
+        let Some(alert) = self.observe(indexed_block, chain_head, Utc::now()) else {
+            return;
+        };
+        if let Some(destination) = &self.destination {
+            let (event, text) = match alert.event {
+                LagEvent::LagExceeded => (
+                    "lag_exceeded",
+                    format!(
+                        "Indexer is {} blocks behind the chain head (block {} of {}), above the {} block limit",
+                        alert.lag_blocks, alert.indexed_block, alert.chain_head, alert.max_lag_blocks
+                    ),
+                ),
+                LagEvent::LagRecovered => (
+                    "lag_recovered",
+                    format!(
+                        "Indexer is back within {} blocks of the chain head (block {} of {})",
+                        alert.max_lag_blocks, alert.indexed_block, alert.chain_head
+                    ),
+                ),
+            };
+            let notification = Notification::new(event, text, &alert);
+            if let Err(e) = self
+                .notifiers
+                .deliver("lag_watchdog", destination, &notification)
+                .await
+            {
+                warn!(error = %e, "Lag alert delivery failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alerts_on_threshold_crossings_only() {
+        let watchdog = LagWatchdog::new(50, None, Notifiers::new().unwrap());
+        let now = Utc::now();
+
+        assert_eq!(watchdog.observe(1_000, 1_050, now), None);
+        let alert = watchdog.observe(1_000, 1_051, now).unwrap();
+        assert_eq!(alert.event, LagEvent::LagExceeded);
+        assert_eq!(alert.lag_blocks, 51);
+
+        // Still lagging: logged, but not alerted again
+        assert_eq!(watchdog.observe(1_000, 1_100, now), None);
+        let stats = watchdog.stats();
+        assert_eq!((stats.checks, stats.alerts), (3, 1));
+        assert!(stats.lagging);
+        assert_eq!(stats.lag_blocks, Some(100));
+
+        let alert = watchdog.observe(1_099, 1_100, now).unwrap();
+        assert_eq!(alert.event, LagEvent::LagRecovered);
+        assert!(!watchdog.stats().lagging);
+
+        let json = serde_json::to_value(&alert).unwrap();
+        assert_eq!(json["event"], "lag_recovered");
+    }
+}
@@ -0,0 +1,207 @@
+//! Resilience test: the indexing pipeline under injected faults.
+//!
+//! Runs the watch-mode pipeline over a synthetic chain while [`ChaosStorage`]
+//! and [`ChaosLogs`] inject delays, RPC errors, WebSocket disconnects, writes
+//! that fail before or after committing, and reorged responses. The harness
+//! reacts the way `watch` does: failed passes are retried, and a reorg
+//! unconfirms stored rows past the fork point and re-indexes from there.
+//!
+//! Once the chain is fully indexed, the confirmed rows must match the
+//! canonical chain exactly: every event present once, with the canonical
+//! block hash and reserves, and nothing from a fork.
+//!
+//! Requires the `chaos` feature:
+//!
+//! ```bash
+//! cargo test --features chaos --test chaos
+//! ```
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use alloy::primitives::{aliases::U112, Address, B256, U256};
+use alloy::rpc::types::Log;
+use alloy::sol_types::SolEvent;
+use eth_uniswap_alloy::chaos::{Chaos, ChaosConfig, ChaosLogs, ChaosStats, ChaosStorage};
+use eth_uniswap_alloy::db::create_pool;
+use eth_uniswap_alloy::db::repository::Repository;
+use eth_uniswap_alloy::db::storage::Storage;
+use eth_uniswap_alloy::events::Sync;
+use eth_uniswap_alloy::pipeline::Pipeline;
+use eth_uniswap_alloy::smoothing::PriceEwma;
+use eth_uniswap_alloy::state::State;
+use sqlx::SqlitePool;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+const FIRST_BLOCK: u64 = 1_000;
+const LAST_BLOCK: u64 = 1_300;
+/// Most blocks indexed per pass, as when watch keeps up with the chain
+const HEAD_STEP: u64 = 25;
+const BATCH_SIZE: u64 = 10;
+const MAX_PASSES: usize = 2_000;
+
+/// Canonical Sync logs: an event every third block, two in every 21st.
+fn canonical_chain(pool_address: Address) -> Vec<Log> {
+    let mut logs = Vec::new();
+    for block in (FIRST_BLOCK..=LAST_BLOCK).filter(|b| b % 3 == 0) {
+        let events = if block % 7 == 0 { 2 } else { 1 };
+        for log_index in 0..events {
+            let sync = Sync {
+                reserve0: U112::from(1_000u64) * U112::from(10u64).pow(U112::from(18u64)),
+                reserve1: U112::from(2_000_000u64 + block * 10 + log_index)
+                    * U112::from(10u64).pow(U112::from(6u64)),
+            };
+            logs.push(Log {
+                inner: alloy::primitives::Log {
+                    address: pool_address,
+                    data: sync.encode_log_data(),
+                },
+                block_hash: Some(B256::from(U256::from(block))),
+                block_number: Some(block),
+                block_timestamp: Some(1_700_000_000 + block * 12),
+                transaction_hash: Some(B256::from(U256::from(block * 10 + log_index))),
+                transaction_index: Some(log_index),
+                log_index: Some(log_index),
+                removed: false,
+            });
+        }
+    }
+    logs
+}
+
+/// `(block_number, tx_hash, log_index, block_hash, reserve1)` of a log, as stored.
+fn stored_key(log: &Log) -> (i64, String, i64, String, String) {
+    let (sync, _) = eth_uniswap_alloy::events::decode_sync_event(log).unwrap();
+    (
+        i64::try_from(log.block_number.unwrap()).unwrap(),
+        format!("{:?}", log.transaction_hash.unwrap()),
+        i64::try_from(log.log_index.unwrap()).unwrap(),
+        format!("{:?}", log.block_hash.unwrap()),
+        U256::from(sync.reserve1).to_string(),
+    )
+}
+
+/// Indexes the chain under faults seeded by `seed`, checks the stored rows
+/// and returns the faults injected.
+async fn run_under_chaos(seed: u64) -> ChaosStats {
+    let db = create_pool("sqlite::memory:").await.unwrap();
+    let repo = Repository::new(db.clone());
+    let pool_id = repo.ensure_default_pool().await.unwrap();
+    let pool = repo.get_pool_by_id(pool_id).await.unwrap().unwrap();
+    let chain = canonical_chain(pool.address.get());
+
+    let chaos = Arc::new(Chaos::new(ChaosConfig::turbulent(), seed));
+    let storage = ChaosStorage::new(repo, Arc::clone(&chaos));
+    let logs = ChaosLogs::new(Arc::clone(&chaos));
+
+    let mut state = State::new();
+    let mut ewma = Some(PriceEwma::new(300));
+    let mut last_processed = FIRST_BLOCK - 1;
+
+    for _ in 0..MAX_PASSES {
+        if last_processed >= LAST_BLOCK {
+            break;
+        }
+        let head = (last_processed + HEAD_STEP).min(LAST_BLOCK);
+        let from_block = last_processed + 1;
+        let batches = (from_block..=head)
+            .step_by(usize::try_from(BATCH_SIZE).unwrap())
+            .map(|start| (start, (start + BATCH_SIZE - 1).min(head)));
+
+        let result = Pipeline::new(&storage, &pool, 1)
+            .unwrap()
+            .with_capacity(2)
+            .run(
+                batches,
+                |from, to| {
+                    let range: Vec<Log> = chain
+                        .iter()
+                        .filter(|log| (from..=to).contains(&log.block_number.unwrap()))
+                        .cloned()
+                        .collect();
+                    logs.fetch(from, to, |_, _| async move { Ok(range) })
+                },
+                &mut state,
+                &mut ewma,
+                |_| {},
+            )
+            .await;
+
+        // The reorg detector: unconfirm everything past the earliest fork
+        if let Some(fork_point) = logs.take_reorgs().into_iter().min() {
+            while storage
+                .invalidate_from_block(pool_id, fork_point + 1)
+                .await
+                .is_err()
+            {}
+            // A failed run already restored the state to `last_processed`
+            if result.is_ok() {
+                state.invalidate_from(fork_point);
+                if let Some(ewma) = ewma.as_mut() {
+                    ewma.rewind(fork_point);
+                }
+                last_processed = fork_point;
+            }
+            continue;
+        }
+
+        if result.is_ok() {
+            last_processed = head;
+        }
+    }
+    assert_eq!(last_processed, LAST_BLOCK, "did not converge (seed {seed})");
+
+    assert_matches_chain(&db, pool_id, &chain).await;
+    chaos.stats()
+}
+
+/// Confirmed rows must be exactly the canonical events.
+async fn assert_matches_chain(db: &SqlitePool, pool_id: i64, chain: &[Log]) {
+    let events: Vec<(i64, String, i64, String, String)> = sqlx::query_as(
+        "SELECT block_number, tx_hash, log_index, block_hash, reserve1 FROM sync_events
+         WHERE pool_id = ? AND is_confirmed = 1 ORDER BY block_number, log_index",
+    )
+    .bind(pool_id)
+    .fetch_all(db)
+    .await
+    .unwrap();
+    let expected: Vec<_> = chain.iter().map(stored_key).collect();
+    assert_eq!(events, expected, "confirmed sync events differ from chain");
+
+    let prices: Vec<(i64, String, String)> = sqlx::query_as(
+        "SELECT block_number, tx_hash, reserve1_raw FROM price_points
+         WHERE pool_id = ? AND is_confirmed = 1 ORDER BY block_number, tx_hash",
+    )
+    .bind(pool_id)
+    .fetch_all(db)
+    .await
+    .unwrap();
+    let expected: BTreeSet<_> = chain
+        .iter()
+        .map(stored_key)
+        .map(|(block, tx, _, _, reserve1)| (block, tx, reserve1))
+        .collect();
+    assert_eq!(prices.len(), expected.len(), "duplicate or missing prices");
+    assert_eq!(
+        prices.into_iter().collect::<BTreeSet<_>>(),
+        expected,
+        "confirmed price points differ from chain"
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_survives_chaos() {
+    let mut total = ChaosStats::default();
+    for seed in 1..=8 {
+        let stats = run_under_chaos(seed).await;
+        total.delays += stats.delays;
+        total.errors += stats.errors;
+        total.disconnects += stats.disconnects;
+        total.reorgs += stats.reorgs;
+    }
+
+    // The test only means something if every kind of fault happened
+    assert!(total.delays > 0 && total.errors > 0, "{total:?}");
+    assert!(total.disconnects > 0 && total.reorgs > 0, "{total:?}");
+}
@@ -0,0 +1,369 @@
+//! Offline integration tests against the mock RPC provider.
+//!
+//! Serves `tests/fixtures/mock_chain.json` with [`MockProvider`] and runs the
+//! reorg detector and the `watch` binary against it: no Anvil, no network.
+//! The fixture chain ends at block 19,000,010 with four Sync events, the last
+//! one in the head block.
+//!
+//! The deep reorg tests serve `tests/fixtures/reorg_depth3.json` with
+//! [`ReorgSimulation`]: chain A is indexed up to block 19,000,010, then chain
+//! B replaces the three blocks after 19,000,007.
+//!
+//! The recording test runs `watch` through `fixture record`, then again
+//! against a [`ReplayProvider`] serving the recording, with the mock gone.
+//!
+//! Requires the `testing` feature:
+//!
+//! ```bash
+//! cargo test --features testing --test mock_provider
+//! ```
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+
+use alloy::primitives::aliases::U112;
+use alloy::providers::Provider as _;
+use alloy::rpc::types::BlockTransactionsKind;
+use eth_uniswap_alloy::db::connect_read_only;
+use eth_uniswap_alloy::reorg::{BlockRecord, ReorgDetector};
+use eth_uniswap_alloy::testing::{MockProvider, ReorgSimulation, ReplayProvider};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+
+const HEAD: u64 = 19_000_010;
+
+/// How long `watch` gets to index something before a test fails.
+const WATCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Last block shared by the chains of the deep reorg fixture
+const FORK_POINT: u64 = 19_000_007;
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mock_chain.json")
+}
+
+fn reorg_fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/reorg_depth3.json")
+}
+
+/// `amount` whole tokens with `decimals` decimals.
+fn tokens(amount: u64, decimals: u8) -> U112 {
+    U112::from(amount) * U112::from(10u64).pow(U112::from(decimals))
+}
+
+async fn record(provider: &eth_uniswap_alloy::rpc::Provider, number: u64) -> BlockRecord {
+    let block = provider
+        .get_block_by_number(number.into(), BlockTransactionsKind::Hashes)
+        .await
+        .unwrap()
+        .unwrap();
+    BlockRecord::from_block(&block)
+}
+
+#[tokio::test]
+async fn test_detector_sees_reorg_of_last_block() {
+    let mock = MockProvider::from_fixture(fixture()).await.unwrap();
+    let provider = mock.provider().await.unwrap();
+    let mut detector = ReorgDetector::with_block(record(&provider, HEAD).await);
+
+    // A new block on the same chain
+    mock.mine(1);
+    assert_eq!(
+        detector.detect_reorg(&provider, HEAD + 1).await.unwrap(),
+        None
+    );
+
+    // The head is replaced, then a block is built on the new fork
+    mock.reorg(HEAD - 1);
+    mock.mine(1);
+    assert_eq!(
+        detector.detect_reorg(&provider, HEAD + 1).await.unwrap(),
+        Some(HEAD - 1)
+    );
+    assert_eq!(detector.reorg_count(), 1);
+}
+
+#[tokio::test]
+async fn test_detector_finds_fork_of_deep_reorg() {
+    let simulation = ReorgSimulation::from_fixture(reorg_fixture())
+        .await
+        .unwrap();
+    let mock = simulation.mock();
+    let provider = mock.provider().await.unwrap();
+
+    let mut detector = ReorgDetector::new();
+    for block in 19_000_000..=19_000_006 {
+        detector.add_block(record(&provider, block).await);
+    }
+    for _ in 0..4 {
+        mock.mine(1);
+        detector.add_block(record(&provider, mock.head()).await);
+    }
+    assert_eq!(mock.head(), 19_000_010);
+
+    simulation.switch().unwrap();
+    assert_eq!(mock.head(), 19_000_012);
+
+    // Both the next block and a gap find the same fork point
+    for latest in [19_000_011, 19_000_012] {
+        let mut detector = detector.clone();
+        assert_eq!(
+            detector.detect_reorg(&provider, latest).await.unwrap(),
+            Some(FORK_POINT)
+        );
+    }
+}
+
+/// The `watch` binary, indexing into a database in a temporary directory.
+struct Watch {
+    child: Child,
+    dir: tempfile::TempDir,
+}
+
+impl Watch {
+    fn start(mock: &MockProvider, args: &[&str]) -> Self {
+        Self::spawn(&mock.http_url(), Some(&mock.ws_url()), args)
+    }
+
+    fn spawn(rpc_url: &str, ws_url: Option<&str>, args: &[&str]) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("prices.db");
+        let mut command = Command::new(env!("CARGO_BIN_EXE_eth-uniswap-alloy"));
+        command.env_clear();
+        if let Some(ws_url) = ws_url {
+            command.env("RPC_WS_URL", ws_url);
+        }
+        let child = command
+            .arg("watch")
+            .args(["--start-block", "19000000"])
+            .args(args)
+            .current_dir(dir.path())
+            .env("RPC_URL", rpc_url)
+            .env("DATABASE_URL", format!("sqlite:{}", database.display()))
+            .env("CONFIRMATION_DEPTH", "0")
+            // Errors still reach stderr, to explain a failing test
+            .env("RUST_LOG", "error")
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        Self { child, dir }
+    }
+
+    /// Confirmed prices at `block`.
+    async fn prices_at(&self, block: u64) -> Vec<f64> {
+        let database = self.dir.path().join("prices.db");
+        let Ok(pool) = connect_read_only(&database, 1).await else {
+            return Vec::new();
+        };
+        sqlx::query_scalar(
+            "SELECT price FROM price_points
+             WHERE block_number = ? AND is_confirmed = 1
+             ORDER BY id",
+        )
+        .bind(i64::try_from(block).unwrap())
+        .fetch_all(&pool)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Indexer checkpoint: last indexed block and reorg count.
+    async fn checkpoint(&self) -> Option<(i64, i64)> {
+        let database = self.dir.path().join("prices.db");
+        let pool = connect_read_only(&database, 1).await.ok()?;
+        sqlx::query_as("SELECT last_indexed_block, reorg_count FROM indexer_state")
+            .fetch_optional(&pool)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Recorded reorgs: fork point, depth and invalidated events.
+    async fn reorgs(&self) -> Vec<(i64, i64, i64)> {
+        let database = self.dir.path().join("prices.db");
+        let Ok(pool) = connect_read_only(&database, 1).await else {
+            return Vec::new();
+        };
+        sqlx::query_as("SELECT fork_point, depth, invalidated_events FROM reorgs ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Waits until `watch` has indexed up to `block`.
+    async fn wait_for_block(&mut self, block: u64) {
+        let expected = i64::try_from(block).unwrap();
+        let deadline = tokio::time::Instant::now() + WATCH_TIMEOUT;
+        loop {
+            let checkpoint = self.checkpoint().await;
+            if checkpoint.is_some_and(|(indexed, _)| indexed >= expected) {
+                return;
+            }
+            assert!(
+                self.child.try_wait().unwrap().is_none(),
+                "watch exited early"
+            );
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "checkpoint is {checkpoint:?}, expected block {block}"
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Waits until the confirmed prices at `block` are `expected`.
+    async fn wait_for_prices(&mut self, block: u64, expected: &[f64]) {
+        let deadline = tokio::time::Instant::now() + WATCH_TIMEOUT;
+        loop {
+            let prices = self.prices_at(block).await;
+            if prices_match(&prices, expected) {
+                return;
+            }
+            assert!(
+                self.child.try_wait().unwrap().is_none(),
+                "watch exited early"
+            );
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "prices at block {block} are {prices:?}, expected {expected:?}"
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+fn prices_match(prices: &[f64], expected: &[f64]) -> bool {
+    prices.len() == expected.len()
+        && prices
+            .iter()
+            .zip(expected)
+            .all(|(price, expected)| (price - expected).abs() < 0.01)
+}
+
+#[tokio::test]
+async fn test_watch_indexes_fixture_and_follows_reorg() {
+    let mock = MockProvider::from_fixture(fixture()).await.unwrap();
+    let mut watch = Watch::start(&mock, &["--interval", "1"]);
+
+    watch.wait_for_prices(19_000_002, &[2450.0]).await;
+    watch.wait_for_prices(19_000_005, &[2439.25, 2460.79]).await;
+    watch.wait_for_prices(HEAD, &[2500.0]).await;
+
+    // The head block is replaced by one with a different Sync
+    mock.reorg(HEAD - 1);
+    mock.push_sync(HEAD, tokens(45, 18), tokens(117_000, 6));
+    mock.mine(1);
+
+    watch.wait_for_prices(HEAD, &[2600.0]).await;
+    assert!(mock.requests("eth_getLogs") > 0);
+}
+
+#[tokio::test]
+async fn test_watch_reindexes_after_deep_reorg() {
+    let simulation = ReorgSimulation::from_fixture(reorg_fixture())
+        .await
+        .unwrap();
+    let mock = simulation.mock();
+    let mut watch = Watch::start(mock, &["--interval", "1"]);
+
+    watch.wait_for_prices(19_000_002, &[2450.0]).await;
+    watch.wait_for_prices(19_000_005, &[2460.0]).await;
+    watch.wait_for_block(19_000_006).await;
+
+    // One block per pass, so watch tracks every block of chain A
+    for block in 19_000_007..=19_000_010 {
+        mock.mine(1);
+        watch.wait_for_block(block).await;
+    }
+    watch.wait_for_prices(19_000_008, &[2480.0]).await;
+    watch.wait_for_prices(19_000_010, &[2500.0]).await;
+
+    simulation.switch().unwrap();
+    watch.wait_for_prices(19_000_011, &[2420.0]).await;
+
+    // Chain A's prices after the fork are gone, chain B's replace them
+    assert!(watch.prices_at(19_000_008).await.is_empty());
+    assert!(watch.prices_at(19_000_010).await.is_empty());
+    watch.wait_for_prices(19_000_009, &[2400.0]).await;
+    watch.wait_for_prices(19_000_002, &[2450.0]).await;
+    watch.wait_for_prices(19_000_005, &[2460.0]).await;
+    assert_eq!(watch.checkpoint().await.map(|(_, reorgs)| reorgs), Some(1));
+    // Blocks 19_000_008..=19_000_010 were rolled back, with their two syncs
+    assert_eq!(watch.reorgs().await, vec![(19_000_007, 3, 2)]);
+}
+
+#[tokio::test]
+async fn test_watch_ws_mode_indexes_on_new_heads() {
+    let mock = MockProvider::from_fixture(fixture()).await.unwrap();
+    // Without the subscription, the next pass would be an hour away
+    let mut watch = Watch::start(&mock, &["--mode", "ws", "--interval", "3600"]);
+    watch.wait_for_prices(HEAD, &[2500.0]).await;
+
+    mock.push_sync(HEAD + 1, tokens(40, 18), tokens(100_000, 6));
+    mock.mine(1);
+
+    watch.wait_for_prices(HEAD + 1, &[2500.0]).await;
+    assert!(mock.requests("eth_subscribe") > 0);
+}
+
+#[tokio::test]
+async fn test_watch_replays_recorded_fixture() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture_path = dir.path().join("watch.json");
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let mock = MockProvider::from_fixture(fixture()).await.unwrap();
+    let mut recorder = Command::new(env!("CARGO_BIN_EXE_eth-uniswap-alloy"))
+        .args(["fixture", "record"])
+        .arg(&fixture_path)
+        .args(["--head", &HEAD.to_string(), "--port", &port.to_string()])
+        .env_clear()
+        .env("RPC_URL", mock.http_url())
+        .env("RUST_LOG", "error")
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let proxy = format!("127.0.0.1:{port}");
+    let deadline = tokio::time::Instant::now() + WATCH_TIMEOUT;
+    while tokio::net::TcpStream::connect(&proxy).await.is_err() {
+        assert!(
+            recorder.try_wait().unwrap().is_none(),
+            "recorder exited early"
+        );
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "recorder never listened"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    let mut watch = Watch::spawn(&format!("http://{proxy}"), None, &["--interval", "1"]);
+    watch.wait_for_prices(19_000_005, &[2439.25, 2460.79]).await;
+    watch.wait_for_prices(HEAD, &[2500.0]).await;
+    drop(watch);
+
+    // The recorder saves the fixture on SIGTERM
+    let pid = recorder.id().unwrap().to_string();
+    let status = Command::new("kill")
+        .args(["-TERM", &pid])
+        .status()
+        .await
+        .unwrap();
+    assert!(status.success());
+    assert!(recorder.wait().await.unwrap().success());
+    drop(mock);
+
+    // A fresh database indexes the same prices from the recording alone
+    let replay = ReplayProvider::from_fixture(&fixture_path).await.unwrap();
+    let mut watch = Watch::spawn(&replay.http_url(), None, &["--interval", "1"]);
+    watch.wait_for_prices(19_000_002, &[2450.0]).await;
+    watch.wait_for_prices(19_000_005, &[2439.25, 2460.79]).await;
+    watch.wait_for_prices(HEAD, &[2500.0]).await;
+}
@@ -0,0 +1,133 @@
+//! Property-based tests for pricing and state.
+//!
+//! Generates reserves and token decimals across the full `uint112` range that
+//! Uniswap V2 stores, checking invariants the unit tests only sample:
+//!
+//! - pricing never panics, whatever the reserves and decimals, and rejects
+//!   decimals too far apart to scale rather than returning a wrapped price
+//! - a price and the price of the inverted pair multiply to one
+//! - the state's block number never moves backwards, and a rejected update
+//!   leaves the state untouched
+
+#![allow(clippy::unwrap_used)]
+#![allow(clippy::expect_used)]
+#![allow(clippy::cast_precision_loss)]
+
+use alloy::primitives::aliases::U112;
+use alloy::primitives::U256;
+use eth_uniswap_alloy::events::Sync;
+use eth_uniswap_alloy::pricing::calculate_price;
+use eth_uniswap_alloy::state::State;
+use proptest::prelude::*;
+
+/// Largest reserve `State` accepts (10^30).
+const MAX_STATE_RESERVE: u128 = 1_000_000_000_000_000_000_000_000_000_000;
+
+/// Any `uint112` reserve, weighted towards the edges of the range.
+fn reserve() -> impl Strategy<Value = U112> {
+    prop_oneof![
+        1 => Just(U112::ZERO),
+        1 => Just(U112::from(1u8)),
+        1 => Just(U112::MAX),
+        1 => Just(U112::from(MAX_STATE_RESERVE)),
+        1 => Just(U112::from(MAX_STATE_RESERVE + 1)),
+        6 => any::<u128>().prop_map(|n| U112::from(n >> 16)),
+    ]
+}
+
+/// A human-readable token amount between 1 and 10^9, scaled by `decimals`.
+fn scaled_amount(decimals: u8) -> impl Strategy<Value = (u64, U256)> {
+    (1u64..=1_000_000_000).prop_map(move |amount| {
+        let scale = U256::from(10u8).pow(U256::from(decimals));
+        (amount, U256::from(amount) * scale)
+    })
+}
+
+/// Two tokens' decimals and reserves holding 1 to 10^9 whole tokens each, so
+/// both a price and its inverse stay well above the 18-decimal resolution.
+fn pool() -> impl Strategy<Value = (u8, u8, (u64, U256), (u64, U256))> {
+    (0u8..=18, 0u8..=18).prop_flat_map(|(decimals0, decimals1)| {
+        (
+            Just(decimals0),
+            Just(decimals1),
+            scaled_amount(decimals0),
+            scaled_amount(decimals1),
+        )
+    })
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    let error = ((actual - expected) / expected).abs();
+    assert!(error < 1e-9, "{actual} is not within 1e-9 of {expected}");
+}
+
+proptest! {
+    #[test]
+    fn pricing_never_panics(
+        reserve0 in reserve(),
+        reserve1 in reserve(),
+        decimals0 in any::<u8>(),
+        decimals1 in any::<u8>(),
+    ) {
+        let result = calculate_price(U256::from(reserve0), U256::from(reserve1), decimals0, decimals1);
+        // 10^78 and up don't fit in a U256
+        let exponent = 18 + i32::from(decimals0) - i32::from(decimals1);
+        if reserve0.is_zero() || reserve1.is_zero() || exponent.abs() > 77 {
+            prop_assert!(result.is_err());
+        }
+        if let Ok(price) = result {
+            prop_assert!(price >= 0.0);
+        }
+    }
+
+    #[test]
+    fn price_matches_human_reserves(
+        (decimals0, decimals1, (amount0, reserve0), (amount1, reserve1)) in pool()
+    ) {
+        let price = calculate_price(reserve0, reserve1, decimals0, decimals1).unwrap();
+        assert_close(price, amount1 as f64 / amount0 as f64);
+    }
+
+    #[test]
+    fn inverted_price_is_reciprocal(
+        (decimals0, decimals1, (_, reserve0), (_, reserve1)) in pool()
+    ) {
+        let price = calculate_price(reserve0, reserve1, decimals0, decimals1).unwrap();
+        let inverted = calculate_price(reserve1, reserve0, decimals1, decimals0).unwrap();
+        assert_close(price * inverted, 1.0);
+    }
+
+    #[test]
+    fn state_accepts_only_valid_reserves(reserve0 in reserve(), reserve1 in reserve()) {
+        let mut state = State::new();
+        let result = state.update_from_sync_event(&Sync { reserve0, reserve1 }, 1);
+
+        let valid = |r: U112| !r.is_zero() && r <= U112::from(MAX_STATE_RESERVE);
+        prop_assert_eq!(result.is_ok(), valid(reserve0) && valid(reserve1));
+        if result.is_err() {
+            prop_assert_eq!(state, State::new());
+        }
+    }
+
+    #[test]
+    fn state_block_number_never_decreases(
+        updates in prop::collection::vec((reserve(), reserve(), 0u64..1_000), 1..50)
+    ) {
+        let mut state = State::new();
+        for (reserve0, reserve1, block) in updates {
+            let before = state.clone();
+            let sync = Sync { reserve0, reserve1 };
+
+            if state.update_from_sync_event(&sync, block).is_ok() {
+                prop_assert!(block >= before.get_last_block());
+                prop_assert_eq!(state.get_last_block(), block);
+                prop_assert_eq!(
+                    state.get_reserves(),
+                    (U256::from(reserve0), U256::from(reserve1))
+                );
+            } else {
+                prop_assert_eq!(&state, &before);
+            }
+        }
+    }
+}